@@ -2,5 +2,6 @@ extern crate build;
 fn main() {
     if cfg!(feature = "canvas") {
         build::link("d2d1", true);
+        build::link("dwrite", true);
     }
 }
\ No newline at end of file
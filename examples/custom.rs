@@ -145,7 +145,7 @@ fn main() {
     // Bind an event
     app.bind(&"MyCustomWindow", &"ExitNWG", nwge::Closed, |_,_,_,_| {
         simple_message("Hello", "Goodbye!");
-        nwg::exit();
+        nwg::exit(0);
     });
 
     // Bind the custom event
@@ -29,7 +29,8 @@ pub fn setup_ui(ui: &Ui<&'static str>) -> Result<(), Error> {
         title: "No template",
         position: (100, 100), size: (280, 105),
         resizable: false, visible: true, disabled: false,
-        exit_on_close: true, icon: None
+        exit_on_close: true, icon: None,
+        accept_files: false
     };
 
     // nwg_label!( parent="MainWindow"; [...] font=Some("TextFont") )
@@ -47,7 +48,8 @@ pub fn setup_ui(ui: &Ui<&'static str>) -> Result<(), Error> {
         position: (85,13), size: (185,22),
         visible: true, disabled: false, readonly: false, password: false,
         limit: 32_767, placeholder: None,
-        parent: "MainWindow", font: Some("TextFont")
+        parent: "MainWindow", font: Some("TextFont"),
+        accept_files: false
     };
 
     // nwg_button!( parent="MainWindow"; [..] font=Some("MainFont") )
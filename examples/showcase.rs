@@ -193,7 +193,7 @@ nwg_template!(
 
         ("NestedAction", "SayHello", nwge::menu::Triggered, |_,_,_,_| { nwg::simple_message("Hello", "Hello World!");  }),
         ("Action3", "SayHello", nwge::menu::Triggered, |_,_,_,_| { nwg::simple_message("Hello", "Hello World!"); }),
-        ("QuitItem", "Quit", nwge::menu::Triggered, |_,_,_,_| { nwg_exit() })
+        ("QuitItem", "Quit", nwge::menu::Triggered, |_,_,_,_| { nwg_exit(0) })
 
     ];
     resources: [
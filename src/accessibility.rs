@@ -0,0 +1,246 @@
+/*!
+    Accessibility metadata for the screen readers and the other assistive technologies.
+
+    By default, the assistive technologies only see the window text and the system role of the controls.
+    The metadata of a control can be changed with its `set_accessibility` method or when it is created
+    by wrapping its template in an `AccessibleT` (see `nwg_accessible!`).
+
+    Controls that draw their own content (ex: `Canvas`) or custom controls can expose a tree of virtual
+    elements by implementing `AccessibleTree` (see `Control::accessible_tree`).
+*/
+
+use std::hash::Hash;
+use std::any::TypeId;
+
+use ui::Ui;
+use controls::{ControlT, Control, AnyHandle};
+use error::Error;
+
+/**
+    The role of an accessible element. Tells the assistive technologies what kind of element it is.
+    `Custom` accepts any of the `ROLE_SYSTEM_*` values of the MSAA api.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessibleRole {
+    Window,
+    Client,
+    Pane,
+    Dialog,
+    Grouping,
+    Separator,
+    ToolBar,
+    StatusBar,
+    StaticText,
+    Text,
+    Graphic,
+    PushButton,
+    CheckButton,
+    RadioButton,
+    ComboBox,
+    List,
+    ListItem,
+    Outline,
+    OutlineItem,
+    Table,
+    Row,
+    Column,
+    Cell,
+    PageTab,
+    PageTabList,
+    ProgressBar,
+    Slider,
+    Link,
+    MenuItem,
+    Chart,
+    Diagram,
+    Custom(u32)
+}
+
+impl AccessibleRole {
+
+    /**
+        Return the `ROLE_SYSTEM_*` value of the role
+    */
+    pub fn to_system_role(&self) -> u32 {
+        match *self {
+            AccessibleRole::Window => 0x09,
+            AccessibleRole::Client => 0x0A,
+            AccessibleRole::MenuItem => 0x0C,
+            AccessibleRole::Pane => 0x10,
+            AccessibleRole::Chart => 0x11,
+            AccessibleRole::Dialog => 0x12,
+            AccessibleRole::Grouping => 0x14,
+            AccessibleRole::Separator => 0x15,
+            AccessibleRole::ToolBar => 0x16,
+            AccessibleRole::StatusBar => 0x17,
+            AccessibleRole::Table => 0x18,
+            AccessibleRole::Column => 0x1B,
+            AccessibleRole::Row => 0x1C,
+            AccessibleRole::Cell => 0x1D,
+            AccessibleRole::Link => 0x1E,
+            AccessibleRole::List => 0x21,
+            AccessibleRole::ListItem => 0x22,
+            AccessibleRole::Outline => 0x23,
+            AccessibleRole::OutlineItem => 0x24,
+            AccessibleRole::PageTab => 0x25,
+            AccessibleRole::Graphic => 0x28,
+            AccessibleRole::StaticText => 0x29,
+            AccessibleRole::Text => 0x2A,
+            AccessibleRole::PushButton => 0x2B,
+            AccessibleRole::CheckButton => 0x2C,
+            AccessibleRole::RadioButton => 0x2D,
+            AccessibleRole::ComboBox => 0x2E,
+            AccessibleRole::ProgressBar => 0x30,
+            AccessibleRole::Slider => 0x33,
+            AccessibleRole::Diagram => 0x35,
+            AccessibleRole::PageTabList => 0x3C,
+            AccessibleRole::Custom(role) => role
+        }
+    }
+
+}
+
+/**
+    The accessibility metadata of a control. A `None` value keeps the value provided by the system.
+
+    Members:
+    • `name`: The name of the control. Should be set on controls without text (ex: an icon button)
+    • `description`: A description of the control
+    • `role`: The role of the control
+    • `help`: A help text for the control
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Accessibility {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub role: Option<AccessibleRole>,
+    pub help: Option<String>
+}
+
+impl Accessibility {
+
+    /**
+        Create a metadata object that keeps every value provided by the system
+    */
+    pub fn new() -> Accessibility {
+        Default::default()
+    }
+
+    /**
+        Return `true` if no value is overridden
+    */
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.role.is_none() && self.help.is_none()
+    }
+
+}
+
+/**
+    A virtual accessible element. Used by the controls that draw their own content.
+
+    Members:
+    • `name`: The name of the element
+    • `description`: A description of the element
+    • `help`: A help text for the element
+    • `value`: The value of the element (ex: the value of a gauge)
+    • `role`: The role of the element
+    • `bounds`: The (x, y, width, height) of the element in the client area of the control, in pixels
+    • `children`: The children of the element
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibleNode {
+    pub name: String,
+    pub description: String,
+    pub help: String,
+    pub value: String,
+    pub role: AccessibleRole,
+    pub bounds: (i32, i32, u32, u32),
+    pub children: Vec<AccessibleNode>
+}
+
+impl AccessibleNode {
+
+    /**
+        Create an element without description, help, value and children
+    */
+    pub fn new<S: Into<String>>(name: S, role: AccessibleRole, bounds: (i32, i32, u32, u32)) -> AccessibleNode {
+        AccessibleNode {
+            name: name.into(),
+            description: String::new(),
+            help: String::new(),
+            value: String::new(),
+            role: role,
+            bounds: bounds,
+            children: Vec::new()
+        }
+    }
+
+    /**
+        Check if a point in the client area of the control is inside the element
+    */
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (bx, by, bw, bh) = self.bounds;
+        let (x, y) = (x as i64, y as i64);
+        x >= (bx as i64) && y >= (by as i64) && x < (bx as i64) + (bw as i64) && y < (by as i64) + (bh as i64)
+    }
+
+    /**
+        Return the index of the child under a point or `None` if no child is under the point.
+        If the children overlap, the last one is returned because it is drawn over the others.
+    */
+    pub fn hit_test(&self, x: i32, y: i32) -> Option<usize> {
+        self.children.iter().rposition(|c| c.contains(x, y))
+    }
+
+}
+
+/**
+    Implemented by the controls that expose their own accessible elements.
+*/
+pub trait AccessibleTree {
+
+    /**
+        Return the accessible elements of the control. Called each time an assistive technology reads the control,
+        so the returned elements should reflect what is currently drawn.
+    */
+    fn accessible_children(&self) -> Vec<AccessibleNode>;
+
+}
+
+/**
+    A template that sets the accessibility metadata of the control created by another template.
+    The created control has the type of the control created by the inner template.
+
+    Members:
+    • `control`: The template of the control
+    • `accessibility`: The accessibility metadata of the control
+*/
+#[derive(Clone)]
+pub struct AccessibleT<T> {
+    pub control: T,
+    pub accessibility: Accessibility
+}
+
+impl<ID: Hash+Clone, T: ControlT<ID>> ControlT<ID> for AccessibleT<T> {
+    fn type_id(&self) -> TypeId { self.control.type_id() }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
+        use low::accessibility_helper::set_window_accessibility;
+
+        let control = match self.control.build(ui) {
+            Ok(c) => c,
+            Err(e) => { return Err(e); }
+        };
+
+        match control.handle() {
+            AnyHandle::HWND(h) => unsafe{ set_window_accessibility(h, &self.accessibility); },
+            h => {
+                let mut control = control;
+                control.free();
+                return Err(Error::BadResource(format!("Window-like control required got {}", h.human_name())));
+            }
+        }
+
+        Ok(control)
+    }
+}
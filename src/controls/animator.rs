@@ -0,0 +1,168 @@
+/*!
+    A control playing a tween on the properties of other controls
+*/
+use std::hash::Hash;
+use std::any::TypeId;
+use std::time::{Duration, Instant};
+
+use winapi::{HWND, UINT_PTR, ULONG_PTR};
+
+use ui::Ui;
+use controls::{Control, ControlT, ControlType, AnyHandle};
+use controls::timer::next_timer_id;
+use tween::{Tween, Timeline, PropertyValue};
+use error::Error;
+
+/**
+    A template that creates an animator. Like the timers, an animator cannot start automatically
+    because its callbacks must be added after its creation.
+
+    Control specific events:  
+    `animator::Completed`  
+
+    Members:  
+    • `tween`: The tween played by the animator  
+    • `interval`: The time between two updates of the properties, in milliseconds. `16` updates the properties about 60 times per second  
+*/
+#[derive(Clone)]
+pub struct AnimatorT<ID: Clone+Hash> {
+    pub tween: Tween<ID>,
+    pub interval: u32
+}
+
+impl<ID: Hash+Clone+'static> ControlT<ID> for AnimatorT<ID> {
+    fn type_id(&self) -> TypeId { TypeId::of::<Animator<ID>>() }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
+        Ok(Box::new(Animator{
+            timeline: Timeline::new(&self.tween),
+            tween: self.tween.clone(),
+            interval: self.interval,
+            start: None,
+            watcher: unsafe{ ui.message_handle() },
+            id_event: next_timer_id(),
+            handle: None
+        }))
+    }
+}
+
+/**
+    Identify the animators in the handles, whatever the type of the identifiers of their `Ui`
+*/
+pub(crate) struct AnimatorHandle;
+
+/**
+    A control that plays a tween on the UI thread. The properties are updated by a system timer
+    of the `Ui`, so the callbacks always see the controls in a consistent state.
+
+    Once every property reached its end value, the animator stops and triggers `animator::Completed`.
+*/
+pub struct Animator<ID: Clone+Hash> {
+    tween: Tween<ID>,
+    timeline: Timeline<ID>,
+    interval: u32,
+    start: Option<Instant>,
+
+    watcher: HWND,             // Like the timers, the animators are updated by their Ui
+    id_event: ULONG_PTR,       // A unique timer id shared with the timers
+    handle: Option<UINT_PTR>,  // If the animator is not running, handle is None.
+}
+
+impl<ID: Clone+Hash> Animator<ID> {
+
+    /**
+        Play the tween from the start. If the animator was already running, it is restarted.
+        The tweens without start value read the current values of their properties again.
+    */
+    pub fn start(&mut self) {
+        use user32::SetTimer;
+
+        self.stop();
+        self.timeline.reset();
+        self.start = Some(Instant::now());
+        self.handle = unsafe{ Some( SetTimer(self.watcher, self.id_event, self.interval.max(1), None) ) };
+    }
+
+    /**
+        Stop the animator. The properties keep their current values and `animator::Completed` is not triggered.
+        If the animator was not started, this do nothing
+    */
+    pub fn stop(&mut self) {
+        use user32::KillTimer;
+        if let Some(id) = self.handle.take() {
+            unsafe{ KillTimer(self.watcher, id); }
+        }
+
+        self.start = None;
+    }
+
+    /// Check if the animator is running. Return `true` if it is or `false` otherwise
+    pub fn running(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Return the time elapsed since the animator started. If the animator is not running, the returned value is 0.
+    pub fn elapsed(&self) -> Duration {
+        match self.start {
+            Some(start) => start.elapsed(),
+            None => Duration::new(0, 0)
+        }
+    }
+
+    /// Return the time needed to play the tween
+    pub fn duration(&self) -> Duration {
+        self.timeline.duration()
+    }
+
+    /// Return the tween played by the animator
+    pub fn get_tween(&self) -> &Tween<ID> {
+        &self.tween
+    }
+
+    /// Replace the tween played by the animator. If the animator is running, it is stopped.
+    pub fn set_tween(&mut self, tween: Tween<ID>) {
+        self.stop();
+        self.timeline = Timeline::new(&tween);
+        self.tween = tween;
+    }
+
+    /// Set the time between two updates of the properties. If the animator is running, it will be applied when the animator is restarted
+    pub fn set_interval(&mut self, interval: u32) { self.interval = interval; }
+
+    /// Return the time between two updates of the properties
+    pub fn get_interval(&self) -> u32 { self.interval }
+
+    /**
+        Return the values of the properties at the current time and stop the animator if the tween is finished.
+        `current` reads the values of the properties, see `Timeline::advance`.
+    */
+    pub(crate) fn step<F>(&mut self, current: F) -> Vec<(ID, PropertyValue)>
+      where F: FnMut(&ID, &PropertyValue) -> Option<PropertyValue>
+    {
+        if !self.running() { return Vec::new(); }
+
+        let values = self.timeline.advance(self.elapsed(), current);
+        if self.timeline.is_finished() {
+            self.stop();
+        }
+
+        values
+    }
+
+}
+
+impl<ID: Clone+Hash> Control for Animator<ID> {
+
+    fn handle(&self) -> AnyHandle {
+        AnyHandle::Custom(TypeId::of::<AnimatorHandle>(), self.id_event as usize)
+    }
+
+    fn control_type(&self) -> ControlType {
+        ControlType::Animator
+    }
+
+    fn free(&mut self) {
+        self.stop();
+    }
+
+}
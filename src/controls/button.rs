@@ -9,6 +9,7 @@ use winapi::{HWND, HFONT};
 
 use ui::Ui;
 use error::Error;
+use accessibility::Accessibility;
 use controls::{Control, ControlT, ControlType, AnyHandle};
 
 /**
@@ -104,6 +105,12 @@ impl Button {
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
     pub fn get_font<ID: Hash+Clone>(&self, ui: &Ui<ID>) -> Option<ID> { unsafe{ ::low::window_helper::get_window_font(self.handle, ui) } }
     pub fn set_font<ID: Hash+Clone>(&self, ui: &Ui<ID>, f: Option<&ID>) -> Result<(), Error> { unsafe{ ::low::window_helper::set_window_font(self.handle, ui, f) } }
+    pub fn get_text_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_text_color(self.handle) }
+    pub fn set_text_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_text_color(self.handle, color); } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 
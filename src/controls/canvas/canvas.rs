@@ -8,12 +8,15 @@ use std::ptr;
 use std::ops::{Deref, DerefMut};
 use std::marker::PhantomData;
 
-use winapi::{HWND, ID2D1Factory, ID2D1HwndRenderTarget};
+use winapi::{HWND, ID2D1Factory, ID2D1HwndRenderTarget, IDWriteFactory};
 
 use controls::{Control, ControlType, AnyHandle};
 use error::{Error, SystemError};
-use super::{CanvasRenderer, RendererProtected, build_render_target, CANVAS_CLASS_NAME};
+use accessibility::{Accessibility, AccessibleTree};
+use super::{CanvasRenderer, RendererProtected, FrameClock, FramePacing, FrameStats, build_render_target, CANVAS_CLASS_NAME};
 use ui::Ui;
+use resources::{ImageData, TextLayout};
+use resources::canvas::{Scene, RenderBackend, SoftwareRenderer, DisplayList};
 
 /**
     A blank control that can be painted to
@@ -21,8 +24,13 @@ use ui::Ui;
 pub struct Canvas<ID: Clone+Hash> {
     handle: HWND,
     factory: *mut ID2D1Factory,
+    write_factory: *mut IDWriteFactory,
     render_target: *mut ID2D1HwndRenderTarget,
     must_recreate_target: bool,
+    dpi: Option<(f32, f32)>,
+    accessible_tree: Option<Box<AccessibleTree>>,
+    scene: Option<Scene<ID>>,
+    animation: Option<FrameClock>,
     p: PhantomData<ID>
 }
 
@@ -35,6 +43,55 @@ impl<ID: Clone+Hash> Canvas<ID> {
         CanvasRenderer::prepare(self, ui)
     }
 
+    /**
+        Draw on an offscreen bitmap of `width` x `height` pixels instead of the canvas window and return the pixels of the bitmap.
+        The bitmap starts transparent. The canvas resources (brushes, pens) can be used by the renderer passed to `draw`.
+        The result can be saved with `ImageData::encode`.
+    */
+    pub fn render_offscreen<F>(&mut self, ui: &Ui<ID>, width: u32, height: u32, draw: F) -> Result<ImageData, Error> 
+      where F: FnOnce(&mut CanvasRenderer<ID>) -> Result<(), Error>
+    {
+        let mut renderer = CanvasRenderer::prepare_offscreen(self, ui, width, height)?;
+        draw(&mut renderer)?;
+        renderer.read_offscreen()
+    }
+
+    /**
+        Send the drawing to `backend` instead of Direct2D. The canvas resources can be used by the renderer passed to `draw`.
+        The text is drawn only if the backend supports it and the text layouts cannot be drawn.
+    */
+    pub fn render_with<F>(&mut self, ui: &Ui<ID>, backend: &mut RenderBackend, draw: F) -> Result<(), Error>
+      where F: FnOnce(&mut CanvasRenderer<ID>) -> Result<(), Error>
+    {
+        let mut renderer = CanvasRenderer::prepare_backend(self, ui, backend)?;
+        draw(&mut renderer)
+    }
+
+    /**
+        Draw on a `SoftwareRenderer` of `width` x `height` pixels and return its pixels. Like `render_offscreen`, but
+        the graphic card is not used, so the result is the same on every computer.
+    */
+    pub fn render_software<F>(&mut self, ui: &Ui<ID>, width: u32, height: u32, draw: F) -> Result<ImageData, Error> 
+      where F: FnOnce(&mut CanvasRenderer<ID>) -> Result<(), Error>
+    {
+        let mut backend = SoftwareRenderer::new(width, height)?;
+        self.render_with(ui, &mut backend, draw)?;
+        Ok(backend.get_image())
+    }
+
+    /**
+        Record the drawing instead of drawing it. `width` and `height` are the size of the drawing area seen by the renderer
+        passed to `draw`. The returned display list can be drawn on any canvas with `CanvasRenderer::draw_display_list`
+        or saved with `DisplayList::to_svg`.
+    */
+    pub fn record<F>(&mut self, ui: &Ui<ID>, width: f32, height: f32, draw: F) -> Result<DisplayList, Error>
+      where F: FnOnce(&mut CanvasRenderer<ID>) -> Result<(), Error>
+    {
+        let mut list = DisplayList::new(width, height);
+        self.render_with(ui, &mut list, draw)?;
+        Ok(list)
+    }
+
     /**
         Redraw the canvas
     */
@@ -57,7 +114,8 @@ impl<ID: Clone+Hash> Canvas<ID> {
     }
 
     /**
-        Return the render target's dots per inch (DPI).
+        Return the render target's dots per inch (DPI). The renderer coordinates are in device independent pixels:
+        one unit covers `dpi / 96` pixels of the canvas.
     */
     pub fn get_dpi(&mut self) -> (f32, f32) {
         let mut x = 0.0f32;
@@ -67,14 +125,123 @@ impl<ID: Clone+Hash> Canvas<ID> {
     }
 
     /**
-        Sets the dots per inch (DPI) of the render target.   
+        Sets the dots per inch (DPI) of the render target. The DPI is kept if the render target has to be recreated.  
 
         Arguments:  
         • `dpix`:  A value greater than or equal to zero that specifies the horizontal DPI of the render target.  
         • `dpiy`:  A value greater than or equal to zero that specifies the vertical DPI of the render target.  
     */
-    pub fn set_dpi(&mut self, dpix: f32, fpiy: f32) {
-        unsafe { self.SetDpi(dpix, fpiy); }
+    pub fn set_dpi(&mut self, dpix: f32, dpiy: f32) {
+        self.dpi = Some((dpix, dpiy));
+        unsafe { self.SetDpi(dpix, dpiy); }
+    }
+
+    /**
+        Set the object describing the content of the canvas to the assistive technologies.
+        `None` removes the accessible elements of the canvas.
+    */
+    pub fn set_accessible_tree(&mut self, tree: Option<Box<AccessibleTree>>) {
+        self.accessible_tree = tree;
+    }
+
+    /**
+        Set the scene drawn by the canvas. `None` removes the scene.
+
+        While a scene is set, the canvas draws it each time it is painted, before the `Paint` callbacks are called,
+        and the mouse events over the scene nodes are sent as `canvas::NodeMouseDown`, `canvas::NodeMouseUp`, `canvas::NodeClick`,
+        `canvas::NodeEnter` and `canvas::NodeLeave` events. The scene coordinates are in device independent pixels.
+    */
+    pub fn set_scene(&mut self, scene: Option<Scene<ID>>) {
+        self.scene = scene;
+        self.redraw();
+    }
+
+    /**
+        Return the scene drawn by the canvas
+    */
+    pub fn scene(&self) -> Option<&Scene<ID>> {
+        self.scene.as_ref()
+    }
+
+    /**
+        Modify the scene drawn by the canvas. If the scene was modified, the canvas is redrawn.
+        Return `None` if the canvas has no scene.
+    */
+    pub fn update_scene<F, R>(&mut self, update: F) -> Option<R> 
+      where F: FnOnce(&mut Scene<ID>) -> R
+    {
+        let (result, dirty) = match self.scene.as_mut() {
+            Some(scene) => (update(scene), scene.take_dirty()),
+            None => { return None; }
+        };
+
+        if dirty { self.redraw(); }
+        Some(result)
+    }
+
+    /**
+        Start animating the canvas. A `canvas::Frame` event is sent each time a frame starts, with the time elapsed
+        since the last frame. The callbacks draw the frame with `renderer`. If the canvas was already animated,
+        the animation restarts with new statistics.
+
+        The animation pauses while the canvas is hidden or while its window is minimized. The delta of the first
+        frame after a pause is zero.
+
+        Arguments:  
+        • `pacing`: `FramePacing::Display` starts a frame each time the display refreshes. `FramePacing::Fps` starts
+           a fixed number of frames per second, timed by the system timers which are only precise to about 15 milliseconds  
+    */
+    pub fn start_animation(&mut self, pacing: FramePacing) {
+        use low::frame_helper::display_refresh_rate;
+
+        let rate = unsafe{ display_refresh_rate(self.handle) };
+        self.animation = Some(FrameClock::new(pacing, rate));
+        self.redraw();
+    }
+
+    /**
+        Stop animating the canvas
+    */
+    pub fn stop_animation(&mut self) {
+        use low::frame_helper::stop_frames;
+
+        self.animation = None;
+        unsafe{ stop_frames(self.handle); }
+    }
+
+    /**
+        Return true if the canvas is animated, even if its animation is paused
+    */
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /**
+        Return the timing statistics of the last frames of the animation, or `None` if the canvas is not animated
+    */
+    pub fn frame_stats(&self) -> Option<FrameStats> {
+        self.animation.as_ref().map(|clock| clock.stats())
+    }
+
+    /**
+        Format `text` with the text format identified by `format` in a layout box of `max_size` (`width`, `height`).
+        The layout can be measured, hit-tested and drawn with `CanvasRenderer::draw_text_layout`.
+
+        Arguments:  
+        • `ui`: The Ui that owns the text format  
+        • `format`: Id of the text format saved in the canvas  
+        • `text`: The text to format  
+        • `max_size`: The size of the layout box. The text wraps at the width of the box if the format allows it  
+    */
+    pub fn create_text_layout(&self, ui: &Ui<ID>, format: &ID, text: &str, max_size: (f32, f32)) -> Result<TextLayout, Error> {
+        use resources::canvas::{text_format_handle, create_text_layout};
+
+        let format = match text_format_handle(ui, format) {
+            Ok(f) => f,
+            Err(e) => { return Err(e); }
+        };
+
+        create_text_layout(unsafe{ &mut *self.write_factory }, format, text, max_size)
     }
 
     /**
@@ -87,6 +254,44 @@ impl<ID: Clone+Hash> Canvas<ID> {
     */
     pub fn get_factory(&self) -> *mut ID2D1Factory { self.factory }
 
+    /**
+        Get the inner DirectWrite factory of the canvas. This is used when building canvas text resources.
+    */
+    pub fn get_write_factory(&self) -> *mut IDWriteFactory { self.write_factory }
+
+    /**
+        Draw the scene of the canvas. Does nothing if the canvas has no scene.
+    */
+    pub(crate) fn paint_scene(&mut self, ui: &Ui<ID>) -> Result<(), Error> {
+        let mut scene = match self.scene.take() {
+            Some(scene) => scene,
+            None => { return Ok(()); }
+        };
+
+        let result = match self.renderer(ui) {
+            Ok(mut renderer) => renderer.draw_scene(&scene),
+            Err(e) => Err(e)
+        };
+
+        scene.take_dirty();
+        self.scene = Some(scene);
+        result
+    }
+
+    /**
+        Return the scene of the canvas to update its mouse state. Unlike `update_scene`, the canvas is not redrawn.
+    */
+    pub(crate) fn scene_input(&mut self) -> Option<&mut Scene<ID>> {
+        self.scene.as_mut()
+    }
+
+    /**
+        Return the clock of the animation to start the frames. Unlike `start_animation`, the canvas is not redrawn.
+    */
+    pub(crate) fn frame_clock(&mut self) -> Option<&mut FrameClock> {
+        self.animation.as_mut()
+    }
+
     pub fn get_visibility(&self) -> bool { unsafe{ ::low::window_helper::get_window_visibility(self.handle) } }
     pub fn set_visibility(&self, visible: bool) { unsafe{ ::low::window_helper::set_window_visibility(self.handle, visible); }}
     pub fn get_position(&self) -> (i32, i32) { unsafe{ ::low::window_helper::get_window_position(self.handle) } }
@@ -95,6 +300,8 @@ impl<ID: Clone+Hash> Canvas<ID> {
     pub fn set_size(&self, w: u32, h: u32) { unsafe{ ::low::window_helper::set_window_size(self.handle, w, h, true); } }
     pub fn get_enabled(&self) -> bool { unsafe{ ::low::window_helper::get_window_enabled(self.handle) } }
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 }
@@ -114,6 +321,10 @@ impl<ID: Clone+Hash> Control for Canvas<ID> {
         unsafe{ list_window_children(self.handle) }
     }
 
+    fn accessible_tree(&self) -> Option<&AccessibleTree> {
+        self.accessible_tree.as_ref().map(|t| &**t)
+    }
+
     fn free(&mut self) {
         unsafe{
             use user32::{DestroyWindow, UnregisterClassW};
@@ -124,6 +335,7 @@ impl<ID: Clone+Hash> Control for Canvas<ID> {
             let render_target = &mut *self.render_target;
 
             render_target.Release();
+            (&mut *self.write_factory).Release();
             factory.Release();
             DestroyWindow(self.handle);
 
@@ -160,7 +372,7 @@ impl<'a, ID: Clone+Hash> DerefMut for Canvas<ID> {
 pub trait CanvasProtected<ID: Clone+Hash>  {
     fn get_must_recreate_target(&mut self) -> bool;
     fn set_must_recreate_target(&mut self, recreate: bool);
-    fn create(h: HWND, f: *mut ID2D1Factory, r: *mut ID2D1HwndRenderTarget) -> Canvas<ID>;
+    fn create(h: HWND, f: *mut ID2D1Factory, w: *mut IDWriteFactory, r: *mut ID2D1HwndRenderTarget) -> Canvas<ID>;
     fn rebuild(&mut self) -> Result<(), SystemError>;
 }
 
@@ -174,12 +386,17 @@ impl<ID: Clone+Hash> CanvasProtected<ID> for Canvas<ID> {
         self.must_recreate_target = recreate;
     }
 
-    fn create(h: HWND, f: *mut ID2D1Factory, r: *mut ID2D1HwndRenderTarget) -> Canvas<ID> {
+    fn create(h: HWND, f: *mut ID2D1Factory, w: *mut IDWriteFactory, r: *mut ID2D1HwndRenderTarget) -> Canvas<ID> {
          Canvas::<ID>{
             handle: h,
             factory: f,
+            write_factory: w,
             render_target: r,
             must_recreate_target: false,
+            dpi: None,
+            accessible_tree: None,
+            scene: None,
+            animation: None,
             p: PhantomData
         }
     }
@@ -191,6 +408,9 @@ impl<ID: Clone+Hash> CanvasProtected<ID> for Canvas<ID> {
             Ok(render_target) => {
                 self.render_target = render_target;
                 self.must_recreate_target = false;
+                if let Some((x, y)) = self.dpi {
+                    unsafe{ (&mut *render_target).SetDpi(x, y); }
+                }
                 Ok(())
             }
             Err(e) => Err(e)
@@ -10,7 +10,7 @@ use std::hash::Hash;
 use std::any::TypeId;
 use std::ptr;
 
-use winapi::{HWND, ID2D1Factory, ID2D1HwndRenderTarget};
+use winapi::{HWND, ID2D1Factory, ID2D1HwndRenderTarget, IDWriteFactory};
 
 use ui::Ui;
 use controls::{Control, ControlT};
@@ -39,24 +39,32 @@ impl<ID: Hash+Clone+'static> ControlT<ID> for CanvasT<ID> {
     fn type_id(&self) -> TypeId { TypeId::of::<Canvas<ID>>() }
 
     fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
-       unsafe{
-            if let Err(e) = build_sysclass() { return Err(e); }
-            match build_window(ui, &self) {
-                Ok((h, (f, r))) => { Ok( Box::new( 
-                    Canvas::<ID>::create(h, f, r)
-                ) as Box<Control> ) },
-                Err(e) => Err(e)
-            }
-        } // unsafe
+        match build_canvas(ui, self) {
+            Ok(canvas) => Ok( Box::new(canvas) as Box<Control> ),
+            Err(e) => Err(e)
+        }
     }
 }
 
+/**
+    Create the canvas described by a template. Used by the controls drawn on a canvas.
+*/
+pub(crate) fn build_canvas<ID: Hash+Clone+'static>(ui: &Ui<ID>, t: &CanvasT<ID>) -> Result<Canvas<ID>, Error> {
+    unsafe{
+        if let Err(e) = build_sysclass() { return Err(e); }
+        match build_window(ui, t) {
+            Ok((h, (f, w, r))) => Ok( Canvas::<ID>::create(h, f, w, r) ),
+            Err(e) => Err(e)
+        }
+    } // unsafe
+}
+
 /*
     Private unsafe control methods
 */
 
 use winapi::{UINT, WPARAM, LPARAM, LRESULT};
-type RenderOut = (*mut ID2D1Factory, *mut ID2D1HwndRenderTarget);
+type RenderOut = (*mut ID2D1Factory, *mut IDWriteFactory, *mut ID2D1HwndRenderTarget);
 
 #[allow(unused_variables)]
 unsafe extern "system" fn canvas_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> LRESULT {
@@ -145,8 +153,9 @@ pub unsafe fn build_render_target(hwnd: HWND, factory: &mut ID2D1Factory) -> Res
 
 #[inline(always)]
 unsafe fn build_renderer(hwnd: HWND) -> Result<RenderOut, SystemError> {
-    use winapi::{UuidOfID2D1Factory, D2D1_FACTORY_TYPE_SINGLE_THREADED, S_OK};
-    use low::defs::D2D1CreateFactory;
+    use winapi::{UuidOfID2D1Factory, D2D1_FACTORY_TYPE_SINGLE_THREADED, DWRITE_FACTORY_TYPE_SHARED, S_OK};
+    use low::defs::{D2D1CreateFactory, DWriteCreateFactory};
+    use low::clsid::IID_IDWriteFactory;
     
     // Build the D2D Factory
     let mut factory: *mut ID2D1Factory = ptr::null_mut();
@@ -162,10 +171,27 @@ unsafe fn build_renderer(hwnd: HWND) -> Result<RenderOut, SystemError> {
         return Err(SystemError::ComError(msg));
     }
 
+    // Build the DirectWrite factory used by the text resources
+    let mut write_factory: *mut IDWriteFactory = ptr::null_mut();
+    let result = DWriteCreateFactory(
+        DWRITE_FACTORY_TYPE_SHARED,
+        &IID_IDWriteFactory(),
+        &mut write_factory as *mut *mut IDWriteFactory as *mut _
+    );
+
+    if result != S_OK {
+        (&mut *factory).Release();
+        let msg = "Could not create DirectWrite factory".to_string();
+        return Err(SystemError::ComError(msg));
+    }
+
     // Build the render target
     match build_render_target(hwnd, &mut *factory) {
-        Ok(render_target) => Ok( (factory, render_target) ),
-        Err(e) => Err(e)
+        Ok(render_target) => Ok( (factory, write_factory, render_target) ),
+        Err(e) => {
+            (&mut *write_factory).Release();
+            Err(e)
+        }
     }
 }
 
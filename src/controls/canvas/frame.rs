@@ -0,0 +1,172 @@
+/*!
+    Timing of the frames of an animated canvas. The frame clock decides when the next frame is due and
+    measures the time between the frames. It does not use any window, so it can be tested with any instant.
+*/
+
+use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+
+/// The number of frames used to compute the frame statistics
+const HISTORY_SIZE: usize = 120;
+
+/**
+    How the frames of an animated canvas are paced
+
+    • `Display`: One frame each time the display refreshes. Drawing on the canvas waits for the refresh  
+    • `Fps`: A fixed number of frames per second, timed by the system timers  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FramePacing {
+    Display,
+    Fps(f32)
+}
+
+/**
+    Timing statistics of the last frames of an animation
+
+    Members:  
+    • `frames`: The number of frames since the animation started  
+    • `fps`: The average number of frames per second  
+    • `average`: The average time between two frames  
+    • `min`, `max`: The shortest and the longest time between two frames  
+    • `late`: The number of frames that came more than 50% later than the frame interval  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameStats {
+    pub frames: u64,
+    pub fps: f32,
+    pub average: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub late: u64
+}
+
+/**
+    The clock of an animated canvas. A frame is due when the frame interval has elapsed since the last frame.
+    With `FramePacing::Display`, the display paces the frames and a frame is due after half of the interval,
+    so that the clock only prevents frames from running faster than the display when nothing waits for it.
+
+    The time during which the clock is paused is not counted: the delta of the first frame after a pause is zero.
+*/
+#[derive(Clone, Debug)]
+pub struct FrameClock {
+    pacing: FramePacing,
+    interval: Duration,
+    last: Option<Instant>,
+    paused: bool,
+    frames: u64,
+    late: u64,
+    history: VecDeque<Duration>
+}
+
+impl FrameClock {
+
+    /**
+        Create a clock. `refresh_rate` is the number of times the display refreshes per second, it sets
+        the frame interval of `FramePacing::Display` and of invalid frame rates.
+    */
+    pub fn new(pacing: FramePacing, refresh_rate: f32) -> FrameClock {
+        let rate = match pacing {
+            FramePacing::Fps(fps) if fps > 0.0 && fps.is_finite() => fps,
+            _ => if refresh_rate > 0.0 && refresh_rate.is_finite() { refresh_rate } else { 60.0 }
+        };
+
+        let nanos = (1_000_000_000.0 / (rate as f64)) as u64;
+        FrameClock {
+            pacing: pacing,
+            interval: Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32),
+            last: None,
+            paused: false,
+            frames: 0,
+            late: 0,
+            history: VecDeque::with_capacity(HISTORY_SIZE)
+        }
+    }
+
+    /// Return how the frames are paced
+    pub fn get_pacing(&self) -> FramePacing {
+        self.pacing
+    }
+
+    /// Return the expected time between two frames
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /**
+        Return the time to wait at `now` before the next frame, or `None` if the next frame is due.
+        The first frame and the first frame after a pause are always due.
+    */
+    pub fn wait_time(&self, now: Instant) -> Option<Duration> {
+        let last = match self.last {
+            Some(last) if !self.paused => last,
+            _ => { return None; }
+        };
+
+        let min = match self.pacing {
+            FramePacing::Display => self.interval / 2,
+            FramePacing::Fps(_) => self.interval
+        };
+
+        let elapsed = if now > last { now.duration_since(last) } else { Duration::new(0, 0) };
+        if elapsed >= min { None } else { Some(min - elapsed) }
+    }
+
+    /**
+        Start a frame at `now` and return the time elapsed since the last frame. Resume the clock if it was paused.
+    */
+    pub fn tick(&mut self, now: Instant) -> Duration {
+        let delta = match self.last {
+            Some(last) if !self.paused && now > last => now.duration_since(last),
+            _ => Duration::new(0, 0)
+        };
+
+        if self.last.is_some() && !self.paused {
+            if self.history.len() == HISTORY_SIZE { self.history.pop_front(); }
+            self.history.push_back(delta);
+            if delta > self.interval + self.interval / 2 { self.late += 1; }
+        }
+
+        self.last = Some(now);
+        self.paused = false;
+        self.frames += 1;
+        delta
+    }
+
+    /**
+        Pause the clock until the next frame
+    */
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Return true if the clock is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Return the number of frames since the clock was created
+    pub fn frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    /**
+        Return the statistics of the last frames. The times are zero until two frames were started.
+    */
+    pub fn stats(&self) -> FrameStats {
+        let zero = Duration::new(0, 0);
+        let total = self.history.iter().fold(zero, |total, &d| total + d);
+        let average = if self.history.len() > 0 { total / (self.history.len() as u32) } else { zero };
+
+        let seconds = (average.as_secs() as f64) + (average.subsec_nanos() as f64) / 1_000_000_000.0;
+        FrameStats {
+            frames: self.frames,
+            fps: if seconds > 0.0 { (1.0 / seconds) as f32 } else { 0.0 },
+            average: average,
+            min: self.history.iter().min().cloned().unwrap_or(zero),
+            max: self.history.iter().max().cloned().unwrap_or(zero),
+            late: self.late
+        }
+    }
+
+}
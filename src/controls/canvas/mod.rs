@@ -1,7 +1,10 @@
 mod canvas;
 mod renderer;
 mod canvas_t;
+mod frame;
 
 pub use self::canvas::{Canvas, CanvasProtected};
 pub use self::renderer::{CanvasRenderer, RendererProtected};
 pub use self::canvas_t::{CanvasT, build_render_target, CANVAS_CLASS_NAME};
+pub(crate) use self::canvas_t::build_canvas;
+pub use self::frame::{FrameClock, FramePacing, FrameStats};
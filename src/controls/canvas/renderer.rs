@@ -7,24 +7,40 @@ use std::hash::Hash;
 use std::ptr;
 use std::mem;
 use std::any::TypeId;
+use std::cell::Ref;
 use std::ops::{Deref, DerefMut};
 
 use winapi::{FLOAT, D2D1_RECT_F, D2D1_ROUNDED_RECT, D2D1_ELLIPSE, D2D1_POINT_2F, 
-  D2D1_MATRIX_3X2_F, ID2D1Brush, ID2D1StrokeStyle};
+  D2D1_MATRIX_3X2_F, ID2D1Brush, ID2D1StrokeStyle, ID2D1RenderTarget, ID2D1BitmapRenderTarget, ID2D1Geometry,
+  ID2D1PathGeometry, ID2D1Bitmap, ID2D1Layer};
 
 use ui::Ui;
-use error::Error;
-use resources::{Brush, Pen};
+use error::{Error, SystemError};
+use resources::{Brush, Pen, Path, Bitmap, ImageData, TextFormat, TextLayout};
+use resources::canvas::{create_path_geometry, text_format_handle, Scene, SceneNode, Shape, RenderBackend, DisplayList, Direct2DBackend};
 use controls::AnyHandle;
-use defs::canvas::{Rectangle, Ellipse};
+use defs::canvas::{Rectangle, Ellipse, PathData, FillMode, BitmapInterpolation, Matrix};
 use super::{Canvas, CanvasProtected};
 
 /**
     Object that offers a light wrapper over the D2D1 api.
+    The renderer draws on the canvas window or, if it was created by `Canvas::render_offscreen`, on an offscreen bitmap.
+    If it was created by `Canvas::render_with`, the drawing is sent to a renderer backend instead of Direct2D.
 */
 pub struct CanvasRenderer<'a, ID: Clone+Hash+'static> {
     pub ui: &'a Ui<ID>,
-    canvas: &'a mut Canvas<ID>
+    canvas: &'a mut Canvas<ID>,
+    target: *mut ID2D1RenderTarget,
+    offscreen: *mut ID2D1BitmapRenderTarget,
+    transforms: Vec<Matrix>,
+    pushed: Vec<Pushed>,
+    backend: Option<&'a mut RenderBackend>
+}
+
+/// A clip or a layer pushed on the render target or on the backend. They must be popped in the reverse order.
+enum Pushed {
+    Clip,
+    Layer(*mut ID2D1Layer)
 }
 
 impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
@@ -40,8 +56,14 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
     */
     pub fn clear(&mut self, r:f32, g:f32, b: f32, a: f32) {
         use winapi::D2D1_COLOR_F;
+
+        if let Some(backend) = self.backend.as_mut() {
+            backend.clear((r, g, b, a));
+            return;
+        }
+
         let color = D2D1_COLOR_F{r:r, g:g, b:b, a:a};
-        unsafe{ self.Clear(&color); }
+        unsafe{ self.target().Clear(&color); }
     }
 
     /**
@@ -49,8 +71,13 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
     */
     pub fn get_render_size(&mut self) -> (f32, f32) {
         use winapi::D2D1_SIZE_F;
+
+        if let Some(backend) = self.backend.as_ref() {
+            return backend.get_size();
+        }
+
         let mut render_size = D2D1_SIZE_F{width: 0.0, height: 0.0};
-        unsafe{ self.GetSize(&mut render_size); }
+        unsafe{ self.target().GetSize(&mut render_size); }
         
         (render_size.width as f32, render_size.height as f32)
     }
@@ -62,20 +89,182 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `m`: The 3x2 matrix.
     */
     pub fn set_transform(&mut self, m: &[[FLOAT; 2]; 3]) {
-        unsafe{ self.SetTransform( &D2D1_MATRIX_3X2_F{ matrix: *m } ); }
+        if let Some(backend) = self.backend.as_mut() {
+            backend.set_matrix(&Matrix::from_array(m));
+            return;
+        }
+
+        unsafe{ self.target().SetTransform( &D2D1_MATRIX_3X2_F{ matrix: *m } ); }
     }
 
     /**
         Return the transformation matrix of the renderer
     */
     pub fn get_transform(&mut self) -> [[FLOAT; 2]; 3] {
+        if let Some(backend) = self.backend.as_ref() {
+            return backend.get_matrix().to_array();
+        }
+
         unsafe{ 
             let mut m: D2D1_MATRIX_3X2_F = mem::uninitialized();
-            self.GetTransform( &mut m );
+            self.target().GetTransform( &mut m );
             m.matrix
         }
     }
 
+    /**
+        Set the transformation matrix of the renderer
+
+        Arguments:  
+        • `m`: The new transformation  
+    */
+    pub fn set_matrix(&mut self, m: &Matrix) {
+        self.set_transform(&m.to_array());
+    }
+
+    /**
+        Return the transformation matrix of the renderer
+    */
+    pub fn get_matrix(&mut self) -> Matrix {
+        Matrix::from_array(&self.get_transform())
+    }
+
+    /**
+        Save the current transformation and apply `m` before it. Shapes drawn until the matching `pop_transform` are
+        transformed by `m` and then by the saved transformation.
+
+        Arguments:  
+        • `m`: The transformation to add  
+    */
+    pub fn push_transform(&mut self, m: &Matrix) {
+        let current = self.get_matrix();
+        self.transforms.push(current);
+        self.set_matrix(&m.multiply(&current));
+    }
+
+    /**
+        Restore the transformation saved by the last `push_transform`.
+        Return an error if there is no transformation to restore.
+    */
+    pub fn pop_transform(&mut self) -> Result<(), Error> {
+        match self.transforms.pop() {
+            Some(m) => { self.set_matrix(&m); Ok(()) },
+            None => Err(Error::UserError("pop_transform called without a matching push_transform".to_string()))
+        }
+    }
+
+    /**
+        Restrict the drawing to the rectangle `r` until the matching `pop_clip`. The rectangle is transformed by the
+        current transformation. If the transformation rotates or skews, the clip is the bounds of the transformed rectangle.
+        Clips and layers must be popped in the reverse order of the pushes.
+
+        Arguments:  
+        • `r`: The area where the shapes can be drawn  
+    */
+    pub fn push_clip(&mut self, r: &Rectangle) {
+        use winapi::D2D1_ANTIALIAS_MODE_PER_PRIMITIVE;
+
+        if let Some(backend) = self.backend.as_mut() {
+            backend.push_clip(r);
+            self.pushed.push(Pushed::Clip);
+            return;
+        }
+
+        let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
+        unsafe{ self.target().PushAxisAlignedClip(&rect, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE); }
+        self.pushed.push(Pushed::Clip);
+    }
+
+    /**
+        Remove the clip added by the last `push_clip`.
+        Return an error if the last push was not a clip.
+    */
+    pub fn pop_clip(&mut self) -> Result<(), Error> {
+        match self.pushed.last() {
+            Some(&Pushed::Clip) => {},
+            _ => { return Err(Error::UserError("pop_clip called without a matching push_clip".to_string())); }
+        }
+
+        self.pop();
+        Ok(())
+    }
+
+    /**
+        Draw the shapes in a layer until the matching `pop_layer`. When the layer is popped, it is blended with
+        the shapes under it using `opacity` and, if `mask` is set, only the area inside the mask is kept.
+        Clips and layers must be popped in the reverse order of the pushes.
+
+        Arguments:  
+        • `opacity`: The opacity of the whole layer. 0.0 - 1.0  
+        • `mask`: Id of a path saved in the canvas. The path is transformed by the current transformation  
+        • `bounds`: The area of the layer. `None` for no limit  
+    */
+    pub fn push_layer(&mut self, opacity: f32, mask: Option<&ID>, bounds: Option<&Rectangle>) -> Result<(), Error> {
+        use winapi::{S_OK, D2D1_LAYER_PARAMETERS, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE, D2D1_LAYER_OPTIONS_NONE};
+        use std::f32::MAX;
+
+        if self.backend.is_some() {
+            let mask = match mask {
+                Some(m) => match self.backend_resource::<Path>(m, "path") {
+                    Ok(p) => Some(p),
+                    Err(e) => { return Err(e); }
+                },
+                None => None
+            };
+
+            let result = self.backend().push_layer(opacity.max(0.0).min(1.0), mask.as_ref().map(|p| p.get_data()), bounds);
+            if result.is_ok() { self.pushed.push(Pushed::Layer(ptr::null_mut())); }
+            return result;
+        }
+
+        let mask = match mask {
+            Some(m) => match self.path_setup(m) {
+                Ok(p) => p,
+                Err(e) => { return Err(e); }
+            },
+            None => ptr::null_mut()
+        };
+
+        let bounds = match bounds {
+            Some(r) => D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right},
+            None => D2D1_RECT_F{left: -MAX, top: -MAX, bottom: MAX, right: MAX}
+        };
+
+        let mut layer: *mut ID2D1Layer = ptr::null_mut();
+        if unsafe{ self.target().CreateLayer(ptr::null(), &mut layer) } != S_OK {
+            return Err(Error::System(SystemError::ComError("Failed to create the layer".to_string())));
+        }
+
+        let params = D2D1_LAYER_PARAMETERS {
+            contentBounds: bounds,
+            geometricMask: mask,
+            maskAntialiasMode: D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+            maskTransform: D2D1_MATRIX_3X2_F{ matrix: Matrix::identity().to_array() },
+            opacity: opacity.max(0.0).min(1.0),
+            opacityBrush: ptr::null_mut(),
+            layerOptions: D2D1_LAYER_OPTIONS_NONE
+        };
+
+        unsafe{ self.target().PushLayer(&params, layer); }
+        self.pushed.push(Pushed::Layer(layer));
+
+        Ok(())
+    }
+
+    /**
+        Blend the layer added by the last `push_layer` with the shapes under it.
+        Return an error if the last push was not a layer.
+    */
+    pub fn pop_layer(&mut self) -> Result<(), Error> {
+        match self.pushed.last() {
+            Some(&Pushed::Layer(_)) => {},
+            _ => { return Err(Error::UserError("pop_layer called without a matching push_layer".to_string())); }
+        }
+
+        self.pop();
+        Ok(())
+    }
+
     /**
         Fill a rectangle shape defined by `r` using the brush identified by `brush`  
 
@@ -84,13 +273,17 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `r`: The rectangle shape to draw  
     */
     pub fn fill_rectangle(&mut self, brush: &ID, r: &Rectangle) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_fill(brush, &Shape::Rectangle(r.clone()).to_path());
+        }
+
         let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
         let brush = match self.fill_setup(brush) {
             Ok(d) => d,
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.FillRectangle(&rect, mem::transmute(brush) ) };
+        unsafe{ self.target().FillRectangle(&rect, mem::transmute(brush) ) };
         Ok(())
     }
 
@@ -103,6 +296,10 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `radius`: Amount of rounding on the rectangle border. (`width`, `height`)
     */
     pub fn fill_rounded_rectangle(&mut self, brush: &ID, r: &Rectangle, radius: (f32, f32)) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_fill(brush, &Shape::RoundedRectangle{ rect: r.clone(), radius: radius }.to_path());
+        }
+
         let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
         let rect = D2D1_ROUNDED_RECT{ rect: rect, radiusX: radius.0, radiusY: radius.1 };
         let brush = match self.fill_setup(brush) {
@@ -110,7 +307,7 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.FillRoundedRectangle(&rect, mem::transmute(brush)); }
+        unsafe{ self.target().FillRoundedRectangle(&rect, mem::transmute(brush)); }
         Ok(())
     }
 
@@ -122,13 +319,17 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `e`: The ellipse shape to draw  
     */
     pub fn fill_ellipse(&mut self, brush: &ID, e: &Ellipse) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_fill(brush, &Shape::Ellipse(e.clone()).to_path());
+        }
+
         let ellipse = D2D1_ELLIPSE{point: D2D1_POINT_2F{ x: e.center.0, y: e.center.1 } , radiusX: e.radius.0, radiusY: e.radius.1};
         let brush = match self.fill_setup(brush) {
             Ok(d) => d,
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.FillEllipse(&ellipse, mem::transmute(brush)); }
+        unsafe{ self.target().FillEllipse(&ellipse, mem::transmute(brush)); }
         Ok(())
     }
 
@@ -143,13 +344,17 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `width`: Width of the outline to draw  
     */
     pub fn draw_rectangle(&mut self, brush: &ID, pen: Option<&ID>, r: &Rectangle, width: f32) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_stroke(brush, pen, &Shape::Rectangle(r.clone()).to_path(), width);
+        }
+
         let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
         let (brush, pen) = match self.draw_setup(brush, pen) {
             Ok(d) => d,
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.DrawRectangle(&rect, mem::transmute(brush), width, mem::transmute(pen)); }
+        unsafe{ self.target().DrawRectangle(&rect, mem::transmute(brush), width, mem::transmute(pen)); }
 
         Ok(())
     }
@@ -166,6 +371,10 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `radius`: Amount of rounding on the rectangle border. (`width`, `height`)  
     */
     pub fn draw_rounded_rectangle(&mut self, brush: &ID, pen: Option<&ID>, r: &Rectangle, width: f32, radius: (f32, f32)) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_stroke(brush, pen, &Shape::RoundedRectangle{ rect: r.clone(), radius: radius }.to_path(), width);
+        }
+
         let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
         let rect = D2D1_ROUNDED_RECT{ rect: rect, radiusX: radius.0, radiusY: radius.1 };
         let (brush, pen) = match self.draw_setup(brush, pen) {
@@ -173,7 +382,7 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.DrawRoundedRectangle(&rect, mem::transmute(brush), width, mem::transmute(pen)); }
+        unsafe{ self.target().DrawRoundedRectangle(&rect, mem::transmute(brush), width, mem::transmute(pen)); }
 
         Ok(())
     }
@@ -189,17 +398,323 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         • `width`: Width of the outline to draw  
     */
     pub fn draw_ellipse(&mut self, brush: &ID, pen: Option<&ID>, e: &Ellipse, width: f32) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_stroke(brush, pen, &Shape::Ellipse(e.clone()).to_path(), width);
+        }
+
         let ellipse = D2D1_ELLIPSE{point: D2D1_POINT_2F{ x: e.center.0, y: e.center.1 } , radiusX: e.radius.0, radiusY: e.radius.1};
         let (brush, pen) = match self.draw_setup(brush, pen) {
             Ok(d) => d,
             Err(e) => { return Err(e); }
         };
 
-        unsafe{ self.DrawEllipse(&ellipse, mem::transmute(brush), width, mem::transmute(pen)); }
+        unsafe{ self.target().DrawEllipse(&ellipse, mem::transmute(brush), width, mem::transmute(pen)); }
+
+        Ok(())
+    }
+
+    /**
+        Draw a straight line from `start` to `end` using the brush identified by `brush` and the pen identifed
+        by `pen`.
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `pen`: Id of the pen saved in the canvas  
+        • `start`: The first point of the line  
+        • `end`: The last point of the line  
+        • `width`: Width of the line to draw  
+    */
+    pub fn draw_line(&mut self, brush: &ID, pen: Option<&ID>, start: (f32, f32), end: (f32, f32), width: f32) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_stroke(brush, pen, &Shape::Line{ start: start, end: end }.to_path(), width);
+        }
+
+        let p0 = D2D1_POINT_2F{ x: start.0, y: start.1 };
+        let p1 = D2D1_POINT_2F{ x: end.0, y: end.1 };
+        let (brush, pen) = match self.draw_setup(brush, pen) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        unsafe{ self.target().DrawLine(p0, p1, mem::transmute(brush), width, mem::transmute(pen)); }
+
+        Ok(())
+    }
+
+    /**
+        Draw straight lines joining `points` using the brush identified by `brush` and the pen identifed
+        by `pen`. The lines are joined with the line join of the pen.
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `pen`: Id of the pen saved in the canvas  
+        • `points`: The points to join  
+        • `closed`: If true, the last point is joined to the first one  
+        • `width`: Width of the lines to draw  
+    */
+    pub fn draw_polyline(&mut self, brush: &ID, pen: Option<&ID>, points: &[(f32, f32)], closed: bool, width: f32) -> Result<(), Error> {
+        self.draw_path_data(brush, pen, &PathData::polyline(points, closed), width)
+    }
+
+    /**
+        Fill the polygon defined by `points` using the brush identified by `brush`  
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `points`: The corners of the polygon  
+        • `mode`: How the polygon is filled if its sides cross each other  
+    */
+    pub fn fill_polygon(&mut self, brush: &ID, points: &[(f32, f32)], mode: FillMode) -> Result<(), Error> {
+        self.fill_path_data(brush, &PathData::polyline(points, true).fill_mode(mode))
+    }
+
+    /**
+        Fill the path identified by `path` using the brush identified by `brush`. Figures that are not closed
+        are filled as if they were.
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `path`: Id of the path saved in the canvas  
+    */
+    pub fn fill_path(&mut self, brush: &ID, path: &ID) -> Result<(), Error> {
+        if self.backend.is_some() {
+            let path = match self.backend_resource::<Path>(path, "path") {
+                Ok(p) => p,
+                Err(e) => { return Err(e); }
+            };
+
+            return self.backend_fill(brush, path.get_data());
+        }
+
+        let brush = match self.fill_setup(brush) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let path = match self.path_setup(path) {
+            Ok(p) => p,
+            Err(e) => { return Err(e); }
+        };
+
+        unsafe{ self.target().FillGeometry(path, mem::transmute(brush), ptr::null_mut()); }
+        Ok(())
+    }
+
+    /**
+        Draw the outline of the path identified by `path` using the brush identified by `brush` and the pen identifed
+        by `pen`.
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `pen`: Id of the pen saved in the canvas  
+        • `path`: Id of the path saved in the canvas  
+        • `width`: Width of the outline to draw  
+    */
+    pub fn draw_path(&mut self, brush: &ID, pen: Option<&ID>, path: &ID, width: f32) -> Result<(), Error> {
+        if self.backend.is_some() {
+            let path = match self.backend_resource::<Path>(path, "path") {
+                Ok(p) => p,
+                Err(e) => { return Err(e); }
+            };
+
+            return self.backend_stroke(brush, pen, path.get_data(), width);
+        }
+
+        let (brush, pen) = match self.draw_setup(brush, pen) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let path = match self.path_setup(path) {
+            Ok(p) => p,
+            Err(e) => { return Err(e); }
+        };
 
+        unsafe{ self.target().DrawGeometry(path, mem::transmute(brush), width, mem::transmute(pen)); }
         Ok(())
     }
 
+    /**
+        Draw `text` in the rectangle `r` using the brush identified by `brush` and the text format identified by `format`.
+        To draw the same text many times, format it once with `Canvas::create_text_layout` and use `draw_text_layout`.
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `format`: Id of the text format saved in the canvas  
+        • `text`: The text to draw  
+        • `r`: The layout box of the text  
+    */
+    pub fn draw_text(&mut self, brush: &ID, format: &ID, text: &str, r: &Rectangle) -> Result<(), Error> {
+        use winapi::{D2D1_DRAW_TEXT_OPTIONS_NONE, DWRITE_MEASURING_MODE_NATURAL};
+
+        if self.backend.is_some() {
+            let brush = match self.backend_resource::<Brush>(brush, "brush") {
+                Ok(b) => b,
+                Err(e) => { return Err(e); }
+            };
+
+            let format = match self.backend_resource::<TextFormat>(format, "text format") {
+                Ok(f) => f,
+                Err(e) => { return Err(e); }
+            };
+
+            return self.backend().draw_text(text, format.get_style(), r, brush.get_paint());
+        }
+
+        let rect = D2D1_RECT_F{left: r.left, top: r.top, bottom: r.bottom, right: r.right};
+        let brush = match self.fill_setup(brush) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let format = match text_format_handle(self.ui, format) {
+            Ok(f) => f,
+            Err(e) => { return Err(e); }
+        };
+
+        let text: Vec<u16> = text.encode_utf16().collect();
+        unsafe{ 
+            self.target().DrawText(text.as_ptr(), text.len() as u32, format, &rect, mem::transmute(brush),
+              D2D1_DRAW_TEXT_OPTIONS_NONE, DWRITE_MEASURING_MODE_NATURAL);
+        }
+
+        Ok(())
+    }
+
+    /**
+        Draw a text layout using the brush identified by `brush`
+
+        Arguments:  
+        • `brush`: Id of the brush saved in the canvas  
+        • `layout`: The text layout to draw  
+        • `origin`: The position of the top left corner of the layout box  
+    */
+    pub fn draw_text_layout(&mut self, brush: &ID, layout: &TextLayout, origin: (f32, f32)) -> Result<(), Error> {
+        use winapi::D2D1_DRAW_TEXT_OPTIONS_NONE;
+
+        if self.backend.is_some() {
+            return Err(Error::UserError("Text layouts cannot be drawn by a renderer backend".to_string()));
+        }
+
+        let brush = match self.fill_setup(brush) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let origin = D2D1_POINT_2F{ x: origin.0, y: origin.1 };
+        unsafe{ self.target().DrawTextLayout(origin, layout.get_handle(), mem::transmute(brush), D2D1_DRAW_TEXT_OPTIONS_NONE); }
+
+        Ok(())
+    }
+
+    /**
+        Draw the bitmap identified by `bitmap` in the rectangle `dest`. The bitmap is stretched to fill the rectangle.
+
+        `dest` is in device independent pixels, like every other renderer coordinates, so the bitmap keeps its size
+        when the canvas DPI changes (see `Canvas::set_dpi`). `src` is in pixels of the bitmap.
+
+        Arguments:  
+        • `bitmap`: Id of the bitmap saved in the canvas  
+        • `dest`: The area of the canvas where the bitmap is drawn  
+        • `src`: The area of the bitmap to draw. `None` draws the whole bitmap  
+        • `opacity`: The opacity of the bitmap. 0.0 - 1.0  
+        • `interpolation`: How the bitmap is sampled when it is scaled  
+    */
+    pub fn draw_bitmap(&mut self, bitmap: &ID, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32, interpolation: BitmapInterpolation) -> Result<(), Error> {
+        use winapi::D2D1_BITMAP_INTERPOLATION_MODE;
+
+        if self.backend.is_some() {
+            let bitmap = match self.backend_resource::<Bitmap>(bitmap, "bitmap") {
+                Ok(b) => b,
+                Err(e) => { return Err(e); }
+            };
+
+            return self.backend().draw_image(bitmap.get_data(), dest, src, opacity.max(0.0).min(1.0), interpolation);
+        }
+
+        let bitmap = match self.bitmap_setup(bitmap) {
+            Ok(b) => b,
+            Err(e) => { return Err(e); }
+        };
+
+        let dest = D2D1_RECT_F{left: dest.left, top: dest.top, bottom: dest.bottom, right: dest.right};
+        let src = src.map(|r| {
+            // The source rectangle of DrawBitmap is in device independent pixels of the bitmap
+            let (mut dpix, mut dpiy) = (96.0, 96.0);
+            unsafe{ (&mut *bitmap).GetDpi(&mut dpix, &mut dpiy); }
+            let (sx, sy) = (96.0 / dpix, 96.0 / dpiy);
+            D2D1_RECT_F{left: r.left * sx, top: r.top * sy, bottom: r.bottom * sy, right: r.right * sx}
+        });
+
+        let src_ptr = match src.as_ref() {
+            Some(r) => r as *const D2D1_RECT_F,
+            None => ptr::null()
+        };
+
+        let opacity = opacity.max(0.0).min(1.0);
+        unsafe{ self.target().DrawBitmap(bitmap, &dest, opacity, D2D1_BITMAP_INTERPOLATION_MODE(interpolation as u32), src_ptr); }
+
+        Ok(())
+    }
+
+    /**
+        Draw the visible nodes of `scene` in their drawing order. If the scene has a background color, the render target
+        is cleared first. The transformation of the nodes is applied before the current transformation of the renderer.
+        The drawing stops at the first node that cannot be drawn.
+
+        Arguments:  
+        • `scene`: The scene to draw. The style of the nodes uses the resources saved in the canvas  
+    */
+    pub fn draw_scene(&mut self, scene: &Scene<ID>) -> Result<(), Error> {
+        if let Some((r, g, b, a)) = scene.get_background() {
+            self.clear(r, g, b, a);
+        }
+
+        let base = self.get_matrix();
+        let mut result = Ok(());
+        for id in scene.draw_order() {
+            let transform = scene.world_transform(id).unwrap();
+            self.set_matrix(&transform.multiply(&base));
+
+            result = self.draw_node(scene.get(id).unwrap());
+            if result.is_err() { break; }
+        }
+
+        self.set_matrix(&base);
+        result
+    }
+
+    /**
+        Draw the commands of a display list recorded by `Canvas::record`. The current transformation of the renderer
+        is applied after the transformation of the commands. The drawing stops at the first command that cannot be drawn.
+
+        Arguments:  
+        • `list`: The display list to draw  
+    */
+    pub fn draw_display_list(&mut self, list: &DisplayList) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return list.replay(self.backend());
+        }
+
+        let mut backend = Direct2DBackend::new(self.target, self.canvas.get_factory(), self.canvas.get_write_factory());
+        list.replay(&mut backend)
+    }
+
+    /**
+        Return true if the renderer draws on an offscreen bitmap or on a renderer backend
+    */
+    pub fn is_offscreen(&self) -> bool {
+        !self.offscreen.is_null() || self.backend.is_some()
+    }
+
+    /**
+        Return the render target the renderer draws on
+    */
+    #[inline(always)]
+    fn target(&mut self) -> &mut ID2D1RenderTarget {
+        unsafe{ &mut *self.target }
+    }
+
     fn fill_setup(&mut self, brush: &ID) -> Result<*mut ID2D1Brush, Error> {
         match self.ui.handle_of(brush) {
             Ok(AnyHandle::Custom(t, h)) => {
@@ -211,6 +726,192 @@ impl<'a, ID: Clone+Hash> CanvasRenderer<'a, ID> {
         }
     }
 
+    /// Pop the last clip or layer
+    fn pop(&mut self) {
+        let pushed = self.pushed.pop();
+        if let Some(backend) = self.backend.as_mut() {
+            match pushed {
+                Some(Pushed::Clip) => backend.pop_clip(),
+                Some(Pushed::Layer(_)) => backend.pop_layer(),
+                None => {}
+            }
+            return;
+        }
+
+        match pushed {
+            Some(Pushed::Clip) => unsafe{ self.target().PopAxisAlignedClip(); },
+            Some(Pushed::Layer(layer)) => unsafe{
+                self.target().PopLayer();
+                (&mut *layer).Release();
+            },
+            None => {}
+        }
+    }
+
+    fn path_setup(&mut self, path: &ID) -> Result<*mut ID2D1Geometry, Error> {
+        match self.ui.handle_of(path) {
+            Ok(AnyHandle::Custom(t, h)) => {
+                if t == TypeId::of::<Path>() { Ok(h as *mut ID2D1Geometry) }
+                else { Err(Error::BadResource( format!("A path resource required. Got a custom handle of another type") )) }
+            },
+            Ok(h) => Err(Error::BadResource( format!("A path resource required. Got {}", h.human_name()) )),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn bitmap_setup(&mut self, bitmap: &ID) -> Result<*mut ID2D1Bitmap, Error> {
+        match self.ui.handle_of(bitmap) {
+            Ok(AnyHandle::Custom(t, h)) => {
+                if t == TypeId::of::<Bitmap>() { Ok(h as *mut ID2D1Bitmap) }
+                else { Err(Error::BadResource( format!("A bitmap resource required. Got a custom handle of another type") )) }
+            },
+            Ok(h) => Err(Error::BadResource( format!("A bitmap resource required. Got {}", h.human_name()) )),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Fill a path that is not saved in the canvas
+    fn fill_path_data(&mut self, brush: &ID, path: &PathData) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_fill(brush, path);
+        }
+
+        let brush = match self.fill_setup(brush) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let geometry = match self.temporary_geometry(path) {
+            Ok(g) => g,
+            Err(e) => { return Err(e); }
+        };
+
+        unsafe{ 
+            self.target().FillGeometry(geometry as *mut ID2D1Geometry, mem::transmute(brush), ptr::null_mut());
+            (&mut *geometry).Release();
+        }
+
+        Ok(())
+    }
+
+    /// Draw the outline of a path that is not saved in the canvas
+    fn draw_path_data(&mut self, brush: &ID, pen: Option<&ID>, path: &PathData, width: f32) -> Result<(), Error> {
+        if self.backend.is_some() {
+            return self.backend_stroke(brush, pen, path, width);
+        }
+
+        let (brush, pen) = match self.draw_setup(brush, pen) {
+            Ok(d) => d,
+            Err(e) => { return Err(e); }
+        };
+
+        let geometry = match self.temporary_geometry(path) {
+            Ok(g) => g,
+            Err(e) => { return Err(e); }
+        };
+
+        unsafe{ 
+            self.target().DrawGeometry(geometry as *mut ID2D1Geometry, mem::transmute(brush), width, mem::transmute(pen));
+            (&mut *geometry).Release();
+        }
+
+        Ok(())
+    }
+
+    /// Draw a scene node with the current transformation
+    fn draw_node(&mut self, node: &SceneNode<ID>) -> Result<(), Error> {
+        let style = &node.style;
+
+        if let Shape::Text{ ref text, ref bounds } = node.shape {
+            return match (style.fill.as_ref(), style.text_format.as_ref()) {
+                (Some(brush), Some(format)) => self.draw_text(brush, format, text, bounds),
+                (Some(_), None) => Err(Error::UserError("A text node requires a text format".to_string())),
+                (None, _) => Ok(())
+            };
+        }
+
+        if let Some(ref brush) = style.fill {
+            let result = match node.shape {
+                Shape::Rectangle(ref r) => self.fill_rectangle(brush, r),
+                Shape::RoundedRectangle{ ref rect, radius } => self.fill_rounded_rectangle(brush, rect, radius),
+                Shape::Ellipse(ref e) => self.fill_ellipse(brush, e),
+                Shape::Polyline{ ref points, .. } => self.fill_polygon(brush, points, FillMode::Winding),
+                Shape::Path(ref path) => self.fill_path_data(brush, path),
+                Shape::Line{..} | Shape::Text{..} => Ok(())
+            };
+
+            if let Err(e) = result { return Err(e); }
+        }
+
+        if let Some(ref brush) = style.stroke {
+            let (pen, width) = (style.pen.as_ref(), style.stroke_width);
+            let result = match node.shape {
+                Shape::Rectangle(ref r) => self.draw_rectangle(brush, pen, r, width),
+                Shape::RoundedRectangle{ ref rect, radius } => self.draw_rounded_rectangle(brush, pen, rect, width, radius),
+                Shape::Ellipse(ref e) => self.draw_ellipse(brush, pen, e, width),
+                Shape::Line{ start, end } => self.draw_line(brush, pen, start, end, width),
+                Shape::Polyline{ ref points, closed } => self.draw_polyline(brush, pen, points, closed, width),
+                Shape::Path(ref path) => self.draw_path_data(brush, pen, path, width),
+                Shape::Text{..} => Ok(())
+            };
+
+            if let Err(e) = result { return Err(e); }
+        }
+
+        Ok(())
+    }
+
+    /// Return the backend of a renderer created by `Canvas::render_with`
+    fn backend(&mut self) -> &mut (RenderBackend + 'a) {
+        match self.backend.as_mut() {
+            Some(b) => &mut **b,
+            None => { unreachable!(); } // Only called when the renderer has a backend
+        }
+    }
+
+    /// Return the resource identified by `id` to draw it with the backend
+    fn backend_resource<T: 'static>(&self, id: &ID, name: &str) -> Result<Ref<'a, Box<T>>, Error> {
+        match self.ui.get::<T>(id) {
+            Ok(r) => Ok(r),
+            Err(Error::BadType) => Err(Error::BadResource( format!("A {} resource required", name) )),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Fill a path on the backend
+    fn backend_fill(&mut self, brush: &ID, path: &PathData) -> Result<(), Error> {
+        let brush = match self.backend_resource::<Brush>(brush, "brush") {
+            Ok(b) => b,
+            Err(e) => { return Err(e); }
+        };
+
+        self.backend().fill_path(path, brush.get_paint())
+    }
+
+    /// Draw the outline of a path on the backend
+    fn backend_stroke(&mut self, brush: &ID, pen: Option<&ID>, path: &PathData, width: f32) -> Result<(), Error> {
+        let brush = match self.backend_resource::<Brush>(brush, "brush") {
+            Ok(b) => b,
+            Err(e) => { return Err(e); }
+        };
+
+        let pen = match pen {
+            Some(p) => match self.backend_resource::<Pen>(p, "pen") {
+                Ok(p) => Some(p),
+                Err(e) => { return Err(e); }
+            },
+            None => None
+        };
+
+        self.backend().stroke_path(path, brush.get_paint(), pen.as_ref().map(|p| p.get_style()), width)
+    }
+
+    /// Build a geometry that is released by the caller once it is drawn
+    fn temporary_geometry(&mut self, path: &PathData) -> Result<*mut ID2D1PathGeometry, Error> {
+        let factory = self.canvas.get_factory();
+        create_path_geometry(path, unsafe{ &mut *factory })
+    }
+
     fn draw_setup(&mut self, brush: &ID, pen: Option<&ID>) -> Result<(*mut ID2D1Brush, *mut ID2D1StrokeStyle), Error> {
         let brush = match self.ui.handle_of(brush) {
             Ok(AnyHandle::Custom(t, h)) => {
@@ -249,10 +950,23 @@ impl<'a, ID: Clone+Hash> DerefMut for CanvasRenderer<'a, ID> {
 
 impl<'a, ID: Clone+Hash> Drop for CanvasRenderer<'a, ID> {
     fn drop(&mut self) {
+        // Direct2D fails to end the drawing if a clip or a layer is not popped
+        while !self.pushed.is_empty() {
+            self.pop();
+        }
+
+        if self.backend.is_some() {
+            return;
+        }
+
         unsafe{ 
             use winapi::D2DERR_RECREATE_TARGET;
-            let recreate = self.EndDraw(ptr::null_mut(), ptr::null_mut()) == D2DERR_RECREATE_TARGET;
-            self.canvas.set_must_recreate_target(recreate); 
+            let recreate = self.target().EndDraw(ptr::null_mut(), ptr::null_mut()) == D2DERR_RECREATE_TARGET;
+            if self.offscreen.is_null() {
+                self.canvas.set_must_recreate_target(recreate); 
+            } else {
+                (&mut *self.offscreen).Release();
+            }
         }
     }
 }
@@ -262,6 +976,9 @@ impl<'a, ID: Clone+Hash> Drop for CanvasRenderer<'a, ID> {
 */
 pub trait RendererProtected<'a, ID: Clone+Hash>  {
     fn prepare(canvas: &'a mut Canvas<ID>, ui: &'a Ui<ID>) -> Result<CanvasRenderer<'a, ID>, Error>;
+    fn prepare_offscreen(canvas: &'a mut Canvas<ID>, ui: &'a Ui<ID>, width: u32, height: u32) -> Result<CanvasRenderer<'a, ID>, Error>;
+    fn prepare_backend(canvas: &'a mut Canvas<ID>, ui: &'a Ui<ID>, backend: &'a mut RenderBackend) -> Result<CanvasRenderer<'a, ID>, Error>;
+    fn read_offscreen(&mut self) -> Result<ImageData, Error>;
 }
 
 impl<'a, ID: Clone+Hash> RendererProtected<'a, ID> for CanvasRenderer<'a, ID> {
@@ -274,17 +991,133 @@ impl<'a, ID: Clone+Hash> RendererProtected<'a, ID> for CanvasRenderer<'a, ID> {
                 }
             }
 
-            let identity = D2D1_MATRIX_3X2_F {
-                matrix: [[1.0, 0.0],
-                         [0.0, 1.0],
-                         [0.0, 0.0]]
-            };
+            let target = canvas.get_render_target() as *mut ID2D1RenderTarget;
+            begin_draw(&mut *target);
+
+            Ok( CanvasRenderer { canvas: canvas, ui: ui, target: target, offscreen: ptr::null_mut(), transforms: Vec::new(), pushed: Vec::new(), backend: None } )
+        }
+    }
+
+    fn prepare_offscreen(canvas: &'a mut Canvas<ID>, ui: &'a Ui<ID>, width: u32, height: u32) -> Result<CanvasRenderer<'a, ID>, Error> {
+        use winapi::{S_OK, D2D1_SIZE_F, D2D1_SIZE_U, D2D1_PIXEL_FORMAT, D2D1_COLOR_F, DXGI_FORMAT_B8G8R8A8_UNORM,
+          D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_GDI_COMPATIBLE};
+        use resources::codecs::check_dimensions;
+
+        if let Err(e) = check_dimensions(width, height) {
+            return Err(Error::UserError(format!("Invalid offscreen size: {}", e)));
+        }
+
+        unsafe{
+            if canvas.get_must_recreate_target() {
+                if let Err(e) = canvas.rebuild() {
+                    return Err(Error::System(e));
+                }
+            }
+
+            // The render target uses 96 DPI, so that one unit is one pixel
+            let size = D2D1_SIZE_F{ width: width as f32, height: height as f32 };
+            let pixel_size = D2D1_SIZE_U{ width: width, height: height };
+            let format = D2D1_PIXEL_FORMAT{ format: DXGI_FORMAT_B8G8R8A8_UNORM, alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED };
+
+            let mut offscreen: *mut ID2D1BitmapRenderTarget = ptr::null_mut();
+            let result = (&mut *canvas.get_render_target()).CreateCompatibleRenderTarget(
+                &size, &pixel_size, &format, D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_GDI_COMPATIBLE, &mut offscreen);
+
+            if result != S_OK {
+                let msg = "Could not create the offscreen render target".to_string();
+                return Err(Error::System(SystemError::ComError(msg)));
+            }
+
+            let target = offscreen as *mut ID2D1RenderTarget;
+            (&mut *target).SetDpi(96.0, 96.0);
+            begin_draw(&mut *target);
+            (&mut *target).Clear(&D2D1_COLOR_F{r: 0.0, g: 0.0, b: 0.0, a: 0.0});
+
+            Ok( CanvasRenderer { canvas: canvas, ui: ui, target: target, offscreen: offscreen, transforms: Vec::new(), pushed: Vec::new(), backend: None } )
+        }
+    }
 
-            canvas.BeginDraw(); 
-            canvas.SetTransform(&identity);
+    fn prepare_backend(canvas: &'a mut Canvas<ID>, ui: &'a Ui<ID>, backend: &'a mut RenderBackend) -> Result<CanvasRenderer<'a, ID>, Error> {
+        backend.set_matrix(&Matrix::identity());
+
+        let target = ptr::null_mut();
+        Ok( CanvasRenderer { canvas: canvas, ui: ui, target: target, offscreen: ptr::null_mut(), transforms: Vec::new(), pushed: Vec::new(), backend: Some(backend) } )
+    }
+
+    /// Copy the pixels of the offscreen bitmap. Must be called before the renderer is dropped.
+    fn read_offscreen(&mut self) -> Result<ImageData, Error> {
+        use winapi::{S_OK, HDC, ID2D1GdiInteropRenderTarget, D2D1_DC_INITIALIZE_MODE_COPY, D2D1_SIZE_U, SRCCOPY};
+        use gdi32::{CreateCompatibleDC, SelectObject, BitBlt, DeleteDC, DeleteObject};
+        use low::clsid::IID_ID2D1GdiInteropRenderTarget;
+        use low::image_helper::{create_dib_section, read_bitmap};
+
+        if self.offscreen.is_null() {
+            return Err(Error::UserError("The renderer does not draw on an offscreen bitmap".to_string()));
         }
 
-        Ok( CanvasRenderer { canvas: canvas, ui: ui } )
+        while !self.pushed.is_empty() {
+            self.pop();
+        }
+
+        unsafe {
+            let mut size = D2D1_SIZE_U{ width: 0, height: 0 };
+            (&mut *self.target).GetPixelSize(&mut size);
+            let (w, h) = (size.width, size.height);
+
+            let mut interop: *mut ID2D1GdiInteropRenderTarget = ptr::null_mut();
+            let iid = IID_ID2D1GdiInteropRenderTarget();
+            if (&mut *self.target).QueryInterface(&iid, mem::transmute(&mut interop)) != S_OK {
+                let msg = "The offscreen render target is not GDI compatible".to_string();
+                return Err(Error::System(SystemError::ComError(msg)));
+            }
+
+            let interop = &mut *interop;
+            let mut source_dc: HDC = ptr::null_mut();
+            if interop.GetDC(D2D1_DC_INITIALIZE_MODE_COPY, &mut source_dc) != S_OK {
+                interop.Release();
+                let msg = "Could not read the offscreen render target".to_string();
+                return Err(Error::System(SystemError::ComError(msg)));
+            }
+
+            let pixels = vec![0u8; (w as usize) * (h as usize) * 4];
+            let result = match create_dib_section(w, h, &pixels) {
+                Ok(bitmap) => {
+                    let dc = CreateCompatibleDC(source_dc);
+                    let old = SelectObject(dc, bitmap as _);
+                    BitBlt(dc, 0, 0, w as i32, h as i32, source_dc, 0, 0, SRCCOPY);
+                    SelectObject(dc, old);
+                    DeleteDC(dc);
+
+                    // A bitmap without alpha is a bitmap where nothing was drawn
+                    let image = read_bitmap(bitmap).map(|(mut image, has_alpha)| {
+                        if !has_alpha { image.data = vec![0; image.data.len()]; }
+                        image
+                    });
+                    DeleteObject(bitmap as _);
+                    image
+                },
+                Err(e) => Err(e)
+            };
+
+            interop.ReleaseDC(ptr::null());
+            interop.Release();
+
+            result
+        }
     }
 
-}
\ No newline at end of file
+}
+
+/**
+    Begin drawing on a render target and reset its transformation matrix
+*/
+unsafe fn begin_draw(target: &mut ID2D1RenderTarget) {
+    let identity = D2D1_MATRIX_3X2_F {
+        matrix: [[1.0, 0.0],
+                 [0.0, 1.0],
+                 [0.0, 0.0]]
+    };
+
+    target.BeginDraw(); 
+    target.SetTransform(&identity);
+}
@@ -0,0 +1,187 @@
+/*!
+    A chart control drawing series of points on a canvas
+*/
+
+use std::hash::Hash;
+use std::any::TypeId;
+
+use ui::Ui;
+use controls::{Control, ControlT, ControlType, AnyHandle};
+use controls::canvas::{CanvasT, Canvas, build_canvas};
+use error::Error;
+use accessibility::Accessibility;
+use resources::canvas::DisplayList;
+use super::Plot;
+
+/**
+    A template that creates a chart. The chart starts without series, see `Chart::update_plot`.
+
+    Members:  
+    • `parent`: The chart parent  
+    • `position`: The start position of the chart  
+    • `size`: The start size of the chart  
+    • `visible`: If the chart should be visible to the user  
+    • `disabled`: If the chart is enabled or not. A disabled chart do not show tooltips  
+    • `title`: The title displayed above the plot. An empty title is not displayed  
+    • `legend`: If the legend should be displayed  
+*/
+#[derive(Clone)]
+pub struct ChartT<S: Clone+Into<String>, ID: Hash+Clone> {
+    pub parent: ID,
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+    pub visible: bool,
+    pub disabled: bool,
+    pub title: S,
+    pub legend: bool
+}
+
+impl<S: Clone+Into<String>, ID: Hash+Clone+'static> ControlT<ID> for ChartT<S, ID> {
+    fn type_id(&self) -> TypeId { TypeId::of::<Chart<ID>>() }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
+        let t = CanvasT{ parent: self.parent.clone(), position: self.position, size: self.size, visible: self.visible, disabled: self.disabled };
+        let canvas = match build_canvas(ui, &t) {
+            Ok(c) => c,
+            Err(e) => { return Err(e); }
+        };
+
+        let mut plot = Plot::new();
+        plot.set_title(self.title.clone());
+        plot.set_legend(self.legend);
+
+        Ok( Box::new(Chart{ canvas: canvas, plot: plot }) as Box<Control> )
+    }
+}
+
+/**
+    A control that draws a plot: line, bar, area and scatter series with their axes and a legend.
+    Hovering a point displays a tooltip with its value.
+
+    The chart is a canvas drawn by the library. It is redrawn each time its plot is modified.
+*/
+pub struct Chart<ID: Clone+Hash> {
+    canvas: Canvas<ID>,
+    plot: Plot
+}
+
+impl<ID: Clone+Hash> Chart<ID> {
+
+    /**
+        Return the plot drawn by the chart
+    */
+    pub fn plot(&self) -> &Plot {
+        &self.plot
+    }
+
+    /**
+        Replace the plot drawn by the chart and redraw the chart
+    */
+    pub fn set_plot(&mut self, plot: Plot) {
+        self.plot = plot;
+        self.redraw();
+    }
+
+    /**
+        Modify the plot drawn by the chart and redraw the chart. Use it to add points to a live plot:
+
+        `chart.update_plot(|p| p.push_points(0, &[(time, value)]));`
+    */
+    pub fn update_plot<F, R>(&mut self, update: F) -> R
+      where F: FnOnce(&mut Plot) -> R
+    {
+        let result = update(&mut self.plot);
+        self.redraw();
+        result
+    }
+
+    /**
+        Record the chart as it is currently displayed. The display list can be saved with `DisplayList::to_svg`.
+    */
+    pub fn record(&self) -> Result<DisplayList, Error> {
+        let (width, height) = self.render_size();
+        let mut list = DisplayList::new(width, height);
+        match self.plot.draw(&mut list) {
+            Ok(()) => Ok(list),
+            Err(e) => Err(e)
+        }
+    }
+
+    /**
+        Redraw the chart
+    */
+    pub fn redraw(&self) {
+        self.canvas.redraw();
+    }
+
+    /**
+        Draw the plot on the chart
+    */
+    pub(crate) fn paint(&mut self, ui: &Ui<ID>) -> Result<(), Error> {
+        let mut renderer = self.canvas.renderer(ui)?;
+        let (width, height) = renderer.get_render_size();
+        let mut list = DisplayList::new(width, height);
+        self.plot.draw(&mut list)?;
+        renderer.draw_display_list(&list)
+    }
+
+    /**
+        Set the point under the mouse, `None` if the mouse left the chart. `pos` is in device independent pixels.
+        Return true if the point with a tooltip changed.
+    */
+    pub(crate) fn hover(&mut self, pos: Option<(f32, f32)>) -> bool {
+        let (width, height) = self.render_size();
+        let hit = match pos {
+            Some(pos) if self.get_enabled() => self.plot.hit_test(width, height, pos),
+            _ => None
+        };
+
+        let changed = self.plot.set_hover(hit);
+        if changed { self.redraw(); }
+        changed
+    }
+
+    /**
+        Return the chart's dots per inch (DPI). The plot coordinates are in device independent pixels:
+        one unit covers `dpi / 96` pixels of the chart.
+    */
+    pub fn get_dpi(&mut self) -> (f32, f32) { self.canvas.get_dpi() }
+
+    /// Size of the chart in device independent pixels
+    fn render_size(&self) -> (f32, f32) {
+        use winapi::D2D1_SIZE_F;
+
+        let mut size = D2D1_SIZE_F{ width: 0.0, height: 0.0 };
+        unsafe{ (&mut *self.canvas.get_render_target()).GetSize(&mut size); }
+        (size.width, size.height)
+    }
+
+    pub fn get_visibility(&self) -> bool { self.canvas.get_visibility() }
+    pub fn set_visibility(&self, visible: bool) { self.canvas.set_visibility(visible); }
+    pub fn get_position(&self) -> (i32, i32) { self.canvas.get_position() }
+    pub fn set_position(&self, x: i32, y: i32) { self.canvas.set_position(x, y); }
+    pub fn get_size(&self) -> (u32, u32) { self.canvas.get_size() }
+    pub fn set_size(&self, w: u32, h: u32) { self.canvas.set_size(w, h); }
+    pub fn get_enabled(&self) -> bool { self.canvas.get_enabled() }
+    pub fn set_enabled(&self, e:bool) { self.canvas.set_enabled(e); }
+    pub fn get_accessibility(&self) -> Accessibility { self.canvas.get_accessibility() }
+    pub fn set_accessibility(&self, info: Accessibility) { self.canvas.set_accessibility(info); }
+    pub fn update(&self) { self.canvas.update(); }
+    pub fn focus(&self) { self.canvas.focus(); }
+}
+
+impl<ID: Clone+Hash> Control for Chart<ID> {
+
+    fn handle(&self) -> AnyHandle {
+        self.canvas.handle()
+    }
+
+    fn control_type(&self) -> ControlType {
+        ControlType::Chart
+    }
+
+    fn free(&mut self) {
+        self.canvas.free();
+    }
+
+}
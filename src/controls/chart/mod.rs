@@ -0,0 +1,5 @@
+mod chart;
+mod plot;
+
+pub use self::chart::{ChartT, Chart};
+pub use self::plot::{Plot, PlotLayout, Series, SeriesType, ChartStyle, Scale, nice_step};
@@ -0,0 +1,842 @@
+/*!
+    The content of a chart: the series of points, the axes and the legend. A plot computes its own layout and draws
+    itself on any renderer backend, so charts can be built, hit-tested and exported without a window.
+*/
+
+use resources::canvas::{RenderBackend, Paint, Shape};
+use resources::canvas::defs::{Rectangle, Ellipse, PathData, Pen, Matrix, CapStyle, LineJoin, DashStyle, TextStyle, FontStyle,
+  TextAlignment, ParagraphAlignment, WordWrapping};
+use error::Error;
+
+/// Space around the plot area and between its parts
+const PADDING: f32 = 8.0;
+
+/// Approximate width of a character, relative to the font size. Used to measure the labels without a text layout.
+const CHAR_WIDTH: f32 = 0.6;
+
+/// Maximum distance between the mouse and a point hit by `hit_test`
+const HIT_DISTANCE: f32 = 8.0;
+
+/// Fraction of the space between two bars covered by a bar group
+const BAR_FILL: f64 = 0.8;
+
+/**
+    How the points of a series are drawn
+
+    • `Line`: The points are joined by straight lines  
+    • `Bar`: A bar goes from zero to each point. The bars of the bar series sharing an x value are drawn side by side  
+    • `Area`: Like `Line`, and the area between the line and zero is filled  
+    • `Scatter`: Each point is drawn as a dot  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeriesType {
+    Line,
+    Bar,
+    Area,
+    Scatter
+}
+
+/**
+    A series of points drawn by a chart
+
+    Members:  
+    • `name`: The name of the series displayed in the legend and in the tooltips  
+    • `kind`: How the series is drawn  
+    • `color`: The color of the series (red, green, blue, alpha)  
+    • `points`: The points (x, y) of the series. Points that are not finite are not drawn  
+    • `max_points`: If set, the oldest points are removed when new points are added to the series  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub kind: SeriesType,
+    pub color: (f32, f32, f32, f32),
+    pub points: Vec<(f64, f64)>,
+    pub max_points: Option<usize>
+}
+
+impl Series {
+
+    /// Remove the oldest points until the series has at most `max_points` points
+    fn truncate(&mut self) {
+        if let Some(max) = self.max_points {
+            if self.points.len() > max {
+                let extra = self.points.len() - max;
+                self.points.drain(0..extra);
+            }
+        }
+    }
+
+}
+
+/**
+    The colors and the sizes used to draw a chart
+
+    Members:  
+    • `background`: The color behind the plot  
+    • `foreground`: The color of the axes, of the text and of the tooltip borders  
+    • `grid`: The color of the lines drawn at each tick  
+    • `font_family`: The font of the text  
+    • `font_size`: The size of the labels in device independent pixels. The title is 20% larger  
+    • `line_width`: The width of the lines of the line and area series  
+    • `point_radius`: The radius of the dots of the scatter series  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartStyle {
+    pub background: (f32, f32, f32, f32),
+    pub foreground: (f32, f32, f32, f32),
+    pub grid: (f32, f32, f32, f32),
+    pub font_family: String,
+    pub font_size: f32,
+    pub line_width: f32,
+    pub point_radius: f32
+}
+
+/**
+    A linear mapping from data values (the domain) to positions in the chart (the range)
+
+    Members:  
+    • `domain`: The values mapped to the ends of the range  
+    • `range`: The positions of the ends of the domain  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scale {
+    pub domain: (f64, f64),
+    pub range: (f64, f64)
+}
+
+impl Scale {
+
+    /// Create a scale mapping `domain` to `range`
+    pub fn new(domain: (f64, f64), range: (f64, f64)) -> Scale {
+        Scale{ domain: domain, range: range }
+    }
+
+    /// Return the position of `value`. An empty domain maps every value to the middle of the range.
+    pub fn map(&self, value: f64) -> f64 {
+        let ((d0, d1), (r0, r1)) = (self.domain, self.range);
+        if d1 == d0 { return (r0 + r1) * 0.5; }
+        r0 + (value - d0) / (d1 - d0) * (r1 - r0)
+    }
+
+    /// Return the value at `position`. An empty range maps every position to the start of the domain.
+    pub fn invert(&self, position: f64) -> f64 {
+        let ((d0, d1), (r0, r1)) = (self.domain, self.range);
+        if r1 == r0 { return d0; }
+        d0 + (position - r0) / (r1 - r0) * (d1 - d0)
+    }
+
+    /**
+        Return the values of about `count` ticks spread over the domain, in increasing order. The ticks are the
+        multiples of the step returned by `nice_step` that are inside the domain.
+    */
+    pub fn ticks(&self, count: usize) -> Vec<f64> {
+        let (low, high) = self.ordered_domain();
+        if low == high && low.is_finite() { return vec![low]; }
+
+        let step = nice_step(high - low, count);
+        if step == 0.0 { return Vec::new(); }
+
+        let first = (step_ratio(low, step) - 1e-9).ceil() as i64;
+        let last = (step_ratio(high, step) + 1e-9).floor() as i64;
+        (first..last+1).map(|i| step_value(i, step)).collect()
+    }
+
+    /**
+        Return the scale with its domain extended to the multiples of the tick step around it, so that
+        the first and the last ticks are at the ends of the domain. The direction of the domain is kept.
+    */
+    pub fn nice(&self, count: usize) -> Scale {
+        let (mut low, mut high) = self.ordered_domain();
+
+        // Extending the domain can change the step, so the domain is extended a second time
+        for _ in 0..2 {
+            let step = nice_step(high - low, count);
+            if step == 0.0 { break; }
+
+            low = step_value((step_ratio(low, step) + 1e-9).floor() as i64, step);
+            high = step_value((step_ratio(high, step) - 1e-9).ceil() as i64, step);
+        }
+
+        let domain = if self.domain.0 <= self.domain.1 { (low, high) } else { (high, low) };
+        Scale{ domain: domain, range: self.range }
+    }
+
+    fn ordered_domain(&self) -> (f64, f64) {
+        let (d0, d1) = self.domain;
+        if d0 <= d1 { (d0, d1) } else { (d1, d0) }
+    }
+
+}
+
+/**
+    Return a tick step splitting `span` in about `count` intervals. The step is 1, 2 or 5 times a power of ten.
+    Return 0 if `span` is not a positive number or if `count` is 0.
+*/
+pub fn nice_step(span: f64, count: usize) -> f64 {
+    if !(span > 0.0) || !span.is_finite() || count == 0 {
+        return 0.0;
+    }
+
+    let raw = span / (count as f64);
+    let power = 10f64.powi(raw.log10().floor() as i32);
+    let error = raw / power;
+
+    let factor = if error >= 50f64.sqrt() { 10.0 }
+    else if error >= 10f64.sqrt() { 5.0 }
+    else if error >= 2f64.sqrt() { 2.0 }
+    else { 1.0 };
+
+    factor * power
+}
+
+/// `value / step` computed with the inverse of the steps lower than 1, which are integers
+fn step_ratio(value: f64, step: f64) -> f64 {
+    if step < 1.0 { value * (1.0 / step).round() } else { value / step }
+}
+
+/// `index * step` without the rounding errors of the decimal steps
+fn step_value(index: i64, step: f64) -> f64 {
+    if step < 1.0 { (index as f64) / (1.0 / step).round() } else { (index as f64) * step }
+}
+
+/**
+    The position of the parts of a plot drawn in a given size. Returned by `Plot::layout`.
+
+    Members:  
+    • `area`: The rectangle where the series are drawn  
+    • `x`: The scale of the horizontal axis. Its range goes from the left to the right of `area`  
+    • `y`: The scale of the vertical axis. Its range goes from the bottom to the top of `area`  
+    • `x_ticks`, `y_ticks`: The values of the ticks of the axes  
+    • `legend`: The rectangle of the legend, if it is displayed  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlotLayout {
+    pub area: Rectangle,
+    pub x: Scale,
+    pub y: Scale,
+    pub x_ticks: Vec<f64>,
+    pub y_ticks: Vec<f64>,
+    pub legend: Option<Rectangle>
+}
+
+/**
+    The content of a chart. The data range of the axes is computed from the points of the series, unless it is set
+    with `set_x_range` or `set_y_range`. The range of the vertical axis is extended to the closest ticks.
+
+    The coordinates used by `layout`, `hit_test` and `draw` are in device independent pixels.
+*/
+#[derive(Clone, Debug)]
+pub struct Plot {
+    series: Vec<Series>,
+    title: String,
+    axis_titles: (String, String),
+    x_range: Option<(f64, f64)>,
+    y_range: Option<(f64, f64)>,
+    x_labels: Option<Vec<(f64, String)>>,
+    legend: bool,
+    style: ChartStyle,
+    hover: Option<(usize, usize)>
+}
+
+impl Plot {
+
+    /// Create an empty plot with a legend and the default light style
+    pub fn new() -> Plot {
+        let style = ChartStyle {
+            background: (1.0, 1.0, 1.0, 1.0),
+            foreground: (0.2, 0.2, 0.2, 1.0),
+            grid: (0.88, 0.88, 0.88, 1.0),
+            font_family: "Segoe UI".to_string(),
+            font_size: 12.0,
+            line_width: 2.0,
+            point_radius: 3.5
+        };
+
+        Plot {
+            series: Vec::new(),
+            title: String::new(),
+            axis_titles: (String::new(), String::new()),
+            x_range: None,
+            y_range: None,
+            x_labels: None,
+            legend: true,
+            style: style,
+            hover: None
+        }
+    }
+
+    /// Add a series drawn over the others and return its index
+    pub fn add_series(&mut self, mut series: Series) -> usize {
+        series.truncate();
+        self.series.push(series);
+        self.series.len() - 1
+    }
+
+    /// Remove the series at `index` and return it. The following series move down by one index.
+    pub fn remove_series(&mut self, index: usize) -> Option<Series> {
+        if index >= self.series.len() { return None; }
+        self.hover = None;
+        Some(self.series.remove(index))
+    }
+
+    /// Return the series of the plot, in the drawing order
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    /**
+        Add points at the end of the series at `index`. If the series has a maximum number of points, the oldest
+        points are removed. Return false if there is no series at `index`.
+    */
+    pub fn push_points(&mut self, index: usize, points: &[(f64, f64)]) -> bool {
+        match self.series.get_mut(index) {
+            Some(series) => {
+                series.points.extend_from_slice(points);
+                series.truncate();
+            },
+            None => { return false; }
+        }
+
+        self.clear_hover_of(index);
+        true
+    }
+
+    /**
+        Replace the points of the series at `index`. Return false if there is no series at `index`.
+    */
+    pub fn set_points(&mut self, index: usize, points: Vec<(f64, f64)>) -> bool {
+        match self.series.get_mut(index) {
+            Some(series) => {
+                series.points = points;
+                series.truncate();
+            },
+            None => { return false; }
+        }
+
+        self.clear_hover_of(index);
+        true
+    }
+
+    /// Set the title displayed above the plot. An empty title is not displayed.
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        self.title = title.into();
+    }
+
+    /// Return the title of the plot
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    /// Set the titles of the horizontal and of the vertical axis. Empty titles are not displayed.
+    pub fn set_axis_titles<S1: Into<String>, S2: Into<String>>(&mut self, x: S1, y: S2) {
+        self.axis_titles = (x.into(), y.into());
+    }
+
+    /// Return the titles of the horizontal and of the vertical axis
+    pub fn get_axis_titles(&self) -> (&str, &str) {
+        (&self.axis_titles.0, &self.axis_titles.1)
+    }
+
+    /// Set the values at the ends of the horizontal axis. `None` computes them from the points.
+    pub fn set_x_range(&mut self, range: Option<(f64, f64)>) {
+        self.x_range = range;
+    }
+
+    /// Set the values at the ends of the vertical axis. `None` computes them from the points.
+    pub fn set_y_range(&mut self, range: Option<(f64, f64)>) {
+        self.y_range = range;
+    }
+
+    /**
+        Replace the ticks of the horizontal axis by labels at the given x values, for example the categories of a
+        bar chart. `None` restores the computed ticks.
+    */
+    pub fn set_x_labels(&mut self, labels: Option<Vec<(f64, String)>>) {
+        self.x_labels = labels;
+    }
+
+    /// Show or hide the legend
+    pub fn set_legend(&mut self, legend: bool) {
+        self.legend = legend;
+    }
+
+    /// Return true if the legend is shown
+    pub fn get_legend(&self) -> bool {
+        self.legend
+    }
+
+    /// Set the colors and the sizes used to draw the plot
+    pub fn set_style(&mut self, style: ChartStyle) {
+        self.style = style;
+    }
+
+    /// Return the colors and the sizes used to draw the plot
+    pub fn get_style(&self) -> &ChartStyle {
+        &self.style
+    }
+
+    /**
+        Set the point with a tooltip: (series index, point index). Return true if the point changed.
+        A point that does not exist is ignored.
+    */
+    pub fn set_hover(&mut self, hover: Option<(usize, usize)>) -> bool {
+        let hover = hover.and_then(|(s, p)| match self.series.get(s) {
+            Some(series) if p < series.points.len() => Some((s, p)),
+            _ => None
+        });
+
+        let changed = hover != self.hover;
+        self.hover = hover;
+        changed
+    }
+
+    /// Return the point with a tooltip: (series index, point index)
+    pub fn get_hover(&self) -> Option<(usize, usize)> {
+        self.hover
+    }
+
+    /**
+        Compute the position of the parts of the plot drawn in an area of `width` x `height`.
+    */
+    pub fn layout(&self, width: f32, height: f32) -> PlotLayout {
+        let font = self.style.font_size;
+        let (x_domain, y_domain) = self.data_domains();
+
+        // The height of the labels do not depend on the ticks, so the vertical axis is computed first
+        let mut top = PADDING;
+        if !self.title.is_empty() { top += font * 1.2 * 1.6; }
+        let mut bottom = height - PADDING - font * 1.5;
+        if !self.axis_titles.0.is_empty() { bottom -= font * 1.5; }
+        if bottom < top + 1.0 { bottom = top + 1.0; }
+
+        let y_count = tick_count(bottom - top, 40.0);
+        let mut y = Scale::new(y_domain, (bottom as f64, top as f64));
+        if self.y_range.is_none() { y = y.nice(y_count); }
+        let y_ticks = y.ticks(y_count);
+
+        let label_chars = y_ticks.iter()
+            .map(|&v| format_tick(v, tick_step(&y_ticks)).chars().count())
+            .max().unwrap_or(0);
+
+        let mut left = PADDING + text_width(label_chars, font) + 6.0;
+        if !self.axis_titles.1.is_empty() { left += font * 1.5; }
+
+        let mut right = width - PADDING;
+        let mut legend = None;
+        if self.legend && self.series.len() > 0 {
+            let chars = self.series.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+            let legend_width = text_width(chars, font) + 18.0;
+            let legend_height = (self.series.len() as f32) * font * 1.6;
+            right -= legend_width + PADDING;
+            legend = Some(Rectangle{ left: right + PADDING, top: top, right: right + PADDING + legend_width, bottom: top + legend_height });
+        }
+        if right < left + 1.0 { right = left + 1.0; }
+
+        let x = Scale::new(x_domain, (left as f64, right as f64));
+        let x_ticks = match self.x_labels {
+            Some(ref labels) => {
+                let (low, high) = x.ordered_domain();
+                labels.iter().map(|&(v, _)| v).filter(|&v| v >= low && v <= high).collect()
+            },
+            None => x.ticks(tick_count(right - left, 80.0))
+        };
+
+        PlotLayout {
+            area: Rectangle{ left: left, top: top, right: right, bottom: bottom },
+            x: x,
+            y: y,
+            x_ticks: x_ticks,
+            y_ticks: y_ticks,
+            legend: legend
+        }
+    }
+
+    /**
+        Return the point under `pos` in a plot drawn in an area of `width` x `height`: (series index, point index).
+        The bars are hit inside their rectangle and the other points at a few pixels of their position.
+    */
+    pub fn hit_test(&self, width: f32, height: f32, pos: (f32, f32)) -> Option<(usize, usize)> {
+        let layout = self.layout(width, height);
+        let area = &layout.area;
+        let (x, y) = pos;
+        if x < area.left - HIT_DISTANCE || x > area.right + HIT_DISTANCE || y < area.top - HIT_DISTANCE || y > area.bottom + HIT_DISTANCE {
+            return None;
+        }
+
+        let mut best = None;
+        let mut best_distance = HIT_DISTANCE * HIT_DISTANCE;
+        for (s, series) in self.series.iter().enumerate() {
+            if series.kind == SeriesType::Bar { continue; }
+            for (p, point) in self.positions(&layout, series) {
+                let distance = (point.0 - x).powi(2) + (point.1 - y).powi(2);
+                if distance <= best_distance {
+                    best = Some((s, p));
+                    best_distance = distance;
+                }
+            }
+        }
+
+        if best.is_some() {
+            return best;
+        }
+
+        self.bars(&layout).into_iter().rev()
+            .find(|&(_, _, ref r)| x >= r.left.min(r.right) && x <= r.left.max(r.right) && y >= r.top.min(r.bottom) && y <= r.top.max(r.bottom))
+            .map(|(s, p, _)| (s, p))
+    }
+
+    /**
+        Draw the plot on `backend`, in an area of the size of the backend. The text is not drawn if the
+        backend cannot draw text.
+    */
+    pub fn draw(&self, backend: &mut RenderBackend) -> Result<(), Error> {
+        let (width, height) = backend.get_size();
+        let layout = self.layout(width, height);
+        let style = &self.style;
+        let area = &layout.area;
+        let font = style.font_size;
+        let grid = Paint::Solid(style.grid);
+        let foreground = Paint::Solid(style.foreground);
+
+        backend.clear(style.background);
+
+        // Grid and labels
+        let y_step = tick_step(&layout.y_ticks);
+        for &value in layout.y_ticks.iter() {
+            let y = snap(layout.y.map(value) as f32);
+            backend.stroke_path(&PathData::polyline(&[(area.left, y), (area.right, y)], false), &grid, None, 1.0)?;
+
+            let bounds = Rectangle{ left: 0.0, top: y - font, right: area.left - 6.0, bottom: y + font };
+            self.text(backend, &format_tick(value, y_step), &bounds, TextAlignment::Trailing, ParagraphAlignment::Center, 1.0);
+        }
+
+        let x_step = tick_step(&layout.x_ticks);
+        for &value in layout.x_ticks.iter() {
+            let x = snap(layout.x.map(value) as f32);
+            backend.stroke_path(&PathData::polyline(&[(x, area.top), (x, area.bottom)], false), &grid, None, 1.0)?;
+
+            let label = match self.x_labels {
+                Some(ref labels) => labels.iter().find(|l| l.0 == value).map(|l| l.1.clone()).unwrap_or(String::new()),
+                None => format_tick(value, x_step)
+            };
+
+            let bounds = Rectangle{ left: x - 60.0, top: area.bottom + 4.0, right: x + 60.0, bottom: area.bottom + 4.0 + font * 1.5 };
+            self.text(backend, &label, &bounds, TextAlignment::Center, ParagraphAlignment::Near, 1.0);
+        }
+
+        // Axes
+        let (left, bottom) = (snap(area.left), snap(area.bottom));
+        backend.stroke_path(&PathData::polyline(&[(left, area.top), (left, bottom), (area.right, bottom)], false), &foreground, None, 1.0)?;
+
+        // Series
+        backend.push_clip(area);
+        let result = self.draw_series(backend, &layout);
+        backend.pop_clip();
+        result?;
+
+        // Titles and legend
+        if !self.title.is_empty() {
+            let bounds = Rectangle{ left: 0.0, top: PADDING, right: width, bottom: PADDING + font * 1.2 * 1.6 };
+            self.text(backend, &self.title, &bounds, TextAlignment::Center, ParagraphAlignment::Near, 1.2);
+        }
+
+        if !self.axis_titles.0.is_empty() {
+            let top = area.bottom + 4.0 + font * 1.5;
+            let bounds = Rectangle{ left: area.left, top: top, right: area.right, bottom: top + font * 1.5 };
+            self.text(backend, &self.axis_titles.0, &bounds, TextAlignment::Center, ParagraphAlignment::Near, 1.0);
+        }
+
+        if !self.axis_titles.1.is_empty() {
+            // Written from bottom to top, left of the tick labels
+            let base = backend.get_matrix();
+            let center = (PADDING + font * 0.75, (area.top + area.bottom) * 0.5);
+            let half = (area.bottom - area.top) * 0.5;
+            let bounds = Rectangle{ left: center.0 - half, top: center.1 - font * 0.75, right: center.0 + half, bottom: center.1 + font * 0.75 };
+            backend.set_matrix(&Matrix::rotation(-90.0, center).multiply(&base));
+            self.text(backend, &self.axis_titles.1, &bounds, TextAlignment::Center, ParagraphAlignment::Center, 1.0);
+            backend.set_matrix(&base);
+        }
+
+        if let Some(ref legend) = layout.legend {
+            for (i, series) in self.series.iter().enumerate() {
+                let top = legend.top + (i as f32) * font * 1.6;
+                let middle = top + font * 0.8;
+                let swatch = Rectangle{ left: legend.left, top: middle - 5.0, right: legend.left + 10.0, bottom: middle + 5.0 };
+                backend.fill_shape(&Shape::Rectangle(swatch), &Paint::Solid(series.color))?;
+
+                let bounds = Rectangle{ left: legend.left + 16.0, top: top, right: legend.right, bottom: top + font * 1.6 };
+                self.text(backend, &series.name, &bounds, TextAlignment::Leading, ParagraphAlignment::Center, 1.0);
+            }
+        }
+
+        self.draw_tooltip(backend, &layout)
+    }
+
+    /// Return the domains of the axes, from the ranges set by the application or from the points
+    fn data_domains(&self) -> ((f64, f64), (f64, f64)) {
+        let mut x: Option<(f64, f64)> = None;
+        let mut y: Option<(f64, f64)> = None;
+        let extend = |range: Option<(f64, f64)>, v: f64| match range {
+            Some((low, high)) => Some((low.min(v), high.max(v))),
+            None => Some((v, v))
+        };
+
+        for series in self.series.iter() {
+            for &(px, py) in series.points.iter().filter(|p| p.0.is_finite() && p.1.is_finite()) {
+                x = extend(x, px);
+                y = extend(y, py);
+            }
+
+            // Bars and areas start at zero
+            if series.kind == SeriesType::Bar || series.kind == SeriesType::Area {
+                if y.is_some() { y = extend(y, 0.0); }
+            }
+        }
+
+        // Half of the space between two bars is kept at the ends of the axis
+        if let Some((low, high)) = x {
+            if self.series.iter().any(|s| s.kind == SeriesType::Bar) {
+                let slot = self.bar_slot();
+                x = Some((low - slot * 0.5, high + slot * 0.5));
+            }
+        }
+
+        let widen = |range: Option<(f64, f64)>| match range {
+            Some((low, high)) if low == high => {
+                let pad = if low == 0.0 { 1.0 } else { low.abs() * 0.1 };
+                (low - pad, high + pad)
+            },
+            Some(range) => range,
+            None => (0.0, 1.0)
+        };
+
+        (self.x_range.unwrap_or(widen(x)), self.y_range.unwrap_or(widen(y)))
+    }
+
+    /// Return the smallest space between two x values of the bar series, in data units
+    fn bar_slot(&self) -> f64 {
+        let mut values: Vec<f64> = self.series.iter()
+            .filter(|s| s.kind == SeriesType::Bar)
+            .flat_map(|s| s.points.iter().map(|p| p.0))
+            .filter(|v| v.is_finite())
+            .collect();
+
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.windows(2).map(|w| w[1] - w[0]).filter(|&d| d > 0.0).fold(None, |min: Option<f64>, d| match min {
+            Some(m) => Some(m.min(d)),
+            None => Some(d)
+        }).unwrap_or(1.0)
+    }
+
+    /// Return the positions of the finite points of `series` with their index
+    fn positions(&self, layout: &PlotLayout, series: &Series) -> Vec<(usize, (f32, f32))> {
+        series.points.iter().enumerate()
+            .filter(|&(_, p)| p.0.is_finite() && p.1.is_finite())
+            .map(|(i, p)| (i, (layout.x.map(p.0) as f32, layout.y.map(p.1) as f32)))
+            .collect()
+    }
+
+    /// Return the position of the zero of the vertical axis, kept inside the plot area
+    fn baseline(&self, layout: &PlotLayout) -> f32 {
+        let (low, high) = layout.y.ordered_domain();
+        layout.y.map(0.0f64.max(low).min(high)) as f32
+    }
+
+    /// Return the rectangles of the bars: (series index, point index, rectangle)
+    fn bars(&self, layout: &PlotLayout) -> Vec<(usize, usize, Rectangle)> {
+        let bar_series: Vec<usize> = (0..self.series.len()).filter(|&i| self.series[i].kind == SeriesType::Bar).collect();
+        if bar_series.len() == 0 {
+            return Vec::new();
+        }
+
+        let slot = self.bar_slot();
+        let group = ((layout.x.map(slot) - layout.x.map(0.0)).abs() * BAR_FILL) as f32;
+        let width = group / (bar_series.len() as f32);
+        let baseline = self.baseline(layout);
+
+        let mut bars = Vec::new();
+        for (j, &s) in bar_series.iter().enumerate() {
+            for (p, (x, y)) in self.positions(layout, &self.series[s]) {
+                let left = x - group * 0.5 + (j as f32) * width;
+                bars.push((s, p, Rectangle{ left: left, top: y.min(baseline), right: left + width, bottom: y.max(baseline) }));
+            }
+        }
+
+        bars
+    }
+
+    fn draw_series(&self, backend: &mut RenderBackend, layout: &PlotLayout) -> Result<(), Error> {
+        let style = &self.style;
+        let pen = line_pen();
+        let bars = self.bars(layout);
+        let baseline = self.baseline(layout);
+
+        for (s, series) in self.series.iter().enumerate() {
+            let color = Paint::Solid(series.color);
+            let points: Vec<(f32, f32)> = self.positions(layout, series).into_iter().map(|(_, p)| p).collect();
+
+            match series.kind {
+                SeriesType::Bar => for &(_, _, ref r) in bars.iter().filter(|b| b.0 == s) {
+                    backend.fill_shape(&Shape::Rectangle(r.clone()), &color)?;
+                },
+                SeriesType::Scatter => for &p in points.iter() {
+                    backend.fill_shape(&dot(p, style.point_radius), &color)?;
+                },
+                SeriesType::Line | SeriesType::Area => {
+                    if points.len() == 1 {
+                        backend.fill_shape(&dot(points[0], style.line_width.max(style.point_radius)), &color)?;
+                        continue;
+                    }
+
+                    if series.kind == SeriesType::Area && points.len() > 1 {
+                        let mut polygon = points.clone();
+                        polygon.push((points[points.len()-1].0, baseline));
+                        polygon.push((points[0].0, baseline));
+                        let (r, g, b, a) = series.color;
+                        backend.fill_path(&PathData::polyline(&polygon, true), &Paint::Solid((r, g, b, a * 0.35)))?;
+                    }
+
+                    if points.len() > 1 {
+                        backend.stroke_path(&PathData::polyline(&points, false), &color, Some(&pen), style.line_width)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Highlight the hovered point and draw its tooltip
+    fn draw_tooltip(&self, backend: &mut RenderBackend, layout: &PlotLayout) -> Result<(), Error> {
+        let (s, p) = match self.hover {
+            Some(hover) => hover,
+            None => { return Ok(()); }
+        };
+
+        let series = &self.series[s];
+        let point = match series.points.get(p) {
+            Some(&point) if point.0.is_finite() && point.1.is_finite() => point,
+            _ => { return Ok(()); }
+        };
+
+        let style = &self.style;
+        let anchor = match series.kind {
+            SeriesType::Bar => match self.bars(layout).into_iter().find(|b| b.0 == s && b.1 == p) {
+                Some((_, _, r)) => {
+                    backend.stroke_shape(&Shape::Rectangle(r.clone()), &Paint::Solid(style.foreground), None, 1.5)?;
+                    ((r.left + r.right) * 0.5, r.top)
+                },
+                None => { return Ok(()); }
+            },
+            _ => {
+                let pos = (layout.x.map(point.0) as f32, layout.y.map(point.1) as f32);
+                let marker = dot(pos, style.point_radius + 2.0);
+                backend.fill_shape(&marker, &Paint::Solid(series.color))?;
+                backend.stroke_shape(&marker, &Paint::Solid(style.background), None, 1.5)?;
+                pos
+            }
+        };
+
+        // The tooltip is placed above and right of the point, and moved inside the chart if needed
+        let (width, height) = backend.get_size();
+        let font = style.font_size;
+        let x_text = match self.x_labels {
+            Some(ref labels) => labels.iter().find(|l| l.0 == point.0).map(|l| l.1.clone()).unwrap_or(format_value(point.0)),
+            None => format_value(point.0)
+        };
+        let text = format!("{}\nx: {}, y: {}", series.name, x_text, format_value(point.1));
+        let chars = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let (box_width, box_height) = (text_width(chars, font) + 12.0, font * 2.6 + 8.0);
+
+        let mut left = anchor.0 + 10.0;
+        if left + box_width > width { left = anchor.0 - 10.0 - box_width; }
+        let mut top = anchor.1 - 10.0 - box_height;
+        if top < 0.0 { top = anchor.1 + 10.0; }
+        let left = left.max(0.0).min((width - box_width).max(0.0));
+        let top = top.max(0.0).min((height - box_height).max(0.0));
+
+        let tooltip = Rectangle{ left: left, top: top, right: left + box_width, bottom: top + box_height };
+        let (r, g, b, _) = style.background;
+        backend.fill_shape(&Shape::Rectangle(tooltip.clone()), &Paint::Solid((r, g, b, 0.95)))?;
+        backend.stroke_shape(&Shape::Rectangle(tooltip.clone()), &Paint::Solid(style.foreground), None, 1.0)?;
+
+        let bounds = Rectangle{ left: left + 6.0, top: top + 4.0, right: tooltip.right - 6.0, bottom: tooltip.bottom - 4.0 };
+        self.text(backend, &text, &bounds, TextAlignment::Leading, ParagraphAlignment::Near, 1.0);
+
+        Ok(())
+    }
+
+    /// Draw a text with the style of the plot. Nothing is drawn if the backend cannot draw text.
+    fn text(&self, backend: &mut RenderBackend, text: &str, bounds: &Rectangle, align: TextAlignment, paragraph: ParagraphAlignment, scale: f32) {
+        let style = TextStyle {
+            family: self.style.font_family.clone(),
+            size: self.style.font_size * scale,
+            weight: if scale > 1.0 { 700 } else { 400 },
+            style: FontStyle::Normal,
+            text_align: align,
+            paragraph_align: paragraph,
+            wrapping: WordWrapping::NoWrap
+        };
+
+        backend.draw_text(text, &style, bounds, &Paint::Solid(self.style.foreground)).ok();
+    }
+
+    fn clear_hover_of(&mut self, index: usize) {
+        if self.hover.map(|h| h.0) == Some(index) {
+            self.hover = None;
+        }
+    }
+
+}
+
+/// Return the number of ticks fitting in `length` with at least `spacing` between them
+fn tick_count(length: f32, spacing: f32) -> usize {
+    ((length / spacing) as usize).max(2)
+}
+
+/// Return the space between the first two ticks
+fn tick_step(ticks: &[f64]) -> f64 {
+    if ticks.len() > 1 { ticks[1] - ticks[0] } else { 1.0 }
+}
+
+/// Format a tick value with the number of decimals of the tick step
+fn format_tick(value: f64, step: f64) -> String {
+    let decimals = if step >= 1.0 || !(step > 0.0) { 0 } else { (-step.log10().floor()) as usize };
+    let value = if value.abs() < step.abs() * 1e-6 { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+/// Format a value of a tooltip with at most four decimals
+fn format_value(value: f64) -> String {
+    let mut text = format!("{:.4}", value);
+    while text.ends_with('0') { text.pop(); }
+    if text.ends_with('.') { text.pop(); }
+    if text == "-0" { "0".to_string() } else { text }
+}
+
+/// Estimate the width of a text of `chars` characters
+fn text_width(chars: usize, font: f32) -> f32 {
+    (chars as f32) * font * CHAR_WIDTH
+}
+
+/// Center a one pixel wide line on a pixel
+fn snap(v: f32) -> f32 {
+    v.floor() + 0.5
+}
+
+fn dot(center: (f32, f32), radius: f32) -> Shape {
+    Shape::Ellipse(Ellipse{ center: center, radius: (radius, radius) })
+}
+
+fn line_pen() -> Pen {
+    Pen {
+        start_cap: CapStyle::Round,
+        end_cap: CapStyle::Round,
+        dash_cap: CapStyle::Round,
+        line_join: LineJoin::Round,
+        miter_limit: 10.0,
+        dash_style: DashStyle::Solid,
+        dash_offset: 0.0
+    }
+}
@@ -9,7 +9,9 @@ use winapi::{HWND};
 
 use ui::Ui;
 use controls::{Control, ControlT, ControlType, AnyHandle};
+use low::drop_helper::set_accept_files;
 use error::Error;
+use accessibility::Accessibility;
 
 /// System class identifier
 const WINDOW_CLASS_NAME: &'static str = "NWG_BUILTIN_FRAME";
@@ -26,6 +28,7 @@ const WINDOW_CLASS_NAME: &'static str = "NWG_BUILTIN_FRAME";
     • `disabled`: If the user can or can't click on the label  
     • `align`: The text align of the label
     • `show_edge`: If the frame edge should be visible
+    • `accept_files`: If the frame accept files dropped from the system shell. See the `FilesDropped` event  
     • `parent`: The label parent  
 */
 #[derive(Clone)]
@@ -35,6 +38,7 @@ pub struct FrameT<ID: Hash+Clone> {
     pub visible: bool,
     pub disabled: bool,
     pub show_edge: bool,
+    pub accept_files: bool,
     pub parent: ID
 }
 
@@ -47,6 +51,12 @@ impl<ID: Hash+Clone> ControlT<ID> for FrameT<ID> {
             if let Err(e) = build_sysclass() { return Err(e); }
             match build_window(ui, &self) {
                 Ok(h) => { 
+                    if self.accept_files {
+                        if let Err(e) = set_accept_files(h, true) {
+                            ::user32::DestroyWindow(h);
+                            return Err(e);
+                        }
+                    }
                     Ok( Box::new(Frame{handle: h}) as Box<Control> ) 
                 },
                 Err(e) => Err(e)
@@ -63,6 +73,9 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Allow or forbid the user to drop files from the system shell on the frame
+    pub fn set_accept_files(&self, accept: bool) -> Result<(), Error> { unsafe{ set_accept_files(self.handle, accept) } }
+
     pub fn get_text(&self) -> String { unsafe{ ::low::window_helper::get_window_text(self.handle) } }
     pub fn set_text<'a>(&self, text: &'a str) { unsafe{ ::low::window_helper::set_window_text(self.handle, text); } }
     pub fn get_visibility(&self) -> bool { unsafe{ ::low::window_helper::get_window_visibility(self.handle) } }
@@ -75,6 +88,10 @@ impl Frame {
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
     pub fn get_font<ID: Hash+Clone>(&self, ui: &Ui<ID>) -> Option<ID> { unsafe{ ::low::window_helper::get_window_font(self.handle, ui) } }
     pub fn set_font<ID: Hash+Clone>(&self, ui: &Ui<ID>, f: Option<&ID>) -> Result<(), Error> { unsafe{ ::low::window_helper::set_window_font(self.handle, ui, f) } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
 }
 
@@ -95,7 +112,10 @@ impl Control for Frame {
 
     fn free(&mut self) {
         use user32::DestroyWindow;
-        unsafe{ DestroyWindow(self.handle) };
+        unsafe{ 
+            set_accept_files(self.handle, false).ok();
+            DestroyWindow(self.handle);
+        }
     }
 
 }
@@ -108,7 +128,7 @@ use winapi::{UINT, WPARAM, LPARAM, LRESULT};
 
 #[allow(unused_variables)]
 unsafe extern "system" fn window_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> LRESULT {
-    use winapi::{UINT, WM_CREATE, WM_PAINT, PAINTSTRUCT, RECT, COLOR_WINDOW};
+    use winapi::{UINT, WM_CREATE, WM_PAINT, PAINTSTRUCT, RECT};
     use user32::{DefWindowProcW, DrawEdge, BeginPaint, EndPaint, FillRect, GetClientRect};
     use std::mem;
 
@@ -122,7 +142,7 @@ unsafe extern "system" fn window_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LP
             let hdc = BeginPaint(hwnd, &mut ps); 
             let mut rect: RECT = mem::zeroed();
             GetClientRect(hwnd, &mut rect);
-            FillRect(hdc, &ps.rcPaint, mem::transmute(COLOR_WINDOW as usize));
+            FillRect(hdc, &ps.rcPaint, ::low::color_helper::background_brush(hwnd));
             DrawEdge(hdc, &mut rect, EDGE_RAISED, BF_RECT);
             EndPaint(hwnd, &ps); 
             true
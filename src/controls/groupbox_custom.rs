@@ -11,6 +11,7 @@ use ui::Ui;
 use controls::{Control, ControlT, ControlType, AnyHandle};
 use defs::HTextAlign;
 use error::Error;
+use accessibility::Accessibility;
 
 /// System class identifier
 const WINDOW_CLASS_NAME: &'static str = "NWG_BUILTIN_GROUPBOX";
@@ -77,6 +78,12 @@ impl GroupBox {
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
     pub fn get_font<ID: Hash+Clone>(&self, ui: &Ui<ID>) -> Option<ID> { unsafe{ ::low::window_helper::get_window_font(self.handle, ui) } }
     pub fn set_font<ID: Hash+Clone>(&self, ui: &Ui<ID>, f: Option<&ID>) -> Result<(), Error> { unsafe{ ::low::window_helper::set_window_font(self.handle, ui, f) } }
+    pub fn get_text_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_text_color(self.handle) }
+    pub fn set_text_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_text_color(self.handle, color); } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 }
@@ -111,7 +118,7 @@ impl Control for GroupBox {
 #[allow(unused_variables)]
 unsafe extern "system" fn window_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> LRESULT {
     use std::mem;
-    use winapi::{UINT, WM_CREATE, WM_PAINT, PAINTSTRUCT, RECT, COLOR_WINDOW};
+    use winapi::{UINT, WM_CREATE, WM_PAINT, PAINTSTRUCT, RECT};
     use user32::{DefWindowProcW, DrawEdge, BeginPaint, EndPaint, FillRect, GetClientRect};
     
     let mut ps: PAINTSTRUCT = mem::uninitialized();
@@ -124,7 +131,7 @@ unsafe extern "system" fn window_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LP
             let hdc = BeginPaint(hwnd, &mut ps); 
             let mut rect: RECT = mem::zeroed();
             GetClientRect(hwnd, &mut rect);
-            FillRect(hdc, &ps.rcPaint, mem::transmute(COLOR_WINDOW as usize));
+            FillRect(hdc, &ps.rcPaint, ::low::color_helper::background_brush(hwnd));
             DrawEdge(hdc, &mut rect, EDGE_RAISED, BF_RECT);
             EndPaint(hwnd, &ps); 
             true
@@ -1,5 +1,5 @@
 /*!
-    A frame that can display a Bitmap image
+    A frame that can display a Bitmap image or play an animation. The bitmap can be scaled, zoomed and panned.
 */
 
 use std::hash::Hash;
@@ -11,14 +11,15 @@ use user32::SendMessageW;
 
 use ui::Ui;
 use controls::{Control, ControlT, ControlType, AnyHandle, HandleSpec};
-use defs::ImageType;
+use defs::{ImageType, ImageScaling};
 use error::Error;
+use accessibility::Accessibility;
 
 /**
     A template that creates a ImageFrame control.
 
     Control specific events:  
-    `image_frame::Click, image_frame::DoubleClick`
+    `image_frame::Click, image_frame::DoubleClick, image_frame::ZoomChanged`
 
     Members:  
     • `position`: The start position of the label  
@@ -26,6 +27,8 @@ use error::Error;
     • `visible`: If the label should be visible to the user  
     • `disabled`: If the user can or can't click on the label  
     • `image`: The image resource to display in the frame  
+    • `scaling`: How the bitmap is placed in the frame. Any value other than `Center` requires a bitmap.  
+    • `zoomable`: If the user can zoom the bitmap with the mouse wheel and pan it by dragging it  
     • `parent`: The label parent  
 */
 #[derive(Clone)]
@@ -35,6 +38,8 @@ pub struct ImageFrameT<ID: Hash+Clone> {
     pub visible: bool,
     pub disabled: bool,
     pub image: Option<ID>,
+    pub scaling: ImageScaling,
+    pub zoomable: bool,
     pub parent: ID
 }
 
@@ -44,8 +49,11 @@ impl<ID: Hash+Clone+'static> ControlT<ID> for ImageFrameT<ID> {
     fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
         use low::window_helper::{WindowParams, build_window, handle_of_window};
         use low::defs::{SS_NOTIFY, SS_BITMAP, SS_CENTERIMAGE, SS_ICON};
+        use low::viewer_helper::set_viewer;
         use winapi::{DWORD, WS_VISIBLE, WS_DISABLED, WS_CHILD};
 
+        let viewer = self.scaling != ImageScaling::Center || self.zoomable;
+
         let mut flags: DWORD = WS_CHILD | SS_NOTIFY | 
         if self.visible    { WS_VISIBLE }   else { 0 } |
         if self.disabled   { WS_DISABLED }  else { 0 };
@@ -60,11 +68,13 @@ impl<ID: Hash+Clone+'static> ControlT<ID> for ImageFrameT<ID> {
         let image = if let &Some(ref img) = &self.image {
             match ui.handle_of(img) {
                 Ok(AnyHandle::HANDLE(h, HandleSpec::Bitmap)) =>  { flags |= SS_CENTERIMAGE | SS_BITMAP; h},
+                Ok(AnyHandle::HICON(_)) if viewer => { return Err(Error::BadResource(VIEWER_BITMAP_ONLY.to_string())); },
                 Ok(AnyHandle::HICON(h)) => { flags |= SS_CENTERIMAGE | SS_ICON; h as HANDLE }
                 Ok(h) => { return Err(Error::BadResource(format!("Image frame image must be a bitmap or icon, got {:?}", h))); },
                 Err(e) => { return Err(e); }
             }
         } else {
+            if viewer { flags |= SS_CENTERIMAGE | SS_BITMAP; }
             ptr::null_mut()
         };
 
@@ -86,7 +96,10 @@ impl<ID: Hash+Clone+'static> ControlT<ID> for ImageFrameT<ID> {
                     ImageType::Bitmap
                 };
                 
-                unsafe{ set_image(h, image, &image_type); }
+                unsafe{ 
+                    if viewer { set_viewer(h, self.scaling, self.zoomable); }
+                    set_image(h, image, &image_type);
+                }
                 Ok( Box::new(ImageFrame{handle: h, image_type: image_type}) )
             },
             Err(e) => Err(Error::System(e))
@@ -95,6 +108,8 @@ impl<ID: Hash+Clone+'static> ControlT<ID> for ImageFrameT<ID> {
 }
 
 
+const VIEWER_BITMAP_ONLY: &'static str = "Only the image frames that display bitmaps can be scaled or zoomed";
+
 /**
     A frame that display a Image resource. Once created, the type of the image resource cannot be changed.  
     For example: an ImageFrame that can display icons will not be able to display bitmaps
@@ -113,11 +128,15 @@ impl ImageFrame {
     pub fn set_size(&self, w: u32, h: u32) { unsafe{ ::low::window_helper::set_window_size(self.handle, w, h, false); } }
     pub fn get_enabled(&self) -> bool { unsafe{ ::low::window_helper::get_window_enabled(self.handle) } }
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 
     /// Set the image of image frame. The new image resource must match the image type of the frame
-    /// Pass `None` as an argument to remove the image.
+    /// Pass `None` as an argument to remove the image. The animation of the frame, if any, is stopped.
     pub fn set_image<ID: Hash+Clone>(&self, ui: &Ui<ID>, img: Option<&ID>) -> Result<(), Error> {
         if !ui.has_handle(&self.handle()) {
             return Err(Error::BadUi("Image resource and control must be in the same Ui.".to_string()));
@@ -135,6 +154,7 @@ impl ImageFrame {
         };
 
         unsafe{
+            ::low::animation_helper::free_animation(self.handle);
             set_image(self.handle, img_handle, &self.image_type);
         }
 
@@ -174,6 +194,149 @@ impl ImageFrame {
     pub fn get_image_type(&self) -> ImageType {
         self.image_type.clone()
     }
+
+    /**
+        Play an `Animation` resource in the frame. The first frame is displayed and the animation starts playing.
+        The animation replaces the image of the frame. Pass `None` as an argument to remove the animation.
+        Only the image frames that display bitmaps can play animations.
+    */
+    pub fn set_animation<ID: Hash+Clone>(&self, ui: &Ui<ID>, animation: Option<&ID>) -> Result<(), Error> {
+        use low::animation_helper::{set_animation, free_animation};
+        use resources::Animation;
+
+        if !ui.has_handle(&self.handle()) {
+            return Err(Error::BadUi("Animation resource and control must be in the same Ui.".to_string()));
+        }
+
+        if self.image_type != ImageType::Bitmap {
+            return Err(Error::BadResource("Only the image frames that display bitmaps can play animations".to_string()));
+        }
+
+        let id = match animation {
+            Some(id) => id,
+            None => { unsafe{ free_animation(self.handle); } return Ok(()); }
+        };
+
+        let frames = ui.get::<Animation>(id)?.frames();
+
+        self.use_bitmap_style();
+        unsafe{ set_animation(self.handle, frames); }
+
+        Ok(())
+    }
+
+    /// Start or resume the animation. If the animation was over, it is restarted from the first frame.
+    pub fn play_animation(&self) { unsafe{ ::low::animation_helper::play(self.handle); } }
+
+    /// Pause the animation on the current frame
+    pub fn pause_animation(&self) { unsafe{ ::low::animation_helper::pause(self.handle); } }
+
+    /// Display the frame at `index` of the animation. If the animation is playing, it continues from this frame.  
+    /// Return `false` if the frame do not have an animation or if the index is out of range.
+    pub fn seek_animation(&self, index: usize) -> bool { unsafe{ ::low::animation_helper::seek(self.handle, index) } }
+
+    /// Return the index of the animation frame displayed, or `None` if the frame do not have an animation
+    pub fn get_animation_frame(&self) -> Option<usize> { ::low::animation_helper::current_frame(self.handle) }
+
+    /// Check if the animation is playing. Return `false` if the frame do not have an animation or if the animation is over.
+    pub fn is_animation_playing(&self) -> bool { ::low::animation_helper::is_playing(self.handle) }
+
+    /**
+        Set how the bitmap is placed in the frame. The view is reset, even if the user zoomed or panned the bitmap.
+        Only the image frames that display bitmaps can be scaled.
+    */
+    pub fn set_scaling(&self, scaling: ImageScaling) -> Result<(), Error> {
+        let zoomable = self.get_zoomable();
+        self.set_viewer(scaling, zoomable)
+    }
+
+    /// Return how the bitmap is placed in the frame
+    pub fn get_scaling(&self) -> ImageScaling { ::low::viewer_helper::get_scaling(self.handle) }
+
+    /**
+        Allow the user to zoom the bitmap with the mouse wheel (around the cursor) and to pan it by dragging it with the left button.
+        Only the image frames that display bitmaps can be zoomed.
+    */
+    pub fn set_zoomable(&self, zoomable: bool) -> Result<(), Error> {
+        let scaling = self.get_scaling();
+        self.set_viewer(scaling, zoomable)
+    }
+
+    /// Check if the user can zoom and pan the bitmap
+    pub fn get_zoomable(&self) -> bool { ::low::viewer_helper::is_zoomable(self.handle) }
+
+    /// Return the horizontal and vertical scale of the bitmap. At `1.0`, a bitmap pixel is displayed by a single screen pixel.
+    pub fn get_zoom(&self) -> (f64, f64) { unsafe{ ::low::viewer_helper::get_view(self.handle).scale } }
+
+    /// Return the part of the bitmap visible in the frame as `(x, y, width, height)`, in bitmap pixels
+    pub fn get_visible_rect(&self) -> (f64, f64, f64, f64) { unsafe{ ::low::viewer_helper::get_view(self.handle).visible } }
+
+    /// Scale the bitmap by `zoom` in both directions. The center of the frame stays in place. The zoom is kept between `0.01` and `64.0`.  
+    /// Does nothing if the frame displays icons.
+    pub fn set_zoom(&self, zoom: f64) {
+        if self.use_viewer() { unsafe{ ::low::viewer_helper::set_zoom(self.handle, zoom); } }
+    }
+
+    /// Multiply the scale of the bitmap by `factor`. The bitmap pixel under `point` (in client coordinates) stays in place.  
+    /// Does nothing if the frame displays icons.
+    pub fn zoom_at(&self, factor: f64, point: (i32, i32)) {
+        if self.use_viewer() { unsafe{ ::low::viewer_helper::zoom_at(self.handle, factor, point); } }
+    }
+
+    /// Move the bitmap by `(dx, dy)` pixels. A bitmap larger than the frame cannot be moved past the borders of the frame.  
+    /// Does nothing if the frame displays icons.
+    pub fn pan(&self, dx: i32, dy: i32) {
+        if self.use_viewer() { unsafe{ ::low::viewer_helper::pan(self.handle, dx, dy); } }
+    }
+
+    /// Discard the zoom and the pan and place the bitmap using the scaling of the frame
+    pub fn reset_view(&self) { unsafe{ ::low::viewer_helper::reset_view(self.handle); } }
+
+    /// Paint the bitmap with a viewer, or let the control paint it if the bitmap is centered and cannot be zoomed
+    fn set_viewer(&self, scaling: ImageScaling, zoomable: bool) -> Result<(), Error> {
+        use low::viewer_helper::{set_viewer, free_viewer};
+
+        if self.image_type != ImageType::Bitmap {
+            return Err(Error::BadResource(VIEWER_BITMAP_ONLY.to_string()));
+        }
+
+        if scaling == ImageScaling::Center && !zoomable {
+            unsafe{ free_viewer(self.handle); }
+        } else {
+            self.use_bitmap_style();
+            unsafe{ set_viewer(self.handle, scaling, zoomable); }
+        }
+
+        Ok(())
+    }
+
+    /// Make sure the frame has a viewer before the view is changed by the application
+    fn use_viewer(&self) -> bool {
+        use low::viewer_helper::{has_viewer, set_viewer};
+
+        if self.image_type != ImageType::Bitmap {
+            return false;
+        }
+
+        if !has_viewer(self.handle) {
+            self.use_bitmap_style();
+            unsafe{ set_viewer(self.handle, ImageScaling::Center, false); }
+        }
+
+        true
+    }
+
+    /// Image frames created without an image are text controls. Turn them into bitmap controls.
+    fn use_bitmap_style(&self) {
+        use low::window_helper::{get_window_long, set_window_long};
+        use low::defs::{SS_BITMAP, SS_CENTERIMAGE, SS_TYPEMASK};
+        use winapi::{GWL_STYLE, UINT};
+
+        let style = get_window_long(self.handle, GWL_STYLE) as UINT;
+        if style & SS_TYPEMASK != SS_BITMAP {
+            set_window_long(self.handle, GWL_STYLE, ((style & !SS_TYPEMASK) | SS_BITMAP | SS_CENTERIMAGE) as usize);
+        }
+    }
 }
 
 impl Control for ImageFrame {
@@ -188,7 +351,13 @@ impl Control for ImageFrame {
 
     fn free(&mut self) {
         use user32::DestroyWindow;
-        unsafe{ DestroyWindow(self.handle) };
+        use low::animation_helper::free_animation;
+        use low::viewer_helper::free_viewer;
+        unsafe{ 
+            free_animation(self.handle);
+            free_viewer(self.handle);
+            DestroyWindow(self.handle);
+        }
     }
 
 }
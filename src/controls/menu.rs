@@ -62,6 +62,16 @@ impl Menu {
     pub fn set_enabled(&self, enabled: bool) {
         unsafe{ ::low::menu_helper::enable_menu(self.handle, &self.parent, enabled); }
     }
+
+    /// Return the text of the menu
+    pub fn get_text(&self) -> String {
+        unsafe{ ::low::menu_helper::get_menu_text(self.handle, &self.parent) }
+    }
+
+    /// Set the text of the menu
+    pub fn set_text<'a>(&self, text: &'a str) {
+        unsafe{ ::low::menu_helper::set_menu_text(self.handle, &self.parent, text); }
+    }
 }
 
 impl Control for Menu {
@@ -212,6 +222,16 @@ impl MenuItem {
         unsafe{ ::low::menu_helper::enable_menuitem(self.parent, None, Some(self.unique_id), enabled); }
     }
 
+    /// Return the text of the menuitem
+    pub fn get_text(&self) -> String {
+        unsafe{ ::low::menu_helper::get_menuitem_text(self.parent, None, Some(self.unique_id)) }
+    }
+
+    /// Set the text of the menuitem
+    pub fn set_text<'a>(&self, text: &'a str) {
+        unsafe{ ::low::menu_helper::set_menuitem_text(self.parent, None, Some(self.unique_id), text); }
+    }
+
 }
 
 
@@ -18,17 +18,21 @@ pub mod datepicker;
 pub mod image_frame;
 pub mod file_dialog;
 pub mod timer;
+pub mod animator;
 pub mod treeview;
 pub mod frame;
 pub mod tabs;
 pub mod listview;
 #[cfg(feature = "canvas")] pub mod canvas;
+#[cfg(feature = "canvas")] pub mod chart;
 
 use std::any::TypeId;
 use std::hash::Hash;
 
 use winapi::{HWND, HANDLE, HCURSOR, HICON, HMENU, UINT, HFONT, HTREEITEM};
 
+use accessibility::AccessibleTree;
+
 pub use controls::window::{WindowT, Window};
 pub use controls::menu::{MenuT, Menu, MenuItemT, MenuItem, SeparatorT, Separator, ContextMenuT, ContextMenu};
 pub use controls::button::{ButtonT, Button};
@@ -43,6 +47,7 @@ pub use controls::groupbox_custom::{GroupBoxT, GroupBox};
 pub use controls::progress_bar::{ProgressBarT, ProgressBar};
 pub use controls::file_dialog::{FileDialogT, FileDialog};
 pub use controls::timer::{TimerT, Timer};
+pub use controls::animator::{AnimatorT, Animator};
 pub use controls::datepicker::{DatePickerT, DatePicker};
 pub use controls::image_frame::{ImageFrameT, ImageFrame};
 pub use controls::treeview::{TreeViewT, TreeView, TreeViewItemT, TreeViewItem, TreeItemIterator};
@@ -50,6 +55,7 @@ pub use controls::frame::{FrameT, Frame};
 pub use controls::tabs::{TabViewT, TabView, TabT, Tab};
 pub use controls::listview::{ListViewT, ListView};
 #[cfg(feature = "canvas")] pub use controls::canvas::{CanvasT, Canvas, CanvasRenderer};
+#[cfg(feature = "canvas")] pub use controls::chart::{ChartT, Chart};
 
 use ui::Ui;
 use error::Error;
@@ -115,6 +121,7 @@ pub enum ControlType {
     Menu,
     MenuItem,
     Timer,
+    Animator,
     ComboBox,
     GroupBox,
     NumericInput,
@@ -128,6 +135,7 @@ pub enum ControlType {
     TabsView,
     Tab,
     ListView,
+    Chart,
     Undefined  // Control is not a common control
 }
 
@@ -169,6 +177,11 @@ pub trait Control {
     */
     fn children(&self) -> Vec<AnyHandle> { Vec::new() }
 
+    /**
+        Should return the accessible elements drawn by the control. Only useful for controls that draw their own content.
+        See `accessibility::AccessibleTree`.
+    */
+    fn accessible_tree(&self) -> Option<&AccessibleTree> { None }
 
     /**
         If specified, should free any ressource allocated in the template `build` function. This includes functions like `DestroyWindow`.
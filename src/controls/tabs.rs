@@ -20,6 +20,7 @@ use user32::SendMessageW;
 
 use ui::Ui;
 use error::{Error, SystemError};
+use accessibility::Accessibility;
 use controls::{Control, ControlT, ControlType, AnyHandle};
 
 /// System class identifier
@@ -109,6 +110,8 @@ impl TabView {
     pub fn set_size(&self, w: u32, h: u32) { unsafe{ ::low::window_helper::set_window_size(self.handle, w, h, true); } }
     pub fn get_enabled(&self) -> bool { unsafe{ ::low::window_helper::get_window_enabled(self.handle) } }
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 
@@ -215,6 +218,11 @@ impl Tab {
         }
     }
 
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
+
 }
 
 impl Control for Tab {
@@ -244,7 +252,7 @@ impl Control for Tab {
 
 #[allow(unused_variables)]
 unsafe extern "system" fn tab_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> LRESULT {
-    use winapi::{WM_CREATE, WM_PAINT, PAINTSTRUCT, COLOR_WINDOW};
+    use winapi::{WM_CREATE, WM_PAINT, PAINTSTRUCT};
     use user32::{BeginPaint, EndPaint, FillRect};
     use user32::{DefWindowProcW};
 
@@ -253,7 +261,7 @@ unsafe extern "system" fn tab_sysproc(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARA
             let mut ps: PAINTSTRUCT = mem::zeroed();
 
             let hdc = BeginPaint(hwnd, &mut ps); 
-            FillRect(hdc, &ps.rcPaint, mem::transmute(COLOR_WINDOW as usize));
+            FillRect(hdc, &ps.rcPaint, ::low::color_helper::background_brush(hwnd));
             EndPaint(hwnd, &ps); 
 
             return 1;
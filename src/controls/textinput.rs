@@ -12,7 +12,9 @@ use user32::SendMessageW;
 use ui::Ui;
 use controls::{Control, ControlT, ControlType, AnyHandle};
 use low::other_helper::to_utf16;
+use low::drop_helper::set_accept_files;
 use error::Error;
+use accessibility::Accessibility;
 
 /**
     A template that creates a single line textinput control
@@ -32,6 +34,7 @@ use error::Error;
     • `limit`: The maximum number of characters that the control can hold  
     • `parent`: The textinput parent  
     • `font`: The textinput font. If None, use the system default  
+    • `accept_files`: If the textinput accept files dropped from the system shell. See the `FilesDropped` event  
 */
 #[derive(Clone)]
 pub struct TextInputT<S1: Clone+Into<String>, S2: Clone+Into<String>, ID: Hash+Clone> {
@@ -46,6 +49,7 @@ pub struct TextInputT<S1: Clone+Into<String>, S2: Clone+Into<String>, ID: Hash+C
     pub limit: u32,
     pub parent: ID,
     pub font: Option<ID>,
+    pub accept_files: bool,
 }
 
 impl<S1: Clone+Into<String>, S2: Clone+Into<String>, ID: Hash+Clone> ControlT<ID> for TextInputT<S1, S2, ID> {
@@ -97,6 +101,13 @@ impl<S1: Clone+Into<String>, S2: Clone+Into<String>, ID: Hash+Clone> ControlT<ID
                     if let Some(placeholder) = self.placeholder.as_ref() {
                         set_placeholder(h, placeholder.clone());
                     }
+
+                    if self.accept_files {
+                        if let Err(e) = set_accept_files(h, true) {
+                            ::user32::DestroyWindow(h);
+                            return Err(e);
+                        }
+                    }
                 };
 
                 Ok( Box::new(TextInput{handle: h}) )
@@ -157,6 +168,9 @@ impl TextInput {
         (style & ES_PASSWORD) == ES_PASSWORD
     }
 
+    /// Allow or forbid the user to drop files from the system shell on the control
+    pub fn set_accept_files(&self, accept: bool) -> Result<(), Error> { unsafe{ set_accept_files(self.handle, accept) } }
+
     /// Set the maximum number of characters that the control can hold
     pub fn set_limit(&self, limit: u32) {
         use low::defs::EM_LIMITTEXT;
@@ -205,6 +219,12 @@ impl TextInput {
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
     pub fn get_font<ID: Hash+Clone>(&self, ui: &Ui<ID>) -> Option<ID> { unsafe{ ::low::window_helper::get_window_font(self.handle, ui) } }
     pub fn set_font<ID: Hash+Clone>(&self, ui: &Ui<ID>, f: Option<&ID>) -> Result<(), Error> { unsafe{ ::low::window_helper::set_window_font(self.handle, ui, f) } }
+    pub fn get_text_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_text_color(self.handle) }
+    pub fn set_text_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_text_color(self.handle, color); } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 }
@@ -221,7 +241,10 @@ impl Control for TextInput {
 
     fn free(&mut self) {
         use user32::DestroyWindow;
-        unsafe{ DestroyWindow(self.handle) };
+        unsafe{ 
+            set_accept_files(self.handle, false).ok();
+            DestroyWindow(self.handle);
+        }
     }
 
 }
@@ -35,13 +35,20 @@ impl<ID: Hash+Clone> ControlT<ID> for TimerT {
         Ok(Box::new(Timer{
             watcher: unsafe{ ui.message_handle() },
             interval: self.interval,
-            id_event: unsafe{ TIMERS_ID+=1; TIMERS_ID },
+            id_event: next_timer_id(),
             handle: None,
             time: 0,
         }))
     }
 }
 
+/**
+    Return a new timer id. The ids are shared by every control using a timer of its `Ui`.
+*/
+pub(crate) fn next_timer_id() -> UINT_PTR {
+    unsafe{ TIMERS_ID+=1; TIMERS_ID }
+}
+
 /**
     A timer control
 */
@@ -11,7 +11,9 @@ use user32::SendMessageW;
 
 use ui::Ui;
 use controls::{Control, ControlT, ControlType, AnyHandle};
+use low::drop_helper::set_accept_files;
 use error::Error;
+use accessibility::Accessibility;
 
 /// System class identifier
 const WINDOW_CLASS_NAME: &'static str = "NWG_BUILTIN_WINDOW";
@@ -27,6 +29,8 @@ const WINDOW_CLASS_NAME: &'static str = "NWG_BUILTIN_WINDOW";
       • `visible` : If the user can see the window or not  
       • `disabled` : If the window is enabled or not. A disabled window do not process events  
       • `exit_on_close` : If NWG should break the event processing loop when this window is closed  
+      • `icon` : The window icon. Must be an icon resource  
+      • `accept_files` : If the window accept files dropped from the system shell. See the `FilesDropped` event  
 */
 #[derive(Clone)]
 pub struct WindowT<ID: Hash+Clone, S: Clone+Into<String>> {
@@ -37,7 +41,8 @@ pub struct WindowT<ID: Hash+Clone, S: Clone+Into<String>> {
     pub visible: bool,
     pub disabled: bool,
     pub exit_on_close: bool,
-    pub icon: Option<ID>
+    pub icon: Option<ID>,
+    pub accept_files: bool
 }
 
 impl<S: Clone+Into<String>, ID: Hash+Clone> ControlT<ID> for WindowT<ID, S> {
@@ -61,6 +66,12 @@ impl<S: Clone+Into<String>, ID: Hash+Clone> ControlT<ID> for WindowT<ID, S> {
             match build_window(&self) {
                 Ok(h) => { 
                     SendMessageW(h, WM_SETICON, 0, icon as LPARAM);
+                    if self.accept_files {
+                        if let Err(e) = set_accept_files(h, true) {
+                            ::user32::DestroyWindow(h);
+                            return Err(e);
+                        }
+                    }
                     Ok( Box::new(Window{handle: h}) as Box<Control> ) 
                 },
                 Err(e) => Err(e)
@@ -149,6 +160,21 @@ impl Window {
         }
     }
 
+    /// Allow or forbid the user to drop files from the system shell on the window
+    pub fn set_accept_files(&self, accept: bool) -> Result<(), Error> { unsafe{ set_accept_files(self.handle, accept) } }
+
+    /// Return the DPI of the monitor displaying the window
+    pub fn dpi(&self) -> u32 { unsafe{ ::low::dpi_helper::window_dpi(self.handle) } }
+
+    /// Return the scale factor of the monitor displaying the window. Ex: `1.5` for a monitor at 144 DPI
+    pub fn scale_factor(&self) -> f64 { ::dpi::scale_factor(self.dpi()) }
+
+    /// Return the opacity of the window, from 0.0 (transparent) to 1.0 (opaque)
+    pub fn get_opacity(&self) -> f32 { unsafe{ ::low::window_helper::get_window_opacity(self.handle) } }
+
+    /// Set the opacity of the window, from 0.0 (transparent) to 1.0 (opaque)
+    pub fn set_opacity(&self, opacity: f32) { unsafe{ ::low::window_helper::set_window_opacity(self.handle, opacity); } }
+
     pub fn get_title(&self) -> String { unsafe{ ::low::window_helper::get_window_text(self.handle) } }
     pub fn set_title<'a>(&self, text: &'a str) { unsafe{ ::low::window_helper::set_window_text(self.handle, text); } }
     pub fn get_visibility(&self) -> bool { unsafe{ ::low::window_helper::get_window_visibility(self.handle) } }
@@ -159,6 +185,10 @@ impl Window {
     pub fn set_size(&self, w: u32, h: u32) { unsafe{ ::low::window_helper::set_window_size(self.handle, w, h, true); } }
     pub fn get_enabled(&self) -> bool { unsafe{ ::low::window_helper::get_window_enabled(self.handle) } }
     pub fn set_enabled(&self, e:bool) { unsafe{ ::low::window_helper::set_window_enabled(self.handle, e); } }
+    pub fn get_background_color(&self) -> Option<(u8, u8, u8)> { ::low::color_helper::get_window_background_color(self.handle) }
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8)>) { unsafe{ ::low::color_helper::set_window_background_color(self.handle, color); } }
+    pub fn get_accessibility(&self) -> Accessibility { ::low::accessibility_helper::get_window_accessibility(self.handle) }
+    pub fn set_accessibility(&self, info: Accessibility) { unsafe{ ::low::accessibility_helper::set_window_accessibility(self.handle, &info); } }
     pub fn update(&self) { unsafe{ ::low::window_helper::update(self.handle); } }
     pub fn focus(&self) { unsafe{ ::user32::SetFocus(self.handle); } }
 }
@@ -180,7 +210,10 @@ impl Control for Window {
 
     fn free(&mut self) {
         use user32::DestroyWindow;
-        unsafe{ DestroyWindow(self.handle) };
+        unsafe{ 
+            set_accept_files(self.handle, false).ok();
+            DestroyWindow(self.handle);
+        }
     }
 
 }
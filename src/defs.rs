@@ -11,6 +11,17 @@ pub mod canvas {
     */
 
     pub use resources::canvas::defs::*;
+    pub use resources::canvas::{FlatFigure, Scene, SceneNode, NodeStyle, NodeId, Shape, SceneEvent, Paint, DrawCommand};
+    pub use controls::canvas::{FrameClock, FramePacing, FrameStats};
+}
+
+#[cfg(feature = "canvas")]
+pub mod chart {
+    /*!
+        Constants and types for the Chart control
+    */
+
+    pub use controls::chart::{Plot, PlotLayout, Series, SeriesType, ChartStyle, Scale, nice_step};
 }
 
 /**
@@ -26,6 +37,7 @@ pub enum CheckState {
 /**
     Mouse buttons
 */
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MouseButton {
     Left,
     Right,
@@ -162,6 +174,25 @@ pub enum ImageType {
     Cursor
 }
 
+/**
+    Define how an image frame places its bitmap
+
+    Members:  
+    • `None`: The image keeps its size and is displayed from the top left corner of the frame  
+    • `Fit`: The image is scaled to fit in the frame. The aspect ratio is kept.  
+    • `Fill`: The image is scaled to cover the whole frame. The aspect ratio is kept and the sides are cropped.  
+    • `Stretch`: The image is stretched to the size of the frame  
+    • `Center`: The image keeps its size and is centered in the frame  
+*/
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageScaling {
+    None,
+    Fit,
+    Fill,
+    Stretch,
+    Center
+}
+
 /**
     List of built-in system images identifiers. To use with the `OemImageT` resource template.
 */
@@ -272,6 +303,13 @@ pub const FONT_DECO_ITALIC: DWORD = 0x01;
 pub const FONT_DECO_UNDERLINE: DWORD = 0x02;
 pub const FONT_DECO_STRIKEOUT: DWORD = 0x04;
 
+// Drag and drop effects constants. Effects can be combined to define the effects allowed by a source or a target.
+pub const DROP_EFFECT_NONE: u32 = 0x00;
+pub const DROP_EFFECT_COPY: u32 = 0x01;
+pub const DROP_EFFECT_MOVE: u32 = 0x02;
+pub const DROP_EFFECT_LINK: u32 = 0x04;
+pub const DROP_EFFECT_ALL: u32 = 0x07;
+
 pub mod keys {
     //! Windows virtual key code
     
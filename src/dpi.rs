@@ -0,0 +1,110 @@
+/*!
+    High DPI support.
+
+    By default, NWG applications are not DPI aware and the system stretches the windows on high DPI monitors.
+    Calling `dpi::enable_awareness` before creating the first `Ui` declares the process per-monitor DPI aware.
+    Once enabled:
+
+    • Positions, sizes and font sizes in the templates are logical units (1 unit = 1 pixel at 96 DPI)
+    • `get_position`/`set_position` and `get_size`/`set_size` on the controls use logical units
+    • The `Moved` and `Resized` events send logical units
+    • When a window is moved to a monitor with a different DPI, its children and their fonts are scaled and the `DpiChanged` event is raised
+
+    Mouse coordinates and canvas drawing still use physical pixels. The functions of this module can be used to convert them.
+*/
+
+/// The DPI of a monitor at 100% scaling. Logical units are defined at this DPI.
+pub const DEFAULT_DPI: u32 = 96;
+
+/**
+    Declare the process as per-monitor DPI aware. Must be called before creating the first Ui.
+
+    Returns `true` if the process is DPI aware after the call. If the awareness was already set
+    (ex: by the application manifest), the function returns `true` without changing it.
+*/
+pub fn enable_awareness() -> bool {
+    unsafe{ ::low::dpi_helper::enable_dpi_awareness() }
+}
+
+/**
+    Check if `enable_awareness` was successfully called.
+*/
+pub fn is_aware() -> bool {
+    ::low::dpi_helper::dpi_aware()
+}
+
+/**
+    Return the DPI of the primary monitor
+*/
+pub fn system_dpi() -> u32 {
+    unsafe{ ::low::dpi_helper::system_dpi() }
+}
+
+/**
+    Return the scale factor of a DPI. Ex: `scale_factor(144)` returns `1.5`
+*/
+pub fn scale_factor(dpi: u32) -> f64 {
+    (dpi as f64) / (DEFAULT_DPI as f64)
+}
+
+/**
+    Convert a value from a DPI to another. The result is rounded to the nearest integer
+    (halfway values are rounded away from zero). If `from_dpi` is 0, the value is returned as is.
+*/
+pub fn rescale(value: i32, from_dpi: u32, to_dpi: u32) -> i32 {
+    if from_dpi == 0 || from_dpi == to_dpi { return value; }
+
+    let num = (value as i64) * (to_dpi as i64);
+    let den = from_dpi as i64;
+    let scaled = if num >= 0 { (num + den/2) / den } else { (num - den/2) / den };
+
+    if scaled > (i32::max_value() as i64) {
+        i32::max_value()
+    } else if scaled < (i32::min_value() as i64) {
+        i32::min_value()
+    } else {
+        scaled as i32
+    }
+}
+
+/**
+    Convert a value in logical units into physical pixels for a DPI
+*/
+pub fn to_physical(value: i32, dpi: u32) -> i32 {
+    rescale(value, DEFAULT_DPI, dpi)
+}
+
+/**
+    Convert a value in physical pixels into logical units for a DPI
+*/
+pub fn to_logical(value: i32, dpi: u32) -> i32 {
+    rescale(value, dpi, DEFAULT_DPI)
+}
+
+/**
+    Convert a point in logical units into physical pixels for a DPI
+*/
+pub fn point_to_physical(point: (i32, i32), dpi: u32) -> (i32, i32) {
+    (to_physical(point.0, dpi), to_physical(point.1, dpi))
+}
+
+/**
+    Convert a point in physical pixels into logical units for a DPI
+*/
+pub fn point_to_logical(point: (i32, i32), dpi: u32) -> (i32, i32) {
+    (to_logical(point.0, dpi), to_logical(point.1, dpi))
+}
+
+/**
+    Convert a size in logical units into physical pixels for a DPI
+*/
+pub fn size_to_physical(size: (u32, u32), dpi: u32) -> (u32, u32) {
+    (to_physical(size.0 as i32, dpi) as u32, to_physical(size.1 as i32, dpi) as u32)
+}
+
+/**
+    Convert a size in physical pixels into logical units for a DPI
+*/
+pub fn size_to_logical(size: (u32, u32), dpi: u32) -> (u32, u32) {
+    (to_logical(size.0 as i32, dpi) as u32, to_logical(size.1 as i32, dpi) as u32)
+}
@@ -3,14 +3,18 @@
 */
 
 use std::time::Duration;
+use std::cell::Cell;
+use std::rc::Rc;
 
 use ui::Ui;
 use defs::MouseButton;
+use controls::AnyHandle;
 
 use winapi::{WPARAM, LPARAM};
 
 // System events that can be applied to any HWND based control
-pub use low::events::{Event, Destroyed, Paint, Closed, Moved, KeyDown, KeyUp, Resized, Char, MouseUp, MouseDown, MouseMove};
+pub use low::events::{Event, Destroyed, Paint, Closed, Moved, KeyDown, KeyUp, Resized, Char, MouseUp, MouseDown, MouseMove,
+ DpiChanged, FilesDragEnter, FilesDragLeave, FilesDropped, DragEnter, DragOver, DragLeave, DragDrop, DragEnd};
 
 // Control specfic events
 pub mod button { pub use low::events::{BtnClick as Click, BtnDoubleClick as DoubleClick, BtnFocus as Focus}; }
@@ -18,13 +22,16 @@ pub use self::button as checkbox; // Checkboxes use the same events of the butto
 pub use self::button as radiobutton; // Radiobuttons use the same events of the buttons
 pub mod combobox { pub use low::events::{CbnFocus as Focus, CbnSelectionChanged as SelectionChanged}; }
 pub mod label { pub use low::events::{StnClick as Click, StnDoubleClick as DoubleClick}; }
-pub use self::label as image_frame;
+#[cfg(feature = "canvas")]
+pub mod canvas { pub use low::events::{NodeMouseDown, NodeMouseUp, NodeClick, NodeEnter, NodeLeave, CanvasFrame as Frame}; }
+pub mod image_frame { pub use low::events::{StnClick as Click, StnDoubleClick as DoubleClick, ImageZoomChanged as ZoomChanged}; }
 pub mod datepicker { pub use low::events::DateChanged; }
 pub mod listbox { pub use low::events::{LbnSelectionChanged as SelectionChanged, LbnDoubleClick as DoubleClick, LbnFocus as Focus}; }
 pub mod textbox { pub use low::events::{EnFocus as Focus, EnLimit as Limit, EnValueChanged as ValueChanged}; }
 pub use self::textbox as textinput; // Textinput use the same events of the textbox
 pub mod menu { pub use low::events::MenuTrigger as Triggered; }
 pub mod timer { pub use low::events::TimerTick as Tick; }
+pub mod animator { pub use low::events::TweenCompleted as Completed; }
 pub mod treeview { pub use low::events::{TreeViewSelectionChanged as SelectionChanged, TreeViewClick as Click, TreeViewDoubleClick as DoubleClick,
  TreeViewFocus as Focus, TreeViewDeleteItem as DeleteItem, TreeViewItemChanged as ItemChanged, TreeViewItemChanging as ItemChanging,
  TreeViewItemExpanded as ItemExpanded, TreeViewItemExpanding as ItemExpanding}; }
@@ -42,6 +49,15 @@ Arguments:
 */
 pub type EventCallback<ID> = Fn(&Ui<ID>, &ID, &Event, &EventArgs) -> ();
 
+/**
+The function signature for the idle callbacks (see `Ui::bind_idle`)
+
+Arguments:  
+  • 1: A reference to the Ui  
+  • 2: A reference to the ID of the callback  
+*/
+pub type IdleCallback<ID> = Fn(&Ui<ID>, &ID) -> ();
+
 /**
     Events arguments definition. If an event do not have arguments, EventArgs::None is passed.
 */
@@ -49,10 +65,72 @@ pub enum EventArgs {
     Key(u32),
     Char(char),
     MouseClick{btn: MouseButton, pos: (i32, i32)},
+    Files{paths: Vec<String>, pos: (i32, i32)},
+    Drag(DragData),
     Focus(bool),
     Tick(Duration),
     Position(i32, i32),
     Size(u32, u32),
+    Dpi(u32),
+    Zoom{scale: (f64, f64), visible: (f64, f64, f64, f64)}, // Visible rectangle in image pixels: x, y, width, height
+    Node{node: u64, btn: Option<MouseButton>, pos: (f32, f32)}, // Canvas scene node. `btn` is None for NodeEnter and NodeLeave
+    Frame{delta: Duration, frame: u64}, // Animated canvas frame. `delta` is the time since the last frame
     Raw(u32, WPARAM, LPARAM), // MSG, WPARAM, LPARAM
     None
-}
\ No newline at end of file
+}
+
+/**
+    Data passed with the drag and drop events (`DragEnter`, `DragOver`, `DragLeave`, `DragDrop` and `DragEnd`).
+
+    Members:  
+    • `source`: The handle of the control that started the drag. Use `ui.id_from_handle` to get its ID  
+    • `pos`: The position of the cursor in the client area of the control that receive the event  
+    • `allowed`: The effects allowed by both the source and the target (`DROP_EFFECT_*` constants)  
+*/
+pub struct DragData {
+    pub source: AnyHandle,
+    pub pos: (i32, i32),
+    pub allowed: u32,
+    pub(crate) payload: Rc<Box<::std::any::Any>>,
+    pub(crate) effect: Rc<Cell<u32>>
+}
+
+impl DragData {
+
+    /**
+        Return a reference to the dragged payload if it is of type `T` or `None` otherwise.
+    */
+    pub fn payload<T: ::std::any::Any>(&self) -> Option<&T> {
+        self.payload.downcast_ref::<T>()
+    }
+
+    /**
+        Check if the dragged payload is of type `T`
+    */
+    pub fn payload_is<T: ::std::any::Any>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+
+    /**
+        Return the effect that will be applied if the payload is dropped. `DROP_EFFECT_NONE` means the drop is refused.
+    */
+    pub fn effect(&self) -> u32 {
+        self.effect.get()
+    }
+
+    /**
+        Set the effect that will be applied if the payload is dropped. Should be called in a `DragEnter`, 
+        `DragOver` or `DragDrop` callback. If the effect is not a single allowed effect, the drop is refused.
+    */
+    pub fn set_effect(&self, effect: u32) {
+        use defs::{DROP_EFFECT_NONE, DROP_EFFECT_COPY, DROP_EFFECT_MOVE, DROP_EFFECT_LINK};
+
+        let single = effect == DROP_EFFECT_COPY || effect == DROP_EFFECT_MOVE || effect == DROP_EFFECT_LINK;
+        if single && (self.allowed & effect) == effect {
+            self.effect.set(effect);
+        } else {
+            self.effect.set(DROP_EFFECT_NONE);
+        }
+    }
+
+}
@@ -21,6 +21,12 @@ mod ui;
 
 pub mod events;
 pub mod templates;
+pub mod dpi;
+pub mod theme;
+pub mod accessibility;
+pub mod locale;
+pub mod tween;
+pub mod tasks;
 
 pub mod custom {
     /*!
@@ -31,8 +37,12 @@ pub mod custom {
     pub use low::events::{Event, UnpackProc, HandleProc, event_unpack_no_args, hwnd_handle, command_handle, notify_handle};
     pub use low::window_helper::{build_window, build_sysclass, SysclassParams, WindowParams, set_window_long, get_window_long,
     get_window_text, set_window_text, get_window_visibility, set_window_visibility, get_window_position, set_window_position,
-    get_window_size, set_window_size, get_window_enabled, set_window_enabled, set_window_font, get_window_font, list_window_children};
+    get_window_size, set_window_size, get_window_enabled, set_window_enabled, set_window_font, get_window_font, list_window_children,
+    get_window_opacity, set_window_opacity};
     pub use low::menu_helper::list_menu_children;
+    pub use low::color_helper::{get_window_text_color, set_window_text_color, get_window_background_color,
+    set_window_background_color, background_brush};
+    pub use low::accessibility_helper::{get_window_accessibility, set_window_accessibility};
 
 }
 
@@ -47,18 +57,21 @@ pub mod constants {
 pub use events::EventArgs;
 pub use error::{Error, SystemError};
 pub use low::other_helper::{message, simple_message, fatal_message, error_message};
+pub use low::capture_helper::{capture, compare_golden};
 pub use controls::{WindowT, Window, MenuT, Menu, MenuItemT, MenuItem, ButtonT, Button, ListBoxT, ListBox, CheckBoxT, CheckBox,
- RadioButtonT, RadioButton, TimerT, Timer, LabelT, Label, ComboBoxT, ComboBox, SeparatorT, Separator, TextInputT, TextInput,
+ RadioButtonT, RadioButton, TimerT, Timer, AnimatorT, Animator, LabelT, Label, ComboBoxT, ComboBox, SeparatorT, Separator, TextInputT, TextInput,
  FileDialogT, FileDialog, TextBoxT, TextBox, GroupBoxT, GroupBox, ProgressBarT, ProgressBar, DatePickerT, DatePicker, ImageFrameT,
  ImageFrame, TreeViewT, TreeView, TreeViewItemT, TreeViewItem, TreeItemIterator, FrameT, Frame, ContextMenuT, ContextMenu,
  TabViewT, TabView, TabT, Tab, ListViewT, ListView};
 
-pub use resources::{FontT, Font, ImageT, OemImageT, MemoryImageT, Image};
-pub use ui::{Ui, dispatch_events, exit, toggle_console};
+pub use resources::{FontT, Font, ImageT, OemImageT, MemoryImageT, Image, ImageData, ImageFormat, ImageDiff, AnimationData, AnimationFrame,
+ AnimationT, MemoryAnimationT, FramesAnimationT, Animation};
+pub use ui::{Ui, UiHandle, dispatch_events, dispatch_pending, wait_timeout, run_until, exit, toggle_console};
 pub use cursor::Cursor;
 
 #[cfg(feature = "canvas")]
-pub use controls::{CanvasT, Canvas, CanvasRenderer};
+pub use controls::{CanvasT, Canvas, CanvasRenderer, ChartT, Chart};
 
 #[cfg(feature = "canvas")]
-pub use resources::{BrushT, Brush, PenT, Pen};
+pub use resources::{BrushT, Brush, PenT, Pen, PathT, Path, TextFormatT, TextFormat, TextLayout, BitmapT, RgbaBitmapT, Bitmap,
+ RenderBackend, SoftwareRenderer, DisplayList};
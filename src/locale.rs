@@ -0,0 +1,556 @@
+/*!
+    Localization of the user interface using message catalogs.
+
+    A `Catalog` maps message keys to the translated text of one language. Catalogs are added to a `Ui` with
+    `Ui::add_catalog` and the language is selected with `Ui::set_language`. The controls created by a `LocalizedT`
+    template (see `nwg_localized!`) or registered with `Ui::localize` get their text from the catalogs and
+    are updated each time the language changes.
+
+    Catalog files are UTF-8 text files with one message per line:
+
+    ```text
+    # Comments start with a '#'
+    @language = fr
+    @rtl = false
+
+    menu.file = &Fichier
+    status.welcome = Bienvenue {name}!
+    status.files[one] = {count} fichier sélectionné
+    status.files[other] = {count} fichiers sélectionnés
+    ```
+
+    Notes:
+    • The values are trimmed. Use `\n`, `\t` and `\\` to insert a new line, a tab or a backslash
+    • The plural forms use the CLDR categories: `zero`, `one`, `two`, `few`, `many` and `other`
+    • Placeholders are written `{name}`. Use `{{` and `}}` to insert braces
+*/
+
+use std::hash::Hash;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use ui::Ui;
+use controls::{ControlT, Control};
+use error::Error;
+
+/**
+    The plural categories of the CLDR plural rules
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other
+}
+
+impl PluralCategory {
+
+    /**
+        Parse the name of a plural category (ex: `"few"`). Return `None` if the name is not a category.
+    */
+    pub fn from_name(name: &str) -> Option<PluralCategory> {
+        match name {
+            "zero" => Some(PluralCategory::Zero),
+            "one" => Some(PluralCategory::One),
+            "two" => Some(PluralCategory::Two),
+            "few" => Some(PluralCategory::Few),
+            "many" => Some(PluralCategory::Many),
+            "other" => Some(PluralCategory::Other),
+            _ => None
+        }
+    }
+
+}
+
+/**
+    Return the plural category of a count in a language. The language is a tag such as `"en"`, `"pt-BR"` or `"sr_Latn"`.
+    Unknown languages use the english rule.
+*/
+pub fn plural_category(language: &str, n: u64) -> PluralCategory {
+    use self::PluralCategory::*;
+
+    let lang = primary_language(language);
+    let (n10, n100) = (n % 10, n % 100);
+
+    match lang.as_ref() {
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" | "lo" | "my" | "km" => Other,
+        "fr" | "pt" | "hy" | "kab" => if n == 0 || n == 1 { One } else { Other },
+        "ru" | "uk" | "be" | "sr" | "hr" | "bs" => {
+            if n10 == 1 && n100 != 11 { One }
+            else if n10 >= 2 && n10 <= 4 && !(n100 >= 12 && n100 <= 14) { Few }
+            else { Many }
+        },
+        "pl" => {
+            if n == 1 { One }
+            else if n10 >= 2 && n10 <= 4 && !(n100 >= 12 && n100 <= 14) { Few }
+            else { Many }
+        },
+        "cs" | "sk" => if n == 1 { One } else if n >= 2 && n <= 4 { Few } else { Other },
+        "lt" => {
+            if n10 == 1 && !(n100 >= 11 && n100 <= 19) { One }
+            else if n10 >= 2 && !(n100 >= 11 && n100 <= 19) { Few }
+            else { Other }
+        },
+        "lv" => {
+            if n10 == 0 || (n100 >= 11 && n100 <= 19) { Zero }
+            else if n10 == 1 && n100 != 11 { One }
+            else { Other }
+        },
+        "ro" => if n == 1 { One } else if n == 0 || (n100 >= 2 && n100 <= 19) { Few } else { Other },
+        "sl" => if n100 == 1 { One } else if n100 == 2 { Two } else if n100 == 3 || n100 == 4 { Few } else { Other },
+        "he" => if n == 1 { One } else if n == 2 { Two } else { Other },
+        "ga" => {
+            if n == 1 { One }
+            else if n == 2 { Two }
+            else if n >= 3 && n <= 6 { Few }
+            else if n >= 7 && n <= 10 { Many }
+            else { Other }
+        },
+        "cy" => match n { 0 => Zero, 1 => One, 2 => Two, 3 => Few, 6 => Many, _ => Other },
+        "ar" => {
+            if n == 0 { Zero }
+            else if n == 1 { One }
+            else if n == 2 { Two }
+            else if n100 >= 3 && n100 <= 10 { Few }
+            else if n100 >= 11 { Many }
+            else { Other }
+        },
+        _ => if n == 1 { One } else { Other }
+    }
+}
+
+/**
+    Replace the `{name}` placeholders of a message with the values of the arguments.
+    `{{` and `}}` are replaced by `{` and `}`. Placeholders without a matching argument are kept as is.
+*/
+pub fn format_message(template: &str, args: &[(&str, &Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); out.push('{'); },
+            '}' if chars.peek() == Some(&'}') => { chars.next(); out.push('}'); },
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                while let Some(&c) = chars.peek() {
+                    if c == '{' { break; }
+                    chars.next();
+                    if c == '}' { closed = true; break; }
+                    name.push(c);
+                }
+
+                match args.iter().find(|&&(n, _)| closed && n == name.trim()) {
+                    Some(&(_, value)) => out.push_str(&value.to_string()),
+                    None => {
+                        out.push('{');
+                        out.push_str(&name);
+                        if closed { out.push('}'); }
+                    }
+                }
+            },
+            c => out.push(c)
+        }
+    }
+
+    out
+}
+
+/**
+    Normalize a language tag: lower case with `-` separators.
+*/
+fn normalize_language(language: &str) -> String {
+    language.trim().replace('_', "-").to_lowercase()
+}
+
+/**
+    Return the primary subtag of a language tag (ex: `"pt"` for `"pt-BR"`)
+*/
+fn primary_language(language: &str) -> String {
+    let language = normalize_language(language);
+    match language.find('-') {
+        Some(i) => language[..i].to_string(),
+        None => language
+    }
+}
+
+/**
+    The translated messages of a single language.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    language: String,
+    rtl: bool,
+    messages: HashMap<String, String>,
+    plurals: HashMap<String, HashMap<PluralCategory, String>>
+}
+
+impl Catalog {
+
+    /**
+        Create an empty catalog for a language
+    */
+    pub fn new<S: Into<String>>(language: S) -> Catalog {
+        Catalog {
+            language: language.into(),
+            rtl: false,
+            messages: HashMap::new(),
+            plurals: HashMap::new()
+        }
+    }
+
+    /**
+        Parse the content of a catalog file. See the module documentation for the format.
+
+        Returns `Error::BadResource` if the content is not a valid catalog. The error message includes the line number.
+    */
+    pub fn parse(source: &str) -> Result<Catalog, Error> {
+        let mut language: Option<String> = None;
+        let mut catalog = Catalog::new("");
+
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let sep = match line.find('=') {
+                Some(sep) => sep,
+                None => { return Err(Error::BadResource(format!("Catalog line {}: expected `key = value`", line_number))); }
+            };
+
+            let (key, value) = (line[..sep].trim(), line[sep+1..].trim());
+
+            if key.starts_with('@') {
+                match key {
+                    "@language" if !value.is_empty() => { language = Some(value.to_string()); },
+                    "@language" => { return Err(Error::BadResource(format!("Catalog line {}: the language cannot be empty", line_number))); },
+                    "@rtl" => match value {
+                        "true" => { catalog.rtl = true; },
+                        "false" => { catalog.rtl = false; },
+                        v => { return Err(Error::BadResource(format!("Catalog line {}: expected `true` or `false` for @rtl, got `{}`", line_number, v))); }
+                    },
+                    k => { return Err(Error::BadResource(format!("Catalog line {}: unknown directive `{}`", line_number, k))); }
+                }
+                continue;
+            }
+
+            let value = match unescape(value) {
+                Ok(v) => v,
+                Err(e) => { return Err(Error::BadResource(format!("Catalog line {}: {}", line_number, e))); }
+            };
+
+            let (key, category) = match key.find('[') {
+                Some(open) if key.ends_with(']') => {
+                    let name = key[open+1..key.len()-1].trim();
+                    match PluralCategory::from_name(name) {
+                        Some(c) => (key[..open].trim(), Some(c)),
+                        None => { return Err(Error::BadResource(format!("Catalog line {}: unknown plural category `{}`", line_number, name))); }
+                    }
+                },
+                Some(_) => { return Err(Error::BadResource(format!("Catalog line {}: unclosed plural category in `{}`", line_number, key))); },
+                None => (key, None)
+            };
+
+            if key.is_empty() || key.chars().any(|c| c.is_whitespace()) {
+                return Err(Error::BadResource(format!("Catalog line {}: invalid key `{}`", line_number, key)));
+            }
+
+            let duplicate = match category {
+                Some(c) => catalog.plurals.get(key).map(|forms| forms.contains_key(&c)).unwrap_or(false),
+                None => catalog.messages.contains_key(key)
+            };
+
+            if duplicate {
+                return Err(Error::BadResource(format!("Catalog line {}: duplicate key `{}`", line_number, key)));
+            }
+
+            match category {
+                Some(c) => catalog.insert_plural(key, c, value),
+                None => catalog.insert(key, value)
+            }
+        }
+
+        match language {
+            Some(l) => { catalog.language = l; Ok(catalog) },
+            None => Err(Error::BadResource("Catalog is missing the @language directive".to_string()))
+        }
+    }
+
+    /**
+        Load a catalog file.
+
+        Returns `Error::BadResource` if the file cannot be read or if it is not a valid catalog.
+    */
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Catalog, Error> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let path = path.as_ref();
+        let mut source = String::new();
+        let read = File::open(path).and_then(|mut f| f.read_to_string(&mut source));
+
+        match read {
+            Ok(_) => Catalog::parse(&source),
+            Err(e) => Err(Error::BadResource(format!("Could not read the catalog {}: {}", path.display(), e)))
+        }
+    }
+
+    /// Return the language of the catalog
+    pub fn language(&self) -> &str { &self.language }
+
+    /// Return `true` if the language is written from right to left
+    pub fn is_rtl(&self) -> bool { self.rtl }
+
+    /// Set if the language is written from right to left
+    pub fn set_rtl(&mut self, rtl: bool) { self.rtl = rtl; }
+
+    /// Return the number of keys in the catalog
+    pub fn len(&self) -> usize {
+        let plurals_only = self.plurals.keys().filter(|k| !self.messages.contains_key(*k)).count();
+        self.messages.len() + plurals_only
+    }
+
+    /// Return `true` if the catalog has no message
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Check if the catalog has a message for a key
+    pub fn contains(&self, key: &str) -> bool {
+        self.messages.contains_key(key) || self.plurals.contains_key(key)
+    }
+
+    /// Add a message to the catalog. Replace the message if the key already exists.
+    pub fn insert<S1: Into<String>, S2: Into<String>>(&mut self, key: S1, text: S2) {
+        self.messages.insert(key.into(), text.into());
+    }
+
+    /// Add a plural form of a message to the catalog. Replace the form if it already exists.
+    pub fn insert_plural<S1: Into<String>, S2: Into<String>>(&mut self, key: S1, category: PluralCategory, text: S2) {
+        self.plurals.entry(key.into()).or_insert_with(HashMap::new).insert(category, text.into());
+    }
+
+    /**
+        Add the messages of another catalog to this catalog. The messages of `other` replace the existing ones.
+    */
+    pub fn extend(&mut self, other: Catalog) {
+        self.rtl = other.rtl;
+        self.messages.extend(other.messages);
+        for (key, forms) in other.plurals {
+            self.plurals.entry(key).or_insert_with(HashMap::new).extend(forms);
+        }
+    }
+
+    /**
+        Return the message of a key. If the key only has plural forms, the `other` form is returned.
+    */
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.messages.get(key) {
+            Some(t) => Some(t.as_ref()),
+            None => self.plurals.get(key).and_then(|forms| forms.get(&PluralCategory::Other)).map(|t| t.as_ref())
+        }
+    }
+
+    /**
+        Return the plural form of a message for a count, using the plural rules of the catalog language.
+        If the form is missing, the `other` form is returned, then the message without plural forms.
+    */
+    pub fn plural(&self, key: &str, count: u64) -> Option<&str> {
+        let category = plural_category(&self.language, count);
+        let form = self.plurals.get(key).and_then(|forms| forms.get(&category).or(forms.get(&PluralCategory::Other)));
+        match form {
+            Some(t) => Some(t.as_ref()),
+            None => self.messages.get(key).map(|t| t.as_ref())
+        }
+    }
+
+}
+
+/**
+    Replace the escape sequences of a catalog value
+*/
+fn unescape(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' { out.push(c); continue; }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some(c) => { return Err(format!("unknown escape sequence `\\{}`", c)); },
+            None => { return Err("unterminated escape sequence".to_string()); }
+        }
+    }
+
+    Ok(out)
+}
+
+/**
+    A collection of catalogs with a current language. Used by the `Ui`, but can be used on its own.
+
+    A message is searched in the catalog of the current language, then in the catalog of its primary language
+    (ex: `"fr"` for `"fr-CA"`) and finally in the catalog of the fallback language. If the message is not found,
+    the key is returned.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct Localization {
+    catalogs: HashMap<String, Catalog>,
+    language: Option<String>,
+    fallback: Option<String>
+}
+
+impl Localization {
+
+    /**
+        Create a localization without catalogs
+    */
+    pub fn new() -> Localization {
+        Default::default()
+    }
+
+    /**
+        Add a catalog. If a catalog of the same language was already added, the messages are merged.
+    */
+    pub fn add_catalog(&mut self, catalog: Catalog) {
+        let language = normalize_language(catalog.language());
+        if let Some(c) = self.catalogs.get_mut(&language) {
+            c.extend(catalog);
+            return;
+        }
+
+        self.catalogs.insert(language, catalog);
+    }
+
+    /**
+        Return the catalog of a language. If there is no catalog for the language, the catalog of its
+        primary language is returned, then a catalog of a language sharing the same primary language 
+        (ex: `"fr-CA"` for `"fr"`).
+    */
+    pub fn catalog(&self, language: &str) -> Option<&Catalog> {
+        let primary = primary_language(language);
+        self.catalogs.get(&normalize_language(language))
+            .or_else(|| self.catalogs.get(&primary))
+            .or_else(|| {
+                let mut related: Vec<(&String, &Catalog)> = self.catalogs.iter().filter(|&(l, _)| primary_language(l) == primary).collect();
+                related.sort_by(|a, b| a.0.cmp(b.0));
+                related.first().map(|&(_, c)| c)
+            })
+    }
+
+    /**
+        Return the list of the languages with a catalog
+    */
+    pub fn languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self.catalogs.values().map(|c| c.language().to_string()).collect();
+        languages.sort();
+        languages
+    }
+
+    /**
+        Select the current language.
+
+        Returns `Error::BadResource` if there is no catalog for the language
+    */
+    pub fn set_language(&mut self, language: &str) -> Result<(), Error> {
+        if self.catalog(language).is_none() {
+            return Err(Error::BadResource(format!("No catalog for the language {}", language)));
+        }
+
+        self.language = Some(language.to_string());
+        Ok(())
+    }
+
+    /// Return the current language
+    pub fn language(&self) -> Option<&str> { self.language.as_ref().map(|l| l.as_ref()) }
+
+    /// Set the language used when a message is missing in the current language
+    pub fn set_fallback(&mut self, language: Option<&str>) { self.fallback = language.map(|l| l.to_string()); }
+
+    /// Return the language used when a message is missing in the current language
+    pub fn fallback(&self) -> Option<&str> { self.fallback.as_ref().map(|l| l.as_ref()) }
+
+    /**
+        Return `true` if the current language is written from right to left
+    */
+    pub fn is_rtl(&self) -> bool {
+        self.language().and_then(|l| self.catalog(l)).map(|c| c.is_rtl()).unwrap_or(false)
+    }
+
+    /**
+        Return the first catalog that has a message for the key
+    */
+    fn find(&self, key: &str) -> Option<&Catalog> {
+        let current = self.language().and_then(|l| self.catalog(l));
+        let fallback = self.fallback().and_then(|l| self.catalog(l));
+        current.into_iter().chain(fallback.into_iter()).find(|c| c.contains(key))
+    }
+
+    /**
+        Return the message of a key in the current language
+    */
+    pub fn text(&self, key: &str) -> String {
+        self.find(key).and_then(|c| c.text(key)).unwrap_or(key).to_string()
+    }
+
+    /**
+        Return the message of a key in the current language with its placeholders replaced by the arguments
+    */
+    pub fn format(&self, key: &str, args: &[(&str, &Display)]) -> String {
+        format_message(&self.text(key), args)
+    }
+
+    /**
+        Return the plural form of a message for a count in the current language with its placeholders replaced
+        by the arguments. The count is available in the message as `{count}`.
+    */
+    pub fn plural(&self, key: &str, count: u64, args: &[(&str, &Display)]) -> String {
+        let text = self.find(key).and_then(|c| c.plural(key, count)).unwrap_or(key);
+
+        let mut all_args: Vec<(&str, &Display)> = Vec::with_capacity(args.len() + 1);
+        all_args.push(("count", &count));
+        all_args.extend_from_slice(args);
+
+        format_message(text, &all_args)
+    }
+
+}
+
+/**
+    A template that sets the text of the control created by another template using a message of the
+    ui catalogs. The text is updated each time the language of the ui changes.
+    The created control has the type of the control created by the inner template.
+
+    For window-like controls, the window text is changed (ex: the title of a window, the text of a button).
+    Menus and menu items are also supported.
+
+    Members:
+    • `control`: The template of the control
+    • `key`: The key of the message
+*/
+#[derive(Clone)]
+pub struct LocalizedT<T> {
+    pub control: T,
+    pub key: String
+}
+
+impl<ID: Hash+Clone, T: ControlT<ID>> ControlT<ID> for LocalizedT<T> {
+    fn type_id(&self) -> TypeId { self.control.type_id() }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Control>, Error> {
+        let control = match self.control.build(ui) {
+            Ok(c) => c,
+            Err(e) => { return Err(e); }
+        };
+
+        // The text is applied by the ui once the control is packed
+        ui.bind_localized_handle(&control.handle(), &self.key);
+
+        Ok(control)
+    }
+}
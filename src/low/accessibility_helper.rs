@@ -0,0 +1,612 @@
+/*!
+    Low level accessibility functions.
+
+    The metadata of the controls is exposed with the dynamic annotation api (`IAccPropServices`).
+    The controls implementing `AccessibleTree` answer `WM_GETOBJECT` with a minimal `IAccessible` object.
+    Each object with children is an `AccessibleElement`; the children without children are simple elements
+    identified by their index + 1 (the MSAA child id).
+*/
+
+use std::ptr;
+use std::mem;
+use std::rc::Rc;
+use std::hash::Hash;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use winapi::{HWND, HRESULT, ULONG, DWORD, LONG, UINT, WORD, LCID, REFIID, GUID, VARIANT, VARTYPE, BSTR, LPOLESTR,
+  WPARAM, LPARAM, LRESULT, S_OK, S_FALSE, E_NOINTERFACE, E_POINTER, E_INVALIDARG, E_NOTIMPL, DISP_E_MEMBERNOTFOUND, c_void, c_int};
+
+use ui::UiInner;
+use controls::AnyHandle;
+use accessibility::{Accessibility, AccessibleNode, AccessibleRole};
+use low::defs::IAccPropServices;
+use low::other_helper::load_function;
+
+type SysAllocStringFn = unsafe extern "system" fn(*const u16) -> BSTR;
+type LresultFromObjectFn = unsafe extern "system" fn(REFIID, WPARAM, *mut c_void) -> LRESULT;
+type CreateStdAccessibleObjectFn = unsafe extern "system" fn(HWND, LONG, REFIID, *mut *mut c_void) -> HRESULT;
+
+const OBJID_WINDOW: LONG = 0;
+const OBJID_CLIENT: LONG = -4;
+const CHILDID_SELF: LONG = 0;
+
+const NAVDIR_NEXT: LONG = 5;
+const NAVDIR_PREVIOUS: LONG = 6;
+const NAVDIR_FIRSTCHILD: LONG = 7;
+const NAVDIR_LASTCHILD: LONG = 8;
+
+thread_local!(static METADATA: RefCell<HashMap<usize, Accessibility>> = RefCell::new(HashMap::new()));
+
+/**
+    Return the accessibility metadata of a window. If no metadata was set, every value is `None`.
+*/
+pub fn get_window_accessibility(hwnd: HWND) -> Accessibility {
+    METADATA.with(|m| m.borrow().get(&(hwnd as usize)).cloned().unwrap_or(Accessibility::new()))
+}
+
+/**
+    Set the accessibility metadata of a window. The `None` values are reset to the value provided by the system.
+*/
+pub unsafe fn set_window_accessibility(hwnd: HWND, info: &Accessibility) {
+    use winapi::VT_I4;
+    use low::clsid::{PROPID_ACC_NAME, PROPID_ACC_DESCRIPTION, PROPID_ACC_ROLE, PROPID_ACC_HELP};
+    use low::other_helper::to_utf16;
+
+    let services = match prop_services() {
+        Some(s) => &mut *s,
+        None => { return; }
+    };
+
+    let props = [PROPID_ACC_NAME(), PROPID_ACC_DESCRIPTION(), PROPID_ACC_ROLE(), PROPID_ACC_HELP()];
+    services.ClearHwndProps(hwnd, OBJID_CLIENT as DWORD, CHILDID_SELF as DWORD, props.as_ptr(), props.len() as c_int);
+
+    let strings = [(PROPID_ACC_NAME(), &info.name), (PROPID_ACC_DESCRIPTION(), &info.description), (PROPID_ACC_HELP(), &info.help)];
+    for &(ref prop, value) in strings.iter() {
+        if let &Some(ref value) = value {
+            let value = to_utf16(value);
+            services.SetHwndPropStr(hwnd, OBJID_CLIENT as DWORD, CHILDID_SELF as DWORD, *prop, value.as_ptr());
+        }
+    }
+
+    if let Some(role) = info.role {
+        let mut var: VARIANT = mem::zeroed();
+        *var.vt_mut() = VT_I4.0 as VARTYPE;
+        *var.lVal_mut() = role.to_system_role() as LONG;
+        services.SetHwndProp(hwnd, OBJID_CLIENT as DWORD, CHILDID_SELF as DWORD, PROPID_ACC_ROLE(), var);
+    }
+
+    services.Release();
+
+    METADATA.with(|m| {
+        let mut m = m.borrow_mut();
+        if info.is_empty() {
+            m.remove(&(hwnd as usize));
+        } else {
+            m.insert(hwnd as usize, info.clone());
+        }
+    });
+}
+
+/**
+    Remove the accessibility metadata of a window. Must be called before the window is destroyed.
+*/
+pub unsafe fn clear_window_accessibility(hwnd: HWND) {
+    let known = METADATA.with(|m| m.borrow().contains_key(&(hwnd as usize)));
+    if known {
+        set_window_accessibility(hwnd, &Accessibility::new());
+    }
+}
+
+unsafe fn prop_services() -> Option<*mut IAccPropServices> {
+    use ole32::CoCreateInstance;
+    use winapi::CLSCTX_INPROC_SERVER;
+    use low::clsid::{CLSID_AccPropServices, IID_IAccPropServices};
+
+    let mut services: *mut IAccPropServices = ptr::null_mut();
+    let result = CoCreateInstance(&CLSID_AccPropServices(), ptr::null_mut(), CLSCTX_INPROC_SERVER, &IID_IAccPropServices(),
+      &mut services as *mut *mut IAccPropServices as *mut *mut c_void);
+
+    if result == S_OK && !services.is_null() {
+        Some(services)
+    } else {
+        None
+    }
+}
+
+/**
+    Answer a `WM_GETOBJECT` message if the control provides its own accessible tree. Returns `None` otherwise.
+*/
+pub unsafe fn process_get_object<ID: Hash+Clone>(inner: &UiInner<ID>, hwnd: HWND, w: WPARAM, l: LPARAM) -> Option<LRESULT> {
+    use user32::GetClientRect;
+    use winapi::RECT;
+    use low::clsid::IID_IAccessible;
+    use low::window_helper::get_window_text;
+
+    if (l as DWORD) != (OBJID_CLIENT as DWORD) { return None; }
+
+    let children = match inner.inner_id_from_handle(&AnyHandle::HWND(hwnd)).and_then(|id| inner.controls.get(&id)) {
+        Some(control) => match control.try_borrow() {
+            Ok(control) => match control.accessible_tree() {
+                Some(tree) => tree.accessible_children(),
+                None => { return None; }
+            },
+            Err(_) => { return None; }
+        },
+        None => { return None; }
+    };
+
+    let lresult_from_object: LresultFromObjectFn = match load_function("oleacc.dll", "LresultFromObject") {
+        Some(f) => mem::transmute(f),
+        None => { return None; }
+    };
+
+    let info = get_window_accessibility(hwnd);
+    let mut rect: RECT = mem::zeroed();
+    GetClientRect(hwnd, &mut rect);
+
+    let root = AccessibleNode {
+        name: info.name.unwrap_or_else(|| get_window_text(hwnd)),
+        description: info.description.unwrap_or(String::new()),
+        help: info.help.unwrap_or(String::new()),
+        value: String::new(),
+        role: info.role.unwrap_or(AccessibleRole::Client),
+        bounds: (0, 0, rect.right as u32, rect.bottom as u32),
+        children: children
+    };
+
+    let element = AccessibleElement::new(hwnd, Rc::new(root), Vec::new(), ptr::null_mut());
+    let result = lresult_from_object(&IID_IAccessible(), w, element as *mut c_void);
+    release(element);
+
+    Some(result)
+}
+
+#[repr(C)]
+struct AccessibleVtbl {
+    query_interface: unsafe extern "system" fn(*mut AccessibleElement, REFIID, *mut *mut c_void) -> HRESULT,
+    add_ref: unsafe extern "system" fn(*mut AccessibleElement) -> ULONG,
+    release: unsafe extern "system" fn(*mut AccessibleElement) -> ULONG,
+    get_type_info_count: unsafe extern "system" fn(*mut AccessibleElement, *mut UINT) -> HRESULT,
+    get_type_info: unsafe extern "system" fn(*mut AccessibleElement, UINT, LCID, *mut *mut c_void) -> HRESULT,
+    get_ids_of_names: unsafe extern "system" fn(*mut AccessibleElement, REFIID, *mut LPOLESTR, UINT, LCID, *mut LONG) -> HRESULT,
+    invoke: unsafe extern "system" fn(*mut AccessibleElement, LONG, REFIID, LCID, WORD, *mut c_void, *mut VARIANT, *mut c_void, *mut UINT) -> HRESULT,
+    get_acc_parent: unsafe extern "system" fn(*mut AccessibleElement, *mut *mut c_void) -> HRESULT,
+    get_acc_child_count: unsafe extern "system" fn(*mut AccessibleElement, *mut LONG) -> HRESULT,
+    get_acc_child: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut *mut c_void) -> HRESULT,
+    get_acc_name: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    get_acc_value: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    get_acc_description: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    get_acc_role: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut VARIANT) -> HRESULT,
+    get_acc_state: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut VARIANT) -> HRESULT,
+    get_acc_help: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    get_acc_help_topic: unsafe extern "system" fn(*mut AccessibleElement, *mut BSTR, VARIANT, *mut LONG) -> HRESULT,
+    get_acc_keyboard_shortcut: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    get_acc_focus: unsafe extern "system" fn(*mut AccessibleElement, *mut VARIANT) -> HRESULT,
+    get_acc_selection: unsafe extern "system" fn(*mut AccessibleElement, *mut VARIANT) -> HRESULT,
+    get_acc_default_action: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, *mut BSTR) -> HRESULT,
+    acc_select: unsafe extern "system" fn(*mut AccessibleElement, LONG, VARIANT) -> HRESULT,
+    acc_location: unsafe extern "system" fn(*mut AccessibleElement, *mut LONG, *mut LONG, *mut LONG, *mut LONG, VARIANT) -> HRESULT,
+    acc_navigate: unsafe extern "system" fn(*mut AccessibleElement, LONG, VARIANT, *mut VARIANT) -> HRESULT,
+    acc_hit_test: unsafe extern "system" fn(*mut AccessibleElement, LONG, LONG, *mut VARIANT) -> HRESULT,
+    acc_do_default_action: unsafe extern "system" fn(*mut AccessibleElement, VARIANT) -> HRESULT,
+    put_acc_name: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, BSTR) -> HRESULT,
+    put_acc_value: unsafe extern "system" fn(*mut AccessibleElement, VARIANT, BSTR) -> HRESULT,
+}
+
+/**
+    A IAccessible implementation exposing a node of an accessible tree. The layout must begin with the vtable pointer.
+
+    Members:
+    • `root`: The root of the tree, shared by every element of the tree
+    • `path`: The indices of the children leading from the root to the node of the element
+    • `parent`: The element exposing the parent node, or null for the root. The element keeps a reference on its parent
+*/
+#[repr(C)]
+struct AccessibleElement {
+    vtbl: *const AccessibleVtbl,
+    refs: ULONG,
+    hwnd: HWND,
+    root: Rc<AccessibleNode>,
+    path: Vec<usize>,
+    parent: *mut AccessibleElement
+}
+
+static ACCESSIBLE_VTBL: AccessibleVtbl = AccessibleVtbl {
+    query_interface: query_interface,
+    add_ref: add_ref,
+    release: release,
+    get_type_info_count: get_type_info_count,
+    get_type_info: get_type_info,
+    get_ids_of_names: get_ids_of_names,
+    invoke: invoke,
+    get_acc_parent: get_acc_parent,
+    get_acc_child_count: get_acc_child_count,
+    get_acc_child: get_acc_child,
+    get_acc_name: get_acc_name,
+    get_acc_value: get_acc_value,
+    get_acc_description: get_acc_description,
+    get_acc_role: get_acc_role,
+    get_acc_state: get_acc_state,
+    get_acc_help: get_acc_help,
+    get_acc_help_topic: get_acc_help_topic,
+    get_acc_keyboard_shortcut: get_acc_keyboard_shortcut,
+    get_acc_focus: get_acc_focus,
+    get_acc_selection: get_acc_selection,
+    get_acc_default_action: get_acc_default_action,
+    acc_select: acc_select,
+    acc_location: acc_location,
+    acc_navigate: acc_navigate,
+    acc_hit_test: acc_hit_test,
+    acc_do_default_action: acc_do_default_action,
+    put_acc_name: put_acc_name,
+    put_acc_value: put_acc_value,
+};
+
+impl AccessibleElement {
+
+    unsafe fn new(hwnd: HWND, root: Rc<AccessibleNode>, path: Vec<usize>, parent: *mut AccessibleElement) -> *mut AccessibleElement {
+        if !parent.is_null() { add_ref(parent); }
+
+        Box::into_raw(Box::new(AccessibleElement {
+            vtbl: &ACCESSIBLE_VTBL,
+            refs: 1,
+            hwnd: hwnd,
+            root: root,
+            path: path,
+            parent: parent
+        }))
+    }
+
+    /// Return the node exposed by the element
+    fn node(&self) -> &AccessibleNode {
+        self.path.iter().fold(&*self.root, |node, &i| &node.children[i])
+    }
+
+    /// Return the node identified by a MSAA child id. `None` if the child id is not valid.
+    unsafe fn child(&self, child: &VARIANT) -> Option<&AccessibleNode> {
+        use winapi::VT_I4;
+
+        if *child.vt() != (VT_I4.0 as VARTYPE) { return None; }
+
+        let node = self.node();
+        match *child.lVal() {
+            CHILDID_SELF => Some(node),
+            i if i > 0 => node.children.get((i - 1) as usize),
+            _ => None
+        }
+    }
+
+    /// Create the element exposing a child of the node
+    unsafe fn child_element(this: *mut AccessibleElement, index: usize) -> *mut AccessibleElement {
+        let element = &*this;
+        let mut path = element.path.clone();
+        path.push(index);
+        AccessibleElement::new(element.hwnd, element.root.clone(), path, this)
+    }
+
+}
+
+unsafe fn alloc_string(value: &str) -> BSTR {
+    use low::other_helper::to_utf16;
+
+    match load_function("oleaut32.dll", "SysAllocString") {
+        Some(f) => {
+            let f: SysAllocStringFn = mem::transmute(f);
+            let value = to_utf16(value);
+            f(value.as_ptr())
+        },
+        None => ptr::null_mut()
+    }
+}
+
+unsafe fn set_variant_i4(out: *mut VARIANT, value: LONG) {
+    use winapi::VT_I4;
+
+    *out = mem::zeroed();
+    *(*out).vt_mut() = VT_I4.0 as VARTYPE;
+    *(*out).lVal_mut() = value;
+}
+
+unsafe fn set_variant_dispatch(out: *mut VARIANT, value: *mut AccessibleElement) {
+    use winapi::VT_DISPATCH;
+
+    *out = mem::zeroed();
+    *(*out).vt_mut() = VT_DISPATCH.0 as VARTYPE;
+    *(*out).pdispVal_mut() = value as *mut _;
+}
+
+/// Write the string property of a node. Empty properties are reported as missing.
+unsafe fn string_property<F: Fn(&AccessibleNode) -> &str>(this: *mut AccessibleElement, child: VARIANT, out: *mut BSTR, prop: F) -> HRESULT {
+    if out.is_null() { return E_POINTER; }
+    *out = ptr::null_mut();
+
+    match (&*this).child(&child) {
+        Some(node) => {
+            let value = prop(node);
+            if value.is_empty() {
+                S_FALSE
+            } else {
+                *out = alloc_string(value);
+                S_OK
+            }
+        },
+        None => E_INVALIDARG
+    }
+}
+
+fn guid_eq(g1: &GUID, g2: &GUID) -> bool {
+    g1.Data1 == g2.Data1 && g1.Data2 == g2.Data2 && g1.Data3 == g2.Data3 && g1.Data4 == g2.Data4
+}
+
+unsafe extern "system" fn query_interface(this: *mut AccessibleElement, riid: REFIID, out: *mut *mut c_void) -> HRESULT {
+    use low::clsid::{IID_IUnknown, IID_IDispatch, IID_IAccessible};
+
+    if out.is_null() { return E_POINTER; }
+
+    let riid = &*riid;
+    if guid_eq(riid, &IID_IUnknown()) || guid_eq(riid, &IID_IDispatch()) || guid_eq(riid, &IID_IAccessible()) {
+        add_ref(this);
+        *out = this as *mut c_void;
+        S_OK
+    } else {
+        *out = ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut AccessibleElement) -> ULONG {
+    let element = &mut *this;
+    element.refs += 1;
+    element.refs
+}
+
+unsafe extern "system" fn release(this: *mut AccessibleElement) -> ULONG {
+    let refs = {
+        let element = &mut *this;
+        element.refs -= 1;
+        element.refs
+    };
+
+    if refs == 0 {
+        let element = Box::from_raw(this);
+        if !element.parent.is_null() {
+            release(element.parent);
+        }
+    }
+
+    refs
+}
+
+unsafe extern "system" fn get_type_info_count(_this: *mut AccessibleElement, count: *mut UINT) -> HRESULT {
+    if count.is_null() { return E_POINTER; }
+    *count = 0;
+    S_OK
+}
+
+unsafe extern "system" fn get_type_info(_this: *mut AccessibleElement, _i: UINT, _lcid: LCID, _info: *mut *mut c_void) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn get_ids_of_names(_this: *mut AccessibleElement, _riid: REFIID, _names: *mut LPOLESTR, _count: UINT, _lcid: LCID, _ids: *mut LONG) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn invoke(_this: *mut AccessibleElement, _id: LONG, _riid: REFIID, _lcid: LCID, _flags: WORD, _params: *mut c_void, _result: *mut VARIANT, _excep: *mut c_void, _arg: *mut UINT) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn get_acc_parent(this: *mut AccessibleElement, out: *mut *mut c_void) -> HRESULT {
+    use low::clsid::IID_IDispatch;
+
+    if out.is_null() { return E_POINTER; }
+    *out = ptr::null_mut();
+
+    let element = &*this;
+    if element.parent.is_null() {
+        // The parent of the client area is the window itself
+        match load_function("oleacc.dll", "CreateStdAccessibleObject") {
+            Some(f) => {
+                let f: CreateStdAccessibleObjectFn = mem::transmute(f);
+                f(element.hwnd, OBJID_WINDOW, &IID_IDispatch(), out)
+            },
+            None => S_FALSE
+        }
+    } else {
+        add_ref(element.parent);
+        *out = element.parent as *mut c_void;
+        S_OK
+    }
+}
+
+unsafe extern "system" fn get_acc_child_count(this: *mut AccessibleElement, out: *mut LONG) -> HRESULT {
+    if out.is_null() { return E_POINTER; }
+    *out = (&*this).node().children.len() as LONG;
+    S_OK
+}
+
+unsafe extern "system" fn get_acc_child(this: *mut AccessibleElement, child: VARIANT, out: *mut *mut c_void) -> HRESULT {
+    if out.is_null() { return E_POINTER; }
+    *out = ptr::null_mut();
+
+    let has_children = match (&*this).child(&child) {
+        Some(node) => !node.children.is_empty(),
+        None => { return E_INVALIDARG; }
+    };
+
+    let index = *child.lVal();
+    if index == CHILDID_SELF {
+        add_ref(this);
+        *out = this as *mut c_void;
+        S_OK
+    } else if has_children {
+        *out = AccessibleElement::child_element(this, (index - 1) as usize) as *mut c_void;
+        S_OK
+    } else {
+        // Simple elements are only accessed through their parent
+        S_FALSE
+    }
+}
+
+unsafe extern "system" fn get_acc_name(this: *mut AccessibleElement, child: VARIANT, out: *mut BSTR) -> HRESULT {
+    string_property(this, child, out, |n| &n.name)
+}
+
+unsafe extern "system" fn get_acc_value(this: *mut AccessibleElement, child: VARIANT, out: *mut BSTR) -> HRESULT {
+    string_property(this, child, out, |n| &n.value)
+}
+
+unsafe extern "system" fn get_acc_description(this: *mut AccessibleElement, child: VARIANT, out: *mut BSTR) -> HRESULT {
+    string_property(this, child, out, |n| &n.description)
+}
+
+unsafe extern "system" fn get_acc_help(this: *mut AccessibleElement, child: VARIANT, out: *mut BSTR) -> HRESULT {
+    string_property(this, child, out, |n| &n.help)
+}
+
+unsafe extern "system" fn get_acc_role(this: *mut AccessibleElement, child: VARIANT, out: *mut VARIANT) -> HRESULT {
+    if out.is_null() { return E_POINTER; }
+
+    match (&*this).child(&child) {
+        Some(node) => {
+            set_variant_i4(out, node.role.to_system_role() as LONG);
+            S_OK
+        },
+        None => E_INVALIDARG
+    }
+}
+
+unsafe extern "system" fn get_acc_state(this: *mut AccessibleElement, child: VARIANT, out: *mut VARIANT) -> HRESULT {
+    const STATE_SYSTEM_NORMAL: LONG = 0;
+    const STATE_SYSTEM_INVISIBLE: LONG = 0x8000;
+
+    if out.is_null() { return E_POINTER; }
+
+    match (&*this).child(&child) {
+        Some(node) => {
+            let empty = node.bounds.2 == 0 || node.bounds.3 == 0;
+            set_variant_i4(out, if empty { STATE_SYSTEM_INVISIBLE } else { STATE_SYSTEM_NORMAL });
+            S_OK
+        },
+        None => E_INVALIDARG
+    }
+}
+
+unsafe extern "system" fn get_acc_help_topic(_this: *mut AccessibleElement, file: *mut BSTR, _child: VARIANT, topic: *mut LONG) -> HRESULT {
+    if !file.is_null() { *file = ptr::null_mut(); }
+    if !topic.is_null() { *topic = 0; }
+    S_FALSE
+}
+
+unsafe extern "system" fn get_acc_keyboard_shortcut(_this: *mut AccessibleElement, _child: VARIANT, out: *mut BSTR) -> HRESULT {
+    if !out.is_null() { *out = ptr::null_mut(); }
+    S_FALSE
+}
+
+unsafe extern "system" fn get_acc_focus(_this: *mut AccessibleElement, out: *mut VARIANT) -> HRESULT {
+    if !out.is_null() { *out = mem::zeroed(); }
+    S_FALSE
+}
+
+unsafe extern "system" fn get_acc_selection(_this: *mut AccessibleElement, out: *mut VARIANT) -> HRESULT {
+    if !out.is_null() { *out = mem::zeroed(); }
+    S_FALSE
+}
+
+unsafe extern "system" fn get_acc_default_action(_this: *mut AccessibleElement, _child: VARIANT, out: *mut BSTR) -> HRESULT {
+    if !out.is_null() { *out = ptr::null_mut(); }
+    S_FALSE
+}
+
+unsafe extern "system" fn acc_select(_this: *mut AccessibleElement, _flags: LONG, _child: VARIANT) -> HRESULT {
+    DISP_E_MEMBERNOTFOUND
+}
+
+unsafe extern "system" fn acc_location(this: *mut AccessibleElement, x: *mut LONG, y: *mut LONG, w: *mut LONG, h: *mut LONG, child: VARIANT) -> HRESULT {
+    use user32::ClientToScreen;
+    use winapi::POINT;
+
+    if x.is_null() || y.is_null() || w.is_null() || h.is_null() { return E_POINTER; }
+
+    let element = &*this;
+    match element.child(&child) {
+        Some(node) => {
+            let (bx, by, bw, bh) = node.bounds;
+            let mut pos = POINT{x: bx, y: by};
+            ClientToScreen(element.hwnd, &mut pos);
+            *x = pos.x; *y = pos.y;
+            *w = bw as LONG; *h = bh as LONG;
+            S_OK
+        },
+        None => E_INVALIDARG
+    }
+}
+
+unsafe extern "system" fn acc_navigate(this: *mut AccessibleElement, dir: LONG, start: VARIANT, out: *mut VARIANT) -> HRESULT {
+    if out.is_null() { return E_POINTER; }
+    *out = mem::zeroed();
+
+    let element = &*this;
+    if element.child(&start).is_none() { return E_INVALIDARG; }
+
+    let count = element.node().children.len() as LONG;
+    let start = *start.lVal();
+    let target = match (dir, start) {
+        (NAVDIR_FIRSTCHILD, CHILDID_SELF) => 1,
+        (NAVDIR_LASTCHILD, CHILDID_SELF) => count,
+        (NAVDIR_NEXT, i) if i != CHILDID_SELF => i + 1,
+        (NAVDIR_PREVIOUS, i) if i != CHILDID_SELF => i - 1,
+        _ => 0
+    };
+
+    if target >= 1 && target <= count {
+        set_variant_i4(out, target);
+        S_OK
+    } else {
+        S_FALSE
+    }
+}
+
+unsafe extern "system" fn acc_hit_test(this: *mut AccessibleElement, x: LONG, y: LONG, out: *mut VARIANT) -> HRESULT {
+    use user32::ScreenToClient;
+    use winapi::POINT;
+
+    if out.is_null() { return E_POINTER; }
+    *out = mem::zeroed();
+
+    let (hit, has_children) = {
+        let element = &*this;
+        let mut pos = POINT{x: x, y: y};
+        ScreenToClient(element.hwnd, &mut pos);
+
+        let node = element.node();
+        if !node.contains(pos.x, pos.y) {
+            return S_FALSE;
+        }
+
+        match node.hit_test(pos.x, pos.y) {
+            Some(i) => (Some(i), !node.children[i].children.is_empty()),
+            None => (None, false)
+        }
+    };
+
+    match hit {
+        Some(i) if has_children => set_variant_dispatch(out, AccessibleElement::child_element(this, i)),
+        Some(i) => set_variant_i4(out, (i + 1) as LONG),
+        None => set_variant_i4(out, CHILDID_SELF)
+    }
+
+    S_OK
+}
+
+unsafe extern "system" fn acc_do_default_action(_this: *mut AccessibleElement, _child: VARIANT) -> HRESULT {
+    DISP_E_MEMBERNOTFOUND
+}
+
+unsafe extern "system" fn put_acc_name(_this: *mut AccessibleElement, _child: VARIANT, _value: BSTR) -> HRESULT {
+    DISP_E_MEMBERNOTFOUND
+}
+
+unsafe extern "system" fn put_acc_value(_this: *mut AccessibleElement, _child: VARIANT, _value: BSTR) -> HRESULT {
+    DISP_E_MEMBERNOTFOUND
+}
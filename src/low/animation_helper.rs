@@ -0,0 +1,234 @@
+/*!
+    Low level animation functions. Play the frames of an `Animation` in a static control.
+
+    The playback state is stored by handle. Like the `Timer` control, the frames are advanced by a system timer
+    callback on the thread of the Ui, so no extra thread is needed.
+*/
+
+use std::ptr;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use winapi::{HWND, HBITMAP, UINT, UINT_PTR, DWORD};
+
+use resources::animation::AnimationFrames;
+
+/// The identifier of the animation timer of a control
+const ANIMATION_TIMER_ID: UINT_PTR = 1;
+
+/// The minimum resolution of the system timers
+const MIN_DELAY: u32 = 10;
+
+/**
+    The playback state of an animation in a control
+
+    • `frame`: The index of the frame displayed  
+    • `plays`: The number of times the animation was played completely  
+    • `copy`: The bitmap created by the control when it displays a bitmap with an alpha channel. Owned by the animation.  
+*/
+struct Playback {
+    frames: Rc<AnimationFrames>,
+    frame: usize,
+    plays: u32,
+    playing: bool,
+    copy: HBITMAP
+}
+
+thread_local!(static PLAYBACKS: RefCell<HashMap<usize, Playback>> = RefCell::new(HashMap::new()));
+
+/**
+    Display the first frame of an animation in a static control and start playing it. Any previous animation is stopped.
+*/
+pub unsafe fn set_animation(hwnd: HWND, frames: Rc<AnimationFrames>) {
+    free_animation(hwnd);
+
+    let first = frames.bitmaps[0];
+    let copy = show_frame(hwnd, first, ptr::null_mut());
+    let playback = Playback{ frames: frames, frame: 0, plays: 0, playing: false, copy: copy };
+    PLAYBACKS.with(|p| p.borrow_mut().insert(hwnd as usize, playback));
+
+    play(hwnd);
+}
+
+/**
+    Stop the animation of a control and remove its image. The bitmaps of the animation may be freed once the animation is removed.
+*/
+pub unsafe fn free_animation(hwnd: HWND) {
+    use user32::KillTimer;
+
+    let playback = PLAYBACKS.with(|p| p.borrow_mut().remove(&(hwnd as usize)));
+    if let Some(playback) = playback {
+        KillTimer(hwnd, ANIMATION_TIMER_ID);
+        show_frame(hwnd, ptr::null_mut(), playback.copy);
+    }
+}
+
+/**
+    Start or resume the animation of a control. If the animation was over, it is restarted from the first frame.
+*/
+pub unsafe fn play(hwnd: HWND) {
+    let restart = PLAYBACKS.with(|p| {
+        match p.borrow_mut().get_mut(&(hwnd as usize)) {
+            Some(playback) => {
+                let over = is_over(playback);
+                if over { playback.plays = 0; }
+                playback.playing = playback.frames.bitmaps.len() > 1;
+                Some(over)
+            },
+            None => None
+        }
+    });
+
+    match restart {
+        Some(true) => { seek(hwnd, 0); },
+        Some(false) => schedule(hwnd),
+        None => {}
+    }
+}
+
+/**
+    Pause the animation of a control on the current frame
+*/
+pub unsafe fn pause(hwnd: HWND) {
+    use user32::KillTimer;
+
+    let paused = PLAYBACKS.with(|p| {
+        match p.borrow_mut().get_mut(&(hwnd as usize)) {
+            Some(playback) => { playback.playing = false; true },
+            None => false
+        }
+    });
+
+    if paused {
+        KillTimer(hwnd, ANIMATION_TIMER_ID);
+    }
+}
+
+/**
+    Check if the animation of a control is playing
+*/
+pub fn is_playing(hwnd: HWND) -> bool {
+    PLAYBACKS.with(|p| p.borrow().get(&(hwnd as usize)).map(|playback| playback.playing).unwrap_or(false))
+}
+
+/**
+    Return the index of the frame displayed by a control, or `None` if the control do not have an animation
+*/
+pub fn current_frame(hwnd: HWND) -> Option<usize> {
+    PLAYBACKS.with(|p| p.borrow().get(&(hwnd as usize)).map(|playback| playback.frame))
+}
+
+/**
+    Display a frame of the animation. If the animation is playing, it continues from this frame.
+    Returns `false` if the control do not have an animation or if the index is out of range.
+*/
+pub unsafe fn seek(hwnd: HWND, frame: usize) -> bool {
+    let bitmap = PLAYBACKS.with(|p| {
+        match p.borrow_mut().get_mut(&(hwnd as usize)) {
+            Some(playback) if frame < playback.frames.bitmaps.len() => {
+                playback.frame = frame;
+                Some((playback.frames.bitmaps[frame], playback.copy))
+            },
+            _ => None
+        }
+    });
+
+    match bitmap {
+        Some((bitmap, copy)) => {
+            set_frame_bitmap(hwnd, bitmap, copy);
+            schedule(hwnd);
+            true
+        },
+        None => false
+    }
+}
+
+/**
+    An animation is over once it was played `loop_count` times
+*/
+fn is_over(playback: &Playback) -> bool {
+    playback.frames.loop_count != 0 && playback.plays >= playback.frames.loop_count
+}
+
+/**
+    Start the timer that displays the next frame, using the delay of the current frame
+*/
+unsafe fn schedule(hwnd: HWND) {
+    use user32::{SetTimer, KillTimer};
+    use std::cmp::max;
+
+    let delay = PLAYBACKS.with(|p| {
+        p.borrow().get(&(hwnd as usize))
+          .and_then(|playback| if playback.playing { Some(playback.frames.delays[playback.frame]) } else { None })
+    });
+
+    match delay {
+        Some(delay) => { SetTimer(hwnd, ANIMATION_TIMER_ID, max(MIN_DELAY, delay), Some(animation_timer)); },
+        None => { KillTimer(hwnd, ANIMATION_TIMER_ID); }
+    }
+}
+
+/**
+    Set the bitmap of a static control and keep track of the bitmap copied by the control
+*/
+unsafe fn set_frame_bitmap(hwnd: HWND, bitmap: HBITMAP, copy: HBITMAP) {
+    let copy = show_frame(hwnd, bitmap, copy);
+    PLAYBACKS.with(|p| {
+        if let Some(playback) = p.borrow_mut().get_mut(&(hwnd as usize)) {
+            playback.copy = copy;
+        }
+    });
+}
+
+/**
+    Send a bitmap to a static control. Since version 6 of the common controls, the control makes a copy of the
+    bitmaps that have an alpha channel and the copy must be freed by the application.
+
+    `copy` is the copy made for the previous frame. It is not displayed anymore, so it is freed.
+    Returns the copy made for the new frame, or null if the control uses the bitmap directly.
+*/
+unsafe fn show_frame(hwnd: HWND, bitmap: HBITMAP, copy: HBITMAP) -> HBITMAP {
+    use user32::SendMessageW;
+    use gdi32::DeleteObject;
+    use winapi::{IMAGE_BITMAP, WPARAM, LPARAM};
+    use low::defs::{STM_SETIMAGE, STM_GETIMAGE};
+
+    SendMessageW(hwnd, STM_SETIMAGE, IMAGE_BITMAP as WPARAM, bitmap as LPARAM);
+    if !copy.is_null() {
+        DeleteObject(copy as _);
+    }
+
+    let current = SendMessageW(hwnd, STM_GETIMAGE, IMAGE_BITMAP as WPARAM, 0) as HBITMAP;
+    if current != bitmap { current } else { ptr::null_mut() }
+}
+
+/**
+    Display the next frame of an animation. If the last frame was reached, the animation loops or stops.
+*/
+#[allow(unused_variables, non_snake_case)]
+unsafe extern "system" fn animation_timer(hwnd: HWND, uMsg: UINT, idEvent: UINT_PTR, dwTime: DWORD) {
+    let next = PLAYBACKS.with(|p| {
+        match p.borrow_mut().get_mut(&(hwnd as usize)) {
+            Some(playback) => {
+                if playback.frame + 1 < playback.frames.bitmaps.len() {
+                    Some(playback.frame + 1)
+                } else {
+                    playback.plays += 1;
+                    if is_over(playback) {
+                        playback.playing = false;
+                        None
+                    } else {
+                        Some(0)
+                    }
+                }
+            },
+            None => None
+        }
+    });
+
+    match next {
+        Some(frame) => { seek(hwnd, frame); },
+        None => { schedule(hwnd); }
+    }
+}
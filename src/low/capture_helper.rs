@@ -0,0 +1,140 @@
+/*!
+    Capture the content of the controls and compare the result with reference images. Used by visual regression tests.
+*/
+
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::fs;
+
+use winapi::{HWND, HBITMAP, UINT};
+
+use ui::Ui;
+use error::{Error, SystemError};
+use resources::{Image, ImageData, ImageFormat, ImageDiff};
+use low::window_helper::handle_of_window;
+
+const PW_CLIENTONLY: UINT = 1;
+const PW_RENDERFULLCONTENT: UINT = 2;
+
+/// If this environment variable is set to `1`, `compare_golden` overwrites the golden images
+const UPDATE_GOLDEN_VAR: &'static str = "NWG_UPDATE_GOLDEN";
+
+/**
+    Render the client area of a window-like control (and its children) into a bitmap.
+    The control do not need to be visible on the screen. For example, windows outside of the desktop can be captured.
+
+    Returns `Error::BadParent` if the control is not window-like.
+*/
+pub fn capture<ID: Clone+Hash>(ui: &Ui<ID>, id: &ID) -> Result<Image, Error> {
+    let handle = handle_of_window(ui, id, "Only window-like controls can be captured.")?;
+
+    unsafe {
+        let image = capture_window(handle)?;
+        let bitmap = ::low::image_helper::bitmap_from_data(&image)?;
+        Ok(Image::from_bitmap(bitmap))
+    }
+}
+
+/**
+    Compare an image with the golden image saved in the PNG file at `path`. Two pixels are different if one
+    of their channels differs by more than `tolerance`.
+
+    If the golden image does not exist, it is created from `image`. Set the environment variable `NWG_UPDATE_GOLDEN`
+    to `1` to overwrite the golden images with the new captures.
+
+    If the images do not match, the captured image and the difference image are saved next to the golden image
+    as `[name].actual.png` and `[name].diff.png`.
+*/
+pub fn compare_golden<P: AsRef<Path>>(image: &ImageData, path: P, tolerance: u8) -> Result<ImageDiff, Error> {
+    use std::env;
+
+    let path = path.as_ref();
+    let update = env::var(UPDATE_GOLDEN_VAR).map(|v| v == "1").unwrap_or(false);
+
+    if update || !path.exists() {
+        save_png(image, path)?;
+        return Ok(image.compare(image, tolerance));
+    }
+
+    let golden = match fs::read(path) {
+        Ok(data) => ImageData::decode(&data)?,
+        Err(e) => { return Err(Error::BadResource(format!("Could not read the golden image {}: {}", path.display(), e))); }
+    };
+
+    let diff = image.compare(&golden, tolerance);
+    if !diff.matches() {
+        save_png(image, &sibling(path, "actual"))?;
+        save_png(&diff.diff, &sibling(path, "diff"))?;
+    }
+
+    Ok(diff)
+}
+
+/**
+    Paint a window into a 32 bits bitmap and return its pixels. GDI do not write the alpha channel, so the pixels are made opaque.
+*/
+unsafe fn capture_window(handle: HWND) -> Result<ImageData, Error> {
+    use winapi::RECT;
+    use user32::{GetClientRect, PrintWindow};
+    use gdi32::{CreateCompatibleDC, SelectObject, DeleteDC, DeleteObject};
+    use low::image_helper::{create_dib_section, read_bitmap};
+
+    let mut rc = RECT{ left: 0, top: 0, right: 0, bottom: 0 };
+    GetClientRect(handle, &mut rc);
+
+    let (width, height) = ((rc.right - rc.left) as u32, (rc.bottom - rc.top) as u32);
+    if width == 0 || height == 0 {
+        return Err(Error::UserError("The control client area is empty".to_string()));
+    }
+
+    let pixels = vec![0u8; (width as usize) * (height as usize) * 4];
+    let bitmap: HBITMAP = create_dib_section(width, height, &pixels)?;
+
+    let dc = CreateCompatibleDC(ptr::null_mut());
+    let old = SelectObject(dc, bitmap as _);
+
+    // PW_RENDERFULLCONTENT is not supported before Windows 8.1
+    let mut printed = PrintWindow(handle, dc, PW_CLIENTONLY | PW_RENDERFULLCONTENT) != 0;
+    if !printed {
+        printed = PrintWindow(handle, dc, PW_CLIENTONLY) != 0;
+    }
+
+    SelectObject(dc, old);
+    DeleteDC(dc);
+
+    let image = if printed {
+        read_bitmap(bitmap).map(|(image, _)| image)
+    } else {
+        Err(Error::System(SystemError::SystemMessageFailed("PrintWindow failed".to_string())))
+    };
+
+    DeleteObject(bitmap as _);
+
+    image.map(|mut image| {
+        for p in image.data.chunks_mut(4) { p[3] = 255; }
+        image
+    })
+}
+
+fn save_png(image: &ImageData, path: &Path) -> Result<(), Error> {
+    let mut data = Vec::new();
+    image.encode(ImageFormat::Png, &mut data)?;
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).ok();
+        }
+    }
+
+    match fs::write(path, &data) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::BadResource(format!("Could not write the image {}: {}", path.display(), e)))
+    }
+}
+
+/// `dir/name.png` -> `dir/name.[suffix].png`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or(String::new());
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
@@ -0,0 +1,51 @@
+/*!
+    Low level chart functions. Draw the plot of a chart each time the chart is painted and move the
+    tooltip of the chart to the point under the mouse.
+*/
+
+use std::hash::Hash;
+use std::any::TypeId;
+
+use winapi::{HWND, UINT, LPARAM, WM_PAINT, WM_MOUSEMOVE, WM_MOUSELEAVE};
+
+use ui::UiInner;
+use controls::{AnyHandle, Chart};
+
+/**
+    Draw the plot of a chart when it receives `WM_PAINT` and update its tooltip when the mouse moves over it or leaves it.
+    Does nothing if `hwnd` is not a chart.
+*/
+pub unsafe fn process_chart<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, l: LPARAM) {
+    use winapi::{GET_X_LPARAM, GET_Y_LPARAM};
+    use low::scene_helper::track_leave;
+
+    match msg {
+        WM_PAINT | WM_MOUSEMOVE | WM_MOUSELEAVE => {},
+        _ => { return; }
+    }
+
+    let id = match inner.inner_id_from_handle(&AnyHandle::HWND(hwnd)).and_then(|i| inner.inner_public_map.get(&i)) {
+        Some(&(ref id, t)) if t == TypeId::of::<Chart<ID>>() => id.clone(),
+        _ => { return; }
+    };
+
+    inner.with_ui(|ui| {
+        let mut chart = match ui.get_mut::<Chart<ID>>(&id) {
+            Ok(c) => c,
+            Err(_) => { return; }
+        };
+
+        match msg {
+            WM_PAINT => { chart.paint(ui).ok(); },
+            WM_MOUSEMOVE => {
+                // The mouse position is in pixels, the plot uses the device independent pixels of the canvas
+                let (dpix, dpiy) = chart.get_dpi();
+                let pos = (GET_X_LPARAM(l) as f32 * 96.0 / dpix, GET_Y_LPARAM(l) as f32 * 96.0 / dpiy);
+                if chart.hover(Some(pos)) && chart.plot().get_hover().is_some() {
+                    track_leave(hwnd);
+                }
+            },
+            _ => { chart.hover(None); }
+        }
+    });
+}
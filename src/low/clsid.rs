@@ -20,4 +20,16 @@ define_guid!(CLSID_FileOpenDialog, 3692845724, 59530, 19934, [165, 161, 96, 248,
 define_guid!(CLSID_FileSaveDialog, 3233080051, 47649, 18291, [141, 186, 51, 94, 201, 70, 235, 139]);
 define_guid!(UUIDOF_IFileDialog, 1123569974, 56190, 17308, [133, 241, 228, 7, 93, 19, 95, 200]);
 define_guid!(UUIDOF_IFileOpenDialog, 3581702792, 54445, 18280, [190, 2, 157, 150, 149, 50, 217, 96]);
-define_guid!(IID_IShellItem, 1132621086, 59160, 17134, [188, 85, 161, 226, 97, 195, 123, 254]);
\ No newline at end of file
+define_guid!(IID_IShellItem, 1132621086, 59160, 17134, [188, 85, 161, 226, 97, 195, 123, 254]);
+define_guid!(IID_IUnknown, 0, 0, 0, [192, 0, 0, 0, 0, 0, 0, 70]);
+define_guid!(IID_IDropTarget, 290, 0, 0, [192, 0, 0, 0, 0, 0, 0, 70]);
+define_guid!(CLSID_AccPropServices, 3052942603, 1352, 18609, [166, 238, 136, 189, 0, 180, 165, 231]);
+define_guid!(IID_IAccPropServices, 1848043382, 1264, 18781, [128, 228, 51, 48, 53, 46, 49, 105]);
+define_guid!(IID_IAccessible, 1636251360, 15421, 4559, [129, 12, 0, 170, 0, 56, 155, 113]);
+define_guid!(IID_IDispatch, 132096, 0, 0, [192, 0, 0, 0, 0, 0, 0, 70]);
+define_guid!(PROPID_ACC_NAME, 1619869176, 33064, 19111, [164, 40, 245, 94, 73, 38, 114, 145]);
+define_guid!(PROPID_ACC_DESCRIPTION, 1296621540, 48447, 18719, [166, 72, 73, 45, 111, 32, 197, 136]);
+define_guid!(PROPID_ACC_ROLE, 3415236594, 31697, 19461, [179, 200, 230, 194, 65, 54, 77, 112]);
+define_guid!(PROPID_ACC_HELP, 3358712095, 17627, 19097, [151, 104, 203, 143, 151, 139, 114, 49]);
+#[cfg(feature = "canvas")] define_guid!(IID_ID2D1GdiInteropRenderTarget, 3772469699, 28535, 19374, [179, 213, 228, 117, 9, 179, 88, 56]);
+#[cfg(feature = "canvas")] define_guid!(IID_IDWriteFactory, 3092901466, 55352, 19291, [162, 232, 26, 220, 125, 147, 219, 72]);
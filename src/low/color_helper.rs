@@ -0,0 +1,415 @@
+/*!
+    Low level control colors and theme functions.
+
+    The colors of a control are stored by handle. They are applied when the parent of the control receives
+    a `WM_CTLCOLOR*` message, because this is the only way to change the colors of most builtin controls.
+    Controls drawing themselves (treeview, listview, progress bar) and the calendar of the date pickers receive their colors directly.
+
+    The dark mode functions are not available on every version of Windows, so they are loaded at runtime.
+*/
+
+use std::ptr;
+use std::mem;
+use std::hash::Hash;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use winapi::{HWND, HBRUSH, HDC, UINT, WPARAM, LPARAM, LRESULT, COLORREF, BOOL, HRESULT, DWORD, HKEY, LONG, c_void};
+
+use ui::UiInner;
+use controls::{AnyHandle, ControlType};
+use theme::{Theme, Palette};
+use low::other_helper::load_function;
+
+type SetWindowThemeFn = unsafe extern "system" fn(HWND, *const u16, *const u16) -> HRESULT;
+type DwmSetWindowAttributeFn = unsafe extern "system" fn(HWND, DWORD, *const c_void, DWORD) -> HRESULT;
+type RegGetValueWFn = unsafe extern "system" fn(HKEY, *const u16, *const u16, DWORD, *mut DWORD, *mut c_void, *mut DWORD) -> LONG;
+
+const DWMWA_USE_IMMERSIVE_DARK_MODE: DWORD = 20;
+const DWMWA_USE_IMMERSIVE_DARK_MODE_OLD: DWORD = 19;
+const RRF_RT_REG_DWORD: DWORD = 0x0000_0010;
+const PERSONALIZE_KEY: &'static str = "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize";
+
+/**
+    The colors of a control. `brush` is a solid brush of the background color or null.
+*/
+struct WindowColors {
+    text: Option<(u8, u8, u8)>,
+    background: Option<(u8, u8, u8)>,
+    brush: HBRUSH
+}
+
+thread_local!(static COLORS: RefCell<HashMap<usize, WindowColors>> = RefCell::new(HashMap::new()));
+
+/**
+    The theme applied to a Ui.
+
+    Members:
+    • `theme`: The theme set by the user
+    • `dark`: If the dark palette is currently applied
+*/
+pub struct ThemeState {
+    pub theme: Theme,
+    pub dark: bool
+}
+
+#[inline(always)]
+fn rgb(c: (u8, u8, u8)) -> COLORREF {
+    (c.0 as COLORREF) | ((c.1 as COLORREF) << 8) | ((c.2 as COLORREF) << 16)
+}
+
+/**
+    Return the text color of a window or `None` if the default color is used
+*/
+pub fn get_window_text_color(hwnd: HWND) -> Option<(u8, u8, u8)> {
+    COLORS.with(|c| c.borrow().get(&(hwnd as usize)).and_then(|colors| colors.text))
+}
+
+/**
+    Return the background color of a window or `None` if the default color is used
+*/
+pub fn get_window_background_color(hwnd: HWND) -> Option<(u8, u8, u8)> {
+    COLORS.with(|c| c.borrow().get(&(hwnd as usize)).and_then(|colors| colors.background))
+}
+
+/**
+    Set the text color of a window. `None` restores the default color.
+*/
+pub unsafe fn set_window_text_color(hwnd: HWND, color: Option<(u8, u8, u8)>) {
+    let background = get_window_background_color(hwnd);
+    set_window_colors(hwnd, color, background);
+}
+
+/**
+    Set the background color of a window. `None` restores the default color.
+*/
+pub unsafe fn set_window_background_color(hwnd: HWND, color: Option<(u8, u8, u8)>) {
+    let text = get_window_text_color(hwnd);
+    set_window_colors(hwnd, text, color);
+}
+
+/**
+    Set both colors of a window and repaint it
+*/
+pub unsafe fn set_window_colors(hwnd: HWND, text: Option<(u8, u8, u8)>, background: Option<(u8, u8, u8)>) {
+    use user32::InvalidateRect;
+    use gdi32::CreateSolidBrush;
+
+    free_window_colors(hwnd);
+
+    if text.is_some() || background.is_some() {
+        let brush = match background {
+            Some(bg) => CreateSolidBrush(rgb(bg)),
+            None => ptr::null_mut()
+        };
+
+        let colors = WindowColors{ text: text, background: background, brush: brush };
+        COLORS.with(|c| c.borrow_mut().insert(hwnd as usize, colors));
+    }
+
+    set_native_colors(hwnd, text, background);
+    InvalidateRect(hwnd, ptr::null(), 1);
+}
+
+/**
+    Remove the colors of a window and free the background brush. Called when a control is unpacked.
+*/
+pub unsafe fn free_window_colors(hwnd: HWND) {
+    use gdi32::DeleteObject;
+
+    let colors = COLORS.with(|c| c.borrow_mut().remove(&(hwnd as usize)));
+    if let Some(colors) = colors {
+        if !colors.brush.is_null() {
+            DeleteObject(colors.brush as _);
+        }
+    }
+}
+
+/**
+    Return the brush used to paint the background of a window. Used by the NWG custom controls.
+*/
+pub unsafe fn background_brush(hwnd: HWND) -> HBRUSH {
+    use winapi::COLOR_WINDOW;
+
+    let brush = COLORS.with(|c| c.borrow().get(&(hwnd as usize)).map(|colors| colors.brush).unwrap_or(ptr::null_mut()));
+    if brush.is_null() {
+        mem::transmute(COLOR_WINDOW as usize)
+    } else {
+        brush
+    }
+}
+
+/**
+    Some controls do not send the `WM_CTLCOLOR*` messages. Their colors are set using their own messages.
+*/
+unsafe fn set_native_colors(hwnd: HWND, text: Option<(u8, u8, u8)>, background: Option<(u8, u8, u8)>) {
+    use user32::{SendMessageW, GetSysColor};
+    use winapi::{TVM_SETTEXTCOLOR, TVM_SETBKCOLOR, LVM_SETTEXTCOLOR, LVM_SETBKCOLOR, LVM_SETTEXTBKCOLOR,
+      DTM_SETMCCOLOR, MCSC_TEXT, MCSC_MONTHBK, MCSC_BACKGROUND, PBM_SETBARCOLOR, PBM_SETBKCOLOR, CLR_DEFAULT,
+      COLOR_WINDOW, COLOR_WINDOWTEXT};
+
+    const DEFAULT_TREE_COLOR: COLORREF = 0xFFFF_FFFF;
+
+    match window_class(hwnd).as_ref() {
+        "SysTreeView32" => {
+            let text = text.map(rgb).unwrap_or(DEFAULT_TREE_COLOR);
+            let bg = background.map(rgb).unwrap_or(DEFAULT_TREE_COLOR);
+            SendMessageW(hwnd, TVM_SETTEXTCOLOR, 0, text as LPARAM);
+            SendMessageW(hwnd, TVM_SETBKCOLOR, 0, bg as LPARAM);
+        },
+        "SysListView32" => {
+            let text = text.map(rgb).unwrap_or(GetSysColor(COLOR_WINDOWTEXT));
+            let bg = background.map(rgb).unwrap_or(GetSysColor(COLOR_WINDOW));
+            SendMessageW(hwnd, LVM_SETTEXTCOLOR, 0, text as LPARAM);
+            SendMessageW(hwnd, LVM_SETBKCOLOR, 0, bg as LPARAM);
+            SendMessageW(hwnd, LVM_SETTEXTBKCOLOR, 0, bg as LPARAM);
+        },
+        "SysDateTimePick32" => {
+            // The calendar of the date picker ignores the `WM_CTLCOLOR*` messages
+            let text = text.map(rgb).unwrap_or(GetSysColor(COLOR_WINDOWTEXT));
+            let bg = background.map(rgb).unwrap_or(GetSysColor(COLOR_WINDOW));
+            SendMessageW(hwnd, DTM_SETMCCOLOR, MCSC_TEXT, text as LPARAM);
+            SendMessageW(hwnd, DTM_SETMCCOLOR, MCSC_MONTHBK, bg as LPARAM);
+            SendMessageW(hwnd, DTM_SETMCCOLOR, MCSC_BACKGROUND, bg as LPARAM);
+        },
+        "msctls_progress32" => {
+            // The text color is the color of the bar. The visual styles ignore the custom colors of a progress bar.
+            let custom = text.is_some() || background.is_some();
+            set_visual_style(hwnd, if custom { Some("") } else { None });
+            SendMessageW(hwnd, PBM_SETBARCOLOR, 0, text.map(rgb).unwrap_or(CLR_DEFAULT) as LPARAM);
+            SendMessageW(hwnd, PBM_SETBKCOLOR, 0, background.map(rgb).unwrap_or(CLR_DEFAULT) as LPARAM);
+        },
+        _ => { /* The colors are applied in `process_colors` */ }
+    }
+}
+
+unsafe fn window_class(hwnd: HWND) -> String {
+    use user32::GetClassNameW;
+    use low::other_helper::from_utf16;
+
+    let mut buffer: [u16; 64] = [0; 64];
+    let length = GetClassNameW(hwnd, buffer.as_mut_ptr(), 64);
+    if length > 0 {
+        from_utf16(&buffer[0..(length as usize)])
+    } else {
+        String::new()
+    }
+}
+
+unsafe fn paint_colors(hdc: HDC, text: Option<(u8, u8, u8)>, background: Option<(u8, u8, u8)>) {
+    use gdi32::{SetTextColor, SetBkColor};
+
+    if let Some(text) = text { SetTextColor(hdc, rgb(text)); }
+    if let Some(bg) = background { SetBkColor(hdc, rgb(bg)); }
+}
+
+/**
+    Apply the custom colors of the children of a window. Returns `None` if the message was not handled.
+
+    The colors of a child that is not a Ui control (ex: the edit of a combobox or the label of a groupbox)
+    are the colors of the control that owns it.
+*/
+pub unsafe fn process_colors<ID: Hash+Clone>(inner: &UiInner<ID>, hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<LRESULT> {
+    use winapi::{WM_CTLCOLOREDIT, WM_CTLCOLORLISTBOX, WM_CTLCOLORBTN, WM_CTLCOLORSTATIC, WM_CTLCOLORSCROLLBAR,
+      WM_ERASEBKGND, RECT};
+    use user32::{GetParent, GetClientRect, FillRect};
+    use comctl32::DefSubclassProc;
+
+    match msg {
+        WM_CTLCOLOREDIT | WM_CTLCOLORLISTBOX | WM_CTLCOLORBTN | WM_CTLCOLORSTATIC | WM_CTLCOLORSCROLLBAR => {
+            let child = l as HWND;
+            let lookup = |h: HWND| COLORS.with(|c| c.borrow().get(&(h as usize)).map(|colors| (colors.text, colors.background, colors.brush)));
+
+            let mut colors = lookup(child);
+            if colors.is_none() && inner.inner_id_from_handle(&AnyHandle::HWND(child)).is_none() {
+                colors = lookup(GetParent(child)).or_else(|| lookup(hwnd));
+            }
+
+            match colors {
+                Some((text, background, brush)) => {
+                    let hdc = w as HDC;
+                    let result = match background.is_some() {
+                        true => brush as LRESULT,
+                        false => DefSubclassProc(hwnd, msg, w, l)
+                    };
+
+                    paint_colors(hdc, text, background);
+                    Some(result)
+                },
+                None => None
+            }
+        },
+        WM_ERASEBKGND => {
+            let brush = COLORS.with(|c| c.borrow().get(&(hwnd as usize)).map(|colors| colors.brush).unwrap_or(ptr::null_mut()));
+            if brush.is_null() {
+                None
+            } else {
+                let mut rect: RECT = mem::zeroed();
+                GetClientRect(hwnd, &mut rect);
+                FillRect(w as HDC, &rect, brush);
+                Some(1)
+            }
+        },
+        _ => None
+    }
+}
+
+/**
+    Read the "apps use light theme" user preference. Returns `false` if the preference is not available.
+*/
+pub unsafe fn system_prefers_dark() -> bool {
+    use winapi::{HKEY_CURRENT_USER, ERROR_SUCCESS};
+    use low::other_helper::to_utf16;
+
+    let f: RegGetValueWFn = match load_function("advapi32.dll", "RegGetValueW") {
+        Some(f) => mem::transmute(f),
+        None => { return false; }
+    };
+
+    let key = to_utf16(PERSONALIZE_KEY);
+    let value = to_utf16("AppsUseLightTheme");
+    let mut data: DWORD = 1;
+    let mut size = mem::size_of::<DWORD>() as DWORD;
+
+    let result = f(HKEY_CURRENT_USER, key.as_ptr(), value.as_ptr(), RRF_RT_REG_DWORD, ptr::null_mut(),
+      &mut data as *mut DWORD as *mut c_void, &mut size);
+
+    result == (ERROR_SUCCESS as LONG) && data == 0
+}
+
+/**
+    Check if a `WM_SETTINGCHANGE` message was sent because the user changed the light/dark preference
+*/
+pub unsafe fn is_color_preference_change(l: LPARAM) -> bool {
+    use low::other_helper::from_wide_ptr;
+
+    if l == 0 {
+        false
+    } else {
+        from_wide_ptr(l as *mut u16) == "ImmersiveColorSet"
+    }
+}
+
+/**
+    Use the dark or the light title bar on a top level window. Does nothing on systems without dark title bars.
+*/
+unsafe fn set_dark_frame(hwnd: HWND, dark: bool) {
+    use winapi::S_OK;
+
+    let f: DwmSetWindowAttributeFn = match load_function("dwmapi.dll", "DwmSetWindowAttribute") {
+        Some(f) => mem::transmute(f),
+        None => { return; }
+    };
+
+    let value: BOOL = dark as BOOL;
+    let value_ptr = &value as *const BOOL as *const c_void;
+    let size = mem::size_of::<BOOL>() as DWORD;
+    if f(hwnd, DWMWA_USE_IMMERSIVE_DARK_MODE, value_ptr, size) != S_OK {
+        f(hwnd, DWMWA_USE_IMMERSIVE_DARK_MODE_OLD, value_ptr, size);
+    }
+}
+
+/**
+    Set the visual style of a control. `None` restores the default visual style.
+*/
+unsafe fn set_visual_style(hwnd: HWND, style: Option<&str>) {
+    use low::other_helper::to_utf16;
+
+    let f: SetWindowThemeFn = match load_function("uxtheme.dll", "SetWindowTheme") {
+        Some(f) => mem::transmute(f),
+        None => { return; }
+    };
+
+    match style {
+        Some(style) => {
+            // An empty style disables the visual styles of the control
+            let disable = style.is_empty();
+            let style = to_utf16(style);
+            let empty = to_utf16("");
+            let id_list = if disable { empty.as_ptr() } else { ptr::null() };
+            f(hwnd, style.as_ptr(), id_list);
+        },
+        None => { f(hwnd, ptr::null(), ptr::null()); }
+    }
+}
+
+/**
+    Apply a palette to a control. If `palette` is `None`, the default colors and visual styles are restored.
+*/
+pub unsafe fn apply_control_theme(hwnd: HWND, ctype: ControlType, palette: Option<&Palette>, dark: bool) {
+    let (text, background) = match palette {
+        Some(p) => match ctype {
+            ControlType::Window | ControlType::Frame | ControlType::Tab | ControlType::ImageFrame => (None, Some(p.background)),
+            ControlType::Label | ControlType::Button | ControlType::CheckBox | ControlType::RadioButton |
+            ControlType::GroupBox => (Some(p.text), Some(p.background)),
+            ControlType::TextInput | ControlType::TextBox | ControlType::ListBox | ControlType::ComboBox |
+            ControlType::TreeView | ControlType::ListView => (Some(p.input_text), Some(p.input_background)),
+            _ => { return; }
+        },
+        None => match ctype {
+            ControlType::Window | ControlType::Frame | ControlType::Tab | ControlType::ImageFrame | ControlType::Label |
+            ControlType::Button | ControlType::CheckBox | ControlType::RadioButton | ControlType::GroupBox |
+            ControlType::TextInput | ControlType::TextBox | ControlType::ListBox | ControlType::ComboBox |
+            ControlType::TreeView | ControlType::ListView => (None, None),
+            _ => { return; }
+        }
+    };
+
+    // The builtin visual styles ignore the custom colors. The dark variants are used instead when they exist.
+    // Checkboxes and radiobuttons do not have a dark variant, so they are drawn without visual styles.
+    let dark = dark && palette.is_some();
+    let style = match ctype {
+        ControlType::Window => { set_dark_frame(hwnd, dark); None },
+        ControlType::Button | ControlType::ListBox | ControlType::TextBox | ControlType::TreeView |
+        ControlType::ListView => Some("DarkMode_Explorer"),
+        ControlType::ComboBox | ControlType::TextInput => Some("DarkMode_CFD"),
+        ControlType::CheckBox | ControlType::RadioButton => Some(""),
+        _ => None
+    };
+
+    if let Some(style) = style {
+        set_visual_style(hwnd, if dark { Some(style) } else { None });
+    }
+
+    set_window_colors(hwnd, text, background);
+}
+
+/**
+    Apply the theme of a Ui to every control of the Ui. If the Ui has no theme, the default colors are restored.
+*/
+pub unsafe fn apply_theme<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    let palette = inner.theme.as_ref().map(|t| if t.dark { t.theme.dark } else { t.theme.light });
+    let dark = inner.theme.as_ref().map(|t| t.dark).unwrap_or(false);
+
+    for control in inner.controls.values() {
+        if let Ok(control) = control.try_borrow() {
+            if let AnyHandle::HWND(h) = control.handle() {
+                apply_control_theme(h, control.control_type(), palette.as_ref(), dark);
+            }
+        }
+    }
+}
+
+/**
+    Called when a top level window receives `WM_SETTINGCHANGE`. If the Ui theme follows the system preference
+    and the preference changed, the theme is applied again.
+*/
+pub unsafe fn process_setting_change<ID: Hash+Clone>(inner: &mut UiInner<ID>, l: LPARAM) {
+    let changed = match inner.theme.as_mut() {
+        Some(state) => {
+            if state.theme.follows_system() && is_color_preference_change(l) {
+                let dark = system_prefers_dark();
+                let changed = dark != state.dark;
+                state.dark = dark;
+                changed
+            } else {
+                false
+            }
+        },
+        None => false
+    };
+
+    if changed {
+        apply_theme(inner);
+    }
+}
@@ -8,9 +8,9 @@ use std::hash::Hash;
 use std::any::{Any, TypeId};
 
 use winapi::{UINT, LRESULT, DWORD, HBRUSH, ULONG_PTR, HMENU, BOOL, WORD, MENUITEMINFOW, IShellItem, HRESULT, IUnknownVtbl,
- IUnknown, PCWSTR, IBindCtx, REFIID, c_void, c_int, HWND};
+ IUnknown, PCWSTR, IBindCtx, REFIID, c_void, c_int, HWND, LONG, HGLOBAL, HDROP, GUID, VARIANT, LPCWSTR, COLORREF, BYTE};
 
-#[cfg(feature = "canvas")] use winapi::{D2D1_FACTORY_TYPE, D2D1_FACTORY_OPTIONS, ID2D1Factory};
+#[cfg(feature = "canvas")] use winapi::{D2D1_FACTORY_TYPE, D2D1_FACTORY_OPTIONS, ID2D1Factory, DWRITE_FACTORY_TYPE};
 
 use std::ops::{Deref, DerefMut};
 
@@ -32,6 +32,25 @@ pub const NWG_TRIGGER:           UINT = 0x406;  /// Message sent when triggering
 pub const NWG_CUSTOM_MAX:        UINT = 0x407;  /// Maximum custom event value
 
 pub const NWG_DESTROY:           UINT = 0x420;  /// NWG `Destroy` event identifier
+pub const NWG_FILES_DRAG_ENTER:  UINT = 0x421;  /// Message sent when files are dragged over a control that accept files
+pub const NWG_FILES_DRAG_LEAVE:  UINT = 0x422;  /// Message sent when dragged files leave a control that accept files
+pub const NWG_FILES_DROPPED:     UINT = 0x423;  /// Message sent when files are dropped on a control that accept files
+pub const NWG_DRAG_ENTER:        UINT = 0x424;  /// NWG `DragEnter` event identifier
+pub const NWG_DRAG_OVER:         UINT = 0x425;  /// NWG `DragOver` event identifier
+pub const NWG_DRAG_LEAVE:        UINT = 0x426;  /// NWG `DragLeave` event identifier
+pub const NWG_DRAG_DROP:         UINT = 0x427;  /// NWG `DragDrop` event identifier
+pub const NWG_DRAG_END:          UINT = 0x428;  /// NWG `DragEnd` event identifier
+pub const NWG_ZOOM_CHANGED:      UINT = 0x429;  /// Message sent when the view of an image frame changes
+pub const NWG_NODE_MOUSE_DOWN:   UINT = 0x42A;  /// Message sent when a mouse button is pressed over a canvas scene node
+pub const NWG_NODE_MOUSE_UP:     UINT = 0x42B;  /// Message sent when a mouse button is released over a canvas scene node
+pub const NWG_NODE_CLICK:        UINT = 0x42C;  /// Message sent when a canvas scene node is clicked
+pub const NWG_NODE_ENTER:        UINT = 0x42D;  /// Message sent when the mouse enters a canvas scene node
+pub const NWG_NODE_LEAVE:        UINT = 0x42E;  /// Message sent when the mouse leaves a canvas scene node
+pub const NWG_CANVAS_FRAME:      UINT = 0x42F;  /// Message sent when an animated canvas starts a frame
+pub const NWG_TWEEN_COMPLETED:   UINT = 0x430;  /// Message sent when an animator finished playing its tween
+pub const NWG_IDLE:              UINT = 0x431;  /// Message sent to the message window of a ui when the message queue of its thread drains
+pub const NWG_TASK_WAKE:         UINT = 0x432;  /// Message posted to the message window of a ui by the waker of a task. The wparam is the task id
+pub const NWG_CANVAS_NEXT_FRAME: UINT = 0x433;  /// Message posted to an animated canvas to paint its next frame once the current paint is done
 
 // Value returned by a window proc if the message execution failed/succeeded
 
@@ -44,6 +63,7 @@ pub const MIM_STYLE: DWORD = 0x00000010;
 
 pub const MIIM_STATE: DWORD = 0x00000001;
 pub const MIIM_ID: DWORD = 0x00000002;
+pub const MIIM_STRING: DWORD = 0x00000040;
 
 pub const MNS_NOTIFYBYPOS: DWORD = 0x08000000;
 
@@ -53,6 +73,8 @@ pub const MFS_ENABLED: DWORD = 0x00000000;
 pub const MF_BYPOSITION: UINT = 0x00000400;
 pub const MF_SEPARATOR: UINT = 0x00000800;
 
+pub const GA_ROOT: UINT = 2;
+
 pub const ACTCTX_FLAG_RESOURCE_NAME_VALID: u32 = 0x008;
 pub const ACTCTX_FLAG_SET_PROCESS_DEFAULT: u32 = 0x010;
 pub const ACTCTX_FLAG_ASSEMBLY_DIRECTORY_VALID: u32 = 0x004;
@@ -104,6 +126,7 @@ pub const SS_BITMAP: UINT = 14;
 pub const SS_CENTERIMAGE: UINT = 512;
 pub const SS_ICON: UINT = 3;
 pub const SS_REALSIZEIMAGE: UINT = 0x800;
+pub const SS_TYPEMASK: UINT = 0x1F;
 
 pub const STM_SETIMAGE: UINT = 370;
 pub const STM_GETIMAGE: UINT = 371;
@@ -142,6 +165,9 @@ pub const EN_MAXTEXT: WORD = 1281;
 pub const STN_CLICKED: WORD = 0;
 pub const STN_DBLCLK: WORD = 1;
 
+pub const AC_SRC_OVER: u8 = 0;
+pub const AC_SRC_ALPHA: u8 = 1;
+
 pub const IDABORT: i32 = 3;
 pub const IDCANCEL: i32 = 2;
 pub const IDCONTINUE: i32 = 11;
@@ -168,6 +194,14 @@ pub const TPM_TOPALIGN: u32 = 0x0000;
 pub const TPM_VCENTERALIGN: u32 = 0x0010;
 pub const TPM_BOTTOMALIGN: u32 = 0x0020;
 
+pub const DROPEFFECT_NONE: DWORD = 0;
+pub const DROPEFFECT_COPY: DWORD = 1;
+pub const DROPEFFECT_MOVE: DWORD = 2;
+pub const DROPEFFECT_LINK: DWORD = 4;
+
+pub const DVASPECT_CONTENT: DWORD = 1;
+pub const TYMED_HGLOBAL: DWORD = 1;
+
 // System structs
 #[repr(C)]
 #[allow(non_snake_case)]
@@ -181,15 +215,22 @@ pub struct MENUINFO {
     pub dwMenuData: ULONG_PTR
 }
 
-#[repr(packed)]
+#[repr(C)]
 #[allow(non_snake_case)]
-#[derive(Debug)]
-pub struct BITMAPFILEHEADER {
-    pub bfType: WORD,
-    pub bfSize: DWORD,
-    pub bfReserved1: WORD,
-    pub bfReserved2: WORD,
-    pub bfOffBits: DWORD
+pub struct FORMATETC {
+    pub cfFormat: WORD,
+    pub ptd: *mut c_void,
+    pub dwAspect: DWORD,
+    pub lindex: LONG,
+    pub tymed: DWORD
+}
+
+#[repr(C)]
+#[allow(non_snake_case)]
+pub struct STGMEDIUM {
+    pub tymed: DWORD,
+    pub hGlobal: HGLOBAL,  // Union of handles. Only HGLOBAL is used by NWG.
+    pub pUnkForRelease: *mut IUnknown
 }
 
 // COM interfaces
@@ -291,6 +332,32 @@ interface IShellItemArray(IShellItemArrayVtbl): IUnknown(IUnknownVtbl) {
 }
 );
 
+RIDL!(
+interface IDataObject(IDataObjectVtbl): IUnknown(IUnknownVtbl) {
+    fn GetData(&mut self, pformatetcIn: *const FORMATETC, pmedium: *mut STGMEDIUM) -> HRESULT,
+    fn GetDataHere(&mut self) -> (),
+    fn QueryGetData(&mut self, pformatetc: *const FORMATETC) -> HRESULT,
+    fn GetCanonicalFormatEtc(&mut self) -> (),
+    fn SetData(&mut self) -> (),
+    fn EnumFormatEtc(&mut self) -> (),
+    fn DAdvise(&mut self) -> (),
+    fn DUnadvise(&mut self) -> (),
+    fn EnumDAdvise(&mut self) -> ()
+}
+);
+
+RIDL!(
+interface IAccPropServices(IAccPropServicesVtbl): IUnknown(IUnknownVtbl) {
+    fn SetPropValue(&mut self) -> (),
+    fn SetPropServer(&mut self) -> (),
+    fn ClearProps(&mut self) -> (),
+    fn SetHwndProp(&mut self, hwnd: HWND, idObject: DWORD, idChild: DWORD, idProp: GUID, var: VARIANT) -> HRESULT,
+    fn SetHwndPropStr(&mut self, hwnd: HWND, idObject: DWORD, idChild: DWORD, idProp: GUID, str: LPCWSTR) -> HRESULT,
+    fn SetHwndPropServer(&mut self) -> (),
+    fn ClearHwndProps(&mut self, hwnd: HWND, idObject: DWORD, idChild: DWORD, paProps: *const GUID, cProps: c_int) -> HRESULT
+}
+);
+
 // System extern
 extern "system" {
     pub fn GetMenuItemCount(menu: HMENU) -> c_int;
@@ -303,6 +370,14 @@ extern "system" {
     pub fn TrackPopupMenuEx(hmenu: HMENU, fuFlags: UINT, x: c_int, y: c_int, hwnd: HWND, lptpm: *mut c_void) -> BOOL;
 
     pub fn SHCreateItemFromParsingName(pszPath: PCWSTR, pbc: *mut IBindCtx, riid: REFIID, ppv: *mut *mut c_void) -> HRESULT;
+    pub fn DragQueryFileW(hDrop: HDROP, iFile: UINT, lpszFile: *mut u16, cch: UINT) -> UINT;
+
+    pub fn OleInitialize(pvReserved: *mut c_void) -> HRESULT;
+    pub fn RegisterDragDrop(hwnd: HWND, pDropTarget: *mut c_void) -> HRESULT;
+    pub fn RevokeDragDrop(hwnd: HWND) -> HRESULT;
+    pub fn ReleaseStgMedium(pmedium: *mut STGMEDIUM);
+
+    pub fn GetLayeredWindowAttributes(hwnd: HWND, pcrKey: *mut COLORREF, pbAlpha: *mut BYTE, pdwFlags: *mut DWORD) -> BOOL;
 
     #[cfg(feature = "canvas")]
     pub fn D2D1CreateFactory(
@@ -311,6 +386,13 @@ extern "system" {
 		pFactoryOptions: *const D2D1_FACTORY_OPTIONS,
         ppIFactory: *mut *mut ID2D1Factory
     ) -> HRESULT;
+
+    #[cfg(feature = "canvas")]
+    pub fn DWriteCreateFactory(
+        factoryType: DWRITE_FACTORY_TYPE,
+        iid: REFIID,
+        factory: *mut *mut IUnknown
+    ) -> HRESULT;
 }
 
 // Arguments passed to the NWG custom events 
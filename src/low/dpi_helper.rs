@@ -0,0 +1,330 @@
+/*!
+    Low level high DPI functions.
+
+    The per-monitor DPI functions are not available on every version of Windows, so they are loaded at runtime.
+*/
+
+use std::ptr;
+use std::mem;
+use std::hash::Hash;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use winapi::{HWND, HFONT, UINT, BOOL, HRESULT, HMONITOR, WPARAM, LPARAM, LOWORD, c_int};
+
+use ui::UiInner;
+use controls::AnyHandle;
+use dpi::{DEFAULT_DPI, rescale};
+use low::other_helper::load_function;
+
+static DPI_AWARE: AtomicBool = AtomicBool::new(false);
+
+// The DPI functions called each time a control is moved or resized. 0 if they were not loaded yet, 1 if they do not exist
+static GET_DPI_FOR_WINDOW: AtomicUsize = AtomicUsize::new(0);
+static GET_DPI_FOR_MONITOR: AtomicUsize = AtomicUsize::new(0);
+
+type SetProcessDpiAwarenessContextFn = unsafe extern "system" fn(isize) -> BOOL;
+type SetProcessDpiAwarenessFn = unsafe extern "system" fn(c_int) -> HRESULT;
+type SetProcessDPIAwareFn = unsafe extern "system" fn() -> BOOL;
+type GetDpiForWindowFn = unsafe extern "system" fn(HWND) -> UINT;
+type GetDpiForMonitorFn = unsafe extern "system" fn(HMONITOR, c_int, *mut UINT, *mut UINT) -> HRESULT;
+
+const DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2: isize = -4;
+const PROCESS_PER_MONITOR_DPI_AWARE: c_int = 2;
+const MDT_EFFECTIVE_DPI: c_int = 0;
+
+/**
+    Font variants created when controls are moved to a monitor with a different DPI than the system DPI.
+
+    Members:
+    • `variants`: Map a (base font, dpi) pair to a scaled font
+    • `bases`: Map a scaled font to its base font
+    • `windows`: The last known DPI of the top level windows
+*/
+pub struct DpiState {
+    pub variants: HashMap<(usize, u32), HFONT>,
+    pub bases: HashMap<usize, usize>,
+    pub windows: HashMap<usize, u32>
+}
+
+impl DpiState {
+
+    pub fn new() -> DpiState {
+        DpiState { variants: HashMap::new(), bases: HashMap::new(), windows: HashMap::new() }
+    }
+
+    /**
+        Return the base font of a font handle. If the font is not a scaled variant, the handle is returned as is.
+    */
+    pub fn base_font(&self, font: HFONT) -> HFONT {
+        match self.bases.get(&(font as usize)) {
+            Some(base) => *base as HFONT,
+            None => font
+        }
+    }
+
+    /**
+        Return the variant of a base font for a DPI. The base font is expected to be created for the system DPI.
+        Returns `None` if the variant could not be created.
+    */
+    pub unsafe fn font_for_dpi(&mut self, base: HFONT, dpi: u32) -> Option<HFONT> {
+        use gdi32::{GetObjectW, CreateFontIndirectW};
+        use winapi::LOGFONTW;
+
+        let sys_dpi = system_dpi();
+        if dpi == sys_dpi { return Some(base); }
+
+        let key = (base as usize, dpi);
+        if let Some(font) = self.variants.get(&key) {
+            return Some(*font);
+        }
+
+        let mut info: LOGFONTW = mem::zeroed();
+        if GetObjectW(base as _, mem::size_of::<LOGFONTW>() as c_int, &mut info as *mut LOGFONTW as *mut _) == 0 {
+            return None;
+        }
+
+        info.lfHeight = rescale(info.lfHeight, sys_dpi, dpi);
+        info.lfWidth = rescale(info.lfWidth, sys_dpi, dpi);
+
+        let font = CreateFontIndirectW(&info);
+        if font.is_null() {
+            None
+        } else {
+            self.variants.insert(key, font);
+            self.bases.insert(font as usize, base as usize);
+            Some(font)
+        }
+    }
+
+    /**
+        Destroy the variants of a base font. Called when a font resource is freed.
+    */
+    pub unsafe fn free_font(&mut self, base: HFONT) {
+        use gdi32::DeleteObject;
+
+        let keys: Vec<(usize, u32)> = self.variants.keys().filter(|k| k.0 == base as usize).map(|k| *k).collect();
+        for key in keys {
+            let font = self.variants.remove(&key).unwrap();
+            self.bases.remove(&(font as usize));
+            DeleteObject(font as _);
+        }
+    }
+
+}
+
+/**
+    Declare the process DPI aware. The most recent awareness mode available on the system is used.
+*/
+pub unsafe fn enable_dpi_awareness() -> bool {
+    use winapi::{S_OK, E_ACCESSDENIED};
+
+    if let Some(f) = load_function("user32.dll", "SetProcessDpiAwarenessContext") {
+        let f: SetProcessDpiAwarenessContextFn = mem::transmute(f);
+        if f(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) != 0 {
+            DPI_AWARE.store(true, Ordering::SeqCst);
+            return true;
+        }
+    }
+
+    if let Some(f) = load_function("shcore.dll", "SetProcessDpiAwareness") {
+        let f: SetProcessDpiAwarenessFn = mem::transmute(f);
+        let result = f(PROCESS_PER_MONITOR_DPI_AWARE);
+        if result == S_OK || result == E_ACCESSDENIED {
+            DPI_AWARE.store(true, Ordering::SeqCst);
+            return true;
+        }
+    }
+
+    // Vista fallback. Only the system DPI is supported.
+    if let Some(f) = load_function("user32.dll", "SetProcessDPIAware") {
+        let f: SetProcessDPIAwareFn = mem::transmute(f);
+        if f() != 0 {
+            DPI_AWARE.store(true, Ordering::SeqCst);
+            return true;
+        }
+    }
+
+    false
+}
+
+/**
+    Check if the process was declared DPI aware by NWG
+*/
+#[inline(always)]
+pub fn dpi_aware() -> bool {
+    DPI_AWARE.load(Ordering::SeqCst)
+}
+
+/**
+    Return the DPI of the primary monitor
+*/
+pub unsafe fn system_dpi() -> u32 {
+    use user32::{GetDC, ReleaseDC};
+    use gdi32::GetDeviceCaps;
+    use winapi::LOGPIXELSX;
+
+    let dc = GetDC(ptr::null_mut());
+    if dc.is_null() { return DEFAULT_DPI; }
+
+    let dpi = GetDeviceCaps(dc, LOGPIXELSX);
+    ReleaseDC(ptr::null_mut(), dc);
+
+    if dpi > 0 { dpi as u32 } else { DEFAULT_DPI }
+}
+
+/**
+    Return the DPI of a window. If the system do not support per-monitor DPI, return the system DPI.
+*/
+pub unsafe fn window_dpi(hwnd: HWND) -> u32 {
+    if let Some(f) = cached_function(&GET_DPI_FOR_WINDOW, "user32.dll", "GetDpiForWindow") {
+        let f: GetDpiForWindowFn = mem::transmute(f);
+        let dpi = f(hwnd);
+        if dpi > 0 { return dpi as u32; }
+    }
+
+    system_dpi()
+}
+
+/**
+    Return the DPI of the monitor nearest to a point in screen coordinates.
+    If the system do not support per-monitor DPI, return the system DPI.
+*/
+pub unsafe fn point_dpi(x: i32, y: i32) -> u32 {
+    use user32::MonitorFromPoint;
+    use winapi::{POINT, MONITOR_DEFAULTTONEAREST, S_OK};
+
+    if let Some(f) = cached_function(&GET_DPI_FOR_MONITOR, "shcore.dll", "GetDpiForMonitor") {
+        let f: GetDpiForMonitorFn = mem::transmute(f);
+        let monitor = MonitorFromPoint(POINT{x: x, y: y}, MONITOR_DEFAULTTONEAREST);
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        if f(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) == S_OK && dpi_x > 0 {
+            return dpi_x as u32;
+        }
+    }
+
+    system_dpi()
+}
+
+/**
+    Load a function the first time it is called and return the saved address after that
+*/
+unsafe fn cached_function(cache: &AtomicUsize, lib: &str, name: &str) -> Option<usize> {
+    let function = match cache.load(Ordering::Relaxed) {
+        0 => {
+            let f = load_function(lib, name).unwrap_or(1);
+            cache.store(f, Ordering::Relaxed);
+            f
+        },
+        f => f
+    };
+
+    if function == 1 { None } else { Some(function) }
+}
+
+/**
+    Return the DPI used to convert the logical units of a window. Returns `DEFAULT_DPI` if the process is not DPI aware
+    because the system already scales the windows.
+*/
+#[inline(always)]
+pub unsafe fn logical_dpi(hwnd: HWND) -> u32 {
+    if dpi_aware() { window_dpi(hwnd) } else { DEFAULT_DPI }
+}
+
+/**
+    Called when a control is packed in a Ui. Save the DPI of the top level windows and,
+    if the control was created on a monitor that do not use the system DPI, swap its font with a scaled variant.
+*/
+pub unsafe fn init_window_dpi<ID: Hash+Clone>(inner: &mut UiInner<ID>, hwnd: HWND) {
+    use user32::GetParent;
+
+    if !dpi_aware() { return; }
+
+    let dpi = window_dpi(hwnd);
+    if GetParent(hwnd).is_null() {
+        inner.dpi.windows.insert(hwnd as usize, dpi);
+    }
+
+    scale_window_font(inner, hwnd, dpi);
+}
+
+/**
+    Handle a `WM_DPICHANGED` message. The window is moved to the rectangle suggested by the system,
+    then the children controls and their fonts are scaled to the new DPI.
+*/
+pub unsafe fn process_dpi_changed<ID: Hash+Clone>(inner: &mut UiInner<ID>, hwnd: HWND, w: WPARAM, l: LPARAM) {
+    use user32::{SetWindowPos, EnumChildWindows};
+    use winapi::{RECT, SWP_NOZORDER, SWP_NOACTIVATE};
+
+    if !dpi_aware() { return; }
+
+    let new_dpi = LOWORD(w as u32) as u32;
+    let old_dpi = inner.dpi.windows.get(&(hwnd as usize)).map(|d| *d).unwrap_or(system_dpi());
+    inner.dpi.windows.insert(hwnd as usize, new_dpi);
+    if new_dpi == old_dpi { return; }
+
+    let r: &RECT = mem::transmute(l);
+    SetWindowPos(hwnd, ptr::null_mut(), r.left, r.top, r.right-r.left, r.bottom-r.top, SWP_NOZORDER|SWP_NOACTIVATE);
+
+    let mut children: Vec<HWND> = Vec::new();
+    EnumChildWindows(hwnd, Some(collect_children), mem::transmute(&mut children));
+
+    for child in children {
+        // Only the controls managed by the ui are scaled. Internal windows are managed by their parent.
+        if inner.inner_id_from_handle(&AnyHandle::HWND(child)).is_none() { continue; }
+
+        scale_child(child, old_dpi, new_dpi);
+        scale_window_font(inner, child, new_dpi);
+    }
+
+    scale_window_font(inner, hwnd, new_dpi);
+}
+
+/**
+    Scale the position and the size of a child window from a DPI to another
+*/
+unsafe fn scale_child(hwnd: HWND, old_dpi: u32, new_dpi: u32) {
+    use user32::{GetWindowRect, GetParent, ScreenToClient, SetWindowPos};
+    use winapi::{RECT, POINT, SWP_NOZORDER, SWP_NOACTIVATE};
+
+    let mut r: RECT = mem::zeroed();
+    GetWindowRect(hwnd, &mut r);
+
+    let mut pt = POINT{x: r.left, y: r.top};
+    ScreenToClient(GetParent(hwnd), &mut pt);
+
+    let x = rescale(pt.x, old_dpi, new_dpi);
+    let y = rescale(pt.y, old_dpi, new_dpi);
+    let w = rescale(r.right - r.left, old_dpi, new_dpi);
+    let h = rescale(r.bottom - r.top, old_dpi, new_dpi);
+
+    SetWindowPos(hwnd, ptr::null_mut(), x, y, w, h, SWP_NOZORDER|SWP_NOACTIVATE);
+}
+
+/**
+    Replace the font of a window by the variant of its base font for a DPI
+*/
+unsafe fn scale_window_font<ID: Hash+Clone>(inner: &mut UiInner<ID>, hwnd: HWND, dpi: u32) {
+    use user32::SendMessageW;
+    use winapi::WM_GETFONT;
+    use low::window_helper::set_window_font_raw;
+
+    let font = SendMessageW(hwnd, WM_GETFONT, 0, 0) as HFONT;
+    if font.is_null() { return; }
+
+    // Only the fonts created by the ui are scaled. Stock fonts cannot be scaled.
+    let base = inner.dpi.base_font(font);
+    if inner.inner_id_from_handle(&AnyHandle::HFONT(base)).is_none() { return; }
+
+    if let Some(scaled) = inner.dpi.font_for_dpi(base, dpi) {
+        if scaled != font {
+            set_window_font_raw(hwnd, Some(scaled), true);
+        }
+    }
+}
+
+unsafe extern "system" fn collect_children(handle: HWND, params: LPARAM) -> BOOL {
+    let children: &mut Vec<HWND> = mem::transmute(params);
+    children.push(handle);
+    1
+}
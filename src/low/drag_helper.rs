@@ -0,0 +1,460 @@
+/*!
+    In-app drag and drop manager.
+
+    A drag operation captures the mouse on the source control. While the operation runs, the mouse messages
+    of the source are consumed by `process_drag` and translated into drag events on the registered drop targets.
+*/
+
+use std::ptr;
+use std::mem;
+use std::any::Any;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::hash::Hash;
+
+use winapi::{HWND, UINT, WPARAM, LPARAM, LRESULT, POINT, HIMAGELIST};
+
+use ui::UiInner;
+use events::{EventArgs, DragData, DragEnter, DragOver, DragLeave, DragDrop, DragEnd};
+use controls::AnyHandle;
+use defs::{DROP_EFFECT_NONE, DROP_EFFECT_COPY, DROP_EFFECT_MOVE, DROP_EFFECT_LINK};
+use error::Error;
+
+/**
+    State of the drag operation currently running in a Ui
+*/
+pub struct DragState {
+    pub source: u64,
+    pub source_hwnd: HWND,
+    pub payload: Rc<Box<Any>>,
+    pub allowed: u32,
+    pub target: Option<(u64, HWND, u32)>,
+    pub effect: Rc<Cell<u32>>,
+    pub image: HIMAGELIST
+}
+
+/**
+    Start a drag operation from a window-like control.
+
+    Arguments:
+    • `inner`: The ui that will manage the drag operation
+    • `source`: The inner id of the source control
+    • `payload`: The dragged data
+    • `allowed`: The effects allowed by the source
+    • `image`: An optional image resource handle (bitmap or icon) to display under the cursor
+*/
+pub unsafe fn begin_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>, source: u64, payload: Box<Any>, allowed: u32, image: Option<AnyHandle>) -> Result<(), Error> {
+    use user32::{SetCapture, GetCursorPos};
+    use comctl32::{ImageList_BeginDrag, ImageList_DragEnter};
+
+    if inner.drag.is_some() {
+        return Err(Error::UserError("A drag operation is already running".to_string()));
+    }
+
+    let source_hwnd = match inner.handle_of(source) {
+        Ok(AnyHandle::HWND(h)) => h,
+        Ok(h) => { return Err(Error::BadResource(format!("Window-like control required got {}", h.human_name()))); },
+        Err(e) => { return Err(e); }
+    };
+
+    let image = match image {
+        Some(h) => match build_drag_image(&h) {
+            Ok(il) => il,
+            Err(e) => { return Err(e); }
+        },
+        None => ptr::null_mut()
+    };
+
+    inner.drag = Some(DragState {
+        source: source,
+        source_hwnd: source_hwnd,
+        payload: Rc::new(payload),
+        allowed: allowed,
+        target: None,
+        effect: Rc::new(Cell::new(DROP_EFFECT_NONE)),
+        image: image
+    });
+
+    if !image.is_null() {
+        let mut pt = POINT{x: 0, y: 0};
+        GetCursorPos(&mut pt);
+        ImageList_BeginDrag(image, 0, 0, 0);
+        ImageList_DragEnter(ptr::null_mut(), pt.x, pt.y);
+    }
+
+    SetCapture(source_hwnd);
+
+    Ok(())
+}
+
+/**
+    Cancel the running drag operation. The current target receives a `DragLeave` event and
+    the source receives a `DragEnd` event with `DROP_EFFECT_NONE`.
+*/
+pub unsafe fn cancel_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    leave_target(inner);
+    end_drag(inner, DROP_EFFECT_NONE);
+}
+
+/**
+    Called when a drop target is unregistered. If it is the current target of the running drag operation,
+    it receives a `DragLeave` event and the operation continues without target.
+*/
+pub unsafe fn leave_drop_target<ID: Hash+Clone>(inner: &mut UiInner<ID>, target: u64) {
+    let current = match inner.drag.as_ref() {
+        Some(state) => state.target.map(|(t, _, _)| t == target).unwrap_or(false),
+        None => false
+    };
+
+    if current {
+        leave_target(inner);
+    }
+}
+
+/**
+    Stop the running drag operation without raising any events. Used when the source or the target gets destroyed.
+*/
+pub unsafe fn abort_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    if let Some(state) = inner.drag.take() {
+        free_drag(state);
+    }
+}
+
+/**
+    Process the messages of a control while a drag operation is running.
+    Returns `Some(result)` if the message was consumed by the drag manager.
+*/
+pub unsafe fn process_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<LRESULT> {
+    use winapi::{WM_MOUSEMOVE, WM_LBUTTONUP, WM_LBUTTONDOWN, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_KEYDOWN, WM_CAPTURECHANGED, VK_ESCAPE};
+
+    let source_hwnd = match inner.drag.as_ref() {
+        Some(state) => state.source_hwnd,
+        None => { return None; }
+    };
+
+    // Escape cancel the operation, no matter which control has the keyboard focus
+    if msg == WM_KEYDOWN && w == VK_ESCAPE as WPARAM {
+        cancel_drag(inner);
+        return Some(0);
+    }
+
+    if hwnd != source_hwnd {
+        return None;
+    }
+
+    match msg {
+        WM_MOUSEMOVE => {
+            update_drag(inner, w);
+            Some(0)
+        },
+        WM_LBUTTONUP => {
+            update_drag(inner, w);
+            drop_payload(inner);
+            Some(0)
+        },
+        WM_RBUTTONDOWN => {
+            cancel_drag(inner);
+            Some(0)
+        },
+        WM_LBUTTONDOWN | WM_RBUTTONUP => Some(0),
+        WM_CAPTURECHANGED => {
+            // Another window stole the mouse capture
+            if (l as HWND) != source_hwnd {
+                cancel_drag(inner);
+            }
+            None
+        },
+        _ => None
+    }
+}
+
+/**
+    Find the registered drop target under the cursor and dispatch the `DragEnter`, `DragLeave` and `DragOver` events.
+*/
+unsafe fn update_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>, keys: WPARAM) {
+    use user32::{GetCursorPos, SetCursor, LoadCursorW};
+    use comctl32::{ImageList_DragMove, ImageList_DragShowNolock};
+    use winapi::{IDC_ARROW, IDC_NO, MK_CONTROL, MK_SHIFT, TRUE, FALSE};
+
+    let mut pt = POINT{x: 0, y: 0};
+    GetCursorPos(&mut pt);
+
+    let (image, current) = match inner.drag.as_ref() {
+        Some(state) => (state.image, state.target.map(|(id, _, _)| id)),
+        None => { return; }
+    };
+
+    // Hide the drag image while the callbacks update the controls
+    if !image.is_null() { ImageList_DragShowNolock(FALSE); }
+
+    let target = find_target(inner, pt);
+    let ctrl = (keys & MK_CONTROL) == MK_CONTROL;
+    let shift = (keys & MK_SHIFT) == MK_SHIFT;
+
+    if target.map(|(id, _, _)| id) != current {
+        leave_target(inner);
+
+        if let Some((id, hwnd, accepted)) = target {
+            if let Some(data) = enter_target(inner, (id, hwnd, accepted), ctrl, shift, pt) {
+                inner.trigger(id, DragEnter, EventArgs::Drag(data));
+            }
+        }
+    } else if let Some((id, hwnd, _)) = target {
+        if let Some(data) = drag_data(inner, hwnd, pt) {
+            data.effect.set(default_effect(data.allowed, ctrl, shift));
+            inner.trigger(id, DragOver, EventArgs::Drag(data));
+        }
+    }
+
+    // The callbacks may have ended the drag operation
+    let effect = match inner.drag.as_ref() {
+        Some(state) => if state.target.is_some() { state.effect.get() } else { DROP_EFFECT_NONE },
+        None => { return; }
+    };
+
+    let cursor = if effect == DROP_EFFECT_NONE { IDC_NO } else { IDC_ARROW };
+    SetCursor(LoadCursorW(ptr::null_mut(), cursor));
+
+    if !image.is_null() {
+        ImageList_DragShowNolock(TRUE);
+        ImageList_DragMove(pt.x, pt.y);
+    }
+}
+
+/**
+    Set the new drop target of the operation and compute the default effect. Returns the data to send with `DragEnter`.
+*/
+unsafe fn enter_target<ID: Hash+Clone>(inner: &mut UiInner<ID>, target: (u64, HWND, u32), ctrl: bool, shift: bool, pt: POINT) -> Option<DragData> {
+    let (_, hwnd, accepted) = target;
+
+    match inner.drag.as_mut() {
+        Some(state) => {
+            state.target = Some(target);
+            state.effect.set(default_effect(state.allowed & accepted, ctrl, shift));
+        },
+        None => { return None; }
+    }
+
+    drag_data(inner, hwnd, pt)
+}
+
+/**
+    Send a `DragLeave` event to the current target (if any) and clear it.
+*/
+unsafe fn leave_target<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    use user32::GetCursorPos;
+
+    let (allowed, target) = match inner.drag.as_mut() {
+        Some(state) => {
+            state.effect.set(DROP_EFFECT_NONE);
+            (state.allowed, state.target.take())
+        },
+        None => { return; }
+    };
+
+    if let Some((id, hwnd, accepted)) = target {
+        let mut pt = POINT{x: 0, y: 0};
+        GetCursorPos(&mut pt);
+
+        if let Some(mut data) = drag_data(inner, hwnd, pt) {
+            data.allowed = allowed & accepted;
+            inner.trigger(id, DragLeave, EventArgs::Drag(data));
+        }
+    }
+}
+
+/**
+    Drop the payload on the current target. If the target accepts the drop, it receives a `DragDrop` event.
+    The source then receives a `DragEnd` event with the final effect.
+*/
+unsafe fn drop_payload<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    use user32::GetCursorPos;
+
+    let target = match inner.drag.as_ref() {
+        Some(state) => match state.target {
+            Some(t) if state.effect.get() != DROP_EFFECT_NONE => Some(t),
+            _ => None
+        },
+        None => { return; }
+    };
+
+    let effect = match target {
+        Some((id, hwnd, _)) => {
+            let mut pt = POINT{x: 0, y: 0};
+            GetCursorPos(&mut pt);
+
+            match drag_data(inner, hwnd, pt) {
+                Some(data) => {
+                    let effect = data.effect.clone();
+                    inner.trigger(id, DragDrop, EventArgs::Drag(data));
+                    effect.get()
+                },
+                None => DROP_EFFECT_NONE
+            }
+        },
+        None => {
+            leave_target(inner);
+            DROP_EFFECT_NONE
+        }
+    };
+
+    end_drag(inner, effect);
+}
+
+/**
+    Release the drag resources and send the `DragEnd` event to the source
+*/
+unsafe fn end_drag<ID: Hash+Clone>(inner: &mut UiInner<ID>, effect: u32) {
+    use user32::GetCursorPos;
+
+    let state = match inner.drag.take() {
+        Some(state) => state,
+        None => { return; }
+    };
+
+    let mut pt = POINT{x: 0, y: 0};
+    GetCursorPos(&mut pt);
+
+    let source = state.source;
+    let data = DragData {
+        source: AnyHandle::HWND(state.source_hwnd),
+        pos: client_pos(state.source_hwnd, pt),
+        allowed: state.allowed,
+        payload: state.payload.clone(),
+        effect: Rc::new(Cell::new(effect))
+    };
+
+    free_drag(state);
+
+    inner.trigger(source, DragEnd, EventArgs::Drag(data));
+}
+
+/**
+    Free the resources allocated by a drag operation. The drag state must be removed from the ui before calling this
+    because releasing the capture sends a `WM_CAPTURECHANGED` message to the source.
+*/
+unsafe fn free_drag(state: DragState) {
+    use user32::{ReleaseCapture, GetCapture};
+    use comctl32::{ImageList_DragLeave, ImageList_EndDrag, ImageList_Destroy};
+
+    if !state.image.is_null() {
+        ImageList_DragLeave(ptr::null_mut());
+        ImageList_EndDrag();
+        ImageList_Destroy(state.image);
+    }
+
+    if GetCapture() == state.source_hwnd {
+        ReleaseCapture();
+    }
+}
+
+/**
+    Build the data sent with a drag event to a target
+*/
+unsafe fn drag_data<ID: Hash+Clone>(inner: &UiInner<ID>, hwnd: HWND, pt: POINT) -> Option<DragData> {
+    inner.drag.as_ref().map(|state| {
+        let accepted = state.target.map(|(_, _, a)| a).unwrap_or(0);
+        DragData {
+            source: AnyHandle::HWND(state.source_hwnd),
+            pos: client_pos(hwnd, pt),
+            allowed: state.allowed & accepted,
+            payload: state.payload.clone(),
+            effect: state.effect.clone()
+        }
+    })
+}
+
+/**
+    Return the registered drop target under a point in screen coordinates. Children of a registered control
+    (ex: the edit of a combobox) are matched to their parent.
+*/
+unsafe fn find_target<ID: Hash+Clone>(inner: &UiInner<ID>, pt: POINT) -> Option<(u64, HWND, u32)> {
+    use user32::{WindowFromPoint, GetParent};
+
+    let mut hwnd = WindowFromPoint(pt);
+    while !hwnd.is_null() {
+        if let Some(id) = inner.inner_id_from_handle(&AnyHandle::HWND(hwnd)) {
+            if let Some(accepted) = inner.drop_targets.get(&id) {
+                return Some((id, hwnd, *accepted));
+            }
+        }
+
+        hwnd = GetParent(hwnd);
+    }
+
+    None
+}
+
+/**
+    Map a point in screen coordinates to the client area of a window
+*/
+unsafe fn client_pos(hwnd: HWND, pt: POINT) -> (i32, i32) {
+    use user32::ScreenToClient;
+
+    let mut pt = pt;
+    ScreenToClient(hwnd, &mut pt);
+    (pt.x as i32, pt.y as i32)
+}
+
+/**
+    Select the default effect of a drop using the same rules as the system: `Ctrl` copies, `Shift` moves,
+    `Ctrl+Shift` links. Without modifiers, move is preferred over copy and link.
+*/
+fn default_effect(allowed: u32, ctrl: bool, shift: bool) -> u32 {
+    let effect = match (ctrl, shift) {
+        (true, true) => DROP_EFFECT_LINK,
+        (true, false) => DROP_EFFECT_COPY,
+        (false, true) => DROP_EFFECT_MOVE,
+        (false, false) => {
+            return [DROP_EFFECT_MOVE, DROP_EFFECT_COPY, DROP_EFFECT_LINK].iter()
+              .find(|&&e| allowed & e == e)
+              .map(|e| *e)
+              .unwrap_or(DROP_EFFECT_NONE);
+        }
+    };
+
+    if allowed & effect == effect { effect } else { DROP_EFFECT_NONE }
+}
+
+/**
+    Create a single image list that holds the drag image
+*/
+unsafe fn build_drag_image(handle: &AnyHandle) -> Result<HIMAGELIST, Error> {
+    use comctl32::{ImageList_Create, ImageList_Add, ImageList_ReplaceIcon, ImageList_Destroy};
+    use gdi32::{GetObjectW, DeleteObject};
+    use winapi::{BITMAP, HBITMAP, ICONINFO, ILC_COLOR32, ILC_MASK, c_int};
+    use user32::GetIconInfo;
+    use controls::HandleSpec;
+
+    let (il, ok) = match handle {
+        &AnyHandle::HANDLE(h, HandleSpec::Bitmap) => {
+            let mut bmp: BITMAP = mem::zeroed();
+            GetObjectW(h, mem::size_of::<BITMAP>() as c_int, &mut bmp as *mut BITMAP as *mut _);
+
+            let il = ImageList_Create(bmp.bmWidth, bmp.bmHeight, ILC_COLOR32 | ILC_MASK, 1, 0);
+            (il, !il.is_null() && ImageList_Add(il, h as HBITMAP, ptr::null_mut()) != -1)
+        },
+        &AnyHandle::HICON(h) | &AnyHandle::HCURSOR(h) => {
+            let mut info: ICONINFO = mem::zeroed();
+            GetIconInfo(h, &mut info);
+
+            let mut bmp: BITMAP = mem::zeroed();
+            let size_source = if info.hbmColor.is_null() { info.hbmMask } else { info.hbmColor };
+            GetObjectW(size_source as _, mem::size_of::<BITMAP>() as c_int, &mut bmp as *mut BITMAP as *mut _);
+            if !info.hbmColor.is_null() { DeleteObject(info.hbmColor as _); }
+            if !info.hbmMask.is_null() { DeleteObject(info.hbmMask as _); }
+
+            // Monochrome icons stack the AND and XOR masks in a single bitmap, so only the width can be trusted
+            let il = ImageList_Create(bmp.bmWidth, bmp.bmWidth, ILC_COLOR32 | ILC_MASK, 1, 0);
+            (il, !il.is_null() && ImageList_ReplaceIcon(il, -1, h) != -1)
+        },
+        h => { return Err(Error::BadResource(format!("Image resource required got {}", h.human_name()))); }
+    };
+
+    if ok {
+        Ok(il)
+    } else {
+        if !il.is_null() { ImageList_Destroy(il); }
+        Err(Error::BadResource("Failed to create the drag image".to_string()))
+    }
+}
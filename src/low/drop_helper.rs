@@ -0,0 +1,271 @@
+/*!
+    A minimal OLE drop target that forward file drops to window-like controls.
+
+    When files are dragged over a registered window, the drop target send one of the
+    `NWG_FILES_*` messages to the window. The `lparam` of the message points to a `FileDropData`
+    structure that lives as long as the message is processed.
+*/
+
+use std::ptr;
+use std::mem;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+
+use winapi::{HWND, HRESULT, ULONG, DWORD, POINTL, REFIID, GUID, S_OK, E_NOINTERFACE, E_POINTER, c_void};
+
+use low::defs::IDataObject;
+use error::{Error, SystemError};
+
+thread_local!(static OLE_INITIALIZED: Cell<bool> = Cell::new(false));
+thread_local!(static REGISTERED: RefCell<HashSet<usize>> = RefCell::new(HashSet::new()));
+
+/**
+    Data sent with the `NWG_FILES_*` messages
+*/
+pub struct FileDropData {
+    pub paths: Vec<String>,
+    pub pos: (i32, i32)
+}
+
+#[repr(C)]
+struct FileDropTargetVtbl {
+    query_interface: unsafe extern "system" fn(*mut FileDropTarget, REFIID, *mut *mut c_void) -> HRESULT,
+    add_ref: unsafe extern "system" fn(*mut FileDropTarget) -> ULONG,
+    release: unsafe extern "system" fn(*mut FileDropTarget) -> ULONG,
+    drag_enter: unsafe extern "system" fn(*mut FileDropTarget, *mut IDataObject, DWORD, POINTL, *mut DWORD) -> HRESULT,
+    drag_over: unsafe extern "system" fn(*mut FileDropTarget, DWORD, POINTL, *mut DWORD) -> HRESULT,
+    drag_leave: unsafe extern "system" fn(*mut FileDropTarget) -> HRESULT,
+    drop: unsafe extern "system" fn(*mut FileDropTarget, *mut IDataObject, DWORD, POINTL, *mut DWORD) -> HRESULT,
+}
+
+/**
+    A IDropTarget implementation. The layout must begin with the vtable pointer.
+*/
+#[repr(C)]
+struct FileDropTarget {
+    vtbl: *const FileDropTargetVtbl,
+    refs: ULONG,
+    hwnd: HWND,
+    accept: bool
+}
+
+static FILE_DROP_TARGET_VTBL: FileDropTargetVtbl = FileDropTargetVtbl {
+    query_interface: query_interface,
+    add_ref: add_ref,
+    release: release,
+    drag_enter: drag_enter,
+    drag_over: drag_over,
+    drag_leave: drag_leave,
+    drop: drag_drop,
+};
+
+/**
+    Initialize OLE on the calling thread. OLE is only required by the drop targets, so it is initialized
+    the first time a window accepts files and it stays initialized for the lifetime of the thread.
+*/
+unsafe fn enable_ole() -> Result<(), Error> {
+    use low::defs::OleInitialize;
+
+    if OLE_INITIALIZED.with(|i| i.get()) {
+        return Ok(());
+    }
+
+    let result = OleInitialize(ptr::null_mut());
+    if result < 0 {
+        return Err(Error::System(SystemError::ComError(format!("OleInitialize failed with the code 0x{:08X}", result))));
+    }
+
+    OLE_INITIALIZED.with(|i| i.set(true));
+    Ok(())
+}
+
+/**
+    Register or unregister a window-like control as a file drop target.
+    Registering a window that nwg already registered do nothing.
+
+    Returns `Error::System` if the window could not be registered, for example if another drop target is registered on it.
+*/
+pub unsafe fn set_accept_files(hwnd: HWND, accept: bool) -> Result<(), Error> {
+    use low::defs::{RegisterDragDrop, RevokeDragDrop};
+
+    let registered = REGISTERED.with(|r| r.borrow().contains(&(hwnd as usize)));
+
+    if accept {
+        if registered { return Ok(()); }
+        if let Err(e) = enable_ole() { return Err(e); }
+
+        let target = Box::into_raw(Box::new(FileDropTarget {
+            vtbl: &FILE_DROP_TARGET_VTBL,
+            refs: 1,
+            hwnd: hwnd,
+            accept: false
+        }));
+
+        // RegisterDragDrop keeps its own reference on the target, the initial one can be released right away
+        let result = RegisterDragDrop(hwnd, target as *mut c_void);
+        release(target);
+
+        if result != S_OK {
+            return Err(Error::System(SystemError::ComError(format!("RegisterDragDrop failed with the code 0x{:08X}", result))));
+        }
+
+        REGISTERED.with(|r| r.borrow_mut().insert(hwnd as usize));
+    } else if registered {
+        // Only the drop targets registered by nwg are revoked
+        RevokeDragDrop(hwnd);
+        REGISTERED.with(|r| r.borrow_mut().remove(&(hwnd as usize)));
+    }
+
+    Ok(())
+}
+
+/**
+    Read the list of files in a data object. Returns an empty vector if the data object do not hold files.
+*/
+unsafe fn read_files(data: *mut IDataObject) -> Vec<String> {
+    use winapi::CF_HDROP;
+    use low::defs::{FORMATETC, STGMEDIUM, DVASPECT_CONTENT, TYMED_HGLOBAL, DragQueryFileW, ReleaseStgMedium};
+    use low::other_helper::from_utf16;
+
+    if data.is_null() { return Vec::new(); }
+
+    let format = FORMATETC {
+        cfFormat: CF_HDROP as u16,
+        ptd: ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL
+    };
+
+    let mut medium: STGMEDIUM = mem::zeroed();
+    if (&mut *data).GetData(&format, &mut medium) != S_OK {
+        return Vec::new();
+    }
+
+    let hdrop = medium.hGlobal as ::winapi::HDROP;
+    let count = DragQueryFileW(hdrop, 0xFFFFFFFF, ptr::null_mut(), 0);
+    let mut paths = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let length = DragQueryFileW(hdrop, i, ptr::null_mut(), 0) + 1;
+        let mut buffer: Vec<u16> = vec![0; length as usize];
+        DragQueryFileW(hdrop, i, buffer.as_mut_ptr(), length);
+        paths.push(from_utf16(&buffer));
+    }
+
+    ReleaseStgMedium(&mut medium);
+
+    paths
+}
+
+/**
+    Send a file drop message to the target window. Screen coordinates are mapped to the window client area.
+*/
+unsafe fn send_drop_message(hwnd: HWND, msg: u32, paths: Vec<String>, pt: POINTL) {
+    use user32::{SendMessageW, ScreenToClient};
+    use winapi::{POINT, LPARAM};
+
+    let mut pos = POINT{x: pt.x, y: pt.y};
+    ScreenToClient(hwnd, &mut pos);
+
+    let data = FileDropData{ paths: paths, pos: (pos.x as i32, pos.y as i32) };
+    SendMessageW(hwnd, msg, 0, &data as *const FileDropData as LPARAM);
+}
+
+fn guid_eq(g1: &GUID, g2: &GUID) -> bool {
+    g1.Data1 == g2.Data1 && g1.Data2 == g2.Data2 && g1.Data3 == g2.Data3 && g1.Data4 == g2.Data4
+}
+
+unsafe extern "system" fn query_interface(this: *mut FileDropTarget, riid: REFIID, out: *mut *mut c_void) -> HRESULT {
+    use low::clsid::{IID_IUnknown, IID_IDropTarget};
+
+    if out.is_null() { return E_POINTER; }
+
+    let riid = &*riid;
+    if guid_eq(riid, &IID_IUnknown()) || guid_eq(riid, &IID_IDropTarget()) {
+        add_ref(this);
+        *out = this as *mut c_void;
+        S_OK
+    } else {
+        *out = ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut FileDropTarget) -> ULONG {
+    let target = &mut *this;
+    target.refs += 1;
+    target.refs
+}
+
+unsafe extern "system" fn release(this: *mut FileDropTarget) -> ULONG {
+    let refs = {
+        let target = &mut *this;
+        target.refs -= 1;
+        target.refs
+    };
+
+    if refs == 0 {
+        drop(Box::from_raw(this));
+    }
+
+    refs
+}
+
+#[allow(unused_variables)]
+unsafe extern "system" fn drag_enter(this: *mut FileDropTarget, data: *mut IDataObject, keys: DWORD, pt: POINTL, effect: *mut DWORD) -> HRESULT {
+    use low::defs::{NWG_FILES_DRAG_ENTER, DROPEFFECT_COPY, DROPEFFECT_NONE};
+
+    let target = &mut *this;
+    let paths = read_files(data);
+    target.accept = paths.len() > 0;
+
+    if target.accept {
+        *effect = DROPEFFECT_COPY;
+        send_drop_message(target.hwnd, NWG_FILES_DRAG_ENTER, paths, pt);
+    } else {
+        *effect = DROPEFFECT_NONE;
+    }
+
+    S_OK
+}
+
+#[allow(unused_variables)]
+unsafe extern "system" fn drag_over(this: *mut FileDropTarget, keys: DWORD, pt: POINTL, effect: *mut DWORD) -> HRESULT {
+    use low::defs::{DROPEFFECT_COPY, DROPEFFECT_NONE};
+
+    let target = &*this;
+    *effect = if target.accept { DROPEFFECT_COPY } else { DROPEFFECT_NONE };
+
+    S_OK
+}
+
+unsafe extern "system" fn drag_leave(this: *mut FileDropTarget) -> HRESULT {
+    use user32::SendMessageW;
+    use low::defs::NWG_FILES_DRAG_LEAVE;
+
+    let target = &mut *this;
+    if target.accept {
+        target.accept = false;
+        SendMessageW(target.hwnd, NWG_FILES_DRAG_LEAVE, 0, 0);
+    }
+
+    S_OK
+}
+
+#[allow(unused_variables)]
+unsafe extern "system" fn drag_drop(this: *mut FileDropTarget, data: *mut IDataObject, keys: DWORD, pt: POINTL, effect: *mut DWORD) -> HRESULT {
+    use low::defs::{NWG_FILES_DROPPED, DROPEFFECT_COPY, DROPEFFECT_NONE};
+
+    let target = &mut *this;
+    let paths = read_files(data);
+    target.accept = false;
+
+    if paths.len() > 0 {
+        *effect = DROPEFFECT_COPY;
+        send_drop_message(target.hwnd, NWG_FILES_DROPPED, paths, pt);
+    } else {
+        *effect = DROPEFFECT_NONE;
+    }
+
+    S_OK
+}
@@ -5,22 +5,27 @@
 
 use std::{fmt, any, ptr, mem};
 use std::hash::{Hash, Hasher};
+use std::cell::RefCell;
+use std::time::Duration;
 
 use winapi::{HWND, UINT, DWORD, WPARAM, LPARAM, UINT_PTR, DWORD_PTR, LRESULT, WORD, HIWORD, NMHDR,
  HMENU, c_int};
 
-use winapi::{WM_MOVE, WM_SIZING, WM_SIZE, WM_EXITSIZEMOVE, WM_PAINT, WM_UNICHAR, WM_CHAR,
+use winapi::{WM_MOVE, WM_SETTINGCHANGE, WM_GETOBJECT, WM_SIZING, WM_SIZE, WM_EXITSIZEMOVE, WM_PAINT, WM_UNICHAR, WM_CHAR,
   WM_CLOSE, WM_LBUTTONUP, WM_RBUTTONUP, WM_MBUTTONUP, WM_LBUTTONDOWN, WM_RBUTTONDOWN,
   WM_MBUTTONDOWN, WM_KEYDOWN, WM_KEYUP, BN_CLICKED, BN_DBLCLK, BN_SETFOCUS, BN_KILLFOCUS,
   DTN_CLOSEUP, WM_COMMAND, WM_NOTIFY, WM_TIMER, WM_MENUCOMMAND, TVN_SELCHANGEDW, WM_MOUSEMOVE,
   NM_CLICK, NM_DBLCLK, NM_KILLFOCUS, NM_SETFOCUS, TVN_ITEMCHANGEDW, TVN_ITEMCHANGINGW, TVN_ITEMEXPANDEDW,
-  TVN_ITEMEXPANDINGW, TVN_DELETEITEMW};
+  TVN_ITEMEXPANDINGW, TVN_DELETEITEMW, WM_DPICHANGED};
 
 use ui::UiInner;
 use events::EventArgs;
 use controls::{AnyHandle, Timer};
+use controls::animator::AnimatorHandle;
 use low::menu_helper::get_menu_id;
-use low::defs::{NWG_DESTROY, CBN_SELCHANGE, CBN_KILLFOCUS, CBN_SETFOCUS, STN_CLICKED, STN_DBLCLK,
+use low::defs::{NWG_DESTROY, NWG_FILES_DRAG_ENTER, NWG_FILES_DRAG_LEAVE, NWG_FILES_DROPPED, NWG_DRAG_ENTER, NWG_DRAG_OVER,
+  NWG_DRAG_LEAVE, NWG_DRAG_DROP, NWG_DRAG_END, NWG_ZOOM_CHANGED, NWG_NODE_MOUSE_DOWN, NWG_NODE_MOUSE_UP, NWG_NODE_CLICK,
+  NWG_NODE_ENTER, NWG_NODE_LEAVE, NWG_CANVAS_FRAME, NWG_TWEEN_COMPLETED, CBN_SELCHANGE, CBN_KILLFOCUS, CBN_SETFOCUS, STN_CLICKED, STN_DBLCLK,
   LBN_SELCHANGE, LBN_DBLCLK, LBN_SETFOCUS, LBN_KILLFOCUS, EN_SETFOCUS, EN_KILLFOCUS, EN_UPDATE,
   EN_MAXTEXT};
 
@@ -155,6 +160,17 @@ pub const Char: Event = Event::Group(&[WM_UNICHAR, WM_CHAR], &unpack_char, &hwnd
 pub const MouseUp: Event = Event::Group(&[WM_LBUTTONUP, WM_RBUTTONUP, WM_MBUTTONUP], &unpack_mouseclick, &hwnd_handle);
 pub const MouseDown: Event = Event::Group(&[WM_LBUTTONDOWN, WM_RBUTTONDOWN, WM_MBUTTONDOWN], &unpack_mouseclick, &hwnd_handle);
 pub const MouseMove: Event = Event::Single(WM_MOUSEMOVE, &unpack_mousemove, &hwnd_handle);
+pub const DpiChanged: Event = Event::Single(WM_DPICHANGED, &unpack_dpi, &hwnd_handle);
+pub const FilesDragEnter: Event = Event::Single(NWG_FILES_DRAG_ENTER, &unpack_files, &hwnd_handle);
+pub const FilesDragLeave: Event = Event::Single(NWG_FILES_DRAG_LEAVE, &event_unpack_no_args, &hwnd_handle);
+pub const FilesDropped: Event = Event::Single(NWG_FILES_DROPPED, &unpack_files, &hwnd_handle);
+
+// Drag and drop events. These events are triggered directly by the ui drag and drop manager.
+pub const DragEnter: Event = Event::Single(NWG_DRAG_ENTER, &event_unpack_no_args, &hwnd_handle);
+pub const DragOver: Event = Event::Single(NWG_DRAG_OVER, &event_unpack_no_args, &hwnd_handle);
+pub const DragLeave: Event = Event::Single(NWG_DRAG_LEAVE, &event_unpack_no_args, &hwnd_handle);
+pub const DragDrop: Event = Event::Single(NWG_DRAG_DROP, &event_unpack_no_args, &hwnd_handle);
+pub const DragEnd: Event = Event::Single(NWG_DRAG_END, &event_unpack_no_args, &hwnd_handle);
 
 // Button events
 fn h1(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { command_handle(h,m,w,l,BN_CLICKED) }
@@ -175,6 +191,15 @@ fn h6(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { command_handle(h,m
 fn h7(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { command_handle(h,m,w,l,STN_DBLCLK) }
 pub const StnClick: Event = Event::Single(WM_COMMAND, &event_unpack_no_args, &h6);
 pub const StnDoubleClick: Event = Event::Single(WM_COMMAND, &event_unpack_no_args, &h7);
+pub const ImageZoomChanged: Event = Event::Single(NWG_ZOOM_CHANGED, &unpack_zoom, &hwnd_handle);
+
+// Canvas scene events. These events are triggered by the canvas scene helper.
+#[cfg(feature = "canvas")] pub const NodeMouseDown: Event = Event::Single(NWG_NODE_MOUSE_DOWN, &unpack_node, &hwnd_handle);
+#[cfg(feature = "canvas")] pub const NodeMouseUp: Event = Event::Single(NWG_NODE_MOUSE_UP, &unpack_node, &hwnd_handle);
+#[cfg(feature = "canvas")] pub const NodeClick: Event = Event::Single(NWG_NODE_CLICK, &unpack_node, &hwnd_handle);
+#[cfg(feature = "canvas")] pub const NodeEnter: Event = Event::Single(NWG_NODE_ENTER, &unpack_node, &hwnd_handle);
+#[cfg(feature = "canvas")] pub const NodeLeave: Event = Event::Single(NWG_NODE_LEAVE, &unpack_node, &hwnd_handle);
+#[cfg(feature = "canvas")] pub const CanvasFrame: Event = Event::Single(NWG_CANVAS_FRAME, &unpack_frame, &hwnd_handle);
 
 // Datepicker events
 fn h8(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { notify_handle(h,m,w,l, DTN_CLOSEUP) }
@@ -200,6 +225,10 @@ pub const EnFocus: Event = Event::Single(WM_COMMAND, &unpack_en_focus, &h14);
 fn h15(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { Some( AnyHandle::Custom(any::TypeId::of::<Timer>(), w as usize) )  }
 pub const TimerTick: Event = Event::Single(WM_TIMER, &event_unpack_no_args, &h15);
 
+// Animator events. These events are triggered by the tween helper.
+fn h25(h:HWND,m:UINT,w:WPARAM,l:LPARAM) -> Option<AnyHandle> { Some( AnyHandle::Custom(any::TypeId::of::<AnimatorHandle>(), w as usize) )  }
+pub const TweenCompleted: Event = Event::Single(NWG_TWEEN_COMPLETED, &event_unpack_no_args, &h25);
+
 // Menu item events
 pub const MenuTrigger: Event = Event::Single(WM_MENUCOMMAND, &event_unpack_no_args, &menuitem_handle);
 
@@ -226,24 +255,26 @@ pub const TreeViewItemExpanding: Event = Event::Single(WM_NOTIFY, &unpack_tree_f
 // Event unpackers for the events defined above
 fn unpack_move(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
     use winapi::{LOWORD, HIWORD};
+    use low::dpi_helper::logical_dpi;
+    use dpi::point_to_logical;
     
-    let (x, y) = (LOWORD(l as u32), HIWORD(l as u32));
-    Some(EventArgs::Position(x as i32, y as i32))
+    let (x, y) = (LOWORD(l as u32) as i16, HIWORD(l as u32) as i16);
+    let (x, y) = point_to_logical((x as i32, y as i32), unsafe{ logical_dpi(hwnd) });
+    Some(EventArgs::Position(x, y))
 }
 
 fn unpack_size(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
-    use winapi::RECT;
-    use user32::GetClientRect;
-
-    let mut r: RECT = unsafe{ mem::uninitialized() };
-
-    unsafe{ GetClientRect(hwnd, &mut r); }
-    let w: u32 = (r.right-r.left) as u32;
-    let h: u32 = (r.bottom-r.top) as u32;
+    use low::window_helper::get_window_size;
 
+    let (w, h) = unsafe{ get_window_size(hwnd) };
     Some(EventArgs::Size(w, h))
 }
 
+fn unpack_dpi(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
+    use winapi::LOWORD;
+    Some(EventArgs::Dpi(LOWORD(w as u32) as u32))
+}
+
 fn unpack_char(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
     use winapi::UNICODE_NOCHAR;
 
@@ -282,6 +313,36 @@ fn unpack_mouseclick(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<Even
   Some(EventArgs::MouseClick{btn: btn, pos: (x, y)})
 }
 
+fn unpack_files(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
+    use low::drop_helper::FileDropData;
+
+    let data: &FileDropData = unsafe{ mem::transmute(l) };
+    Some(EventArgs::Files{paths: data.paths.clone(), pos: data.pos})
+}
+
+fn unpack_zoom(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
+    use low::viewer_helper::ViewData;
+
+    let data: &ViewData = unsafe{ &*(l as *const ViewData) };
+    Some(EventArgs::Zoom{scale: data.scale, visible: data.visible})
+}
+
+#[cfg(feature = "canvas")]
+fn unpack_node(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
+    use low::scene_helper::NodeData;
+
+    let data: &NodeData = unsafe{ &*(l as *const NodeData) };
+    Some(EventArgs::Node{node: data.node, btn: data.btn, pos: data.pos})
+}
+
+#[cfg(feature = "canvas")]
+fn unpack_frame(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
+    use low::frame_helper::FrameData;
+
+    let data: &FrameData = unsafe{ &*(l as *const FrameData) };
+    Some(EventArgs::Frame{delta: data.delta, frame: data.frame})
+}
+
 fn unpack_key(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventArgs> {
    Some(EventArgs::Key(w as u32))
 }
@@ -313,11 +374,72 @@ fn unpack_en_focus(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM) -> Option<EventA
 #[allow(unused_variables)]
 unsafe extern "system" fn process_events<ID: Hash+Clone+'static>(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM, id: UINT_PTR, data: DWORD_PTR) -> LRESULT {
     use comctl32::DefSubclassProc;
-    use low::defs::{NWG_CUSTOM_MIN, NWG_CUSTOM_MAX};
+    use low::defs::{NWG_CUSTOM_MIN, NWG_CUSTOM_MAX, NWG_IDLE};
 
     let inner: &mut UiInner<ID> = mem::transmute(data);
     let inner_id: u64;
 
+    // Idle callbacks are run when the message loops drain the message queue
+    if msg == NWG_IDLE && hwnd == inner.messages.hwnd {
+        inner.run_idle_callbacks();
+        return 0;
+    }
+
+    // Scale the window before the `DpiChanged` callbacks are called
+    if msg == WM_DPICHANGED {
+        ::low::dpi_helper::process_dpi_changed(inner, hwnd, w, l);
+    }
+
+    // Custom control colors
+    if let Some(result) = ::low::color_helper::process_colors(inner, hwnd, msg, w, l) {
+        return result;
+    }
+
+    // Controls drawing their own content expose their own accessible tree
+    if msg == WM_GETOBJECT {
+        if let Some(result) = ::low::accessibility_helper::process_get_object(inner, hwnd, w, l) {
+            return result;
+        }
+    }
+
+    // Follow the light/dark preference of the user
+    if msg == WM_SETTINGCHANGE {
+        ::low::color_helper::process_setting_change(inner, l);
+    }
+
+    // While a drag and drop operation is running, the mouse input is consumed by the drag manager
+    if inner.drag.is_some() {
+        if let Some(result) = ::low::drag_helper::process_drag(inner, hwnd, msg, w, l) {
+            return result;
+        }
+    }
+
+    // Canvas scenes are drawn before the `Paint` callbacks and turn the mouse input into node events
+    #[cfg(feature = "canvas")]
+    ::low::scene_helper::process_scene(inner, hwnd, msg, l);
+
+    // Charts are drawn the same way and show a tooltip over the hovered point
+    #[cfg(feature = "canvas")]
+    ::low::chart_helper::process_chart(inner, hwnd, msg, l);
+
+    // Animated canvases start a frame each time they are painted
+    #[cfg(feature = "canvas")]
+    {
+        if let Some(result) = ::low::frame_helper::process_frame(inner, hwnd, msg, w) {
+            return result;
+        }
+    }
+
+    // Animators update the controls when the timers of the message window fire
+    if ::low::tween_helper::process_tween(inner, hwnd, msg, w) {
+        return 0;
+    }
+
+    // Tasks are polled when their waker posts a message to the message window
+    if ::low::task_helper::process_task(inner, hwnd, msg, w) {
+        return 0;
+    }
+
     let trigger_event = |inner: &mut UiInner<ID>, evt: &Event, get_handle: &HandleProc, get_params: &UnpackProc| {
         if let Some(handle) = (get_handle)(hwnd, msg, w, l) {
             if let Some(inner_id) = inner.inner_id_from_handle( &handle ) {
@@ -344,7 +466,11 @@ unsafe extern "system" fn process_events<ID: Hash+Clone+'static>(hwnd: HWND, msg
       }
     }
 
-    DefSubclassProc(hwnd, msg, w, l)
+    // File drop messages are only understood by NWG, do not forward them to the control
+    match msg {
+      NWG_FILES_DRAG_ENTER | NWG_FILES_DRAG_LEAVE | NWG_FILES_DROPPED => 0,
+      _ => DefSubclassProc(hwnd, msg, w, l)
+    }
 }
 
 /**
@@ -371,33 +497,129 @@ pub fn unhook_window_events<ID: Hash+Clone+'static>(handle: HWND) { unsafe {
   }
 }}
 
+thread_local!(static IDLE_WINDOWS: RefCell<Vec<HWND>> = RefCell::new(Vec::new()));
+
+/**
+    Register or unregister the message window of a ui to receive a `NWG_IDLE` message each time the message queue
+    of the thread drains
+*/
+pub fn set_idle_window(hwnd: HWND, idle: bool) {
+  IDLE_WINDOWS.with(|windows| {
+    let mut windows = windows.borrow_mut();
+    windows.retain(|&h| h != hwnd);
+    if idle { windows.push(hwnd); }
+  });
+}
+
 /**
     Dispatch the messages waiting the the system message queue to the associated Uis. This includes NWG custom messages.
 
-    Return once a quit event was received.
+    Return the exit code passed to `exit` once a quit event was received.
 */
-#[inline(always)]
-pub unsafe fn dispatch_events() {
-  use winapi::MSG;
-  use user32::{GetMessageW, TranslateMessage, DispatchMessageW};
+pub unsafe fn dispatch_events() -> i32 {
+  use winapi::INFINITE;
+
+  loop {
+    if let Some(code) = dispatch_queue() {
+      return code;
+    }
+
+    wait_message(INFINITE);
+  }
+}
+
+/**
+    Dispatch the messages waiting in the system message queue without waiting for new messages.
+    Return `false` if a quit event was received. The quit event is posted again, so that the outer loops also stop.
+*/
+pub unsafe fn dispatch_pending() -> bool {
+  use user32::PostQuitMessage;
+
+  match dispatch_queue() {
+    Some(code) => { PostQuitMessage(code); false },
+    None => true
+  }
+}
+
+/**
+    Wait until a message is received or until `timeout` elapsed, then dispatch the messages waiting in the system message queue.
+    Return `false` if a quit event was received, like `dispatch_pending`.
+*/
+pub unsafe fn wait_timeout(timeout: Duration) -> bool {
+  use winapi::INFINITE;
+
+  // The system waits are not more precise than a millisecond
+  let millis = timeout.as_secs().saturating_mul(1000) + ((timeout.subsec_nanos() + 999_999) / 1_000_000) as u64;
+  wait_message(millis.min((INFINITE - 1) as u64) as DWORD);
+  dispatch_pending()
+}
+
+/**
+    Dispatch the messages until `predicate` returns `true`. The predicate is checked before waiting for new messages.
+    Return `true` if the predicate was satisfied or `false` if a quit event was received, like `dispatch_pending`.
+*/
+pub unsafe fn run_until<F: FnMut() -> bool>(mut predicate: F) -> bool {
+  use winapi::INFINITE;
+
+  if predicate() { return true; }
+
+  loop {
+    if !dispatch_pending() { return false; }
+    if predicate() { return true; }
+    wait_message(INFINITE);
+  }
+}
+
+/**
+    Dispatch the messages waiting in the system message queue. Once the queue is empty, send `NWG_IDLE` to the
+    uis that have idle callbacks if at least one message was dispatched.
+
+    Return the exit code of the quit event if one was received. The messages that follow the quit event are not dispatched.
+*/
+unsafe fn dispatch_queue() -> Option<i32> {
+  use winapi::{MSG, PM_REMOVE, WM_QUIT};
+  use user32::{PeekMessageW, TranslateMessage, DispatchMessageW, SendMessageW};
+  use low::defs::NWG_IDLE;
 
   let mut msg: MSG = mem::uninitialized();
-  while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) != 0 {
-      TranslateMessage(&msg); 
-      DispatchMessageW(&msg); 
-      // TODO dispatch events sent from other thread / other processes ( after first stable release )
+  let mut dispatched = false;
+  while PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+    if msg.message == WM_QUIT {
+      return Some(msg.wParam as i32);
+    }
+
+    TranslateMessage(&msg); 
+    DispatchMessageW(&msg);
+    dispatched = true;
+  }
+
+  if dispatched {
+    let windows = IDLE_WINDOWS.with(|windows| windows.borrow().clone());
+    for hwnd in windows {
+      SendMessageW(hwnd, NWG_IDLE, 0, 0);
+    }
   }
+
+  None
 }
 
 /**
-    Send a WM_QUIT to the system queue. Breaks the dispatch_events loop.
+    Wait until a message is received or until `timeout` (in milliseconds) elapsed. Return at once if messages are waiting.
 */
-#[inline(always)]
-pub unsafe fn exit() {
-  use user32::PostMessageW;
-  use winapi::WM_QUIT;
+unsafe fn wait_message(timeout: DWORD) {
+  use user32::MsgWaitForMultipleObjectsEx;
+  use winapi::{QS_ALLINPUT, MWMO_INPUTAVAILABLE};
 
-  PostMessageW(ptr::null_mut(), WM_QUIT, 0, 0);
+  MsgWaitForMultipleObjectsEx(0, ptr::null(), timeout, QS_ALLINPUT, MWMO_INPUTAVAILABLE);
+}
+
+/**
+    Send a WM_QUIT to the system queue. Breaks the dispatch_events loop, which returns `code`.
+*/
+#[inline(always)]
+pub unsafe fn exit(code: i32) {
+  use user32::PostQuitMessage;
+  PostQuitMessage(code);
 }
 
 /**
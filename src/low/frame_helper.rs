@@ -0,0 +1,190 @@
+/*!
+    Low level canvas animation functions. Start the frames of the animated canvases and schedule the next ones.
+
+    The frames are started when the canvas is painted. After a frame, the canvas is invalidated again, either
+    at once when the frames are paced by the display, or by a system timer when the frames are paced to a frame rate.
+    The canvas cannot be invalidated while it is painted because the paint validates the whole canvas, so a
+    `NWG_CANVAS_NEXT_FRAME` message is posted instead and the canvas is invalidated when the message is received.
+    A canvas that is hidden or in a minimized window is not painted, so its animation pauses until it is painted again.
+
+    For each frame, a `NWG_CANVAS_FRAME` message is sent to the canvas. The `lparam` of the message points to a
+    `FrameData` structure that lives as long as the message is processed.
+*/
+
+use std::ptr;
+use std::hash::Hash;
+use std::any::TypeId;
+use std::time::{Duration, Instant};
+
+use winapi::{HWND, UINT, UINT_PTR, WPARAM, LPARAM, LRESULT, WM_PAINT, WM_TIMER};
+
+use ui::UiInner;
+use controls::{AnyHandle, Canvas};
+use controls::canvas::FramePacing;
+
+/// The identifier of the frame timer of a canvas
+const FRAME_TIMER_ID: UINT_PTR = 0x4E57_4652;
+
+/// If a canvas paced by the display is not painted during this time, its window is checked to pause the animation
+const WATCHDOG_DELAY: u32 = 100;
+
+/**
+    Data sent with the canvas frame messages
+
+    • `delta`: The time elapsed since the last frame. Zero for the first frame and the first frame after a pause  
+    • `frame`: The number of the frame, starting at 1  
+*/
+pub struct FrameData {
+    pub delta: Duration,
+    pub frame: u64
+}
+
+/// What to do with a canvas message
+enum Step {
+    Ignore,
+    Invalidate,
+    Wait(Duration),
+    Frame(FrameData)
+}
+
+/**
+    Return the refresh rate of the display showing a window, in hertz. Return 0 if it is unknown.
+*/
+pub unsafe fn display_refresh_rate(hwnd: HWND) -> f32 {
+    use user32::{GetDC, ReleaseDC};
+    use gdi32::GetDeviceCaps;
+    use winapi::VREFRESH;
+
+    let dc = GetDC(hwnd);
+    if dc.is_null() { return 0.0; }
+
+    // 0 and 1 mean that the hardware uses its default refresh rate
+    let rate = GetDeviceCaps(dc, VREFRESH);
+    ReleaseDC(hwnd, dc);
+
+    if rate > 1 { rate as f32 } else { 0.0 }
+}
+
+/**
+    Stop the timer scheduling the next frame of a canvas
+*/
+pub unsafe fn stop_frames(hwnd: HWND) {
+    use user32::KillTimer;
+    KillTimer(hwnd, FRAME_TIMER_ID);
+}
+
+/**
+    Start a frame of an animated canvas when it is painted and schedule the next frame. Return `Some` if the message
+    was the frame timer or the next frame message of the canvas, which must not be forwarded. Does nothing if `hwnd` is not an animated canvas.
+*/
+pub unsafe fn process_frame<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, w: WPARAM) -> Option<LRESULT> {
+    use user32::SendMessageW;
+    use low::defs::{NWG_CANVAS_FRAME, NWG_CANVAS_NEXT_FRAME};
+
+    match msg {
+        WM_PAINT | NWG_CANVAS_NEXT_FRAME => {},
+        WM_TIMER if w as UINT_PTR == FRAME_TIMER_ID => { stop_frames(hwnd); },
+        _ => { return None; }
+    }
+
+    let handled = if msg != WM_PAINT { Some(0) } else { None };
+    let id = match inner.inner_id_from_handle(&AnyHandle::HWND(hwnd)).and_then(|i| inner.inner_public_map.get(&i)) {
+        Some(&(ref id, t)) if t == TypeId::of::<Canvas<ID>>() => id.clone(),
+        _ => { return handled; }
+    };
+
+    let drawn = is_drawn(hwnd);
+    let now = Instant::now();
+    let step = inner.with_ui(|ui| {
+        let mut canvas = match ui.get_mut::<Canvas<ID>>(&id) {
+            Ok(c) => c,
+            Err(_) => { return Step::Ignore; }
+        };
+
+        let clock = match canvas.frame_clock() {
+            Some(clock) => clock,
+            None => { return Step::Ignore; }
+        };
+
+        if !drawn {
+            clock.pause();
+            return Step::Ignore;
+        }
+
+        if msg != WM_PAINT {
+            return Step::Invalidate;
+        }
+
+        match clock.wait_time(now) {
+            Some(wait) => Step::Wait(wait),
+            None => Step::Frame(FrameData{ delta: clock.tick(now), frame: clock.frame_count() })
+        }
+    });
+
+    match step {
+        Step::Ignore => {},
+        Step::Invalidate => invalidate(hwnd),
+        Step::Wait(wait) => set_frame_timer(hwnd, wait),
+        Step::Frame(data) => {
+            SendMessageW(hwnd, NWG_CANVAS_FRAME, 0, &data as *const FrameData as LPARAM);
+            schedule_next_frame(inner, hwnd, &id);
+        }
+    }
+
+    handled
+}
+
+/**
+    Schedule the frame following the one that was just drawn, if the canvas is still animated
+*/
+unsafe fn schedule_next_frame<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, id: &ID) {
+    let next = inner.with_ui(|ui| {
+        let mut canvas = match ui.get_mut::<Canvas<ID>>(id) {
+            Ok(c) => c,
+            Err(_) => { return None; }
+        };
+
+        canvas.frame_clock().map(|clock| (clock.get_pacing(), clock.wait_time(Instant::now())))
+    });
+
+    match next {
+        Some((FramePacing::Display, _)) => {
+            // Drawing waits for the display, so the canvas is painted again at once. The watchdog
+            // detects when the canvas stops being painted because it was hidden.
+            request_frame(hwnd);
+            set_frame_timer(hwnd, Duration::from_millis(WATCHDOG_DELAY as u64));
+        },
+        Some((FramePacing::Fps(_), Some(wait))) => set_frame_timer(hwnd, wait),
+        Some((FramePacing::Fps(_), None)) => request_frame(hwnd),
+        None => {}
+    }
+}
+
+/// Return true if the canvas is visible and its window is not minimized
+unsafe fn is_drawn(hwnd: HWND) -> bool {
+    use user32::{IsWindowVisible, IsIconic, GetAncestor};
+    use low::defs::GA_ROOT;
+
+    IsWindowVisible(hwnd) != 0 && IsIconic(GetAncestor(hwnd, GA_ROOT)) == 0
+}
+
+/// Invalidate the canvas once the current paint is done
+unsafe fn request_frame(hwnd: HWND) {
+    use user32::PostMessageW;
+    use low::defs::NWG_CANVAS_NEXT_FRAME;
+    PostMessageW(hwnd, NWG_CANVAS_NEXT_FRAME, 0, 0);
+}
+
+unsafe fn invalidate(hwnd: HWND) {
+    use user32::InvalidateRect;
+    use winapi::FALSE;
+    InvalidateRect(hwnd, ptr::null(), FALSE);
+}
+
+/// Paint the canvas again after `wait`. The system timers are not more precise than a millisecond.
+unsafe fn set_frame_timer(hwnd: HWND, wait: Duration) {
+    use user32::SetTimer;
+
+    let millis = wait.as_secs() * 1000 + ((wait.subsec_nanos() + 999_999) / 1_000_000) as u64;
+    SetTimer(hwnd, FRAME_TIMER_ID, millis.max(1).min(u32::max_value() as u64) as u32, None);
+}
@@ -0,0 +1,193 @@
+/*!
+    Low level image functions. Convert the decoded pixels of an `ImageData` to system bitmaps and icons, and read the pixels back.
+*/
+
+use std::ptr;
+use std::mem;
+
+use winapi::{HANDLE, HBITMAP, HICON, c_int};
+
+use resources::ImageData;
+use resources::codecs::{put_pixel, get_pixel};
+use error::{Error, SystemError};
+
+/**
+    Create a 32 bits top-down DIB section from decoded pixels
+*/
+pub unsafe fn create_dib_section(width: u32, height: u32, pixels: &[u8]) -> Result<HBITMAP, Error> {
+    use winapi::{BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, BI_RGB, LONG, DWORD};
+    use gdi32::CreateDIBSection;
+    use std::os::raw::c_void;
+
+    let header = BITMAPINFOHEADER {
+        biSize: mem::size_of::<BITMAPINFOHEADER>() as DWORD,
+        biWidth: width as LONG, biHeight: -(height as LONG),
+        biPlanes: 1, biBitCount: 32, biCompression: BI_RGB,
+        biSizeImage: (width * height * 4) as u32,
+        biXPelsPerMeter: 0, biYPelsPerMeter: 0,
+        biClrUsed: 0, biClrImportant: 0
+    };
+
+    let info = BITMAPINFO {
+        bmiHeader: header,
+        bmiColors: [],
+    };
+
+    let mut bits: *mut c_void = ptr::null_mut();
+    let bitmap = CreateDIBSection(ptr::null_mut(), &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0);
+    if bitmap.is_null() || bits.is_null() {
+        return Err(Error::System(SystemError::ImageCreation));
+    }
+
+    ptr::copy_nonoverlapping(pixels.as_ptr(), bits as *mut u8, pixels.len());
+
+    Ok(bitmap)
+}
+
+/**
+    Create a bitmap from decoded pixels
+*/
+pub unsafe fn bitmap_from_data(image: &ImageData) -> Result<HBITMAP, Error> {
+    create_dib_section(image.width, image.height, &image.data)
+}
+
+/**
+    Create an icon or a cursor from decoded pixels. The hotspot of cursors is the top left corner.
+*/
+pub unsafe fn icon_from_data(image: &ImageData, cursor: bool) -> Result<HANDLE, Error> {
+    use winapi::{ICONINFO, TRUE, FALSE};
+    use user32::CreateIconIndirect;
+    use gdi32::{CreateBitmap, DeleteObject};
+
+    // Icons use straight alpha
+    let mut pixels = image.data.clone();
+    for p in pixels.chunks_mut(4) {
+        let (r, g, b, a) = get_pixel(p);
+        p[0] = b; p[1] = g; p[2] = r; p[3] = a;
+    }
+
+    let color = create_dib_section(image.width, image.height, &pixels)?;
+    let mask = CreateBitmap(image.width as c_int, image.height as c_int, 1, 1, ptr::null());
+    if mask.is_null() {
+        DeleteObject(color as _);
+        return Err(Error::System(SystemError::ImageCreation));
+    }
+
+    let mut info = ICONINFO {
+        fIcon: if cursor { FALSE } else { TRUE },
+        xHotspot: 0,
+        yHotspot: 0,
+        hbmMask: mask,
+        hbmColor: color
+    };
+
+    let icon = CreateIconIndirect(&mut info);
+    DeleteObject(color as _);
+    DeleteObject(mask as _);
+
+    if icon.is_null() {
+        Err(Error::System(SystemError::ImageCreation))
+    } else {
+        Ok(icon as HANDLE)
+    }
+}
+
+/**
+    Read the pixels of a bitmap as a top-down 32 bits DIB. Return the pixels and a boolean that indicates if
+    the alpha channel is used. If the alpha channel is empty, the returned pixels are opaque.
+*/
+pub unsafe fn read_bitmap(bitmap: HBITMAP) -> Result<(ImageData, bool), Error> {
+    use winapi::{BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, BI_RGB, LONG, DWORD};
+    use gdi32::{GetObjectW, GetDIBits};
+    use user32::{GetDC, ReleaseDC};
+
+    let mut bm: BITMAP = mem::zeroed();
+    if GetObjectW(bitmap as HANDLE, mem::size_of::<BITMAP>() as c_int, &mut bm as *mut BITMAP as *mut _) == 0 {
+        return Err(Error::BadResource("The image handle is not a bitmap".to_string()));
+    }
+
+    let (width, height) = (bm.bmWidth.abs() as u32, bm.bmHeight.abs() as u32);
+    let mut image = ImageData::new(width, height);
+    if width == 0 || height == 0 {
+        return Ok((image, false));
+    }
+
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as DWORD,
+            biWidth: width as LONG, biHeight: -(height as LONG),
+            biPlanes: 1, biBitCount: 32, biCompression: BI_RGB,
+            biSizeImage: 0,
+            biXPelsPerMeter: 0, biYPelsPerMeter: 0,
+            biClrUsed: 0, biClrImportant: 0
+        },
+        bmiColors: [],
+    };
+
+    let dc = GetDC(ptr::null_mut());
+    let lines = GetDIBits(dc, bitmap, 0, height, image.data.as_mut_ptr() as *mut _, &mut info, DIB_RGB_COLORS);
+    ReleaseDC(ptr::null_mut(), dc);
+
+    if lines == 0 {
+        return Err(Error::System(SystemError::SystemMessageFailed("GetDIBits failed".to_string())));
+    }
+
+    let has_alpha = bm.bmBitsPixel == 32 && image.data.chunks(4).any(|p| p[3] != 0);
+    if !has_alpha {
+        for p in image.data.chunks_mut(4) { p[3] = 255; }
+    }
+
+    Ok((image, has_alpha))
+}
+
+/**
+    Read the pixels of an icon or a cursor. Pixels hidden by the AND mask are transparent.
+*/
+pub unsafe fn read_icon(icon: HICON) -> Result<ImageData, Error> {
+    use winapi::ICONINFO;
+    use user32::GetIconInfo;
+    use gdi32::DeleteObject;
+
+    let mut info: ICONINFO = mem::zeroed();
+    if GetIconInfo(icon, &mut info) == 0 {
+        return Err(Error::BadResource("The image handle is not an icon".to_string()));
+    }
+
+    let result = read_icon_bitmaps(info.hbmColor, info.hbmMask);
+
+    if !info.hbmColor.is_null() { DeleteObject(info.hbmColor as _); }
+    if !info.hbmMask.is_null() { DeleteObject(info.hbmMask as _); }
+
+    result
+}
+
+unsafe fn read_icon_bitmaps(color: HBITMAP, mask: HBITMAP) -> Result<ImageData, Error> {
+    let (mask, _) = read_bitmap(mask)?;
+
+    // Monochrome icons store the AND mask and the XOR mask in the same bitmap
+    if color.is_null() {
+        let (w, h) = (mask.width as usize, mask.height as usize / 2);
+        let mut image = ImageData::new(w as u32, h as u32);
+        for i in 0..(w * h) {
+            let and = mask.data[i * 4] != 0;
+            let xor = mask.data[(w * h + i) * 4];
+            if !and {
+                put_pixel(&mut image.data[i*4..i*4+4], xor, xor, xor, 255);
+            }
+        }
+        return Ok(image);
+    }
+
+    let (mut image, has_alpha) = read_bitmap(color)?;
+    for (i, p) in image.data.chunks_mut(4).enumerate() {
+        if has_alpha {
+            // Icons use straight alpha
+            let (r, g, b, a) = (p[2], p[1], p[0], p[3]);
+            put_pixel(p, r, g, b, a);
+        } else if mask.data.get(i * 4).map(|&m| m != 0).unwrap_or(false) {
+            put_pixel(p, 0, 0, 0, 0);
+        }
+    }
+
+    Ok(image)
+}
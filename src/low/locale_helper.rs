@@ -0,0 +1,173 @@
+/*!
+    Low level localization functions.
+
+    The localized controls are stored by inner id with the key of their message. Their text is applied again each
+    time the language of the ui changes. Right to left mirroring toggles `WS_EX_LAYOUTRTL` on every window-like
+    control of the ui and moves the children so that the layout is mirrored.
+*/
+
+use std::ptr;
+use std::mem;
+use std::hash::Hash;
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use winapi::{HWND, RECT, POINT, UINT};
+
+use ui::UiInner;
+use controls::{AnyHandle, Menu};
+use locale::Localization;
+use low::window_helper::{get_window_long, set_window_long};
+
+/**
+    The localization state of a ui
+
+    Members:
+    • `localization`: The catalogs and the current language
+    • `bindings`: Map the inner id of the localized controls to the key of their message
+    • `pending`: The keys of the controls built by a `LocalizedT`, by handle hash, waiting for the control to be packed
+    • `mirroring`: If the layout should be mirrored when the language is written from right to left
+    • `mirrored`: If the layout is currently mirrored
+*/
+pub struct LocaleState {
+    pub localization: Localization,
+    pub bindings: HashMap<u64, String>,
+    pub pending: HashMap<u64, String>,
+    pub mirroring: bool,
+    pub mirrored: bool
+}
+
+impl LocaleState {
+
+    pub fn new() -> LocaleState {
+        LocaleState {
+            localization: Localization::new(),
+            bindings: HashMap::new(),
+            pending: HashMap::new(),
+            mirroring: false,
+            mirrored: false
+        }
+    }
+
+}
+
+/**
+    Set the text of a packed control. Menus and menuitems use their menu text, the other window-like controls
+    use their window text. Other controls are ignored.
+*/
+pub unsafe fn apply_control_text<ID: Hash+Clone>(inner: &UiInner<ID>, id: u64, text: &str) {
+    use low::window_helper::set_window_text;
+    use low::menu_helper::set_menuitem_text;
+
+    let control = match inner.controls.get(&id) {
+        Some(c) => c,
+        None => { return; }
+    };
+
+    let is_menu = inner.inner_public_map.get(&id).map(|&(_, tid)| tid == TypeId::of::<Menu>()).unwrap_or(false);
+    if is_menu {
+        let menu: &RefCell<Box<Menu>> = mem::transmute(control);
+        if let Ok(menu) = menu.try_borrow() { menu.set_text(text); }
+        return;
+    }
+
+    let handle = match control.try_borrow() {
+        Ok(c) => c.handle(),
+        Err(_) => { return; }
+    };
+
+    match handle {
+        AnyHandle::HWND(h) => set_window_text(h, text),
+        AnyHandle::HMENU_ITEM(parent, uid) => set_menuitem_text(parent, None, Some(uid), text),
+        _ => {}
+    }
+}
+
+/**
+    Apply the current language to every localized control and update the mirroring of the layout
+*/
+pub unsafe fn apply_language<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    let texts: Vec<(u64, String)> = inner.locale.bindings.iter()
+        .map(|(id, key)| (*id, inner.locale.localization.text(key)))
+        .collect();
+
+    for (id, text) in texts {
+        apply_control_text(inner, id, &text);
+    }
+
+    update_mirroring(inner);
+}
+
+/**
+    Mirror or restore the layout of the ui if the mirroring state do not match the current language
+*/
+pub unsafe fn update_mirroring<ID: Hash+Clone>(inner: &mut UiInner<ID>) {
+    let rtl = inner.locale.mirroring && inner.locale.localization.is_rtl();
+    if rtl == inner.locale.mirrored { return; }
+
+    let handles: Vec<HWND> = inner.controls.values()
+        .filter_map(|c| c.try_borrow().ok().map(|c| c.handle()))
+        .filter_map(|h| match h { AnyHandle::HWND(h) => Some(h), _ => None })
+        .collect();
+
+    mirror_windows(&handles, rtl);
+    inner.locale.mirrored = rtl;
+}
+
+/**
+    Mirror a newly packed top level window if the ui layout is mirrored. Child controls inherit the layout of their parent.
+*/
+pub unsafe fn mirror_new_window(handle: HWND, mirrored: bool) {
+    use winapi::{GWL_STYLE, WS_CHILD};
+
+    let style = get_window_long(handle, GWL_STYLE) as UINT;
+    if mirrored && (style & WS_CHILD) != WS_CHILD {
+        set_layout_rtl(handle, true);
+    }
+}
+
+/**
+    Toggle the right to left layout of windows. The position of the children are kept relative to the
+    leading edge of their parent, so the whole layout is mirrored.
+*/
+unsafe fn mirror_windows(handles: &[HWND], rtl: bool) {
+    use user32::{GetParent, GetWindowRect, MapWindowPoints, SetWindowPos, InvalidateRect, GetMenu, DrawMenuBar};
+    use winapi::{GWL_STYLE, WS_CHILD, SWP_NOZORDER, SWP_NOSIZE, SWP_NOACTIVATE, TRUE};
+
+    // Positions of the children in their parent client area
+    let mut positions: Vec<(HWND, i32, i32)> = Vec::with_capacity(handles.len());
+    for &h in handles.iter() {
+        let style = get_window_long(h, GWL_STYLE) as UINT;
+        if (style & WS_CHILD) != WS_CHILD { continue; }
+
+        let mut r: RECT = mem::uninitialized();
+        GetWindowRect(h, &mut r);
+        MapWindowPoints(ptr::null_mut(), GetParent(h), &mut r as *mut RECT as *mut POINT, 2);
+        positions.push((h, r.left, r.top));
+    }
+
+    for &h in handles.iter() {
+        set_layout_rtl(h, rtl);
+    }
+
+    for &(h, x, y) in positions.iter() {
+        SetWindowPos(h, ptr::null_mut(), x, y, 0, 0, SWP_NOZORDER|SWP_NOSIZE|SWP_NOACTIVATE);
+    }
+
+    for &h in handles.iter() {
+        let style = get_window_long(h, GWL_STYLE) as UINT;
+        if (style & WS_CHILD) != WS_CHILD && !GetMenu(h).is_null() { DrawMenuBar(h); }
+        InvalidateRect(h, ptr::null(), TRUE);
+    }
+}
+
+unsafe fn set_layout_rtl(handle: HWND, rtl: bool) {
+    use winapi::{GWL_EXSTYLE, WS_EX_LAYOUTRTL};
+
+    let ex_style = get_window_long(handle, GWL_EXSTYLE) as UINT;
+    let new_style = if rtl { ex_style | WS_EX_LAYOUTRTL } else { ex_style & !WS_EX_LAYOUTRTL };
+    if new_style != ex_style {
+        set_window_long(handle, GWL_EXSTYLE, new_style as usize);
+    }
+}
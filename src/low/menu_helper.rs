@@ -207,6 +207,92 @@ pub unsafe fn is_menu_enabled(menu: HMENU, parent: &AnyHandle) -> bool {
     is_menuitem_enabled(parent_h, Some(index), None)
 }
 
+/**
+    Return the text of a menuitem at the selected position or using the selected ID. Panic if both pos and id are None.
+*/
+pub unsafe fn get_menuitem_text(h: HMENU, pos: Option<UINT>, id: Option<UINT>) -> String {
+    use winapi::MENUITEMINFOW;
+    use low::defs::{GetMenuItemInfoW, MIIM_STRING};
+    use low::other_helper::from_utf16;
+
+    if id.is_none() && pos.is_none() { panic!("Both pos and id are None"); }
+
+    let use_position = id.is_none();
+    let value = if use_position { pos.unwrap() } else { id.unwrap() };
+
+    let mut info = MENUITEMINFOW { 
+        cbSize: mem::size_of::<MENUITEMINFOW>() as UINT,
+        fMask: MIIM_STRING, fType: 0, fState: 0,
+        wID: 0, hSubMenu: ptr::null_mut(), hbmpChecked: ptr::null_mut(),
+        hbmpUnchecked: ptr::null_mut(), dwItemData: 0, dwTypeData: ptr::null_mut(),
+        cch: 0, hbmpItem: ptr::null_mut()
+    };
+
+    // First call returns the length of the text
+    if GetMenuItemInfoW(h, value, use_position as BOOL, &mut info) == 0 || info.cch == 0 {
+        return String::new();
+    }
+
+    let mut buffer: Vec<u16> = vec![0; (info.cch + 1) as usize];
+    info.cch += 1;
+    info.dwTypeData = buffer.as_mut_ptr();
+
+    if GetMenuItemInfoW(h, value, use_position as BOOL, &mut info) == 0 {
+        String::new()
+    } else {
+        from_utf16(&buffer[..])
+    }
+}
+
+/**
+    Set the text of a menuitem at the selected position or using the selected ID. Panic if both pos and id are None.
+*/
+pub unsafe fn set_menuitem_text(h: HMENU, pos: Option<UINT>, id: Option<UINT>, text: &str) {
+    use winapi::MENUITEMINFOW;
+    use low::defs::{SetMenuItemInfoW, MIIM_STRING};
+    use low::other_helper::to_utf16;
+
+    if id.is_none() && pos.is_none() { panic!("Both pos and id are None"); }
+
+    let use_position = id.is_none();
+    let value = if use_position { pos.unwrap() } else { id.unwrap() };
+
+    let mut text = to_utf16(text);
+    let mut info = MENUITEMINFOW { 
+        cbSize: mem::size_of::<MENUITEMINFOW>() as UINT,
+        fMask: MIIM_STRING, fType: 0, fState: 0,
+        wID: 0, hSubMenu: ptr::null_mut(), hbmpChecked: ptr::null_mut(),
+        hbmpUnchecked: ptr::null_mut(), dwItemData: 0, dwTypeData: text.as_mut_ptr(),
+        cch: 0, hbmpItem: ptr::null_mut()
+    };
+
+    SetMenuItemInfoW(h, value, use_position as BOOL, &mut info);
+}
+
+/**
+    Return the text of a menu.
+*/
+pub unsafe fn get_menu_text(menu: HMENU, parent: &AnyHandle) -> String {
+    let parent_h = resolve_menu_parent(parent);
+    let index = menu_index_in_parent(menu, parent);
+    get_menuitem_text(parent_h, Some(index), None)
+}
+
+/**
+    Set the text of a menu. If the menu is in a menubar, the menubar is redrawn.
+*/
+pub unsafe fn set_menu_text(menu: HMENU, parent: &AnyHandle, text: &str) {
+    use user32::DrawMenuBar;
+
+    let parent_h = resolve_menu_parent(parent);
+    let index = menu_index_in_parent(menu, parent);
+    set_menuitem_text(parent_h, Some(index), None, text);
+
+    if let &AnyHandle::HWND(h) = parent {
+        DrawMenuBar(h);
+    }
+}
+
 /**
     Used in the events proc to return the inner id of a menuitem when triggering the clicked action
 */
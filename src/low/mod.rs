@@ -8,4 +8,19 @@ pub mod events;
 pub mod message_handler;
 pub mod menu_helper;
 pub mod window_helper;
-pub mod other_helper;
\ No newline at end of file
+pub mod other_helper;
+pub mod drop_helper;
+pub mod drag_helper;
+pub mod dpi_helper;
+pub mod color_helper;
+pub mod accessibility_helper;
+pub mod locale_helper;
+pub mod image_helper;
+pub mod capture_helper;
+pub mod animation_helper;
+pub mod viewer_helper;
+pub mod tween_helper;
+pub mod task_helper;
+#[cfg(feature = "canvas")] pub mod scene_helper;
+#[cfg(feature = "canvas")] pub mod chart_helper;
+#[cfg(feature = "canvas")] pub mod frame_helper;
@@ -75,6 +75,30 @@ pub unsafe fn get_system_error() -> (DWORD, String) {
   (code, error_message)
 }
 
+/**
+    Load a function from a system library. Returns `None` if the library or the function do not exist.
+    The library is only loaded if the process did not already load it, and it stays loaded.
+*/
+pub unsafe fn load_function(lib: &str, name: &str) -> Option<usize> {
+    use kernel32::{LoadLibraryW, GetModuleHandleW, GetProcAddress};
+
+    let lib = to_utf16(lib);
+    let mut module = GetModuleHandleW(lib.as_ptr());
+    if module.is_null() {
+        module = LoadLibraryW(lib.as_ptr());
+    }
+
+    if module.is_null() { return None; }
+
+    let name = format!("{}\0", name);
+    let function = GetProcAddress(module, name.as_ptr() as *const _);
+    if function.is_null() {
+        None
+    } else {
+        Some(function as usize)
+    }
+}
+
 /**
   Enable the Windows visual style in the application without having to use a manifest
 */
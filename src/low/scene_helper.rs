@@ -0,0 +1,117 @@
+/*!
+    Low level canvas scene functions. Draw the scene of a canvas each time the canvas is painted and turn
+    the mouse messages of the canvas into scene node events.
+
+    For each node event, a `NWG_NODE_*` message is sent to the canvas. The `lparam` of the message points to a
+    `NodeData` structure that lives as long as the message is processed.
+*/
+
+use std::mem;
+use std::hash::Hash;
+use std::any::TypeId;
+
+use winapi::{HWND, UINT, LPARAM, WM_PAINT, WM_MOUSEMOVE, WM_MOUSELEAVE, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_RBUTTONDOWN,
+  WM_RBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP};
+
+use ui::UiInner;
+use controls::{AnyHandle, Canvas};
+use defs::MouseButton;
+use resources::canvas::{NodeId, SceneEvent};
+
+/**
+    Data sent with the scene node messages
+
+    • `node`: The id of the node in the scene  
+    • `btn`: The mouse button of the event. `None` for the enter and leave events  
+    • `pos`: The position of the mouse in the canvas, in device independent pixels  
+*/
+pub struct NodeData {
+    pub node: NodeId,
+    pub btn: Option<MouseButton>,
+    pub pos: (f32, f32)
+}
+
+/**
+    Draw the scene of a canvas when it receives `WM_PAINT` and send the node events matching a mouse message.
+    Does nothing if `hwnd` is not a canvas with a scene. The canvas is not borrowed while the events are processed,
+    so the callbacks can modify the scene.
+*/
+pub unsafe fn process_scene<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, l: LPARAM) {
+    use user32::SendMessageW;
+    use winapi::{GET_X_LPARAM, GET_Y_LPARAM};
+    use low::defs::{NWG_NODE_MOUSE_DOWN, NWG_NODE_MOUSE_UP, NWG_NODE_CLICK, NWG_NODE_ENTER, NWG_NODE_LEAVE};
+
+    let btn = match msg {
+        WM_LBUTTONDOWN | WM_LBUTTONUP => Some(MouseButton::Left),
+        WM_RBUTTONDOWN | WM_RBUTTONUP => Some(MouseButton::Right),
+        WM_MBUTTONDOWN | WM_MBUTTONUP => Some(MouseButton::Middle),
+        WM_PAINT | WM_MOUSEMOVE | WM_MOUSELEAVE => None,
+        _ => { return; }
+    };
+
+    let id = match inner.inner_id_from_handle(&AnyHandle::HWND(hwnd)).and_then(|i| inner.inner_public_map.get(&i)) {
+        Some(&(ref id, t)) if t == TypeId::of::<Canvas<ID>>() => id.clone(),
+        _ => { return; }
+    };
+
+    if msg == WM_PAINT {
+        inner.with_ui(|ui| {
+            if let Ok(mut canvas) = ui.get_mut::<Canvas<ID>>(&id) {
+                canvas.paint_scene(ui).ok();
+            }
+        });
+        return;
+    }
+
+    // The mouse position is in pixels, the scene uses the device independent pixels of the renderer
+    let (events, pos) = inner.with_ui(|ui| {
+        let mut canvas = match ui.get_mut::<Canvas<ID>>(&id) {
+            Ok(c) => c,
+            Err(_) => { return (Vec::new(), (0.0, 0.0)); }
+        };
+
+        let (dpix, dpiy) = canvas.get_dpi();
+        let pos = (GET_X_LPARAM(l) as f32 * 96.0 / dpix, GET_Y_LPARAM(l) as f32 * 96.0 / dpiy);
+        let events = match (canvas.scene_input(), btn) {
+            (Some(scene), None) if msg == WM_MOUSEMOVE => scene.mouse_move(pos),
+            (Some(scene), None) => scene.mouse_leave(),
+            (Some(scene), Some(btn)) => match msg {
+                WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN => scene.mouse_down(pos, btn),
+                _ => scene.mouse_up(pos, btn)
+            },
+            (None, _) => Vec::new()
+        };
+
+        (events, pos)
+    });
+
+    for event in events {
+        let (msg, node, btn) = match event {
+            SceneEvent::MouseDown(node, btn) => (NWG_NODE_MOUSE_DOWN, node, Some(btn)),
+            SceneEvent::MouseUp(node, btn) => (NWG_NODE_MOUSE_UP, node, Some(btn)),
+            SceneEvent::Click(node, btn) => (NWG_NODE_CLICK, node, Some(btn)),
+            SceneEvent::Enter(node) => { track_leave(hwnd); (NWG_NODE_ENTER, node, None) },
+            SceneEvent::Leave(node) => (NWG_NODE_LEAVE, node, None)
+        };
+
+        let data = NodeData{ node: node, btn: btn, pos: pos };
+        SendMessageW(hwnd, msg, 0, &data as *const NodeData as LPARAM);
+    }
+}
+
+/**
+    Ask the system to send `WM_MOUSELEAVE` when the mouse leaves the canvas, so that the hovered node receives its leave event
+*/
+pub unsafe fn track_leave(hwnd: HWND) {
+    use user32::TrackMouseEvent;
+    use winapi::{TRACKMOUSEEVENT, TME_LEAVE, DWORD};
+
+    let mut track = TRACKMOUSEEVENT{
+        cbSize: mem::size_of::<TRACKMOUSEEVENT>() as DWORD,
+        dwFlags: TME_LEAVE,
+        hwndTrack: hwnd,
+        dwHoverTime: 0
+    };
+
+    TrackMouseEvent(&mut track);
+}
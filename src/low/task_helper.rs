@@ -0,0 +1,210 @@
+/*!
+    Low level task functions. Run the futures spawned on a ui and wake them from any thread.
+
+    The waker of a task posts a `NWG_TASK_WAKE` message to the message window of its ui, with the id of the task
+    in the `wparam`. The task is polled when the message is dispatched, so the tasks only run on the thread of their ui.
+    A task woken several times before it is polled is only polled once.
+
+    The `sleep` futures use thread timers without window. Their callback is called by the message loop.
+*/
+
+use std::ptr;
+use std::pin::Pin;
+use std::hash::Hash;
+use std::future::Future;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+use winapi::{HWND, UINT, UINT_PTR, WPARAM, DWORD};
+
+use ui::UiInner;
+
+/// A future spawned on a ui
+struct Task {
+    future: Option<Pin<Box<Future<Output=()>>>>,
+    waker: Arc<TaskWaker>,
+    repoll: bool
+}
+
+/**
+    The tasks of a ui. `alive` is cleared when the ui is freed, after that the wakers and the ui handles do nothing.
+*/
+pub struct TaskState {
+    tasks: HashMap<u64, Task>,
+    next_id: u64,
+    alive: Arc<AtomicBool>
+}
+
+impl TaskState {
+
+    pub fn new() -> TaskState {
+        TaskState{ tasks: HashMap::new(), next_id: 0, alive: Arc::new(AtomicBool::new(true)) }
+    }
+
+    /// Return the flag cleared when the ui is freed
+    pub fn alive(&self) -> Arc<AtomicBool> {
+        self.alive.clone()
+    }
+
+    /// Mark the ui as freed. The tasks are dropped with the ui.
+    pub fn close(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+    }
+
+}
+
+/// Wake a task by posting a message to the message window of its ui. The window handle is saved as an integer, so that the waker can be sent to other threads.
+struct TaskWaker {
+    hwnd: usize,
+    task: u64,
+    alive: Arc<AtomicBool>,
+    queued: AtomicBool
+}
+
+impl Wake for TaskWaker {
+
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        use user32::PostMessageW;
+        use low::defs::NWG_TASK_WAKE;
+
+        if !self.alive.load(Ordering::SeqCst) || self.queued.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        unsafe{ PostMessageW(self.hwnd as HWND, NWG_TASK_WAKE, self.task as WPARAM, 0); }
+    }
+
+}
+
+/**
+    Add a task to a ui. The task is polled for the first time by the message loop.
+*/
+pub fn spawn_task<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, future: Pin<Box<Future<Output=()>>>) {
+    let id = inner.tasks.next_id;
+    inner.tasks.next_id += 1;
+
+    let waker = Arc::new(TaskWaker{
+        hwnd: inner.messages.hwnd as usize,
+        task: id,
+        alive: inner.tasks.alive(),
+        queued: AtomicBool::new(false)
+    });
+
+    inner.tasks.tasks.insert(id, Task{ future: Some(future), waker: waker.clone(), repoll: false });
+    waker.wake_by_ref();
+}
+
+/**
+    Poll a task when its wake message is received. Return true if the message was the wake message of a task.
+*/
+pub fn process_task<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, w: WPARAM) -> bool {
+    use low::defs::NWG_TASK_WAKE;
+
+    if msg != NWG_TASK_WAKE || hwnd != inner.messages.hwnd {
+        return false;
+    }
+
+    let id = w as u64;
+    let (mut future, waker) = match inner.tasks.tasks.get_mut(&id) {
+        Some(task) => match task.future.take() {
+            Some(future) => (future, task.waker.clone()),
+            None => {
+                // The task is woken by a message loop that runs while it is polled
+                task.repoll = true;
+                task.waker.queued.store(false, Ordering::SeqCst);
+                return true;
+            }
+        },
+        None => { return true; }
+    };
+
+    // Wakes received while the task runs post a new message
+    waker.queued.store(false, Ordering::SeqCst);
+
+    let result = {
+        let task_waker = Waker::from(waker.clone());
+        let mut context = Context::from_waker(&task_waker);
+        future.as_mut().poll(&mut context)
+    };
+
+    match result {
+        Poll::Ready(()) => { inner.tasks.tasks.remove(&id); },
+        Poll::Pending => {
+            if let Some(task) = inner.tasks.tasks.get_mut(&id) {
+                task.future = Some(future);
+                if task.repoll {
+                    task.repoll = false;
+                    waker.wake_by_ref();
+                }
+            }
+        }
+    }
+
+    true
+}
+
+thread_local!(static SLEEP_TIMERS: RefCell<HashMap<UINT_PTR, (u64, Waker)>> = RefCell::new(HashMap::new()));
+
+/**
+    Start or update the timer of a sleep future. `timer` is the timer of the future and `token` identifies the future.
+    The timer ids are reused by the system once the timers are killed, the token prevents a future from
+    taking the timer of another future.
+*/
+pub fn set_sleep_timer(timer: &mut Option<UINT_PTR>, token: u64, millis: u32, waker: &Waker) {
+    use user32::SetTimer;
+
+    let updated = SLEEP_TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        match timer.and_then(|id| timers.get_mut(&id)) {
+            Some(entry) if entry.0 == token => { entry.1 = waker.clone(); true },
+            _ => false
+        }
+    });
+
+    if updated { return; }
+
+    let id = unsafe{ SetTimer(ptr::null_mut(), 0, millis.max(1), Some(sleep_timer_proc)) };
+    *timer = if id != 0 { Some(id) } else { None };
+    if id != 0 {
+        SLEEP_TIMERS.with(|timers| timers.borrow_mut().insert(id, (token, waker.clone())));
+    }
+}
+
+/**
+    Stop the timer of a sleep future if it is still running
+*/
+pub fn kill_sleep_timer(timer: &mut Option<UINT_PTR>, token: u64) {
+    use user32::KillTimer;
+
+    if let Some(id) = timer.take() {
+        let owned = SLEEP_TIMERS.with(|timers| {
+            let mut timers = timers.borrow_mut();
+            match timers.get(&id) {
+                Some(&(t, _)) if t == token => { timers.remove(&id); true },
+                _ => false
+            }
+        });
+
+        if owned {
+            unsafe{ KillTimer(ptr::null_mut(), id); }
+        }
+    }
+}
+
+#[allow(unused_variables, non_snake_case)]
+unsafe extern "system" fn sleep_timer_proc(hwnd: HWND, uMsg: UINT, idEvent: UINT_PTR, dwTime: DWORD) {
+    use user32::KillTimer;
+
+    KillTimer(ptr::null_mut(), idEvent);
+    let entry = SLEEP_TIMERS.with(|timers| timers.borrow_mut().remove(&idEvent));
+    if let Some((_, waker)) = entry {
+        waker.wake();
+    }
+}
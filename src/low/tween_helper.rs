@@ -0,0 +1,126 @@
+/*!
+    Low level tween functions. Update the animators when their timer fires and apply the values of their tweens to the controls.
+
+    The animators use the timers of the message window of their `Ui`. When an animator finishes, a `NWG_TWEEN_COMPLETED`
+    message is sent to the message window with the timer id of the animator in the `wparam`.
+*/
+
+use std::hash::Hash;
+use std::any::TypeId;
+
+use winapi::{HWND, UINT, WPARAM, WM_TIMER};
+
+use ui::{Ui, UiInner};
+use controls::{AnyHandle, ControlType, Animator};
+use controls::animator::AnimatorHandle;
+use tween::PropertyValue;
+
+/**
+    Update an animator when its timer fires. Return true if the message was the timer of an animator.
+*/
+pub unsafe fn process_tween<ID: Hash+Clone+'static>(inner: &mut UiInner<ID>, hwnd: HWND, msg: UINT, w: WPARAM) -> bool {
+    use user32::SendMessageW;
+    use low::defs::NWG_TWEEN_COMPLETED;
+
+    if msg != WM_TIMER || hwnd != inner.messages.hwnd {
+        return false;
+    }
+
+    let handle = AnyHandle::Custom(TypeId::of::<AnimatorHandle>(), w as usize);
+    let id = match inner.inner_id_from_handle(&handle).and_then(|i| inner.inner_public_map.get(&i)) {
+        Some(&(ref id, t)) if t == TypeId::of::<Animator<ID>>() => id.clone(),
+        _ => { return false; }
+    };
+
+    let completed = inner.with_ui(|ui| {
+        let (values, finished) = {
+            let mut animator = match ui.get_mut::<Animator<ID>>(&id) {
+                Ok(a) => a,
+                Err(_) => { return false; }
+            };
+
+            // A timer message may still be queued after the animator was stopped
+            let running = animator.running();
+            let values = animator.step(|control, to| get_property(ui, control, to));
+            (values, running && !animator.running())
+        };
+
+        // The animator is released first, so that the controls can be animators too
+        for &(ref control, ref value) in values.iter() {
+            set_property(ui, control, value);
+        }
+
+        finished
+    });
+
+    if completed {
+        SendMessageW(hwnd, NWG_TWEEN_COMPLETED, w, 0);
+    }
+
+    true
+}
+
+/**
+    Return the value of the property of `control` that has the same type as `value`.
+    Return `None` if the control is not in the ui or if it does not have the property.
+*/
+pub fn get_property<ID: Hash+Clone>(ui: &Ui<ID>, control: &ID, value: &PropertyValue) -> Option<PropertyValue> {
+    use winapi::PBM_GETPOS;
+    use user32::SendMessageW;
+    use low::window_helper::{get_window_position, get_window_size, get_window_opacity};
+
+    let hwnd = match property_target(ui, control, value) {
+        Some(h) => h,
+        None => { return None; }
+    };
+
+    unsafe {
+        Some(match value {
+            &PropertyValue::Position(..) => { let (x, y) = get_window_position(hwnd); PropertyValue::Position(x, y) },
+            &PropertyValue::Size(..) => { let (w, h) = get_window_size(hwnd); PropertyValue::Size(w, h) },
+            &PropertyValue::Progress(_) => PropertyValue::Progress(SendMessageW(hwnd, PBM_GETPOS, 0, 0) as u32),
+            &PropertyValue::Opacity(_) => PropertyValue::Opacity(get_window_opacity(hwnd))
+        })
+    }
+}
+
+/**
+    Set a property of `control`. Does nothing if the control is not in the ui or if it does not have the property.
+*/
+pub fn set_property<ID: Hash+Clone>(ui: &Ui<ID>, control: &ID, value: &PropertyValue) {
+    use winapi::PBM_SETPOS;
+    use user32::SendMessageW;
+    use low::window_helper::{set_window_position, set_window_size, set_window_opacity};
+
+    let hwnd = match property_target(ui, control, value) {
+        Some(h) => h,
+        None => { return; }
+    };
+
+    // Like `Window::set_size`, the size of a window is the size of its client area
+    let fix = ui.type_of_control(control).ok() == Some(ControlType::Window);
+
+    unsafe {
+        match value {
+            &PropertyValue::Position(x, y) => set_window_position(hwnd, x, y),
+            &PropertyValue::Size(w, h) => set_window_size(hwnd, w, h, fix),
+            &PropertyValue::Progress(v) => { SendMessageW(hwnd, PBM_SETPOS, v as WPARAM, 0); },
+            &PropertyValue::Opacity(o) => set_window_opacity(hwnd, o)
+        }
+    }
+}
+
+/// Return the window of a control if it has the property of `value`
+fn property_target<ID: Hash+Clone>(ui: &Ui<ID>, control: &ID, value: &PropertyValue) -> Option<HWND> {
+    let hwnd = match ui.handle_of(control) {
+        Ok(AnyHandle::HWND(h)) => h,
+        _ => { return None; }
+    };
+
+    let control_type = ui.type_of_control(control).ok();
+    match value {
+        &PropertyValue::Progress(_) if control_type != Some(ControlType::ProgressBar) => None,
+        &PropertyValue::Opacity(_) if control_type != Some(ControlType::Window) => None,
+        _ => Some(hwnd)
+    }
+}
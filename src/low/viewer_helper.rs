@@ -0,0 +1,525 @@
+/*!
+    Low level image viewer functions. Scale, zoom and pan the bitmap of an image frame.
+
+    A viewer subclasses the static control of the frame and paints the bitmap itself, so that the bitmap can be
+    resampled at any scale. The state of the viewers is stored by handle. Each time the view changes, a `NWG_ZOOM_CHANGED`
+    message is sent to the control. The `lparam` of the message points to a `ViewData` structure that lives as long
+    as the message is processed.
+*/
+
+use std::ptr;
+use std::mem;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use winapi::{HWND, HDC, HBITMAP, UINT, WPARAM, LPARAM, LRESULT, UINT_PTR, DWORD_PTR, RECT};
+
+use defs::ImageScaling;
+use resources::ImageData;
+
+/// A magic number to identify the NWG subclass of the image viewers
+const VIEWER_SUBCLASS_ID: UINT_PTR = 2466;
+
+const MIN_ZOOM: f64 = 0.01;
+const MAX_ZOOM: f64 = 64.0;
+
+/// The zoom factor applied by one notch of the mouse wheel
+const WHEEL_ZOOM: f64 = 1.25;
+
+/**
+    The view of an image frame
+
+    • `scale`: The horizontal and vertical scale of the image  
+    • `visible`: The part of the image visible in the frame, in image pixels (x, y, width, height)  
+*/
+#[derive(Clone, PartialEq, Debug)]
+pub struct ViewData {
+    pub scale: (f64, f64),
+    pub visible: (f64, f64, f64, f64)
+}
+
+/**
+    The state of an image viewer
+
+    • `free`: If the user zoomed or panned the image. A free view is kept when the frame is resized.  
+    • `offset`: The position of the top left corner of the image in the frame, in pixels  
+    • `mipmaps`: The pixels of the bitmap followed by copies of half the size. Empty if the frame do not have an image.  
+    • `pan`: The last position of the cursor while the image is dragged  
+*/
+struct Viewer {
+    scaling: ImageScaling,
+    zoomable: bool,
+    free: bool,
+    size: (f64, f64),
+    scale: (f64, f64),
+    offset: (f64, f64),
+    mipmaps: Vec<ImageData>,
+    pan: Option<(i32, i32)>
+}
+
+thread_local!(static VIEWERS: RefCell<HashMap<usize, Viewer>> = RefCell::new(HashMap::new()));
+
+impl Viewer {
+
+    fn new(scaling: ImageScaling, zoomable: bool) -> Viewer {
+        Viewer {
+            scaling: scaling, zoomable: zoomable, free: false,
+            size: (0.0, 0.0), scale: (1.0, 1.0), offset: (0.0, 0.0),
+            mipmaps: Vec::new(), pan: None
+        }
+    }
+
+    /// Place the image in the frame using the scaling of the viewer
+    fn reset(&mut self, client: (f64, f64)) {
+        let ((iw, ih), (cw, ch)) = (self.size, client);
+
+        self.free = false;
+        self.scale = if iw == 0.0 || ih == 0.0 || cw == 0.0 || ch == 0.0 {
+            (1.0, 1.0)
+        } else {
+            match self.scaling {
+                ImageScaling::None | ImageScaling::Center => (1.0, 1.0),
+                ImageScaling::Fit => { let s = (cw / iw).min(ch / ih); (s, s) },
+                ImageScaling::Fill => { let s = (cw / iw).max(ch / ih); (s, s) },
+                ImageScaling::Stretch => (cw / iw, ch / ih)
+            }
+        };
+
+        self.offset = match self.scaling {
+            ImageScaling::None => (0.0, 0.0),
+            _ => ((cw - iw * self.scale.0) / 2.0, (ch - ih * self.scale.1) / 2.0)
+        };
+
+        self.clamp(client);
+    }
+
+    /// Keep the image in the frame. An image smaller than the frame is aligned, a larger image cannot leave an empty border.
+    fn clamp(&mut self, client: (f64, f64)) {
+        let align = |offset: f64, scaled: f64, client: f64, scaling: ImageScaling| -> f64 {
+            if scaled <= client {
+                if scaling == ImageScaling::None { 0.0 } else { (client - scaled) / 2.0 }
+            } else {
+                offset.min(0.0).max(client - scaled)
+            }
+        };
+
+        self.offset.0 = align(self.offset.0, self.size.0 * self.scale.0, client.0, self.scaling);
+        self.offset.1 = align(self.offset.1, self.size.1 * self.scale.1, client.1, self.scaling);
+    }
+
+    /// Multiply the scale of the image, keeping the image pixel under `point` in place
+    fn zoom_at(&mut self, factor: f64, point: (f64, f64), client: (f64, f64)) {
+        let max_factor = (MAX_ZOOM / self.scale.0.max(self.scale.1)).max(1.0);
+        let min_factor = (MIN_ZOOM / self.scale.0.min(self.scale.1)).min(1.0);
+        let factor = factor.min(max_factor).max(min_factor);
+
+        let anchor = ((point.0 - self.offset.0) / self.scale.0, (point.1 - self.offset.1) / self.scale.1);
+        self.scale = (self.scale.0 * factor, self.scale.1 * factor);
+        self.offset = (point.0 - anchor.0 * self.scale.0, point.1 - anchor.1 * self.scale.1);
+        self.free = true;
+        self.clamp(client);
+    }
+
+    fn pan(&mut self, dx: f64, dy: f64, client: (f64, f64)) {
+        self.offset = (self.offset.0 + dx, self.offset.1 + dy);
+        self.free = true;
+        self.clamp(client);
+    }
+
+    fn view(&self, client: (f64, f64)) -> ViewData {
+        let ((iw, ih), (sx, sy)) = (self.size, self.scale);
+        if iw == 0.0 || ih == 0.0 {
+            return ViewData{ scale: self.scale, visible: (0.0, 0.0, 0.0, 0.0) };
+        }
+
+        let x0 = (-self.offset.0 / sx).max(0.0).min(iw);
+        let y0 = (-self.offset.1 / sy).max(0.0).min(ih);
+        let x1 = ((client.0 - self.offset.0) / sx).min(iw).max(x0);
+        let y1 = ((client.1 - self.offset.1) / sy).min(ih).max(y0);
+
+        ViewData{ scale: self.scale, visible: (x0, y0, x1 - x0, y1 - y0) }
+    }
+
+    /**
+        Resample the visible part of the image. Return the position of the pixels in the frame and the pixels.
+        The pixels are sampled from the smallest mipmap that is still larger than the displayed image.
+    */
+    fn render(&self, client: (f64, f64)) -> Option<(i32, i32, ImageData)> {
+        let ((iw, ih), (sx, sy), (ox, oy)) = (self.size, self.scale, self.offset);
+        if self.mipmaps.is_empty() {
+            return None;
+        }
+
+        let (left, top) = (ox.max(0.0).floor(), oy.max(0.0).floor());
+        let (right, bottom) = ((ox + iw * sx).min(client.0).ceil(), (oy + ih * sy).min(client.1).ceil());
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        let scale = sx.min(sy);
+        let mut level = 0;
+        while level + 1 < self.mipmaps.len() && scale * ((1u64 << (level + 1)) as f64) <= 1.0 {
+            level += 1;
+        }
+
+        let source = &self.mipmaps[level];
+        let (fx, fy) = (source.width as f64 / iw, source.height as f64 / ih);
+        let origin = ((left - ox) / sx * fx, (top - oy) / sy * fy);
+
+        let mut pixels = ImageData::new((right - left) as u32, (bottom - top) as u32);
+        source.sample_into(&mut pixels, origin, (fx / sx, fy / sy));
+
+        Some((left as i32, top as i32, pixels))
+    }
+
+}
+
+/**
+    Paint the bitmap of a static control with a viewer. If the control already has a viewer, its scaling is changed
+    and the view is reset.
+*/
+pub unsafe fn set_viewer(hwnd: HWND, scaling: ImageScaling, zoomable: bool) {
+    use comctl32::SetWindowSubclass;
+
+    let created = VIEWERS.with(|v| {
+        let mut viewers = v.borrow_mut();
+        match viewers.get_mut(&(hwnd as usize)) {
+            Some(viewer) => { viewer.scaling = scaling; viewer.zoomable = zoomable; false },
+            None => { viewers.insert(hwnd as usize, Viewer::new(scaling, zoomable)); true }
+        }
+    });
+
+    if created {
+        SetWindowSubclass(hwnd, Some(process_viewer), VIEWER_SUBCLASS_ID, 0);
+        reload_image(hwnd);
+    }
+
+    update_view(hwnd, |viewer, client| viewer.reset(client));
+}
+
+/**
+    Remove the viewer of a static control. The control paints its bitmap again.
+*/
+pub unsafe fn free_viewer(hwnd: HWND) {
+    use comctl32::RemoveWindowSubclass;
+    use user32::{InvalidateRect, GetCapture, ReleaseCapture};
+
+    let viewer = VIEWERS.with(|v| v.borrow_mut().remove(&(hwnd as usize)));
+    if viewer.is_some() {
+        if GetCapture() == hwnd {
+            ReleaseCapture();
+        }
+
+        RemoveWindowSubclass(hwnd, Some(process_viewer), VIEWER_SUBCLASS_ID);
+        InvalidateRect(hwnd, ptr::null(), 1);
+    }
+}
+
+/**
+    Check if a control has a viewer
+*/
+pub fn has_viewer(hwnd: HWND) -> bool {
+    VIEWERS.with(|v| v.borrow().contains_key(&(hwnd as usize)))
+}
+
+/**
+    Return the scaling of a viewer. Controls without viewers center their image.
+*/
+pub fn get_scaling(hwnd: HWND) -> ImageScaling {
+    VIEWERS.with(|v| v.borrow().get(&(hwnd as usize)).map(|viewer| viewer.scaling).unwrap_or(ImageScaling::Center))
+}
+
+/**
+    Check if the user can zoom and pan the image of a control
+*/
+pub fn is_zoomable(hwnd: HWND) -> bool {
+    VIEWERS.with(|v| v.borrow().get(&(hwnd as usize)).map(|viewer| viewer.zoomable).unwrap_or(false))
+}
+
+/**
+    Return the view of a control. The view of a control without viewer is computed from its centered bitmap.
+*/
+pub unsafe fn get_view(hwnd: HWND) -> ViewData {
+    let client = client_size(hwnd);
+    let view = VIEWERS.with(|v| v.borrow().get(&(hwnd as usize)).map(|viewer| viewer.view(client)));
+
+    match view {
+        Some(view) => view,
+        None => {
+            let mut viewer = Viewer::new(ImageScaling::Center, false);
+            viewer.size = bitmap_size(hwnd);
+            viewer.reset(client);
+            viewer.view(client)
+        }
+    }
+}
+
+/**
+    Set the scale of the image in both directions. The pixel in the center of the control stays in place.
+*/
+pub unsafe fn set_zoom(hwnd: HWND, zoom: f64) {
+    update_view(hwnd, |viewer, client| {
+        let center = (client.0 / 2.0, client.1 / 2.0);
+        let anchor = ((center.0 - viewer.offset.0) / viewer.scale.0, (center.1 - viewer.offset.1) / viewer.scale.1);
+        let zoom = zoom.max(MIN_ZOOM).min(MAX_ZOOM);
+
+        viewer.scale = (zoom, zoom);
+        viewer.offset = (center.0 - anchor.0 * zoom, center.1 - anchor.1 * zoom);
+        viewer.free = true;
+        viewer.clamp(client);
+    });
+}
+
+/**
+    Multiply the scale of the image by `factor`. The image pixel under `point` (in client coordinates) stays in place.
+*/
+pub unsafe fn zoom_at(hwnd: HWND, factor: f64, point: (i32, i32)) {
+    update_view(hwnd, |viewer, client| viewer.zoom_at(factor, (point.0 as f64, point.1 as f64), client));
+}
+
+/**
+    Move the image of a control
+*/
+pub unsafe fn pan(hwnd: HWND, dx: i32, dy: i32) {
+    update_view(hwnd, |viewer, client| viewer.pan(dx as f64, dy as f64, client));
+}
+
+/**
+    Place the image using the scaling of the viewer, discarding the zoom and the pan of the user
+*/
+pub unsafe fn reset_view(hwnd: HWND) {
+    update_view(hwnd, |viewer, client| viewer.reset(client));
+}
+
+/**
+    Apply a change to a viewer. The control is repainted and, if the view changed, a `NWG_ZOOM_CHANGED` message is sent.
+    The viewers are not borrowed while the message is processed, so the callbacks can query the view.
+*/
+unsafe fn update_view<F: FnOnce(&mut Viewer, (f64, f64))>(hwnd: HWND, change: F) {
+    use user32::{InvalidateRect, SendMessageW};
+    use low::defs::NWG_ZOOM_CHANGED;
+
+    let client = client_size(hwnd);
+    let views = VIEWERS.with(|v| {
+        v.borrow_mut().get_mut(&(hwnd as usize)).map(|viewer| {
+            let before = viewer.view(client);
+            change(viewer, client);
+            (before, viewer.view(client))
+        })
+    });
+
+    if let Some((before, after)) = views {
+        InvalidateRect(hwnd, ptr::null(), 0);
+        if before != after {
+            SendMessageW(hwnd, NWG_ZOOM_CHANGED, 0, &after as *const ViewData as LPARAM);
+        }
+    }
+}
+
+/**
+    Read the bitmap of a control into the viewer. The view is reset if the new bitmap do not have the size of the old one
+    (animations keep their view).
+*/
+unsafe fn reload_image(hwnd: HWND) {
+    use user32::SendMessageW;
+    use winapi::IMAGE_BITMAP;
+    use low::defs::STM_GETIMAGE;
+    use low::image_helper::read_bitmap;
+
+    let bitmap = SendMessageW(hwnd, STM_GETIMAGE, IMAGE_BITMAP as WPARAM, 0) as HBITMAP;
+    let mut mipmaps = Vec::new();
+    if !bitmap.is_null() {
+        if let Ok((image, _)) = read_bitmap(bitmap) {
+            if image.width > 0 && image.height > 0 {
+                mipmaps.push(image);
+            }
+        }
+    }
+
+    // Reduce the image until it is a single pixel
+    loop {
+        let next = match mipmaps.last() {
+            Some(last) if last.width > 1 || last.height > 1 => last.half(last.width > 1, last.height > 1),
+            _ => { break; }
+        };
+        mipmaps.push(next);
+    }
+
+    update_view(hwnd, move |viewer, client| {
+        let size = mipmaps.first().map(|m| (m.width as f64, m.height as f64)).unwrap_or((0.0, 0.0));
+        let resized = size != viewer.size;
+
+        viewer.size = size;
+        viewer.mipmaps = mipmaps;
+        if resized {
+            viewer.reset(client);
+        }
+    });
+}
+
+unsafe fn client_size(hwnd: HWND) -> (f64, f64) {
+    use user32::GetClientRect;
+
+    let mut rect: RECT = mem::zeroed();
+    GetClientRect(hwnd, &mut rect);
+    ((rect.right - rect.left) as f64, (rect.bottom - rect.top) as f64)
+}
+
+unsafe fn bitmap_size(hwnd: HWND) -> (f64, f64) {
+    use user32::SendMessageW;
+    use gdi32::GetObjectW;
+    use winapi::{IMAGE_BITMAP, BITMAP, c_int};
+    use low::defs::STM_GETIMAGE;
+
+    let bitmap = SendMessageW(hwnd, STM_GETIMAGE, IMAGE_BITMAP as WPARAM, 0) as HBITMAP;
+    let mut bm: BITMAP = mem::zeroed();
+    if bitmap.is_null() || GetObjectW(bitmap as _, mem::size_of::<BITMAP>() as c_int, &mut bm as *mut BITMAP as *mut _) == 0 {
+        return (0.0, 0.0);
+    }
+
+    (bm.bmWidth.abs() as f64, bm.bmHeight.abs() as f64)
+}
+
+/**
+    Paint the background of a control and its resampled image. The painting is done in a memory bitmap
+    to avoid flickering while the image is panned.
+*/
+unsafe fn paint(hwnd: HWND, hdc: HDC) {
+    use winapi::{WM_CTLCOLORSTATIC, COLOR_BTNFACE, SRCCOPY, HBRUSH, BLENDFUNCTION, c_int};
+    use user32::{GetClientRect, GetParent, SendMessageW, FillRect, GetSysColorBrush};
+    use gdi32::{CreateCompatibleDC, CreateCompatibleBitmap, SelectObject, DeleteDC, DeleteObject, BitBlt, GdiAlphaBlend};
+    use low::image_helper::create_dib_section;
+    use low::defs::{AC_SRC_OVER, AC_SRC_ALPHA};
+
+    let mut rect: RECT = mem::zeroed();
+    GetClientRect(hwnd, &mut rect);
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    if width <= 0 || height <= 0 {
+        return;
+    }
+
+    let memory = CreateCompatibleDC(hdc);
+    let target = CreateCompatibleBitmap(hdc, width, height);
+    let old_target = SelectObject(memory, target as _);
+
+    // Like the builtin static controls, the background brush is provided by the parent
+    let mut brush = SendMessageW(GetParent(hwnd), WM_CTLCOLORSTATIC, memory as WPARAM, hwnd as LPARAM) as HBRUSH;
+    if brush.is_null() {
+        brush = GetSysColorBrush(COLOR_BTNFACE);
+    }
+    FillRect(memory, &rect, brush);
+
+    let client = (width as f64, height as f64);
+    let frame = VIEWERS.with(|v| v.borrow().get(&(hwnd as usize)).and_then(|viewer| viewer.render(client)));
+    if let Some((x, y, pixels)) = frame {
+        if let Ok(bitmap) = create_dib_section(pixels.width, pixels.height, &pixels.data) {
+            let source = CreateCompatibleDC(hdc);
+            let old_source = SelectObject(source, bitmap as _);
+            let blend = BLENDFUNCTION{ BlendOp: AC_SRC_OVER, BlendFlags: 0, SourceConstantAlpha: 255, AlphaFormat: AC_SRC_ALPHA };
+            let (w, h) = (pixels.width as c_int, pixels.height as c_int);
+
+            GdiAlphaBlend(memory, x, y, w, h, source, 0, 0, w, h, blend);
+
+            SelectObject(source, old_source);
+            DeleteDC(source);
+            DeleteObject(bitmap as _);
+        }
+    }
+
+    BitBlt(hdc, 0, 0, width, height, memory, 0, 0, SRCCOPY);
+
+    SelectObject(memory, old_target);
+    DeleteObject(target as _);
+    DeleteDC(memory);
+}
+
+fn set_pan(hwnd: HWND, pan: Option<(i32, i32)>) {
+    VIEWERS.with(|v| {
+        if let Some(viewer) = v.borrow_mut().get_mut(&(hwnd as usize)) {
+            viewer.pan = pan;
+        }
+    });
+}
+
+fn get_pan(hwnd: HWND) -> Option<(i32, i32)> {
+    VIEWERS.with(|v| v.borrow().get(&(hwnd as usize)).and_then(|viewer| viewer.pan))
+}
+
+/**
+    Proc that paints the image of a viewer and translates the mouse input into zoom and pan
+*/
+#[allow(unused_variables)]
+unsafe extern "system" fn process_viewer(hwnd: HWND, msg: UINT, w: WPARAM, l: LPARAM, id: UINT_PTR, data: DWORD_PTR) -> LRESULT {
+    use comctl32::DefSubclassProc;
+    use winapi::{WM_PAINT, WM_PRINTCLIENT, WM_ERASEBKGND, WM_SIZE, WM_MOUSEWHEEL, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MOUSEMOVE,
+      WM_CAPTURECHANGED, WM_SETCURSOR, WHEEL_DELTA, HTCLIENT, IDC_HAND, PAINTSTRUCT, POINT, LOWORD, GET_X_LPARAM, GET_Y_LPARAM,
+      GET_WHEEL_DELTA_WPARAM};
+    use user32::{BeginPaint, EndPaint, SetCapture, GetCapture, ReleaseCapture, SetFocus, ScreenToClient, SetCursor, LoadCursorW};
+    use low::defs::STM_SETIMAGE;
+
+    let pos = || (GET_X_LPARAM(l) as i32, GET_Y_LPARAM(l) as i32);
+
+    match msg {
+        WM_PAINT => {
+            let mut ps: PAINTSTRUCT = mem::zeroed();
+            let hdc = BeginPaint(hwnd, &mut ps);
+            paint(hwnd, hdc);
+            EndPaint(hwnd, &ps);
+            0
+        },
+        WM_PRINTCLIENT => {
+            paint(hwnd, w as HDC);
+            0
+        },
+        WM_ERASEBKGND => 1,
+        WM_SIZE => {
+            let result = DefSubclassProc(hwnd, msg, w, l);
+            update_view(hwnd, |viewer, client| if viewer.free { viewer.clamp(client) } else { viewer.reset(client) });
+            result
+        },
+        STM_SETIMAGE => {
+            let result = DefSubclassProc(hwnd, msg, w, l);
+            reload_image(hwnd);
+            result
+        },
+        WM_MOUSEWHEEL if is_zoomable(hwnd) => {
+            let mut point = POINT{ x: GET_X_LPARAM(l), y: GET_Y_LPARAM(l) };
+            ScreenToClient(hwnd, &mut point);
+
+            let notches = GET_WHEEL_DELTA_WPARAM(w) as f64 / WHEEL_DELTA as f64;
+            zoom_at(hwnd, WHEEL_ZOOM.powf(notches), (point.x as i32, point.y as i32));
+            0
+        },
+        WM_LBUTTONDOWN if is_zoomable(hwnd) => {
+            // The wheel messages are sent to the focused window
+            SetFocus(hwnd);
+            SetCapture(hwnd);
+            set_pan(hwnd, Some(pos()));
+            DefSubclassProc(hwnd, msg, w, l)
+        },
+        WM_MOUSEMOVE => {
+            if let Some((x0, y0)) = get_pan(hwnd) {
+                let (x, y) = pos();
+                set_pan(hwnd, Some((x, y)));
+                pan(hwnd, x - x0, y - y0);
+            }
+            DefSubclassProc(hwnd, msg, w, l)
+        },
+        WM_LBUTTONUP => {
+            if get_pan(hwnd).is_some() && GetCapture() == hwnd {
+                ReleaseCapture();
+            }
+            set_pan(hwnd, None);
+            DefSubclassProc(hwnd, msg, w, l)
+        },
+        WM_CAPTURECHANGED => {
+            set_pan(hwnd, None);
+            DefSubclassProc(hwnd, msg, w, l)
+        },
+        WM_SETCURSOR if is_zoomable(hwnd) && LOWORD(l as u32) as i32 == HTCLIENT => {
+            SetCursor(LoadCursorW(ptr::null_mut(), IDC_HAND));
+            1
+        },
+        _ => DefSubclassProc(hwnd, msg, w, l)
+    }
+}
@@ -138,22 +138,24 @@ pub unsafe fn build_window<S1: Into<String>, S2: Into<String>>(p: WindowParams<S
     let class_name = to_utf16(p.class_name.into().as_ref());
     let window_name = to_utf16(p.title.into().as_ref());
 
-    let px = match p.position.0 { 
+    let (position, size) = scale_window_geometry(p.parent, p.position, p.size);
+
+    let px = match position.0 { 
         ::defs::CENTER_POSITION => {
             let mut rect: RECT = mem::uninitialized();
             let parent = if p.parent.is_null() { GetDesktopWindow() } else {p.parent};
             GetWindowRect(parent, &mut rect);
-            (rect.right/2) - ((p.size.0/2) as i32)
+            (rect.right/2) - ((size.0/2) as i32)
         },
         x => x
     };
 
-    let py = match p.position.1 { 
+    let py = match position.1 { 
         ::defs::CENTER_POSITION => {
             let mut rect: RECT = mem::uninitialized();
             let parent = if p.parent.is_null() { GetDesktopWindow() } else {p.parent};
             GetWindowRect(parent, &mut rect);
-            (rect.bottom/2) - ((p.size.1/2) as i32)
+            (rect.bottom/2) - ((size.1/2) as i32)
         },
         y => y
     };
@@ -168,7 +170,7 @@ pub unsafe fn build_window<S1: Into<String>, S2: Into<String>>(p: WindowParams<S
         class_name.as_ptr(), window_name.as_ptr(),
         p.flags,
         px, py,
-        p.size.0 as i32, p.size.1 as i32,
+        size.0 as i32, size.1 as i32,
         p.parent,
         ptr::null_mut(),
         hmod,
@@ -178,11 +180,43 @@ pub unsafe fn build_window<S1: Into<String>, S2: Into<String>>(p: WindowParams<S
     if handle.is_null() {
         Err(SystemError::WindowCreationFail)
     } else {
-        fix_overlapped_window_size(handle, p.size);
+        fix_overlapped_window_size(handle, size);
         Ok(handle)
     }
 }
 
+/**
+    If the process is DPI aware, convert the position and the size of a new window from logical units
+    to physical pixels. Children use the DPI of their parent and top level windows use the DPI of the monitor
+    at their position. Special position and size values are left untouched.
+*/
+unsafe fn scale_window_geometry(parent: HWND, position: (i32, i32), size: (u32, u32)) -> ((i32, i32), (u32, u32)) {
+    use low::dpi_helper::{dpi_aware, window_dpi, point_dpi, system_dpi};
+    use defs::{DEFAULT_POSITION, CENTER_POSITION, DEFAULT_SIZE};
+    use dpi::to_physical;
+
+    if !dpi_aware() { return (position, size); }
+
+    let is_special = |v: i32| v == DEFAULT_POSITION || v == CENTER_POSITION;
+    let (x, y) = position;
+    let (w, h) = size;
+
+    let dpi = if !parent.is_null() {
+        window_dpi(parent)
+    } else if is_special(x) || is_special(y) {
+        system_dpi()
+    } else {
+        point_dpi(x, y)
+    };
+
+    let x = if is_special(x) { x } else { to_physical(x, dpi) };
+    let y = if is_special(y) { y } else { to_physical(y, dpi) };
+    let w = if w as i32 == DEFAULT_SIZE { w } else { to_physical(w as i32, dpi) as u32 };
+    let h = if h as i32 == DEFAULT_SIZE { h } else { to_physical(h as i32, dpi) as u32 };
+
+    ((x, y), (w, h))
+}
+
 /** 
     Fix: Window size include the non client area. This behaviour is not wanted
     Resize the client area to match the "true" size. 
@@ -310,55 +344,69 @@ pub unsafe fn set_window_text<'a>(handle: HWND, text: &'a str) {
 }
 
 
-/// Set window position
+/// Set window position. If the process is DPI aware, the position is in logical units.
 #[inline(always)]
 pub unsafe fn set_window_position(handle: HWND, x: i32, y: i32) {
     use user32::SetWindowPos;
     use winapi::{c_int, SWP_NOZORDER, SWP_NOSIZE, SWP_NOACTIVATE};
+    use low::dpi_helper::logical_dpi;
+    use dpi::point_to_physical;
+
+    let (x, y) = point_to_physical((x, y), logical_dpi(handle));
 
     SetWindowPos(handle, ptr::null_mut(), x as c_int, y as c_int, 0, 0, SWP_NOZORDER|SWP_NOSIZE|SWP_NOACTIVATE);
 }
 
-/// Get window position
+/// Get window position. If the process is DPI aware, the position is in logical units.
 #[inline(always)]
 pub unsafe fn get_window_position(handle: HWND) -> (i32, i32) {
     use user32::{GetWindowRect, ScreenToClient, GetParent};
     use winapi::{RECT, POINT};
+    use low::dpi_helper::logical_dpi;
+    use dpi::point_to_logical;
     
     let mut r: RECT = mem::uninitialized();
     GetWindowRect(handle, &mut r);
 
     let parent = GetParent(handle);
-    if !parent.is_null() {
+    let pos = if !parent.is_null() {
         let mut pt = POINT{x: r.left, y: r.top};
         ScreenToClient(parent, &mut pt);
         (pt.x as i32, pt.y as i32)
     } else {
         (r.left as i32, r.top as i32)
-    }
+    };
+
+    point_to_logical(pos, logical_dpi(handle))
 }
 
-/// Set window size
+/// Set window size. If the process is DPI aware, the size is in logical units.
 #[inline(always)]
 pub unsafe fn set_window_size(handle: HWND, w: u32, h: u32, fix: bool) {
     use user32::{SetWindowPos};
     use winapi::{c_int, SWP_NOZORDER, SWP_NOMOVE, SWP_NOACTIVATE, SWP_NOCOPYBITS};
+    use low::dpi_helper::logical_dpi;
+    use dpi::size_to_physical;
+
+    let (w, h) = size_to_physical((w, h), logical_dpi(handle));
 
     SetWindowPos(handle, ptr::null_mut(), 0, 0, w as c_int, h as c_int, SWP_NOZORDER|SWP_NOMOVE|SWP_NOACTIVATE|SWP_NOCOPYBITS);
 
     if fix { fix_overlapped_window_size(handle, (w, h)); }
 }
 
-/// Get window size
+/// Get window size. If the process is DPI aware, the size is in logical units.
 #[inline(always)]
 pub unsafe fn get_window_size(handle: HWND) -> (u32, u32) {
     use user32::GetClientRect;
     use winapi::RECT;
+    use low::dpi_helper::logical_dpi;
+    use dpi::size_to_logical;
     
     let mut r: RECT = mem::uninitialized();
     GetClientRect(handle, &mut r);
 
-    (r.right as u32, r.bottom as u32)
+    size_to_logical((r.right as u32, r.bottom as u32), logical_dpi(handle))
 }
 
 /// Get the window enabled state
@@ -407,6 +455,51 @@ pub unsafe fn get_window_visibility(handle: HWND) -> bool {
     IsWindowVisible(handle) != 0
 }
 
+/**
+    Set the opacity of a top level window, from 0.0 (transparent) to 1.0 (opaque).
+    An opaque window stops being a layered window, because layered windows are slower to draw.
+*/
+pub unsafe fn set_window_opacity(handle: HWND, opacity: f32) {
+    use user32::{SetLayeredWindowAttributes, RedrawWindow};
+    use winapi::{GWL_EXSTYLE, WS_EX_LAYERED, LWA_ALPHA, RDW_ERASE, RDW_INVALIDATE, RDW_FRAME, RDW_ALLCHILDREN, BYTE};
+
+    let ex_style = get_window_long(handle, GWL_EXSTYLE) as DWORD;
+    let layered = ex_style & WS_EX_LAYERED == WS_EX_LAYERED;
+
+    if !(opacity < 1.0) {
+        if layered {
+            set_window_long(handle, GWL_EXSTYLE, (ex_style & !WS_EX_LAYERED) as usize);
+            RedrawWindow(handle, ptr::null(), ptr::null_mut(), RDW_ERASE|RDW_INVALIDATE|RDW_FRAME|RDW_ALLCHILDREN);
+        }
+        return;
+    }
+
+    if !layered {
+        set_window_long(handle, GWL_EXSTYLE, (ex_style | WS_EX_LAYERED) as usize);
+    }
+
+    let alpha = if opacity > 0.0 { (opacity * 255.0).round() as BYTE } else { 0 };
+    SetLayeredWindowAttributes(handle, 0, alpha, LWA_ALPHA);
+}
+
+/**
+    Get the opacity of a top level window, from 0.0 (transparent) to 1.0 (opaque)
+*/
+pub unsafe fn get_window_opacity(handle: HWND) -> f32 {
+    use winapi::{GWL_EXSTYLE, WS_EX_LAYERED, LWA_ALPHA, BYTE, COLORREF};
+    use low::defs::GetLayeredWindowAttributes;
+
+    let ex_style = get_window_long(handle, GWL_EXSTYLE) as DWORD;
+    if ex_style & WS_EX_LAYERED != WS_EX_LAYERED { return 1.0; }
+
+    let (mut key, mut alpha, mut flags): (COLORREF, BYTE, DWORD) = (0, 255, 0);
+    if GetLayeredWindowAttributes(handle, &mut key, &mut alpha, &mut flags) == 0 || flags & LWA_ALPHA == 0 {
+        return 1.0;
+    }
+
+    (alpha as f32) / 255.0
+}
+
 /**
     Send a WM_PAINT event to the window if it must be redrawn
 */
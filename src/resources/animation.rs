@@ -0,0 +1,155 @@
+/*!
+    Animation resources creation
+*/
+
+use std::any::TypeId;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use winapi::HBITMAP;
+
+use ui::Ui;
+use controls::AnyHandle;
+use resources::{ResourceT, Resource, AnimationData};
+use error::Error;
+
+/**
+    A template that creates an animation resource from an animated GIF or PNG file.
+    The other image formats create an animation with a single frame.
+
+    Params:  
+    • `source`: The path to the image file  
+*/
+#[derive(Clone)]
+pub struct AnimationT<S: Clone+Into<String>> {
+    pub source: S
+}
+
+impl<ID: Clone+Hash, S: Clone+Into<String>> ResourceT<ID> for AnimationT<S> {
+    fn type_id(&self) -> TypeId { TypeId::of::<Animation>() }
+
+    #[allow(unused_variables)]
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+        use std::fs;
+
+        let path: String = self.source.clone().into();
+        match fs::read(&path) {
+            Ok(data) => Animation::from_data(&AnimationData::decode(&data)?),
+            Err(e) => Err(Error::BadResource(format!("Could not read the animation {}: {}", path, e)))
+        }
+    }
+}
+
+/**
+    A template that creates an animation resource from the content of an animated GIF or PNG file.
+
+    Params:  
+    • `source`: The content of the image file  
+*/
+#[derive(Clone)]
+pub struct MemoryAnimationT {
+    pub source: Vec<u8>
+}
+
+impl<ID: Clone+Hash> ResourceT<ID> for MemoryAnimationT {
+    fn type_id(&self) -> TypeId { TypeId::of::<Animation>() }
+
+    #[allow(unused_variables)]
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+        Animation::from_data(&AnimationData::decode(&self.source)?)
+    }
+}
+
+/**
+    A template that creates an animation resource from frames built by the application (ex: a spinner).
+
+    Params:  
+    • `animation`: The frames of the animation. Every frame must have the same size.  
+*/
+#[derive(Clone)]
+pub struct FramesAnimationT {
+    pub animation: AnimationData
+}
+
+impl<ID: Clone+Hash> ResourceT<ID> for FramesAnimationT {
+    fn type_id(&self) -> TypeId { TypeId::of::<Animation>() }
+
+    #[allow(unused_variables)]
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+        Animation::from_data(&self.animation)
+    }
+}
+
+/**
+    The bitmaps of an animation. Shared between the resource and the image frames playing it,
+    so that unpacking the resource does not free the bitmaps of a running animation.
+*/
+pub struct AnimationFrames {
+    pub bitmaps: Vec<HBITMAP>,
+    pub delays: Vec<u32>,
+    pub loop_count: u32,
+    pub size: (u32, u32)
+}
+
+impl Drop for AnimationFrames {
+    fn drop(&mut self) {
+        use gdi32::DeleteObject;
+        for &bitmap in self.bitmaps.iter() {
+            unsafe{ DeleteObject(bitmap as _); }
+        }
+    }
+}
+
+/**
+    An animation resource. An animation can be played by an `ImageFrame` (see `ImageFrame::set_animation`).
+*/
+pub struct Animation {
+    frames: Rc<AnimationFrames>
+}
+
+impl Animation {
+
+    fn from_data(animation: &AnimationData) -> Result<Box<Resource>, Error> {
+        use low::image_helper::bitmap_from_data;
+
+        let size = animation.size();
+        if animation.frames.is_empty() {
+            return Err(Error::BadResource("An animation must have at least one frame".to_string()));
+        }
+
+        if animation.frames.iter().any(|f| (f.image.width, f.image.height) != size) {
+            return Err(Error::BadResource("The frames of an animation must have the same size".to_string()));
+        }
+
+        // The bitmaps created before an error are freed when `frames` is dropped
+        let mut frames = AnimationFrames{ bitmaps: Vec::with_capacity(animation.frames.len()), delays: Vec::with_capacity(animation.frames.len()), loop_count: animation.loop_count, size: size };
+        for frame in animation.frames.iter() {
+            frames.bitmaps.push(unsafe{ bitmap_from_data(&frame.image)? });
+            frames.delays.push(frame.delay);
+        }
+
+        Ok( Box::new( Animation{ frames: Rc::new(frames) } ) )
+    }
+
+    /// Return the number of frames of the animation
+    pub fn frame_count(&self) -> usize { self.frames.bitmaps.len() }
+
+    /// Return the size of the frames of the animation
+    pub fn get_size(&self) -> (u32, u32) { self.frames.size }
+
+    /// Return the number of times the animation is played. `0` plays the animation forever.
+    pub fn loop_count(&self) -> u32 { self.frames.loop_count }
+
+    /// Return the delay of every frame in milliseconds
+    pub fn delays(&self) -> &[u32] { &self.frames.delays }
+
+    /// Return the shared bitmaps of the animation
+    pub(crate) fn frames(&self) -> Rc<AnimationFrames> { self.frames.clone() }
+
+}
+
+impl Resource for Animation {
+    fn handle(&self) -> AnyHandle {
+        AnyHandle::Custom(TypeId::of::<Animation>(), &*self.frames as *const AnimationFrames as usize)
+    }
+}
@@ -0,0 +1,105 @@
+/*!
+    The drawing backends of the canvas renderer. A backend receives the shapes with their brushes already resolved,
+    so it does not need Direct2D nor the Ui to draw.
+*/
+
+use std::rc::Rc;
+
+use super::defs::{LinearGradientBrush, RadialGradientBrush, ExtendMode, BitmapInterpolation, Pen, PathData,
+  Rectangle, Matrix, TextStyle};
+use super::scene::Shape;
+use resources::ImageData;
+use error::Error;
+
+/**
+    How a backend paints the inside of a shape. This is the content of a canvas brush.
+
+    • `Solid`: A single color (red, green, blue, alpha)  
+    • `Linear`: A gradient along a line  
+    • `Radial`: A gradient from the origin of an ellipse to its edge  
+    • `Bitmap`: An image with its top left corner at the origin. One pixel covers one device independent pixel  
+*/
+#[derive(Clone, Debug)]
+pub enum Paint {
+    Solid((f32, f32, f32, f32)),
+    Linear(LinearGradientBrush),
+    Radial(RadialGradientBrush),
+    Bitmap{ image: Rc<ImageData>, extend: (ExtendMode, ExtendMode), interpolation: BitmapInterpolation }
+}
+
+/**
+    A target for the drawing commands of a `CanvasRenderer`. See `Canvas::render_with`.
+
+    The coordinates received by the drawing functions are transformed by the current matrix. Clips and layers
+    are always popped in the reverse order of the pushes, the renderer checks it before calling the backend.
+*/
+pub trait RenderBackend {
+
+    /// Return the size of the drawing area (`width`, `height`)
+    fn get_size(&self) -> (f32, f32);
+
+    /// Replace the drawing area inside the current clip by `color` (red, green, blue, alpha)
+    fn clear(&mut self, color: (f32, f32, f32, f32));
+
+    /// Set the transformation applied to everything drawn after the call
+    fn set_matrix(&mut self, m: &Matrix);
+
+    /// Return the current transformation
+    fn get_matrix(&self) -> Matrix;
+
+    /// Restrict the drawing to the bounds of the rectangle `r` transformed by the current matrix
+    fn push_clip(&mut self, r: &Rectangle);
+
+    /// Remove the last clip
+    fn pop_clip(&mut self);
+
+    /**
+        Draw in a layer until `pop_layer`. The layer is blended with `opacity` and only its part inside `mask`
+        and `bounds` is kept. Backends that do not support layers return an error.
+    */
+    fn push_layer(&mut self, opacity: f32, mask: Option<&PathData>, bounds: Option<&Rectangle>) -> Result<(), Error> {
+        let _ = (opacity, mask, bounds);
+        Err(Error::UserError("Layers are not supported by this renderer backend".to_string()))
+    }
+
+    /// Blend the last layer with the shapes under it
+    fn pop_layer(&mut self) {}
+
+    /// Fill the figures of `path`. Figures that are not closed are filled as if they were.
+    fn fill_path(&mut self, path: &PathData, paint: &Paint) -> Result<(), Error>;
+
+    /// Draw the outline of `path` with a line of `width`. `None` draws a plain line.
+    fn stroke_path(&mut self, path: &PathData, paint: &Paint, pen: Option<&Pen>, width: f32) -> Result<(), Error>;
+
+    /**
+        Draw the area `src` of `image` in the rectangle `dest`. `src` is in pixels of the image and
+        `None` draws the whole image.
+    */
+    fn draw_image(&mut self, image: &ImageData, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32,
+      interpolation: BitmapInterpolation) -> Result<(), Error>;
+
+    /**
+        Draw `text` in the layout box `bounds`. Backends that cannot draw text return an error.
+    */
+    fn draw_text(&mut self, text: &str, style: &TextStyle, bounds: &Rectangle, paint: &Paint) -> Result<(), Error> {
+        let _ = (text, style, bounds, paint);
+        Err(Error::UserError("Text cannot be drawn by this renderer backend".to_string()))
+    }
+
+    /// Fill a shape. Lines and the boxes of text shapes are not filled.
+    fn fill_shape(&mut self, shape: &Shape, paint: &Paint) -> Result<(), Error> {
+        match shape {
+            &Shape::Line{..} | &Shape::Text{..} => Ok(()),
+            s => self.fill_path(&s.to_path(), paint)
+        }
+    }
+
+    /// Draw the outline of a shape. The boxes of text shapes are not drawn.
+    fn stroke_shape(&mut self, shape: &Shape, paint: &Paint, pen: Option<&Pen>, width: f32) -> Result<(), Error> {
+        match shape {
+            &Shape::Text{..} => Ok(()),
+            s => self.stroke_path(&s.to_path(), paint, pen, width)
+        }
+    }
+
+}
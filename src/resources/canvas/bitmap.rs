@@ -0,0 +1,174 @@
+/*!
+    Bitmap resources for a canvas control
+*/
+use std::hash::Hash;
+use std::any::TypeId;
+use std::rc::Rc;
+
+use controls::{Canvas, AnyHandle, ControlType};
+use resources::{ResourceT, Resource, Image, ImageData};
+use error::{Error, SystemError};
+use ui::Ui;
+
+use winapi::{ID2D1Bitmap};
+
+/**
+    A template that uploads the pixels of an image resource to a Canvas control. The resource is unpacked with the canvas.
+    One pixel of the image covers one device independent pixel of the canvas.
+
+    Params:  
+    • `canvas`: The canvas control that will use the resource  
+    • `image`: The id of an image resource. The image must be created before the bitmap  
+*/
+#[derive(Clone)]
+pub struct BitmapT<ID: Hash+Clone> {
+    pub canvas: ID,
+    pub image: ID
+}
+
+impl<ID: Hash+Clone> ResourceT<ID> for BitmapT<ID> {
+    fn type_id(&self) -> TypeId {
+        TypeId::of::<Bitmap>()
+    }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+        let image = match ui.get::<Image>(&self.image) {
+            Ok(i) => i.to_data(),
+            Err(_) => { return Err(Error::BadResource("The image of a canvas bitmap must be an image resource".to_string())); }
+        };
+
+        match image {
+            Ok(image) => build_bitmap(ui, &self.canvas, image),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+/**
+    A template that uploads raw pixels to a Canvas control. The resource is unpacked with the canvas.
+    One pixel of the image covers one device independent pixel of the canvas.
+
+    Params:  
+    • `canvas`: The canvas control that will use the resource  
+    • `size`: The size of the image in pixels (width, height)  
+    • `rgba`: The pixels of the image as straight alpha RGBA values, top-down, 4 bytes per pixel  
+*/
+#[derive(Clone)]
+pub struct RgbaBitmapT<ID: Hash+Clone> {
+    pub canvas: ID,
+    pub size: (u32, u32),
+    pub rgba: Vec<u8>
+}
+
+impl<ID: Hash+Clone> ResourceT<ID> for RgbaBitmapT<ID> {
+    fn type_id(&self) -> TypeId {
+        TypeId::of::<Bitmap>()
+    }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+        match ImageData::from_rgba(self.size.0, self.size.1, &self.rgba) {
+            Ok(image) => build_bitmap(ui, &self.canvas, image),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+
+/**
+    A canvas bitmap resource. Drawn with `CanvasRenderer::draw_bitmap`.
+*/
+pub struct Bitmap {
+    handle: *mut ID2D1Bitmap,
+    size: (u32, u32),
+    data: Rc<ImageData>
+}
+
+impl Bitmap {
+
+    /**
+        Return the size of the bitmap in pixels (`width`, `height`)
+    */
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /**
+        Return the pixels uploaded to the canvas. Used by the renderer backends.
+    */
+    pub fn get_data(&self) -> &ImageData {
+        &self.data
+    }
+
+}
+
+impl Resource for Bitmap {
+
+    /**
+        Should return the underlying handle to the object
+    */
+    fn handle(&self) -> AnyHandle {
+        AnyHandle::Custom(TypeId::of::<Bitmap>(), self.handle as usize)
+    }
+
+    /**
+        If specified, should free any ressource allocated in the template `build` function.
+    */
+    fn free(&mut self) {
+        if !self.handle.is_null() {
+            unsafe{ (&mut *self.handle).Release(); }
+            self.handle = ptr::null_mut();
+        }
+    }
+
+}
+
+
+// Private functions
+use winapi::{ID2D1RenderTarget, S_OK};
+use std::ptr;
+
+fn build_bitmap<ID: Hash+Clone>(ui: &Ui<ID>, canvas: &ID, image: ImageData) -> Result<Box<Resource>, Error> {
+    match ui.type_of_control(canvas) {
+        Ok(ControlType::Canvas) => {/* All good */},
+        Ok(t) => { return Err(Error::BadParent( format!("A Bitmap resource canvas must be a Canvas control. Got {:?}", t) )); }
+        Err(e) => { return Err(e); }
+    }
+
+    let rt = match ui.get::<Canvas<ID>>(canvas) {
+        Ok(c) => c.get_render_target(),
+        Err(_) => { unreachable!(); } // ui.type_of_control already check this
+    };
+
+    let rt = unsafe{&mut * rt};
+    match create_bitmap(rt, &image) {
+        Ok(h) => {
+            Ok(Box::new( Bitmap{ handle: h, size: (image.width, image.height), data: Rc::new(image) } ))
+        },
+        Err(e) => { Err(e) }
+    }
+}
+
+/**
+    Upload `image` to the render target. One pixel of the image covers one device independent pixel.
+*/
+pub(crate) fn create_bitmap(rt: &mut ID2D1RenderTarget, image: &ImageData) -> Result<*mut ID2D1Bitmap, Error> {
+    use winapi::{D2D1_SIZE_U, D2D1_BITMAP_PROPERTIES, D2D1_PIXEL_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM, D2D1_ALPHA_MODE_PREMULTIPLIED};
+
+    if image.width == 0 || image.height == 0 {
+        return Err(Error::BadResource("A canvas bitmap cannot be empty".to_string()));
+    }
+
+    // The image data is already premultiplied BGRA
+    let size = D2D1_SIZE_U{ width: image.width, height: image.height };
+    let format = D2D1_PIXEL_FORMAT{ format: DXGI_FORMAT_B8G8R8A8_UNORM, alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED };
+    let property = D2D1_BITMAP_PROPERTIES{ pixelFormat: format, dpiX: 96.0, dpiY: 96.0 };
+
+    let mut bitmap: *mut ID2D1Bitmap = ptr::null_mut();
+    let result = unsafe{ rt.CreateBitmap(size, image.data.as_ptr() as *const _, image.width * 4, &property, &mut bitmap) };
+
+    if result == S_OK {
+        Ok(bitmap)
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to create the canvas bitmap".to_string())))
+    }
+}
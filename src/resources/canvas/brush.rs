@@ -3,14 +3,16 @@
 */
 use std::hash::Hash;
 use std::any::TypeId;
+use std::rc::Rc;
 
-use super::defs::{BrushType, SolidBrush};
+use super::defs::{BrushType, SolidBrush, LinearGradientBrush, RadialGradientBrush, GradientStop, ExtendMode, BitmapInterpolation};
+use super::backend::Paint;
 use controls::{Canvas, AnyHandle, ControlType};
-use resources::{ResourceT, Resource};
+use resources::{ResourceT, Resource, Image, ImageData};
 use error::{Error, SystemError};
 use ui::Ui;
 
-use winapi::{ID2D1SolidColorBrush};
+use winapi::{ID2D1Brush, ID2D1SolidColorBrush, ID2D1LinearGradientBrush, ID2D1RadialGradientBrush, ID2D1BitmapBrush};
 
 /**
     A template that creates a brush used by a Canvas control. The resource is unpacked with the canvas.
@@ -22,7 +24,7 @@ use winapi::{ID2D1SolidColorBrush};
 #[derive(Clone)]
 pub struct BrushT<ID: Hash+Clone> {
     pub canvas: ID,
-    pub btype: BrushType
+    pub btype: BrushType<ID>
 }
 
 impl<ID: Hash+Clone> ResourceT<ID> for BrushT<ID> {
@@ -44,13 +46,29 @@ impl<ID: Hash+Clone> ResourceT<ID> for BrushT<ID> {
         };
 
         let rt = unsafe{&mut * rt};
-        let handle = match &self.btype {
-            &BrushType::SolidBrush(ref c) => create_solid_brush(rt, c)
+        let (handle, paint) = match &self.btype {
+            &BrushType::SolidBrush(ref c) => (create_solid_brush(rt, c), Paint::Solid(c.color)),
+            &BrushType::LinearGradientBrush(ref g) => (create_linear_brush(rt, g), Paint::Linear(g.clone())),
+            &BrushType::RadialGradientBrush(ref g) => (create_radial_brush(rt, g), Paint::Radial(g.clone())),
+            &BrushType::BitmapBrush(ref b) => {
+                let image = match ui.get::<Image>(&b.image) {
+                    Ok(i) => i.to_data(),
+                    Err(_) => { return Err(Error::BadResource("The image of a bitmap brush must be an image resource".to_string())); }
+                };
+
+                let image = match image {
+                    Ok(i) => Rc::new(i),
+                    Err(e) => { return Err(e); }
+                };
+
+                let paint = Paint::Bitmap{ image: image.clone(), extend: b.extend, interpolation: b.interpolation };
+                (create_bitmap_brush(rt, &image, b.extend, b.interpolation), paint)
+            }
         };
 
         match handle {
             Ok(h) => { 
-                Ok(Box::new( Brush{ handle: h } ))
+                Ok(Box::new( Brush{ handle: h, paint: paint } ))
             },
             Err(e) => { Err(e) }
         }
@@ -62,12 +80,38 @@ impl<ID: Hash+Clone> ResourceT<ID> for BrushT<ID> {
     A brush resource
 */
 pub struct Brush {
-    handle: BrushHandle
+    handle: BrushHandle,
+    paint: Paint
+}
+
+impl Brush {
+
+    /**
+        Return how the brush paints. Used by the renderer backends.
+    */
+    pub fn get_paint(&self) -> &Paint {
+        &self.paint
+    }
+
 }
 
 #[derive(Clone, Copy)]
 enum BrushHandle {
-    SolidBrush(*mut ID2D1SolidColorBrush)
+    SolidBrush(*mut ID2D1SolidColorBrush),
+    LinearGradientBrush(*mut ID2D1LinearGradientBrush),
+    RadialGradientBrush(*mut ID2D1RadialGradientBrush),
+    BitmapBrush(*mut ID2D1BitmapBrush)
+}
+
+impl BrushHandle {
+    fn as_brush(&self) -> *mut ID2D1Brush {
+        match self {
+            &BrushHandle::SolidBrush(h) => h as *mut ID2D1Brush,
+            &BrushHandle::LinearGradientBrush(h) => h as *mut ID2D1Brush,
+            &BrushHandle::RadialGradientBrush(h) => h as *mut ID2D1Brush,
+            &BrushHandle::BitmapBrush(h) => h as *mut ID2D1Brush
+        }
+    }
 }
 
 impl Resource for Brush {
@@ -76,28 +120,39 @@ impl Resource for Brush {
         Should return the underlying handle to the object
     */
     fn handle(&self) -> AnyHandle {
-        let handle = match self.handle {
-            BrushHandle::SolidBrush(h) => h as usize
-        };
-
-        AnyHandle::Custom(TypeId::of::<Brush>(), handle)
+        AnyHandle::Custom(TypeId::of::<Brush>(), self.handle.as_brush() as usize)
     }
 
     /**
         If specified, should free any ressource allocated in the template `build` function.
     */
     fn free(&mut self) {
-        // TODO
+        unsafe{ (&mut *self.handle.as_brush()).Release(); }
     }
 
 }
 
 
 // Private functions
-use winapi::{ID2D1HwndRenderTarget, D2D1_MATRIX_3X2_F, S_OK};
+use winapi::{ID2D1RenderTarget, ID2D1GradientStopCollection, D2D1_MATRIX_3X2_F, S_OK};
+use super::bitmap::create_bitmap;
 use std::ptr;
 
-fn create_solid_brush(rt: &mut ID2D1HwndRenderTarget, b: &SolidBrush) -> Result<BrushHandle, Error> {
+/**
+    Create a brush painting `paint`. The brush is released by the caller.
+*/
+pub(crate) fn create_paint_brush(rt: &mut ID2D1RenderTarget, paint: &Paint) -> Result<*mut ID2D1Brush, Error> {
+    let handle = match paint {
+        &Paint::Solid(c) => create_solid_brush(rt, &SolidBrush{ color: c }),
+        &Paint::Linear(ref g) => create_linear_brush(rt, g),
+        &Paint::Radial(ref g) => create_radial_brush(rt, g),
+        &Paint::Bitmap{ ref image, extend, interpolation } => create_bitmap_brush(rt, image, extend, interpolation)
+    };
+
+    handle.map(|h| h.as_brush())
+}
+
+fn create_solid_brush(rt: &mut ID2D1RenderTarget, b: &SolidBrush) -> Result<BrushHandle, Error> {
     use winapi::{D2D1_COLOR_F, D2D1_BRUSH_PROPERTIES};
 
     let c = &b.color;
@@ -112,4 +167,117 @@ fn create_solid_brush(rt: &mut ID2D1HwndRenderTarget, b: &SolidBrush) -> Result<
     } else {
         Err(Error::System(SystemError::ComError("Failed to import brush".to_string())))
     }
-}
\ No newline at end of file
+}
+
+fn create_linear_brush(rt: &mut ID2D1RenderTarget, g: &LinearGradientBrush) -> Result<BrushHandle, Error> {
+    use winapi::{D2D1_POINT_2F, D2D1_BRUSH_PROPERTIES, D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES};
+
+    let stops = match create_gradient_stops(rt, &g.stops, g.extend) {
+        Ok(s) => s,
+        Err(e) => { return Err(e); }
+    };
+
+    let identity = D2D1_MATRIX_3X2_F {matrix: [[1.0, 0.0],[0.0, 1.0],[0.0, 0.0]]};
+    let property = D2D1_BRUSH_PROPERTIES { opacity: 1.0, transform: identity};
+    let gradient = D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES {
+        startPoint: D2D1_POINT_2F{ x: g.start.0, y: g.start.1 },
+        endPoint: D2D1_POINT_2F{ x: g.end.0, y: g.end.1 }
+    };
+
+    let mut brush: *mut ID2D1LinearGradientBrush = ptr::null_mut();
+    let result = unsafe{ 
+        let result = rt.CreateLinearGradientBrush(&gradient, &property, stops, &mut brush);
+        (&mut *stops).Release();
+        result
+    };
+
+    if result == S_OK {
+        Ok(BrushHandle::LinearGradientBrush(brush))
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to import brush".to_string())))
+    }
+}
+
+fn create_radial_brush(rt: &mut ID2D1RenderTarget, g: &RadialGradientBrush) -> Result<BrushHandle, Error> {
+    use winapi::{D2D1_POINT_2F, D2D1_BRUSH_PROPERTIES, D2D1_RADIAL_GRADIENT_BRUSH_PROPERTIES};
+
+    let stops = match create_gradient_stops(rt, &g.stops, g.extend) {
+        Ok(s) => s,
+        Err(e) => { return Err(e); }
+    };
+
+    let identity = D2D1_MATRIX_3X2_F {matrix: [[1.0, 0.0],[0.0, 1.0],[0.0, 0.0]]};
+    let property = D2D1_BRUSH_PROPERTIES { opacity: 1.0, transform: identity};
+    let gradient = D2D1_RADIAL_GRADIENT_BRUSH_PROPERTIES {
+        center: D2D1_POINT_2F{ x: g.center.0, y: g.center.1 },
+        gradientOriginOffset: D2D1_POINT_2F{ x: g.origin_offset.0, y: g.origin_offset.1 },
+        radiusX: g.radius.0,
+        radiusY: g.radius.1
+    };
+
+    let mut brush: *mut ID2D1RadialGradientBrush = ptr::null_mut();
+    let result = unsafe{ 
+        let result = rt.CreateRadialGradientBrush(&gradient, &property, stops, &mut brush);
+        (&mut *stops).Release();
+        result
+    };
+
+    if result == S_OK {
+        Ok(BrushHandle::RadialGradientBrush(brush))
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to import brush".to_string())))
+    }
+}
+
+fn create_bitmap_brush(rt: &mut ID2D1RenderTarget, image: &ImageData, extend: (ExtendMode, ExtendMode), interpolation: BitmapInterpolation) -> Result<BrushHandle, Error> {
+    use winapi::{D2D1_BRUSH_PROPERTIES, D2D1_BITMAP_BRUSH_PROPERTIES, D2D1_EXTEND_MODE, D2D1_BITMAP_INTERPOLATION_MODE};
+
+    let bitmap = match create_bitmap(rt, image) {
+        Ok(b) => b,
+        Err(e) => { return Err(e); }
+    };
+
+    let identity = D2D1_MATRIX_3X2_F {matrix: [[1.0, 0.0],[0.0, 1.0],[0.0, 0.0]]};
+    let property = D2D1_BRUSH_PROPERTIES { opacity: 1.0, transform: identity};
+    let bitmap_property = D2D1_BITMAP_BRUSH_PROPERTIES {
+        extendModeX: D2D1_EXTEND_MODE(extend.0 as u32),
+        extendModeY: D2D1_EXTEND_MODE(extend.1 as u32),
+        interpolationMode: D2D1_BITMAP_INTERPOLATION_MODE(interpolation as u32)
+    };
+
+    // The brush keeps a reference to the bitmap
+    let mut brush: *mut ID2D1BitmapBrush = ptr::null_mut();
+    let result = unsafe{ 
+        let result = rt.CreateBitmapBrush(bitmap, &bitmap_property, &property, &mut brush);
+        (&mut *bitmap).Release();
+        result
+    };
+
+    if result == S_OK {
+        Ok(BrushHandle::BitmapBrush(brush))
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to import brush".to_string())))
+    }
+}
+
+fn create_gradient_stops(rt: &mut ID2D1RenderTarget, stops: &[GradientStop], extend: ExtendMode) -> Result<*mut ID2D1GradientStopCollection, Error> {
+    use winapi::{D2D1_GRADIENT_STOP, D2D1_COLOR_F, D2D1_GAMMA_2_2, D2D1_EXTEND_MODE};
+
+    if stops.is_empty() {
+        return Err(Error::BadResource("A gradient brush requires at least one gradient stop".to_string()));
+    }
+
+    let stops: Vec<D2D1_GRADIENT_STOP> = stops.iter().map(|s| {
+        let c = &s.color;
+        D2D1_GRADIENT_STOP{ position: s.position, color: D2D1_COLOR_F{r: c.0, g: c.1, b: c.2, a: c.3} }
+    }).collect();
+
+    let mut collection: *mut ID2D1GradientStopCollection = ptr::null_mut();
+    let result = unsafe{ rt.CreateGradientStopCollection(stops.as_ptr(), stops.len() as u32, D2D1_GAMMA_2_2, D2D1_EXTEND_MODE(extend as u32), &mut collection) };
+
+    if result == S_OK {
+        Ok(collection)
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to create the gradient stops".to_string())))
+    }
+}
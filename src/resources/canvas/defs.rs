@@ -5,7 +5,7 @@
 /**
     Define a rectangle shape that can be used with canvases
 */
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Rectangle {
     pub left: f32,
     pub top: f32,
@@ -26,8 +26,11 @@ pub struct Ellipse {
     A enumeration of the different brush type that can be created using a `BrushT` resource.
 */
 #[derive(Clone)]
-pub enum BrushType {
-    SolidBrush(SolidBrush)
+pub enum BrushType<ID: Clone> {
+    SolidBrush(SolidBrush),
+    LinearGradientBrush(LinearGradientBrush),
+    RadialGradientBrush(RadialGradientBrush),
+    BitmapBrush(BitmapBrush<ID>)
 }
 
 /**
@@ -42,9 +45,97 @@ pub struct SolidBrush {
 }
 
 /**
-    Cap style used when creating a Pen
+    How a brush paints the area outside of its gradient or its bitmap
+
+    • `Clamp`: Repeat the color of the edge  
+    • `Wrap`: Repeat the gradient or the bitmap  
+    • `Mirror`: Repeat the gradient or the bitmap, flipping every other copy  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtendMode {
+    Clamp = 0,
+    Wrap = 1,
+    Mirror = 2
+}
+
+/**
+    A color of a gradient
+
+    Members:  
+    • `position`: The position of the color in the gradient. 0.0 - 1.0  
+    • `color`: The color at this position (red, green, blue, alpha)  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: (f32, f32, f32, f32)
+}
+
+/**
+    A brush painting a gradient along a line. Used when painting in a canvas
+
+    Members:  
+    • `start`: The position of the gradient start, in canvas coordinates  
+    • `end`: The position of the gradient end, in canvas coordinates  
+    • `stops`: The colors of the gradient. At least one stop is required  
+    • `extend`: How the area before the start and after the end is painted  
+*/
+#[derive(Clone, Debug)]
+pub struct LinearGradientBrush {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub stops: Vec<GradientStop>,
+    pub extend: ExtendMode
+}
+
+/**
+    A brush painting a gradient from the origin of an ellipse to its edge. Used when painting in a canvas
+
+    Members:  
+    • `center`: The center of the ellipse, in canvas coordinates  
+    • `origin_offset`: The offset of the gradient origin from the center  
+    • `radius`: The radius of the ellipse (x, y)  
+    • `stops`: The colors of the gradient. At least one stop is required  
+    • `extend`: How the area outside of the ellipse is painted  
+*/
+#[derive(Clone, Debug)]
+pub struct RadialGradientBrush {
+    pub center: (f32, f32),
+    pub origin_offset: (f32, f32),
+    pub radius: (f32, f32),
+    pub stops: Vec<GradientStop>,
+    pub extend: ExtendMode
+}
+
+/**
+    How a bitmap is sampled when it is scaled or rotated
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitmapInterpolation {
+    NearestNeighbor = 0,
+    Linear = 1
+}
+
+/**
+    A brush painting an image. The top left corner of the image is at the canvas origin and one pixel of
+    the image covers one device independent pixel. Used when painting in a canvas
+
+    Members:  
+    • `image`: The id of an image resource. The image must be created before the brush  
+    • `extend`: How the image is repeated horizontally and vertically  
+    • `interpolation`: How the image is sampled when it is scaled  
 */
 #[derive(Clone, Debug)]
+pub struct BitmapBrush<ID: Clone> {
+    pub image: ID,
+    pub extend: (ExtendMode, ExtendMode),
+    pub interpolation: BitmapInterpolation
+}
+
+/**
+    Cap style used when creating a Pen
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CapStyle {
     Flat = 0,
     Square = 1,
@@ -55,7 +146,7 @@ pub enum CapStyle {
 /**
     Line join type used when creating a Pen
 */
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LineJoin {
     Miter = 0,
     Bevel = 1,
@@ -66,7 +157,7 @@ pub enum LineJoin {
 /**
     Dash style used when creating a Pen
 */
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DashStyle {
     Solid = 0,
     Dash = 1,
@@ -98,3 +189,448 @@ pub struct Pen {
     pub dash_style: DashStyle,
     pub dash_offset: f32,
 }
+
+/**
+    Fill mode of a path. Decide which areas of overlapping figures are inside the path.
+    
+    • `Alternate`: A point is inside the path if a ray from the point crosses an odd number of segments  
+    • `Winding`: A point is inside the path if the segments around the point do not cancel each other  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillMode {
+    Alternate = 0,
+    Winding = 1
+}
+
+/**
+    Select which of the two possible arcs is drawn by an arc segment
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArcSize {
+    Small = 0,
+    Large = 1
+}
+
+/**
+    Direction in which an arc segment is drawn
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SweepDirection {
+    CounterClockwise = 0,
+    Clockwise = 1
+}
+
+/**
+    A segment of a path figure. Every segment starts where the previous one ends.
+
+    • `Line`: A straight line to a point  
+    • `QuadraticBezier`: A quadratic Bézier curve with one control point  
+    • `CubicBezier`: A cubic Bézier curve with two control points  
+    • `Arc`: An elliptical arc with the radius `radius`, rotated by `rotation` degrees  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Line((f32, f32)),
+    QuadraticBezier{ control: (f32, f32), end: (f32, f32) },
+    CubicBezier{ control1: (f32, f32), control2: (f32, f32), end: (f32, f32) },
+    Arc{ end: (f32, f32), radius: (f32, f32), rotation: f32, size: ArcSize, sweep: SweepDirection }
+}
+
+/**
+    A continuous figure of a path
+
+    Members:  
+    • `start`: The first point of the figure  
+    • `segments`: The segments of the figure  
+    • `closed`: If the last point of the figure is joined to the first one  
+    • `filled`: If the figure is painted when the path is filled  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Figure {
+    pub start: (f32, f32),
+    pub segments: Vec<PathSegment>,
+    pub closed: bool,
+    pub filled: bool
+}
+
+/**
+    Describe the geometry of a path. Used by `PathT` to create a path resource or by the canvas
+    renderer to draw polylines.
+
+    The builder methods follow the usual pen-plotter model: `move_to` begins a new figure and the
+    other methods add a segment to the current figure. A segment added after `close` begins a new figure
+    at the start of the closed one.
+
+    Members:  
+    • `fill_mode`: How the overlapping figures are filled  
+    • `figures`: The figures of the path  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathData {
+    pub fill_mode: FillMode,
+    pub figures: Vec<Figure>
+}
+
+impl PathData {
+
+    /// Create an empty path
+    pub fn new() -> PathData {
+        PathData{ fill_mode: FillMode::Alternate, figures: Vec::new() }
+    }
+
+    /**
+        Create a path made of a single figure joining `points` with straight lines.
+        If `closed` is true, the last point is joined to the first one.
+    */
+    pub fn polyline(points: &[(f32, f32)], closed: bool) -> PathData {
+        let mut path = PathData::new();
+        if let Some((first, rest)) = points.split_first() {
+            path = path.move_to(first.0, first.1);
+            for p in rest { path = path.line_to(p.0, p.1); }
+            if closed { path = path.close(); }
+        }
+
+        path
+    }
+
+    /// Set the fill mode of the path
+    pub fn fill_mode(mut self, mode: FillMode) -> PathData {
+        self.fill_mode = mode;
+        self
+    }
+
+    /// Begin a new figure at `(x, y)`
+    pub fn move_to(mut self, x: f32, y: f32) -> PathData {
+        let replace = match self.figures.last() {
+            Some(f) => !f.closed && f.segments.is_empty(),
+            None => false
+        };
+
+        if replace { self.figures.pop(); }
+        self.figures.push(Figure{ start: (x, y), segments: Vec::new(), closed: false, filled: true });
+        self
+    }
+
+    /// Add a straight line from the current point to `(x, y)`
+    pub fn line_to(self, x: f32, y: f32) -> PathData {
+        self.segment(PathSegment::Line((x, y)))
+    }
+
+    /// Add a quadratic Bézier curve from the current point to `end`
+    pub fn quadratic_to(self, control: (f32, f32), end: (f32, f32)) -> PathData {
+        self.segment(PathSegment::QuadraticBezier{ control: control, end: end })
+    }
+
+    /// Add a cubic Bézier curve from the current point to `end`
+    pub fn cubic_to(self, control1: (f32, f32), control2: (f32, f32), end: (f32, f32)) -> PathData {
+        self.segment(PathSegment::CubicBezier{ control1: control1, control2: control2, end: end })
+    }
+
+    /**
+        Add an elliptical arc from the current point to `end`
+
+        Arguments:  
+        • `end`: The end point of the arc  
+        • `radius`: The radius of the ellipse (x, y)  
+        • `rotation`: The rotation of the ellipse in degrees  
+        • `size`: If the arc is smaller or greater than 180 degrees  
+        • `sweep`: The direction of the arc  
+    */
+    pub fn arc_to(self, end: (f32, f32), radius: (f32, f32), rotation: f32, size: ArcSize, sweep: SweepDirection) -> PathData {
+        self.segment(PathSegment::Arc{ end: end, radius: radius, rotation: rotation, size: size, sweep: sweep })
+    }
+
+    /// Join the end of the current figure to its start. Does nothing if there is no open figure.
+    pub fn close(mut self) -> PathData {
+        if let Some(f) = self.figures.last_mut() { f.closed = true; }
+        self
+    }
+
+    /// Exclude the current figure from the fill. The figure is still drawn by the outline.
+    pub fn hollow(mut self) -> PathData {
+        if let Some(f) = self.figures.last_mut() { f.filled = false; }
+        self
+    }
+
+    /**
+        Return the point where the next segment will start. Return `None` if the path is empty.
+    */
+    pub fn current_point(&self) -> Option<(f32, f32)> {
+        self.figures.last().map(|f| {
+            if f.closed { return f.start; }
+            match f.segments.last() {
+                Some(&PathSegment::Line(end)) => end,
+                Some(&PathSegment::QuadraticBezier{end, ..}) => end,
+                Some(&PathSegment::CubicBezier{end, ..}) => end,
+                Some(&PathSegment::Arc{end, ..}) => end,
+                None => f.start
+            }
+        })
+    }
+
+    fn segment(mut self, segment: PathSegment) -> PathData {
+        let start = match self.figures.last() {
+            Some(f) if !f.closed => None,
+            _ => Some(self.current_point().unwrap_or((0.0, 0.0)))
+        };
+
+        if let Some(start) = start {
+            self.figures.push(Figure{ start: start, segments: Vec::new(), closed: false, filled: true });
+        }
+
+        self.figures.last_mut().unwrap().segments.push(segment);
+        self
+    }
+}
+
+/**
+    Slant of the font used by a text format
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontStyle {
+    Normal = 0,
+    Oblique = 1,
+    Italic = 2
+}
+
+/**
+    Horizontal alignment of the text in its layout box
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlignment {
+    Leading = 0,
+    Trailing = 1,
+    Center = 2,
+    Justified = 3
+}
+
+/**
+    Vertical alignment of the text in its layout box
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParagraphAlignment {
+    Near = 0,
+    Far = 1,
+    Center = 2
+}
+
+/**
+    How the lines of text that are wider than the layout box are broken
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordWrapping {
+    Wrap = 0,
+    NoWrap = 1
+}
+
+/**
+    Describe how a text is formatted. This is the content of a text format resource.
+
+    Members:  
+    • `family`: The name of the font family  
+    • `size`: The size of the font in device independent pixels  
+    • `weight`: The weight of the font in the range 1 through 999  
+    • `style`: The slant of the font  
+    • `text_align`: The horizontal alignment of the text in the layout box  
+    • `paragraph_align`: The vertical alignment of the text in the layout box  
+    • `wrapping`: How the lines that are wider than the layout box are broken  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub family: String,
+    pub size: f32,
+    pub weight: i32,
+    pub style: FontStyle,
+    pub text_align: TextAlignment,
+    pub paragraph_align: ParagraphAlignment,
+    pub wrapping: WordWrapping
+}
+
+/**
+    The size of a text layout
+
+    Members:  
+    • `left`, `top`: Position of the text relative to the layout box  
+    • `width`: Width of the widest line, without the trailing whitespaces  
+    • `height`: Height of all the lines  
+    • `layout_size`: The maximum size of the layout box (width, height)  
+    • `line_count`: The number of lines of the text  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextMetrics {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+    pub layout_size: (f32, f32),
+    pub line_count: u32
+}
+
+/**
+    The result of a hit test on a text layout. Text positions are counted in UTF-16 code units,
+    like the selection of the text controls.
+
+    Members:  
+    • `position`: The position of the first character under the hit  
+    • `length`: The number of code units of the text under the hit (ex: 2 for a surrogate pair)  
+    • `trailing`: If the hit is on the trailing side of the character  
+    • `inside`: If the hit is inside the text  
+    • `point`: The position of the caret for the hit  
+    • `bounds`: The bounds of the text under the hit  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct HitTest {
+    pub position: u32,
+    pub length: u32,
+    pub trailing: bool,
+    pub inside: bool,
+    pub point: (f32, f32),
+    pub bounds: Rectangle
+}
+
+/**
+    A 2D affine transformation. Used to move, rotate, scale or skew what is drawn in a canvas.
+
+    A point `(x, y)` is transformed to `(x*m11 + y*m21 + dx, x*m12 + y*m22 + dy)`. Matrices are combined
+    in the order they are applied: `a.multiply(&b)` (or `a * b`) applies `a` then `b`. The angles are in degrees
+    and positive angles rotate clockwise, because the y axis of the canvas points down.
+
+    Members:  
+    • `m11`, `m12`, `m21`, `m22`: The linear part of the transformation  
+    • `dx`, `dy`: The translation  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix {
+    pub m11: f32,
+    pub m12: f32,
+    pub m21: f32,
+    pub m22: f32,
+    pub dx: f32,
+    pub dy: f32
+}
+
+impl Matrix {
+
+    /// The transformation that does nothing
+    pub fn identity() -> Matrix {
+        Matrix{ m11: 1.0, m12: 0.0, m21: 0.0, m22: 1.0, dx: 0.0, dy: 0.0 }
+    }
+
+    /// A translation by `(x, y)`
+    pub fn translation(x: f32, y: f32) -> Matrix {
+        Matrix{ m11: 1.0, m12: 0.0, m21: 0.0, m22: 1.0, dx: x, dy: y }
+    }
+
+    /// A scaling by `(sx, sy)` that keeps `center` in place
+    pub fn scaling(sx: f32, sy: f32, center: (f32, f32)) -> Matrix {
+        Matrix{ m11: sx, m12: 0.0, m21: 0.0, m22: sy, dx: center.0 - sx * center.0, dy: center.1 - sy * center.1 }
+    }
+
+    /// A rotation of `angle` degrees around `center`
+    pub fn rotation(angle: f32, center: (f32, f32)) -> Matrix {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Matrix {
+            m11: cos, m12: sin,
+            m21: -sin, m22: cos,
+            dx: center.0 - center.0 * cos + center.1 * sin,
+            dy: center.1 - center.0 * sin - center.1 * cos
+        }
+    }
+
+    /// A skew of `angle_x` degrees along the x axis and `angle_y` degrees along the y axis that keeps `center` in place
+    pub fn skewing(angle_x: f32, angle_y: f32, center: (f32, f32)) -> Matrix {
+        let (tx, ty) = (angle_x.to_radians().tan(), angle_y.to_radians().tan());
+        Matrix{ m11: 1.0, m12: ty, m21: tx, m22: 1.0, dx: -center.1 * tx, dy: -center.0 * ty }
+    }
+
+    /// Create a matrix from the `[[m11, m12], [m21, m22], [dx, dy]]` layout used by `CanvasRenderer::set_transform`
+    pub fn from_array(m: &[[f32; 2]; 3]) -> Matrix {
+        Matrix{ m11: m[0][0], m12: m[0][1], m21: m[1][0], m22: m[1][1], dx: m[2][0], dy: m[2][1] }
+    }
+
+    /// Return the matrix in the `[[m11, m12], [m21, m22], [dx, dy]]` layout used by `CanvasRenderer::set_transform`
+    pub fn to_array(&self) -> [[f32; 2]; 3] {
+        [[self.m11, self.m12], [self.m21, self.m22], [self.dx, self.dy]]
+    }
+
+    /// Return the transformation that applies `self` then `other`
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            m11: self.m11 * other.m11 + self.m12 * other.m21,
+            m12: self.m11 * other.m12 + self.m12 * other.m22,
+            m21: self.m21 * other.m11 + self.m22 * other.m21,
+            m22: self.m21 * other.m12 + self.m22 * other.m22,
+            dx: self.dx * other.m11 + self.dy * other.m21 + other.dx,
+            dy: self.dx * other.m12 + self.dy * other.m22 + other.dy
+        }
+    }
+
+    /// Apply a translation after `self`
+    pub fn translate(self, x: f32, y: f32) -> Matrix {
+        self.multiply(&Matrix::translation(x, y))
+    }
+
+    /// Apply a scaling after `self`
+    pub fn scale(self, sx: f32, sy: f32, center: (f32, f32)) -> Matrix {
+        self.multiply(&Matrix::scaling(sx, sy, center))
+    }
+
+    /// Apply a rotation after `self`
+    pub fn rotate(self, angle: f32, center: (f32, f32)) -> Matrix {
+        self.multiply(&Matrix::rotation(angle, center))
+    }
+
+    /// Apply a skew after `self`
+    pub fn skew(self, angle_x: f32, angle_y: f32, center: (f32, f32)) -> Matrix {
+        self.multiply(&Matrix::skewing(angle_x, angle_y, center))
+    }
+
+    /// Return the determinant of the linear part of the matrix. A matrix with a determinant of 0 cannot be inverted.
+    pub fn determinant(&self) -> f32 {
+        self.m11 * self.m22 - self.m12 * self.m21
+    }
+
+    /// Return true if the matrix does nothing
+    pub fn is_identity(&self) -> bool {
+        *self == Matrix::identity()
+    }
+
+    /// Return the transformation that undoes `self`, or `None` if the matrix flattens the plane
+    pub fn invert(&self) -> Option<Matrix> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() { return None; }
+
+        let (m11, m12, m21, m22) = (self.m22 / det, -self.m12 / det, -self.m21 / det, self.m11 / det);
+        Some(Matrix {
+            m11: m11, m12: m12, m21: m21, m22: m22,
+            dx: -(self.dx * m11 + self.dy * m21),
+            dy: -(self.dx * m12 + self.dy * m22)
+        })
+    }
+
+    /// Transform a point
+    pub fn transform_point(&self, p: (f32, f32)) -> (f32, f32) {
+        (p.0 * self.m11 + p.1 * self.m21 + self.dx, p.0 * self.m12 + p.1 * self.m22 + self.dy)
+    }
+
+    /// Return the smallest axis-aligned rectangle that contains the transformed rectangle `r`
+    pub fn transform_rect(&self, r: &Rectangle) -> Rectangle {
+        let corners = [(r.left, r.top), (r.right, r.top), (r.left, r.bottom), (r.right, r.bottom)];
+        let points: Vec<(f32, f32)> = corners.iter().map(|&c| self.transform_point(c)).collect();
+
+        let mut out = Rectangle{ left: points[0].0, top: points[0].1, right: points[0].0, bottom: points[0].1 };
+        for p in points[1..].iter() {
+            out.left = out.left.min(p.0);
+            out.top = out.top.min(p.1);
+            out.right = out.right.max(p.0);
+            out.bottom = out.bottom.max(p.1);
+        }
+
+        out
+    }
+}
+
+impl ::std::ops::Mul for Matrix {
+    type Output = Matrix;
+    fn mul(self, other: Matrix) -> Matrix { self.multiply(&other) }
+}
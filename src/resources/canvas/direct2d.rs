@@ -0,0 +1,240 @@
+/*!
+    A renderer backend drawing with Direct2D. The brushes, the pens and the paths of the drawing commands are
+    created for each command, so it is only used to draw the display lists on a canvas.
+*/
+
+use std::ptr;
+use std::mem;
+
+use winapi::{ID2D1RenderTarget, ID2D1Factory, IDWriteFactory, ID2D1Layer, ID2D1PathGeometry, ID2D1Geometry,
+  ID2D1StrokeStyle, D2D1_RECT_F, D2D1_MATRIX_3X2_F};
+
+use super::defs::{BitmapInterpolation, Pen, PathData, Rectangle, Matrix, TextStyle};
+use super::backend::{RenderBackend, Paint};
+use super::brush::create_paint_brush;
+use super::pen::create_pen;
+use super::path::create_path_geometry;
+use super::bitmap::create_bitmap;
+use super::text::create_text_format;
+use resources::ImageData;
+use error::{Error, SystemError};
+
+pub(crate) struct Direct2DBackend {
+    target: *mut ID2D1RenderTarget,
+    factory: *mut ID2D1Factory,
+    write_factory: *mut IDWriteFactory,
+    layers: Vec<(*mut ID2D1Layer, *mut ID2D1PathGeometry)>
+}
+
+impl Direct2DBackend {
+
+    /**
+        Draw on `target`. The target must be between `BeginDraw` and `EndDraw` while the backend is used.
+    */
+    pub(crate) fn new(target: *mut ID2D1RenderTarget, factory: *mut ID2D1Factory, write_factory: *mut IDWriteFactory) -> Direct2DBackend {
+        Direct2DBackend{ target: target, factory: factory, write_factory: write_factory, layers: Vec::new() }
+    }
+
+    #[inline(always)]
+    fn target(&mut self) -> &mut ID2D1RenderTarget {
+        unsafe{ &mut *self.target }
+    }
+
+    fn geometry(&self, path: &PathData) -> Result<*mut ID2D1PathGeometry, Error> {
+        create_path_geometry(path, unsafe{ &mut *self.factory })
+    }
+
+}
+
+impl RenderBackend for Direct2DBackend {
+
+    fn get_size(&self) -> (f32, f32) {
+        use winapi::D2D1_SIZE_F;
+
+        let mut size = D2D1_SIZE_F{ width: 0.0, height: 0.0 };
+        unsafe{ (&mut *self.target).GetSize(&mut size); }
+        (size.width, size.height)
+    }
+
+    fn clear(&mut self, color: (f32, f32, f32, f32)) {
+        use winapi::D2D1_COLOR_F;
+
+        let color = D2D1_COLOR_F{ r: color.0, g: color.1, b: color.2, a: color.3 };
+        unsafe{ self.target().Clear(&color); }
+    }
+
+    fn set_matrix(&mut self, m: &Matrix) {
+        unsafe{ self.target().SetTransform(&D2D1_MATRIX_3X2_F{ matrix: m.to_array() }); }
+    }
+
+    fn get_matrix(&self) -> Matrix {
+        unsafe {
+            let mut m: D2D1_MATRIX_3X2_F = mem::zeroed();
+            (&mut *self.target).GetTransform(&mut m);
+            Matrix::from_array(&m.matrix)
+        }
+    }
+
+    fn push_clip(&mut self, r: &Rectangle) {
+        use winapi::D2D1_ANTIALIAS_MODE_PER_PRIMITIVE;
+
+        let rect = D2D1_RECT_F{ left: r.left, top: r.top, bottom: r.bottom, right: r.right };
+        unsafe{ self.target().PushAxisAlignedClip(&rect, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE); }
+    }
+
+    fn pop_clip(&mut self) {
+        unsafe{ self.target().PopAxisAlignedClip(); }
+    }
+
+    fn push_layer(&mut self, opacity: f32, mask: Option<&PathData>, bounds: Option<&Rectangle>) -> Result<(), Error> {
+        use winapi::{S_OK, D2D1_LAYER_PARAMETERS, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE, D2D1_LAYER_OPTIONS_NONE};
+        use std::f32::MAX;
+
+        let mask = match mask {
+            Some(m) => match self.geometry(m) {
+                Ok(g) => g,
+                Err(e) => { return Err(e); }
+            },
+            None => ptr::null_mut()
+        };
+
+        let bounds = match bounds {
+            Some(r) => D2D1_RECT_F{ left: r.left, top: r.top, bottom: r.bottom, right: r.right },
+            None => D2D1_RECT_F{ left: -MAX, top: -MAX, bottom: MAX, right: MAX }
+        };
+
+        let mut layer: *mut ID2D1Layer = ptr::null_mut();
+        if unsafe{ self.target().CreateLayer(ptr::null(), &mut layer) } != S_OK {
+            if !mask.is_null() { unsafe{ (&mut *mask).Release(); } }
+            return Err(Error::System(SystemError::ComError("Failed to create the layer".to_string())));
+        }
+
+        let params = D2D1_LAYER_PARAMETERS {
+            contentBounds: bounds,
+            geometricMask: mask as *mut ID2D1Geometry,
+            maskAntialiasMode: D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+            maskTransform: D2D1_MATRIX_3X2_F{ matrix: Matrix::identity().to_array() },
+            opacity: opacity.max(0.0).min(1.0),
+            opacityBrush: ptr::null_mut(),
+            layerOptions: D2D1_LAYER_OPTIONS_NONE
+        };
+
+        unsafe{ self.target().PushLayer(&params, layer); }
+        self.layers.push((layer, mask));
+
+        Ok(())
+    }
+
+    fn pop_layer(&mut self) {
+        if let Some((layer, mask)) = self.layers.pop() {
+            unsafe {
+                self.target().PopLayer();
+                (&mut *layer).Release();
+                if !mask.is_null() { (&mut *mask).Release(); }
+            }
+        }
+    }
+
+    fn fill_path(&mut self, path: &PathData, paint: &Paint) -> Result<(), Error> {
+        let brush = match create_paint_brush(self.target(), paint) {
+            Ok(b) => b,
+            Err(e) => { return Err(e); }
+        };
+
+        let geometry = match self.geometry(path) {
+            Ok(g) => g,
+            Err(e) => { unsafe{ (&mut *brush).Release(); } return Err(e); }
+        };
+
+        unsafe {
+            self.target().FillGeometry(geometry as *mut ID2D1Geometry, brush, ptr::null_mut());
+            (&mut *geometry).Release();
+            (&mut *brush).Release();
+        }
+
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &PathData, paint: &Paint, pen: Option<&Pen>, width: f32) -> Result<(), Error> {
+        let pen = match pen {
+            Some(p) => match create_pen(p, unsafe{ &mut *self.factory }) {
+                Ok(p) => p,
+                Err(e) => { return Err(e); }
+            },
+            None => ptr::null_mut()
+        };
+
+        let release_pen = |pen: *mut ID2D1StrokeStyle| if !pen.is_null() { unsafe{ (&mut *pen).Release(); } };
+
+        let brush = match create_paint_brush(self.target(), paint) {
+            Ok(b) => b,
+            Err(e) => { release_pen(pen); return Err(e); }
+        };
+
+        let geometry = match self.geometry(path) {
+            Ok(g) => g,
+            Err(e) => { unsafe{ (&mut *brush).Release(); } release_pen(pen); return Err(e); }
+        };
+
+        unsafe {
+            self.target().DrawGeometry(geometry as *mut ID2D1Geometry, brush, width, pen);
+            (&mut *geometry).Release();
+            (&mut *brush).Release();
+        }
+        release_pen(pen);
+
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &ImageData, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32,
+      interpolation: BitmapInterpolation) -> Result<(), Error>
+    {
+        use winapi::D2D1_BITMAP_INTERPOLATION_MODE;
+
+        // The bitmap uses 96 DPI, so its device independent pixels are its pixels
+        let bitmap = match create_bitmap(self.target(), image) {
+            Ok(b) => b,
+            Err(e) => { return Err(e); }
+        };
+
+        let dest = D2D1_RECT_F{ left: dest.left, top: dest.top, bottom: dest.bottom, right: dest.right };
+        let src = src.map(|r| D2D1_RECT_F{ left: r.left, top: r.top, bottom: r.bottom, right: r.right });
+        let src_ptr = match src.as_ref() {
+            Some(r) => r as *const D2D1_RECT_F,
+            None => ptr::null()
+        };
+
+        unsafe {
+            self.target().DrawBitmap(bitmap, &dest, opacity.max(0.0).min(1.0), D2D1_BITMAP_INTERPOLATION_MODE(interpolation as u32), src_ptr);
+            (&mut *bitmap).Release();
+        }
+
+        Ok(())
+    }
+
+    fn draw_text(&mut self, text: &str, style: &TextStyle, bounds: &Rectangle, paint: &Paint) -> Result<(), Error> {
+        use winapi::{D2D1_DRAW_TEXT_OPTIONS_NONE, DWRITE_MEASURING_MODE_NATURAL};
+
+        let format = match create_text_format(style, unsafe{ &mut *self.write_factory }) {
+            Ok(f) => f,
+            Err(e) => { return Err(e); }
+        };
+
+        let brush = match create_paint_brush(self.target(), paint) {
+            Ok(b) => b,
+            Err(e) => { unsafe{ (&mut *format).Release(); } return Err(e); }
+        };
+
+        let rect = D2D1_RECT_F{ left: bounds.left, top: bounds.top, bottom: bounds.bottom, right: bounds.right };
+        let text: Vec<u16> = text.encode_utf16().collect();
+        unsafe {
+            self.target().DrawText(text.as_ptr(), text.len() as u32, format, &rect, brush,
+              D2D1_DRAW_TEXT_OPTIONS_NONE, DWRITE_MEASURING_MODE_NATURAL);
+            (&mut *brush).Release();
+            (&mut *format).Release();
+        }
+
+        Ok(())
+    }
+
+}
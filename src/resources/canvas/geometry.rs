@@ -0,0 +1,499 @@
+/*!
+    Geometry computations on the canvas paths. Nothing in this module calls Direct2D, so paths can be
+    measured and hit-tested without a canvas.
+*/
+
+use std::f32::consts::PI;
+
+use super::defs::{PathData, PathSegment, Figure, FillMode, ArcSize, SweepDirection, Rectangle, Pen, CapStyle,
+  LineJoin, DashStyle};
+
+/// The maximum number of lines used to approximate a single curve
+const MAX_CURVE_LINES: u32 = 1000;
+
+/**
+    A figure of a path approximated with straight lines
+
+    Members:  
+    • `points`: The points joined by the lines. The first point is the start of the figure  
+    • `closed`: If the last point is joined to the first one  
+    • `filled`: If the figure is painted when the path is filled  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlatFigure {
+    pub points: Vec<(f32, f32)>,
+    pub closed: bool,
+    pub filled: bool
+}
+
+impl PathData {
+
+    /**
+        Approximate the curves of the path with straight lines. The lines are never further than
+        `tolerance` from the curves they replace.
+    */
+    pub fn flatten(&self, tolerance: f32) -> Vec<FlatFigure> {
+        let tolerance = if tolerance > 0.0 { tolerance } else { 0.25 };
+        self.figures.iter().map(|f| flatten_figure(f, tolerance)).collect()
+    }
+
+    /**
+        Return the smallest rectangle that contains the path, or `None` if the path is empty.
+        Curves are measured on their approximation, see `flatten`.
+    */
+    pub fn bounds(&self, tolerance: f32) -> Option<Rectangle> {
+        let figures = self.flatten(tolerance);
+        bounds_of(figures.iter().flat_map(|f| f.points.iter()))
+    }
+
+    /**
+        Check if `point` is inside the area painted when the path is filled. The figures that are not closed
+        are filled as if they were, and the figures made `hollow` are ignored.
+    */
+    pub fn fill_contains(&self, point: (f32, f32), tolerance: f32) -> bool {
+        fill_contains(&self.flatten(tolerance), self.fill_mode, point)
+    }
+
+    /**
+        Check if `point` is on the outline of the path drawn with a line of `width`.
+    */
+    pub fn stroke_contains(&self, point: (f32, f32), width: f32, tolerance: f32) -> bool {
+        stroke_contains(&self.flatten(tolerance), point, width * 0.5)
+    }
+
+}
+
+/**
+    Return the smallest rectangle that contains `points`, or `None` if there is no point
+*/
+pub(crate) fn bounds_of<'a, I: Iterator<Item=&'a (f32, f32)>>(points: I) -> Option<Rectangle> {
+    let mut bounds: Option<Rectangle> = None;
+    for &(x, y) in points {
+        bounds = Some(match bounds {
+            Some(b) => Rectangle{ left: b.left.min(x), top: b.top.min(y), right: b.right.max(x), bottom: b.bottom.max(y) },
+            None => Rectangle{ left: x, top: y, right: x, bottom: y }
+        });
+    }
+
+    bounds
+}
+
+/**
+    Check if `point` is inside the filled figures using the rule of `mode`
+*/
+pub(crate) fn fill_contains(figures: &[FlatFigure], mode: FillMode, point: (f32, f32)) -> bool {
+    let (px, py) = point;
+    let mut winding = 0i32;
+
+    for f in figures.iter().filter(|f| f.filled && f.points.len() > 2) {
+        let count = f.points.len();
+        for i in 0..count {
+            let (x0, y0) = f.points[i];
+            let (x1, y1) = f.points[(i + 1) % count];
+
+            // Count the edges crossing the horizontal ray that goes right of the point
+            let cross = (x1 - x0) * (py - y0) - (px - x0) * (y1 - y0);
+            if y0 <= py && y1 > py && cross > 0.0 {
+                winding += 1;
+            } else if y1 <= py && y0 > py && cross < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+
+    match mode {
+        FillMode::Alternate => winding % 2 != 0,
+        FillMode::Winding => winding != 0
+    }
+}
+
+/**
+    Check if `point` is at most `distance` away from the lines of the figures
+*/
+pub(crate) fn stroke_contains(figures: &[FlatFigure], point: (f32, f32), distance: f32) -> bool {
+    for f in figures.iter() {
+        let count = f.points.len();
+        if count == 1 && segment_distance(point, f.points[0], f.points[0]) <= distance {
+            return true;
+        }
+
+        let lines = if f.closed { count } else { count.saturating_sub(1) };
+        for i in 0..lines {
+            if segment_distance(point, f.points[i], f.points[(i + 1) % count]) <= distance {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/**
+    Return the distance between `p` and the segment going from `a` to `b`
+*/
+pub(crate) fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length > 0.0 { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).max(0.0).min(1.0) } else { 0.0 };
+    let (x, y) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (x * x + y * y).sqrt()
+}
+
+/**
+    Return the lengths of the dashes and of the gaps of a dash style, in units of stroke width.
+    The first value is a dash. A solid line has no pattern.
+*/
+pub(crate) fn dash_pattern(style: DashStyle) -> &'static [f32] {
+    match style {
+        DashStyle::Solid => &[],
+        DashStyle::Dash => &[2.0, 2.0],
+        DashStyle::Dot => &[0.0, 2.0],
+        DashStyle::DashDot => &[2.0, 2.0, 0.0, 2.0],
+        DashStyle::DashDotDot => &[2.0, 2.0, 0.0, 2.0, 0.0, 2.0]
+    }
+}
+
+/**
+    Return polygons that cover the outline of the figures drawn with `pen` and a line of `width`.
+    All the polygons turn in the same direction, so the outline is their union filled with the `Winding` mode.
+*/
+pub(crate) fn stroke_outline(figures: &[FlatFigure], pen: &Pen, width: f32, tolerance: f32) -> Vec<Vec<(f32, f32)>> {
+    let mut out = Vec::new();
+    if !(width > 0.0) { return out; }
+
+    let stroker = Stroker{ pen: pen, half: width * 0.5, tolerance: tolerance };
+    let pattern = dash_pattern(pen.dash_style);
+    let period: f32 = pattern.iter().map(|l| l * width).sum();
+
+    for f in figures.iter() {
+        let mut points = f.points.clone();
+        points.dedup();
+        if f.closed && points.len() > 2 && points.first() == points.last() { points.pop(); }
+        let closed = f.closed && points.len() > 2;
+        if points.is_empty() { continue; }
+
+        let total = polyline_length(&points, closed);
+        if period <= 0.0 || total / period > MAX_DASHES {
+            stroker.run(&mut out, &points, closed, (1.0, 0.0), (pen.start_cap, pen.end_cap));
+            continue;
+        }
+
+        for (dash, direction, start, end) in dashes(&points, closed, pattern, pen.dash_offset, width) {
+            let start_cap = if !closed && start <= 0.0 { pen.start_cap } else { pen.dash_cap };
+            let end_cap = if !closed && end >= total { pen.end_cap } else { pen.dash_cap };
+            stroker.run(&mut out, &dash, false, direction, (start_cap, end_cap));
+        }
+    }
+
+    for polygon in out.iter_mut() {
+        if signed_area(polygon) < 0.0 { polygon.reverse(); }
+    }
+
+    out
+}
+
+/// The maximum number of dashes in a figure. Longer figures are drawn with a solid line
+const MAX_DASHES: f32 = 100000.0;
+
+/// Build the polygons of an outline. See `stroke_outline`
+struct Stroker<'a> {
+    pen: &'a Pen,
+    half: f32,
+    tolerance: f32
+}
+
+impl<'a> Stroker<'a> {
+
+    /**
+        Add the polygons of a polyline. `direction` is the direction of the caps of a polyline made of a single point.
+    */
+    fn run(&self, out: &mut Vec<Vec<(f32, f32)>>, points: &[(f32, f32)], closed: bool, direction: (f32, f32), caps: (CapStyle, CapStyle)) {
+        let count = points.len();
+        if count == 1 {
+            let p = points[0];
+            self.cap(out, p, (-direction.0, -direction.1), caps.0);
+            self.cap(out, p, direction, caps.1);
+            return;
+        }
+
+        let lines = if closed { count } else { count - 1 };
+        for i in 0..lines {
+            let (a, b) = (points[i], points[(i + 1) % count]);
+            let d = unit((b.0 - a.0, b.1 - a.1));
+            let n = (-d.1 * self.half, d.0 * self.half);
+            out.push(vec![(a.0 + n.0, a.1 + n.1), (b.0 + n.0, b.1 + n.1), (b.0 - n.0, b.1 - n.1), (a.0 - n.0, a.1 - n.1)]);
+        }
+
+        let joins = if closed { 0..count } else { 1..(count - 1) };
+        for i in joins {
+            self.join(out, points[(i + count - 1) % count], points[i], points[(i + 1) % count]);
+        }
+
+        if !closed {
+            let (first, last) = (points[0], points[count - 1]);
+            self.cap(out, first, unit((first.0 - points[1].0, first.1 - points[1].1)), caps.0);
+            self.cap(out, last, unit((last.0 - points[count - 2].0, last.1 - points[count - 2].1)), caps.1);
+        }
+    }
+
+    /// Add the join between the line ending at `p` and the line starting at `p`
+    fn join(&self, out: &mut Vec<Vec<(f32, f32)>>, previous: (f32, f32), p: (f32, f32), next: (f32, f32)) {
+        let d0 = unit((p.0 - previous.0, p.1 - previous.1));
+        let d1 = unit((next.0 - p.0, next.1 - p.1));
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        let dot = d0.0 * d1.0 + d0.1 * d1.1;
+        if cross.abs() < 1e-6 && dot > 0.0 { return; }
+
+        // The offsets of the two lines on the outer side of the turn
+        let side = if cross > 0.0 { -self.half } else { self.half };
+        let (n0, n1) = ((-d0.1 * side, d0.0 * side), (-d1.1 * side, d1.0 * side));
+        let (a, b) = ((p.0 + n0.0, p.1 + n0.1), (p.0 + n1.0, p.1 + n1.1));
+
+        match self.pen.line_join {
+            LineJoin::Round => out.push(self.circle(p)),
+            LineJoin::Bevel => out.push(vec![p, a, b]),
+            LineJoin::Miter | LineJoin::MiterOrBevel => {
+                // The miter length divided by the half width is 1 / cos(θ/2), θ being the angle between the offsets
+                let u = (n0.0 + n1.0, n0.1 + n1.1);
+                let cos = (length(u) / (2.0 * self.half)).min(1.0);
+                let limit = self.pen.miter_limit.max(1.0);
+
+                if cos * limit >= 1.0 {
+                    let k = 1.0 / (2.0 * cos * cos);
+                    out.push(vec![p, a, (p.0 + u.0 * k, p.1 + u.1 * k), b]);
+                } else if self.pen.line_join == LineJoin::MiterOrBevel {
+                    out.push(vec![p, a, b]);
+                } else {
+                    // Cut the miter at `limit` half widths from `p`
+                    let sin = (1.0 - cos * cos).sqrt();
+                    let t = self.half * (limit - cos) / sin;
+                    out.push(vec![p, a, (a.0 + d0.0 * t, a.1 + d0.1 * t), (b.0 - d1.0 * t, b.1 - d1.1 * t), b]);
+                }
+            }
+        }
+    }
+
+    /// Add the cap at the end `p` of a line. `d` is the unit vector pointing out of the line
+    fn cap(&self, out: &mut Vec<Vec<(f32, f32)>>, p: (f32, f32), d: (f32, f32), style: CapStyle) {
+        let h = self.half;
+        let n = (-d.1 * h, d.0 * h);
+        match style {
+            CapStyle::Flat => {},
+            CapStyle::Square => out.push(vec![
+                (p.0 + n.0, p.1 + n.1), (p.0 + n.0 + d.0 * h, p.1 + n.1 + d.1 * h),
+                (p.0 - n.0 + d.0 * h, p.1 - n.1 + d.1 * h), (p.0 - n.0, p.1 - n.1)
+            ]),
+            CapStyle::Round => out.push(self.circle(p)),
+            CapStyle::Triangle => out.push(vec![(p.0 + n.0, p.1 + n.1), (p.0 + d.0 * h, p.1 + d.1 * h), (p.0 - n.0, p.1 - n.1)])
+        }
+    }
+
+    /// Return a circle of the stroke width centered on `p`
+    fn circle(&self, p: (f32, f32)) -> Vec<(f32, f32)> {
+        let r = self.half;
+        let step = if self.tolerance < r { 2.0 * (1.0 - self.tolerance / r).acos() } else { PI / 2.0 };
+        let lines = curve_lines(2.0 * PI / step).max(8);
+        (0..lines).map(|i| {
+            let (s, c) = (2.0 * PI * (i as f32 / lines as f32)).sin_cos();
+            (p.0 + r * c, p.1 + r * s)
+        }).collect()
+    }
+
+}
+
+/**
+    Split a polyline in dashes. Return the points of each dash with its direction and the distances of its ends
+    from the start of the polyline.
+*/
+fn dashes(points: &[(f32, f32)], closed: bool, pattern: &[f32], offset: f32, width: f32) -> Vec<(Vec<(f32, f32)>, (f32, f32), f32, f32)> {
+    let mut points = points.to_vec();
+    if closed { let first = points[0]; points.push(first); }
+
+    let mut distances = vec![0.0];
+    for i in 1..points.len() {
+        let last = distances[i - 1];
+        distances.push(last + length((points[i].0 - points[i - 1].0, points[i].1 - points[i - 1].1)));
+    }
+
+    let total = distances[distances.len() - 1];
+    let lengths: Vec<f32> = pattern.iter().map(|l| l * width).collect();
+    let period: f32 = lengths.iter().sum();
+
+    // A positive offset moves the pattern toward the start of the line
+    let mut skip = (offset * width) % period;
+    if skip < 0.0 { skip += period; }
+
+    let mut index = 0;
+    while skip > 0.0 && skip >= lengths[index] {
+        skip -= lengths[index];
+        index = (index + 1) % lengths.len();
+    }
+
+    let mut out = Vec::new();
+    let (mut start, mut left) = (0.0, lengths[index] - skip);
+    loop {
+        let end = start + left;
+        if index % 2 == 0 {
+            out.push(sub_polyline(&points, &distances, start, end.min(total)));
+        }
+
+        if end >= total { break; }
+        start = end;
+        index = (index + 1) % lengths.len();
+        left = lengths[index];
+    }
+
+    out
+}
+
+/// Return the part of a polyline between two distances from its start, with the direction of its first line
+fn sub_polyline(points: &[(f32, f32)], distances: &[f32], start: f32, end: f32) -> (Vec<(f32, f32)>, (f32, f32), f32, f32) {
+    let at = |d: f32| -> ((f32, f32), (f32, f32)) {
+        let mut i = 0;
+        while i + 2 < points.len() && distances[i + 1] < d { i += 1; }
+        let (a, b) = (points[i], points[i + 1]);
+        let span = distances[i + 1] - distances[i];
+        let t = if span > 0.0 { ((d - distances[i]) / span).max(0.0).min(1.0) } else { 0.0 };
+        ((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t), unit((b.0 - a.0, b.1 - a.1)))
+    };
+
+    let (first, direction) = at(start);
+    let mut dash = vec![first];
+    for i in 1..(points.len() - 1) {
+        if distances[i] > start && distances[i] < end { dash.push(points[i]); }
+    }
+
+    let (last, _) = at(end);
+    if last != *dash.last().unwrap() { dash.push(last); }
+
+    (dash, direction, start, end)
+}
+
+fn polyline_length(points: &[(f32, f32)], closed: bool) -> f32 {
+    let count = points.len();
+    let lines = if closed { count } else { count - 1 };
+    (0..lines).map(|i| {
+        let (a, b) = (points[i], points[(i + 1) % count]);
+        length((b.0 - a.0, b.1 - a.1))
+    }).sum()
+}
+
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    let count = points.len();
+    (0..count).map(|i| {
+        let (a, b) = (points[i], points[(i + 1) % count]);
+        a.0 * b.1 - b.0 * a.1
+    }).sum::<f32>() * 0.5
+}
+
+fn unit(v: (f32, f32)) -> (f32, f32) {
+    let l = length(v);
+    if l > 0.0 { (v.0 / l, v.1 / l) } else { (1.0, 0.0) }
+}
+
+fn flatten_figure(figure: &Figure, tolerance: f32) -> FlatFigure {
+    let mut points = vec![figure.start];
+
+    for segment in figure.segments.iter() {
+        let start = *points.last().unwrap();
+        match segment {
+            &PathSegment::Line(end) => points.push(end),
+            &PathSegment::QuadraticBezier{ control, end } => {
+                // The distance between a parabola and its chords is at most |p0 - 2p1 + p2| * h² / 4
+                let dd = length((start.0 - 2.0 * control.0 + end.0, start.1 - 2.0 * control.1 + end.1));
+                let lines = curve_lines((dd / (4.0 * tolerance)).sqrt());
+                for i in 1..(lines + 1) {
+                    let t = i as f32 / lines as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c) = (u * u, 2.0 * u * t, t * t);
+                    points.push((a * start.0 + b * control.0 + c * end.0, a * start.1 + b * control.1 + c * end.1));
+                }
+            },
+            &PathSegment::CubicBezier{ control1, control2, end } => {
+                // The second derivative of a cubic curve is at most 6 times the largest second difference of its points
+                let dd1 = length((start.0 - 2.0 * control1.0 + control2.0, start.1 - 2.0 * control1.1 + control2.1));
+                let dd2 = length((control1.0 - 2.0 * control2.0 + end.0, control1.1 - 2.0 * control2.1 + end.1));
+                let lines = curve_lines((3.0 * dd1.max(dd2) / (4.0 * tolerance)).sqrt());
+                for i in 1..(lines + 1) {
+                    let t = i as f32 / lines as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    points.push((
+                        a * start.0 + b * control1.0 + c * control2.0 + d * end.0,
+                        a * start.1 + b * control1.1 + c * control2.1 + d * end.1
+                    ));
+                }
+            },
+            &PathSegment::Arc{ end, radius, rotation, size, sweep } => {
+                flatten_arc(&mut points, start, end, radius, rotation, size, sweep, tolerance);
+            }
+        }
+    }
+
+    FlatFigure{ points: points, closed: figure.closed, filled: figure.filled }
+}
+
+/**
+    Add the points of an elliptical arc going from `start` to `end`. The center of the ellipse is found
+    like in the SVG specification: radii too small to join the two points are scaled up.
+*/
+fn flatten_arc(points: &mut Vec<(f32, f32)>, start: (f32, f32), end: (f32, f32), radius: (f32, f32), rotation: f32,
+  size: ArcSize, sweep: SweepDirection, tolerance: f32)
+{
+    let (mut rx, mut ry) = (radius.0.abs(), radius.1.abs());
+    if start == end { return; }
+    if rx == 0.0 || ry == 0.0 {
+        points.push(end);
+        return;
+    }
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((start.0 - end.0) * 0.5, (start.1 - end.1) * 0.5);
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let large = size == ArcSize::Large;
+    let clockwise = sweep == SweepDirection::Clockwise;
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = if den > 0.0 { (num / den).max(0.0).sqrt() } else { 0.0 };
+    if large == clockwise { coef = -coef; }
+
+    let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+    let center = (cos * cx1 - sin * cy1 + (start.0 + end.0) * 0.5, sin * cx1 + cos * cy1 + (start.1 + end.1) * 0.5);
+
+    let angle = |x: f32, y: f32| y.atan2(x);
+    let theta = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - theta;
+    if clockwise && delta < 0.0 { delta += 2.0 * PI; }
+    if !clockwise && delta > 0.0 { delta -= 2.0 * PI; }
+
+    // The distance between a circle of radius r and a chord covering the angle a is r * (1 - cos(a / 2))
+    let r = rx.max(ry);
+    let step = if tolerance < r { 2.0 * (1.0 - tolerance / r).acos() } else { PI / 2.0 };
+    let lines = curve_lines(delta.abs() / step);
+
+    for i in 1..lines {
+        let (s, c) = (theta + delta * (i as f32 / lines as f32)).sin_cos();
+        points.push((center.0 + rx * c * cos - ry * s * sin, center.1 + rx * c * sin + ry * s * cos));
+    }
+
+    points.push(end);
+}
+
+fn curve_lines(estimate: f32) -> u32 {
+    if estimate.is_finite() {
+        (estimate.ceil() as u32).max(1).min(MAX_CURVE_LINES)
+    } else {
+        1
+    }
+}
+
+fn length(v: (f32, f32)) -> f32 {
+    (v.0 * v.0 + v.1 * v.1).sqrt()
+}
@@ -4,6 +4,27 @@
 pub mod defs;
 mod brush;
 mod pen;
+mod path;
+mod text;
+mod bitmap;
+mod geometry;
+mod scene;
+mod backend;
+mod software;
+mod recording;
+mod svg;
+mod direct2d;
 
 pub use self::brush::{Brush, BrushT};
 pub use self::pen::{PenT, Pen};
+pub use self::path::{PathT, Path};
+pub use self::text::{TextFormatT, TextFormat, TextLayout};
+pub use self::bitmap::{BitmapT, RgbaBitmapT, Bitmap};
+pub use self::geometry::FlatFigure;
+pub use self::scene::{Scene, SceneNode, NodeStyle, NodeId, Shape, SceneEvent};
+pub use self::backend::{RenderBackend, Paint};
+pub use self::software::SoftwareRenderer;
+pub use self::recording::{DisplayList, DrawCommand};
+pub(crate) use self::direct2d::Direct2DBackend;
+pub(crate) use self::path::create_path_geometry;
+pub(crate) use self::text::{text_format_handle, create_text_layout};
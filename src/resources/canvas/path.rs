@@ -0,0 +1,170 @@
+/*!
+    Path resources for a canvas control
+*/
+use std::hash::Hash;
+use std::any::TypeId;
+
+use super::defs::{PathData, PathSegment};
+use controls::{Canvas, AnyHandle, ControlType};
+use resources::{ResourceT, Resource};
+use error::{Error, SystemError};
+use ui::Ui;
+
+use winapi::{ID2D1PathGeometry};
+
+/**
+    A template that creates a path used by a Canvas control. The resource is unpacked with the canvas.
+    The geometry is built once and can be filled or drawn any number of times.
+
+    Params:  
+    • `canvas`: The canvas control that will use the resource
+    • `path`: The figures of the path
+*/
+#[derive(Clone)]
+pub struct PathT<ID: Hash+Clone> {
+    pub canvas: ID,
+    pub path: PathData
+}
+
+impl<ID: Hash+Clone> ResourceT<ID> for PathT<ID> {
+    fn type_id(&self) -> TypeId {
+        TypeId::of::<Path>()
+    }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+
+        match ui.type_of_control(&self.canvas) {
+            Ok(ControlType::Canvas) => {/* All good */},
+            Ok(t) => { return Err(Error::BadParent( format!("A Path resource canvas must be a Canvas control. Got {:?}", t) )); }
+            Err(e) => { return Err(e); }
+        }
+
+        let factory = match ui.get::<Canvas<ID>>(&self.canvas) {
+            Ok(c) => c.get_factory(),
+            Err(_) => { unreachable!(); } // ui.type_of_control already check this
+        };
+
+        let factory = unsafe{&mut * factory};
+        match create_path_geometry(&self.path, factory) {
+            Ok(h) => { 
+                Ok(Box::new( Path{ handle: h, data: self.path.clone() } ))
+            },
+            Err(e) => { Err(e) }
+        }
+    }
+}
+
+
+/**
+    A path resource
+*/
+pub struct Path {
+    handle: *mut ID2D1PathGeometry,
+    data: PathData
+}
+
+impl Path {
+
+    /**
+        Return the figures of the path. Used by the renderer backends.
+    */
+    pub fn get_data(&self) -> &PathData {
+        &self.data
+    }
+
+}
+
+impl Resource for Path {
+
+    /**
+        Should return the underlying handle to the object
+    */
+    fn handle(&self) -> AnyHandle {
+        AnyHandle::Custom(TypeId::of::<Path>(), self.handle as usize)
+    }
+
+    /**
+        If specified, should free any ressource allocated in the template `build` function.
+    */
+    fn free(&mut self) {
+        if !self.handle.is_null() {
+            unsafe{ (&mut *self.handle).Release(); }
+            self.handle = ptr::null_mut();
+        }
+    }
+
+}
+
+
+// Private functions
+use winapi::{ID2D1Factory, S_OK};
+use std::ptr;
+
+/**
+    Build a path geometry from `path`. The caller owns the returned geometry.
+*/
+pub(crate) fn create_path_geometry(path: &PathData, factory: &mut ID2D1Factory) -> Result<*mut ID2D1PathGeometry, Error> {
+    use winapi::{ID2D1GeometrySink, D2D1_POINT_2F, D2D1_SIZE_F, D2D1_BEZIER_SEGMENT, D2D1_QUADRATIC_BEZIER_SEGMENT,
+      D2D1_ARC_SEGMENT, D2D1_FILL_MODE, D2D1_FIGURE_BEGIN_FILLED, D2D1_FIGURE_BEGIN_HOLLOW, D2D1_FIGURE_END_OPEN,
+      D2D1_FIGURE_END_CLOSED, D2D1_ARC_SIZE, D2D1_SWEEP_DIRECTION};
+
+    let point = |p: (f32, f32)| D2D1_POINT_2F{ x: p.0, y: p.1 };
+
+    let mut geometry: *mut ID2D1PathGeometry = ptr::null_mut();
+    if unsafe{ factory.CreatePathGeometry(&mut geometry) } != S_OK {
+        return Err(Error::System(SystemError::ComError("Failed to create the path geometry".to_string())));
+    }
+
+    let mut sink: *mut ID2D1GeometrySink = ptr::null_mut();
+    if unsafe{ (&mut *geometry).Open(&mut sink) } != S_OK {
+        unsafe{ (&mut *geometry).Release(); }
+        return Err(Error::System(SystemError::ComError("Failed to open the path geometry".to_string())));
+    }
+
+    let result = unsafe {
+        let sink = &mut *sink;
+        sink.SetFillMode(D2D1_FILL_MODE(path.fill_mode as u32));
+
+        for figure in path.figures.iter() {
+            let begin = if figure.filled { D2D1_FIGURE_BEGIN_FILLED } else { D2D1_FIGURE_BEGIN_HOLLOW };
+            sink.BeginFigure(point(figure.start), begin);
+
+            for segment in figure.segments.iter() {
+                match segment {
+                    &PathSegment::Line(end) => sink.AddLine(point(end)),
+                    &PathSegment::QuadraticBezier{control, end} => {
+                        let s = D2D1_QUADRATIC_BEZIER_SEGMENT{ point1: point(control), point2: point(end) };
+                        sink.AddQuadraticBezier(&s);
+                    },
+                    &PathSegment::CubicBezier{control1, control2, end} => {
+                        let s = D2D1_BEZIER_SEGMENT{ point1: point(control1), point2: point(control2), point3: point(end) };
+                        sink.AddBezier(&s);
+                    },
+                    &PathSegment::Arc{end, radius, rotation, size, sweep} => {
+                        let s = D2D1_ARC_SEGMENT{
+                            point: point(end),
+                            size: D2D1_SIZE_F{ width: radius.0, height: radius.1 },
+                            rotationAngle: rotation,
+                            sweepDirection: D2D1_SWEEP_DIRECTION(sweep as u32),
+                            arcSize: D2D1_ARC_SIZE(size as u32)
+                        };
+                        sink.AddArc(&s);
+                    }
+                }
+            }
+
+            sink.EndFigure(if figure.closed { D2D1_FIGURE_END_CLOSED } else { D2D1_FIGURE_END_OPEN });
+        }
+
+        let result = sink.Close();
+        sink.Release();
+        result
+    };
+
+    if result == S_OK {
+        Ok(geometry)
+    } else {
+        unsafe{ (&mut *geometry).Release(); }
+        Err(Error::System(SystemError::ComError("Failed to build the path geometry".to_string())))
+    }
+}
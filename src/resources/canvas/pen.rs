@@ -4,7 +4,7 @@
 use std::hash::Hash;
 use std::any::TypeId;
 
-use super::defs::{CapStyle, LineJoin, DashStyle};
+use super::defs::{CapStyle, LineJoin, DashStyle, Pen as PenStyle};
 use controls::{Canvas, AnyHandle, ControlType};
 use resources::{ResourceT, Resource};
 use error::{Error, SystemError};
@@ -49,10 +49,20 @@ impl<ID: Hash+Clone> ResourceT<ID> for PenT<ID> {
             Err(_) => { unreachable!(); } // ui.type_of_control already check this
         };
 
+        let style = PenStyle {
+            start_cap: self.start_cap,
+            end_cap: self.end_cap,
+            dash_cap: self.dash_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+            dash_style: self.dash_style,
+            dash_offset: self.dash_offset
+        };
+
         let factory = unsafe{&mut * factory};
-        match create_pen(self, factory) {
+        match create_pen(&style, factory) {
             Ok(h) => { 
-                Ok(Box::new( Pen{ handle: h } ))
+                Ok(Box::new( Pen{ handle: h, style: style } ))
             },
             Err(e) => { Err(e) }
         }
@@ -64,7 +74,19 @@ impl<ID: Hash+Clone> ResourceT<ID> for PenT<ID> {
     A brush resource
 */
 pub struct Pen {
-    handle: *mut ID2D1StrokeStyle
+    handle: *mut ID2D1StrokeStyle,
+    style: PenStyle
+}
+
+impl Pen {
+
+    /**
+        Return the style of the lines drawn with the pen. Used by the renderer backends.
+    */
+    pub fn get_style(&self) -> &PenStyle {
+        &self.style
+    }
+
 }
 
 impl Resource for Pen {
@@ -90,10 +112,12 @@ impl Resource for Pen {
 use winapi::{ID2D1Factory, S_OK};
 use std::ptr;
 
-fn create_pen<ID: Hash+Clone>(pen: &PenT<ID>, factory: &mut ID2D1Factory) -> Result<*mut ID2D1StrokeStyle, Error> {
+/**
+    Create the stroke style of a pen
+*/
+pub(crate) fn create_pen(pen: &PenStyle, factory: &mut ID2D1Factory) -> Result<*mut ID2D1StrokeStyle, Error> {
     use winapi::{D2D1_STROKE_STYLE_PROPERTIES, D2D1_CAP_STYLE, D2D1_LINE_JOIN, D2D1_DASH_STYLE};
 
-    let pen = pen.clone();
     let start_cap = D2D1_CAP_STYLE(pen.start_cap as u32);
     let end_cap = D2D1_CAP_STYLE(pen.end_cap as u32);
     let dash_cap = D2D1_CAP_STYLE(pen.dash_cap as u32);
@@ -0,0 +1,238 @@
+/*!
+    Recording of the canvas drawing. A display list saves the commands received by a renderer backend,
+    so that they can be drawn again or exported as an SVG document.
+*/
+
+use std::rc::Rc;
+
+use super::defs::{BitmapInterpolation, Pen, PathData, Rectangle, Matrix, TextStyle};
+use super::backend::{RenderBackend, Paint};
+use super::svg::write_svg;
+use resources::ImageData;
+use error::Error;
+
+/**
+    A drawing command saved in a display list. The coordinates are not transformed, the transformation used by the
+    commands that follow a `SetMatrix` command is its matrix.
+
+    • `Clear`: Replace the drawing area inside the current clip by a color (red, green, blue, alpha)  
+    • `SetMatrix`: Set the transformation of the next commands  
+    • `PushClip`, `PopClip`: Restrict the drawing to the bounds of a transformed rectangle  
+    • `PushLayer`, `PopLayer`: Draw the commands in a layer blended with `opacity` and limited by `mask` and `bounds`  
+    • `FillPath`: Fill the figures of a path  
+    • `StrokePath`: Draw the outline of a path. `pen` is `None` for a plain line  
+    • `DrawImage`: Draw the area `src` (in pixels) of an image in the rectangle `dest`  
+    • `DrawText`: Draw a text in the layout box `bounds`  
+*/
+#[derive(Clone, Debug)]
+pub enum DrawCommand {
+    Clear((f32, f32, f32, f32)),
+    SetMatrix(Matrix),
+    PushClip(Rectangle),
+    PopClip,
+    PushLayer{ opacity: f32, mask: Option<PathData>, bounds: Option<Rectangle> },
+    PopLayer,
+    FillPath{ path: PathData, paint: Paint },
+    StrokePath{ path: PathData, paint: Paint, pen: Option<Pen>, width: f32 },
+    DrawImage{ image: Rc<ImageData>, dest: Rectangle, src: Option<Rectangle>, opacity: f32, interpolation: BitmapInterpolation },
+    DrawText{ text: String, style: TextStyle, bounds: Rectangle, paint: Paint }
+}
+
+/**
+    A list of drawing commands. A display list is a renderer backend that saves what is drawn instead of drawing it,
+    see `Canvas::record`.
+
+    The commands can be drawn again on any renderer backend with `replay`, on a canvas with `CanvasRenderer::draw_display_list`,
+    or saved as an SVG document with `to_svg`.
+*/
+#[derive(Clone, Debug)]
+pub struct DisplayList {
+    size: (f32, f32),
+    matrix: Matrix,
+    commands: Vec<DrawCommand>
+}
+
+impl DisplayList {
+
+    /**
+        Create an empty display list. `width` and `height` are the size of the drawing area returned by `get_size`
+        and the size of the exported SVG documents.
+    */
+    pub fn new(width: f32, height: f32) -> DisplayList {
+        DisplayList{ size: (width, height), matrix: Matrix::identity(), commands: Vec::new() }
+    }
+
+    /**
+        Return the commands of the list in the drawing order
+    */
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /**
+        Return the number of commands of the list
+    */
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /**
+        Return true if the list has no command
+    */
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /**
+        Add a command at the end of the list
+    */
+    pub fn push(&mut self, command: DrawCommand) {
+        if let DrawCommand::SetMatrix(ref m) = command {
+            self.matrix = m.clone();
+        }
+
+        self.commands.push(command);
+    }
+
+    /**
+        Draw the commands of the list on `backend`. The transformation of the backend is applied after the
+        transformation of the commands and it is restored once the commands are drawn.
+
+        The drawing stops at the first command that cannot be drawn. The clips and the layers that are not
+        popped by the list are popped before returning.
+    */
+    pub fn replay(&self, backend: &mut RenderBackend) -> Result<(), Error> {
+        let base = backend.get_matrix();
+        let mut layers: Vec<bool> = Vec::new();
+        let mut result = Ok(());
+
+        for command in self.commands.iter() {
+            result = match command {
+                &DrawCommand::Clear(color) => { backend.clear(color); Ok(()) },
+                &DrawCommand::SetMatrix(ref m) => { backend.set_matrix(&m.multiply(&base)); Ok(()) },
+                &DrawCommand::PushClip(ref r) => { backend.push_clip(r); layers.push(false); Ok(()) },
+                &DrawCommand::PopClip => match layers.last() {
+                    Some(&false) => { layers.pop(); backend.pop_clip(); Ok(()) },
+                    _ => Err(Error::UserError("The display list pops a clip that was not pushed".to_string()))
+                },
+                &DrawCommand::PushLayer{ opacity, ref mask, ref bounds } => {
+                    let result = backend.push_layer(opacity, mask.as_ref(), bounds.as_ref());
+                    if result.is_ok() { layers.push(true); }
+                    result
+                },
+                &DrawCommand::PopLayer => match layers.last() {
+                    Some(&true) => { layers.pop(); backend.pop_layer(); Ok(()) },
+                    _ => Err(Error::UserError("The display list pops a layer that was not pushed".to_string()))
+                },
+                &DrawCommand::FillPath{ ref path, ref paint } => backend.fill_path(path, paint),
+                &DrawCommand::StrokePath{ ref path, ref paint, ref pen, width } => backend.stroke_path(path, paint, pen.as_ref(), width),
+                &DrawCommand::DrawImage{ ref image, ref dest, ref src, opacity, interpolation } =>
+                    backend.draw_image(image, dest, src.as_ref(), opacity, interpolation),
+                &DrawCommand::DrawText{ ref text, ref style, ref bounds, ref paint } => backend.draw_text(text, style, bounds, paint)
+            };
+
+            if result.is_err() { break; }
+        }
+
+        while let Some(layer) = layers.pop() {
+            if layer { backend.pop_layer(); } else { backend.pop_clip(); }
+        }
+
+        backend.set_matrix(&base);
+        result
+    }
+
+    /**
+        Return the commands of the list as an SVG document of the size of the list.
+
+        The brushes, the pens and the transformations are kept. A few features do not exist in SVG and are approximated:
+        • The pens use the dash cap for the ends of the lines if they are dashed, and the start cap if they are not  
+        • Triangle caps are exported as round caps and miter joins are exported like miter or bevel joins  
+        • The clamped bitmap brushes are exported like the wrapped ones  
+        • The text is not wrapped and its position uses an approximation of the font metrics  
+    */
+    pub fn to_svg(&self) -> String {
+        write_svg(self.size, &self.commands)
+    }
+
+    /// Share the pixels of the images already saved in the list
+    fn share_image(&self, image: &ImageData) -> Rc<ImageData> {
+        for command in self.commands.iter().rev() {
+            if let &DrawCommand::DrawImage{ image: ref saved, .. } = command {
+                if **saved == *image { return saved.clone(); }
+            }
+        }
+
+        Rc::new(image.clone())
+    }
+
+}
+
+impl RenderBackend for DisplayList {
+
+    fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    fn clear(&mut self, color: (f32, f32, f32, f32)) {
+        self.push(DrawCommand::Clear(color));
+    }
+
+    fn set_matrix(&mut self, m: &Matrix) {
+        if *m == self.matrix { return; }
+
+        // Only the last transformation of a series is used
+        if let Some(&mut DrawCommand::SetMatrix(ref mut last)) = self.commands.last_mut() {
+            *last = m.clone();
+            self.matrix = m.clone();
+            return;
+        }
+
+        self.push(DrawCommand::SetMatrix(m.clone()));
+    }
+
+    fn get_matrix(&self) -> Matrix {
+        self.matrix.clone()
+    }
+
+    fn push_clip(&mut self, r: &Rectangle) {
+        self.push(DrawCommand::PushClip(r.clone()));
+    }
+
+    fn pop_clip(&mut self) {
+        self.push(DrawCommand::PopClip);
+    }
+
+    fn push_layer(&mut self, opacity: f32, mask: Option<&PathData>, bounds: Option<&Rectangle>) -> Result<(), Error> {
+        self.push(DrawCommand::PushLayer{ opacity: opacity, mask: mask.cloned(), bounds: bounds.cloned() });
+        Ok(())
+    }
+
+    fn pop_layer(&mut self) {
+        self.push(DrawCommand::PopLayer);
+    }
+
+    fn fill_path(&mut self, path: &PathData, paint: &Paint) -> Result<(), Error> {
+        self.push(DrawCommand::FillPath{ path: path.clone(), paint: paint.clone() });
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &PathData, paint: &Paint, pen: Option<&Pen>, width: f32) -> Result<(), Error> {
+        self.push(DrawCommand::StrokePath{ path: path.clone(), paint: paint.clone(), pen: pen.cloned(), width: width });
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &ImageData, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32,
+      interpolation: BitmapInterpolation) -> Result<(), Error>
+    {
+        let image = self.share_image(image);
+        self.push(DrawCommand::DrawImage{ image: image, dest: dest.clone(), src: src.cloned(), opacity: opacity, interpolation: interpolation });
+        Ok(())
+    }
+
+    fn draw_text(&mut self, text: &str, style: &TextStyle, bounds: &Rectangle, paint: &Paint) -> Result<(), Error> {
+        self.push(DrawCommand::DrawText{ text: text.to_string(), style: style.clone(), bounds: bounds.clone(), paint: paint.clone() });
+        Ok(())
+    }
+
+}
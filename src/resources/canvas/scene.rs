@@ -0,0 +1,576 @@
+/*!
+    A retained description of the content of a canvas. The scene keeps the shapes to draw, finds the shape
+    under a point and tracks the mouse buttons pressed on the shapes. Nothing in this module calls Direct2D,
+    so scenes can be built and hit-tested without a canvas.
+*/
+
+use defs::MouseButton;
+use super::defs::{Rectangle, Ellipse, PathData, FillMode, ArcSize, SweepDirection, Matrix};
+use super::geometry::{FlatFigure, bounds_of, fill_contains, stroke_contains};
+
+/// The identifier of a node in a scene. Chosen by the application when the node is inserted.
+pub type NodeId = u64;
+
+/// The distance between the curves of the shapes and the lines used to hit-test them
+const FLATTEN_TOLERANCE: f32 = 0.1;
+
+/**
+    The geometry of a scene node, in the coordinates of the node
+
+    • `Rectangle`: A rectangle  
+    • `RoundedRectangle`: A rectangle with rounded corners of radius `radius` (x, y)  
+    • `Ellipse`: An ellipse  
+    • `Line`: A straight line. A line is only hit on its stroke  
+    • `Polyline`: Straight lines joining `points`. Filling the node fills the polygon  
+    • `Path`: Any path  
+    • `Text`: A text drawn in the layout box `bounds`. The whole box can be hit  
+*/
+#[derive(Clone)]
+pub enum Shape {
+    Rectangle(Rectangle),
+    RoundedRectangle{ rect: Rectangle, radius: (f32, f32) },
+    Ellipse(Ellipse),
+    Line{ start: (f32, f32), end: (f32, f32) },
+    Polyline{ points: Vec<(f32, f32)>, closed: bool },
+    Path(PathData),
+    Text{ text: String, bounds: Rectangle }
+}
+
+impl Shape {
+
+    /**
+        Return the path describing the outline of the shape. The shapes are drawn with the matching renderer
+        methods, the path is used to measure and hit-test them.
+    */
+    pub fn to_path(&self) -> PathData {
+        match self {
+            &Shape::Rectangle(ref r) | &Shape::Text{ bounds: ref r, .. } => rectangle_path(r),
+            &Shape::RoundedRectangle{ rect: ref r, radius } => {
+                let rx = radius.0.abs().min((r.right - r.left).abs() * 0.5);
+                let ry = radius.1.abs().min((r.bottom - r.top).abs() * 0.5);
+                if rx == 0.0 || ry == 0.0 { return rectangle_path(r); }
+
+                let corner = |path: PathData, x: f32, y: f32| path.arc_to((x, y), (rx, ry), 0.0, ArcSize::Small, SweepDirection::Clockwise);
+                let path = PathData::new().fill_mode(FillMode::Winding).move_to(r.left + rx, r.top).line_to(r.right - rx, r.top);
+                let path = corner(path, r.right, r.top + ry).line_to(r.right, r.bottom - ry);
+                let path = corner(path, r.right - rx, r.bottom).line_to(r.left + rx, r.bottom);
+                let path = corner(path, r.left, r.bottom - ry).line_to(r.left, r.top + ry);
+                corner(path, r.left + rx, r.top).close()
+            },
+            &Shape::Ellipse(ref e) => {
+                let (cx, cy) = e.center;
+                let (rx, ry) = e.radius;
+                PathData::new().fill_mode(FillMode::Winding)
+                    .move_to(cx - rx, cy)
+                    .arc_to((cx + rx, cy), (rx, ry), 0.0, ArcSize::Small, SweepDirection::Clockwise)
+                    .arc_to((cx - rx, cy), (rx, ry), 0.0, ArcSize::Small, SweepDirection::Clockwise)
+                    .close()
+            },
+            &Shape::Line{ start, end } => PathData::polyline(&[start, end], false).hollow(),
+            &Shape::Polyline{ ref points, closed } => PathData::polyline(points, closed),
+            &Shape::Path(ref path) => path.clone()
+        }
+    }
+
+}
+
+/**
+    How a scene node is painted. The ids are the ids of resources saved in the canvas.
+    A node without a fill brush nor a stroke brush is not drawn, but it can still be hit.
+
+    Members:  
+    • `fill`: The brush that fills the shape. Text nodes draw their text with this brush  
+    • `stroke`: The brush that draws the outline of the shape  
+    • `pen`: The pen used to draw the outline. `None` draws a plain line  
+    • `stroke_width`: The width of the outline  
+    • `text_format`: The text format of the text nodes  
+*/
+#[derive(Clone)]
+pub struct NodeStyle<ID: Clone> {
+    pub fill: Option<ID>,
+    pub stroke: Option<ID>,
+    pub pen: Option<ID>,
+    pub stroke_width: f32,
+    pub text_format: Option<ID>
+}
+
+/**
+    A shape in a scene
+
+    Members:  
+    • `shape`: The geometry of the node  
+    • `style`: How the node is painted  
+    • `transform`: The transformation from the node coordinates to the coordinates of its parent  
+    • `parent`: The node that positions this node. A node is moved and hidden with its parent  
+    • `z`: The drawing order. Nodes with a greater `z` are drawn over the others, equal values are drawn in insertion order  
+    • `visible`: If the node is drawn and can be hit  
+    • `hit_testable`: If the node can be returned by the hit tests. Nodes that are not hit testable let the mouse through  
+*/
+#[derive(Clone)]
+pub struct SceneNode<ID: Clone> {
+    pub shape: Shape,
+    pub style: NodeStyle<ID>,
+    pub transform: Matrix,
+    pub parent: Option<NodeId>,
+    pub z: i32,
+    pub visible: bool,
+    pub hit_testable: bool
+}
+
+impl<ID: Clone> SceneNode<ID> {
+
+    /// Create a visible node that is not painted, at the origin of the scene
+    pub fn new(shape: Shape) -> SceneNode<ID> {
+        SceneNode {
+            shape: shape,
+            style: NodeStyle{ fill: None, stroke: None, pen: None, stroke_width: 1.0, text_format: None },
+            transform: Matrix::identity(),
+            parent: None,
+            z: 0,
+            visible: true,
+            hit_testable: true
+        }
+    }
+
+    /// Fill the shape with the brush identified by `brush`
+    pub fn fill(mut self, brush: ID) -> SceneNode<ID> {
+        self.style.fill = Some(brush);
+        self
+    }
+
+    /// Draw the outline of the shape with the brush identified by `brush` and a line of `width`
+    pub fn stroke(mut self, brush: ID, width: f32) -> SceneNode<ID> {
+        self.style.stroke = Some(brush);
+        self.style.stroke_width = width;
+        self
+    }
+
+    /// Draw the outline of the shape with the pen identified by `pen`
+    pub fn pen(mut self, pen: ID) -> SceneNode<ID> {
+        self.style.pen = Some(pen);
+        self
+    }
+
+    /// Draw the text of a text node with the text format identified by `format`
+    pub fn text_format(mut self, format: ID) -> SceneNode<ID> {
+        self.style.text_format = Some(format);
+        self
+    }
+
+    /// Set the transformation of the node
+    pub fn transform(mut self, transform: Matrix) -> SceneNode<ID> {
+        self.transform = transform;
+        self
+    }
+
+    /// Set the parent of the node
+    pub fn parent(mut self, parent: NodeId) -> SceneNode<ID> {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Set the drawing order of the node
+    pub fn z(mut self, z: i32) -> SceneNode<ID> {
+        self.z = z;
+        self
+    }
+
+    /// Hide the node
+    pub fn hidden(mut self) -> SceneNode<ID> {
+        self.visible = false;
+        self
+    }
+
+    /// Exclude the node from the hit tests
+    pub fn ignore_hits(mut self) -> SceneNode<ID> {
+        self.hit_testable = false;
+        self
+    }
+
+    /**
+        Check if `point`, in the coordinates of the node, hits the shape. The fill area is hit if the node
+        has a fill brush or no style at all, the outline is hit if the node has a stroke brush.
+        `margin` extends the outline on both sides.
+    */
+    pub fn contains(&self, point: (f32, f32), margin: f32) -> bool {
+        let figures = self.shape.to_path().flatten(FLATTEN_TOLERANCE);
+        let (fill, stroke) = (&self.style.fill, &self.style.stroke);
+
+        let filled = match self.shape {
+            Shape::Line{..} => false,
+            Shape::Text{..} => true,
+            _ => fill.is_some() || stroke.is_none()
+        };
+
+        let mode = match self.shape {
+            Shape::Path(ref p) => p.fill_mode,
+            _ => FillMode::Winding
+        };
+
+        let width = if stroke.is_some() { self.style.stroke_width } else { 0.0 };
+        (filled && fill_contains(&figures, mode, point)) || stroke_contains(&figures, point, width * 0.5 + margin)
+    }
+
+}
+
+/**
+    A change of the mouse state over the nodes of a scene. Returned by the `Scene::mouse_*` methods.
+
+    • `MouseDown`: A mouse button was pressed over a node  
+    • `MouseUp`: A mouse button was released over a node  
+    • `Click`: A mouse button was pressed and released over the same node  
+    • `Enter`: The mouse entered a node  
+    • `Leave`: The mouse left a node  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneEvent {
+    MouseDown(NodeId, MouseButton),
+    MouseUp(NodeId, MouseButton),
+    Click(NodeId, MouseButton),
+    Enter(NodeId),
+    Leave(NodeId)
+}
+
+/**
+    A retained list of shapes. Set it on a canvas with `Canvas::set_scene` to draw it automatically
+    and to receive the node events, or draw it with `CanvasRenderer::draw_scene`.
+
+    The scene remembers if it was modified since the last call to `take_dirty`. Every method that takes
+    `&mut self`, except the `mouse_*` methods, marks the scene as modified.
+*/
+#[derive(Clone)]
+pub struct Scene<ID: Clone> {
+    nodes: Vec<(NodeId, SceneNode<ID>)>,
+    background: Option<(f32, f32, f32, f32)>,
+    hit_margin: f32,
+    hovered: Option<NodeId>,
+    pressed: Vec<(MouseButton, NodeId)>,
+    dirty: bool
+}
+
+impl<ID: Clone> Scene<ID> {
+
+    /// Create an empty scene with a transparent background
+    pub fn new() -> Scene<ID> {
+        Scene{ nodes: Vec::new(), background: None, hit_margin: 0.0, hovered: None, pressed: Vec::new(), dirty: true }
+    }
+
+    /**
+        Add a node to the scene. If a node with the same id exists, it is replaced and returned.
+        A replaced node keeps its place in the drawing order.
+    */
+    pub fn insert(&mut self, id: NodeId, node: SceneNode<ID>) -> Option<SceneNode<ID>> {
+        self.dirty = true;
+        match self.nodes.iter().position(|&(i, _)| i == id) {
+            Some(index) => Some(::std::mem::replace(&mut self.nodes[index].1, node)),
+            None => {
+                self.nodes.push((id, node));
+                None
+            }
+        }
+    }
+
+    /**
+        Remove a node and its children from the scene. Return the removed node.
+    */
+    pub fn remove(&mut self, id: NodeId) -> Option<SceneNode<ID>> {
+        let index = match self.nodes.iter().position(|&(i, _)| i == id) {
+            Some(index) => index,
+            None => { return None; }
+        };
+
+        self.dirty = true;
+        let (_, node) = self.nodes.remove(index);
+
+        let children: Vec<NodeId> = self.nodes.iter().filter(|&&(_, ref n)| n.parent == Some(id)).map(|&(i, _)| i).collect();
+        for child in children {
+            self.remove(child);
+        }
+
+        if self.hovered == Some(id) { self.hovered = None; }
+        self.pressed.retain(|&(_, n)| n != id);
+
+        Some(node)
+    }
+
+    /// Remove every node of the scene
+    pub fn clear(&mut self) {
+        self.dirty = true;
+        self.nodes.clear();
+        self.hovered = None;
+        self.pressed.clear();
+    }
+
+    /// Return the node identified by `id`
+    pub fn get(&self, id: NodeId) -> Option<&SceneNode<ID>> {
+        self.nodes.iter().find(|&&(i, _)| i == id).map(|&(_, ref n)| n)
+    }
+
+    /// Return a mutable reference to the node identified by `id`
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut SceneNode<ID>> {
+        self.dirty = true;
+        self.nodes.iter_mut().find(|&&mut (i, _)| i == id).map(|&mut (_, ref mut n)| n)
+    }
+
+    /// Check if the scene has a node identified by `id`
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Return the number of nodes in the scene
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if the scene has no node
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Return the ids of the nodes in insertion order
+    pub fn ids(&self) -> Vec<NodeId> {
+        self.nodes.iter().map(|&(i, _)| i).collect()
+    }
+
+    /**
+        Set the transformation of a node. Return false if the node does not exist.
+    */
+    pub fn set_transform(&mut self, id: NodeId, transform: Matrix) -> bool {
+        self.get_mut(id).map(|n| n.transform = transform).is_some()
+    }
+
+    /**
+        Set the style of a node. Return false if the node does not exist.
+    */
+    pub fn set_style(&mut self, id: NodeId, style: NodeStyle<ID>) -> bool {
+        self.get_mut(id).map(|n| n.style = style).is_some()
+    }
+
+    /**
+        Set the shape of a node. Return false if the node does not exist.
+    */
+    pub fn set_shape(&mut self, id: NodeId, shape: Shape) -> bool {
+        self.get_mut(id).map(|n| n.shape = shape).is_some()
+    }
+
+    /**
+        Set the drawing order of a node. Return false if the node does not exist.
+    */
+    pub fn set_z(&mut self, id: NodeId, z: i32) -> bool {
+        self.get_mut(id).map(|n| n.z = z).is_some()
+    }
+
+    /**
+        Show or hide a node. Return false if the node does not exist.
+    */
+    pub fn set_visible(&mut self, id: NodeId, visible: bool) -> bool {
+        self.get_mut(id).map(|n| n.visible = visible).is_some()
+    }
+
+    /// Set the color that clears the canvas before the nodes are drawn. `None` keeps the canvas content.
+    pub fn set_background(&mut self, color: Option<(f32, f32, f32, f32)>) {
+        self.dirty = true;
+        self.background = color;
+    }
+
+    /// Return the color that clears the canvas before the nodes are drawn
+    pub fn get_background(&self) -> Option<(f32, f32, f32, f32)> {
+        self.background
+    }
+
+    /**
+        Set the distance around the outlines of the shapes that still hits them, in scene units.
+        Makes thin lines easier to click.
+    */
+    pub fn set_hit_margin(&mut self, margin: f32) {
+        self.dirty = true;
+        self.hit_margin = margin.max(0.0);
+    }
+
+    /// Return the distance around the outlines of the shapes that still hits them
+    pub fn get_hit_margin(&self) -> f32 {
+        self.hit_margin
+    }
+
+    /**
+        Return the transformation from the coordinates of a node to the coordinates of the scene,
+        or `None` if the node does not exist. A node without parent uses its own transformation.
+    */
+    pub fn world_transform(&self, id: NodeId) -> Option<Matrix> {
+        let mut node = match self.get(id) {
+            Some(n) => n,
+            None => { return None; }
+        };
+
+        // The depth is limited by the number of nodes in case the parents form a cycle
+        let mut transform = node.transform;
+        for _ in 0..self.nodes.len() {
+            node = match node.parent.and_then(|p| self.get(p)) {
+                Some(parent) => parent,
+                None => break
+            };
+            transform = transform.multiply(&node.transform);
+        }
+
+        Some(transform)
+    }
+
+    /**
+        Check if a node and all its parents are visible
+    */
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        let mut node = match self.get(id) {
+            Some(n) => n,
+            None => { return false; }
+        };
+
+        for _ in 0..self.nodes.len() {
+            if !node.visible { return false; }
+            node = match node.parent.and_then(|p| self.get(p)) {
+                Some(parent) => parent,
+                None => break
+            };
+        }
+
+        true
+    }
+
+    /**
+        Return the ids of the visible nodes in the order they are drawn
+    */
+    pub fn draw_order(&self) -> Vec<NodeId> {
+        let mut order: Vec<(i32, usize, NodeId)> = self.nodes.iter().enumerate()
+            .filter(|&(_, &(id, _))| self.is_visible(id))
+            .map(|(index, &(id, ref node))| (node.z, index, id))
+            .collect();
+
+        order.sort();
+        order.into_iter().map(|(_, _, id)| id).collect()
+    }
+
+    /**
+        Return the smallest axis-aligned rectangle that contains a node, in the coordinates of the scene.
+        Return `None` if the node does not exist or has an empty shape. The width of the outline is not included.
+    */
+    pub fn bounds(&self, id: NodeId) -> Option<Rectangle> {
+        let (node, transform) = match (self.get(id), self.world_transform(id)) {
+            (Some(n), Some(t)) => (n, t),
+            _ => { return None; }
+        };
+
+        let figures: Vec<FlatFigure> = node.shape.to_path().flatten(FLATTEN_TOLERANCE);
+        let points: Vec<(f32, f32)> = figures.iter().flat_map(|f| f.points.iter()).map(|&p| transform.transform_point(p)).collect();
+        bounds_of(points.iter())
+    }
+
+    /**
+        Return the topmost node under `point`, in the coordinates of the scene. Hidden nodes and nodes
+        excluded from the hit tests are skipped.
+    */
+    pub fn hit_test(&self, point: (f32, f32)) -> Option<NodeId> {
+        self.hit_test_all(point).into_iter().next()
+    }
+
+    /**
+        Return every node under `point`, from the topmost to the bottommost
+    */
+    pub fn hit_test_all(&self, point: (f32, f32)) -> Vec<NodeId> {
+        let mut hits = Vec::new();
+        for id in self.draw_order().into_iter().rev() {
+            let node = self.get(id).unwrap();
+            if !node.hit_testable { continue; }
+
+            let inverse = match self.world_transform(id).and_then(|t| t.invert()) {
+                Some(m) => m,
+                None => { continue; }
+            };
+
+            // The margin is given in scene units, scale it like the shape
+            let scale = inverse.determinant().abs().sqrt();
+            if node.contains(inverse.transform_point(point), self.hit_margin * scale) {
+                hits.push(id);
+            }
+        }
+
+        hits
+    }
+
+    /// Return the node under the mouse, as tracked by `mouse_move`
+    pub fn get_hovered(&self) -> Option<NodeId> {
+        self.hovered
+    }
+
+    /**
+        Update the node under the mouse after the mouse moved to `point`. Return the `Leave` and `Enter` events
+        if the mouse changed node.
+    */
+    pub fn mouse_move(&mut self, point: (f32, f32)) -> Vec<SceneEvent> {
+        let hit = self.hit_test(point);
+        self.hover(hit)
+    }
+
+    /**
+        Forget the node under the mouse after the mouse left the canvas. Return a `Leave` event if the mouse was over a node.
+    */
+    pub fn mouse_leave(&mut self) -> Vec<SceneEvent> {
+        self.hover(None)
+    }
+
+    /**
+        Handle a mouse button pressed at `point`. Return the events sent to the node under the mouse.
+    */
+    pub fn mouse_down(&mut self, point: (f32, f32), btn: MouseButton) -> Vec<SceneEvent> {
+        let hit = self.hit_test(point);
+        let mut events = self.hover(hit);
+
+        self.pressed.retain(|&(b, _)| b != btn);
+        if let Some(id) = hit {
+            self.pressed.push((btn, id));
+            events.push(SceneEvent::MouseDown(id, btn));
+        }
+
+        events
+    }
+
+    /**
+        Handle a mouse button released at `point`. Return the events sent to the node under the mouse.
+        A `Click` is returned if the button was pressed on the same node.
+    */
+    pub fn mouse_up(&mut self, point: (f32, f32), btn: MouseButton) -> Vec<SceneEvent> {
+        let hit = self.hit_test(point);
+        let mut events = self.hover(hit);
+
+        let pressed = self.pressed.iter().position(|&(b, _)| b == btn).map(|index| self.pressed.remove(index).1);
+        if let Some(id) = hit {
+            events.push(SceneEvent::MouseUp(id, btn));
+            if pressed == Some(id) {
+                events.push(SceneEvent::Click(id, btn));
+            }
+        }
+
+        events
+    }
+
+    /**
+        Return true if the scene was modified since the last call and reset the flag
+    */
+    pub fn take_dirty(&mut self) -> bool {
+        ::std::mem::replace(&mut self.dirty, false)
+    }
+
+    fn hover(&mut self, hit: Option<NodeId>) -> Vec<SceneEvent> {
+        let mut events = Vec::new();
+        if hit != self.hovered {
+            if let Some(id) = self.hovered { events.push(SceneEvent::Leave(id)); }
+            if let Some(id) = hit { events.push(SceneEvent::Enter(id)); }
+            self.hovered = hit;
+        }
+
+        events
+    }
+
+}
+
+fn rectangle_path(r: &Rectangle) -> PathData {
+    PathData::polyline(&[(r.left, r.top), (r.right, r.top), (r.right, r.bottom), (r.left, r.bottom)], true)
+}
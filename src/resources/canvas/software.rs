@@ -0,0 +1,599 @@
+/*!
+    A canvas backend that draws in memory. Nothing in this module calls Direct2D, so it can render
+    without a window, for example to produce snapshots in tests.
+*/
+
+use std::cmp::Ordering;
+use std::mem;
+
+use super::defs::{PathData, Pen, CapStyle, LineJoin, DashStyle, FillMode, Rectangle, Matrix, ExtendMode,
+  BitmapInterpolation, GradientStop};
+use super::backend::{RenderBackend, Paint};
+use super::geometry::stroke_outline;
+use resources::ImageData;
+use error::Error;
+
+/// The number of sample lines in a row of pixels. The horizontal coverage is exact.
+const SAMPLE_LINES: usize = 16;
+
+/// The maximum distance, in pixels, between the curves and the lines that replace them
+const TOLERANCE: f32 = 0.1;
+
+/// A premultiplied (red, green, blue, alpha) color
+type Color = [f32; 4];
+
+/**
+    A backend that rasterizes the drawing in a premultiplied RGBA buffer. The shapes are antialiased,
+    the gradients and the bitmaps are sampled at the center of the pixels.
+    Text cannot be drawn because the glyphs are rendered by DirectWrite.
+
+    Use it with `Canvas::render_with` to draw with the canvas resources, or call the `RenderBackend`
+    functions directly to draw without any window. One unit is one pixel.
+*/
+pub struct SoftwareRenderer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    matrix: Matrix,
+    clips: Vec<Rectangle>,
+    layers: Vec<Layer>
+}
+
+/// A layer pushed on the renderer. The pixels under the layer are saved until it is popped
+struct Layer {
+    below: Vec<Color>,
+    opacity: f32,
+    mask: Option<Vec<f32>>
+}
+
+impl SoftwareRenderer {
+
+    /**
+        Create a transparent renderer of `width` x `height` pixels.
+        Return an error if the size is empty or too large for an image.
+    */
+    pub fn new(width: u32, height: u32) -> Result<SoftwareRenderer, Error> {
+        use resources::codecs::check_dimensions;
+
+        if let Err(e) = check_dimensions(width, height) {
+            return Err(Error::UserError(format!("Invalid software renderer size: {}", e)));
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        Ok(SoftwareRenderer {
+            width: width,
+            height: height,
+            pixels: vec![[0.0; 4]; width * height],
+            matrix: Matrix::identity(),
+            clips: Vec::new(),
+            layers: Vec::new()
+        })
+    }
+
+    /**
+        Return a copy of the drawing. The layers that were not popped are blended as if they were.
+    */
+    pub fn get_image(&self) -> ImageData {
+        let mut pixels = self.pixels.clone();
+        for layer in self.layers.iter().rev() {
+            let mut below = layer.below.clone();
+            blend_layer(&mut below, &pixels, layer);
+            pixels = below;
+        }
+
+        let byte = |v: f32| (v.max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+        let mut image = ImageData::new(self.width as u32, self.height as u32);
+        for (p, out) in pixels.iter().zip(image.data.chunks_mut(4)) {
+            out[0] = byte(p[2]);
+            out[1] = byte(p[1]);
+            out[2] = byte(p[0]);
+            out[3] = byte(p[3]);
+        }
+
+        image
+    }
+
+    /// Return the area that can be drawn, in pixels: (left, top, right, bottom)
+    fn area(&self) -> (usize, usize, usize, usize) {
+        let clamp = |v: f32, max: usize| v.max(0.0).min(max as f32) as usize;
+        match self.clips.last() {
+            Some(r) => {
+                let (left, top) = (clamp(r.left.floor(), self.width), clamp(r.top.floor(), self.height));
+                let (right, bottom) = (clamp(r.right.ceil(), self.width), clamp(r.bottom.ceil(), self.height));
+                (left, top, right.max(left), bottom.max(top))
+            },
+            None => (0, 0, self.width, self.height)
+        }
+    }
+
+    /// Return the tolerance used to flatten the curves in the coordinates before the transformation
+    fn tolerance(&self) -> f32 {
+        let m = &self.matrix;
+        let scale = (m.m11 * m.m11 + m.m12 * m.m12).sqrt().max((m.m21 * m.m21 + m.m22 * m.m22).sqrt());
+        TOLERANCE / scale.max(1e-6)
+    }
+
+    /// Return the polygons transformed to pixels
+    fn transform(&self, polygons: Vec<Vec<(f32, f32)>>) -> Vec<Vec<(f32, f32)>> {
+        let m = &self.matrix;
+        polygons.into_iter().map(|p| p.into_iter().map(|point| m.transform_point(point)).collect()).collect()
+    }
+
+    /// Blend the color of `sampler` with the pixels covered by the polygons
+    fn paint(&mut self, polygons: &[Vec<(f32, f32)>], mode: FillMode, sampler: &Sampler) {
+        let area = self.area();
+        let clip = self.clips.last().cloned();
+        let width = self.width;
+        let pixels = &mut self.pixels;
+
+        rasterize(polygons, mode, area, |x, y, coverage| {
+            let coverage = match clip.as_ref() {
+                Some(r) => coverage * rect_coverage(r, x, y),
+                None => coverage
+            };
+
+            if coverage > 0.0 {
+                let color = sampler.sample(x as f32 + 0.5, y as f32 + 0.5);
+                blend(&mut pixels[y * width + x], color, coverage);
+            }
+        });
+    }
+
+}
+
+impl RenderBackend for SoftwareRenderer {
+
+    fn get_size(&self) -> (f32, f32) {
+        (self.width as f32, self.height as f32)
+    }
+
+    fn clear(&mut self, color: (f32, f32, f32, f32)) {
+        let (r, g, b, a) = color;
+        let a = a.max(0.0).min(1.0);
+        let color = [r * a, g * a, b * a, a];
+
+        let (left, top, right, bottom) = self.area();
+        let clip = self.clips.last().cloned();
+        for y in top..bottom {
+            for x in left..right {
+                let coverage = match clip.as_ref() { Some(r) => rect_coverage(r, x, y), None => 1.0 };
+                let pixel = &mut self.pixels[y * self.width + x];
+                for c in 0..4 {
+                    pixel[c] = color[c] * coverage + pixel[c] * (1.0 - coverage);
+                }
+            }
+        }
+    }
+
+    fn set_matrix(&mut self, m: &Matrix) {
+        self.matrix = *m;
+    }
+
+    fn get_matrix(&self) -> Matrix {
+        self.matrix
+    }
+
+    fn push_clip(&mut self, r: &Rectangle) {
+        let mut clip = self.matrix.transform_rect(r);
+        if let Some(last) = self.clips.last() {
+            clip.left = clip.left.max(last.left);
+            clip.top = clip.top.max(last.top);
+            clip.right = clip.right.min(last.right);
+            clip.bottom = clip.bottom.min(last.bottom);
+        }
+
+        self.clips.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn push_layer(&mut self, opacity: f32, mask: Option<&PathData>, bounds: Option<&Rectangle>) -> Result<(), Error> {
+        let count = self.width * self.height;
+
+        let mut coverage = match mask {
+            Some(path) => {
+                let figures = path.flatten(self.tolerance());
+                let polygons = figures.into_iter().filter(|f| f.filled).map(|f| f.points).collect();
+                let polygons = self.transform(polygons);
+
+                let mut coverage = vec![0.0; count];
+                let width = self.width;
+                rasterize(&polygons, path.fill_mode, (0, 0, self.width, self.height), |x, y, c| coverage[y * width + x] = c);
+                Some(coverage)
+            },
+            None => None
+        };
+
+        if let Some(r) = bounds {
+            let r = self.matrix.transform_rect(r);
+            let mut values = coverage.unwrap_or(vec![1.0; count]);
+            for (i, value) in values.iter_mut().enumerate() {
+                *value *= rect_coverage(&r, i % self.width, i / self.width);
+            }
+            coverage = Some(values);
+        }
+
+        let below = mem::replace(&mut self.pixels, vec![[0.0; 4]; count]);
+        self.layers.push(Layer{ below: below, opacity: opacity.max(0.0).min(1.0), mask: coverage });
+
+        Ok(())
+    }
+
+    fn pop_layer(&mut self) {
+        if let Some(layer) = self.layers.pop() {
+            let content = mem::replace(&mut self.pixels, Vec::new());
+            let mut below = layer.below.clone();
+            blend_layer(&mut below, &content, &layer);
+            self.pixels = below;
+        }
+    }
+
+    fn fill_path(&mut self, path: &PathData, paint: &Paint) -> Result<(), Error> {
+        let sampler = match Sampler::new(paint, &self.matrix) {
+            Some(s) => s,
+            None => { return Ok(()); } // The transformation flattens everything
+        };
+
+        let figures = path.flatten(self.tolerance());
+        let polygons = figures.into_iter().filter(|f| f.filled).map(|f| f.points).collect();
+        let polygons = self.transform(polygons);
+        self.paint(&polygons, path.fill_mode, &sampler);
+
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, path: &PathData, paint: &Paint, pen: Option<&Pen>, width: f32) -> Result<(), Error> {
+        let sampler = match Sampler::new(paint, &self.matrix) {
+            Some(s) => s,
+            None => { return Ok(()); }
+        };
+
+        // The default stroke style of Direct2D
+        let plain = Pen {
+            start_cap: CapStyle::Flat, end_cap: CapStyle::Flat, dash_cap: CapStyle::Flat,
+            line_join: LineJoin::Miter, miter_limit: 10.0,
+            dash_style: DashStyle::Solid, dash_offset: 0.0
+        };
+
+        let tolerance = self.tolerance();
+        let outline = stroke_outline(&path.flatten(tolerance), pen.unwrap_or(&plain), width, tolerance);
+        let polygons = self.transform(outline);
+        self.paint(&polygons, FillMode::Winding, &sampler);
+
+        Ok(())
+    }
+
+    fn draw_image(&mut self, image: &ImageData, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32,
+      interpolation: BitmapInterpolation) -> Result<(), Error>
+    {
+        let src = match src {
+            Some(r) => r.clone(),
+            None => Rectangle{ left: 0.0, top: 0.0, right: image.width as f32, bottom: image.height as f32 }
+        };
+
+        let (width, height) = (dest.right - dest.left, dest.bottom - dest.top);
+        if width == 0.0 || height == 0.0 || image.width == 0 || image.height == 0 {
+            return Ok(());
+        }
+
+        let inverse = match self.matrix.invert() {
+            Some(m) => m,
+            None => { return Ok(()); }
+        };
+
+        let to_image = Matrix::translation(-dest.left, -dest.top)
+            .scale((src.right - src.left) / width, (src.bottom - src.top) / height, (0.0, 0.0))
+            .translate(src.left, src.top);
+
+        let sampler = Sampler {
+            map: inverse.multiply(&to_image),
+            source: Source::Image{ image: image, extend: (ExtendMode::Clamp, ExtendMode::Clamp), interpolation: interpolation },
+            opacity: opacity.max(0.0).min(1.0)
+        };
+
+        let corners = vec![(dest.left, dest.top), (dest.right, dest.top), (dest.right, dest.bottom), (dest.left, dest.bottom)];
+        let polygons = self.transform(vec![corners]);
+        self.paint(&polygons, FillMode::Winding, &sampler);
+
+        Ok(())
+    }
+
+}
+
+/// Compute the color of the pixels painted with a `Paint`
+struct Sampler<'a> {
+    /// The transformation from the pixels to the coordinates of the source
+    map: Matrix,
+    source: Source<'a>,
+    opacity: f32
+}
+
+enum Source<'a> {
+    Solid(Color),
+    Linear{ start: (f32, f32), end: (f32, f32), stops: Vec<GradientStop>, extend: ExtendMode },
+    Radial{ center: (f32, f32), offset: (f32, f32), radius: (f32, f32), stops: Vec<GradientStop>, extend: ExtendMode },
+    Image{ image: &'a ImageData, extend: (ExtendMode, ExtendMode), interpolation: BitmapInterpolation }
+}
+
+impl<'a> Sampler<'a> {
+
+    /// Return `None` if the transformation `m` cannot be inverted
+    fn new(paint: &'a Paint, m: &Matrix) -> Option<Sampler<'a>> {
+        let sorted = |stops: &Vec<GradientStop>| {
+            let mut stops = stops.clone();
+            stops.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap_or(Ordering::Equal));
+            stops
+        };
+
+        let source = match paint {
+            &Paint::Solid((r, g, b, a)) => Source::Solid([r * a, g * a, b * a, a]),
+            &Paint::Linear(ref g) => Source::Linear{ start: g.start, end: g.end, stops: sorted(&g.stops), extend: g.extend },
+            &Paint::Radial(ref g) => Source::Radial {
+                center: g.center, offset: g.origin_offset, radius: g.radius, stops: sorted(&g.stops), extend: g.extend
+            },
+            &Paint::Bitmap{ ref image, extend, interpolation } => Source::Image{ image: &**image, extend: extend, interpolation: interpolation }
+        };
+
+        m.invert().map(|map| Sampler{ map: map, source: source, opacity: 1.0 })
+    }
+
+    /// Return the premultiplied color at the position `(x, y)` in pixels
+    fn sample(&self, x: f32, y: f32) -> Color {
+        let (x, y) = self.map.transform_point((x, y));
+        let color = match self.source {
+            Source::Solid(c) => c,
+            Source::Linear{ start, end, ref stops, extend } => {
+                let d = (end.0 - start.0, end.1 - start.1);
+                let length = d.0 * d.0 + d.1 * d.1;
+                let t = if length > 0.0 { ((x - start.0) * d.0 + (y - start.1) * d.1) / length } else { 0.0 };
+                gradient_color(stops, t, extend)
+            },
+            Source::Radial{ center, offset, radius, ref stops, extend } => {
+                // Find the ellipse scaled around the gradient origin that goes through the point
+                if radius.0 == 0.0 || radius.1 == 0.0 { return [0.0; 4]; }
+                let p = ((x - center.0) / radius.0, (y - center.1) / radius.1);
+                let mut f = (offset.0 / radius.0, offset.1 / radius.1);
+                let fl = (f.0 * f.0 + f.1 * f.1).sqrt();
+                if fl > 0.999 { f = (f.0 * 0.999 / fl, f.1 * 0.999 / fl); }
+
+                let d = (p.0 - f.0, p.1 - f.1);
+                let (a, b, c) = (d.0 * d.0 + d.1 * d.1, f.0 * d.0 + f.1 * d.1, f.0 * f.0 + f.1 * f.1 - 1.0);
+                let t = if a > 0.0 { a / (-b + (b * b - a * c).max(0.0).sqrt()) } else { 0.0 };
+                gradient_color(stops, t, extend)
+            },
+            Source::Image{ image, extend, interpolation } => sample_image(image, (x, y), extend, interpolation)
+        };
+
+        if self.opacity < 1.0 {
+            [color[0] * self.opacity, color[1] * self.opacity, color[2] * self.opacity, color[3] * self.opacity]
+        } else {
+            color
+        }
+    }
+
+}
+
+/// Return the color of a gradient at the position `t`. The stops are sorted by position.
+fn gradient_color(stops: &[GradientStop], t: f32, extend: ExtendMode) -> Color {
+    if stops.is_empty() { return [0.0; 4]; }
+
+    let t = if t.is_finite() { t } else { 0.0 };
+    let t = match extend {
+        ExtendMode::Clamp => t.max(0.0).min(1.0),
+        ExtendMode::Wrap => t - t.floor(),
+        ExtendMode::Mirror => {
+            let m = t.abs() % 2.0;
+            if m > 1.0 { 2.0 - m } else { m }
+        }
+    };
+
+    let premultiply = |c: (f32, f32, f32, f32)| [c.0 * c.3, c.1 * c.3, c.2 * c.3, c.3];
+    let (first, last) = (&stops[0], &stops[stops.len() - 1]);
+    if t <= first.position { return premultiply(first.color); }
+    if t >= last.position { return premultiply(last.color); }
+
+    for w in stops.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+        if t <= b.position {
+            let span = b.position - a.position;
+            let k = if span > 0.0 { (t - a.position) / span } else { 1.0 };
+            let mix = |x: f32, y: f32| x + (y - x) * k;
+            return premultiply((mix(a.color.0, b.color.0), mix(a.color.1, b.color.1), mix(a.color.2, b.color.2), mix(a.color.3, b.color.3)));
+        }
+    }
+
+    premultiply(last.color)
+}
+
+/// Return the color of an image at the position `p` in pixels of the image
+fn sample_image(image: &ImageData, p: (f32, f32), extend: (ExtendMode, ExtendMode), interpolation: BitmapInterpolation) -> Color {
+    let (w, h) = (image.width as i64, image.height as i64);
+    if w == 0 || h == 0 || !p.0.is_finite() || !p.1.is_finite() { return [0.0; 4]; }
+
+    let texel = |x: i64, y: i64| -> Color {
+        let (x, y) = (extend_index(x, w, extend.0), extend_index(y, h, extend.1));
+        let i = ((y * w + x) * 4) as usize;
+        let d = &image.data;
+        [d[i + 2] as f32 / 255.0, d[i + 1] as f32 / 255.0, d[i] as f32 / 255.0, d[i + 3] as f32 / 255.0]
+    };
+
+    match interpolation {
+        BitmapInterpolation::NearestNeighbor => texel(p.0.floor() as i64, p.1.floor() as i64),
+        BitmapInterpolation::Linear => {
+            let (x, y) = (p.0 - 0.5, p.1 - 0.5);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (tx, ty) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+
+            let (a, b, c, d) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+            let mut out = [0.0; 4];
+            for i in 0..4 {
+                let top = a[i] + (b[i] - a[i]) * tx;
+                let bottom = c[i] + (d[i] - c[i]) * tx;
+                out[i] = top + (bottom - top) * ty;
+            }
+            out
+        }
+    }
+}
+
+/// Return the index of the pixel used for the position `i` of an image axis of `size` pixels
+fn extend_index(i: i64, size: i64, mode: ExtendMode) -> i64 {
+    match mode {
+        ExtendMode::Clamp => i.max(0).min(size - 1),
+        ExtendMode::Wrap => ((i % size) + size) % size,
+        ExtendMode::Mirror => {
+            let m = ((i % (2 * size)) + 2 * size) % (2 * size);
+            if m < size { m } else { 2 * size - 1 - m }
+        }
+    }
+}
+
+/// Blend `color` over `pixel` with the coverage `coverage`
+fn blend(pixel: &mut Color, color: Color, coverage: f32) {
+    let keep = 1.0 - color[3] * coverage;
+    for c in 0..4 {
+        pixel[c] = color[c] * coverage + pixel[c] * keep;
+    }
+}
+
+/// Blend the pixels drawn in a layer over the pixels that were under it
+fn blend_layer(below: &mut [Color], content: &[Color], layer: &Layer) {
+    for (i, (pixel, color)) in below.iter_mut().zip(content.iter()).enumerate() {
+        let coverage = match layer.mask.as_ref() {
+            Some(mask) => layer.opacity * mask[i],
+            None => layer.opacity
+        };
+
+        if coverage > 0.0 {
+            blend(pixel, *color, coverage);
+        }
+    }
+}
+
+/// Return the part of the pixel `(x, y)` inside the rectangle `r`
+fn rect_coverage(r: &Rectangle, x: usize, y: usize) -> f32 {
+    let (x, y) = (x as f32, y as f32);
+    let horizontal = (r.right.min(x + 1.0) - r.left.max(x)).max(0.0);
+    let vertical = (r.bottom.min(y + 1.0) - r.top.max(y)).max(0.0);
+    horizontal * vertical
+}
+
+/// A polygon edge going down
+struct Edge {
+    top: (f32, f32),
+    bottom: (f32, f32),
+    winding: i32
+}
+
+/**
+    Call `f` with the part of each pixel covered by the polygons, for the pixels of `area` (left, top, right, bottom).
+    Each row of pixels is sampled with `SAMPLE_LINES` horizontal lines. On a line, the spans inside the polygons
+    are added exactly, so vertical edges are perfectly antialiased.
+*/
+fn rasterize<F: FnMut(usize, usize, f32)>(polygons: &[Vec<(f32, f32)>], mode: FillMode, area: (usize, usize, usize, usize), mut f: F) {
+    let (left, top, right, bottom) = area;
+    if right <= left || bottom <= top { return; }
+
+    let mut edges = Vec::new();
+    for polygon in polygons.iter() {
+        let count = polygon.len();
+        if count < 3 || polygon.iter().any(|p| !p.0.is_finite() || !p.1.is_finite()) { continue; }
+
+        for i in 0..count {
+            let (a, b) = (polygon[i], polygon[(i + 1) % count]);
+            if a.1 < b.1 {
+                edges.push(Edge{ top: a, bottom: b, winding: 1 });
+            } else if a.1 > b.1 {
+                edges.push(Edge{ top: b, bottom: a, winding: -1 });
+            }
+        }
+    }
+
+    edges.sort_by(|a, b| a.top.1.partial_cmp(&b.top.1).unwrap_or(Ordering::Equal));
+
+    let width = right - left;
+    let weight = 1.0 / SAMPLE_LINES as f32;
+    let (mut cover, mut partial) = (vec![0.0f32; width + 1], vec![0.0f32; width + 1]);
+    let mut active: Vec<usize> = Vec::new();
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    let mut next = 0;
+
+    for y in top..bottom {
+        let (row_top, row_bottom) = (y as f32, (y + 1) as f32);
+        while next < edges.len() && edges[next].top.1 < row_bottom {
+            active.push(next);
+            next += 1;
+        }
+
+        active.retain(|&i| edges[i].bottom.1 > row_top);
+        if active.is_empty() {
+            if next == edges.len() { break; }
+            continue;
+        }
+
+        for v in cover.iter_mut() { *v = 0.0; }
+        for v in partial.iter_mut() { *v = 0.0; }
+
+        for line in 0..SAMPLE_LINES {
+            let sy = row_top + (line as f32 + 0.5) * weight;
+
+            crossings.clear();
+            for &i in active.iter() {
+                let e = &edges[i];
+                if e.top.1 <= sy && sy < e.bottom.1 {
+                    let x = e.top.0 + (sy - e.top.1) * (e.bottom.0 - e.top.0) / (e.bottom.1 - e.top.1);
+                    crossings.push((x - left as f32, e.winding));
+                }
+            }
+
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+            let mut winding = 0;
+            for k in 0..crossings.len() {
+                winding += crossings[k].1;
+                let inside = match mode {
+                    FillMode::Alternate => winding % 2 != 0,
+                    FillMode::Winding => winding != 0
+                };
+
+                if inside && k + 1 < crossings.len() {
+                    add_span(&mut cover, &mut partial, crossings[k].0, crossings[k + 1].0, width, weight);
+                }
+            }
+        }
+
+        let mut full = 0.0;
+        for x in 0..width {
+            full += cover[x];
+            let coverage = (full + partial[x]).min(1.0);
+            if coverage > 0.0 {
+                f(left + x, y, coverage);
+            }
+        }
+    }
+}
+
+/**
+    Add the span `[a, b)` of a sample line. The pixels completely covered are counted in `cover` as a
+    difference with the previous pixel, the pixels partially covered are counted in `partial`.
+*/
+fn add_span(cover: &mut [f32], partial: &mut [f32], a: f32, b: f32, width: usize, weight: f32) {
+    let (a, b) = (a.max(0.0), b.min(width as f32));
+    if a >= b { return; }
+
+    let (ia, ib) = (a.floor() as usize, b.floor() as usize);
+    if ia == ib {
+        partial[ia] += (b - a) * weight;
+        return;
+    }
+
+    partial[ia] += ((ia + 1) as f32 - a) * weight;
+    cover[ia + 1] += weight;
+    cover[ib] -= weight;
+    if ib < width {
+        partial[ib] += (b - ib as f32) * weight;
+    }
+}
@@ -0,0 +1,429 @@
+/*!
+    Export of the display lists as SVG documents
+*/
+
+use std::rc::Rc;
+use std::fmt::Write;
+
+use super::defs::{GradientStop, ExtendMode, BitmapInterpolation, Pen, PathData, PathSegment, FillMode, CapStyle, LineJoin,
+  DashStyle, ArcSize, SweepDirection, Rectangle, Matrix, TextStyle, FontStyle, TextAlignment, ParagraphAlignment};
+use super::backend::Paint;
+use super::recording::DrawCommand;
+use super::geometry::dash_pattern;
+use resources::{ImageData, ImageFormat};
+
+/**
+    Write the commands of a display list as an SVG document of `size`
+*/
+pub(crate) fn write_svg(size: (f32, f32), commands: &[DrawCommand]) -> String {
+    let mut writer = SvgWriter {
+        size: size,
+        defs: String::new(),
+        body: String::new(),
+        next_id: 0,
+        groups: Vec::new(),
+        matrix: Matrix::identity(),
+        images: Vec::new()
+    };
+
+    for command in commands.iter() {
+        writer.command(command);
+    }
+
+    while !writer.groups.is_empty() {
+        writer.pop();
+    }
+
+    let mut svg = String::new();
+    let _ = write!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" ");
+    let _ = write!(svg, "width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n", size.0, size.1);
+    if !writer.defs.is_empty() {
+        let _ = write!(svg, "<defs>\n{}</defs>\n", writer.defs);
+    }
+
+    svg.push_str(&writer.body);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+struct SvgWriter {
+    size: (f32, f32),
+    defs: String,
+    body: String,
+    next_id: usize,
+
+    /// The number of `g` elements opened by each clip or layer
+    groups: Vec<usize>,
+
+    matrix: Matrix,
+    images: Vec<(Rc<ImageData>, Option<String>)>
+}
+
+impl SvgWriter {
+
+    fn command(&mut self, command: &DrawCommand) {
+        match command {
+            &DrawCommand::Clear(color) => self.clear(color),
+            &DrawCommand::SetMatrix(ref m) => { self.matrix = m.clone(); },
+            &DrawCommand::PushClip(ref r) => {
+                let clip = self.clip_rect(r);
+                let _ = write!(self.body, "<g clip-path=\"url(#{})\">\n", clip);
+                self.groups.push(1);
+            },
+            &DrawCommand::PushLayer{ opacity, ref mask, ref bounds } => self.push_layer(opacity, mask.as_ref(), bounds.as_ref()),
+            &DrawCommand::PopClip | &DrawCommand::PopLayer => self.pop(),
+            &DrawCommand::FillPath{ ref path, ref paint } => {
+                let data = path_data(path, true);
+                if data.is_empty() { return; }
+
+                let fill = self.paint(paint, "fill");
+                let _ = write!(self.body, "<path d=\"{}\"{} fill-rule=\"{}\"{}/>\n", data, fill, fill_rule(path.fill_mode), transform(&self.matrix));
+            },
+            &DrawCommand::StrokePath{ ref path, ref paint, ref pen, width } => {
+                let data = path_data(path, false);
+                if data.is_empty() { return; }
+
+                let stroke = self.paint(paint, "stroke");
+                let pen = pen_attributes(pen.as_ref(), width);
+                let _ = write!(self.body, "<path d=\"{}\" fill=\"none\"{} stroke-width=\"{}\"{}{}/>\n", data, stroke, width, pen, transform(&self.matrix));
+            },
+            &DrawCommand::DrawImage{ ref image, ref dest, ref src, opacity, interpolation } => self.image(image, dest, src.as_ref(), opacity, interpolation),
+            &DrawCommand::DrawText{ ref text, ref style, ref bounds, ref paint } => self.text(text, style, bounds, paint)
+        }
+    }
+
+    /// A clear outside of any clip replaces everything drawn before it
+    fn clear(&mut self, color: (f32, f32, f32, f32)) {
+        if self.groups.is_empty() {
+            self.body.clear();
+        }
+
+        if color.3 > 0.0 {
+            let fill = self.paint(&Paint::Solid(color), "fill");
+            let _ = write!(self.body, "<rect width=\"{}\" height=\"{}\"{}/>\n", self.size.0, self.size.1, fill);
+        }
+    }
+
+    fn push_layer(&mut self, opacity: f32, mask: Option<&PathData>, bounds: Option<&Rectangle>) {
+        let mut groups = 0;
+
+        if let Some(r) = bounds {
+            let clip = self.clip_rect(r);
+            let _ = write!(self.body, "<g clip-path=\"url(#{})\">\n", clip);
+            groups += 1;
+        }
+
+        let mut attributes = String::new();
+        if opacity < 1.0 {
+            let _ = write!(attributes, " opacity=\"{}\"", opacity.max(0.0));
+        }
+
+        if let Some(path) = mask {
+            let id = self.new_id("clip");
+            let _ = write!(self.defs, "<clipPath id=\"{}\"><path d=\"{}\" clip-rule=\"{}\"{}/></clipPath>\n",
+              id, path_data(path, true), fill_rule(path.fill_mode), transform(&self.matrix));
+            let _ = write!(attributes, " clip-path=\"url(#{})\"", id);
+        }
+
+        let _ = write!(self.body, "<g{}>\n", attributes);
+        self.groups.push(groups + 1);
+    }
+
+    fn pop(&mut self) {
+        if let Some(groups) = self.groups.pop() {
+            for _ in 0..groups {
+                self.body.push_str("</g>\n");
+            }
+        }
+    }
+
+    fn image(&mut self, image: &Rc<ImageData>, dest: &Rectangle, src: Option<&Rectangle>, opacity: f32, interpolation: BitmapInterpolation) {
+        let (width, height) = (dest.right - dest.left, dest.bottom - dest.top);
+        if !(width > 0.0 && height > 0.0) { return; }
+
+        let id = match self.image_id(image) {
+            Some(id) => id,
+            None => { return; }
+        };
+
+        let view = match src {
+            Some(r) => format!("{} {} {} {}", r.left, r.top, r.right - r.left, r.bottom - r.top),
+            None => format!("0 0 {} {}", image.width, image.height)
+        };
+
+        let opacity = if opacity < 1.0 { format!(" opacity=\"{}\"", opacity.max(0.0)) } else { String::new() };
+        let _ = write!(self.body, "<g{}{}><svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" viewBox=\"{}\" preserveAspectRatio=\"none\">",
+          transform(&self.matrix), opacity, dest.left, dest.top, width, height, view);
+        let _ = write!(self.body, "<use xlink:href=\"#{}\"{}/></svg></g>\n", id, image_rendering(interpolation));
+    }
+
+    fn text(&mut self, text: &str, style: &TextStyle, bounds: &Rectangle, paint: &Paint) {
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.is_empty() { return; }
+
+        let (x, anchor) = match style.text_align {
+            TextAlignment::Leading | TextAlignment::Justified => (bounds.left, "start"),
+            TextAlignment::Trailing => (bounds.right, "end"),
+            TextAlignment::Center => ((bounds.left + bounds.right) / 2.0, "middle")
+        };
+
+        // The line height and the ascent of most fonts are close to these values
+        let line_height = style.size * 1.2;
+        let height = line_height * (lines.len() as f32);
+        let top = match style.paragraph_align {
+            ParagraphAlignment::Near => bounds.top,
+            ParagraphAlignment::Far => bounds.bottom - height,
+            ParagraphAlignment::Center => (bounds.top + bounds.bottom - height) / 2.0
+        };
+
+        let font_style = match style.style {
+            FontStyle::Normal => "normal",
+            FontStyle::Oblique => "oblique",
+            FontStyle::Italic => "italic"
+        };
+
+        let weight = if style.weight <= 0 { 400 } else { style.weight.min(999) };
+        let fill = self.paint(paint, "fill");
+        let _ = write!(self.body, "<text font-family=\"{}\" font-size=\"{}\" font-weight=\"{}\" font-style=\"{}\" text-anchor=\"{}\"{}{}>",
+          escape(&style.family), style.size, weight, font_style, anchor, fill, transform(&self.matrix));
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = ((top + line_height * (i as f32) + style.size * 0.9) * 100.0).round() / 100.0;
+            let _ = write!(self.body, "<tspan x=\"{}\" y=\"{}\">{}</tspan>", x, y, escape(line));
+        }
+
+        self.body.push_str("</text>\n");
+    }
+
+    /// Return the attributes painting an element with `paint`
+    fn paint(&mut self, paint: &Paint, attribute: &str) -> String {
+        match paint {
+            &Paint::Solid(c) => {
+                let mut value = format!(" {}=\"{}\"", attribute, color(c));
+                if c.3 < 1.0 { let _ = write!(value, " {}-opacity=\"{}\"", attribute, c.3.max(0.0)); }
+                value
+            },
+            &Paint::Linear(ref g) => {
+                let id = self.new_id("paint");
+                let _ = write!(self.defs, "<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" spreadMethod=\"{}\">\n{}</linearGradient>\n",
+                  id, g.start.0, g.start.1, g.end.0, g.end.1, spread_method(g.extend), gradient_stops(&g.stops));
+                format!(" {}=\"url(#{})\"", attribute, id)
+            },
+            &Paint::Radial(ref g) => {
+                // The gradient is a unit circle scaled to the ellipse
+                let id = self.new_id("paint");
+                let (fx, fy) = (ratio(g.origin_offset.0, g.radius.0), ratio(g.origin_offset.1, g.radius.1));
+                let _ = write!(self.defs, "<radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" cx=\"0\" cy=\"0\" r=\"1\" fx=\"{}\" fy=\"{}\" gradientTransform=\"matrix({} 0 0 {} {} {})\" spreadMethod=\"{}\">\n{}</radialGradient>\n",
+                  id, fx, fy, g.radius.0, g.radius.1, g.center.0, g.center.1, spread_method(g.extend), gradient_stops(&g.stops));
+                format!(" {}=\"url(#{})\"", attribute, id)
+            },
+            &Paint::Bitmap{ ref image, extend, interpolation } => {
+                let image_id = match self.image_id(image) {
+                    Some(id) => id,
+                    None => { return format!(" {}=\"none\"", attribute); }
+                };
+
+                // A mirrored bitmap repeats a tile of two flipped copies
+                let (w, h) = (image.width as f32, image.height as f32);
+                let (mirror_x, mirror_y) = (extend.0 == ExtendMode::Mirror, extend.1 == ExtendMode::Mirror);
+                let id = self.new_id("paint");
+                let _ = write!(self.defs, "<pattern id=\"{}\" patternUnits=\"userSpaceOnUse\" width=\"{}\" height=\"{}\"{}>",
+                  id, if mirror_x { w * 2.0 } else { w }, if mirror_y { h * 2.0 } else { h }, image_rendering(interpolation));
+                let _ = write!(self.defs, "<use xlink:href=\"#{}\"/>", image_id);
+                if mirror_x {
+                    let _ = write!(self.defs, "<use xlink:href=\"#{}\" transform=\"matrix(-1 0 0 1 {} 0)\"/>", image_id, w * 2.0);
+                }
+                if mirror_y {
+                    let _ = write!(self.defs, "<use xlink:href=\"#{}\" transform=\"matrix(1 0 0 -1 0 {})\"/>", image_id, h * 2.0);
+                }
+                if mirror_x && mirror_y {
+                    let _ = write!(self.defs, "<use xlink:href=\"#{}\" transform=\"matrix(-1 0 0 -1 {} {})\"/>", image_id, w * 2.0, h * 2.0);
+                }
+                self.defs.push_str("</pattern>\n");
+
+                format!(" {}=\"url(#{})\"", attribute, id)
+            }
+        }
+    }
+
+    /// Define a clip path for the bounds of a transformed rectangle
+    fn clip_rect(&mut self, r: &Rectangle) -> String {
+        let r = self.matrix.transform_rect(r);
+        let id = self.new_id("clip");
+        let _ = write!(self.defs, "<clipPath id=\"{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>\n",
+          id, r.left, r.top, (r.right - r.left).max(0.0), (r.bottom - r.top).max(0.0));
+        id
+    }
+
+    /// Define an image the first time it is used. The images that cannot be encoded are not exported.
+    fn image_id(&mut self, image: &Rc<ImageData>) -> Option<String> {
+        for &(ref saved, ref id) in self.images.iter() {
+            if Rc::ptr_eq(saved, image) { return id.clone(); }
+        }
+
+        let mut png = Vec::new();
+        let id = match image.encode(ImageFormat::Png, &mut png) {
+            Ok(_) => {
+                let id = self.new_id("image");
+                let _ = write!(self.defs, "<image id=\"{}\" width=\"{}\" height=\"{}\" xlink:href=\"data:image/png;base64,{}\"/>\n",
+                  id, image.width, image.height, base64(&png));
+                Some(id)
+            },
+            Err(_) => None
+        };
+
+        self.images.push((image.clone(), id.clone()));
+        id
+    }
+
+    fn new_id(&mut self, name: &str) -> String {
+        self.next_id += 1;
+        format!("{}{}", name, self.next_id)
+    }
+
+}
+
+fn path_data(path: &PathData, filled_only: bool) -> String {
+    let mut data = String::new();
+
+    for figure in path.figures.iter().filter(|f| f.filled || !filled_only) {
+        if !data.is_empty() { data.push(' '); }
+        let _ = write!(data, "M{} {}", figure.start.0, figure.start.1);
+
+        for segment in figure.segments.iter() {
+            let _ = match segment {
+                &PathSegment::Line(p) => write!(data, " L{} {}", p.0, p.1),
+                &PathSegment::QuadraticBezier{ control, end } => write!(data, " Q{} {} {} {}", control.0, control.1, end.0, end.1),
+                &PathSegment::CubicBezier{ control1, control2, end } =>
+                    write!(data, " C{} {} {} {} {} {}", control1.0, control1.1, control2.0, control2.1, end.0, end.1),
+                &PathSegment::Arc{ end, radius, rotation, size, sweep } =>
+                    write!(data, " A{} {} {} {} {} {} {}", radius.0, radius.1, rotation, (size == ArcSize::Large) as u8,
+                      (sweep == SweepDirection::Clockwise) as u8, end.0, end.1)
+            };
+        }
+
+        if figure.closed { data.push_str(" Z"); }
+    }
+
+    data
+}
+
+/// The pen attributes of a stroke. Without a pen, the lines use the Direct2D defaults
+fn pen_attributes(pen: Option<&Pen>, width: f32) -> String {
+    let pen = match pen {
+        Some(p) => p,
+        None => { return " stroke-miterlimit=\"10\"".to_string(); }
+    };
+
+    let dashed = pen.dash_style != DashStyle::Solid;
+    let cap = match if dashed { pen.dash_cap } else { pen.start_cap } {
+        CapStyle::Flat => "butt",
+        CapStyle::Square => "square",
+        CapStyle::Round | CapStyle::Triangle => "round"
+    };
+
+    let join = match pen.line_join {
+        LineJoin::Miter | LineJoin::MiterOrBevel => "miter",
+        LineJoin::Bevel => "bevel",
+        LineJoin::Round => "round"
+    };
+
+    let mut attributes = format!(" stroke-linecap=\"{}\" stroke-linejoin=\"{}\" stroke-miterlimit=\"{}\"", cap, join, pen.miter_limit.max(1.0));
+    if dashed {
+        let dashes: Vec<String> = dash_pattern(pen.dash_style).iter().map(|d| (d * width).to_string()).collect();
+        let _ = write!(attributes, " stroke-dasharray=\"{}\"", dashes.join(" "));
+        if pen.dash_offset != 0.0 {
+            let _ = write!(attributes, " stroke-dashoffset=\"{}\"", pen.dash_offset * width);
+        }
+    }
+
+    attributes
+}
+
+fn gradient_stops(stops: &[GradientStop]) -> String {
+    let mut sorted: Vec<&GradientStop> = stops.iter().collect();
+    sorted.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap_or(::std::cmp::Ordering::Equal));
+
+    let mut data = String::new();
+    for stop in sorted {
+        let _ = write!(data, "<stop offset=\"{}\" stop-color=\"{}\"", stop.position.max(0.0).min(1.0), color(stop.color));
+        if stop.color.3 < 1.0 { let _ = write!(data, " stop-opacity=\"{}\"", stop.color.3.max(0.0)); }
+        data.push_str("/>\n");
+    }
+
+    data
+}
+
+fn transform(m: &Matrix) -> String {
+    if m.is_identity() {
+        String::new()
+    } else {
+        format!(" transform=\"matrix({} {} {} {} {} {})\"", m.m11, m.m12, m.m21, m.m22, m.dx, m.dy)
+    }
+}
+
+fn color(c: (f32, f32, f32, f32)) -> String {
+    let channel = |v: f32| (v.max(0.0).min(1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(c.0), channel(c.1), channel(c.2))
+}
+
+fn fill_rule(mode: FillMode) -> &'static str {
+    match mode {
+        FillMode::Alternate => "evenodd",
+        FillMode::Winding => "nonzero"
+    }
+}
+
+fn spread_method(extend: ExtendMode) -> &'static str {
+    match extend {
+        ExtendMode::Clamp => "pad",
+        ExtendMode::Wrap => "repeat",
+        ExtendMode::Mirror => "reflect"
+    }
+}
+
+fn image_rendering(interpolation: BitmapInterpolation) -> &'static str {
+    match interpolation {
+        BitmapInterpolation::NearestNeighbor => " image-rendering=\"optimizeSpeed\"",
+        BitmapInterpolation::Linear => ""
+    }
+}
+
+fn ratio(a: f32, b: f32) -> f32 {
+    if b == 0.0 { 0.0 } else { a / b }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c)
+        }
+    }
+
+    escaped
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
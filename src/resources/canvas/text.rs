@@ -0,0 +1,364 @@
+/*!
+    Text resources for a canvas control
+*/
+use std::hash::Hash;
+use std::any::TypeId;
+use std::ops::Range;
+use std::ptr;
+
+use winapi::{IDWriteTextFormat, IDWriteTextLayout, IDWriteFactory, c_int};
+
+use super::defs::{FontStyle, TextAlignment, ParagraphAlignment, WordWrapping, TextStyle, TextMetrics, HitTest, Rectangle};
+use controls::{Canvas, AnyHandle, ControlType};
+use resources::{ResourceT, Resource};
+use error::{Error, SystemError};
+use ui::Ui;
+
+/**
+    A template that creates a text format used by a Canvas control. The resource is unpacked with the canvas.
+
+    Params:  
+    • `canvas`: The canvas control that will use the resource  
+    • `family`: The name of the font family  
+    • `size`: The size of the font in device independent pixels (1/96 inch)  
+    • `weight`: The weight of the font in the range 1 through 999. See the FONT_WEIGHT_* constants for convenience  
+    • `style`: The slant of the font  
+    • `text_align`: The horizontal alignment of the text in the layout box  
+    • `paragraph_align`: The vertical alignment of the text in the layout box  
+    • `wrapping`: How the lines that are wider than the layout box are broken  
+*/
+#[derive(Clone)]
+pub struct TextFormatT<S: Clone+Into<String>, ID: Hash+Clone> {
+    pub canvas: ID,
+    pub family: S,
+    pub size: f32,
+    pub weight: c_int,
+    pub style: FontStyle,
+    pub text_align: TextAlignment,
+    pub paragraph_align: ParagraphAlignment,
+    pub wrapping: WordWrapping
+}
+
+impl<S: Clone+Into<String>, ID: Hash+Clone> ResourceT<ID> for TextFormatT<S, ID> {
+    fn type_id(&self) -> TypeId {
+        TypeId::of::<TextFormat>()
+    }
+
+    fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
+
+        match ui.type_of_control(&self.canvas) {
+            Ok(ControlType::Canvas) => {/* All good */},
+            Ok(t) => { return Err(Error::BadParent( format!("A TextFormat resource canvas must be a Canvas control. Got {:?}", t) )); }
+            Err(e) => { return Err(e); }
+        }
+
+        if !(self.size > 0.0) {
+            return Err(Error::UserError( format!("The size of a text format must be greater than 0. Got {}", self.size) ));
+        }
+
+        let factory = match ui.get::<Canvas<ID>>(&self.canvas) {
+            Ok(c) => c.get_write_factory(),
+            Err(_) => { unreachable!(); } // ui.type_of_control already check this
+        };
+
+        let style = TextStyle {
+            family: self.family.clone().into(),
+            size: self.size,
+            weight: self.weight,
+            style: self.style,
+            text_align: self.text_align,
+            paragraph_align: self.paragraph_align,
+            wrapping: self.wrapping
+        };
+
+        let factory = unsafe{&mut * factory};
+        match create_text_format(&style, factory) {
+            Ok(h) => {
+                Ok(Box::new( TextFormat{ handle: h, style: style } ))
+            },
+            Err(e) => { Err(e) }
+        }
+    }
+}
+
+
+/**
+    A text format resource
+*/
+pub struct TextFormat {
+    handle: *mut IDWriteTextFormat,
+    style: TextStyle
+}
+
+impl TextFormat {
+
+    /**
+        Return how the text format formats a text. Used by the renderer backends.
+    */
+    pub fn get_style(&self) -> &TextStyle {
+        &self.style
+    }
+
+}
+
+impl Resource for TextFormat {
+
+    /**
+        Should return the underlying handle to the object
+    */
+    fn handle(&self) -> AnyHandle {
+        AnyHandle::Custom(TypeId::of::<TextFormat>(), self.handle as usize)
+    }
+
+    /**
+        If specified, should free any ressource allocated in the template `build` function.
+    */
+    fn free(&mut self) {
+        if !self.handle.is_null() {
+            unsafe{ (&mut *self.handle).Release(); }
+            self.handle = ptr::null_mut();
+        }
+    }
+
+}
+
+
+/**
+    A block of text formatted with a text format. A layout measures the text once and can then be drawn by a
+    canvas renderer (see `CanvasRenderer::draw_text_layout`) or hit-tested any number of times.
+
+    Text positions are counted in UTF-16 code units, like the selection of the text controls.
+    Created with `Canvas::create_text_layout`.
+*/
+pub struct TextLayout {
+    handle: *mut IDWriteTextLayout,
+    length: u32
+}
+
+impl TextLayout {
+
+    /**
+        Return the size of the formatted text
+    */
+    pub fn get_metrics(&self) -> TextMetrics {
+        use winapi::DWRITE_TEXT_METRICS;
+        unsafe {
+            let mut m: DWRITE_TEXT_METRICS = ::std::mem::zeroed();
+            (&mut *self.handle).GetMetrics(&mut m);
+            TextMetrics {
+                left: m.left, top: m.top,
+                width: m.width, height: m.height,
+                layout_size: (m.layoutWidth, m.layoutHeight),
+                line_count: m.lineCount
+            }
+        }
+    }
+
+    /**
+        Return the number of UTF-16 code units of the text
+    */
+    pub fn len(&self) -> u32 {
+        self.length
+    }
+
+    /**
+        Return the maximum size of the layout box (`width`, `height`)
+    */
+    pub fn get_max_size(&self) -> (f32, f32) {
+        unsafe{ ((&mut *self.handle).GetMaxWidth(), (&mut *self.handle).GetMaxHeight()) }
+    }
+
+    /**
+        Set the maximum size of the layout box. The text is formatted again.
+    */
+    pub fn set_max_size(&mut self, width: f32, height: f32) {
+        unsafe{
+            (&mut *self.handle).SetMaxWidth(width);
+            (&mut *self.handle).SetMaxHeight(height);
+        }
+    }
+
+    /**
+        Set the weight of the font for a range of the text. See the FONT_WEIGHT_* constants for convenience.
+    */
+    pub fn set_weight(&mut self, range: Range<u32>, weight: c_int) {
+        use winapi::DWRITE_FONT_WEIGHT;
+        unsafe{ (&mut *self.handle).SetFontWeight(DWRITE_FONT_WEIGHT(font_weight(weight)), text_range(&range)); }
+    }
+
+    /**
+        Set the slant of the font for a range of the text
+    */
+    pub fn set_style(&mut self, range: Range<u32>, style: FontStyle) {
+        use winapi::DWRITE_FONT_STYLE;
+        unsafe{ (&mut *self.handle).SetFontStyle(DWRITE_FONT_STYLE(style as u32), text_range(&range)); }
+    }
+
+    /**
+        Underline a range of the text or remove its underline
+    */
+    pub fn set_underline(&mut self, range: Range<u32>, underline: bool) {
+        unsafe{ (&mut *self.handle).SetUnderline(underline as i32, text_range(&range)); }
+    }
+
+    /**
+        Strike a range of the text or remove its strikethrough
+    */
+    pub fn set_strikethrough(&mut self, range: Range<u32>, strikethrough: bool) {
+        unsafe{ (&mut *self.handle).SetStrikethrough(strikethrough as i32, text_range(&range)); }
+    }
+
+    /**
+        Return the text position under the point (`x`, `y`). The point is relative to the origin of the layout.
+        If the point is outside of the text, the nearest position is returned and `inside` is false.
+    */
+    pub fn hit_test_point(&self, x: f32, y: f32) -> HitTest {
+        use winapi::{BOOL, DWRITE_HIT_TEST_METRICS};
+        unsafe {
+            let (mut trailing, mut inside): (BOOL, BOOL) = (0, 0);
+            let mut m: DWRITE_HIT_TEST_METRICS = ::std::mem::zeroed();
+            (&mut *self.handle).HitTestPoint(x, y, &mut trailing, &mut inside, &mut m);
+
+            let caret_x = if trailing != 0 { m.left + m.width } else { m.left };
+            hit_test(&m, trailing != 0, inside != 0, (caret_x, m.top))
+        }
+    }
+
+    /**
+        Return the caret position of a text position. If `trailing` is true, the caret is placed after the
+        character instead of before it.
+    */
+    pub fn hit_test_position(&self, position: u32, trailing: bool) -> HitTest {
+        use winapi::DWRITE_HIT_TEST_METRICS;
+        unsafe {
+            let (mut x, mut y) = (0.0, 0.0);
+            let mut m: DWRITE_HIT_TEST_METRICS = ::std::mem::zeroed();
+            (&mut *self.handle).HitTestTextPosition(position, trailing as i32, &mut x, &mut y, &mut m);
+            hit_test(&m, trailing, m.isText != 0, (x, y))
+        }
+    }
+
+    /**
+        Return the rectangles covering a range of the text, for example to paint a selection.
+        A range that spans many lines returns at least one rectangle per line.
+    */
+    pub fn hit_test_range(&self, range: Range<u32>) -> Vec<Rectangle> {
+        use winapi::{DWRITE_HIT_TEST_METRICS, S_OK};
+
+        if range.end <= range.start { return Vec::new(); }
+
+        unsafe {
+            let length = range.end - range.start;
+            let mut count = 0;
+            (&mut *self.handle).HitTestTextRange(range.start, length, 0.0, 0.0, ptr::null_mut(), 0, &mut count);
+
+            let mut metrics: Vec<DWRITE_HIT_TEST_METRICS> = vec![::std::mem::zeroed(); count as usize];
+            let result = (&mut *self.handle).HitTestTextRange(range.start, length, 0.0, 0.0, metrics.as_mut_ptr(), count, &mut count);
+            if result != S_OK { return Vec::new(); }
+
+            metrics.truncate(count as usize);
+            metrics.iter().map(|m| Rectangle{ left: m.left, top: m.top, right: m.left + m.width, bottom: m.top + m.height }).collect()
+        }
+    }
+
+    /// Return the inner text layout. Used by the canvas renderer.
+    pub(crate) fn get_handle(&self) -> *mut IDWriteTextLayout {
+        self.handle
+    }
+
+}
+
+impl Drop for TextLayout {
+    fn drop(&mut self) {
+        unsafe{ (&mut *self.handle).Release(); }
+    }
+}
+
+
+// Private functions
+use winapi::S_OK;
+
+/**
+    Return the text format identified by `format`
+*/
+pub(crate) fn text_format_handle<ID: Hash+Clone>(ui: &Ui<ID>, format: &ID) -> Result<*mut IDWriteTextFormat, Error> {
+    match ui.handle_of(format) {
+        Ok(AnyHandle::Custom(t, h)) => {
+            if t == TypeId::of::<TextFormat>() { Ok(h as *mut IDWriteTextFormat) }
+            else { Err(Error::BadResource( format!("A text format resource required. Got a custom handle of another type") )) }
+        },
+        Ok(h) => Err(Error::BadResource( format!("A text format resource required. Got {}", h.human_name()) )),
+        Err(e) => Err(e)
+    }
+}
+
+/**
+    Format `text` with `format` in a layout box of `max_size`
+*/
+pub(crate) fn create_text_layout(factory: &mut IDWriteFactory, format: *mut IDWriteTextFormat, text: &str, max_size: (f32, f32)) -> Result<TextLayout, Error> {
+    let text: Vec<u16> = text.encode_utf16().collect();
+    let mut layout: *mut IDWriteTextLayout = ptr::null_mut();
+    let result = unsafe{ factory.CreateTextLayout(text.as_ptr(), text.len() as u32, format, max_size.0, max_size.1, &mut layout) };
+
+    if result == S_OK {
+        Ok(TextLayout{ handle: layout, length: text.len() as u32 })
+    } else {
+        Err(Error::System(SystemError::ComError("Failed to create the text layout".to_string())))
+    }
+}
+
+/**
+    Create a text format from its description
+*/
+pub(crate) fn create_text_format(format: &TextStyle, factory: &mut IDWriteFactory) -> Result<*mut IDWriteTextFormat, Error> {
+    use winapi::{DWRITE_FONT_WEIGHT, DWRITE_FONT_STYLE, DWRITE_FONT_STRETCH_NORMAL, DWRITE_TEXT_ALIGNMENT,
+      DWRITE_PARAGRAPH_ALIGNMENT, DWRITE_WORD_WRAPPING};
+    use low::other_helper::to_utf16;
+
+    let family = to_utf16(&format.family);
+    let locale = to_utf16("en-us");
+    let weight = DWRITE_FONT_WEIGHT(font_weight(format.weight));
+    let style = DWRITE_FONT_STYLE(format.style as u32);
+
+    let mut text_format: *mut IDWriteTextFormat = ptr::null_mut();
+    let result = unsafe{ factory.CreateTextFormat(family.as_ptr(), ptr::null_mut(), weight, style, DWRITE_FONT_STRETCH_NORMAL,
+      format.size, locale.as_ptr(), &mut text_format) };
+
+    if result != S_OK {
+        return Err(Error::System(SystemError::ComError("Failed to create the text format".to_string())));
+    }
+
+    unsafe {
+        let text_format = &mut *text_format;
+        text_format.SetTextAlignment(DWRITE_TEXT_ALIGNMENT(format.text_align as u32));
+        text_format.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT(format.paragraph_align as u32));
+        text_format.SetWordWrapping(DWRITE_WORD_WRAPPING(format.wrapping as u32));
+    }
+
+    Ok(text_format)
+}
+
+/// DirectWrite does not accept FONT_WEIGHT_DONTCARE or weights over 999
+fn font_weight(weight: c_int) -> u32 {
+    match weight {
+        w if w <= 0 => 400,
+        w if w > 999 => 999,
+        w => w as u32
+    }
+}
+
+fn text_range(range: &Range<u32>) -> ::winapi::DWRITE_TEXT_RANGE {
+    let length = if range.end > range.start { range.end - range.start } else { 0 };
+    ::winapi::DWRITE_TEXT_RANGE{ startPosition: range.start, length: length }
+}
+
+fn hit_test(m: &::winapi::DWRITE_HIT_TEST_METRICS, trailing: bool, inside: bool, point: (f32, f32)) -> HitTest {
+    HitTest {
+        position: m.textPosition,
+        length: m.length,
+        trailing: trailing,
+        inside: inside,
+        point: point,
+        bounds: Rectangle{ left: m.left, top: m.top, right: m.left + m.width, bottom: m.top + m.height }
+    }
+}
@@ -0,0 +1,463 @@
+/*!
+    Device independent bitmap decoder and encoder. Used to read and write BMP files and to read the bitmaps stored in icon files.
+
+    Supports the core (OS/2), info, V2 to V5 headers, top-down and bottom-up images, 1/4/8 bits palettes,
+    RLE4 and RLE8 compression, 16 and 32 bits bitfields, 24 bits images, 32 bits images with an alpha channel
+    and the PNG or JPEG images embedded in a bitmap.
+    The encoder writes 32 bits bitmaps with a V4 header and an alpha channel.
+*/
+
+use resources::image::ImageData;
+use super::{check_dimensions, put_pixel, get_pixel, le_u16, le_u32, png, jpeg};
+
+const FILE_HEADER_SIZE: usize = 14;
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_JPEG: u32 = 4;
+const BI_PNG: u32 = 5;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/**
+    The fields of the bitmap header used by the decoder
+*/
+struct Header {
+    size: usize,
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bit_count: u32,
+    compression: u32,
+    image_size: usize,
+    colors_used: usize,
+    masks: Option<[u32; 4]>
+}
+
+/**
+    A color channel described by a bit mask
+*/
+#[derive(Clone, Copy)]
+struct Channel {
+    shift: u32,
+    max: u32,
+    mask: u32
+}
+
+impl Channel {
+
+    fn new(mask: u32) -> Channel {
+        if mask == 0 { return Channel{ shift: 0, max: 0, mask: 0 }; }
+        let shift = mask.trailing_zeros();
+        Channel{ shift: shift, max: mask >> shift, mask: mask }
+    }
+
+    /// Extract the value of the channel from a pixel and scale it to 8 bits. Empty channels return `default`.
+    #[inline(always)]
+    fn read(&self, pixel: u32, default: u8) -> u8 {
+        if self.max == 0 { return default; }
+        let v = (pixel & self.mask) >> self.shift;
+        ((v as u64 * 255 + self.max as u64 / 2) / self.max as u64) as u8
+    }
+
+}
+
+/**
+    Decode a BMP file
+*/
+pub fn decode(data: &[u8]) -> Result<ImageData, String> {
+    if data.len() < 2 || &data[0..2] != b"BM" {
+        return Err("missing BMP signature".to_string());
+    }
+
+    if data.len() < FILE_HEADER_SIZE + 4 {
+        return Err(format!("the file ({} bytes) is smaller than the bitmap file header", data.len()));
+    }
+
+    let offset = le_u32(data, 10) as usize;
+    if offset > data.len() {
+        return Err(format!("the pixel data offset ({}) is outside of the file ({} bytes)", offset, data.len()));
+    }
+
+    let pixels_offset = if offset == 0 { None } else { Some(offset) };
+    decode_dib(&data[FILE_HEADER_SIZE..], pixels_offset.map(|o| o.saturating_sub(FILE_HEADER_SIZE)), FILE_HEADER_SIZE, false)
+}
+
+/**
+    Encode an image as a bottom-up 32 bits BMP file. The alpha channel is described by the bit masks of a BITMAPV4HEADER.
+*/
+pub fn encode(image: &ImageData) -> Vec<u8> {
+    const V4_HEADER_SIZE: u32 = 108;
+    const LCS_SRGB: u32 = 0x7352_4742;
+    const PIXELS_PER_METER: u32 = 2835; // 72 DPI
+
+    let (w, h) = (image.width as usize, image.height as usize);
+    let offset = FILE_HEADER_SIZE as u32 + V4_HEADER_SIZE;
+    let image_size = (w * h * 4) as u32;
+
+    let mut out: Vec<u8> = Vec::with_capacity(offset as usize + image_size as usize);
+    out.extend_from_slice(b"BM");
+    for &v in [offset + image_size, 0, offset].iter() { out.extend_from_slice(&le_bytes(v)); }
+
+    let fields = [V4_HEADER_SIZE, image.width, image.height, 1 | (32 << 16), BI_BITFIELDS, image_size, PIXELS_PER_METER, PIXELS_PER_METER, 0, 0,
+        0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000, LCS_SRGB];
+    for &v in fields.iter() { out.extend_from_slice(&le_bytes(v)); }
+    out.resize(offset as usize, 0); // Color space endpoints and gamma
+
+    for row in 0..h {
+        let y = h - 1 - row;
+        for p in image.data[y * w * 4..(y + 1) * w * 4].chunks(4) {
+            let (r, g, b, a) = get_pixel(p);
+            out.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+
+    out
+}
+
+#[inline(always)]
+fn le_bytes(v: u32) -> [u8; 4] {
+    [v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]
+}
+
+/**
+    Decode the DIB of an icon or a cursor. The height of the header includes the AND mask that follows the color data.
+*/
+pub fn decode_icon(data: &[u8]) -> Result<ImageData, String> {
+    decode_dib(data, None, 0, true)
+}
+
+fn parse_header(data: &[u8], base: usize) -> Result<Header, String> {
+    if data.len() < 4 {
+        return Err("the bitmap header is truncated".to_string());
+    }
+
+    let size = le_u32(data, 0) as usize;
+    match size {
+        12 | 40 | 52 | 56 | 64 | 108 | 124 => {},
+        s => { return Err(format!("unsupported bitmap header size {}", s)); }
+    }
+
+    if data.len() < size {
+        return Err(format!("the bitmap header ({} bytes at offset {}) is truncated", size, base));
+    }
+
+    // BITMAPCOREHEADER
+    if size == 12 {
+        let planes = le_u16(data, 8);
+        if planes != 1 { return Err(format!("invalid number of color planes {}", planes)); }
+
+        return Ok(Header {
+            size: size,
+            width: le_u16(data, 4) as usize,
+            height: le_u16(data, 6) as usize,
+            top_down: false,
+            bit_count: le_u16(data, 10) as u32,
+            compression: BI_RGB,
+            image_size: 0,
+            colors_used: 0,
+            masks: None
+        });
+    }
+
+    let width = le_u32(data, 4) as i32 as i64;
+    let height = le_u32(data, 8) as i32 as i64;
+    let planes = le_u16(data, 12);
+    let compression = le_u32(data, 16);
+
+    if planes != 1 { return Err(format!("invalid number of color planes {}", planes)); }
+    if width < 0 { return Err(format!("invalid bitmap width {}", width)); }
+
+    let masks = if size >= 52 && size != 64 {
+        Some([le_u32(data, 40), le_u32(data, 44), le_u32(data, 48), if size >= 56 { le_u32(data, 52) } else { 0 }])
+    } else {
+        None
+    };
+
+    Ok(Header {
+        size: size,
+        width: width as usize,
+        height: height.abs() as usize,
+        top_down: height < 0,
+        bit_count: le_u16(data, 14) as u32,
+        compression: compression,
+        image_size: le_u32(data, 20) as usize,
+        colors_used: le_u32(data, 32) as usize,
+        masks: masks
+    })
+}
+
+/**
+    Decode a DIB that starts with a bitmap header. `pixels_offset` is the position of the pixels from the start of the header.
+    If it is None, the pixels follow the color table. `base` is the offset of the DIB in the file, for the error messages.
+*/
+fn decode_dib(data: &[u8], pixels_offset: Option<usize>, base: usize, icon: bool) -> Result<ImageData, String> {
+    let mut header = parse_header(data, base)?;
+
+    if icon {
+        header.height /= 2;
+    }
+
+    // Embedded images keep their own size
+    if header.compression == BI_PNG || header.compression == BI_JPEG {
+        let start = pixels_offset.unwrap_or(header.size);
+        let end = if header.image_size == 0 { data.len() } else { start.saturating_add(header.image_size) };
+        if start > data.len() || end > data.len() {
+            return Err(format!("the embedded image ({} bytes at offset {}) is truncated", header.image_size, base + start));
+        }
+
+        return if header.compression == BI_PNG { png::decode(&data[start..end]) } else { jpeg::decode(&data[start..end]) };
+    }
+
+    check_dimensions(header.width as u32, header.height as u32)?;
+
+    // The OS/2 2.x header uses the same values for other compression methods
+    if header.size == 64 && header.compression != BI_RGB && header.compression != BI_RLE8 && header.compression != BI_RLE4 {
+        return Err(format!("unsupported OS/2 bitmap compression {}", header.compression));
+    }
+
+    let bit_count = header.bit_count;
+    match (header.compression, bit_count) {
+        (BI_RGB, 1) | (BI_RGB, 4) | (BI_RGB, 8) | (BI_RGB, 16) | (BI_RGB, 24) | (BI_RGB, 32) => {},
+        (BI_RLE8, 8) | (BI_RLE4, 4) => {},
+        (BI_BITFIELDS, 16) | (BI_BITFIELDS, 32) | (BI_ALPHABITFIELDS, 16) | (BI_ALPHABITFIELDS, 32) => {},
+        (BI_RGB, b) | (BI_BITFIELDS, b) | (BI_ALPHABITFIELDS, b) => { return Err(format!("unsupported bit count {}", b)); },
+        (BI_RLE8, b) | (BI_RLE4, b) => { return Err(format!("invalid bit count {} for RLE compression", b)); },
+        (c, _) => { return Err(format!("unsupported bitmap compression {}", c)); }
+    }
+
+    if header.top_down && (header.compression == BI_RLE8 || header.compression == BI_RLE4) {
+        return Err("top-down bitmaps cannot be compressed".to_string());
+    }
+
+    // Bit masks stored after a BITMAPINFOHEADER
+    let mut table_start = header.size;
+    if header.compression == BI_BITFIELDS || header.compression == BI_ALPHABITFIELDS {
+        if header.size == 40 {
+            let count = if header.compression == BI_ALPHABITFIELDS { 4 } else { 3 };
+            if data.len() < 40 + count * 4 {
+                return Err(format!("the color masks at offset {} are truncated", base + 40));
+            }
+
+            let alpha = if count == 4 { le_u32(data, 52) } else { 0 };
+            header.masks = Some([le_u32(data, 40), le_u32(data, 44), le_u32(data, 48), alpha]);
+            table_start += count * 4;
+        }
+    }
+
+    // Color table
+    let entry_size = if header.size == 12 { 3 } else { 4 };
+    let palette_size = if bit_count <= 8 {
+        let max = 1 << bit_count;
+        match header.colors_used {
+            0 => max,
+            n if n > max => { return Err(format!("the color table has {} colors, the maximum for {} bits per pixel is {}", n, bit_count, max)); },
+            n => n
+        }
+    } else {
+        // A color table can be used to optimize the display of true color images. It is skipped.
+        ::std::cmp::min(header.colors_used, 256)
+    };
+
+    let palette_end = table_start + palette_size * entry_size;
+    let palette_available = match pixels_offset {
+        Some(o) => ::std::cmp::min(o, data.len()),
+        None => data.len()
+    };
+
+    if bit_count <= 8 && palette_end > palette_available {
+        return Err(format!("the color table ({} colors at offset {}) is truncated", palette_size, base + table_start));
+    }
+
+    let palette: Vec<(u8, u8, u8)> = if bit_count <= 8 {
+        data[table_start..palette_end].chunks(entry_size).map(|c| (c[2], c[1], c[0])).collect()
+    } else {
+        Vec::new()
+    };
+
+    let pixels_start = match pixels_offset {
+        Some(o) => {
+            if o < table_start {
+                return Err(format!("the pixel data offset ({}) overlaps the bitmap header", base + o));
+            }
+            o
+        },
+        None => ::std::cmp::min(palette_end, data.len())
+    };
+
+    if pixels_start > data.len() {
+        return Err(format!("the pixel data offset ({}) is outside of the file", base + pixels_start));
+    }
+
+    let pixels = &data[pixels_start..];
+    let (w, h) = (header.width, header.height);
+    let mut image = ImageData::new(w as u32, h as u32);
+
+    match header.compression {
+        BI_RLE8 | BI_RLE4 => {
+            let compressed = if header.image_size > 0 && header.image_size <= pixels.len() { &pixels[..header.image_size] } else { pixels };
+            let indices = decode_rle(compressed, w, h, header.compression == BI_RLE4, base + pixels_start)?;
+            for (i, index) in indices.iter().enumerate() {
+                let (x, row) = (i % w, i / w);
+                let y = h - 1 - row;
+                if let &Some(index) = index {
+                    let (r, g, b) = palette_color(&palette, index, x, y)?;
+                    let o = (y * w + x) * 4;
+                    put_pixel(&mut image.data[o..o+4], r, g, b, 255);
+                }
+            }
+        },
+        _ => {
+            let stride = ((w * bit_count as usize + 31) / 32) * 4;
+            let last_row = (w * bit_count as usize + 7) / 8;
+            let color_size = stride * (h - 1) + last_row;
+            if pixels.len() < color_size {
+                return Err(format!("the pixel data is truncated: {} bytes are required at offset {}, but only {} are available",
+                    color_size, base + pixels_start, pixels.len()));
+            }
+
+            // The AND mask of icons is optional for 32 bits images that have an alpha channel
+            let mask_stride = ((w + 31) / 32) * 4;
+            let mask = if icon && pixels.len() >= stride * h + mask_stride * h {
+                Some(&pixels[stride * h..stride * h + mask_stride * h])
+            } else {
+                None
+            };
+
+            // An alpha channel that is empty everywhere is ignored, the images saved without alpha often leave it to zero
+            let channels = channels(&header);
+            let use_alpha = (bit_count == 16 || bit_count == 32) && channels[3].max != 0 && (0..h).any(|row| {
+                (0..w).any(|x| channels[3].read(read_packed(&pixels[row * stride..], x, bit_count), 0) != 0)
+            });
+
+            for row in 0..h {
+                let y = if header.top_down { row } else { h - 1 - row };
+                let line = &pixels[row * stride..];
+
+                for x in 0..w {
+                    let (r, g, b, mut a) = match bit_count {
+                        24 => (line[x * 3 + 2], line[x * 3 + 1], line[x * 3], 255),
+                        16 | 32 => {
+                            let p = read_packed(line, x, bit_count);
+                            (channels[0].read(p, 0), channels[1].read(p, 0), channels[2].read(p, 0), if use_alpha { channels[3].read(p, 255) } else { 255 })
+                        },
+                        d => {
+                            let bit = x * d as usize;
+                            let index = (line[bit / 8] >> (8 - d as usize - bit % 8)) & ((1u16 << d) - 1) as u8;
+                            let (r, g, b) = palette_color(&palette, index, x, y)?;
+                            (r, g, b, 255)
+                        }
+                    };
+
+                    if let Some(mask) = mask {
+                        if !use_alpha && (mask[row * mask_stride + x / 8] >> (7 - x % 8)) & 1 == 1 {
+                            a = 0;
+                        }
+                    }
+
+                    let o = (y * w + x) * 4;
+                    put_pixel(&mut image.data[o..o+4], r, g, b, a);
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+#[inline(always)]
+fn palette_color(palette: &[(u8, u8, u8)], index: u8, x: usize, y: usize) -> Result<(u8, u8, u8), String> {
+    match palette.get(index as usize) {
+        Some(&c) => Ok(c),
+        None => Err(format!("the palette index {} at ({}, {}) is out of range ({} colors)", index, x, y, palette.len()))
+    }
+}
+
+/**
+    Read a 16 or 32 bits pixel in a row
+*/
+#[inline(always)]
+fn read_packed(line: &[u8], x: usize, bit_count: u32) -> u32 {
+    if bit_count == 16 { le_u16(line, x * 2) as u32 } else { le_u32(line, x * 4) }
+}
+
+/**
+    Return the red, green, blue and alpha channels of a 16 or 32 bits bitmap
+*/
+fn channels(header: &Header) -> [Channel; 4] {
+    let masks = match (header.compression, header.masks) {
+        (BI_BITFIELDS, Some(m)) | (BI_ALPHABITFIELDS, Some(m)) => m,
+        _ if header.bit_count == 16 => [0x7C00, 0x03E0, 0x001F, 0],
+        _ => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]
+    };
+
+    [Channel::new(masks[0]), Channel::new(masks[1]), Channel::new(masks[2]), Channel::new(masks[3])]
+}
+
+/**
+    Decode RLE4 or RLE8 data into palette indices, bottom-up. The pixels skipped by the delta and end of line
+    codes are `None` and stay transparent. Runs that go past the end of a row are clipped.
+*/
+fn decode_rle(data: &[u8], w: usize, h: usize, rle4: bool, base: usize) -> Result<Vec<Option<u8>>, String> {
+    let mut out: Vec<Option<u8>> = vec![None; w * h];
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut pos = 0;
+
+    let truncated = |pos: usize| Err(format!("the RLE data is truncated at offset {}", base + pos));
+
+    loop {
+        if y >= h { break; }
+        if pos + 2 > data.len() {
+            return truncated(pos);
+        }
+
+        let (count, value) = (data[pos] as usize, data[pos + 1]);
+        pos += 2;
+
+        if count > 0 {
+            // Encoded run
+            for i in 0..count {
+                if x >= w { break; }
+                let index = if rle4 { if i % 2 == 0 { value >> 4 } else { value & 0x0F } } else { value };
+                out[y * w + x] = Some(index);
+                x += 1;
+            }
+            continue;
+        }
+
+        match value {
+            0 => { x = 0; y += 1; },
+            1 => { break; },
+            2 => {
+                if pos + 2 > data.len() { return truncated(pos); }
+                x += data[pos] as usize;
+                y += data[pos + 1] as usize;
+                pos += 2;
+            },
+            n => {
+                // Absolute run, padded to a 16 bits boundary
+                let n = n as usize;
+                let bytes = if rle4 { (n + 1) / 2 } else { n };
+                let padded = (bytes + 1) & !1;
+                if pos + bytes > data.len() { return truncated(pos); }
+
+                for i in 0..n {
+                    if x >= w { break; }
+                    let index = if rle4 {
+                        let b = data[pos + i / 2];
+                        if i % 2 == 0 { b >> 4 } else { b & 0x0F }
+                    } else {
+                        data[pos + i]
+                    };
+                    out[y * w + x] = Some(index);
+                    x += 1;
+                }
+
+                pos += ::std::cmp::min(padded, data.len() - pos);
+            }
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,144 @@
+/*!
+    Zlib (RFC 1950) compression. The data is compressed with LZ77 and the fixed huffman codes of deflate (RFC 1951).
+*/
+
+use super::inflate::{adler32, LENGTH_BASE, LENGTH_EXTRA, DIST_BASE, DIST_EXTRA};
+
+const WINDOW_SIZE: usize = 32768;
+const HASH_BITS: usize = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 128;
+
+/**
+    Write bits least significant bit first
+*/
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u32
+}
+
+impl BitWriter {
+
+    fn bits(&mut self, value: u32, count: u32) {
+        self.acc |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a huffman code. Huffman codes are stored most significant bit first.
+    fn code(&mut self, code: u32, length: u32) {
+        let mut reversed = 0;
+        for i in 0..length {
+            reversed |= ((code >> i) & 1) << (length - 1 - i);
+        }
+        self.bits(reversed, length);
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.acc as u8);
+            self.acc = 0;
+            self.count = 0;
+        }
+    }
+
+}
+
+/**
+    Compress data into a zlib stream
+*/
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter{ out: vec![0x78, 0x01], acc: 0, count: 0 };
+
+    // A single final block using the fixed codes
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let mut head = vec![usize::max_value(); 1 << HASH_BITS];
+    let mut prev = vec![usize::max_value(); WINDOW_SIZE];
+    let hash = |i: usize| -> usize {
+        let v = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+        (v.wrapping_mul(2654435761) >> 8) & ((1 << HASH_BITS) - 1)
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            let max_len = ::std::cmp::min(MAX_MATCH, data.len() - i);
+            let mut candidate = head[h];
+            let mut chain = 0;
+
+            while candidate != usize::max_value() && i - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let mut len = 0;
+                while len < max_len && data[candidate + len] == data[i + len] { len += 1; }
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len { break; }
+                }
+
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::max_value() || next >= candidate { break; }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        let step = if best_len >= MIN_MATCH {
+            write_match(&mut writer, best_len, best_dist);
+            best_len
+        } else {
+            write_symbol(&mut writer, data[i] as u32);
+            1
+        };
+
+        // Insert the positions covered by the step in the hash chains
+        for j in i..(i + step) {
+            if j + MIN_MATCH <= data.len() {
+                let h = hash(j);
+                prev[j % WINDOW_SIZE] = head[h];
+                head[h] = j;
+            }
+        }
+
+        i += step;
+    }
+
+    write_symbol(&mut writer, 256);
+    writer.flush();
+
+    let checksum = adler32(data);
+    writer.out.extend_from_slice(&[(checksum >> 24) as u8, (checksum >> 16) as u8, (checksum >> 8) as u8, checksum as u8]);
+    writer.out
+}
+
+/**
+    Write a literal/length symbol with the fixed huffman code
+*/
+fn write_symbol(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.code(0x30 + symbol, 8),
+        144..=255 => writer.code(0x190 + symbol - 144, 9),
+        256..=279 => writer.code(symbol - 256, 7),
+        _ => writer.code(0xC0 + symbol - 280, 8)
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let l = LENGTH_BASE.iter().rposition(|&b| b as usize <= length).unwrap_or(0);
+    write_symbol(writer, 257 + l as u32);
+    writer.bits((length - LENGTH_BASE[l] as usize) as u32, LENGTH_EXTRA[l] as u32);
+
+    let d = DIST_BASE.iter().rposition(|&b| b as usize <= distance).unwrap_or(0);
+    writer.code(d as u32, 5);
+    writer.bits((distance - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d] as u32);
+}
@@ -0,0 +1,345 @@
+/*!
+    GIF decoder. The first frame or every frame of animated images can be decoded.
+*/
+
+use resources::image::{ImageData, AnimationData, AnimationFrame};
+use super::{check_dimensions, put_pixel, clear_rect, le_u16, MAX_ANIMATION_PIXELS};
+
+const MAX_CODE_SIZE: u32 = 12;
+
+/// Delays below this value (in milliseconds) are replaced by `DEFAULT_DELAY`, like in web browsers
+const MIN_DELAY: u32 = 20;
+const DEFAULT_DELAY: u32 = 100;
+
+/**
+    The header of a GIF file
+*/
+struct Screen {
+    width: usize,
+    height: usize,
+    global_palette: Vec<[u8; 3]>
+}
+
+/**
+    A frame of a GIF file, before it is composited on the logical screen
+*/
+struct Frame {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    palette: Vec<[u8; 3]>,
+    transparent: Option<u8>,
+    indices: Vec<u8>
+}
+
+/**
+    The values of a graphic control extension. They apply to the next frame.
+*/
+#[derive(Clone, Copy)]
+struct Control {
+    delay: u32,
+    disposal: u8,
+    transparent: Option<u8>
+}
+
+const NO_CONTROL: Control = Control{ delay: 0, disposal: 0, transparent: None };
+
+/**
+    Decode the first frame of a GIF file. The frame is drawn over a transparent logical screen.
+*/
+pub fn decode(data: &[u8]) -> Result<ImageData, String> {
+    let mut animation = decode_frames(data, Some(1))?;
+    Ok(animation.frames.remove(0).image)
+}
+
+/**
+    Decode every frame of a GIF file. The frames are composited following their disposal method.
+    Without a NETSCAPE2.0 extension, the animation is played once.
+*/
+pub fn decode_animation(data: &[u8]) -> Result<AnimationData, String> {
+    decode_frames(data, None)
+}
+
+fn decode_frames(data: &[u8], limit: Option<usize>) -> Result<AnimationData, String> {
+    let (screen, mut pos) = parse_screen(data)?;
+    let mut canvas = ImageData::new(screen.width as u32, screen.height as u32);
+    let mut animation = AnimationData{ frames: Vec::new(), loop_count: 1 };
+    let mut control = NO_CONTROL;
+    let mut total_pixels = 0u64;
+
+    loop {
+        match data.get(pos) {
+            Some(&0x21) => {
+                if pos + 2 > data.len() { return Err("truncated extension".to_string()); }
+                let label = data[pos + 1];
+                let (block, next) = read_sub_blocks(data, pos + 2)?;
+                if label == 0xF9 && block.len() >= 4 {
+                    control = Control {
+                        delay: le_u16(&block, 1) as u32 * 10,
+                        disposal: (block[0] >> 2) & 0x07,
+                        transparent: if block[0] & 1 == 1 { Some(block[3]) } else { None }
+                    };
+                } else if label == 0xFF && block.len() >= 14 && (&block[0..11] == b"NETSCAPE2.0" || &block[0..11] == b"ANIMEXTS1.0") && block[11] == 1 {
+                    // The value is the number of repetitions after the first play. 0 loops forever.
+                    let repeat = le_u16(&block, 12) as u32;
+                    animation.loop_count = if repeat == 0 { 0 } else { repeat + 1 };
+                }
+                pos = next;
+            },
+            Some(&0x2C) => {
+                let (frame, next) = read_frame(data, pos, &screen, control.transparent)?;
+
+                total_pixels += (screen.width * screen.height) as u64;
+                if total_pixels > MAX_ANIMATION_PIXELS {
+                    return Err("the animation has too many frames".to_string());
+                }
+
+                let previous = if control.disposal == 3 { Some(canvas.clone()) } else { None };
+                compose(&mut canvas, &frame);
+
+                let delay = if control.delay < MIN_DELAY { DEFAULT_DELAY } else { control.delay };
+                animation.frames.push(AnimationFrame{ image: canvas.clone(), delay: delay });
+
+                match (control.disposal, previous) {
+                    (2, _) => clear_rect(&mut canvas, frame.left, frame.top, frame.width, frame.height),
+                    (3, Some(previous)) => { canvas = previous; },
+                    _ => {}
+                }
+
+                control = NO_CONTROL;
+                pos = next;
+
+                if Some(animation.frames.len()) == limit { return Ok(animation); }
+            },
+            Some(&0x3B) | None if !animation.frames.is_empty() => { return Ok(animation); },
+            Some(&0x3B) => { return Err("the image does not contain any frame".to_string()); },
+            Some(&b) => { return Err(format!("unknown block 0x{:02X} at offset {}", b, pos)); },
+            None => { return Err("unexpected end of file".to_string()); }
+        }
+    }
+}
+
+fn parse_screen(data: &[u8]) -> Result<(Screen, usize), String> {
+    if data.len() < 13 || (&data[0..6] != b"GIF87a" && &data[0..6] != b"GIF89a") {
+        return Err("missing GIF signature".to_string());
+    }
+
+    let width = le_u16(data, 6) as usize;
+    let height = le_u16(data, 8) as usize;
+    let flags = data[10];
+    check_dimensions(width as u32, height as u32)?;
+
+    let mut pos = 13;
+    let mut global_palette = Vec::new();
+    if flags & 0x80 != 0 {
+        let (palette, next) = read_palette(data, pos, flags)?;
+        global_palette = palette;
+        pos = next;
+    }
+
+    Ok((Screen{ width: width, height: height, global_palette: global_palette }, pos))
+}
+
+fn read_palette(data: &[u8], pos: usize, flags: u8) -> Result<(Vec<[u8; 3]>, usize), String> {
+    let size = 2usize << (flags & 0x07);
+    if pos + size * 3 > data.len() {
+        return Err("truncated color table".to_string());
+    }
+
+    let palette = data[pos..pos + size * 3].chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+    Ok((palette, pos + size * 3))
+}
+
+/**
+    Concatenate the data sub blocks starting at `pos`. Return the data and the position after the block terminator.
+*/
+fn read_sub_blocks(data: &[u8], mut pos: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut out = Vec::new();
+    loop {
+        let size = match data.get(pos) {
+            Some(&s) => s as usize,
+            None => { return Err("truncated data sub block".to_string()); }
+        };
+
+        pos += 1;
+        if size == 0 { return Ok((out, pos)); }
+        if pos + size > data.len() { return Err("truncated data sub block".to_string()); }
+
+        out.extend_from_slice(&data[pos..pos + size]);
+        pos += size;
+    }
+}
+
+/**
+    Read an image descriptor and its data. Return the frame and the position after the image data.
+*/
+fn read_frame(data: &[u8], pos: usize, screen: &Screen, transparent: Option<u8>) -> Result<(Frame, usize), String> {
+    if pos + 10 > data.len() { return Err("truncated image descriptor".to_string()); }
+
+    let left = le_u16(data, pos + 1) as usize;
+    let top = le_u16(data, pos + 3) as usize;
+    let width = le_u16(data, pos + 5) as usize;
+    let height = le_u16(data, pos + 7) as usize;
+    let flags = data[pos + 9];
+    let mut pos = pos + 10;
+
+    check_dimensions(width as u32, height as u32)?;
+
+    let palette = if flags & 0x80 != 0 {
+        let (palette, next) = read_palette(data, pos, flags)?;
+        pos = next;
+        palette
+    } else if !screen.global_palette.is_empty() {
+        screen.global_palette.clone()
+    } else {
+        return Err("the frame does not have a color table".to_string());
+    };
+
+    let min_code_size = match data.get(pos) {
+        Some(&s) if s >= 1 && s <= 11 => s as u32,
+        Some(&s) => { return Err(format!("invalid LZW code size {}", s)); },
+        None => { return Err("truncated image data".to_string()); }
+    };
+
+    let (compressed, next) = read_sub_blocks(data, pos + 1)?;
+    let mut indices = lzw_decode(&compressed, min_code_size, width * height)?;
+
+    if flags & 0x40 != 0 {
+        indices = deinterlace(&indices, width, height);
+    }
+
+    let frame = Frame{ left: left, top: top, width: width, height: height, palette: palette, transparent: transparent, indices: indices };
+    Ok((frame, next))
+}
+
+/**
+    Decode the LZW compressed indices of a frame. Missing pixels are left to 0.
+*/
+fn lzw_decode(data: &[u8], min_code_size: u32, pixels: usize) -> Result<Vec<u8>, String> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    // Each code is stored as its prefix code and its last byte
+    let mut prefix = [0u16; 4096];
+    let mut suffix = [0u8; 4096];
+    let mut first = [0u8; 4096];
+    for i in 0..clear as usize {
+        suffix[i] = i as u8;
+        first[i] = i as u8;
+    }
+
+    let mut out: Vec<u8> = Vec::with_capacity(pixels);
+    let mut stack: Vec<u8> = Vec::with_capacity(4096);
+
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end + 1;
+    let mut previous: Option<u16> = None;
+
+    let (mut acc, mut count, mut pos) = (0u32, 0u32, 0usize);
+
+    while out.len() < pixels {
+        while count < code_size {
+            match data.get(pos) {
+                Some(&b) => { acc |= (b as u32) << count; count += 8; pos += 1; },
+                None => { break; }
+            }
+        }
+
+        if count < code_size { break; }
+
+        let code = (acc & ((1 << code_size) - 1)) as u16;
+        acc >>= code_size;
+        count -= code_size;
+
+        if code == clear {
+            code_size = min_code_size + 1;
+            next_code = end + 1;
+            previous = None;
+            continue;
+        }
+
+        if code == end { break; }
+
+        let prev = match previous {
+            Some(p) => p,
+            None => {
+                if code >= clear { return Err(format!("invalid LZW code {}", code)); }
+                out.push(code as u8);
+                previous = Some(code);
+                continue;
+            }
+        };
+
+        if code > next_code || (code == next_code && next_code >= 4096) {
+            return Err(format!("invalid LZW code {}", code));
+        }
+
+        // A code equal to the next code is the previous string followed by its own first byte
+        let (mut c, tail) = if code == next_code { (prev, Some(first[prev as usize])) } else { (code, None) };
+
+        stack.clear();
+        if let Some(t) = tail { stack.push(t); }
+        while c >= clear {
+            stack.push(suffix[c as usize]);
+            c = prefix[c as usize];
+        }
+        stack.push(c as u8);
+
+        out.extend(stack.iter().rev());
+
+        if next_code < 4096 {
+            prefix[next_code as usize] = prev;
+            suffix[next_code as usize] = stack[stack.len() - 1];
+            first[next_code as usize] = first[prev as usize];
+            next_code += 1;
+            if next_code == (1 << code_size) && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        }
+
+        previous = Some(code);
+    }
+
+    out.resize(pixels, 0);
+    Ok(out)
+}
+
+fn deinterlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut out = vec![0u8; indices.len()];
+    let mut row = 0;
+    for &(start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)].iter() {
+        let mut y = start;
+        while y < height {
+            out[y * width..(y + 1) * width].copy_from_slice(&indices[row * width..(row + 1) * width]);
+            row += 1;
+            y += step;
+        }
+    }
+    out
+}
+
+/**
+    Draw a frame on the logical screen. Transparent pixels are left untouched.
+*/
+fn compose(canvas: &mut ImageData, frame: &Frame) {
+    let (width, height) = (canvas.width as usize, canvas.height as usize);
+
+    for y in 0..frame.height {
+        let sy = frame.top + y;
+        if sy >= height { break; }
+
+        for x in 0..frame.width {
+            let sx = frame.left + x;
+            if sx >= width { break; }
+
+            let index = frame.indices[y * frame.width + x];
+            if Some(index) == frame.transparent { continue; }
+
+            if let Some(c) = frame.palette.get(index as usize) {
+                let i = (sy * width + sx) * 4;
+                put_pixel(&mut canvas.data[i..i+4], c[0], c[1], c[2], 255);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/*!
+    ICO and CUR decoder. Every image of the file is decoded, the images can be stored as PNG or as DIB.
+*/
+
+use resources::image::ImageData;
+use super::{le_u16, le_u32, png, bmp};
+
+/**
+    Decode every image of an icon or cursor file, in the order of the directory
+*/
+pub fn decode(data: &[u8]) -> Result<Vec<ImageData>, String> {
+    if data.len() < 6 || le_u16(data, 0) != 0 || (le_u16(data, 2) != 1 && le_u16(data, 2) != 2) {
+        return Err("missing ICO header".to_string());
+    }
+
+    let count = le_u16(data, 4) as usize;
+    if count == 0 {
+        return Err("the icon does not contain any image".to_string());
+    }
+
+    if 6 + count * 16 > data.len() {
+        return Err(format!("the icon directory ({} entries) is truncated", count));
+    }
+
+    let mut images = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = &data[6 + i * 16..6 + (i + 1) * 16];
+        let size = le_u32(entry, 8) as usize;
+        let offset = le_u32(entry, 12) as usize;
+
+        if offset > data.len() || size > data.len() - offset {
+            return Err(format!("the data of the image {} is outside of the file", i));
+        }
+
+        let bytes = &data[offset..offset + size];
+        let image = if bytes.len() >= 8 && bytes[0..8] == png::SIGNATURE[..] {
+            png::decode(bytes)
+        } else {
+            bmp::decode_icon(bytes)
+        };
+
+        match image {
+            Ok(image) => images.push(image),
+            Err(e) => { return Err(format!("image {}: {}", i, e)); }
+        }
+    }
+
+    Ok(images)
+}
@@ -0,0 +1,321 @@
+/*!
+    Zlib (RFC 1950) and deflate (RFC 1951) decompression
+*/
+
+const MAX_BITS: usize = 15;
+
+pub const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+pub const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+pub const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+pub const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/**
+    Read the bits of a deflate stream, least significant bit first
+*/
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32
+}
+
+impl<'a> BitReader<'a> {
+
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.bit_count < count {
+            match self.data.get(self.pos) {
+                Some(&b) => {
+                    self.bit_buf |= (b as u32) << self.bit_count;
+                    self.bit_count += 8;
+                    self.pos += 1;
+                },
+                None => { return Err("unexpected end of the compressed data".to_string()); }
+            }
+        }
+
+        let value = self.bit_buf & ((1u32 << count) - 1);
+        self.bit_buf >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+
+}
+
+/**
+    A canonical huffman code. `counts` is the number of codes of each length and `symbols` the symbols sorted by code.
+*/
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+
+    /**
+        Build a huffman code from the code length of each symbol. Incomplete codes are only accepted if `allow_incomplete` is set.
+    */
+    fn new(lengths: &[u8], allow_incomplete: bool) -> Result<Huffman, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &l in lengths.iter() { counts[l as usize] += 1; }
+
+        if counts[0] as usize == lengths.len() {
+            return Ok(Huffman{ counts: counts, symbols: Vec::new() });
+        }
+
+        let mut left: i32 = 1;
+        for len in 1..(MAX_BITS + 1) {
+            left <<= 1;
+            left -= counts[len] as i32;
+            if left < 0 { return Err("over-subscribed huffman code".to_string()); }
+        }
+
+        if left > 0 && !allow_incomplete {
+            let used = lengths.iter().filter(|&&l| l != 0).count();
+            if used > 1 { return Err("incomplete huffman code".to_string()); }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = symbol as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+
+        Ok(Huffman{ counts: counts, symbols: symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index): (i32, i32, i32) = (0, 0, 0);
+
+        for len in 1..(MAX_BITS + 1) {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err("invalid huffman code".to_string())
+    }
+
+}
+
+/**
+    Decompress a zlib stream. The adler-32 checksum is validated.
+    Decompressing fails if the data is bigger than `max_size` bytes, so that small malformed streams cannot allocate unlimited memory.
+*/
+pub fn zlib_decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 2 {
+        return Err("the zlib stream is too short".to_string());
+    }
+
+    let (cmf, flg) = (data[0] as u32, data[1] as u32);
+    if cmf & 0x0F != 8 { return Err(format!("unsupported zlib compression method {}", cmf & 0x0F)); }
+    if (cmf >> 4) > 7 { return Err("invalid zlib window size".to_string()); }
+    if (cmf * 256 + flg) % 31 != 0 { return Err("invalid zlib header checksum".to_string()); }
+    if flg & 0x20 != 0 { return Err("zlib preset dictionaries are not supported".to_string()); }
+
+    let (out, end) = inflate_at(data, 2, max_size)?;
+
+    let checksum = &data[end..];
+    if checksum.len() >= 4 {
+        let expected = ((checksum[0] as u32) << 24) | ((checksum[1] as u32) << 16) | ((checksum[2] as u32) << 8) | (checksum[3] as u32);
+        if adler32(&out) != expected {
+            return Err("adler-32 checksum mismatch".to_string());
+        }
+    }
+
+    Ok(out)
+}
+
+/**
+    Return the adler-32 checksum of some data
+*/
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk.iter() {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+/**
+    Decompress a deflate stream starting at `start`. Return the data and the position of the first byte after the stream.
+    Return an error if the data is bigger than `max_size` bytes.
+*/
+fn inflate_at(data: &[u8], start: usize, max_size: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader{ data: data, pos: start, bit_buf: 0, bit_count: 0 };
+    let mut out: Vec<u8> = Vec::with_capacity(::std::cmp::min(data.len() * 4, max_size));
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => stored_block(&mut reader, &mut out, max_size)?,
+            1 => {
+                let (lit, dist) = fixed_codes()?;
+                compressed_block(&mut reader, &mut out, &lit, &dist, max_size)?;
+            },
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut reader)?;
+                compressed_block(&mut reader, &mut out, &lit, &dist, max_size)?;
+            },
+            _ => { return Err("invalid deflate block type".to_string()); }
+        }
+
+        if last { break; }
+    }
+
+    Ok((out, reader.pos))
+}
+
+fn stored_block(reader: &mut BitReader, out: &mut Vec<u8>, max_size: usize) -> Result<(), String> {
+    reader.align();
+
+    let pos = reader.pos;
+    if pos + 4 > reader.data.len() {
+        return Err("unexpected end of the compressed data".to_string());
+    }
+
+    let len = (reader.data[pos] as usize) | ((reader.data[pos+1] as usize) << 8);
+    let nlen = (reader.data[pos+2] as usize) | ((reader.data[pos+3] as usize) << 8);
+    if len != (!nlen & 0xFFFF) {
+        return Err("invalid stored block length".to_string());
+    }
+
+    let start = pos + 4;
+    if start + len > reader.data.len() {
+        return Err("unexpected end of the compressed data".to_string());
+    }
+
+    if out.len() + len > max_size {
+        return Err(too_big(max_size));
+    }
+
+    out.extend_from_slice(&reader.data[start..start+len]);
+    reader.pos = start + len;
+
+    Ok(())
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), String> {
+    let mut lengths = [0u8; 288];
+    for (i, l) in lengths.iter_mut().enumerate() {
+        *l = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8
+        };
+    }
+
+    let lit = Huffman::new(&lengths, false)?;
+    let dist = Huffman::new(&[5u8; 30], true)?;
+    Ok((lit, dist))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+    if hlit > 286 || hdist > 30 {
+        return Err("invalid dynamic block code counts".to_string());
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for i in 0..hclen {
+        code_lengths[CODE_LENGTH_ORDER[i]] = reader.bits(3)? as u8;
+    }
+
+    let code_length_code = Huffman::new(&code_lengths, false)?;
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut index = 0;
+    while index < hlit + hdist {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if index == 0 { return Err("repeated code length without a previous length".to_string()); }
+                (lengths[index - 1], 3 + reader.bits(2)? as usize)
+            },
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize)
+        };
+
+        if index + repeat > hlit + hdist {
+            return Err("too many code lengths".to_string());
+        }
+
+        for l in lengths[index..index+repeat].iter_mut() { *l = value; }
+        index += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err("missing end of block code".to_string());
+    }
+
+    let lit = Huffman::new(&lengths[..hlit], false)?;
+    let dist = Huffman::new(&lengths[hlit..], true)?;
+    Ok((lit, dist))
+}
+
+fn compressed_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman, max_size: usize) -> Result<(), String> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        if symbol < 256 {
+            if out.len() >= max_size { return Err(too_big(max_size)); }
+            out.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let symbol = symbol - 257;
+            if symbol >= 29 { return Err("invalid length code".to_string()); }
+            let length = LENGTH_BASE[symbol] as usize + reader.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+
+            let symbol = dist.decode(reader)? as usize;
+            if symbol >= 30 { return Err("invalid distance code".to_string()); }
+            let distance = DIST_BASE[symbol] as usize + reader.bits(DIST_EXTRA[symbol] as u32)? as usize;
+
+            if distance > out.len() {
+                return Err("distance is too far back".to_string());
+            }
+
+            if out.len() + length > max_size {
+                return Err(too_big(max_size));
+            }
+
+            let from = out.len() - distance;
+            for i in 0..length {
+                let b = out[from + i];
+                out.push(b);
+            }
+        }
+    }
+}
+
+fn too_big(max_size: usize) -> String {
+    format!("the decompressed data is bigger than the expected {} bytes", max_size)
+}
@@ -0,0 +1,670 @@
+/*!
+    JPEG decoder. Supports the baseline, extended and progressive huffman coded images with 8 bits samples,
+    grayscale, YCbCr, RGB, CMYK and YCCK color spaces and any chroma subsampling.
+    Arithmetic coding, lossless and hierarchical images are not supported.
+*/
+
+use resources::image::ImageData;
+use super::{check_dimensions, put_pixel, be_u16};
+
+/// The position of the coefficients of a block in the zig-zag order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5,
+    12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63
+];
+
+/**
+    A huffman table in the form described in the annex F of the JPEG specification
+*/
+#[derive(Clone)]
+struct Huffman {
+    max_code: [i32; 18],
+    val_ptr: [i32; 17],
+    min_code: [i32; 17],
+    values: Vec<u8>
+}
+
+impl Huffman {
+
+    fn new(counts: &[u8], values: &[u8]) -> Result<Huffman, String> {
+        let mut h = Huffman{ max_code: [-1; 18], val_ptr: [0; 17], min_code: [0; 17], values: values.to_vec() };
+        let mut code: i32 = 0;
+        let mut k: i32 = 0;
+
+        for len in 1..17 {
+            let count = counts[len - 1] as i32;
+            h.val_ptr[len] = k;
+            h.min_code[len] = code;
+            code += count;
+            k += count;
+            h.max_code[len] = if count > 0 { code - 1 } else { -1 };
+            if code > (1 << len) {
+                return Err("invalid huffman table".to_string());
+            }
+            code <<= 1;
+        }
+
+        h.max_code[17] = i32::max_value();
+        Ok(h)
+    }
+
+}
+
+/**
+    Read the entropy coded data, most significant bit first. Stuffed bytes are removed.
+    When a marker is found, zeros are returned.
+*/
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u32,
+    count: u32,
+    marker: bool
+}
+
+impl<'a> BitReader<'a> {
+
+    fn new(data: &'a [u8], pos: usize) -> BitReader<'a> {
+        BitReader{ data: data, pos: pos, acc: 0, count: 0, marker: false }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let byte = if self.marker || self.pos >= self.data.len() {
+                0
+            } else if self.data[self.pos] == 0xFF {
+                match self.data.get(self.pos + 1) {
+                    Some(&0) => { self.pos += 2; 0xFF },
+                    _ => { self.marker = true; 0 }
+                }
+            } else {
+                self.pos += 1;
+                self.data[self.pos - 1]
+            };
+
+            self.acc |= (byte as u32) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> u32 {
+        if n == 0 { return 0; }
+        if self.count < n { self.fill(); }
+        let value = self.acc >> (32 - n);
+        self.acc <<= n;
+        self.count -= n;
+        value
+    }
+
+    fn bit(&mut self) -> u32 {
+        self.bits(1)
+    }
+
+    /// Read a value of `n` bits and extend its sign
+    fn receive_extend(&mut self, n: u32) -> i32 {
+        if n == 0 { return 0; }
+        let value = self.bits(n) as i32;
+        if value < (1 << (n - 1)) { value - (1 << n) + 1 } else { value }
+    }
+
+    fn decode(&mut self, h: &Huffman) -> Result<u8, String> {
+        let mut code = self.bit() as i32;
+        let mut len = 1;
+        while code > h.max_code[len] {
+            code = (code << 1) | (self.bit() as i32);
+            len += 1;
+            if len > 16 { return Err("invalid huffman code".to_string()); }
+        }
+
+        let index = h.val_ptr[len] + code - h.min_code[len];
+        match h.values.get(index as usize) {
+            Some(&v) => Ok(v),
+            None => Err("invalid huffman code".to_string())
+        }
+    }
+
+    /// Discard the buffered bits and skip the next restart marker
+    fn restart(&mut self) {
+        self.acc = 0;
+        self.count = 0;
+        self.marker = false;
+
+        while self.pos + 1 < self.data.len() {
+            if self.data[self.pos] == 0xFF && self.data[self.pos + 1] >= 0xD0 && self.data[self.pos + 1] <= 0xD7 {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    tq: usize,
+    blocks_w: usize,
+    blocks_h: usize,
+    coefs: Vec<i32>,
+    dc_pred: i32,
+    dc_table: usize,
+    ac_table: usize
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    progressive: bool,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+    components: Vec<Component>
+}
+
+/**
+    Decode a JPEG file
+*/
+pub fn decode(data: &[u8]) -> Result<ImageData, String> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err("missing JPEG start of image marker".to_string());
+    }
+
+    let mut qtables = [[0u16; 64]; 4];
+    let mut dc_tables: Vec<Option<Huffman>> = vec![None, None, None, None];
+    let mut ac_tables: Vec<Option<Huffman>> = vec![None, None, None, None];
+    let mut frame: Option<Frame> = None;
+    let mut restart_interval = 0;
+    let mut adobe_transform: Option<u8> = None;
+    let mut pos = 2;
+
+    loop {
+        // Find the next marker, skipping the fill bytes
+        while pos < data.len() && data[pos] != 0xFF { pos += 1; }
+        while pos < data.len() && data[pos] == 0xFF { pos += 1; }
+        if pos >= data.len() { break; }
+
+        let marker = data[pos];
+        pos += 1;
+
+        match marker {
+            0xD8 | 0x01 | 0xD0..=0xD7 => continue,
+            0xD9 => break,
+            _ => {}
+        }
+
+        if pos + 2 > data.len() { return Err(format!("truncated marker 0x{:02X}", marker)); }
+        let length = be_u16(data, pos) as usize;
+        if length < 2 || pos + length > data.len() {
+            return Err(format!("segment of marker 0x{:02X} at offset {} is truncated", marker, pos - 2));
+        }
+
+        let body = &data[pos+2..pos+length];
+        pos += length;
+
+        match marker {
+            0xDB => parse_quantization(body, &mut qtables)?,
+            0xC4 => parse_huffman(body, &mut dc_tables, &mut ac_tables)?,
+            0xDD => {
+                if body.len() < 2 { return Err("invalid DRI segment".to_string()); }
+                restart_interval = be_u16(body, 0) as usize;
+            },
+            0xEE => {
+                if body.len() >= 12 && &body[0..5] == b"Adobe" {
+                    adobe_transform = Some(body[11]);
+                }
+            },
+            0xC0 | 0xC1 | 0xC2 => {
+                if frame.is_some() { return Err("multiple frames are not supported".to_string()); }
+                frame = Some(parse_frame(body, marker == 0xC2)?);
+            },
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                return Err(format!("unsupported JPEG process (marker 0x{:02X}). Only huffman baseline and progressive images are supported", marker));
+            },
+            0xDA => {
+                let frame = match frame.as_mut() {
+                    Some(f) => f,
+                    None => { return Err("scan found before the frame header".to_string()); }
+                };
+
+                pos = decode_scan(data, pos, body, frame, &dc_tables, &ac_tables, restart_interval)?;
+            },
+            _ => { /* APPn, COM and the other markers are ignored */ }
+        }
+    }
+
+    let frame = match frame {
+        Some(f) => f,
+        None => { return Err("missing frame header".to_string()); }
+    };
+
+    for c in frame.components.iter() {
+        if c.tq > 3 { return Err(format!("invalid quantization table {}", c.tq)); }
+    }
+
+    let planes: Vec<Vec<u8>> = frame.components.iter().map(|c| idct_component(c, &qtables[c.tq])).collect();
+    convert_colors(&frame, &planes, adobe_transform)
+}
+
+fn parse_quantization(mut body: &[u8], qtables: &mut [[u16; 64]; 4]) -> Result<(), String> {
+    while !body.is_empty() {
+        let precision = body[0] >> 4;
+        let id = (body[0] & 0x0F) as usize;
+        if id > 3 { return Err(format!("invalid quantization table id {}", id)); }
+
+        let size = if precision == 0 { 64 } else { 128 };
+        if body.len() < 1 + size { return Err("truncated quantization table".to_string()); }
+
+        for k in 0..64 {
+            let value = if precision == 0 { body[1 + k] as u16 } else { be_u16(body, 1 + k * 2) };
+            qtables[id][ZIGZAG[k]] = value;
+        }
+
+        body = &body[1 + size..];
+    }
+
+    Ok(())
+}
+
+fn parse_huffman(mut body: &[u8], dc: &mut Vec<Option<Huffman>>, ac: &mut Vec<Option<Huffman>>) -> Result<(), String> {
+    while !body.is_empty() {
+        if body.len() < 17 { return Err("truncated huffman table".to_string()); }
+
+        let class = body[0] >> 4;
+        let id = (body[0] & 0x0F) as usize;
+        if class > 1 || id > 3 { return Err(format!("invalid huffman table id {}/{}", class, id)); }
+
+        let counts = &body[1..17];
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        if body.len() < 17 + total || total > 256 { return Err("truncated huffman table".to_string()); }
+
+        let table = Huffman::new(counts, &body[17..17 + total])?;
+        if class == 0 { dc[id] = Some(table); } else { ac[id] = Some(table); }
+
+        body = &body[17 + total..];
+    }
+
+    Ok(())
+}
+
+fn parse_frame(body: &[u8], progressive: bool) -> Result<Frame, String> {
+    if body.len() < 6 { return Err("truncated frame header".to_string()); }
+    if body[0] != 8 { return Err(format!("unsupported sample precision {}", body[0])); }
+
+    let height = be_u16(body, 1) as usize;
+    let width = be_u16(body, 3) as usize;
+    let count = body[5] as usize;
+
+    if height == 0 { return Err("images with a DNL marker are not supported".to_string()); }
+    check_dimensions(width as u32, height as u32)?;
+
+    if count != 1 && count != 3 && count != 4 {
+        return Err(format!("unsupported number of components {}", count));
+    }
+
+    if body.len() < 6 + count * 3 { return Err("truncated frame header".to_string()); }
+
+    let mut components = Vec::with_capacity(count);
+    for i in 0..count {
+        let c = &body[6 + i * 3..9 + i * 3];
+        let (h, v) = ((c[1] >> 4) as usize, (c[1] & 0x0F) as usize);
+        if h == 0 || h > 4 || v == 0 || v > 4 {
+            return Err(format!("invalid sampling factors {}x{}", h, v));
+        }
+
+        components.push(Component{ id: c[0], h: h, v: v, tq: c[2] as usize, blocks_w: 0, blocks_h: 0, coefs: Vec::new(),
+            dc_pred: 0, dc_table: 0, ac_table: 0 });
+    }
+
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let mcus_x = (width + 8 * h_max - 1) / (8 * h_max);
+    let mcus_y = (height + 8 * v_max - 1) / (8 * v_max);
+
+    for c in components.iter_mut() {
+        c.blocks_w = mcus_x * c.h;
+        c.blocks_h = mcus_y * c.v;
+        c.coefs = vec![0; c.blocks_w * c.blocks_h * 64];
+    }
+
+    Ok(Frame{ width: width, height: height, progressive: progressive, h_max: h_max, v_max: v_max, mcus_x: mcus_x, mcus_y: mcus_y, components: components })
+}
+
+/**
+    Decode a scan. Return the position of the first byte after the entropy coded data.
+*/
+fn decode_scan(data: &[u8], pos: usize, header: &[u8], frame: &mut Frame, dc_tables: &[Option<Huffman>], ac_tables: &[Option<Huffman>], restart_interval: usize) -> Result<usize, String> {
+    if header.is_empty() { return Err("truncated scan header".to_string()); }
+
+    let count = header[0] as usize;
+    if count == 0 || count > 4 || header.len() < 4 + count * 2 {
+        return Err("invalid scan header".to_string());
+    }
+
+    let mut scan_components: Vec<usize> = Vec::with_capacity(count);
+    for i in 0..count {
+        let (id, tables) = (header[1 + i * 2], header[2 + i * 2]);
+        let index = match frame.components.iter().position(|c| c.id == id) {
+            Some(i) => i,
+            None => { return Err(format!("scan references the unknown component {}", id)); }
+        };
+
+        let c = &mut frame.components[index];
+        c.dc_table = (tables >> 4) as usize;
+        c.ac_table = (tables & 0x0F) as usize;
+        c.dc_pred = 0;
+        if c.dc_table > 3 || c.ac_table > 3 { return Err("invalid huffman table selector".to_string()); }
+        scan_components.push(index);
+    }
+
+    let p = 1 + count * 2;
+    let (ss, se, ah, al) = (header[p] as usize, header[p + 1] as usize, (header[p + 2] >> 4) as u32, (header[p + 2] & 0x0F) as u32);
+
+    if frame.progressive {
+        if se > 63 || ss > se || (ss == 0 && se != 0) || (ss > 0 && count != 1) || al > 13 {
+            return Err("invalid progressive scan parameters".to_string());
+        }
+    } else if ss != 0 || se != 63 || ah != 0 || al != 0 {
+        return Err("invalid baseline scan parameters".to_string());
+    }
+
+    // Check that the tables used by the scan were defined
+    for &i in scan_components.iter() {
+        let c = &frame.components[i];
+        let needs_dc = ss == 0 && ah == 0 || !frame.progressive;
+        let needs_ac = se > 0;
+        if needs_dc && dc_tables[c.dc_table].is_none() { return Err(format!("missing DC huffman table {}", c.dc_table)); }
+        if needs_ac && ac_tables[c.ac_table].is_none() { return Err(format!("missing AC huffman table {}", c.ac_table)); }
+    }
+
+    let mut reader = BitReader::new(data, pos);
+    let mut eobrun: u32 = 0;
+    let empty = Huffman{ max_code: [-1; 18], val_ptr: [0; 17], min_code: [0; 17], values: Vec::new() };
+
+    // Single component scans are not interleaved and only cover the visible blocks of the component
+    let (units_x, units_y) = if count == 1 {
+        let c = &frame.components[scan_components[0]];
+        let w = (frame.width * c.h + frame.h_max - 1) / frame.h_max;
+        let h = (frame.height * c.v + frame.v_max - 1) / frame.v_max;
+        ((w + 7) / 8, (h + 7) / 8)
+    } else {
+        (frame.mcus_x, frame.mcus_y)
+    };
+
+    let total = units_x * units_y;
+    for unit in 0..total {
+        if restart_interval > 0 && unit > 0 && unit % restart_interval == 0 {
+            reader.restart();
+            eobrun = 0;
+            for &i in scan_components.iter() { frame.components[i].dc_pred = 0; }
+        }
+
+        let (ux, uy) = (unit % units_x, unit / units_x);
+
+        for &ci in scan_components.iter() {
+            let c = &mut frame.components[ci];
+            let dc = dc_tables[c.dc_table].as_ref().unwrap_or(&empty);
+            let ac = ac_tables[c.ac_table].as_ref().unwrap_or(&empty);
+
+            let blocks: Vec<(usize, usize)> = if count == 1 {
+                vec![(ux, uy)]
+            } else {
+                let mut b = Vec::with_capacity(c.h * c.v);
+                for by in 0..c.v {
+                    for bx in 0..c.h { b.push((ux * c.h + bx, uy * c.v + by)); }
+                }
+                b
+            };
+
+            for (bx, by) in blocks {
+                let offset = (by * c.blocks_w + bx) * 64;
+                let mut pred = c.dc_pred;
+                {
+                    let block = &mut c.coefs[offset..offset + 64];
+                    if !frame.progressive {
+                        decode_block(&mut reader, block, dc, ac, &mut pred)?;
+                    } else if ss == 0 {
+                        if ah == 0 {
+                            let s = reader.decode(dc)? as u32;
+                            if s > 11 { return Err("invalid DC coefficient".to_string()); }
+                            pred += reader.receive_extend(s);
+                            block[0] = pred * (1 << al);
+                        } else if reader.bit() == 1 {
+                            block[0] |= 1 << al;
+                        }
+                    } else if ah == 0 {
+                        decode_ac_first(&mut reader, block, ac, ss, se, al, &mut eobrun)?;
+                    } else {
+                        decode_ac_refine(&mut reader, block, ac, ss, se, al, &mut eobrun)?;
+                    }
+                }
+                c.dc_pred = pred;
+            }
+        }
+    }
+
+    // Find the marker following the entropy coded data
+    let mut end = reader.pos;
+    while end + 1 < data.len() {
+        if data[end] == 0xFF && data[end + 1] != 0 && !(data[end + 1] >= 0xD0 && data[end + 1] <= 0xD7) {
+            break;
+        }
+        end += 1;
+    }
+
+    Ok(end)
+}
+
+fn decode_block(reader: &mut BitReader, block: &mut [i32], dc: &Huffman, ac: &Huffman, pred: &mut i32) -> Result<(), String> {
+    let s = reader.decode(dc)? as u32;
+    if s > 11 { return Err("invalid DC coefficient".to_string()); }
+    *pred += reader.receive_extend(s);
+    block[0] = *pred;
+
+    let mut k = 1;
+    while k < 64 {
+        let rs = reader.decode(ac)?;
+        let (r, s) = ((rs >> 4) as usize, (rs & 0x0F) as u32);
+        if s == 0 {
+            if r == 15 { k += 16; continue; }
+            break;
+        }
+
+        k += r;
+        if k > 63 { return Err("invalid AC coefficient run".to_string()); }
+        block[ZIGZAG[k]] = reader.receive_extend(s);
+        k += 1;
+    }
+
+    Ok(())
+}
+
+fn decode_ac_first(reader: &mut BitReader, block: &mut [i32], ac: &Huffman, ss: usize, se: usize, al: u32, eobrun: &mut u32) -> Result<(), String> {
+    if *eobrun > 0 {
+        *eobrun -= 1;
+        return Ok(());
+    }
+
+    let mut k = ss;
+    while k <= se {
+        let rs = reader.decode(ac)?;
+        let (r, s) = ((rs >> 4) as u32, (rs & 0x0F) as u32);
+        if s == 0 {
+            if r < 15 {
+                *eobrun = (1 << r) - 1;
+                if r > 0 { *eobrun += reader.bits(r); }
+                break;
+            }
+            k += 16;
+        } else {
+            k += r as usize;
+            if k > 63 { return Err("invalid AC coefficient run".to_string()); }
+            block[ZIGZAG[k]] = reader.receive_extend(s) * (1 << al);
+            k += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_ac_refine(reader: &mut BitReader, block: &mut [i32], ac: &Huffman, ss: usize, se: usize, al: u32, eobrun: &mut u32) -> Result<(), String> {
+    let bit = 1i32 << al;
+
+    if *eobrun > 0 {
+        *eobrun -= 1;
+        for k in ss..(se + 1) {
+            refine(reader, &mut block[ZIGZAG[k]], bit);
+        }
+        return Ok(());
+    }
+
+    let mut k = ss;
+    while k <= se {
+        let rs = reader.decode(ac)?;
+        let (mut r, s) = ((rs >> 4) as i32, (rs & 0x0F) as u32);
+        let mut value = 0;
+
+        if s == 0 {
+            if r < 15 {
+                *eobrun = (1 << r) - 1;
+                if r > 0 { *eobrun += reader.bits(r as u32); }
+                r = 64; // Refine the remaining coefficients of the block
+            }
+        } else {
+            if s != 1 { return Err("invalid AC refinement value".to_string()); }
+            value = if reader.bit() == 1 { bit } else { -bit };
+        }
+
+        while k <= se {
+            let coef = &mut block[ZIGZAG[k]];
+            k += 1;
+            if *coef != 0 {
+                refine(reader, coef, bit);
+            } else {
+                if r == 0 {
+                    *coef = value;
+                    break;
+                }
+                r -= 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[inline(always)]
+fn refine(reader: &mut BitReader, coef: &mut i32, bit: i32) {
+    if *coef != 0 && reader.bit() == 1 && (*coef & bit) == 0 {
+        if *coef >= 0 { *coef += bit; } else { *coef -= bit; }
+    }
+}
+
+/**
+    Dequantize and transform the blocks of a component. Return the samples of the component.
+*/
+fn idct_component(c: &Component, qtable: &[u16; 64]) -> Vec<u8> {
+    use std::f32::consts::PI;
+
+    // cos((2x + 1) * u * PI / 16) * C(u) / 2
+    let mut table = [0f32; 64];
+    for x in 0..8 {
+        for u in 0..8 {
+            let cu = if u == 0 { 1.0 / 2f32.sqrt() } else { 1.0 };
+            table[x * 8 + u] = cu * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos() / 2.0;
+        }
+    }
+
+    let stride = c.blocks_w * 8;
+    let mut out = vec![0u8; stride * c.blocks_h * 8];
+    let mut coefs = [0f32; 64];
+    let mut tmp = [0f32; 64];
+
+    for by in 0..c.blocks_h {
+        for bx in 0..c.blocks_w {
+            let block = &c.coefs[(by * c.blocks_w + bx) * 64..][..64];
+            for i in 0..64 { coefs[i] = (block[i] * qtable[i] as i32) as f32; }
+
+            // Rows then columns
+            for v in 0..8 {
+                for x in 0..8 {
+                    let mut sum = 0.0;
+                    for u in 0..8 { sum += table[x * 8 + u] * coefs[v * 8 + u]; }
+                    tmp[v * 8 + x] = sum;
+                }
+            }
+
+            for x in 0..8 {
+                for y in 0..8 {
+                    let mut sum = 0.0;
+                    for v in 0..8 { sum += table[y * 8 + v] * tmp[v * 8 + x]; }
+                    let value = (sum + 128.0).round();
+                    let value = if value < 0.0 { 0 } else if value > 255.0 { 255 } else { value as u8 };
+                    out[(by * 8 + y) * stride + bx * 8 + x] = value;
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[inline(always)]
+fn clamp(v: f32) -> u8 {
+    if v < 0.0 { 0 } else if v > 255.0 { 255 } else { (v + 0.5) as u8 }
+}
+
+#[inline(always)]
+fn ycc_to_rgb(y: u8, cb: u8, cr: u8) -> (u8, u8, u8) {
+    let (y, cb, cr) = (y as f32, cb as f32 - 128.0, cr as f32 - 128.0);
+    (clamp(y + 1.402 * cr), clamp(y - 0.344136 * cb - 0.714136 * cr), clamp(y + 1.772 * cb))
+}
+
+fn convert_colors(frame: &Frame, planes: &[Vec<u8>], adobe_transform: Option<u8>) -> Result<ImageData, String> {
+    let (w, h) = (frame.width, frame.height);
+    let mut image = ImageData::new(w as u32, h as u32);
+    let count = frame.components.len();
+
+    // RGB images are identified by the Adobe marker or by the component ids
+    let ids: Vec<u8> = frame.components.iter().map(|c| c.id).collect();
+    let rgb = count == 3 && (adobe_transform == Some(0) || (adobe_transform.is_none() && ids == b"RGB"));
+
+    let mut samples = [0u8; 4];
+    for y in 0..h {
+        for x in 0..w {
+            // Nearest neighbour upsampling of the subsampled components
+            for (i, c) in frame.components.iter().enumerate() {
+                let sx = x * c.h / frame.h_max;
+                let sy = y * c.v / frame.v_max;
+                samples[i] = planes[i][sy * c.blocks_w * 8 + sx];
+            }
+
+            let (r, g, b) = match count {
+                1 => (samples[0], samples[0], samples[0]),
+                3 if rgb => (samples[0], samples[1], samples[2]),
+                3 => ycc_to_rgb(samples[0], samples[1], samples[2]),
+                _ => {
+                    // Adobe CMYK images are stored inverted
+                    let (c, m, ye) = if adobe_transform == Some(2) {
+                        let (r, g, b) = ycc_to_rgb(samples[0], samples[1], samples[2]);
+                        (255 - r, 255 - g, 255 - b)
+                    } else {
+                        (samples[0], samples[1], samples[2])
+                    };
+
+                    let k = samples[3] as u32;
+                    (((c as u32 * k + 127) / 255) as u8, ((m as u32 * k + 127) / 255) as u8, ((ye as u32 * k + 127) / 255) as u8)
+                }
+            };
+
+            let i = (y * w + x) * 4;
+            put_pixel(&mut image.data[i..i+4], r, g, b, 255);
+        }
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,118 @@
+/*!
+    Image file decoders and encoders.
+
+    The codecs only work on bytes, so they can be used without a display. The decoders return an `ImageData`
+    (premultiplied BGRA pixels, top-down) or an error message that is wrapped in `Error::BadResource` by the caller.
+    The GIF and PNG decoders can also decode every frame of animated images into an `AnimationData`.
+*/
+
+use resources::image::ImageData;
+
+pub mod inflate;
+pub mod deflate;
+pub mod png;
+pub mod jpeg;
+pub mod gif;
+pub mod bmp;
+pub mod ico;
+
+/// The maximum width or height of a decoded image
+pub const MAX_DIMENSION: u32 = 32768;
+
+/// The maximum number of pixels of a decoded image
+pub const MAX_PIXELS: u64 = 1 << 27;
+
+/// The maximum number of pixels of all the frames of a decoded animation
+pub const MAX_ANIMATION_PIXELS: u64 = 1 << 28;
+
+/**
+    Check that the size of an image is not empty and that its pixels can be allocated
+*/
+pub fn check_dimensions(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 {
+        Err(format!("the image size ({}x{}) is empty", width, height))
+    } else if width > MAX_DIMENSION || height > MAX_DIMENSION || (width as u64) * (height as u64) > MAX_PIXELS {
+        Err(format!("the image size ({}x{}) is too large", width, height))
+    } else {
+        Ok(())
+    }
+}
+
+/**
+    Write a straight alpha RGBA color as a premultiplied BGRA pixel
+*/
+#[inline(always)]
+pub fn put_pixel(out: &mut [u8], r: u8, g: u8, b: u8, a: u8) {
+    if a == 255 {
+        out[0] = b; out[1] = g; out[2] = r; out[3] = 255;
+    } else {
+        let a32 = a as u32;
+        out[0] = ((b as u32 * a32 + 127) / 255) as u8;
+        out[1] = ((g as u32 * a32 + 127) / 255) as u8;
+        out[2] = ((r as u32 * a32 + 127) / 255) as u8;
+        out[3] = a;
+    }
+}
+
+/**
+    Convert a premultiplied BGRA pixel to a straight alpha RGBA color
+*/
+#[inline(always)]
+pub fn get_pixel(p: &[u8]) -> (u8, u8, u8, u8) {
+    let a = p[3] as u32;
+    match a {
+        255 => (p[2], p[1], p[0], 255),
+        0 => (0, 0, 0, 0),
+        a => {
+            let f = |c: u8| ::std::cmp::min(255, (c as u32 * 255 + a / 2) / a) as u8;
+            (f(p[2]), f(p[1]), f(p[0]), a as u8)
+        }
+    }
+}
+
+/**
+    Draw a premultiplied BGRA pixel over another one
+*/
+#[inline(always)]
+pub fn blend_pixel(dst: &mut [u8], src: &[u8]) {
+    match src[3] {
+        255 => dst[0..4].copy_from_slice(&src[0..4]),
+        0 => {},
+        a => {
+            let inv = 255 - a as u32;
+            for i in 0..4 {
+                dst[i] = (src[i] as u32 + (dst[i] as u32 * inv + 127) / 255) as u8;
+            }
+        }
+    }
+}
+
+/**
+    Make a rectangle of an image transparent. The rectangle is clipped to the image.
+*/
+pub fn clear_rect(image: &mut ImageData, x: usize, y: usize, width: usize, height: usize) {
+    let (w, h) = (image.width as usize, image.height as usize);
+    for row in y..::std::cmp::min(h, y + height) {
+        let start = (row * w + ::std::cmp::min(w, x)) * 4;
+        let end = (row * w + ::std::cmp::min(w, x + width)) * 4;
+        for v in image.data[start..end].iter_mut() { *v = 0; }
+    }
+}
+
+/**
+    Read a big endian u16 / u32 in a slice. The caller must check the bounds.
+*/
+#[inline(always)]
+pub fn be_u16(d: &[u8], pos: usize) -> u16 { ((d[pos] as u16) << 8) | (d[pos+1] as u16) }
+
+#[inline(always)]
+pub fn be_u32(d: &[u8], pos: usize) -> u32 { ((d[pos] as u32) << 24) | ((d[pos+1] as u32) << 16) | ((d[pos+2] as u32) << 8) | (d[pos+3] as u32) }
+
+/**
+    Read a little endian u16 / u32 in a slice. The caller must check the bounds.
+*/
+#[inline(always)]
+pub fn le_u16(d: &[u8], pos: usize) -> u16 { (d[pos] as u16) | ((d[pos+1] as u16) << 8) }
+
+#[inline(always)]
+pub fn le_u32(d: &[u8], pos: usize) -> u32 { (d[pos] as u32) | ((d[pos+1] as u32) << 8) | ((d[pos+2] as u32) << 16) | ((d[pos+3] as u32) << 24) }
@@ -0,0 +1,562 @@
+/*!
+    PNG decoder and encoder. The decoder supports every color type and bit depth, transparency chunks, interlaced images
+    and animated PNG (APNG) files. Ancillary chunks other than `tRNS` and the APNG chunks are ignored. The encoder writes 8 bits RGB or RGBA images.
+*/
+
+use resources::image::{ImageData, AnimationData, AnimationFrame};
+use super::{check_dimensions, put_pixel, get_pixel, blend_pixel, clear_rect, be_u16, be_u32, inflate, deflate, MAX_ANIMATION_PIXELS};
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool
+}
+
+impl Header {
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            0 | 3 => 1,
+            2 => 3,
+            4 => 2,
+            _ => 4
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * (self.bit_depth as usize)
+    }
+
+}
+
+/**
+    Transparency information from the tRNS chunk
+*/
+enum Transparency {
+    None,
+    Palette(Vec<u8>),
+    Gray(u16),
+    Rgb(u16, u16, u16)
+}
+
+/**
+    The frame control chunk (fcTL) of an animated PNG
+*/
+struct FrameControl {
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    delay: u32,
+    dispose: u8,
+    blend: u8
+}
+
+/**
+    The chunks of a PNG file that are needed to decode it
+*/
+struct Png {
+    header: Header,
+    palette: Vec<[u8; 3]>,
+    transparency: Transparency,
+    default_image: Vec<u8>,
+
+    /// The number of plays of the animation (acTL). `None` if the image is not animated.
+    plays: Option<u32>,
+
+    /// The frames of an animated image. If a frame control comes before the default image, the default image
+    /// is the first frame and the data of this frame is empty.
+    frames: Vec<(FrameControl, Vec<u8>)>
+}
+
+/**
+    Decode a PNG file. Only the default image of animated PNG files is decoded.
+*/
+pub fn decode(data: &[u8]) -> Result<ImageData, String> {
+    let png = parse(data)?;
+    decode_pixels(&png, png.header.width, png.header.height, &png.default_image)
+}
+
+/**
+    Decode every frame of an animated PNG (APNG) file. The frames are composited following their dispose and blend operations.
+    Files that are not animated return a single frame.
+*/
+pub fn decode_animation(data: &[u8]) -> Result<AnimationData, String> {
+    let png = parse(data)?;
+    let plays = match png.plays {
+        Some(plays) if !png.frames.is_empty() => plays,
+        _ => {
+            let image = decode_pixels(&png, png.header.width, png.header.height, &png.default_image)?;
+            return Ok(AnimationData{ frames: vec![AnimationFrame{ image: image, delay: 0 }], loop_count: 1 });
+        }
+    };
+
+    let (w, h) = (png.header.width, png.header.height);
+    if (w as u64) * (h as u64) * (png.frames.len() as u64) > MAX_ANIMATION_PIXELS {
+        return Err("the animation has too many frames".to_string());
+    }
+
+    let mut canvas = ImageData::new(w, h);
+    let mut animation = AnimationData{ frames: Vec::with_capacity(png.frames.len()), loop_count: plays };
+
+    for (i, &(ref control, ref compressed)) in png.frames.iter().enumerate() {
+        if control.x as u64 + control.width as u64 > w as u64 || control.y as u64 + control.height as u64 > h as u64 {
+            return Err(format!("frame {} is outside of the image", i));
+        }
+
+        let compressed = if compressed.is_empty() { &png.default_image } else { compressed };
+        let frame = match decode_pixels(&png, control.width, control.height, compressed) {
+            Ok(frame) => frame,
+            Err(e) => { return Err(format!("frame {}: {}", i, e)); }
+        };
+
+        // The first frame cannot restore the previous frame, it is cleared instead
+        let dispose = if i == 0 && control.dispose == 2 { 1 } else { control.dispose };
+        let previous = if dispose == 2 { Some(canvas.clone()) } else { None };
+
+        let (fw, x0, y0) = (control.width as usize, control.x as usize, control.y as usize);
+        for y in 0..(control.height as usize) {
+            for x in 0..fw {
+                let src = &frame.data[(y * fw + x) * 4..(y * fw + x) * 4 + 4];
+                let i = ((y0 + y) * (w as usize) + x0 + x) * 4;
+                match control.blend {
+                    0 => canvas.data[i..i+4].copy_from_slice(src),
+                    _ => blend_pixel(&mut canvas.data[i..i+4], src)
+                }
+            }
+        }
+
+        animation.frames.push(AnimationFrame{ image: canvas.clone(), delay: control.delay });
+
+        match (dispose, previous) {
+            (1, _) => clear_rect(&mut canvas, x0, y0, fw, control.height as usize),
+            (2, Some(previous)) => { canvas = previous; },
+            _ => {}
+        }
+    }
+
+    Ok(animation)
+}
+
+fn parse(data: &[u8]) -> Result<Png, String> {
+    if data.len() < 8 || data[0..8] != SIGNATURE[..] {
+        return Err("missing PNG signature".to_string());
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut transparency = Transparency::None;
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut plays: Option<u32> = None;
+    let mut frames: Vec<(FrameControl, Vec<u8>)> = Vec::new();
+    let mut pos = 8;
+    let mut ended = false;
+
+    while pos < data.len() {
+        if pos + 12 > data.len() {
+            return Err(format!("truncated chunk header at offset {}", pos));
+        }
+
+        let length = be_u32(data, pos) as usize;
+        let kind = &data[pos+4..pos+8];
+        let kind_name = String::from_utf8_lossy(kind).into_owned();
+        if length > data.len() - pos - 12 {
+            return Err(format!("chunk {} at offset {} is truncated", kind_name, pos));
+        }
+
+        let body = &data[pos+8..pos+8+length];
+        let crc = be_u32(data, pos + 8 + length);
+        if crc32(&data[pos+4..pos+8+length]) != crc {
+            return Err(format!("CRC mismatch in chunk {}", kind_name));
+        }
+
+        if header.is_none() && kind != b"IHDR" {
+            return Err("the first chunk must be IHDR".to_string());
+        }
+
+        match kind {
+            b"IHDR" => {
+                if header.is_some() { return Err("duplicate IHDR chunk".to_string()); }
+                header = Some(parse_header(body)?);
+            },
+            b"PLTE" => {
+                if length % 3 != 0 || length == 0 || length > 256 * 3 {
+                    return Err(format!("invalid PLTE chunk length {}", length));
+                }
+                palette = body.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+            },
+            b"tRNS" => {
+                transparency = match header.as_ref().map(|h| h.color_type) {
+                    Some(3) => Transparency::Palette(body.to_vec()),
+                    Some(0) if length >= 2 => Transparency::Gray(((body[0] as u16) << 8) | body[1] as u16),
+                    Some(2) if length >= 6 => Transparency::Rgb(
+                        ((body[0] as u16) << 8) | body[1] as u16,
+                        ((body[2] as u16) << 8) | body[3] as u16,
+                        ((body[4] as u16) << 8) | body[5] as u16
+                    ),
+                    _ => { return Err("invalid tRNS chunk".to_string()); }
+                };
+            },
+            b"IDAT" => { compressed.extend_from_slice(body); },
+            b"acTL" => {
+                if length != 8 { return Err("invalid acTL chunk".to_string()); }
+                plays = Some(be_u32(body, 4));
+            },
+            b"fcTL" => {
+                if length != 26 { return Err("invalid fcTL chunk".to_string()); }
+                let control = parse_frame_control(body)?;
+                frames.push((control, Vec::new()));
+            },
+            b"fdAT" => {
+                if length < 4 { return Err("invalid fdAT chunk".to_string()); }
+                match frames.last_mut() {
+                    Some(&mut (_, ref mut data)) => data.extend_from_slice(&body[4..]),
+                    None => { return Err("fdAT chunk without a frame control".to_string()); }
+                }
+            },
+            b"IEND" => { ended = true; break; },
+            _ => {
+                // Chunks starting with an uppercase letter are critical
+                if kind[0] & 0x20 == 0 {
+                    return Err(format!("unknown critical chunk {}", kind_name));
+                }
+            }
+        }
+
+        pos += 12 + length;
+    }
+
+    let header = match header {
+        Some(h) => h,
+        None => { return Err("missing IHDR chunk".to_string()); }
+    };
+
+    if !ended { return Err("missing IEND chunk".to_string()); }
+    if compressed.is_empty() { return Err("missing IDAT chunk".to_string()); }
+    if header.color_type == 3 && palette.is_empty() { return Err("missing PLTE chunk".to_string()); }
+
+    Ok(Png{ header: header, palette: palette, transparency: transparency, default_image: compressed, plays: plays, frames: frames })
+}
+
+fn parse_frame_control(body: &[u8]) -> Result<FrameControl, String> {
+    let (num, den) = (be_u16(body, 20) as u32, be_u16(body, 22) as u32);
+    let den = if den == 0 { 100 } else { den };
+
+    let control = FrameControl {
+        width: be_u32(body, 4),
+        height: be_u32(body, 8),
+        x: be_u32(body, 12),
+        y: be_u32(body, 16),
+        delay: num * 1000 / den,
+        dispose: body[24],
+        blend: body[25]
+    };
+
+    check_dimensions(control.width, control.height)?;
+    if control.dispose > 2 { return Err(format!("unknown dispose operation {}", control.dispose)); }
+    if control.blend > 1 { return Err(format!("unknown blend operation {}", control.blend)); }
+
+    Ok(control)
+}
+
+/**
+    Decode the compressed pixels of an image of `width` x `height` pixels that uses the format of the PNG header
+*/
+fn decode_pixels(png: &Png, width: u32, height: u32, compressed: &[u8]) -> Result<ImageData, String> {
+    let header = &png.header;
+    let (w, h) = (width as usize, height as usize);
+    let bpp = header.bits_per_pixel();
+    let filter_bpp = ::std::cmp::max(1, bpp / 8);
+
+    let passes: Vec<(usize, usize, usize, usize)> = if header.interlaced { ADAM7.to_vec() } else { vec![(0, 0, 1, 1)] };
+
+    // Every row of every pass starts with its filter byte
+    let max_size: usize = passes.iter()
+        .filter(|&&(x0, y0, _, _)| x0 < w && y0 < h)
+        .map(|&(x0, y0, dx, dy)| ((h - y0 + dy - 1) / dy) * (1 + ((w - x0 + dx - 1) / dx * bpp + 7) / 8))
+        .sum();
+
+    let raw = match inflate::zlib_decompress(compressed, max_size) {
+        Ok(raw) => raw,
+        Err(e) => { return Err(format!("invalid image data: {}", e)); }
+    };
+
+    let mut image = ImageData::new(width, height);
+    let mut offset = 0;
+
+    for &(x0, y0, dx, dy) in passes.iter() {
+        if x0 >= w || y0 >= h { continue; }
+
+        let pass_w = (w - x0 + dx - 1) / dx;
+        let pass_h = (h - y0 + dy - 1) / dy;
+        let row_len = (pass_w * bpp + 7) / 8;
+
+        let mut previous = vec![0u8; row_len];
+        let mut row = vec![0u8; row_len];
+
+        for py in 0..pass_h {
+            if offset + 1 + row_len > raw.len() {
+                return Err("the image data is truncated".to_string());
+            }
+
+            let filter = raw[offset];
+            row.copy_from_slice(&raw[offset+1..offset+1+row_len]);
+            offset += 1 + row_len;
+
+            unfilter(filter, &mut row, &previous, filter_bpp)?;
+
+            let y = y0 + py * dy;
+            for px in 0..pass_w {
+                let x = x0 + px * dx;
+                let (r, g, b, a) = read_pixel(header, &row, px, &png.palette, &png.transparency)?;
+                let i = (y * w + x) * 4;
+                put_pixel(&mut image.data[i..i+4], r, g, b, a);
+            }
+
+            ::std::mem::swap(&mut row, &mut previous);
+        }
+    }
+
+    Ok(image)
+}
+
+fn parse_header(body: &[u8]) -> Result<Header, String> {
+    if body.len() != 13 {
+        return Err(format!("invalid IHDR chunk length {}", body.len()));
+    }
+
+    let header = Header {
+        width: be_u32(body, 0),
+        height: be_u32(body, 4),
+        bit_depth: body[8],
+        color_type: body[9],
+        interlaced: body[12] == 1
+    };
+
+    check_dimensions(header.width, header.height)?;
+
+    let valid_depth = match header.color_type {
+        0 => [1, 2, 4, 8, 16].contains(&header.bit_depth),
+        3 => [1, 2, 4, 8].contains(&header.bit_depth),
+        2 | 4 | 6 => header.bit_depth == 8 || header.bit_depth == 16,
+        t => { return Err(format!("invalid color type {}", t)); }
+    };
+
+    if !valid_depth {
+        return Err(format!("invalid bit depth {} for color type {}", header.bit_depth, header.color_type));
+    }
+
+    if body[10] != 0 { return Err(format!("unknown compression method {}", body[10])); }
+    if body[11] != 0 { return Err(format!("unknown filter method {}", body[11])); }
+    if body[12] > 1 { return Err(format!("unknown interlace method {}", body[12])); }
+
+    Ok(header)
+}
+
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], bpp: usize) -> Result<(), String> {
+    let len = row.len();
+    match filter {
+        0 => {},
+        1 => for i in bpp..len { row[i] = row[i].wrapping_add(row[i - bpp]); },
+        2 => for i in 0..len { row[i] = row[i].wrapping_add(previous[i]); },
+        3 => for i in 0..len {
+            let left = if i >= bpp { row[i - bpp] as u16 } else { 0 };
+            row[i] = row[i].wrapping_add(((left + previous[i] as u16) / 2) as u8);
+        },
+        4 => for i in 0..len {
+            let (a, b, c) = if i >= bpp {
+                (row[i - bpp] as i16, previous[i] as i16, previous[i - bpp] as i16)
+            } else {
+                (0, previous[i] as i16, 0)
+            };
+
+            let p = a + b - c;
+            let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+            let predictor = if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c };
+            row[i] = row[i].wrapping_add(predictor as u8);
+        },
+        f => { return Err(format!("unknown filter type {}", f)); }
+    }
+
+    Ok(())
+}
+
+/**
+    Read a sample of `depth` bits in a row
+*/
+#[inline(always)]
+fn sample(row: &[u8], index: usize, depth: u8) -> u16 {
+    match depth {
+        8 => row[index] as u16,
+        16 => ((row[index * 2] as u16) << 8) | (row[index * 2 + 1] as u16),
+        d => {
+            let bit = index * (d as usize);
+            let shift = 8 - (d as usize) - (bit % 8);
+            ((row[bit / 8] >> shift) as u16) & ((1 << d) - 1)
+        }
+    }
+}
+
+/**
+    Scale a sample to 8 bits
+*/
+#[inline(always)]
+fn to_8bits(value: u16, depth: u8) -> u8 {
+    match depth {
+        16 => (value >> 8) as u8,
+        8 => value as u8,
+        d => ((value as u32 * 255) / ((1u32 << d) - 1)) as u8
+    }
+}
+
+fn read_pixel(h: &Header, row: &[u8], x: usize, palette: &[[u8; 3]], trns: &Transparency) -> Result<(u8, u8, u8, u8), String> {
+    let d = h.bit_depth;
+    match h.color_type {
+        0 => {
+            let v = sample(row, x, d);
+            let a = match trns { &Transparency::Gray(k) if k == v => 0, _ => 255 };
+            let v = to_8bits(v, d);
+            Ok((v, v, v, a))
+        },
+        2 => {
+            let (r, g, b) = (sample(row, x * 3, d), sample(row, x * 3 + 1, d), sample(row, x * 3 + 2, d));
+            let a = match trns { &Transparency::Rgb(kr, kg, kb) if (kr, kg, kb) == (r, g, b) => 0, _ => 255 };
+            Ok((to_8bits(r, d), to_8bits(g, d), to_8bits(b, d), a))
+        },
+        3 => {
+            let index = sample(row, x, d) as usize;
+            match palette.get(index) {
+                Some(c) => {
+                    let a = match trns { &Transparency::Palette(ref alpha) => alpha.get(index).cloned().unwrap_or(255), _ => 255 };
+                    Ok((c[0], c[1], c[2], a))
+                },
+                None => Err(format!("palette index {} out of range", index))
+            }
+        },
+        4 => {
+            let v = to_8bits(sample(row, x * 2, d), d);
+            Ok((v, v, v, to_8bits(sample(row, x * 2 + 1, d), d)))
+        },
+        _ => {
+            let i = x * 4;
+            Ok((to_8bits(sample(row, i, d), d), to_8bits(sample(row, i + 1, d), d), to_8bits(sample(row, i + 2, d), d), to_8bits(sample(row, i + 3, d), d)))
+        }
+    }
+}
+
+/**
+    Encode an image as a PNG file. Images without transparency are saved without an alpha channel.
+*/
+pub fn encode(image: &ImageData) -> Vec<u8> {
+    let (w, h) = (image.width as usize, image.height as usize);
+    let opaque = image.data.chunks(4).all(|p| p[3] == 255);
+    let channels = if opaque { 3 } else { 4 };
+    let row_len = w * channels;
+
+    let mut raw: Vec<u8> = Vec::with_capacity((row_len + 1) * h);
+    let mut previous = vec![0u8; row_len];
+    let mut row = vec![0u8; row_len];
+    let mut filtered = vec![0u8; row_len];
+    let mut best = vec![0u8; row_len];
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = (y * w + x) * 4;
+            let (r, g, b, a) = get_pixel(&image.data[i..i+4]);
+            let o = x * channels;
+            row[o] = r; row[o + 1] = g; row[o + 2] = b;
+            if !opaque { row[o + 3] = a; }
+        }
+
+        // Keep the filter that gives the smallest sum of absolute differences
+        let mut best_filter = 0;
+        let mut best_score = u64::max_value();
+        for filter in 0..5u8 {
+            apply_filter(filter, &row, &previous, channels, &mut filtered);
+            let score: u64 = filtered.iter().map(|&v| (v as i8 as i64).abs() as u64).sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                best.copy_from_slice(&filtered);
+            }
+        }
+
+        raw.push(best_filter);
+        raw.extend_from_slice(&best);
+        ::std::mem::swap(&mut row, &mut previous);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&be_bytes(image.width));
+    header.extend_from_slice(&be_bytes(image.height));
+    header.extend_from_slice(&[8, if opaque { 2 } else { 6 }, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &deflate::zlib_compress(&raw));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn apply_filter(filter: u8, row: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = previous[i];
+        let c = if i >= bpp { previous[i - bpp] } else { 0 };
+
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => {
+                let p = a as i16 + b as i16 - c as i16;
+                let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+                if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+            }
+        };
+
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(&be_bytes(body.len() as u32));
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&be_bytes(crc));
+}
+
+#[inline(always)]
+fn be_bytes(v: u32) -> [u8; 4] {
+    [(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+}
+
+/**
+    Compute the CRC-32 of some data (the checksum used by the PNG chunks)
+*/
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data.iter() {
+        crc = table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+
+    !crc
+}
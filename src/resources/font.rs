@@ -16,7 +16,8 @@ use defs::{FONT_DECO_ITALIC, FONT_DECO_UNDERLINE, FONT_DECO_STRIKEOUT};
     A template that can create a font resource
 
     Params:  
-    • `size`: The height, in logical units, of the font's character cell or character. 0 means default height.  
+    • `size`: The height, in logical units, of the font's character cell or character. 0 means default height.
+      If the process is DPI aware (see `dpi::enable_awareness`), the size is scaled to the system DPI.  
     • `weight`: The weight of the font in the range 0 through 1000. For example, 400 is normal and 700 is bold. See the FONT_WEIGHT_* constants for convenience  
     • `decoration`: Extra style for the font. A bitwise combination of the FONT_DECO_* constants. Ex: FONT_DECO_ITALIC | FONT_DECO_UNDERLINE | FONT_DECO_STRIKEOUT  
 */
@@ -43,8 +44,13 @@ impl<ID: Clone+Hash, S: Clone+Into<String>> ResourceT<ID> for FontT<S> {
 
         let family_name = to_utf16(self.family.clone().into().as_ref());
 
+        let size = match ::low::dpi_helper::dpi_aware() {
+            true => ::dpi::to_physical(self.size, unsafe{ ::low::dpi_helper::system_dpi() }),
+            false => self.size
+        };
+
         let handle = unsafe{ CreateFontW(
-            self.size as c_int,       // nHeight
+            size as c_int,            // nHeight
             0, 0, 0,                  // nWidth, nEscapement, nOrientation
             self.weight,              // fnWeight
             use_italic,               // fdwItalic
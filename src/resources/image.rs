@@ -4,7 +4,7 @@
 use std::any::TypeId;
 use std::hash::Hash;
 use std::ptr;
-use std::mem;
+use std::io::Write;
 
 use winapi::{HANDLE, HBITMAP, c_int};
 
@@ -14,6 +14,7 @@ use resources::{ResourceT, Resource};
 use error::{Error, SystemError};
 use defs::{ImageType, OemImage};
 use low::other_helper::to_utf16;
+use low::image_helper::{bitmap_from_data, icon_from_data};
 
 /**
     A template that creates a image resource from a global system resource.
@@ -66,7 +67,8 @@ impl<ID: Clone+Hash> ResourceT<ID> for OemImageT {
 }
 
 /**
-    A template that creates a image resource
+    A template that creates a image resource. PNG, JPEG and GIF files are decoded into a 32 bits image
+    of the requested type, the other files are loaded by the system.
 
     Params:  
     • `source`: The path to the image resource  
@@ -93,7 +95,26 @@ impl<ID: Clone+Hash, S: Clone+Into<String>> ResourceT<ID> for ImageT<S> {
         use low::defs::OIC_HAND;
         use low::other_helper::get_system_error;
 
-        let filepath = to_utf16(self.source.clone().into().as_ref());
+        let path: String = self.source.clone().into();
+
+        // PNG, JPEG and GIF files cannot be loaded by the system, they are decoded instead
+        if let Some(data) = read_decodable_file(&path) {
+            let mut image = ImageData::decode(&data)?;
+            if self.size != (0, 0) {
+                let (w, h) = self.size;
+                image = image.resize(w as u32, h as u32);
+            }
+
+            let handle = match self.image_type {
+                ImageType::Bitmap => unsafe{ bitmap_from_data(&image)? as HANDLE },
+                ImageType::Icon => unsafe{ icon_from_data(&image, false)? },
+                ImageType::Cursor => unsafe{ icon_from_data(&image, true)? }
+            };
+
+            return Ok( Box::new( Image{ handle: handle, image_type: self.image_type.clone() } ) );
+        }
+
+        let filepath = to_utf16(&path);
         let (width, height) = self.size;
         let mut res_type = self.image_type.clone();
         let c_res_type = match self.image_type {
@@ -123,9 +144,11 @@ impl<ID: Clone+Hash, S: Clone+Into<String>> ResourceT<ID> for ImageT<S> {
 }
 
 /**
-    A template a create a image from a bitmap file that was loaded in memory.
-    Both the bitmap file header and the bitmap information must be present in
-    the source array
+    A template a create a image from an image file that was loaded in memory.
+    
+    Bitmap files must include both the bitmap file header and the bitmap information. Every bitmap variant
+    is decoded into a 32 bits bitmap (palettes, RLE compression, bitfields, alpha channel, top-down images).
+    PNG, JPEG and GIF files are decoded into a 32 bits bitmap. ICO files are decoded into an icon using their largest image.
 */
 #[derive(Clone)]
 pub struct MemoryImageT {
@@ -138,9 +161,18 @@ impl<ID: Clone+Hash> ResourceT<ID> for MemoryImageT {
 
     #[allow(unused_variables)]
     fn build(&self, ui: &Ui<ID>) -> Result<Box<Resource>, Error> {
-        match unsafe{ create_dib(self) } {
-            Ok(h) => Ok( Box::new( Image{ handle: h as HANDLE, image_type: ImageType::Bitmap } ) ),
-            Err(e) => Err(e)
+        match ImageFormat::detect(&self.source) {
+            None => Err(Error::BadResource("Invalid source. The image format is unknown.".to_string())),
+            Some(ImageFormat::Ico) => {
+                let image = ImageData::decode(&self.source)?;
+                let handle = unsafe{ icon_from_data(&image, false)? };
+                Ok( Box::new( Image{ handle: handle, image_type: ImageType::Icon } ) )
+            },
+            Some(_) => {
+                let image = ImageData::decode(&self.source)?;
+                let handle = unsafe{ bitmap_from_data(&image)? };
+                Ok( Box::new( Image{ handle: handle as HANDLE, image_type: ImageType::Bitmap } ) )
+            }
         }
     }
 }
@@ -154,9 +186,46 @@ pub struct Image {
 }
 
 impl Image {
+    /**
+        Wrap a bitmap created outside of a template (ex: by `capture`). The bitmap is owned by the image.
+    */
+    pub(crate) fn from_bitmap(handle: HBITMAP) -> Image {
+        Image{ handle: handle as HANDLE, image_type: ImageType::Bitmap }
+    }
+
     pub fn resource_type(&self) -> ImageType {
         self.image_type.clone()
     }
+
+    /**
+        Read the pixels of the image. The pixels of icons and cursors hidden by their mask are transparent.
+    */
+    pub fn to_data(&self) -> Result<ImageData, Error> {
+        use winapi::HICON;
+        use low::image_helper::{read_bitmap, read_icon};
+
+        unsafe {
+            match self.image_type {
+                ImageType::Bitmap => read_bitmap(self.handle as HBITMAP).map(|(image, _)| image),
+                ImageType::Icon | ImageType::Cursor => read_icon(self.handle as HICON)
+            }
+        }
+    }
+
+    /**
+        Return the pixels of the image as straight alpha RGBA values, top-down, 4 bytes per pixel.
+        The size of the image can be read with `to_data`.
+    */
+    pub fn to_rgba(&self) -> Result<Vec<u8>, Error> {
+        self.to_data().map(|image| image.to_rgba())
+    }
+
+    /**
+        Encode the image in `format` (BMP or PNG) and write it to `out`
+    */
+    pub fn encode<W: Write>(&self, format: ImageFormat, out: &mut W) -> Result<(), Error> {
+        self.to_data().and_then(|image| image.encode(format, out))
+    }
 }
 
 impl Resource for Image {
@@ -175,6 +244,8 @@ impl Resource for Image {
         use user32::{DestroyCursor, DestroyIcon};
         use std::mem;
 
+        if self.handle.is_null() { return; }
+
         unsafe{
             match self.image_type {
                 ImageType::Bitmap => DeleteObject(mem::transmute(self.handle)),
@@ -182,63 +253,483 @@ impl Resource for Image {
                 ImageType::Icon => DestroyIcon(mem::transmute(self.handle))
             };
         }
+
+        self.handle = ptr::null_mut();
+    }
+}
+
+/// Images that are not stored in a Ui (ex: the result of `capture`) free their handle when dropped
+impl Drop for Image {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+
+/**
+    The image file formats that can be decoded by `ImageData`
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+    Jpeg,
+    Gif,
+    Ico
+}
+
+impl ImageFormat {
+
+    /**
+        Guess the format of an image file from its first bytes. Return `None` if the format is unknown.
+    */
+    pub fn detect(data: &[u8]) -> Option<ImageFormat> {
+        use resources::codecs::png::SIGNATURE;
+
+        if data.len() >= 8 && data[0..8] == SIGNATURE[..] {
+            Some(ImageFormat::Png)
+        } else if data.len() >= 3 && data[0..3] == [0xFF, 0xD8, 0xFF] {
+            Some(ImageFormat::Jpeg)
+        } else if data.len() >= 6 && (&data[0..6] == b"GIF87a" || &data[0..6] == b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if data.len() >= 6 && data[0..4] == [0, 0, 1, 0] || data.len() >= 6 && data[0..4] == [0, 0, 2, 0] {
+            if data[4] != 0 || data[5] != 0 { Some(ImageFormat::Ico) } else { None }
+        } else if data.len() >= 2 && &data[0..2] == b"BM" {
+            Some(ImageFormat::Bmp)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            &ImageFormat::Bmp => "BMP",
+            &ImageFormat::Png => "PNG",
+            &ImageFormat::Jpeg => "JPEG",
+            &ImageFormat::Gif => "GIF",
+            &ImageFormat::Ico => "ICO"
+        }
     }
+
+}
+
+/**
+    Decoded image pixels. The decoders do not need a display, so an `ImageData` can be created and inspected anywhere.
+
+    Members:  
+    • `width`: The width of the image in pixels  
+    • `height`: The height of the image in pixels  
+    • `data`: The pixels, top-down, as 4 bytes premultiplied alpha BGRA values (the layout of a 32 bits DIB)  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>
 }
 
+impl ImageData {
+
+    /**
+        Create a transparent image
+    */
+    pub fn new(width: u32, height: u32) -> ImageData {
+        ImageData{ width: width, height: height, data: vec![0; (width as usize) * (height as usize) * 4] }
+    }
+
+    /**
+        Create an image from straight alpha RGBA pixels
+    */
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Result<ImageData, Error> {
+        use resources::codecs::put_pixel;
+
+        let expected = (width as usize) * (height as usize) * 4;
+        if rgba.len() != expected {
+            let msg = format!("The RGBA data size ({} bytes) does not match the image size {}x{} ({} bytes)", rgba.len(), width, height, expected);
+            return Err(Error::BadResource(msg));
+        }
+
+        let mut image = ImageData::new(width, height);
+        for (src, dst) in rgba.chunks(4).zip(image.data.chunks_mut(4)) {
+            put_pixel(dst, src[0], src[1], src[2], src[3]);
+        }
+
+        Ok(image)
+    }
+
+    /**
+        Return the premultiplied (red, green, blue, alpha) value of a pixel, or `None` if the position is outside of the image
+    */
+    pub fn pixel(&self, x: u32, y: u32) -> Option<(u8, u8, u8, u8)> {
+        if x >= self.width || y >= self.height { return None; }
+        let i = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+        Some((self.data[i+2], self.data[i+1], self.data[i], self.data[i+3]))
+    }
+
+    /**
+        Return a copy of the image scaled to a new size. When the image is reduced, it is first halved with
+        a box filter until it is less than twice the new size, so that every pixel contributes to the result.
+        The result is then sampled with a bilinear filter.
+    */
+    pub fn resize(&self, width: u32, height: u32) -> ImageData {
+        let mut out = ImageData::new(width, height);
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 { return out; }
+
+        let mut reduced: Option<ImageData> = None;
+        loop {
+            let next = {
+                let source = reduced.as_ref().unwrap_or(self);
+                let horizontal = source.width >= width * 2;
+                let vertical = source.height >= height * 2;
+                if !horizontal && !vertical { break; }
+                source.half(horizontal, vertical)
+            };
+            reduced = Some(next);
+        }
+
+        let source = reduced.as_ref().unwrap_or(self);
+        let step = (source.width as f64 / width as f64, source.height as f64 / height as f64);
+        source.sample_into(&mut out, (0.0, 0.0), step);
+
+        out
+    }
+
+    /**
+        Return a copy of the image with half the width and/or half the height. Each pixel is the average of
+        the pixels it replaces. Used to build the reduced images of a mipmap chain.
+    */
+    pub(crate) fn half(&self, horizontal: bool, vertical: bool) -> ImageData {
+        let (sw, sh) = (self.width as usize, self.height as usize);
+        let width = if horizontal { (self.width + 1) / 2 } else { self.width };
+        let height = if vertical { (self.height + 1) / 2 } else { self.height };
+        let mut out = ImageData::new(width, height);
+
+        let (fx, fy) = (if horizontal { 2 } else { 1 }, if vertical { 2 } else { 1 });
+        for y in 0..(height as usize) {
+            let (y0, y1) = (y * fy, ::std::cmp::min(y * fy + fy - 1, sh - 1));
+            for x in 0..(width as usize) {
+                let (x0, x1) = (x * fx, ::std::cmp::min(x * fx + fx - 1, sw - 1));
+                let o = (y * (width as usize) + x) * 4;
+                for c in 0..4 {
+                    let p = |px: usize, py: usize| self.data[(py * sw + px) * 4 + c] as u32;
+                    let sum = p(x0, y0) + p(x1, y0) + p(x0, y1) + p(x1, y1);
+                    out.data[o + c] = ((sum + 2) / 4) as u8;
+                }
+            }
+        }
+
+        out
+    }
+
+    /**
+        Fill `out` with a bilinear sampling of the image. The center of the pixel `(x, y)` of `out` samples the
+        position `origin + (x + 0.5, y + 0.5) * step` of the image. The pixels that sample a position outside
+        of the image are left untouched.
+    */
+    pub(crate) fn sample_into(&self, out: &mut ImageData, origin: (f64, f64), step: (f64, f64)) {
+        let (sw, sh) = (self.width as usize, self.height as usize);
+        if sw == 0 || sh == 0 { return; }
+
+        // The two source pixels and the weight of the second one for each column or row of `out`
+        let axis = |count: u32, origin: f64, step: f64, size: usize| -> Vec<Option<(usize, usize, f32)>> {
+            (0..count).map(|i| {
+                let pos = origin + (i as f64 + 0.5) * step;
+                if pos < 0.0 || pos >= size as f64 { return None; }
+
+                let pos = (pos - 0.5).max(0.0);
+                let p0 = ::std::cmp::min(pos as usize, size - 1);
+                let p1 = ::std::cmp::min(p0 + 1, size - 1);
+                Some((p0, p1, (pos - p0 as f64).min(1.0) as f32))
+            }).collect()
+        };
+
+        let columns = axis(out.width, origin.0, step.0, sw);
+        let rows = axis(out.height, origin.1, step.1, sh);
+        let width = out.width as usize;
+
+        for (y, row) in rows.iter().enumerate() {
+            let (y0, y1, ty) = match row { &Some(r) => r, &None => { continue; } };
+            for (x, column) in columns.iter().enumerate() {
+                let (x0, x1, tx) = match column { &Some(c) => c, &None => { continue; } };
+                let o = (y * width + x) * 4;
+                for c in 0..4 {
+                    let p = |px: usize, py: usize| self.data[(py * sw + px) * 4 + c] as f32;
+                    let top = p(x0, y0) * (1.0 - tx) + p(x1, y0) * tx;
+                    let bottom = p(x0, y1) * (1.0 - tx) + p(x1, y1) * tx;
+                    out.data[o + c] = (top * (1.0 - ty) + bottom * ty + 0.5) as u8;
+                }
+            }
+        }
+    }
+
+    /**
+        Return the pixels of the image as straight alpha RGBA values, top-down, 4 bytes per pixel
+    */
+    pub fn to_rgba(&self) -> Vec<u8> {
+        use resources::codecs::get_pixel;
+
+        let mut rgba = Vec::with_capacity(self.data.len());
+        for p in self.data.chunks(4) {
+            let (r, g, b, a) = get_pixel(p);
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+
+        rgba
+    }
+
+    /**
+        Encode the image and write it to `out`. Only the BMP and the PNG formats can be encoded.
+        Returns `Error::BadResource` if the format cannot be encoded or if the data could not be written.
+    */
+    pub fn encode<W: Write>(&self, format: ImageFormat, out: &mut W) -> Result<(), Error> {
+        use resources::codecs::{bmp, png};
+
+        if self.data.len() != (self.width as usize) * (self.height as usize) * 4 {
+            let msg = format!("The image data size ({} bytes) does not match the image size {}x{}", self.data.len(), self.width, self.height);
+            return Err(Error::BadResource(msg));
+        }
+
+        let bytes = match format {
+            ImageFormat::Bmp => bmp::encode(self),
+            ImageFormat::Png => png::encode(self),
+            f => { return Err(Error::BadResource(format!("Images cannot be encoded in the {} format", f.name()))); }
+        };
+
+        match out.write_all(&bytes) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(Error::BadResource(format!("Could not write the {} image: {}", format.name(), e)))
+        }
+    }
+
+    /**
+        Compare the image with the `expected` image. Two pixels are different if one of their channels differs by more than `tolerance`.
+        If the sizes of the images do not match, the pixels that are outside of one of the images are different, whatever the tolerance.
+    */
+    pub fn compare(&self, expected: &ImageData, tolerance: u8) -> ImageDiff {
+        use std::cmp::max;
+
+        let (width, height) = (max(self.width, expected.width), max(self.height, expected.height));
+        let mut diff = ImageDiff{ different_pixels: 0, max_difference: 0, diff: ImageData::new(width, height) };
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = ((y as usize) * (width as usize) + (x as usize)) * 4;
+                let out = &mut diff.diff.data[i..i+4];
+
+                // Pixels outside of one of the images are always different
+                let (difference, different) = match (self.pixel_data(x, y), expected.pixel_data(x, y)) {
+                    (Some(a), Some(b)) => {
+                        let d = a.iter().zip(b.iter()).map(|(&a, &b)| (a as i32 - b as i32).abs() as u8).max().unwrap_or(0);
+                        (d, d > tolerance)
+                    },
+                    _ => (255, true)
+                };
+
+                diff.max_difference = max(diff.max_difference, difference);
+
+                if different {
+                    diff.different_pixels += 1;
+                    out.copy_from_slice(&[0, 0, 255, 255]);
+                } else {
+                    // Faded grayscale copy of the expected image
+                    let (r, g, b, _) = expected.pixel(x, y).unwrap_or((0, 0, 0, 0));
+                    let gray = ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8;
+                    let faded = 192 + gray / 4;
+                    out.copy_from_slice(&[faded, faded, faded, 255]);
+                }
+            }
+        }
+
+        diff
+    }
+
+    fn pixel_data(&self, x: u32, y: u32) -> Option<&[u8]> {
+        if x >= self.width || y >= self.height { return None; }
+        let i = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+        self.data.get(i..i+4)
+    }
+
+    /**
+        Decode an image file of any supported format. For ICO files, the largest image is returned.
+    */
+    pub fn decode(data: &[u8]) -> Result<ImageData, Error> {
+        match ImageFormat::detect(data) {
+            Some(ImageFormat::Bmp) => ImageData::decode_bmp(data),
+            Some(ImageFormat::Png) => ImageData::decode_png(data),
+            Some(ImageFormat::Jpeg) => ImageData::decode_jpeg(data),
+            Some(ImageFormat::Gif) => ImageData::decode_gif(data),
+            Some(ImageFormat::Ico) => {
+                let images = ImageData::decode_ico(data)?;
+                Ok(images.into_iter().max_by_key(|i| (i.width as u64) * (i.height as u64)).unwrap())
+            },
+            None => Err(Error::BadResource("Unknown image format".to_string()))
+        }
+    }
+
+    /**
+        Decode a BMP file
+    */
+    pub fn decode_bmp(data: &[u8]) -> Result<ImageData, Error> {
+        codec_result(ImageFormat::Bmp, ::resources::codecs::bmp::decode(data))
+    }
+
+    /**
+        Decode a PNG file
+    */
+    pub fn decode_png(data: &[u8]) -> Result<ImageData, Error> {
+        codec_result(ImageFormat::Png, ::resources::codecs::png::decode(data))
+    }
+
+    /**
+        Decode a baseline or progressive JPEG file
+    */
+    pub fn decode_jpeg(data: &[u8]) -> Result<ImageData, Error> {
+        codec_result(ImageFormat::Jpeg, ::resources::codecs::jpeg::decode(data))
+    }
+
+    /**
+        Decode the first frame of a GIF file
+    */
+    pub fn decode_gif(data: &[u8]) -> Result<ImageData, Error> {
+        codec_result(ImageFormat::Gif, ::resources::codecs::gif::decode(data))
+    }
+
+    /**
+        Decode every image of an ICO or CUR file
+    */
+    pub fn decode_ico(data: &[u8]) -> Result<Vec<ImageData>, Error> {
+        codec_result(ImageFormat::Ico, ::resources::codecs::ico::decode(data))
+    }
+
+}
 
 // Private functions
 
-unsafe fn create_dib(i: &MemoryImageT) -> Result<HBITMAP, Error> {
-    use winapi::{BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, BI_RGB, LONG, DWORD};
-    use gdi32::{CreateCompatibleBitmap, CreateCompatibleDC, SetDIBits};
-    use user32::{GetDC, ReleaseDC};
-    use low::defs::BITMAPFILEHEADER;
-    use std::os::raw::c_void;
-
-    // Check the header size requirement
-    let fheader_size = mem::size_of::<BITMAPFILEHEADER>();
-    let iheader_size = mem::size_of::<BITMAPINFOHEADER>();
-    let header_size = fheader_size + iheader_size;
-    if i.source.len() < header_size {
-        let msg = format!("Invalid source. The source size ({} bytes) is smaller than the required headers size ({} bytes).", i.source.len(), header_size);
-        return Err(Error::BadResource(msg));
-    }
-
-    // Read the bitmap file header
-    let src: *const u8 = i.source.as_ptr();
-    let fheader_ptr: *const BITMAPFILEHEADER = mem::transmute(src);
-    let fheader: BITMAPFILEHEADER = ptr::read( fheader_ptr );
-
-    // Read the bitmap info header
-    let iheader_ptr: *const BITMAPINFOHEADER = mem::transmute(src.offset(fheader_size as isize));
-    let iheader: BITMAPINFOHEADER = ptr::read( iheader_ptr );
-
-    let (w, h) = (iheader.biWidth, iheader.biHeight);
-
-    let screen_dc = GetDC(ptr::null_mut());
-    let hdc = CreateCompatibleDC(screen_dc);
-    let bitmap = CreateCompatibleBitmap(screen_dc, w, h);
-    ReleaseDC(ptr::null_mut(), screen_dc);
-
-    let header = BITMAPINFOHEADER {
-        biSize: mem::size_of::<BITMAPINFOHEADER>() as DWORD,
-        biWidth: w as LONG, biHeight: h as LONG, 
-        biPlanes: 1, biBitCount: 24, biCompression: BI_RGB,
-        biSizeImage: (w * h * 3) as u32,
-        biXPelsPerMeter: 0, biYPelsPerMeter: 0,
-        biClrUsed: 0, biClrImportant: 0
-    };
-
-    let info = BITMAPINFO {
-        bmiHeader: header,
-        bmiColors: [],
-    };
-
-    let data_ptr = i.source.as_ptr().offset(fheader.bfOffBits as isize) as *const c_void;
-    if 0 == SetDIBits(hdc, bitmap, 0, h as u32, data_ptr, &info, DIB_RGB_COLORS) {
-        let msg = "SetDIBits failed.".to_string();
-        return Err(Error::BadResource(msg));
-    }
-
-    return Ok(bitmap);
+fn codec_result<T>(format: ImageFormat, result: Result<T, String>) -> Result<T, Error> {
+    result.map_err(|e| Error::BadResource(format!("Invalid {} image: {}", format.name(), e)))
+}
+
+/**
+    A frame of an animation
+
+    Members:  
+    • `image`: The pixels of the frame, already composited with the previous frames  
+    • `delay`: How long the frame is displayed, in milliseconds  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationFrame {
+    pub image: ImageData,
+    pub delay: u32
+}
+
+/**
+    The decoded frames of an animated image. Can be created from a GIF or an APNG file or built frame by frame (ex: for a spinner).
+
+    Members:  
+    • `frames`: The frames of the animation. Every frame should have the same size  
+    • `loop_count`: The number of times the animation is played. `0` plays the animation forever  
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationData {
+    pub frames: Vec<AnimationFrame>,
+    pub loop_count: u32
+}
+
+impl AnimationData {
+
+    /**
+        Decode an animated GIF or PNG file. The other image formats return an animation with a single frame.
+    */
+    pub fn decode(data: &[u8]) -> Result<AnimationData, Error> {
+        use resources::codecs::{gif, png};
+
+        match ImageFormat::detect(data) {
+            Some(ImageFormat::Gif) => codec_result(ImageFormat::Gif, gif::decode_animation(data)),
+            Some(ImageFormat::Png) => codec_result(ImageFormat::Png, png::decode_animation(data)),
+            _ => ImageData::decode(data).map(|image| AnimationData{ frames: vec![AnimationFrame{ image: image, delay: 0 }], loop_count: 1 })
+        }
+    }
+
+    /**
+        Return the size of the animation (the size of its first frame)
+    */
+    pub fn size(&self) -> (u32, u32) {
+        self.frames.first().map(|f| (f.image.width, f.image.height)).unwrap_or((0, 0))
+    }
+
+    /**
+        Return the time needed to play the frames once, in milliseconds
+    */
+    pub fn duration(&self) -> u64 {
+        self.frames.iter().map(|f| f.delay as u64).sum()
+    }
+
+    /**
+        Return the index of the frame displayed `elapsed` milliseconds after the animation started, taking the loops
+        into account. Once the animation is over, the last frame is returned.
+    */
+    pub fn frame_at(&self, elapsed: u64) -> usize {
+        let duration = self.duration();
+        let last = self.frames.len().saturating_sub(1);
+
+        if duration == 0 || (self.loop_count != 0 && elapsed >= duration * (self.loop_count as u64)) {
+            return if duration == 0 { 0 } else { last };
+        }
+
+        let mut time = elapsed % duration;
+        for (i, frame) in self.frames.iter().enumerate() {
+            if time < frame.delay as u64 { return i; }
+            time -= frame.delay as u64;
+        }
+
+        last
+    }
+
+}
+
+/**
+    The result of `ImageData::compare`
+
+    Members:  
+    • `different_pixels`: The number of pixels that differ by more than the tolerance  
+    • `max_difference`: The largest difference between two channels of the compared images  
+    • `diff`: An image that shows the differences. Different pixels are red, the other pixels are a faded copy of the expected image  
+*/
+#[derive(Clone, Debug)]
+pub struct ImageDiff {
+    pub different_pixels: usize,
+    pub max_difference: u8,
+    pub diff: ImageData
+}
+
+impl ImageDiff {
+
+    /**
+        Return `true` if no pixel differs by more than the tolerance
+    */
+    pub fn matches(&self) -> bool {
+        self.different_pixels == 0
+    }
+
+}
+
+/**
+    Read a file if it is an image that must be decoded (PNG, JPEG or GIF)
+*/
+fn read_decodable_file(path: &str) -> Option<Vec<u8>> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 8];
+    let read = file.read(&mut header).ok()?;
+
+    match ImageFormat::detect(&header[..read]) {
+        Some(ImageFormat::Png) | Some(ImageFormat::Jpeg) | Some(ImageFormat::Gif) => {
+            let mut data = header[..read].to_vec();
+            file.read_to_end(&mut data).ok()?;
+            Some(data)
+        },
+        _ => None
+    }
 }
@@ -4,6 +4,8 @@
 
 pub mod font;
 pub mod image;
+pub mod animation;
+pub mod codecs;
 #[cfg(feature = "canvas")] pub mod canvas;
 
 use std::any::TypeId;
@@ -14,8 +16,10 @@ use controls::AnyHandle;
 use error::Error;
 
 pub use self::font::{FontT, Font};
-pub use self::image::{ImageT, OemImageT, MemoryImageT, Image};
-#[cfg(feature = "canvas")] pub use self::canvas::{BrushT, Brush, PenT, Pen};
+pub use self::image::{ImageT, OemImageT, MemoryImageT, Image, ImageData, ImageFormat, ImageDiff, AnimationData, AnimationFrame};
+pub use self::animation::{AnimationT, MemoryAnimationT, FramesAnimationT, Animation};
+#[cfg(feature = "canvas")] pub use self::canvas::{BrushT, Brush, PenT, Pen, PathT, Path, TextFormatT, TextFormat, TextLayout,
+ BitmapT, RgbaBitmapT, Bitmap, RenderBackend, SoftwareRenderer, DisplayList};
 
 /**
     Structures implementing this trait can be used by a Ui to build a Resource
@@ -0,0 +1,222 @@
+/*!
+    Asynchronous tasks running on the thread of a `Ui`.
+
+    `Ui::spawn` adds a future to a ui. The future is polled by the message loop of the ui thread (`dispatch_events`,
+    `dispatch_pending`, etc), so it can use the controls between two `await` without blocking the interface.
+    The futures are woken from any thread: the wakers post a message to the message window of the ui.
+
+    This module contains the futures most useful to the tasks of an interface: `sleep` and the `channel` receiving
+    the results of other threads. A `UiHandle` (see `Ui::handle`) gives access to the ui from a task:
+
+    ```rust,ignore
+    let handle = ui.handle();
+    let (sender, mut receiver) = nwg::tasks::channel();
+    thread::spawn(move || { sender.send(download()).ok(); });
+
+    ui.spawn(async move {
+        if let Some(text) = receiver.recv().await {
+            handle.with(|ui| ui.get::<Label>(&"Status").map(|l| l.set_text(&text)));
+        }
+    });
+    ```
+
+    Notes:
+    • The tasks are dropped with their ui. A task that never finishes does not prevent the message loop from exiting
+    • The `sleep` futures only work on threads running a message loop
+*/
+
+use std::pin::Pin;
+use std::future::Future;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use winapi::UINT_PTR;
+
+/**
+    A future that completes once a duration elapsed. See `sleep`.
+*/
+pub struct Sleep {
+    deadline: Instant,
+    timer: Option<UINT_PTR>,
+    token: u64
+}
+
+/**
+    Return a future that completes after `duration`. The timers of the system are not more precise than a millisecond.
+*/
+pub fn sleep(duration: Duration) -> Sleep {
+    static TOKENS: AtomicUsize = AtomicUsize::new(0);
+    Sleep{ deadline: Instant::now() + duration, timer: None, token: TOKENS.fetch_add(1, Ordering::SeqCst) as u64 }
+}
+
+impl Sleep {
+
+    /// Return the instant at which the future completes
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        use low::task_helper::{set_sleep_timer, kill_sleep_timer};
+
+        let sleep = self.get_mut();
+        let now = Instant::now();
+        if now >= sleep.deadline {
+            kill_sleep_timer(&mut sleep.timer, sleep.token);
+            return Poll::Ready(());
+        }
+
+        let wait = sleep.deadline - now;
+        let millis = wait.as_secs().saturating_mul(1000) + ((wait.subsec_nanos() + 999_999) / 1_000_000) as u64;
+        set_sleep_timer(&mut sleep.timer, sleep.token, millis.min(u32::max_value() as u64) as u32, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        ::low::task_helper::kill_sleep_timer(&mut self.timer, self.token);
+    }
+}
+
+/// The state shared by the two ends of a channel
+struct Shared<T> {
+    queue: VecDeque<T>,
+    waker: Option<Waker>,
+    senders: usize,
+    receiver: bool
+}
+
+/**
+    The sending end of a channel. A sender can be cloned and sent to other threads.
+*/
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>
+}
+
+/**
+    The receiving end of a channel. The values are received by a task with `recv`.
+*/
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>
+}
+
+/**
+    A future that receives the next value of a channel. See `Receiver::recv`.
+*/
+pub struct Recv<'a, T: 'a> {
+    receiver: &'a mut Receiver<T>
+}
+
+/**
+    Create a channel sending values to a task. The values are received in the order they were sent.
+*/
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Mutex::new(Shared{ queue: VecDeque::new(), waker: None, senders: 1, receiver: true }));
+    (Sender{ shared: shared.clone() }, Receiver{ shared: shared })
+}
+
+impl<T> Sender<T> {
+
+    /**
+        Send a value to the receiver and wake the task waiting for it.
+        Return `Err(value)` if the receiver was dropped.
+    */
+    pub fn send(&self, value: T) -> Result<(), T> {
+        let waker = {
+            let mut shared = self.shared.lock().unwrap();
+            if !shared.receiver {
+                return Err(value);
+            }
+
+            shared.queue.push_back(value);
+            shared.waker.take()
+        };
+
+        if let Some(waker) = waker { waker.wake(); }
+        Ok(())
+    }
+
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.shared.lock().unwrap().senders += 1;
+        Sender{ shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // The receiver is woken when the last sender is dropped, so that `recv` returns `None`
+        let waker = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.senders -= 1;
+                if shared.senders == 0 { shared.waker.take() } else { None }
+            },
+            Err(_) => None
+        };
+
+        if let Some(waker) = waker { waker.wake(); }
+    }
+}
+
+impl<T> Receiver<T> {
+
+    /**
+        Return a future that receives the next value. The future returns `None` if every sender was dropped
+        and every value was received.
+    */
+    pub fn recv(&mut self) -> Recv<T> {
+        Recv{ receiver: self }
+    }
+
+    /**
+        Return the next value if one was sent, without waiting
+    */
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.shared.lock().unwrap().queue.pop_front()
+    }
+
+    /**
+        Poll the next value of the channel. Use it to receive values in a future implemented by hand.
+    */
+    pub fn poll_recv(&mut self, cx: &mut Context) -> Poll<Option<T>> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.queue.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if shared.senders == 0 => Poll::Ready(None),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.receiver = false;
+            shared.queue.clear();
+            shared.waker = None;
+        }
+    }
+}
+
+impl<'a, T> Future for Recv<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
@@ -131,6 +131,7 @@ pub fn $n(ui: &$crate::Ui<$t>) -> Result<(), $crate::Error> {
     • disabled: `false`  
     • exit_on_close: `true`  
     • icon: `None`  
+    • accept_files: `false`  
 
     Usage:  
     `nwg_window!()`  
@@ -145,7 +146,8 @@ macro_rules! nwg_window {
             title: "Native Windows GUI", 
             position: (100, 100), size: (800, 600), 
             resizable: false, visible: true, disabled: false, 
-            exit_on_close: true, icon: None
+            exit_on_close: true, icon: None,
+            accept_files: false
         };
         
         $( t.$i = $v; );*
@@ -504,6 +506,7 @@ macro_rules! nwg_timer {
     • limit: `32_767`  
     • placeholder: `None`  
     • font: `None`
+    • accept_files: `false`
 
     Usage:  
     `nwg_textinput!(parent="MyParent";)`  
@@ -521,7 +524,8 @@ macro_rules! nwg_textinput {
             limit: 32_767,
             placeholder: None,
             parent: $p,
-            font: None
+            font: None,
+            accept_files: false
         };
         $( t.$i = $v; );*
         t
@@ -749,6 +753,8 @@ macro_rules! nwg_canvas {
     • visible: `true`  
     • disabled: `false`  
     • image: `None`  
+    • scaling: `ImageScaling::Center`  
+    • zoomable: `false`  
 
     Usage:  
     `nwg_image_frame!(parent="MyParent";)`  
@@ -762,7 +768,8 @@ macro_rules! nwg_image_frame {
         $crate::ImageFrameT{ 
             position: (0, 0), size: (100, 30), 
             visible: true, disabled: false, 
-            parent: $p, image: None
+            parent: $p, image: None,
+            scaling: $crate::constants::ImageScaling::Center, zoomable: false
         };
         
         $( t.$i = $v; );*
@@ -837,6 +844,7 @@ macro_rules! nwg_treeview_item {
     • visible: `true`  
     • disabled: `false`  
     • show_edge: `true`  
+    • accept_files: `false`  
 
     Usage:  
     `nwg_frame!(parent="MyParent";)`  
@@ -850,7 +858,7 @@ macro_rules! nwg_frame {
         $crate::FrameT{ 
             position: (0, 0), size: (100, 30), 
             visible: true, disabled: false, show_edge: true,
-            parent: $p
+            accept_files: false, parent: $p
         };
         
         $( t.$i = $v; );*
@@ -947,6 +955,52 @@ macro_rules! nwg_listview {
     }}
 }
 
+/**
+    Set the accessibility metadata of the control created by another template.
+
+    Defaults:  
+    • name: `None`  
+    • description: `None`  
+    • role: `None`  
+    • help: `None`  
+
+    Usage:  
+    `nwg_accessible!(control=nwg_button!(parent="MyParent";); name="Save")`  
+    `nwg_accessible!(control=nwg_canvas!(parent="MyParent";); name="Sales"; role=AccessibleRole::Chart)`  
+*/
+#[macro_export]
+macro_rules! nwg_accessible {
+    (control=$c:expr; $( $i:ident=$v:expr );* ) => { {
+        let mut t = 
+        $crate::accessibility::AccessibleT { 
+            control: $c,
+            accessibility: $crate::accessibility::Accessibility::new()
+        };
+        
+        $( t.accessibility.$i = Some($v.into()); );*
+
+        t
+    }}
+}
+
+/**
+    Set the text of the control created by another template using a message of the ui catalogs.
+    The text is updated each time the language of the ui changes.
+
+    Usage:  
+    `nwg_localized!(control=nwg_button!(parent="MyParent";); key="actions.save")`  
+    `nwg_localized!(control=nwg_menu!(parent="MainWindow";); key="menu.file")`  
+*/
+#[macro_export]
+macro_rules! nwg_localized {
+    (control=$c:expr; key=$k:expr) => { {
+        $crate::locale::LocalizedT { 
+            control: $c,
+            key: $k.into()
+        }
+    }}
+}
+
 
 
 //---- Resources ----//
@@ -992,6 +1046,7 @@ macro_rules! nwg_font {
     Usage:  
     `nwg_image!(source="test.bmp")`  
     `nwg_image!(source="test.ico"; image_type=ImageType::Icon)`   
+    `nwg_image!(source="test.png"; size=(32, 32))`   
 */
 #[macro_export]
 macro_rules! nwg_image {
@@ -0,0 +1,128 @@
+/*!
+    Control colors and light/dark themes.
+
+    The colors of a single control can be changed with its `set_text_color` and `set_background_color` methods.
+    A `Theme` changes the colors of every control in a `Ui` at once (see `Ui::set_theme`). A theme using
+    `ThemeMode::System` follows the light/dark preference of the user and is applied again when it changes.
+
+    Notes:
+    • Buttons drawn by the visual styles ignore the text color. The dark visual style is used instead in dark themes
+    • The colors of the controls created after the theme was set are changed when the controls are packed
+*/
+
+/**
+    Select the palette used by a theme.
+
+    • `Light`: Always use the light palette
+    • `Dark`: Always use the dark palette
+    • `System`: Follow the "apps use light theme" preference of the user
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeMode {
+    Light,
+    Dark,
+    System
+}
+
+/**
+    The colors applied by a theme. The colors are (red, green, blue) values.
+
+    Members:
+    • `background`: The background color of the windows, frames, tabs and of the static controls (labels, checkboxes, etc)
+    • `text`: The text color of the static controls
+    • `input_background`: The background color of the controls accepting input (textinput, listbox, treeview, etc)
+    • `input_text`: The text color of the controls accepting input
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub background: (u8, u8, u8),
+    pub text: (u8, u8, u8),
+    pub input_background: (u8, u8, u8),
+    pub input_text: (u8, u8, u8)
+}
+
+impl Palette {
+
+    /**
+        A palette matching the default light colors of Windows
+    */
+    pub fn light() -> Palette {
+        Palette {
+            background: (240, 240, 240),
+            text: (0, 0, 0),
+            input_background: (255, 255, 255),
+            input_text: (0, 0, 0)
+        }
+    }
+
+    /**
+        A palette matching the dark mode of the Windows applications
+    */
+    pub fn dark() -> Palette {
+        Palette {
+            background: (32, 32, 32),
+            text: (240, 240, 240),
+            input_background: (43, 43, 43),
+            input_text: (255, 255, 255)
+        }
+    }
+
+}
+
+/**
+    A theme that can be applied to a Ui
+
+    Members:
+    • `mode`: Select the palette to use
+    • `light`: The palette used when the theme is light
+    • `dark`: The palette used when the theme is dark
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub mode: ThemeMode,
+    pub light: Palette,
+    pub dark: Palette
+}
+
+impl Theme {
+
+    /**
+        Create a new theme using the default light and dark palettes
+    */
+    pub fn new(mode: ThemeMode) -> Theme {
+        Theme { mode: mode, light: Palette::light(), dark: Palette::dark() }
+    }
+
+    /**
+        Return `true` if the theme follows the light/dark preference of the user
+    */
+    pub fn follows_system(&self) -> bool {
+        self.mode == ThemeMode::System
+    }
+
+    /**
+        Return `true` if the theme currently uses the dark palette
+    */
+    pub fn is_dark(&self) -> bool {
+        match self.mode {
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+            ThemeMode::System => system_prefers_dark()
+        }
+    }
+
+    /**
+        Return the palette currently used by the theme
+    */
+    pub fn palette(&self) -> Palette {
+        if self.is_dark() { self.dark } else { self.light }
+    }
+
+}
+
+/**
+    Return `true` if the user prefers dark applications. Always `false` on the systems without a dark mode.
+*/
+pub fn system_prefers_dark() -> bool {
+    unsafe{ ::low::color_helper::system_prefers_dark() }
+}
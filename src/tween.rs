@@ -0,0 +1,404 @@
+/*!
+    Property animations. A `Tween` changes the numeric properties of controls over time: their position, their size,
+    the value of a progress bar or the opacity of a window.
+
+    A tween is played by an `Animator` control (see `AnimatorT`). The animator runs on the thread of its `Ui` and
+    triggers the `animator::Completed` event once every property reached its final value.
+
+    The easing curves and the `Timeline` that computes the values of a tween do not use any window, so they can be used
+    and tested without a `Ui`.
+
+    Notes:
+    • The values of the properties are applied by the animator. A control that is not in the `Ui` anymore is ignored
+    • The opacity can only be changed on windows. The progress value can only be changed on progress bars
+*/
+
+use std::f64::consts::PI;
+use std::time::Duration;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+/**
+    The easing curves of the tweens. An easing curve maps the time elapsed in a tween (from 0 to 1) to the progress
+    of the value (0 is the start value and 1 the end value).
+
+    • `Linear`: A constant speed  
+    • `QuadIn`, `QuadOut`, `QuadInOut`: Accelerate, decelerate or both along a quadratic curve  
+    • `CubicIn`, `CubicOut`, `CubicInOut`: Accelerate, decelerate or both along a cubic curve  
+    • `SineIn`, `SineOut`, `SineInOut`: Accelerate, decelerate or both along a sine curve  
+    • `ExpoIn`, `ExpoOut`: Accelerate or decelerate along an exponential curve  
+    • `BackIn`, `BackOut`: Move a little backward at the start or overshoot the end value before settling  
+    • `ElasticOut`: Oscillate around the end value before settling  
+    • `BounceOut`: Bounce on the end value  
+    • `CubicBezier`: A cubic bezier curve from (0, 0) to (1, 1) with the control points (x1, y1) and (x2, y2), like the CSS
+      `cubic-bezier` function. The x coordinates are clamped between 0 and 1  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    BackIn,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+    CubicBezier(f64, f64, f64, f64)
+}
+
+impl Easing {
+
+    /**
+        Return the progress of the value at the time `t`. `t` is clamped between 0 and 1. Every curve returns 0 at
+        the start and 1 at the end, the `Back` and `Elastic` curves go beyond these values in between.
+    */
+    pub fn apply(&self, t: f64) -> f64 {
+        const BACK: f64 = 1.70158;
+
+        let t = if t.is_nan() || t < 0.0 { 0.0 } else if t > 1.0 { 1.0 } else { t };
+        match *self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (2.0 - 2.0 * t).powi(2) / 2.0 },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0 },
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => (1.0 - (t * PI).cos()) / 2.0,
+            Easing::ExpoIn => if t == 0.0 { 0.0 } else { 2f64.powf(10.0 * t - 10.0) },
+            Easing::ExpoOut => if t == 1.0 { 1.0 } else { 1.0 - 2f64.powf(-10.0 * t) },
+            Easing::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Easing::BackOut => {
+                let u = t - 1.0;
+                1.0 + (BACK + 1.0) * u * u * u + BACK * u * u
+            },
+            Easing::ElasticOut => if t == 0.0 || t == 1.0 { t } else {
+                2f64.powf(-10.0 * t) * ((10.0 * t - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+            },
+            Easing::BounceOut => bounce_out(t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t)
+        }
+    }
+
+}
+
+/**
+    The value of an animated property
+
+    • `Position`: The position of a control (x, y)  
+    • `Size`: The size of a control (width, height)  
+    • `Progress`: The value of a progress bar  
+    • `Opacity`: The opacity of a window, from 0.0 (transparent) to 1.0 (opaque)  
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropertyValue {
+    Position(i32, i32),
+    Size(u32, u32),
+    Progress(u32),
+    Opacity(f32)
+}
+
+impl PropertyValue {
+
+    /**
+        Return true if `self` and `other` are values of the same property
+    */
+    pub fn same_property(&self, other: &PropertyValue) -> bool {
+        match (self, other) {
+            (&PropertyValue::Position(..), &PropertyValue::Position(..)) |
+            (&PropertyValue::Size(..), &PropertyValue::Size(..)) |
+            (&PropertyValue::Progress(..), &PropertyValue::Progress(..)) |
+            (&PropertyValue::Opacity(..), &PropertyValue::Opacity(..)) => true,
+            _ => false
+        }
+    }
+
+    /**
+        Return the value between `self` (at 0) and `to` (at 1) at the progress `t`. `t` is not clamped, so that
+        the easing curves can overshoot the values. The integer values are rounded, the sizes, the progress values and
+        the opacity are clamped to their valid range.
+
+        Return `None` if `to` is the value of another property.
+    */
+    pub fn interpolate(&self, to: &PropertyValue, t: f64) -> Option<PropertyValue> {
+        match (*self, *to) {
+            (PropertyValue::Position(x1, y1), PropertyValue::Position(x2, y2)) =>
+                Some(PropertyValue::Position(lerp_signed(x1, x2, t), lerp_signed(y1, y2, t))),
+            (PropertyValue::Size(w1, h1), PropertyValue::Size(w2, h2)) =>
+                Some(PropertyValue::Size(lerp_unsigned(w1, w2, t), lerp_unsigned(h1, h2, t))),
+            (PropertyValue::Progress(v1), PropertyValue::Progress(v2)) =>
+                Some(PropertyValue::Progress(lerp_unsigned(v1, v2, t))),
+            (PropertyValue::Opacity(o1), PropertyValue::Opacity(o2)) => {
+                let o = lerp(o1 as f64, o2 as f64, t);
+                Some(PropertyValue::Opacity(if o < 0.0 { 0.0 } else if o > 1.0 { 1.0 } else { o as f32 }))
+            },
+            _ => None
+        }
+    }
+
+}
+
+/**
+    A description of an animation. Tweens can be nested to build complex animations.
+
+    • `Property`: Change a property of the control `control` from `from` to `to` during `duration` along the curve `easing`.
+      If `from` is `None`, the animation starts from the value of the property when the tween starts  
+    • `Delay`: Wait during a duration  
+    • `Sequence`: Play the tweens one after the other  
+    • `Parallel`: Play the tweens at the same time. The group ends when its longest tween ends  
+
+    When two tweens change the same property of a control at the same time, the last one in the description wins.
+*/
+#[derive(Clone, Debug)]
+pub enum Tween<ID: Clone> {
+    Property{ control: ID, from: Option<PropertyValue>, to: PropertyValue, duration: Duration, easing: Easing },
+    Delay(Duration),
+    Sequence(Vec<Tween<ID>>),
+    Parallel(Vec<Tween<ID>>)
+}
+
+impl<ID: Clone> Tween<ID> {
+
+    /**
+        Create a tween that changes a property of `control` from its current value to `to`
+    */
+    pub fn property(control: ID, to: PropertyValue, duration: Duration, easing: Easing) -> Tween<ID> {
+        Tween::Property{ control: control, from: None, to: to, duration: duration, easing: easing }
+    }
+
+    /**
+        Return the time needed to play the tween
+    */
+    pub fn duration(&self) -> Duration {
+        let zero = Duration::new(0, 0);
+        match self {
+            &Tween::Property{ duration, .. } | &Tween::Delay(duration) => duration,
+            &Tween::Sequence(ref tweens) => tweens.iter().fold(zero, |total, t| total + t.duration()),
+            &Tween::Parallel(ref tweens) => tweens.iter().map(|t| t.duration()).max().unwrap_or(zero)
+        }
+    }
+
+}
+
+/// A property tween placed on a timeline
+#[derive(Clone, Debug)]
+struct Track<ID: Clone> {
+    control: ID,
+    key: u64,
+    initial: Option<PropertyValue>,
+    from: Option<PropertyValue>,
+    to: PropertyValue,
+    start: Duration,
+    duration: Duration,
+    easing: Easing,
+    done: bool
+}
+
+/**
+    The values of a tween over time. A timeline is played by calling `advance` with the time elapsed since its start.
+    The time must not go backward, use `reset` to play the timeline again.
+*/
+#[derive(Clone, Debug)]
+pub struct Timeline<ID: Clone> {
+    tracks: Vec<Track<ID>>,
+    duration: Duration
+}
+
+impl<ID: Clone+Hash> Timeline<ID> {
+
+    /**
+        Create a timeline playing `tween`
+    */
+    pub fn new(tween: &Tween<ID>) -> Timeline<ID> {
+        let mut tracks = Vec::new();
+        let duration = place_tween(tween, Duration::new(0, 0), &mut tracks);
+        Timeline{ tracks: tracks, duration: duration }
+    }
+
+    /**
+        Return the time needed to play the timeline
+    */
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /**
+        Return true if every property reached its end value
+    */
+    pub fn is_finished(&self) -> bool {
+        self.tracks.iter().all(|t| t.done)
+    }
+
+    /**
+        Rewind the timeline. The start values that were read from the controls will be read again.
+    */
+    pub fn reset(&mut self) {
+        for track in self.tracks.iter_mut() {
+            track.from = track.initial;
+            track.done = false;
+        }
+    }
+
+    /**
+        Return the values of the properties at the time `elapsed` since the start of the timeline.
+
+        Only the properties changed by the tweens that are playing or that just ended are returned. A property
+        that reached its end value is returned once with its end value and never again.
+
+        `current` is called when a tween without start value starts, with the control and the end value of the tween.
+        It must return the current value of the property, or `None` if it is unknown (the tween then jumps to its end value).
+        The values returned by a previous call count as the current values, even if they were not applied yet.
+    */
+    pub fn advance<F>(&mut self, elapsed: Duration, mut current: F) -> Vec<(ID, PropertyValue)>
+      where F: FnMut(&ID, &PropertyValue) -> Option<PropertyValue>
+    {
+        let mut values: Vec<(u64, ID, PropertyValue)> = Vec::new();
+
+        for track in self.tracks.iter_mut() {
+            if track.done || elapsed < track.start { continue; }
+
+            if track.from.is_none() {
+                let pending = values.iter().rev()
+                    .find(|&&(key, _, ref v)| key == track.key && v.same_property(&track.to))
+                    .map(|&(_, _, v)| v);
+
+                let from = match pending {
+                    Some(v) => Some(v),
+                    None => current(&track.control, &track.to)
+                };
+
+                track.from = Some(match from {
+                    Some(ref v) if v.same_property(&track.to) => *v,
+                    _ => track.to
+                });
+            }
+
+            let played = elapsed - track.start;
+            let value = if played >= track.duration {
+                track.done = true;
+                track.to
+            } else {
+                let t = seconds(played) / seconds(track.duration);
+                let from = track.from.unwrap();
+                from.interpolate(&track.to, track.easing.apply(t)).unwrap_or(track.to)
+            };
+
+            values.push((track.key, track.control.clone(), value));
+        }
+
+        values.into_iter().map(|(_, id, v)| (id, v)).collect()
+    }
+
+}
+
+/// Add the tracks of a tween starting at `start` and return the duration of the tween
+fn place_tween<ID: Clone+Hash>(tween: &Tween<ID>, start: Duration, tracks: &mut Vec<Track<ID>>) -> Duration {
+    match tween {
+        &Tween::Property{ ref control, from, to, duration, easing } => {
+            tracks.push(Track{ control: control.clone(), key: hash_id(control), initial: from, from: from, to: to, start: start,
+                duration: duration, easing: easing, done: false });
+            duration
+        },
+        &Tween::Delay(duration) => duration,
+        &Tween::Sequence(ref tweens) => {
+            let mut offset = Duration::new(0, 0);
+            for t in tweens.iter() {
+                offset += place_tween(t, start + offset, tracks);
+            }
+            offset
+        },
+        &Tween::Parallel(ref tweens) => {
+            let mut longest = Duration::new(0, 0);
+            for t in tweens.iter() {
+                let d = place_tween(t, start, tracks);
+                if d > longest { longest = d; }
+            }
+            longest
+        }
+    }
+}
+
+/// Hash the controls like the `Ui` does, so that two identifiers of the same control are equal
+fn hash_id<ID: Hash>(id: &ID) -> u64 {
+    let mut s = DefaultHasher::new();
+    id.hash(&mut s);
+    s.finish()
+}
+
+fn seconds(d: Duration) -> f64 {
+    (d.as_secs() as f64) + (d.subsec_nanos() as f64) / 1_000_000_000.0
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+fn lerp_signed(from: i32, to: i32, t: f64) -> i32 {
+    let v = lerp(from as f64, to as f64, t).round();
+    if v < (i32::min_value() as f64) { i32::min_value() } else if v > (i32::max_value() as f64) { i32::max_value() } else { v as i32 }
+}
+
+fn lerp_unsigned(from: u32, to: u32, t: f64) -> u32 {
+    let v = lerp(from as f64, to as f64, t).round();
+    if v < 0.0 { 0 } else if v > (u32::max_value() as f64) { u32::max_value() } else { v as u32 }
+}
+
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Return the y coordinate of the point of a cubic bezier curve at the x coordinate `x`
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    let clamp = |v: f64| if v < 0.0 { 0.0 } else if v > 1.0 { 1.0 } else { v };
+    let (x1, x2) = (clamp(x1), clamp(x2));
+
+    // Coordinate of a point of the curve at the parameter `s`, with the start at 0 and the end at 1
+    let curve = |a: f64, b: f64, s: f64| 3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s;
+    let slope = |a: f64, b: f64, s: f64| 3.0 * a * (1.0 - s) * (1.0 - s) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s;
+
+    // Newton's method converges quickly on most curves, the bisection handles the flat parts
+    let mut s = x;
+    for _ in 0..8 {
+        let error = curve(x1, x2, s) - x;
+        if error.abs() < 1e-9 { return curve(y1, y2, s); }
+
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 { break; }
+        s -= error / d;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..64 {
+        let value = curve(x1, x2, s);
+        if (value - x).abs() < 1e-9 { break; }
+        if value < x { low = s; } else { high = s; }
+        s = (low + high) / 2.0;
+    }
+
+    curve(y1, y2, s)
+}
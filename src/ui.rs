@@ -8,13 +8,24 @@ use std::collections::HashMap;
 use std::any::{Any, TypeId};
 use std::cell::{RefCell, Ref, RefMut};
 use std::rc::Rc;
+use std::time::Duration;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use low::message_handler::MessageHandler;
+use low::drag_helper::DragState;
+use low::dpi_helper::DpiState;
+use low::color_helper::ThemeState;
+use low::locale_helper::LocaleState;
+use low::task_helper::TaskState;
 use low::defs::{PackUserValueArgs, PackControlArgs, PackResourceArgs, UnpackArgs, BindArgs, UnbindArgs, TriggerArgs};
 use controls::{ControlT, Control, AnyHandle, ControlType};
 use resources::{ResourceT, Resource};
-use events::{Event, EventCallback, EventArgs, Destroyed};
+use events::{Event, EventCallback, IdleCallback, EventArgs, Destroyed};
 use error::Error;
+use theme::Theme;
+use locale::Catalog;
 
 type InnerId = u64;
 type HandleHash = u64;
@@ -47,7 +58,28 @@ pub struct UiInner<ID: Hash+Clone+'static> {
     pub inner_public_map: HashMap<InnerId, (ID, TypeId)>,
 
     // Map the handle of the controls to its ui inner id. Used when matching controls from within the events proc
-    pub handle_inner_map: HashMap<HandleHash, InnerId>
+    pub handle_inner_map: HashMap<HandleHash, InnerId>,
+
+    // Map the registered drop targets to the drop effects they accept
+    pub drop_targets: HashMap<InnerId, u32>,
+
+    // The drag and drop operation currently running in the ui
+    pub drag: Option<DragState>,
+
+    // Fonts scaled for the monitors that do not use the system DPI
+    pub dpi: DpiState,
+
+    // The theme applied to the controls of the ui
+    pub theme: Option<ThemeState>,
+
+    // The catalogs, the current language and the localized controls
+    pub locale: LocaleState,
+
+    // The callbacks called when the message queue drains
+    pub idle_callbacks: Vec<(InnerId, ID, Rc<IdleCallback<ID>>)>,
+
+    // The futures spawned on the ui
+    pub tasks: TaskState
 }
 
 impl<ID: Hash+Clone> UiInner<ID> {
@@ -72,7 +104,14 @@ impl<ID: Hash+Clone> UiInner<ID> {
             events_definitions: HashMap::with_capacity(32),
             resources: HashMap::with_capacity(16),
             inner_public_map: HashMap::with_capacity(64),
-            handle_inner_map: HashMap::with_capacity(32) })
+            handle_inner_map: HashMap::with_capacity(32),
+            drop_targets: HashMap::new(),
+            drag: None,
+            dpi: DpiState::new(),
+            theme: None,
+            locale: LocaleState::new(),
+            idle_callbacks: Vec::new(),
+            tasks: TaskState::new() })
     }
 
     pub fn pack_user_value(&mut self, params: PackUserValueArgs<ID>) -> Option<Error> {
@@ -88,6 +127,7 @@ impl<ID: Hash+Clone> UiInner<ID> {
 
     pub fn pack_control(&mut self, params: PackControlArgs<ID>) -> Option<Error> {
         use low::events::hook_window_events;
+        use low::dpi_helper::init_window_dpi;
 
         let inner_id = UiInner::hash_id(&params.id);
         if self.inner_public_map.contains_key(&inner_id) {
@@ -111,6 +151,21 @@ impl<ID: Hash+Clone> UiInner<ID> {
                     self.control_events.insert(inner_id, event_collection);
                     self.handle_inner_map.insert(handle_hash, inner_id);
 
+                    if let Ok(AnyHandle::HWND(h)) = self.handle_of(inner_id) {
+                        unsafe{ 
+                            init_window_dpi(self, h);
+                            ::low::locale_helper::mirror_new_window(h, self.locale.mirrored);
+                        }
+                        self.theme_control(inner_id, h);
+                    }
+
+                    // Controls built by a `LocalizedT`
+                    if let Some(key) = self.locale.pending.remove(&handle_hash) {
+                        let text = self.locale.localization.text(&key);
+                        self.locale.bindings.insert(inner_id, key);
+                        unsafe{ ::low::locale_helper::apply_control_text(self, inner_id, &text); }
+                    }
+
                     ::std::mem::forget(tmp_ui);
 
                     None
@@ -120,6 +175,21 @@ impl<ID: Hash+Clone> UiInner<ID> {
         }
     }
 
+    /**
+        Apply the theme of the ui to a newly packed window-like control
+    */
+    fn theme_control(&self, id: InnerId, handle: ::winapi::HWND) {
+        use low::color_helper::apply_control_theme;
+
+        if let Some(state) = self.theme.as_ref() {
+            let palette = if state.dark { state.theme.dark } else { state.theme.light };
+            if let Some(control) = self.controls.get(&id) {
+                let control_type = control.borrow().control_type();
+                unsafe{ apply_control_theme(handle, control_type, Some(&palette), state.dark); }
+            }
+        }
+    }
+
     pub fn pack_resource(&mut self, params: PackResourceArgs<ID>) -> Option<Error> {
         let inner_id = UiInner::hash_id(&params.id);
         if self.inner_public_map.contains_key(&inner_id) {
@@ -171,6 +241,15 @@ impl<ID: Hash+Clone> UiInner<ID> {
         let ids_to_free: Vec<InnerId> = control_to_free.iter().filter_map(|handle|{ self.inner_id_from_handle(handle) }).collect();
         for id in ids_to_free.iter().rev() {
 
+            // A drag operation cannot outlive its source or its target
+            let in_drag = match self.drag.as_ref() {
+                Some(d) => d.source == *id || d.target.map(|(t, _, _)| t == *id).unwrap_or(false),
+                None => false
+            };
+            if in_drag { unsafe{ ::low::drag_helper::abort_drag(self); } }
+            self.drop_targets.remove(id);
+            self.locale.bindings.remove(id);
+
             // Call the destroy callbacks
             self.trigger(*id, Destroyed, EventArgs::None);
 
@@ -185,7 +264,14 @@ impl<ID: Hash+Clone> UiInner<ID> {
 
             // Unhook the events dispatcher if its a window
             match control.handle() {
-                AnyHandle::HWND(h) => unhook_window_events::<ID>(h),
+                AnyHandle::HWND(h) => {
+                    unhook_window_events::<ID>(h);
+                    self.dpi.windows.remove(&(h as usize));
+                    unsafe{ 
+                        ::low::color_helper::free_window_colors(h);
+                        ::low::accessibility_helper::clear_window_accessibility(h);
+                    }
+                },
                 _ => {/* Nothing to free here */}
             };
             
@@ -207,6 +293,11 @@ impl<ID: Hash+Clone> UiInner<ID> {
         self.inner_public_map.remove(&id).unwrap();
         let resource = self.resources.remove(&id).unwrap();
         let mut resource = resource.into_inner();
+
+        // Free the fonts scaled from a font resource
+        if let AnyHandle::HFONT(h) = resource.handle() {
+            unsafe{ self.dpi.free_font(h); }
+        }
         
         // Free the control custom resources
         resource.free();
@@ -410,6 +501,30 @@ impl<ID: Hash+Clone> UiInner<ID> {
         Err(Error::ControlRequired)
     }
 
+    /**
+        Call `f` with a temporary Ui wrapping this inner ui. Used by the low level helpers that need the public
+        methods of the Ui, like `get_mut`, from within the events proc.
+    */
+    pub fn with_ui<R, F: FnOnce(&Ui<ID>) -> R>(&mut self, f: F) -> R {
+        let tmp_ui: Ui<ID> = Ui{inner: self as *mut UiInner<ID>};
+        let result = f(&tmp_ui);
+        ::std::mem::forget(tmp_ui);
+        result
+    }
+
+    pub fn run_idle_callbacks(&mut self) {
+        // Copy the callback list, so that the callbacks can be removed while they run
+        let callbacks: Vec<(ID, Rc<IdleCallback<ID>>)> = self.idle_callbacks.iter()
+            .map(|&(_, ref id, ref cb)| (id.clone(), cb.clone()))
+            .collect();
+
+        self.with_ui(|ui| {
+            for &(ref id, ref callback) in callbacks.iter() {
+                (callback)(ui, id);
+            }
+        });
+    }
+
     #[inline(always)]
     pub fn event_handlers(&self, msg: u32) -> Option<EventHandlerCollection> {
         self.events_definitions.get(&msg).cloned()
@@ -417,7 +532,13 @@ impl<ID: Hash+Clone> UiInner<ID> {
 
     #[inline(always)]
     pub fn inner_id_from_handle(&self, handle: &AnyHandle) -> Option<InnerId> {
-        if let Some(id) = self.handle_inner_map.get(&UiInner::<ID>::hash_handle(handle)) {
+        // Fonts scaled for a monitor DPI are identified by their base font
+        let handle = match handle {
+            &AnyHandle::HFONT(f) => AnyHandle::HFONT(self.dpi.base_font(f)),
+            h => h.clone()
+        };
+
+        if let Some(id) = self.handle_inner_map.get(&UiInner::<ID>::hash_handle(&handle)) {
             Some(*id) 
         } else {
             None 
@@ -480,6 +601,9 @@ impl<ID: Hash+Clone> Drop for UiInner<ID> {
 
     fn drop(&mut self) {
         use low::events::unhook_window_events;
+
+        // The handles of the ui stop working before the controls are freed
+        self.tasks.close();
         
         let controls_ids: Vec<u64> = self.controls.keys().map(|k| *k).collect();
         for id in controls_ids {
@@ -492,6 +616,7 @@ impl<ID: Hash+Clone> Drop for UiInner<ID> {
         }
         
         unhook_window_events::<ID>(self.messages.hwnd);
+        ::low::events::set_idle_window(self.messages.hwnd, false);
 
         self.messages.free();
     }
@@ -856,6 +981,332 @@ impl<ID:Hash+Clone> Ui<ID> {
         inner.id_from_handle(h)
     }
 
+    /**
+        Register a window-like control as a drop target for the in-app drag and drop operations.
+        Once registered, the control receives the `DragEnter`, `DragOver`, `DragLeave` and `DragDrop` events.
+        Registering a control a second time updates the accepted effects.
+
+        Params:
+          • id: The id that identify the control in the ui
+          • effects: The drop effects accepted by the control. A combination of the `DROP_EFFECT_*` constants
+
+        Returns:
+          • `Ok(())` if the control was registered
+          • `Error::KeyNotFound` if the id is not in the Ui.
+          • `Error::BadResource` if the id do not identify a window-like control
+    */
+    pub fn register_drop_target(&self, id: &ID, effects: u32) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+        let inner_id = UiInner::hash_id(id);
+
+        match inner.handle_of(inner_id) {
+            Ok(AnyHandle::HWND(_)) => {
+                inner.drop_targets.insert(inner_id, effects);
+                Ok(())
+            },
+            Ok(h) => Err(Error::BadResource(format!("Window-like control required got {}", h.human_name()))),
+            Err(e) => Err(e)
+        }
+    }
+
+    /**
+        Remove a control from the drop targets. If the payload is currently over the control, the control receives
+        a `DragLeave` event and the drag operation continues without target.
+
+        Returns:
+          • `Ok(())` if the control was unregistered
+          • `Error::KeyNotFound` if the id is not a registered drop target
+    */
+    pub fn unregister_drop_target(&self, id: &ID) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+        let inner_id = UiInner::hash_id(id);
+
+        // The target must remove its hover feedback if the cursor is over it
+        unsafe{ ::low::drag_helper::leave_drop_target(inner, inner_id); }
+
+        match inner.drop_targets.remove(&inner_id) {
+            Some(_) => Ok(()),
+            None => Err(Error::KeyNotFound)
+        }
+    }
+
+    /**
+        Start an in-app drag and drop operation from a window-like control. This is usually called from a
+        `MouseMove` callback once `Cursor::dragging` detected a drag gesture.
+
+        The source captures the mouse until the left button is released. The registered drop targets under the cursor
+        receive the drag events and can choose the drop effect. Once the operation is done, the source receives
+        a `DragEnd` event with the final effect (`DROP_EFFECT_NONE` if the drop was cancelled or refused).
+        Pressing `Escape` or the right mouse button cancels the operation.
+
+        Params:
+          • source: The id of the control that starts the drag
+          • payload: The dragged data. Targets can read it with `DragData::payload`
+          • effects: The drop effects allowed by the source. A combination of the `DROP_EFFECT_*` constants
+          • image: The id of an image resource (bitmap or icon) to display under the cursor
+
+        Returns:
+          • `Ok(())` if the drag operation started
+          • `Error::KeyNotFound` if the source or the image id is not in the Ui.
+          • `Error::BadResource` if the source is not a window-like control or if the image is not an image resource
+          • `Error::UserError` if a drag operation is already running
+    */
+    pub fn begin_drag(&self, source: &ID, payload: Box<Any>, effects: u32, image: Option<&ID>) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+
+        let image = match image {
+            Some(id) => match inner.handle_of(UiInner::hash_id(id)) {
+                Ok(h) => Some(h),
+                Err(e) => { return Err(e); }
+            },
+            None => None
+        };
+
+        unsafe{ ::low::drag_helper::begin_drag(inner, UiInner::hash_id(source), payload, effects, image) }
+    }
+
+    /**
+        Cancel the running drag and drop operation. Do nothing if there is no drag operation running.
+    */
+    pub fn cancel_drag(&self) {
+        let inner = unsafe{ &mut *self.inner };
+        unsafe{ ::low::drag_helper::cancel_drag(inner); }
+    }
+
+    /**
+        Check if a drag and drop operation is running in the ui
+    */
+    pub fn is_dragging(&self) -> bool {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.drag.is_some()
+    }
+
+    /**
+        Apply a theme to every control of the ui. The controls packed later also use the theme.
+        If the theme mode is `ThemeMode::System`, the colors are updated when the user changes the light/dark preference.
+
+        Setting `None` removes the theme and restores the default colors of every control, including the colors
+        set with `set_text_color` and `set_background_color`.
+    */
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        let inner = unsafe{ &mut *self.inner };
+        inner.theme = theme.map(|t| ThemeState{ dark: t.is_dark(), theme: t });
+        unsafe{ ::low::color_helper::apply_theme(inner); }
+    }
+
+    /**
+        Return the theme applied to the ui or `None` if the controls use the default colors
+    */
+    pub fn get_theme(&self) -> Option<Theme> {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.theme.as_ref().map(|t| t.theme)
+    }
+
+    /**
+        Add a message catalog to the ui. If a catalog of the same language was already added, the messages are merged.
+        Call `set_language` to update the localized controls with the new messages.
+    */
+    pub fn add_catalog(&self, catalog: Catalog) {
+        let inner = unsafe{ &mut *self.inner };
+        inner.locale.localization.add_catalog(catalog);
+    }
+
+    /**
+        Select the language of the ui. The text of every localized control and menu is updated.
+        If the right to left mirroring is enabled, the layout is mirrored when the language is written from right to left.
+
+        Returns:
+          • `Ok(())` if the language was changed
+          • `Error::BadResource` if there is no catalog for the language
+    */
+    pub fn set_language(&self, language: &str) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+        match inner.locale.localization.set_language(language) {
+            Ok(_) => {
+                unsafe{ ::low::locale_helper::apply_language(inner); }
+                Ok(())
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /**
+        Return the current language of the ui or `None` if no language was selected
+    */
+    pub fn get_language(&self) -> Option<String> {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.locale.localization.language().map(|l| l.to_string())
+    }
+
+    /**
+        Set the language used when a message is missing in the current language
+    */
+    pub fn set_fallback_language(&self, language: Option<&str>) {
+        let inner = unsafe{ &mut *self.inner };
+        inner.locale.localization.set_fallback(language);
+    }
+
+    /**
+        Return the message of a key in the current language. If the message is not found, the key is returned.
+    */
+    pub fn translate(&self, key: &str) -> String {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.locale.localization.text(key)
+    }
+
+    /**
+        Return the message of a key in the current language with its `{name}` placeholders replaced by the arguments.  
+        Ex: `ui.translate_with("welcome", &[("name", &"John")])`
+    */
+    pub fn translate_with(&self, key: &str, args: &[(&str, &::std::fmt::Display)]) -> String {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.locale.localization.format(key, args)
+    }
+
+    /**
+        Return the plural form of a message for a count using the plural rules of the current language.
+        The count is available in the message as `{count}`.  
+        Ex: `ui.translate_plural("files", 3, &[])`
+    */
+    pub fn translate_plural(&self, key: &str, count: u64, args: &[(&str, &::std::fmt::Display)]) -> String {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.locale.localization.plural(key, count, args)
+    }
+
+    /**
+        Set the text of a control using a message of the catalogs. The text is updated each time the language changes.
+        Setting `None` stops updating the text of the control.
+
+        Window-like controls use their window text. Menus and menu items use their menu text.
+
+        Returns:
+          • `Ok(())` if the control was localized
+          • `Error::KeyNotFound` if the id is not a control of the ui
+          • `Error::BadResource` if the control has no text (ex: a timer)
+    */
+    pub fn localize(&self, id: &ID, key: Option<&str>) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+        let inner_id = UiInner::hash_id(id);
+
+        let handle = match inner.controls.get(&inner_id) {
+            Some(c) => match c.try_borrow() {
+                Ok(c) => c.handle(),
+                Err(_) => { return Err(Error::BorrowError); }
+            },
+            None => { return Err(Error::KeyNotFound); }
+        };
+
+        match handle {
+            AnyHandle::HWND(_) | AnyHandle::HMENU(_) | AnyHandle::HMENU_ITEM(_, _) => {},
+            h => { return Err(Error::BadResource(format!("A control with a text is required, got {}", h.human_name()))); }
+        }
+
+        match key {
+            Some(key) => {
+                let text = inner.locale.localization.text(key);
+                inner.locale.bindings.insert(inner_id, key.to_string());
+                unsafe{ ::low::locale_helper::apply_control_text(inner, inner_id, &text); }
+            },
+            None => { inner.locale.bindings.remove(&inner_id); }
+        }
+
+        Ok(())
+    }
+
+    /**
+        Enable or disable the mirroring of the layout when the current language is written from right to left
+        (see `Catalog::is_rtl`). When the layout is mirrored, the x position of the controls starts from the right edge
+        of their parent.
+    */
+    pub fn set_rtl_mirroring(&self, enabled: bool) {
+        let inner = unsafe{ &mut *self.inner };
+        inner.locale.mirroring = enabled;
+        unsafe{ ::low::locale_helper::update_mirroring(inner); }
+    }
+
+    /**
+        Return `true` if the layout is mirrored when the current language is written from right to left
+    */
+    pub fn get_rtl_mirroring(&self) -> bool {
+        let inner = unsafe{ &mut (&*self.inner) };
+        inner.locale.mirroring
+    }
+
+    /**
+        Register the message key of a control built by a `LocalizedT`. The text is applied when the control is packed.
+    */
+    pub(crate) fn bind_localized_handle(&self, handle: &AnyHandle, key: &str) {
+        let inner = unsafe{ &mut *self.inner };
+        let handle_hash = UiInner::<ID>::hash_handle(handle);
+        inner.locale.pending.insert(handle_hash, key.to_string());
+    }
+
+    /**
+        Add a callback called each time the message loop drained the message queue of the thread.
+        The idle callbacks are called by `dispatch_events`, `dispatch_pending`, `wait_timeout` and `run_until`,
+        once the messages waiting in the queue were dispatched. They are not called when no message was dispatched.
+
+        Unlike `bind`, this is not delayed.
+
+        Returns:  
+          • `Ok(())` if the callback was added  
+          • `Error::KeyExists` if the cb_id is already used by an idle callback  
+    */
+    pub fn bind_idle<T>(&self, cb_id: &ID, cb: T) -> Result<(), Error> where
+      T: Fn(&Ui<ID>, &ID) -> ()+'static {
+        let inner = unsafe{ &mut *self.inner };
+        let cb_inner_id = UiInner::hash_id(cb_id);
+        if inner.idle_callbacks.iter().any(|&(id, _, _)| id == cb_inner_id) {
+            return Err(Error::KeyExists);
+        }
+
+        inner.idle_callbacks.push((cb_inner_id, cb_id.clone(), Rc::new(cb)));
+        ::low::events::set_idle_window(inner.messages.hwnd, true);
+        Ok(())
+    }
+
+    /**
+        Remove an idle callback. An idle callback can remove itself.
+
+        Returns:  
+          • `Ok(())` if the callback was removed  
+          • `Error::KeyNotFound` if there is no idle callback identified by cb_id  
+    */
+    pub fn unbind_idle(&self, cb_id: &ID) -> Result<(), Error> {
+        let inner = unsafe{ &mut *self.inner };
+        let cb_inner_id = UiInner::hash_id(cb_id);
+        match inner.idle_callbacks.iter().position(|&(id, _, _)| id == cb_inner_id) {
+            Some(index) => { inner.idle_callbacks.remove(index); },
+            None => { return Err(Error::KeyNotFound); }
+        }
+
+        if inner.idle_callbacks.is_empty() {
+            ::low::events::set_idle_window(inner.messages.hwnd, false);
+        }
+
+        Ok(())
+    }
+
+    /**
+        Add a future to the ui. The future is polled by the message loop of the ui thread, so it can use the
+        controls of the ui (see `handle`) without blocking the interface. The first poll happens once the message loop
+        dispatches the messages, never from within `spawn`.
+
+        The future is dropped when it completes or when the ui is freed. See the `tasks` module.
+    */
+    pub fn spawn<F: Future<Output=()>+'static>(&self, future: F) {
+        let inner = unsafe{ &mut *self.inner };
+        ::low::task_helper::spawn_task(inner, Box::pin(future));
+    }
+
+    /**
+        Return a handle to the ui that can be moved into the futures and the callbacks of the ui.
+    */
+    pub fn handle(&self) -> UiHandle<ID> {
+        let inner = unsafe{ &mut *self.inner };
+        UiHandle{ inner: self.inner, alive: inner.tasks.alive() }
+    }
+
     /**
         Return the message window handle of the ui. Useful for controls or functions that requires a window (such as timers)
     */
@@ -873,23 +1324,96 @@ impl<ID: Hash+Clone> Drop for Ui<ID> {
     }
 }
 
+/**
+    A handle to a `Ui` used by its tasks. Unlike the ui, the handle does not free the ui when it is dropped,
+    and it can outlive it.
+
+    The handle must only be used on the thread of its ui.
+*/
+pub struct UiHandle<ID: Hash+Clone+'static> {
+    inner: *mut UiInner<ID>,
+    alive: Arc<AtomicBool>
+}
+
+impl<ID: Hash+Clone> UiHandle<ID> {
+
+    /**
+        Call `f` with the ui.
+
+        Returns:  
+          • `Some(result)` with the value returned by `f`  
+          • `None` if the ui was freed  
+    */
+    pub fn with<R, F: FnOnce(&Ui<ID>) -> R>(&self, f: F) -> Option<R> {
+        if !self.is_alive() {
+            return None;
+        }
+
+        let inner = unsafe{ &mut *self.inner };
+        Some(inner.with_ui(f))
+    }
+
+    /// Check if the ui of the handle was not freed. Return `true` if it was not or `false` otherwise
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+}
+
+impl<ID: Hash+Clone> Clone for UiHandle<ID> {
+    fn clone(&self) -> UiHandle<ID> {
+        UiHandle{ inner: self.inner, alive: self.alive.clone() }
+    }
+}
+
 
 /**
     Dispatch the messages waiting the the system message queue to the associated Uis. This includes NWG custom messages.
 
-    Return once a quit event was received.
+    Return once a quit event was received, with the exit code passed to `exit`.
 */
-pub fn dispatch_events() {
+pub fn dispatch_events() -> i32 {
     // Actual code is located under the low module because that's where most of the unsafe code should be
-    unsafe{ ::low::events::dispatch_events(); }
+    unsafe{ ::low::events::dispatch_events() }
+}
+
+/**
+    Dispatch the messages waiting in the system message queue and return without waiting for new messages.
+    Use it to run the Uis from another loop, such as the loop of a game:
+
+    `while dispatch_pending() { /* Draw a frame */ }`
+
+    Return `false` if a quit event was received. The quit event is kept in the queue, so that an outer
+    `dispatch_events` loop returns its exit code.
+*/
+pub fn dispatch_pending() -> bool {
+    unsafe{ ::low::events::dispatch_pending() }
+}
+
+/**
+    Wait until a message is received or until `timeout` elapsed, then dispatch the messages waiting in the system message queue.
+    Return `false` if a quit event was received, like `dispatch_pending`.
+*/
+pub fn wait_timeout(timeout: Duration) -> bool {
+    unsafe{ ::low::events::wait_timeout(timeout) }
+}
+
+/**
+    Dispatch the messages until `predicate` returns `true`. The predicate is called before waiting for new messages,
+    so it should depend on the callbacks of the Uis.
+
+    Return `true` if the predicate returned `true` or `false` if a quit event was received, like `dispatch_pending`.
+*/
+pub fn run_until<F: FnMut() -> bool>(predicate: F) -> bool {
+    unsafe{ ::low::events::run_until(predicate) }
 }
 
 /**
-    Send a WM_QUIT to the system queue. Breaks the `dispatch_events` loop.
+    Send a WM_QUIT to the system queue. Breaks the `dispatch_events` loop, which returns `code`.
 */
-pub fn exit() {
+pub fn exit(code: i32) {
     // Actual code is located under the low module because that's where most of the unsafe code should be
-    unsafe{ ::low::events::exit(); }
+    unsafe{ ::low::events::exit(code); }
 }
 
 /**
@@ -13,7 +13,7 @@ use nwg::events::*;
 use nwg::events as nwge;
 
 fn setup_ui() -> Ui<u64> { Ui::new().unwrap() }
-fn window<T: Hash+Clone>() -> WindowT<T, &'static str> {  WindowT{title: "", position:(-600,-600), size:(100, 100), resizable:true, visible:true, disabled:false, exit_on_close:true, icon: None} }
+fn window<T: Hash+Clone>() -> WindowT<T, &'static str> {  WindowT{title: "", position:(-600,-600), size:(100, 100), resizable:true, visible:true, disabled:false, exit_on_close:true, icon: None, accept_files: false} }
 fn default_font() -> FontT<&'static str> { FontT{ family: "Arial", size: 10, weight: FONT_WEIGHT_BOLD, decoration: FONT_DECO_ITALIC|FONT_DECO_STRIKEOUT } }
 
 macro_rules! test_visibility {
@@ -259,7 +259,7 @@ fn test_ui_bind() {
         // Still binding new events in a destroy callback is a horrible idea, because, unless specified, the NWG destroy order is random.
         ui.bind(&1002, &5001, KeyDown, |_, _, _, _|{ } );
         ui.commit().expect("Commit was not successful");
-        exit();
+        exit(0);
     });
 
     ui.commit().expect("Commit was not successful");
@@ -386,6 +386,105 @@ fn test_ui_trigger() {
 }
 
 
+#[test]
+fn test_event_loop() {
+    use std::rc::Rc;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    let ui = setup_ui();
+    ui.pack_control(&1000, window());
+    ui.commit().expect("Commit was not successful");
+
+    let idle_count = Rc::new(Cell::new(0));
+    let count = idle_count.clone();
+    ui.bind_idle(&5000, move |_, id| { assert_eq!(*id, 5000); count.set(count.get() + 1); }).expect("Idle callback was not bound");
+    assert!(ui.bind_idle(&5000, |_, _| {}).err().unwrap() == Error::KeyExists, "Idle callback id was reused");
+
+    // Packing a value posts a message to the ui
+    ui.pack_value(&1, 10u32);
+    assert!(dispatch_pending());
+    assert!(idle_count.get() >= 1, "Idle callback was not called");
+    assert!(wait_timeout(Duration::from_millis(10)));
+
+    // An idle callback can remove itself
+    let done = Rc::new(Cell::new(false));
+    let flag = done.clone();
+    ui.bind_idle(&5001, move |ui, id| { ui.unbind_idle(id).unwrap(); flag.set(true); }).unwrap();
+    ui.pack_value(&2, 20u32);
+    assert!(run_until(|| done.get()));
+    assert!(ui.unbind_idle(&5001).err().unwrap() == Error::KeyNotFound, "Idle callback was not removed");
+    assert!(ui.has_id(&2));
+
+    // The quit event stops every loop and its code is returned by `dispatch_events`
+    exit(42);
+    assert!(!dispatch_pending());
+    assert!(!run_until(|| false));
+    assert_eq!(dispatch_events(), 42);
+
+    ui.unbind_idle(&5000).unwrap();
+}
+
+#[test]
+fn test_tasks() {
+    use std::rc::Rc;
+    use std::cell::Cell;
+    use std::thread;
+    use std::pin::Pin;
+    use std::future::{Future, poll_fn};
+    use std::task::Poll;
+    use std::time::{Duration, Instant};
+    use nwg::tasks::{channel, sleep};
+
+    let ui = setup_ui();
+    ui.pack_control(&1000, window());
+    ui.commit().expect("Commit was not successful");
+
+    // A task receives the values sent by another thread and uses the ui
+    let handle = ui.handle();
+    let (sender, mut receiver) = channel::<u32>();
+    let total = Rc::new(Cell::new(0));
+    let finished = Rc::new(Cell::new(false));
+    let (t, f) = (total.clone(), finished.clone());
+    ui.spawn(poll_fn(move |cx| {
+        loop {
+            match receiver.poll_recv(cx) {
+                Poll::Ready(Some(v)) => {
+                    t.set(t.get() + v);
+                    assert!(handle.with(|ui| ui.has_id(&1000)) == Some(true), "Ui was not available in the task");
+                },
+                Poll::Ready(None) => { f.set(true); return Poll::Ready(()); },
+                Poll::Pending => { return Poll::Pending; }
+            }
+        }
+    }));
+
+    assert!(!finished.get(), "Task was polled by spawn");
+    thread::spawn(move || { sender.send(10).unwrap(); sender.send(32).unwrap(); });
+    assert!(run_until(|| finished.get()));
+    assert_eq!(total.get(), 42);
+
+    // Sleep completes once its duration elapsed
+    let start = Instant::now();
+    let done = Rc::new(Cell::new(false));
+    let d = done.clone();
+    let mut delay = sleep(Duration::from_millis(30));
+    ui.spawn(poll_fn(move |cx| Pin::new(&mut delay).poll(cx).map(|_| d.set(true))));
+    assert!(run_until(|| done.get()));
+    assert!(start.elapsed() >= Duration::from_millis(30), "Sleep completed too early");
+
+    // The handles stop working once their ui is freed
+    let (sender, mut receiver) = channel::<u32>();
+    let other = setup_ui();
+    let other_handle = other.handle();
+    other.spawn(poll_fn(move |cx| receiver.poll_recv(cx).map(|_| ())));
+    assert!(other_handle.with(|_| ()).is_some());
+    drop(other);
+    assert!(!other_handle.is_alive());
+    assert!(other_handle.with(|_| ()).is_none());
+    assert!(sender.send(1).is_err(), "Task was not dropped with its ui");
+}
+
 #[test]
 fn test_window_control_user_close() {
     let ui = setup_ui();
@@ -531,6 +630,2080 @@ fn test_window() {
     test_enabled!(ui, &1000, Window);
 }
 
+#[test]
+fn test_tween_easing() {
+    use nwg::tween::Easing;
+
+    let curves = [Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut, Easing::CubicIn, Easing::CubicOut,
+        Easing::CubicInOut, Easing::SineIn, Easing::SineOut, Easing::SineInOut, Easing::ExpoIn, Easing::ExpoOut,
+        Easing::BackIn, Easing::BackOut, Easing::ElasticOut, Easing::BounceOut, Easing::CubicBezier(0.25, 0.1, 0.25, 1.0)];
+
+    for curve in curves.iter() {
+        assert!(curve.apply(0.0).abs() < 1e-9, "{:?} does not start at 0", curve);
+        assert!((curve.apply(1.0) - 1.0).abs() < 1e-9, "{:?} does not end at 1", curve);
+        assert!(curve.apply(-1.0).abs() < 1e-9 && (curve.apply(2.0) - 1.0).abs() < 1e-9, "{:?} time is not clamped", curve);
+    }
+
+    assert_eq!(Easing::Linear.apply(0.3), 0.3);
+    assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    assert_eq!(Easing::QuadInOut.apply(0.25), 0.125);
+    assert_eq!(Easing::CubicOut.apply(0.5), 0.875);
+    assert!(Easing::BackIn.apply(0.2) < 0.0);
+    assert!(Easing::BackOut.apply(0.8) > 1.0);
+
+    assert!((Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 1e-6);
+    assert!((Easing::CubicBezier(0.42, 0.0, 0.58, 1.0).apply(0.5) - 0.5).abs() < 1e-6);
+    assert!((Easing::CubicBezier(0.25, 0.1, 0.25, 1.0).apply(0.25) - 0.4085).abs() < 1e-3);
+}
+
+#[test]
+fn test_tween_timeline() {
+    use std::time::Duration;
+    use nwg::tween::{Easing, PropertyValue, Tween, Timeline};
+
+    let ms = |v| Duration::from_millis(v);
+
+    let origin = PropertyValue::Position(0, 0);
+    assert_eq!(origin.interpolate(&PropertyValue::Position(100, -50), 0.5), Some(PropertyValue::Position(50, -25)));
+    assert_eq!(PropertyValue::Size(10, 10).interpolate(&PropertyValue::Size(20, 0), 1.5), Some(PropertyValue::Size(25, 0)));
+    assert_eq!(PropertyValue::Opacity(0.0).interpolate(&PropertyValue::Opacity(1.0), 1.2), Some(PropertyValue::Opacity(1.0)));
+    assert_eq!(origin.interpolate(&PropertyValue::Progress(3), 0.5), None);
+
+    let tween = Tween::Sequence(vec![
+        Tween::Parallel(vec![
+            Tween::Property{ control: 1, from: Some(origin), to: PropertyValue::Position(100, 0), duration: ms(100), easing: Easing::Linear },
+            Tween::Property{ control: 2, from: Some(PropertyValue::Progress(0)), to: PropertyValue::Progress(100), duration: ms(200), easing: Easing::Linear },
+        ]),
+        Tween::Delay(ms(50)),
+        Tween::property(1, PropertyValue::Position(100, 100), ms(100), Easing::QuadIn),
+    ]);
+    assert_eq!(tween.duration(), ms(350));
+
+    let mut timeline = Timeline::new(&tween);
+    let mut reads = 0;
+    assert_eq!(timeline.duration(), ms(350));
+    assert_eq!(timeline.advance(ms(0), |_, _| { reads += 1; None }), vec![(1, origin), (2, PropertyValue::Progress(0))]);
+    assert_eq!(timeline.advance(ms(50), |_, _| { reads += 1; None }), vec![(1, PropertyValue::Position(50, 0)), (2, PropertyValue::Progress(25))]);
+    assert_eq!(timeline.advance(ms(150), |_, _| { reads += 1; None }), vec![(1, PropertyValue::Position(100, 0)), (2, PropertyValue::Progress(75))]);
+    assert_eq!(timeline.advance(ms(200), |_, _| { reads += 1; None }), vec![(2, PropertyValue::Progress(100))]);
+    assert_eq!(reads, 0);
+
+    // The last tween starts from the current value of the control
+    assert_eq!(timeline.advance(ms(300), |_, _| { reads += 1; Some(PropertyValue::Position(100, 0)) }), vec![(1, PropertyValue::Position(100, 25))]);
+    assert_eq!(reads, 1);
+    assert!(!timeline.is_finished());
+    assert_eq!(timeline.advance(ms(400), |_, _| None), vec![(1, PropertyValue::Position(100, 100))]);
+    assert!(timeline.is_finished());
+    assert_eq!(timeline.advance(ms(500), |_, _| None), vec![]);
+
+    // The values that were not applied yet count as the current values
+    timeline.reset();
+    assert!(!timeline.is_finished());
+    assert_eq!(timeline.advance(ms(1000), |_, _| { reads += 1; None }),
+        vec![(1, PropertyValue::Position(100, 0)), (2, PropertyValue::Progress(100)), (1, PropertyValue::Position(100, 100))]);
+    assert_eq!(reads, 1);
+}
+
+#[test]
+fn test_animator() {
+    use std::time::Duration;
+    use nwg::tween::{Easing, PropertyValue, Tween};
+
+    let ui = setup_ui();
+    let tween = Tween::Parallel(vec![
+        Tween::property(1000, PropertyValue::Opacity(0.5), Duration::from_millis(200), Easing::SineOut),
+        Tween::property(1001, PropertyValue::Progress(100), Duration::from_millis(300), Easing::Linear),
+    ]);
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, ProgressBarT{ position: (0, 0), size: (100, 20), visible: true, disabled: false, range: (0, 100), value: 0, step: 1, state: ProgressBarState::Normal, vertical: false, parent: 1000 });
+    ui.pack_control(&1002, AnimatorT{ tween: tween.clone(), interval: 16 });
+    ui.commit().expect("Commit was not successful");
+    ui.bind(&1002, &5000, nwge::animator::Completed, |_, _, _, _| {});
+
+    assert_eq!(ui.type_of_control(&1002).unwrap(), ControlType::Animator);
+
+    {
+        let window = ui.get::<Window>(&1000).unwrap();
+        assert_eq!(window.get_opacity(), 1.0);
+        window.set_opacity(0.5);
+        assert!((window.get_opacity() - 0.5).abs() < 0.01);
+        window.set_opacity(1.0);
+        assert_eq!(window.get_opacity(), 1.0);
+    }
+
+    let mut animator = ui.get_mut::<Animator<u64>>(&1002).unwrap();
+    assert!(!animator.running());
+    assert_eq!(animator.duration(), Duration::from_millis(300));
+    assert_eq!(animator.elapsed(), Duration::new(0, 0));
+
+    animator.start();
+    assert!(animator.running());
+    animator.stop();
+    assert!(!animator.running());
+
+    animator.start();
+    animator.set_tween(Tween::Delay(Duration::from_millis(50)));
+    assert!(!animator.running(), "Setting a tween did not stop the animator");
+    assert_eq!(animator.duration(), Duration::from_millis(50));
+}
+
+#[test]
+fn test_dpi_conversions() {
+    use nwg::dpi;
+
+    assert_eq!(dpi::scale_factor(96), 1.0);
+    assert_eq!(dpi::scale_factor(144), 1.5);
+    assert_eq!(dpi::scale_factor(192), 2.0);
+
+    assert_eq!(dpi::to_physical(100, 96), 100);
+    assert_eq!(dpi::to_physical(100, 120), 125);
+    assert_eq!(dpi::to_physical(100, 144), 150);
+    assert_eq!(dpi::to_physical(-100, 144), -150);
+    assert_eq!(dpi::to_logical(150, 144), 100);
+    assert_eq!(dpi::to_logical(-150, 144), -100);
+
+    // Rounding is done to the nearest integer, halfway values are rounded away from zero
+    assert_eq!(dpi::to_physical(1, 144), 2);
+    assert_eq!(dpi::to_physical(-1, 144), -2);
+    assert_eq!(dpi::to_physical(3, 120), 4);
+    assert_eq!(dpi::to_logical(5, 120), 4);
+
+    assert_eq!(dpi::rescale(150, 144, 192), 200);
+    assert_eq!(dpi::rescale(150, 144, 144), 150);
+    assert_eq!(dpi::rescale(150, 0, 144), 150);
+    assert_eq!(dpi::rescale(i32::max_value(), 96, 192), i32::max_value());
+    assert_eq!(dpi::rescale(i32::min_value(), 96, 192), i32::min_value());
+
+    assert_eq!(dpi::point_to_physical((10, -20), 192), (20, -40));
+    assert_eq!(dpi::point_to_logical((20, -40), 192), (10, -20));
+    assert_eq!(dpi::size_to_physical((100, 50), 120), (125, 63));
+    assert_eq!(dpi::size_to_logical((125, 63), 120), (100, 50));
+}
+
+#[test]
+fn test_window_dpi() {
+    let ui = setup_ui();
+
+    ui.pack_control(&1000, window());
+    ui.bind(&1000, &5000, DpiChanged, |_, _, _, args|{ 
+        if let &EventArgs::Dpi(dpi) = args { assert!(dpi > 0, "Bad dpi value"); }
+    });
+    ui.commit().expect("Commit was not successful");
+
+    let w = ui.get::<Window>(&1000).unwrap();
+    assert!(w.dpi() > 0);
+    assert_eq!(w.scale_factor(), nwg::dpi::scale_factor(w.dpi()));
+}
+
+#[test]
+fn test_control_colors() {
+    let ui = setup_ui();
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, LabelT{text: "Test", position: (0, 0), size: (50, 20), visible: true, disabled: false, align: HTextAlign::Left, parent: 1000, font: None});
+    ui.pack_control(&1002, DatePickerT{value: None, position: (0, 20), size: (100, 20), visible: true, disabled: false, parent: 1000, font: None,
+      align: HTextAlign::Left, format: "", optional: false, range: (None, None)});
+    ui.pack_control(&1003, ProgressBarT{ position: (0, 40), size: (100, 20), visible: true, disabled: false, range: (0, 100), value: 50, step: 1, state: ProgressBarState::Normal, vertical: false, parent: 1000 });
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let w = ui.get::<Window>(&1000).unwrap();
+        let l = ui.get::<Label>(&1001).unwrap();
+
+        assert_eq!(w.get_background_color(), None);
+        assert_eq!(l.get_text_color(), None);
+
+        w.set_background_color(Some((10, 20, 30)));
+        l.set_text_color(Some((255, 0, 0)));
+        l.set_background_color(Some((10, 20, 30)));
+        assert_eq!(w.get_background_color(), Some((10, 20, 30)));
+        assert_eq!(l.get_text_color(), Some((255, 0, 0)));
+        assert_eq!(l.get_background_color(), Some((10, 20, 30)));
+
+        // Changing a color keeps the other one
+        l.set_text_color(None);
+        assert_eq!(l.get_text_color(), None);
+        assert_eq!(l.get_background_color(), Some((10, 20, 30)));
+
+        // The date picker and the progress bar receive their colors directly
+        let d = ui.get::<DatePicker>(&1002).unwrap();
+        d.set_text_color(Some((255, 0, 0)));
+        d.set_background_color(Some((10, 20, 30)));
+        assert_eq!(d.get_text_color(), Some((255, 0, 0)));
+        assert_eq!(d.get_background_color(), Some((10, 20, 30)));
+
+        let p = ui.get::<ProgressBar>(&1003).unwrap();
+        p.set_text_color(Some((0, 128, 0)));
+        p.set_background_color(Some((10, 20, 30)));
+        assert_eq!(p.get_text_color(), Some((0, 128, 0)));
+        assert_eq!(p.get_background_color(), Some((10, 20, 30)));
+        p.set_text_color(None);
+        p.set_background_color(None);
+        assert_eq!(p.get_text_color(), None);
+        assert_eq!(p.get_background_color(), None);
+    }
+
+    ui.unpack(&1003);
+    ui.unpack(&1002);
+    ui.unpack(&1001);
+    ui.commit().expect("Commit was not successful");
+}
+
+#[test]
+fn test_theme() {
+    use nwg::theme::{Theme, ThemeMode, Palette};
+
+    let ui = setup_ui();
+    assert_eq!(ui.get_theme(), None);
+
+    ui.pack_control(&1000, window());
+    ui.commit().expect("Commit was not successful");
+
+    ui.set_theme(Some(Theme::new(ThemeMode::Dark)));
+    ui.pack_control(&1001, LabelT{text: "Test", position: (0, 0), size: (50, 20), visible: true, disabled: false, align: HTextAlign::Left, parent: 1000, font: None});
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let dark = Palette::dark();
+        let w = ui.get::<Window>(&1000).unwrap();
+        let l = ui.get::<Label>(&1001).unwrap();
+        assert_eq!(w.get_background_color(), Some(dark.background));
+        assert_eq!(l.get_text_color(), Some(dark.text));
+        assert!(ui.get_theme().unwrap().is_dark());
+    }
+
+    ui.set_theme(Some(Theme::new(ThemeMode::Light)));
+    assert_eq!(ui.get::<Label>(&1001).unwrap().get_text_color(), Some(Palette::light().text));
+
+    ui.set_theme(None);
+    assert_eq!(ui.get_theme(), None);
+    assert_eq!(ui.get::<Window>(&1000).unwrap().get_background_color(), None);
+    assert_eq!(ui.get::<Label>(&1001).unwrap().get_text_color(), None);
+}
+
+#[test]
+fn test_accessible_nodes() {
+    use nwg::accessibility::{AccessibleNode, AccessibleRole};
+
+    let mut root = AccessibleNode::new("Chart", AccessibleRole::Chart, (0, 0, 100, 100));
+    root.children.push(AccessibleNode::new("Bar 1", AccessibleRole::Graphic, (10, 10, 20, 50)));
+    root.children.push(AccessibleNode::new("Bar 2", AccessibleRole::Graphic, (20, 10, 20, 50)));
+
+    assert!(root.contains(0, 0));
+    assert!(root.contains(99, 99));
+    assert!(!root.contains(100, 50));
+    assert!(!root.contains(-1, 50));
+
+    assert_eq!(root.hit_test(12, 20), Some(0));
+    assert_eq!(root.hit_test(25, 20), Some(1), "Overlapping children should return the topmost one");
+    assert_eq!(root.hit_test(50, 50), None);
+
+    assert_eq!(AccessibleRole::PushButton.to_system_role(), 0x2B);
+    assert_eq!(AccessibleRole::Custom(0x3D).to_system_role(), 0x3D);
+}
+
+#[test]
+fn test_accessibility() {
+    use nwg::accessibility::{Accessibility, AccessibleRole, AccessibleT};
+
+    let ui = setup_ui();
+
+    let mut info = Accessibility::new();
+    info.name = Some("Save".to_string());
+    info.help = Some("Save the document".to_string());
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, AccessibleT{
+        control: ButtonT{text: "", position:(10, 10), size: (30, 30), visible: true, disabled: false, parent: 1000, font: None},
+        accessibility: info
+    });
+    ui.commit().expect("Commit was not successful");
+
+    ui.pack_control(&1002, AccessibleT{ control: MenuT{text: "Menu", parent: 1000, disabled: false}, accessibility: Accessibility::new() });
+    match ui.commit() { Err(Error::BadResource(_)) => {}, r => panic!("Should have returned Error::BadResource, got {:?}", r) }
+
+    {
+        let btn = ui.get::<Button>(&1001).expect("The accessible template should build a button");
+        let info = btn.get_accessibility();
+        assert_eq!(info.name, Some("Save".to_string()));
+        assert_eq!(info.help, Some("Save the document".to_string()));
+        assert_eq!(info.role, None);
+
+        let mut info = Accessibility::new();
+        info.description = Some("An icon button".to_string());
+        info.role = Some(AccessibleRole::PushButton);
+        btn.set_accessibility(info.clone());
+        assert_eq!(btn.get_accessibility(), info);
+
+        btn.set_accessibility(Accessibility::new());
+        assert!(btn.get_accessibility().is_empty());
+    }
+
+    ui.unpack(&1001);
+    ui.commit().expect("Commit was not successful");
+}
+
+#[test]
+fn test_catalogs() {
+    use nwg::locale::{Catalog, Localization, PluralCategory, plural_category, format_message};
+
+    let source = "
+        # French messages
+        @language = fr-CA
+        @rtl = false
+
+        greeting = Bonjour {name}!
+        multiline = Ligne 1\\nLigne 2
+        files[one] = {count} fichier
+        files[other] = {count} fichiers
+    ";
+
+    let catalog = Catalog::parse(source).expect("Failed to parse the catalog");
+    assert_eq!(catalog.language(), "fr-CA");
+    assert_eq!(catalog.len(), 3);
+    assert_eq!(catalog.text("multiline"), Some("Ligne 1\nLigne 2"));
+    assert_eq!(catalog.plural("files", 0), Some("{count} fichier"));
+    assert_eq!(catalog.plural("files", 2), Some("{count} fichiers"));
+    assert_eq!(catalog.text("missing"), None);
+
+    for bad in &["@language = en\nno separator", "@language = en\nkey[several] = x", "@language = en\na = 1\na = 2", "a = 1", "@language = en\nbad = \\q"] {
+        match Catalog::parse(bad) { Err(Error::BadResource(_)) => {}, r => panic!("Should have returned Error::BadResource, got {:?}", r) }
+    }
+
+    assert_eq!(plural_category("en", 1), PluralCategory::One);
+    assert_eq!(plural_category("en", 0), PluralCategory::Other);
+    assert_eq!(plural_category("ru", 21), PluralCategory::One);
+    assert_eq!(plural_category("ru", 12), PluralCategory::Many);
+    assert_eq!(plural_category("pl", 24), PluralCategory::Few);
+    assert_eq!(plural_category("ar", 102), PluralCategory::Other);
+    assert_eq!(plural_category("ja", 1), PluralCategory::Other);
+    assert_eq!(plural_category("pt_BR", 0), PluralCategory::One);
+
+    assert_eq!(format_message("{{{a}}} {b} {c", &[("a", &1), ("b", &"two")]), "{1} two {c");
+    assert_eq!(format_message("{missing}", &[]), "{missing}");
+
+    let mut english = Catalog::new("en");
+    english.insert("greeting", "Hello {name}!");
+    english.insert("only.english", "English");
+    english.insert_plural("files", PluralCategory::One, "{count} file");
+    english.insert_plural("files", PluralCategory::Other, "{count} files");
+
+    let mut l = Localization::new();
+    l.add_catalog(english);
+    l.add_catalog(catalog);
+    l.set_fallback(Some("en"));
+
+    assert!(l.set_language("de").is_err());
+    l.set_language("fr").expect("The primary language should match the fr-CA catalog");
+    assert_eq!(l.format("greeting", &[("name", &"Marie")]), "Bonjour Marie!");
+    assert_eq!(l.plural("files", 1, &[]), "1 fichier");
+    assert_eq!(l.text("only.english"), "English");
+    assert_eq!(l.text("unknown.key"), "unknown.key");
+
+    l.set_language("en").unwrap();
+    assert_eq!(l.plural("files", 0, &[]), "0 files");
+}
+
+#[test]
+fn test_localization() {
+    use nwg::locale::{Catalog, LocalizedT};
+
+    let ui = setup_ui();
+
+    let mut en = Catalog::new("en");
+    en.insert("title", "Title");
+    en.insert("menu", "File");
+    en.insert("label", "Hello");
+
+    let mut ar = Catalog::new("ar");
+    ar.set_rtl(true);
+    ar.insert("title", "عنوان");
+    ar.insert("menu", "ملف");
+    ar.insert("label", "مرحبا");
+
+    ui.add_catalog(en);
+    ui.add_catalog(ar);
+    ui.set_language("en").expect("Failed to set the language");
+    assert_eq!(ui.get_language(), Some("en".to_string()));
+    assert!(ui.set_language("xx").is_err());
+
+    ui.pack_control(&1000, LocalizedT{ control: window(), key: "title".to_string() });
+    ui.pack_control(&1001, LocalizedT{ control: MenuT{text: "", parent: 1000, disabled: false}, key: "menu".to_string() });
+    ui.pack_control(&1002, LabelT{text: "", position: (0, 0), size: (50, 20), visible: true, disabled: false, align: HTextAlign::Left, parent: 1000, font: None});
+    ui.pack_control(&1003, TimerT{interval: 100});
+    ui.commit().expect("Commit was not successful");
+
+    ui.localize(&1002, Some("label")).expect("Failed to localize the label");
+    assert!(ui.localize(&1003, Some("label")).is_err());
+    assert_eq!(ui.localize(&9999, Some("label")), Err(Error::KeyNotFound));
+
+    assert_eq!(ui.get::<Window>(&1000).unwrap().get_title(), "Title");
+    assert_eq!(ui.get::<Menu>(&1001).unwrap().get_text(), "File");
+    assert_eq!(ui.get::<Label>(&1002).unwrap().get_text(), "Hello");
+
+    ui.set_rtl_mirroring(true);
+    ui.set_language("ar").unwrap();
+    assert_eq!(ui.get::<Window>(&1000).unwrap().get_title(), "عنوان");
+    assert_eq!(ui.get::<Menu>(&1001).unwrap().get_text(), "ملف");
+    assert_eq!(ui.get::<Label>(&1002).unwrap().get_text(), "مرحبا");
+    assert_eq!(ui.translate("missing"), "missing");
+
+    ui.set_language("en").unwrap();
+    assert_eq!(ui.get::<Label>(&1002).unwrap().get_text(), "Hello");
+}
+
+/// Append a PNG chunk with its CRC
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = 0xFFFFFFFFu32;
+        for &b in data.iter() {
+            crc ^= b as u32;
+            for _ in 0..8 { crc = if crc & 1 == 1 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 }; }
+        }
+        !crc
+    }
+
+    let mut data = kind.to_vec();
+    data.extend_from_slice(body);
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    out.extend_from_slice(&data);
+    out.extend_from_slice(&crc32(&data).to_be_bytes());
+}
+
+/// Wrap data in a zlib stream made of a single stored (uncompressed) block
+fn stored_zlib(raw: &[u8]) -> Vec<u8> {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in raw.iter() { a = (a + byte as u32) % 65521; b = (b + a) % 65521; }
+
+    let mut zlib = vec![0x78, 0x01, 0x01];
+    zlib.extend_from_slice(&(raw.len() as u16).to_le_bytes());
+    zlib.extend_from_slice(&(!(raw.len() as u16)).to_le_bytes());
+    zlib.extend_from_slice(raw);
+    zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    zlib
+}
+
+/// Build a PNG file with a single stored (uncompressed) IDAT chunk
+fn png_file(width: u32, height: u32, bit_depth: u8, color_type: u8, extra: &[(&[u8; 4], Vec<u8>)], raw: &[u8]) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    png_chunk(&mut out, b"IHDR", &header);
+    for &(kind, ref body) in extra.iter() { png_chunk(&mut out, kind, body); }
+    png_chunk(&mut out, b"IDAT", &stored_zlib(raw));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+#[test]
+fn test_image_decoding() {
+    // 2x2 RGBA: red, half transparent green / blue, transparent white. The second row uses the "up" filter.
+    let raw = [0, 255, 0, 0, 255, 0, 255, 0, 128,   2, 1, 0, 255, 0, 255, 0, 255, 128];
+    let png = png_file(2, 2, 8, 6, &[], &raw);
+    assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+
+    let image = ImageData::decode(&png).expect("Failed to decode the PNG image");
+    assert_eq!((image.width, image.height), (2, 2));
+    assert_eq!(image.pixel(0, 0), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(1, 0), Some((0, 128, 0, 128)));
+    assert_eq!(image.pixel(0, 1), Some((0, 0, 255, 255)));
+    assert_eq!(image.pixel(1, 1), Some((0, 0, 0, 0)));
+    assert_eq!(image.pixel(2, 0), None);
+    assert_eq!(image, ImageData::from_rgba(2, 2, &[255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 255, 255, 255, 255, 0]).unwrap());
+
+    // 4x1 palette image with 2 bits per pixel and a transparent palette entry
+    let palette = vec![0, 0, 0, 255, 255, 255, 10, 20, 30];
+    let png = png_file(4, 1, 2, 3, &[(b"PLTE", palette), (b"tRNS", vec![0])], &[0, 0b00011000]);
+    let image = ImageData::decode_png(&png).expect("Failed to decode the palette image");
+    assert_eq!(image.pixel(0, 0), Some((0, 0, 0, 0)));
+    assert_eq!(image.pixel(1, 0), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(2, 0), Some((10, 20, 30, 255)));
+    assert_eq!(image.pixel(3, 0), Some((0, 0, 0, 0)));
+    assert!(ImageData::decode_png(&png_file(4, 1, 2, 3, &[], &[0, 0])).is_err(), "Missing PLTE chunk was accepted");
+
+    // Corrupted or truncated files
+    let mut bad_crc = png_file(2, 2, 8, 6, &[], &raw);
+    bad_crc[20] ^= 0xFF;
+    assert!(ImageData::decode(&bad_crc).is_err());
+    assert!(ImageData::decode(&png[..png.len() - 20]).is_err());
+    assert!(ImageData::decode_png(&png_file(0, 1, 8, 6, &[], &[])).is_err());
+    assert!(ImageData::decode_png(&png_file(1, 1, 8, 6, &[], &[7, 0, 0, 0, 0])).is_err(), "Invalid filter type was accepted");
+
+    // 1x1 GIF with a red pixel
+    let gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00;";
+    assert_eq!(ImageFormat::detect(gif), Some(ImageFormat::Gif));
+    assert_eq!(ImageData::decode(gif).unwrap().pixel(0, 0), Some((255, 0, 0, 255)));
+    assert!(ImageData::decode_gif(&gif[..20]).is_err());
+
+    // ICO file with two PNG images, the largest one is used
+    let small = png_file(1, 1, 8, 6, &[], &[0, 1, 2, 3, 255]);
+    let large = png_file(2, 1, 8, 2, &[], &[0, 4, 5, 6, 4, 5, 6]);
+    let mut ico = vec![0, 0, 1, 0, 2, 0];
+    let mut offset = 6 + 32;
+    for image in [&small, &large].iter() {
+        ico.extend_from_slice(&[image[19], 1, 0, 0, 1, 0, 32, 0]);
+        ico.extend_from_slice(&(image.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += image.len();
+    }
+    ico.extend_from_slice(&small);
+    ico.extend_from_slice(&large);
+
+    assert_eq!(ImageFormat::detect(&ico), Some(ImageFormat::Ico));
+    assert_eq!(ImageData::decode_ico(&ico).unwrap().len(), 2);
+    assert_eq!(ImageData::decode(&ico).unwrap().pixel(1, 0), Some((4, 5, 6, 255)));
+    assert!(ImageData::decode_ico(&ico[..50]).is_err());
+
+    assert_eq!(ImageFormat::detect(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(ImageFormat::Jpeg));
+    assert!(ImageData::decode_jpeg(&[0xFF, 0xD8, 0xFF, 0xD9]).is_err());
+
+    // Progressive DC first scan of an 8x8 image whose only DC symbol (40) is bigger than the 11 bits of a DC coefficient
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xC4, 0x00, 0x14, 0x00, 1];
+    jpeg.extend_from_slice(&[0; 15]);
+    jpeg.extend_from_slice(&[40, 0xFF, 0xC2, 0x00, 0x0B, 8, 0, 8, 0, 8, 1, 1, 0x11, 0]);
+    jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 1, 1, 0x00, 0, 0, 0x00, 0x00, 0x00, 0xFF, 0xD9]);
+    assert!(ImageData::decode_jpeg(&jpeg).is_err());
+    assert_eq!(ImageFormat::detect(b"not an image"), None);
+    assert!(ImageData::decode(b"not an image").is_err());
+
+    let scaled = ImageData::from_rgba(1, 1, &[10, 20, 30, 255]).unwrap().resize(3, 2);
+    assert_eq!((scaled.width, scaled.height), (3, 2));
+    assert_eq!(scaled.pixel(2, 1), Some((10, 20, 30, 255)));
+
+    // Every pixel contributes to a reduced image
+    let stripes: Vec<u8> = (0..64).flat_map(|i| if i % 2 == 0 { vec![0, 0, 0, 255] } else { vec![255, 255, 255, 255] }).collect();
+    let reduced = ImageData::from_rgba(8, 8, &stripes).unwrap().resize(2, 3);
+    assert_eq!((reduced.width, reduced.height), (2, 3));
+    assert!(reduced.to_rgba().chunks(4).all(|p| p[0] >= 126 && p[0] <= 129 && p[3] == 255), "Reduced stripes are not gray");
+    assert_eq!(ImageData::new(4, 4).resize(0, 2).data.len(), 0);
+}
+
+/// Build a BMP file with a BITMAPINFOHEADER followed by `header_tail` (the extra fields of the V2 to V5 headers)
+fn bmp_file(width: i32, height: i32, bit_count: u16, compression: u32, colors_used: u32, header_tail: &[u8], table: &[u8], pixels: &[u8]) -> Vec<u8> {
+    let header_size = 40 + header_tail.len() as u32;
+    let offset = 14 + header_size + table.len() as u32;
+
+    let mut out = b"BM".to_vec();
+    out.extend_from_slice(&(offset + pixels.len() as u32).to_le_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&offset.to_le_bytes());
+
+    out.extend_from_slice(&header_size.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&bit_count.to_le_bytes());
+    out.extend_from_slice(&compression.to_le_bytes());
+    out.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&colors_used.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(header_tail);
+
+    out.extend_from_slice(table);
+    out.extend_from_slice(pixels);
+    out
+}
+
+fn bmp_error(data: &[u8]) -> String {
+    match ImageData::decode_bmp(data) {
+        Err(Error::BadResource(msg)) => msg,
+        Err(_) => panic!("Unexpected error type"),
+        Ok(_) => panic!("An invalid bitmap was decoded")
+    }
+}
+
+#[test]
+fn test_bmp_decoding() {
+    let opaque = |r, g, b| Some((r, g, b, 255));
+    let clear = Some((0, 0, 0, 0));
+
+    // 24 bits, bottom-up and top-down. Rows are padded to 4 bytes.
+    let pixels = [255, 0, 0, 0, 255, 0, 0, 0,   0, 0, 255, 255, 255, 255, 0, 0];
+    let image = ImageData::decode(&bmp_file(2, 2, 24, 0, 0, &[], &[], &pixels)).expect("Failed to decode a 24 bits bitmap");
+    assert_eq!((image.width, image.height), (2, 2));
+    assert_eq!(image.pixel(0, 0), opaque(255, 0, 0));
+    assert_eq!(image.pixel(1, 0), opaque(255, 255, 255));
+    assert_eq!(image.pixel(0, 1), opaque(0, 0, 255));
+    assert_eq!(image.pixel(1, 1), opaque(0, 255, 0));
+
+    let image = ImageData::decode_bmp(&bmp_file(2, -2, 24, 0, 0, &[], &[], &pixels)).expect("Failed to decode a top-down bitmap");
+    assert_eq!(image.pixel(0, 0), opaque(0, 0, 255));
+    assert_eq!(image.pixel(1, 1), opaque(255, 255, 255));
+
+    // 1, 4 and 8 bits palettes
+    let image = ImageData::decode_bmp(&bmp_file(3, 1, 1, 0, 0, &[], &[0, 0, 0, 0, 255, 0, 0, 0], &[0b1010_0000, 0, 0, 0])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(0, 0, 255));
+    assert_eq!(image.pixel(1, 0), opaque(0, 0, 0));
+    assert_eq!(image.pixel(2, 0), opaque(0, 0, 255));
+
+    let palette = [0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 255, 0];
+    let image = ImageData::decode_bmp(&bmp_file(3, 1, 4, 0, 3, &[], &palette, &[0x12, 0x00, 0, 0])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(0, 255, 0));
+    assert_eq!(image.pixel(1, 0), opaque(255, 0, 0));
+    assert_eq!(image.pixel(2, 0), opaque(0, 0, 0));
+    assert!(bmp_error(&bmp_file(3, 1, 4, 0, 3, &[], &palette, &[0x15, 0x00, 0, 0])).contains("palette index 5"));
+
+    let image = ImageData::decode_bmp(&bmp_file(2, 1, 8, 0, 2, &[], &[10, 20, 30, 0, 40, 50, 60, 0], &[1, 0, 0, 0])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(60, 50, 40));
+    assert_eq!(image.pixel(1, 0), opaque(30, 20, 10));
+
+    // RLE8: encoded run, end of line, absolute run and end of bitmap. The pixels that are not written are transparent.
+    let palette = [0, 0, 255, 0, 255, 0, 0, 0];
+    let rle = [3, 1,   0, 0,   0, 3, 0, 1, 0, 0,   0, 1];
+    let image = ImageData::decode_bmp(&bmp_file(4, 2, 8, 1, 2, &[], &palette, &rle)).expect("Failed to decode a RLE8 bitmap");
+    assert_eq!(image.pixel(0, 1), opaque(0, 0, 255));
+    assert_eq!(image.pixel(2, 1), opaque(0, 0, 255));
+    assert_eq!(image.pixel(3, 1), clear);
+    assert_eq!(image.pixel(0, 0), opaque(255, 0, 0));
+    assert_eq!(image.pixel(1, 0), opaque(0, 0, 255));
+    assert_eq!(image.pixel(3, 0), clear);
+
+    // RLE4 with a delta
+    let image = ImageData::decode_bmp(&bmp_file(4, 1, 4, 2, 2, &[], &palette, &[0, 2, 1, 0,   3, 0x10,   0, 1])).expect("Failed to decode a RLE4 bitmap");
+    assert_eq!(image.pixel(0, 0), clear);
+    assert_eq!(image.pixel(1, 0), opaque(0, 0, 255));
+    assert_eq!(image.pixel(2, 0), opaque(255, 0, 0));
+    assert_eq!(image.pixel(3, 0), opaque(0, 0, 255));
+    assert!(bmp_error(&bmp_file(4, 1, 4, 2, 2, &[], &palette, &[3])).contains("RLE data is truncated"));
+
+    // 16 bits, default 5-5-5 layout and 5-6-5 bitfields
+    let image = ImageData::decode_bmp(&bmp_file(2, 1, 16, 0, 0, &[], &[], &[0x00, 0x7C, 0x1F, 0x00])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(255, 0, 0));
+    assert_eq!(image.pixel(1, 0), opaque(0, 0, 255));
+
+    let masks = [0x00, 0xF8, 0, 0,   0xE0, 0x07, 0, 0,   0x1F, 0, 0, 0];
+    let image = ImageData::decode_bmp(&bmp_file(2, 1, 16, 3, 0, &[], &masks, &[0xE0, 0x07, 0x00, 0xF8])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(0, 255, 0));
+    assert_eq!(image.pixel(1, 0), opaque(255, 0, 0));
+
+    // 32 bits, with an alpha channel or with an empty alpha channel
+    let image = ImageData::decode_bmp(&bmp_file(1, 1, 32, 0, 0, &[], &[], &[0, 0, 255, 128])).unwrap();
+    assert_eq!(image.pixel(0, 0), Some((128, 0, 0, 128)));
+    let image = ImageData::decode_bmp(&bmp_file(1, 1, 32, 0, 0, &[], &[], &[0, 0, 255, 0])).unwrap();
+    assert_eq!(image.pixel(0, 0), opaque(255, 0, 0));
+
+    // V5 header with RGBA bitfields
+    let mut v5 = vec![0xFF, 0, 0, 0,   0, 0xFF, 0, 0,   0, 0, 0xFF, 0,   0, 0, 0, 0xFF];
+    v5.resize(84, 0);
+    let image = ImageData::decode_bmp(&bmp_file(1, 1, 32, 3, 0, &v5, &[], &[255, 0, 0, 255])).expect("Failed to decode a V5 bitmap");
+    assert_eq!(image.pixel(0, 0), opaque(255, 0, 0));
+
+    // OS/2 core header with a 3 bytes palette
+    let mut core = b"BM".to_vec();
+    core.extend_from_slice(&[0; 8]);
+    core.extend_from_slice(&(14u32 + 12 + 6).to_le_bytes());
+    core.extend_from_slice(&[12, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0]);
+    core.extend_from_slice(&[0, 0, 0, 0, 255, 0, 0x80, 0, 0, 0]);
+    assert_eq!(ImageData::decode_bmp(&core).unwrap().pixel(0, 0), opaque(0, 255, 0));
+
+    // Invalid bitmaps
+    assert!(bmp_error(&bmp_file(2, 2, 24, 0, 0, &[], &[], &pixels[..12])).contains("pixel data is truncated"));
+    assert!(bmp_error(&bmp_file(2, 2, 24, 7, 0, &[], &[], &pixels)).contains("unsupported bitmap compression 7"));
+    assert!(bmp_error(&bmp_file(2, 2, 24, 1, 0, &[], &[], &pixels)).contains("invalid bit count 24"));
+    assert!(bmp_error(&bmp_file(0, 2, 24, 0, 0, &[], &[], &pixels)).contains("empty"));
+    assert!(bmp_error(&bmp_file(2, 1, 8, 0, 300, &[], &[], &[0; 4])).contains("300 colors"));
+    assert!(bmp_error(&bmp_file(2, 1, 8, 0, 0, &[], &[0; 16], &[0; 4])).contains("color table"));
+    assert!(bmp_error(&bmp_file(1, 1, 24, 0, 0, &[0; 4], &[], &[0; 4])).contains("header size 44"));
+    assert!(bmp_error(&bmp_file(1, -1, 8, 1, 0, &[], &[0; 1024], &[0, 1])).contains("top-down"));
+    assert!(bmp_error(b"BM\x00\x00").contains("smaller than the bitmap file header"));
+
+    let mut bad_offset = bmp_file(2, 2, 24, 0, 0, &[], &[], &pixels);
+    bad_offset[10] = 0xFF;
+    assert!(bmp_error(&bad_offset).contains("outside of the file"));
+
+    let logo = ImageData::decode(include_bytes!("../img/rust-logo.bmp")).expect("Failed to decode the rust logo");
+    assert_eq!((logo.width, logo.height), (96, 96));
+}
+
+#[test]
+fn test_image_encoding() {
+    let rgba = [255, 0, 0, 255,   0, 255, 0, 128,   0, 0, 255, 0,
+                10, 20, 30, 255,  200, 100, 50, 64,  255, 255, 255, 1];
+    let image = ImageData::from_rgba(3, 2, &rgba).unwrap();
+    assert_eq!(image.to_rgba()[0..8].to_vec(), vec![255, 0, 0, 255, 0, 255, 0, 128]);
+
+    let mut png = Vec::new();
+    image.encode(ImageFormat::Png, &mut png).expect("PNG encoding failed");
+    assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+    assert_eq!(ImageData::decode(&png).unwrap(), image);
+
+    let mut bmp = Vec::new();
+    image.encode(ImageFormat::Bmp, &mut bmp).expect("BMP encoding failed");
+    assert_eq!(ImageFormat::detect(&bmp), Some(ImageFormat::Bmp));
+    assert_eq!(ImageData::decode(&bmp).unwrap(), image);
+
+    // Opaque images and repeated rows exercise the RGB color type and the compression
+    let opaque: Vec<u8> = (0..64*64).flat_map(|i| vec![(i % 64) as u8 * 4, (i / 64) as u8, 128, 255]).collect();
+    let opaque = ImageData::from_rgba(64, 64, &opaque).unwrap();
+    let mut png = Vec::new();
+    opaque.encode(ImageFormat::Png, &mut png).unwrap();
+    assert!(png.len() < 64 * 64 * 3, "The PNG data was not compressed");
+    assert_eq!(ImageData::decode(&png).unwrap(), opaque);
+
+    let empty = ImageData::new(1, 1);
+    let mut out = Vec::new();
+    empty.encode(ImageFormat::Png, &mut out).unwrap();
+    assert_eq!(ImageData::decode(&out).unwrap(), empty);
+
+    match image.encode(ImageFormat::Gif, &mut Vec::new()) {
+        Err(Error::BadResource(_)) => {},
+        r => panic!("GIF encoding should fail with a BadResource error, got {:?}", r.is_ok())
+    }
+
+    let mut small = [0u8; 10];
+    assert!(image.encode(ImageFormat::Png, &mut &mut small[..]).is_err(), "Write errors must be returned");
+}
+
+/// Build the body of an APNG frame control chunk
+fn apng_frame_control(sequence: u32, size: (u32, u32), offset: (u32, u32), delay: (u16, u16), dispose: u8, blend: u8) -> Vec<u8> {
+    let mut body = Vec::new();
+    for v in [sequence, size.0, size.1, offset.0, offset.1].iter() { body.extend_from_slice(&v.to_be_bytes()); }
+    body.extend_from_slice(&delay.0.to_be_bytes());
+    body.extend_from_slice(&delay.1.to_be_bytes());
+    body.extend_from_slice(&[dispose, blend]);
+    body
+}
+
+#[test]
+fn test_animation_decoding() {
+    // Two frames: a red 2x1 frame disposed to the background, then a blue pixel on the right. Repeated twice after the first play.
+    let gif = b"GIF89a\x02\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\xff\
+        \x21\xff\x0bNETSCAPE2.0\x03\x01\x02\x00\x00\
+        \x21\xf9\x04\x08\x05\x00\x00\x00,\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x04\x0a\x00\
+        \x21\xf9\x04\x01\x00\x00\x00\x00,\x01\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00;";
+
+    let animation = AnimationData::decode(gif).expect("GIF animation decoding failed");
+    assert_eq!(animation.frames.len(), 2);
+    assert_eq!(animation.loop_count, 3);
+    assert_eq!(animation.size(), (2, 1));
+    assert_eq!(animation.frames[0].delay, 50);
+    assert_eq!(animation.frames[1].delay, 100, "Short delays must use the default delay");
+    assert_eq!(animation.frames[0].image.pixel(1, 0), Some((255, 0, 0, 255)));
+    assert_eq!(animation.frames[1].image.pixel(0, 0), Some((0, 0, 0, 0)));
+    assert_eq!(animation.frames[1].image.pixel(1, 0), Some((0, 0, 255, 255)));
+
+    // The static decoder only returns the first frame
+    assert_eq!(ImageData::decode(gif).unwrap(), animation.frames[0].image);
+
+    assert_eq!(animation.duration(), 150);
+    assert_eq!(animation.frame_at(0), 0);
+    assert_eq!(animation.frame_at(49), 0);
+    assert_eq!(animation.frame_at(50), 1);
+    assert_eq!(animation.frame_at(150), 0);
+    assert_eq!(animation.frame_at(449), 1);
+    assert_eq!(animation.frame_at(450), 1);
+    assert_eq!(animation.frame_at(10000), 1);
+
+    // APNG: the default image is the first frame, the second frame blends a half transparent blue pixel over the green pixel
+    let extra = [
+        (b"acTL", vec![0, 0, 0, 2, 0, 0, 0, 0]),
+        (b"fcTL", apng_frame_control(0, (2, 1), (0, 0), (1, 10), 0, 0))
+    ];
+    let mut apng = png_file(2, 1, 8, 6, &extra, &[0, 255, 0, 0, 255, 0, 255, 0, 255]);
+    let end = apng.split_off(apng.len() - 12);
+    png_chunk(&mut apng, b"fcTL", &apng_frame_control(1, (1, 1), (1, 0), (3, 0), 0, 1));
+    let mut data = vec![0, 0, 0, 2];
+    data.extend_from_slice(&stored_zlib(&[0, 0, 0, 255, 128]));
+    png_chunk(&mut apng, b"fdAT", &data);
+    apng.extend_from_slice(&end);
+
+    let animation = AnimationData::decode(&apng).expect("APNG decoding failed");
+    assert_eq!(animation.frames.len(), 2);
+    assert_eq!(animation.loop_count, 0);
+    assert_eq!((animation.frames[0].delay, animation.frames[1].delay), (100, 30));
+    assert_eq!(animation.frames[0].image.pixel(0, 0), Some((255, 0, 0, 255)));
+    assert_eq!(animation.frames[1].image.pixel(0, 0), Some((255, 0, 0, 255)));
+    assert_eq!(animation.frames[1].image.pixel(1, 0), Some((0, 127, 128, 255)));
+    assert_eq!(animation.frame_at(1_000_000), 0);
+    assert_eq!(ImageData::decode(&apng).unwrap(), animation.frames[0].image);
+
+    // Static images are animations with a single frame
+    let image = png_file(1, 1, 8, 2, &[], &[0, 1, 2, 3]);
+    let animation = AnimationData::decode(&image).unwrap();
+    assert_eq!((animation.frames.len(), animation.frame_at(500)), (1, 0));
+
+    let truncated = &gif[..40];
+    assert!(AnimationData::decode(truncated).is_err());
+}
+
+#[test]
+fn test_image_compare() {
+    let a = ImageData::from_rgba(2, 1, &[10, 20, 30, 255,  100, 100, 100, 255]).unwrap();
+    let b = ImageData::from_rgba(2, 1, &[12, 20, 30, 255,  100, 140, 100, 255]).unwrap();
+
+    assert!(a.compare(&a, 0).matches());
+
+    let diff = a.compare(&b, 2);
+    assert_eq!(diff.different_pixels, 1);
+    assert_eq!(diff.max_difference, 40);
+    assert_eq!((diff.diff.width, diff.diff.height), (2, 1));
+    assert_eq!(diff.diff.pixel(1, 0), Some((255, 0, 0, 255)));
+    assert!(diff.diff.pixel(0, 0).map(|(r, g, b, _)| r == g && g == b).unwrap(), "Matching pixels must be gray");
+
+    assert!(a.compare(&b, 40).matches());
+
+    // Pixels outside of one of the images are different
+    let c = ImageData::from_rgba(1, 2, &[10, 20, 30, 255,  0, 0, 0, 255]).unwrap();
+    let diff = a.compare(&c, 255);
+    assert_eq!((diff.diff.width, diff.diff.height), (2, 2));
+    assert_eq!(diff.different_pixels, 3);
+}
+
+#[test]
+fn test_decoded_images() {
+    let raw = [0, 255, 0, 0, 255, 0, 255, 0, 128,   0, 0, 0, 255, 255, 255, 255, 255, 0];
+    let ui = setup_ui();
+
+    ui.pack_resource(&1000, MemoryImageT{ source: png_file(2, 2, 8, 6, &[], &raw) });
+    ui.pack_resource(&1001, MemoryImageT{ source: b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00;".to_vec() });
+    ui.commit().expect("Commit was not successful");
+
+    assert_eq!(ui.get::<Image>(&1000).unwrap().resource_type(), ImageType::Bitmap);
+    assert_eq!(ui.get::<Image>(&1001).unwrap().resource_type(), ImageType::Bitmap);
+
+    ui.pack_resource(&1002, MemoryImageT{ source: bmp_file(1, -1, 32, 0, 0, &[], &[], &[0, 0, 255, 128]) });
+    ui.commit().expect("Commit was not successful");
+    assert_eq!(ui.get::<Image>(&1002).unwrap().resource_type(), ImageType::Bitmap);
+
+    {
+        let image = ui.get::<Image>(&1000).unwrap();
+        let data = image.to_data().expect("Could not read the image pixels");
+        assert_eq!(data, ImageData::decode(&png_file(2, 2, 8, 6, &[], &raw)).unwrap());
+        assert_eq!(image.to_rgba().unwrap().len(), 16);
+
+        let mut out = Vec::new();
+        image.encode(ImageFormat::Png, &mut out).unwrap();
+        assert_eq!(ImageData::decode(&out).unwrap(), data);
+    }
+
+    ui.pack_resource(&1003, MemoryImageT{ source: png_file(2, 2, 8, 6, &[], &raw[..10]) });
+    assert!(ui.commit().is_err(), "A truncated image was accepted");
+
+    ui.pack_resource(&1004, MemoryImageT{ source: bmp_file(2, 2, 24, 0, 0, &[], &[], &[0; 10]) });
+    assert!(ui.commit().is_err(), "A truncated bitmap was accepted");
+}
+
+#[test]
+fn test_capture() {
+    let ui = setup_ui();
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, FrameT{position: (10, 10), size: (20, 20), visible: true, disabled: false, show_edge: false, accept_files: false, parent: 1000});
+    ui.pack_resource(&1002, default_font());
+    ui.commit().expect("Commit was not successful");
+
+    ui.get::<Window>(&1000).unwrap().set_background_color(Some((0, 128, 255)));
+
+    let image = capture(&ui, &1000).expect("Could not capture the window");
+    assert_eq!(image.resource_type(), ImageType::Bitmap);
+
+    let data = image.to_data().unwrap();
+    let (w, h) = ui.get::<Window>(&1000).unwrap().get_size();
+    assert_eq!((data.width, data.height), (w, h));
+    assert_eq!(data.pixel(w - 1, h - 1), Some((0, 128, 255, 255)));
+
+    let frame = capture(&ui, &1001).expect("Could not capture the frame");
+    assert_eq!(frame.to_data().unwrap().width, 20);
+
+    assert!(capture(&ui, &1002).is_err(), "A font was captured");
+
+    let mut golden = ::std::env::temp_dir();
+    golden.push("nwg_test_capture.png");
+    let _ = ::std::fs::remove_file(&golden);
+
+    assert!(compare_golden(&data, &golden, 0).unwrap().matches(), "The golden image was not created");
+    assert!(golden.exists());
+    assert!(compare_golden(&data, &golden, 0).unwrap().matches());
+
+    ui.get::<Window>(&1000).unwrap().set_background_color(Some((255, 0, 0)));
+    let changed = capture(&ui, &1000).unwrap().to_data().unwrap();
+    let diff = compare_golden(&changed, &golden, 8).unwrap();
+    assert!(!diff.matches());
+    assert!(golden.with_file_name("nwg_test_capture.diff.png").exists());
+    assert!(golden.with_file_name("nwg_test_capture.actual.png").exists());
+}
+
+#[test]
+fn test_animated_image_frame() {
+    let ui = setup_ui();
+    let red = ImageData::from_rgba(2, 2, &[255, 0, 0, 255].repeat(4)).unwrap();
+    let blue = ImageData::from_rgba(2, 2, &[0, 0, 255, 128].repeat(4)).unwrap();
+    let frames = vec![AnimationFrame{ image: red.clone(), delay: 50 }, AnimationFrame{ image: blue, delay: 50 }];
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, ImageFrameT{ position: (0, 0), size: (10, 10), visible: true, disabled: false, image: None, scaling: ImageScaling::Center, zoomable: false, parent: 1000 });
+    ui.pack_resource(&1002, FramesAnimationT{ animation: AnimationData{ frames: frames, loop_count: 0 } });
+    ui.pack_resource(&1003, MemoryAnimationT{ source: png_file(1, 1, 8, 2, &[], &[0, 1, 2, 3]) });
+    ui.pack_resource(&1004, MemoryImageT{ source: png_file(1, 1, 8, 2, &[], &[0, 1, 2, 3]) });
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let animation = ui.get::<Animation>(&1002).unwrap();
+        assert_eq!((animation.frame_count(), animation.get_size(), animation.loop_count()), (2, (2, 2), 0));
+        assert_eq!(animation.delays(), &[50, 50]);
+        assert_eq!(ui.get::<Animation>(&1003).unwrap().frame_count(), 1);
+    }
+
+    {
+        let frame = ui.get::<ImageFrame>(&1001).unwrap();
+        assert_eq!(frame.get_animation_frame(), None);
+        assert!(!frame.seek_animation(0));
+
+        frame.set_animation(&ui, Some(&1002)).expect("Could not set the animation");
+        assert_eq!(frame.get_animation_frame(), Some(0));
+        assert!(frame.is_animation_playing());
+
+        frame.pause_animation();
+        assert!(!frame.is_animation_playing());
+        assert!(frame.seek_animation(1));
+        assert_eq!(frame.get_animation_frame(), Some(1));
+        assert!(!frame.seek_animation(2));
+
+        frame.play_animation();
+        assert!(frame.is_animation_playing());
+
+        assert!(frame.set_animation(&ui, Some(&1004)).is_err(), "An image was accepted as an animation");
+
+        // Setting an image removes the animation
+        frame.set_image(&ui, Some(&1004)).unwrap();
+        assert_eq!(frame.get_animation_frame(), None);
+        assert_eq!(frame.get_image(&ui), Some(1004));
+
+        // A single frame is never playing
+        frame.set_animation(&ui, Some(&1003)).unwrap();
+        assert!(!frame.is_animation_playing());
+        frame.set_animation(&ui, Some(&1002)).unwrap();
+    }
+
+    // Unpacking the resource does not stop the animation
+    ui.unpack(&1002);
+    ui.commit().expect("Commit was not successful");
+    assert!(ui.get::<ImageFrame>(&1001).unwrap().seek_animation(1));
+
+    let small = ImageData::from_rgba(1, 1, &[0, 0, 0, 255]).unwrap();
+    let frames = vec![AnimationFrame{ image: red, delay: 10 }, AnimationFrame{ image: small, delay: 10 }];
+    ui.pack_resource(&1005, FramesAnimationT{ animation: AnimationData{ frames: frames, loop_count: 1 } });
+    assert!(ui.commit().is_err(), "Frames of different sizes were accepted");
+
+    ui.pack_resource(&1006, FramesAnimationT{ animation: AnimationData{ frames: Vec::new(), loop_count: 1 } });
+    assert!(ui.commit().is_err(), "An animation without frames was accepted");
+}
+
+#[test]
+fn test_image_frame_viewer() {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    let ui = setup_ui();
+    let views: Rc<RefCell<Vec<((f64, f64), (f64, f64, f64, f64))>>> = Rc::new(RefCell::new(Vec::new()));
+    let views_cb = views.clone();
+
+    let mut png = Vec::new();
+    ImageData::from_rgba(40, 20, &[255, 0, 0, 255].repeat(800)).unwrap().encode(ImageFormat::Png, &mut png).unwrap();
+
+    ui.pack_control(&1000, window());
+    ui.pack_resource(&1001, MemoryImageT{ source: png });
+    ui.pack_resource(&1002, OemImageT{ source: OemImage::Icon(OemIcon::Information), size: (0, 0) });
+    ui.pack_control(&1003, ImageFrameT{ position: (0, 0), size: (20, 20), visible: true, disabled: false, image: Some(1001), scaling: ImageScaling::Fit, zoomable: true, parent: 1000 });
+    ui.pack_control(&1004, ImageFrameT{ position: (0, 0), size: (20, 20), visible: true, disabled: false, image: Some(1002), scaling: ImageScaling::Center, zoomable: false, parent: 1000 });
+    ui.bind(&1003, &5000, nwge::image_frame::ZoomChanged, move |_, _, _, args| {
+        if let &EventArgs::Zoom{scale, visible} = args { views_cb.borrow_mut().push((scale, visible)); }
+    });
+
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let frame = ui.get::<ImageFrame>(&1003).unwrap();
+        assert_eq!((frame.get_scaling(), frame.get_zoomable()), (ImageScaling::Fit, true));
+        assert_eq!(frame.get_zoom(), (0.5, 0.5));
+        assert_eq!(frame.get_visible_rect(), (0.0, 0.0, 40.0, 20.0));
+
+        frame.set_scaling(ImageScaling::Fill).unwrap();
+        assert_eq!(frame.get_zoom(), (1.0, 1.0));
+        assert_eq!(frame.get_visible_rect(), (10.0, 0.0, 20.0, 20.0));
+        assert_eq!(views.borrow().last(), Some(&((1.0, 1.0), (10.0, 0.0, 20.0, 20.0))));
+
+        // The bitmap cannot leave an empty border
+        frame.pan(-100, 0);
+        assert_eq!(frame.get_visible_rect(), (20.0, 0.0, 20.0, 20.0));
+
+        // The pixel under the point stays in place
+        frame.zoom_at(2.0, (0, 0));
+        assert_eq!(frame.get_zoom(), (2.0, 2.0));
+        assert_eq!(frame.get_visible_rect(), (20.0, 0.0, 10.0, 10.0));
+
+        frame.set_zoom(1000.0);
+        assert_eq!(frame.get_zoom(), (64.0, 64.0));
+
+        // The view is kept when the frame is resized after a zoom
+        frame.set_size(10, 10);
+        assert_eq!(frame.get_zoom(), (64.0, 64.0));
+
+        frame.reset_view();
+        assert_eq!(frame.get_zoom(), (0.5, 0.5));
+
+        let count = views.borrow().len();
+        frame.reset_view();
+        assert_eq!(views.borrow().len(), count, "An unchanged view was notified");
+
+        frame.set_image(&ui, None).unwrap();
+        assert_eq!(frame.get_visible_rect(), (0.0, 0.0, 0.0, 0.0));
+
+        frame.set_zoomable(false).unwrap();
+        frame.set_scaling(ImageScaling::Center).unwrap();
+        assert_eq!((frame.get_scaling(), frame.get_zoomable()), (ImageScaling::Center, false));
+
+        // Frames that display icons cannot be scaled
+        let icon_frame = ui.get::<ImageFrame>(&1004).unwrap();
+        assert!(icon_frame.set_scaling(ImageScaling::Fit).is_err());
+        assert!(icon_frame.set_zoomable(true).is_err());
+        icon_frame.set_zoom(2.0);
+        assert_eq!(icon_frame.get_zoom(), (1.0, 1.0));
+    }
+
+    ui.pack_control(&1005, ImageFrameT{ position: (0, 0), size: (20, 20), visible: true, disabled: false, image: Some(1002), scaling: ImageScaling::Fit, zoomable: false, parent: 1000 });
+    assert!(ui.commit().is_err(), "An icon frame was scaled");
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_path_data() {
+    use nwg::constants::canvas::*;
+
+    let path = PathData::new()
+        .line_to(10.0, 0.0)
+        .move_to(5.0, 5.0)
+        .move_to(20.0, 20.0)
+        .quadratic_to((25.0, 15.0), (30.0, 20.0))
+        .cubic_to((30.0, 30.0), (20.0, 30.0), (20.0, 25.0))
+        .close()
+        .arc_to((40.0, 20.0), (10.0, 10.0), 0.0, ArcSize::Small, SweepDirection::Clockwise)
+        .hollow()
+        .fill_mode(FillMode::Winding);
+
+    // A segment without a figure starts at the origin and a move without segments is replaced
+    assert_eq!(path.fill_mode, FillMode::Winding);
+    assert_eq!(path.figures.len(), 3);
+    assert_eq!(path.figures[0].start, (0.0, 0.0));
+    assert_eq!(path.figures[0].segments, vec![PathSegment::Line((10.0, 0.0))]);
+    assert!(!path.figures[0].closed);
+
+    assert_eq!(path.figures[1].start, (20.0, 20.0));
+    assert_eq!(path.figures[1].segments.len(), 2);
+    assert!(path.figures[1].closed && path.figures[1].filled);
+
+    // A segment after a closed figure starts a new figure where the closed figure started
+    assert_eq!(path.figures[2].start, (20.0, 20.0));
+    assert!(!path.figures[2].closed && !path.figures[2].filled);
+    assert_eq!(path.current_point(), Some((40.0, 20.0)));
+    assert_eq!(path.clone().close().current_point(), Some((20.0, 20.0)));
+
+    let line = PathData::polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true);
+    assert_eq!(line.figures.len(), 1);
+    assert_eq!(line.figures[0].segments, vec![PathSegment::Line((10.0, 0.0)), PathSegment::Line((10.0, 10.0))]);
+    assert!(line.figures[0].closed);
+
+    assert_eq!(PathData::polyline(&[], false), PathData::new());
+    assert_eq!(PathData::new().current_point(), None);
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_text_layout() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let format = |canvas: u64, size: f32| TextFormatT{ canvas: canvas, family: "Arial", size: size, weight: FONT_WEIGHT_NORMAL, style: FontStyle::Normal,
+      text_align: TextAlignment::Leading, paragraph_align: ParagraphAlignment::Near, wrapping: WordWrapping::Wrap };
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, format(1001, 16.0));
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let canvas = ui.get::<Canvas<u64>>(&1001).unwrap();
+        let mut layout = canvas.create_text_layout(&ui, &1002, "Hello world", (1000.0, 100.0)).unwrap();
+        assert_eq!(layout.len(), 11);
+
+        let metrics = layout.get_metrics();
+        assert_eq!(metrics.line_count, 1);
+        assert_eq!(metrics.layout_size, (1000.0, 100.0));
+        assert!(metrics.width > 0.0 && metrics.height > 0.0);
+
+        // The caret moves forward with the text position
+        let first = layout.hit_test_position(0, false);
+        let last = layout.hit_test_position(10, true);
+        assert_eq!(first.point.0, 0.0);
+        assert!(last.point.0 > first.point.0 && (last.point.0 - metrics.width).abs() < 0.5);
+
+        let hit = layout.hit_test_point(last.point.0 - 1.0, 1.0);
+        assert_eq!((hit.position, hit.trailing, hit.inside), (10, true, true));
+        assert!(!layout.hit_test_point(metrics.width + 50.0, 1.0).inside);
+        assert_eq!(layout.hit_test_range(0..5).len(), 1);
+        assert_eq!(layout.hit_test_range(5..5).len(), 0);
+
+        // Narrowing the box wraps the text
+        layout.set_max_size(metrics.width / 2.0, 100.0);
+        assert_eq!(layout.get_max_size(), (metrics.width / 2.0, 100.0));
+        assert!(layout.get_metrics().line_count > 1);
+
+        assert!(canvas.create_text_layout(&ui, &1000, "", (10.0, 10.0)).is_err());
+    }
+
+    // Text formats need a canvas and a valid size
+    ui.pack_resource(&1003, format(1000, 16.0));
+    assert!(ui.commit().is_err(), "A text format was created without a canvas");
+
+    ui.pack_resource(&1004, format(1001, 0.0));
+    assert!(ui.commit().is_err(), "A text format was created with an empty size");
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_gradient_and_bitmap_brushes() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let stops = vec![GradientStop{ position: 0.0, color: (1.0, 0.0, 0.0, 1.0) }, GradientStop{ position: 1.0, color: (0.0, 0.0, 1.0, 1.0) }];
+    let mut png = Vec::new();
+    ImageData::from_rgba(2, 1, &[0, 255, 0, 255, 255, 255, 255, 255]).unwrap().encode(ImageFormat::Png, &mut png).unwrap();
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, MemoryImageT{ source: png });
+    ui.pack_resource(&1003, BrushT{ canvas: 1001, btype: BrushType::LinearGradientBrush(LinearGradientBrush{ start: (0.0, 0.0), end: (100.0, 0.0), stops: stops.clone(), extend: ExtendMode::Clamp }) });
+    ui.pack_resource(&1004, BrushT{ canvas: 1001, btype: BrushType::RadialGradientBrush(RadialGradientBrush{ center: (50.0, 5.0), origin_offset: (0.0, 0.0), radius: (50.0, 50.0), stops: stops.clone(), extend: ExtendMode::Clamp }) });
+    ui.pack_resource(&1005, BrushT{ canvas: 1001, btype: BrushType::BitmapBrush(BitmapBrush{ image: 1002, extend: (ExtendMode::Wrap, ExtendMode::Wrap), interpolation: BitmapInterpolation::NearestNeighbor }) });
+    ui.commit().expect("Commit was not successful");
+
+    {
+        let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+        let full = Rectangle{ left: 0.0, top: 0.0, right: 100.0, bottom: 10.0 };
+
+        let linear = canvas.render_offscreen(&ui, 100, 10, |r| r.fill_rectangle(&1003, &full)).unwrap();
+        let (r, _, b, a) = linear.pixel(1, 5).unwrap();
+        assert!(r > 240 && b < 15 && a == 255, "Linear gradient start is {:?}", linear.pixel(1, 5));
+        let (r, _, b, _) = linear.pixel(98, 5).unwrap();
+        assert!(r < 15 && b > 240, "Linear gradient end is {:?}", linear.pixel(98, 5));
+
+        let radial = canvas.render_offscreen(&ui, 100, 10, |r| r.fill_rectangle(&1004, &full)).unwrap();
+        let (center, edge) = (radial.pixel(50, 5).unwrap(), radial.pixel(0, 5).unwrap());
+        assert!(center.0 > 240 && center.2 < 15, "Radial gradient center is {:?}", center);
+        assert!(edge.0 < 15 && edge.2 > 240, "Radial gradient edge is {:?}", edge);
+
+        // The 2x1 bitmap is repeated over the whole rectangle
+        let tiled = canvas.render_offscreen(&ui, 100, 10, |r| r.fill_rectangle(&1005, &full)).unwrap();
+        for x in [0, 2, 50, 98].iter() {
+            assert_eq!(tiled.pixel(*x, 7), Some((0, 255, 0, 255)));
+            assert_eq!(tiled.pixel(*x + 1, 7), Some((255, 255, 255, 255)));
+        }
+    }
+
+    ui.pack_resource(&1006, BrushT{ canvas: 1001, btype: BrushType::LinearGradientBrush(LinearGradientBrush{ start: (0.0, 0.0), end: (1.0, 0.0), stops: Vec::new(), extend: ExtendMode::Clamp }) });
+    assert!(ui.commit().is_err(), "A gradient without stops was accepted");
+
+    ui.pack_resource(&1007, BrushT{ canvas: 1001, btype: BrushType::BitmapBrush(BitmapBrush{ image: 1003, extend: (ExtendMode::Clamp, ExtendMode::Clamp), interpolation: BitmapInterpolation::Linear }) });
+    assert!(ui.commit().is_err(), "A bitmap brush was created from a brush");
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_bitmaps() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let rgba = [255, 0, 0, 255,  0, 255, 0, 255,  0, 0, 255, 255,  255, 255, 255, 255];
+    let mut png = Vec::new();
+    ImageData::from_rgba(2, 2, &rgba).unwrap().encode(ImageFormat::Png, &mut png).unwrap();
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, MemoryImageT{ source: png });
+    ui.pack_resource(&1003, BitmapT{ canvas: 1001, image: 1002 });
+    ui.pack_resource(&1004, RgbaBitmapT{ canvas: 1001, size: (2, 2), rgba: rgba.to_vec() });
+    ui.commit().expect("Commit was not successful");
+
+    assert_eq!(ui.get::<Bitmap>(&1003).unwrap().get_size(), (2, 2));
+
+    {
+        let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+        let dest = Rectangle{ left: 0.0, top: 0.0, right: 4.0, bottom: 4.0 };
+        let nearest = BitmapInterpolation::NearestNeighbor;
+
+        // Both bitmaps hold the same pixels, scaled two times
+        for id in [1003, 1004].iter() {
+            let image = canvas.render_offscreen(&ui, 4, 4, |r| r.draw_bitmap(id, &dest, None, 1.0, nearest)).unwrap();
+            assert_eq!(image.pixel(1, 1), Some((255, 0, 0, 255)));
+            assert_eq!(image.pixel(2, 1), Some((0, 255, 0, 255)));
+            assert_eq!(image.pixel(1, 2), Some((0, 0, 255, 255)));
+            assert_eq!(image.pixel(3, 3), Some((255, 255, 255, 255)));
+        }
+
+        // The source rectangle is in pixels of the bitmap
+        let src = Rectangle{ left: 1.0, top: 1.0, right: 2.0, bottom: 2.0 };
+        let image = canvas.render_offscreen(&ui, 4, 4, |r| r.draw_bitmap(&1004, &dest, Some(&src), 0.5, nearest)).unwrap();
+        let (r, g, b, a) = image.pixel(0, 0).unwrap();
+        assert!((a as i32 - 128).abs() <= 1 && r == a && g == a && b == a, "Half transparent white is {:?}", (r, g, b, a));
+        assert_eq!(image.pixel(3, 3), image.pixel(0, 0));
+
+        let result = canvas.render_offscreen(&ui, 4, 4, |r| r.draw_bitmap(&1002, &dest, None, 1.0, nearest));
+        assert!(result.is_err(), "An image resource was drawn as a canvas bitmap");
+
+        // The DPI is a property of the canvas render target
+        canvas.set_dpi(192.0, 192.0);
+        assert_eq!(canvas.get_dpi(), (192.0, 192.0));
+    }
+
+    ui.pack_resource(&1005, RgbaBitmapT{ canvas: 1001, size: (2, 2), rgba: vec![0; 12] });
+    assert!(ui.commit().is_err(), "A bitmap was created with too few pixels");
+
+    ui.pack_resource(&1006, BitmapT{ canvas: 1000, image: 1002 });
+    assert!(ui.commit().is_err(), "A bitmap was created without a canvas");
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_matrix() {
+    use nwg::constants::canvas::*;
+
+    fn near(a: (f32, f32), b: (f32, f32)) -> bool { (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4 }
+
+    let id = Matrix::identity();
+    assert!(id.is_identity());
+    assert_eq!(id.transform_point((3.0, 4.0)), (3.0, 4.0));
+    assert_eq!(Matrix::from_array(&[[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]).to_array(), [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+
+    // Each transformation keeps its center in place
+    assert_eq!(Matrix::translation(10.0, -5.0).transform_point((1.0, 1.0)), (11.0, -4.0));
+    assert_eq!(Matrix::scaling(2.0, 3.0, (10.0, 10.0)).transform_point((10.0, 10.0)), (10.0, 10.0));
+    assert_eq!(Matrix::scaling(2.0, 3.0, (10.0, 10.0)).transform_point((11.0, 11.0)), (12.0, 13.0));
+    assert!(near(Matrix::rotation(90.0, (0.0, 0.0)).transform_point((1.0, 0.0)), (0.0, 1.0)));
+    assert!(near(Matrix::rotation(90.0, (5.0, 5.0)).transform_point((5.0, 5.0)), (5.0, 5.0)));
+    assert!(near(Matrix::rotation(180.0, (5.0, 5.0)).transform_point((6.0, 5.0)), (4.0, 5.0)));
+    assert!(near(Matrix::skewing(45.0, 0.0, (0.0, 0.0)).transform_point((0.0, 2.0)), (2.0, 2.0)));
+    assert!(near(Matrix::skewing(0.0, 45.0, (1.0, 1.0)).transform_point((1.0, 1.0)), (1.0, 1.0)));
+
+    // Matrices are applied from left to right
+    let m = Matrix::translation(10.0, 0.0).rotate(90.0, (0.0, 0.0));
+    assert!(near(m.transform_point((0.0, 0.0)), (0.0, 10.0)));
+    assert_eq!(m, Matrix::translation(10.0, 0.0) * Matrix::rotation(90.0, (0.0, 0.0)));
+    let m = Matrix::rotation(90.0, (0.0, 0.0)).translate(10.0, 0.0);
+    assert!(near(m.transform_point((0.0, 0.0)), (10.0, 0.0)));
+
+    // Inverting
+    let m = Matrix::scaling(2.0, 4.0, (3.0, 1.0)).rotate(30.0, (1.0, 2.0)).skew(10.0, 5.0, (0.0, 0.0)).translate(7.0, -3.0);
+    let inv = m.invert().unwrap();
+    assert!(near(inv.transform_point(m.transform_point((12.0, -8.0))), (12.0, -8.0)));
+    let round = m * inv;
+    assert!(near((round.m11, round.m12), (1.0, 0.0)) && near((round.m21, round.m22), (0.0, 1.0)) && near((round.dx, round.dy), (0.0, 0.0)));
+    assert_eq!(Matrix::scaling(0.0, 1.0, (0.0, 0.0)).invert(), None);
+    assert_eq!(Matrix::scaling(2.0, 3.0, (0.0, 0.0)).determinant(), 6.0);
+
+    let r = Matrix::rotation(90.0, (0.0, 0.0)).transform_rect(&Rectangle{ left: 0.0, top: 0.0, right: 10.0, bottom: 20.0 });
+    assert!(near((r.left, r.top), (-20.0, 0.0)) && near((r.right, r.bottom), (0.0, 10.0)), "Rotated rectangle is {:?}", r);
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_transform_clip_and_layers() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let white = BrushType::SolidBrush(SolidBrush{ color: (1.0, 1.0, 1.0, 1.0) });
+    let mask = PathData::polyline(&[(0.0, 0.0), (5.0, 0.0), (5.0, 10.0), (0.0, 10.0)], true);
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, BrushT{ canvas: 1001, btype: white });
+    ui.pack_resource(&1003, PathT{ canvas: 1001, path: mask });
+    ui.commit().expect("Commit was not successful");
+
+    let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+    let unit = Rectangle{ left: 0.0, top: 0.0, right: 1.0, bottom: 1.0 };
+    let full = Rectangle{ left: 0.0, top: 0.0, right: 10.0, bottom: 10.0 };
+
+    // Pushed transforms are applied before the current one
+    let image = canvas.render_offscreen(&ui, 10, 10, |r| {
+        r.set_matrix(&Matrix::translation(5.0, 0.0));
+        r.push_transform(&Matrix::scaling(2.0, 2.0, (0.0, 0.0)));
+        assert_eq!(r.get_matrix(), Matrix::scaling(2.0, 2.0, (0.0, 0.0)).translate(5.0, 0.0));
+        r.fill_rectangle(&1002, &unit)?;
+        r.pop_transform()?;
+        assert_eq!(r.get_matrix(), Matrix::translation(5.0, 0.0));
+        assert!(r.pop_transform().is_err());
+        r.fill_rectangle(&1002, &Rectangle{ left: 0.0, top: 5.0, right: 1.0, bottom: 6.0 })
+    }).unwrap();
+    assert_eq!(image.pixel(6, 1), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(7, 1), Some((0, 0, 0, 0)));
+    assert_eq!(image.pixel(5, 5), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(0, 0), Some((0, 0, 0, 0)));
+
+    // Clips and layers, popped in the reverse order of the pushes
+    let image = canvas.render_offscreen(&ui, 10, 10, |r| {
+        r.push_clip(&Rectangle{ left: 0.0, top: 0.0, right: 10.0, bottom: 5.0 });
+        r.push_layer(0.5, Some(&1003), None)?;
+        assert!(r.pop_clip().is_err());
+        r.fill_rectangle(&1002, &full)?;
+        r.pop_layer()?;
+        assert!(r.pop_layer().is_err());
+        r.pop_clip()
+    }).unwrap();
+    let (c, a) = (image.pixel(2, 2).unwrap().0, image.pixel(2, 2).unwrap().3);
+    assert!((a as i32 - 128).abs() <= 1 && c == a, "The layer pixel is {:?}", image.pixel(2, 2));
+    assert_eq!(image.pixel(7, 2), Some((0, 0, 0, 0)));
+    assert_eq!(image.pixel(2, 7), Some((0, 0, 0, 0)));
+
+    // Unbalanced pushes are popped by the renderer
+    let image = canvas.render_offscreen(&ui, 10, 10, |r| {
+        r.push_clip(&unit);
+        r.fill_rectangle(&1002, &full)
+    }).unwrap();
+    assert_eq!(image.pixel(0, 0), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(1, 1), Some((0, 0, 0, 0)));
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_scene_hit_testing() {
+    use nwg::constants::canvas::*;
+
+    let square = |size: f32| Shape::Rectangle(Rectangle{ left: 0.0, top: 0.0, right: size, bottom: size });
+
+    // Flattened curves stay close to the shapes they approximate
+    let circle = Shape::Ellipse(Ellipse{ center: (50.0, 50.0), radius: (20.0, 20.0) }).to_path();
+    for f in circle.flatten(0.1) {
+        assert!(f.closed && f.points.len() > 8);
+        for p in f.points.iter() {
+            let d = ((p.0 - 50.0).powi(2) + (p.1 - 50.0).powi(2)).sqrt();
+            assert!((d - 20.0).abs() < 0.01, "Point {:?} is not on the circle", p);
+        }
+    }
+    let b = circle.bounds(0.1).unwrap();
+    assert!((b.left - 30.0).abs() < 0.01 && (b.bottom - 70.0).abs() < 0.01, "Circle bounds are {:?}", b);
+    assert!(circle.fill_contains((60.0, 60.0), 0.1) && !circle.fill_contains((66.0, 66.0), 0.1));
+    assert!(circle.stroke_contains((50.0, 30.5), 2.0, 0.1) && !circle.stroke_contains((50.0, 50.0), 2.0, 0.1));
+
+    let quad = PathData::new().move_to(0.0, 0.0).quadratic_to((10.0, 20.0), (20.0, 0.0));
+    assert!(quad.stroke_contains((10.0, 10.0), 0.2, 0.01));
+
+    // Fill modes
+    let rings = PathData::polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)], true)
+        .move_to(2.0, 2.0).line_to(8.0, 2.0).line_to(8.0, 8.0).line_to(2.0, 8.0).close();
+    assert!(!rings.fill_contains((5.0, 5.0), 0.1) && rings.fill_contains((1.0, 5.0), 0.1));
+    assert!(rings.clone().fill_mode(FillMode::Winding).fill_contains((5.0, 5.0), 0.1));
+
+    // Topmost node wins, z-order before insertion order
+    let mut scene: Scene<u64> = Scene::new();
+    scene.insert(1, SceneNode::new(square(100.0)).fill(10));
+    scene.insert(2, SceneNode::new(square(20.0)).fill(10).transform(Matrix::translation(10.0, 10.0)).z(1));
+    scene.insert(3, SceneNode::new(square(20.0)).fill(10).transform(Matrix::translation(20.0, 20.0)));
+    assert_eq!(scene.len(), 3);
+    assert_eq!(scene.draw_order(), vec![1, 3, 2]);
+    assert_eq!(scene.hit_test((25.0, 25.0)), Some(2));
+    assert_eq!(scene.hit_test_all((25.0, 25.0)), vec![2, 3, 1]);
+    assert_eq!(scene.hit_test((35.0, 35.0)), Some(3));
+    assert_eq!(scene.hit_test((150.0, 50.0)), None);
+
+    // Children are moved and hidden with their parent
+    scene.insert(4, SceneNode::new(square(5.0)).fill(10).parent(3).transform(Matrix::scaling(2.0, 2.0, (0.0, 0.0))).z(2));
+    assert_eq!(scene.world_transform(4), Some(Matrix::scaling(2.0, 2.0, (0.0, 0.0)).translate(20.0, 20.0)));
+    assert_eq!(scene.bounds(4), Some(Rectangle{ left: 20.0, top: 20.0, right: 30.0, bottom: 30.0 }));
+    assert_eq!(scene.hit_test((29.0, 29.0)), Some(4));
+    scene.set_visible(3, false);
+    assert!(!scene.is_visible(4));
+    assert_eq!(scene.hit_test((29.0, 29.0)), Some(2));
+    scene.set_visible(3, true);
+
+    // Rotated nodes are hit in their own coordinates
+    scene.set_transform(3, Matrix::rotation(45.0, (10.0, 10.0)).translate(50.0, 50.0));
+    assert_eq!(scene.hit_test((60.0, 72.0)), Some(3));
+    assert_eq!(scene.hit_test((51.0, 51.0)), Some(1));
+
+    // Outlines, margins and nodes that let the mouse through
+    scene.insert(5, SceneNode::new(Shape::Line{ start: (0.0, 90.0), end: (100.0, 90.0) }).stroke(10, 2.0).z(3));
+    assert_eq!(scene.hit_test((50.0, 90.5)), Some(5));
+    assert_eq!(scene.hit_test((50.0, 93.0)), Some(1));
+    scene.set_hit_margin(3.0);
+    assert_eq!(scene.hit_test((50.0, 93.0)), Some(5));
+    scene.get_mut(5).unwrap().hit_testable = false;
+    assert_eq!(scene.hit_test((50.0, 90.0)), Some(1));
+
+    // Removing a node removes its children
+    assert!(scene.remove(3).is_some());
+    assert!(!scene.contains(4));
+    assert!(scene.remove(3).is_none());
+    assert!(scene.take_dirty());
+    assert!(!scene.take_dirty());
+
+    // Mouse tracking
+    assert_eq!(scene.mouse_move((15.0, 15.0)), vec![SceneEvent::Enter(2)]);
+    assert_eq!(scene.mouse_move((16.0, 15.0)), vec![]);
+    assert_eq!(scene.mouse_down((15.0, 15.0), MouseButton::Left), vec![SceneEvent::MouseDown(2, MouseButton::Left)]);
+    assert_eq!(scene.mouse_up((50.0, 50.0), MouseButton::Left), vec![SceneEvent::Leave(2), SceneEvent::Enter(1), SceneEvent::MouseUp(1, MouseButton::Left)]);
+    scene.mouse_down((50.0, 50.0), MouseButton::Right);
+    assert_eq!(scene.mouse_up((60.0, 60.0), MouseButton::Right), vec![SceneEvent::MouseUp(1, MouseButton::Right), SceneEvent::Click(1, MouseButton::Right)]);
+    assert_eq!(scene.get_hovered(), Some(1));
+    assert_eq!(scene.mouse_leave(), vec![SceneEvent::Leave(1)]);
+    assert!(!scene.take_dirty());
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_scene() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let white = BrushType::SolidBrush(SolidBrush{ color: (1.0, 1.0, 1.0, 1.0) });
+    let red = BrushType::SolidBrush(SolidBrush{ color: (1.0, 0.0, 0.0, 1.0) });
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, BrushT{ canvas: 1001, btype: white });
+    ui.pack_resource(&1003, BrushT{ canvas: 1001, btype: red });
+    ui.commit().expect("Commit was not successful");
+
+    let mut scene = Scene::new();
+    scene.insert(1, SceneNode::new(Shape::Rectangle(Rectangle{ left: 0.0, top: 0.0, right: 4.0, bottom: 4.0 })).fill(1002));
+    scene.insert(2, SceneNode::new(Shape::Ellipse(Ellipse{ center: (2.0, 2.0), radius: (2.0, 2.0) })).fill(1003)
+        .transform(Matrix::translation(4.0, 4.0)));
+
+    let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+    assert!(canvas.update_scene(|s| s.len()).is_none());
+    canvas.set_scene(Some(scene));
+    assert_eq!(canvas.scene().map(|s| s.len()), Some(2));
+    assert_eq!(canvas.update_scene(|s| s.set_z(1, 5)), Some(true));
+
+    let image = canvas.render_offscreen(&ui, 10, 10, |r| {
+        let scene = r.scene().unwrap().clone();
+        r.draw_scene(&scene)
+    }).unwrap();
+    assert_eq!(image.pixel(1, 1), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(6, 6), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(9, 1), Some((0, 0, 0, 0)));
+
+    // Drawing a node with a missing resource fails
+    let mut broken = Scene::new();
+    broken.insert(1, SceneNode::new(Shape::Text{ text: "Hi".to_string(), bounds: Rectangle{ left: 0.0, top: 0.0, right: 10.0, bottom: 10.0 } }).fill(1002));
+    assert!(canvas.render_offscreen(&ui, 10, 10, |r| r.draw_scene(&broken)).is_err());
+
+    canvas.set_scene(None);
+    assert!(canvas.scene().is_none());
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_software_renderer() {
+    use nwg::constants::canvas::*;
+    use std::rc::Rc;
+
+    let red = Paint::Solid((1.0, 0.0, 0.0, 1.0));
+    let white = (1.0, 1.0, 1.0, 1.0);
+    let rect = |left: f32, top: f32, right: f32, bottom: f32| Rectangle{ left: left, top: top, right: right, bottom: bottom };
+    let pen = |join: LineJoin, cap: CapStyle, dash: DashStyle| Pen{ start_cap: cap, end_cap: cap, dash_cap: cap, line_join: join,
+        miter_limit: 10.0, dash_style: dash, dash_offset: 0.0 };
+
+    assert!(SoftwareRenderer::new(0, 10).is_err());
+
+    // Shapes are antialiased: a pixel half covered by a rectangle is painted at half opacity
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.clear(white);
+    r.fill_shape(&Shape::Rectangle(rect(2.0, 2.0, 10.5, 10.0)), &red).unwrap();
+    let image = r.get_image();
+    assert_eq!(image.pixel(5, 5), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(10, 5), Some((255, 128, 128, 255)));
+    assert_eq!(image.pixel(11, 5), Some((255, 255, 255, 255)));
+    assert_eq!(image.pixel(5, 1), Some((255, 255, 255, 255)));
+
+    // The area painted by an ellipse is close to the area of the ellipse
+    let mut r = SoftwareRenderer::new(40, 40).unwrap();
+    r.fill_shape(&Shape::Ellipse(Ellipse{ center: (20.0, 20.0), radius: (15.0, 10.0) }), &red).unwrap();
+    let image = r.get_image();
+    let area: f32 = image.data.chunks(4).map(|p| p[3] as f32 / 255.0).sum();
+    assert!((area / (3.14159 * 150.0) - 1.0).abs() < 0.01, "Ellipse area is {}", area);
+    assert_eq!(image.pixel(20, 20), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(3, 20), Some((0, 0, 0, 0)));
+
+    // Caps, joins and dashes
+    let line = Shape::Line{ start: (4.0, 10.0), end: (16.0, 10.0) };
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.stroke_shape(&line, &red, None, 2.0).unwrap();
+    let image = r.get_image();
+    assert!(image.pixel(4, 9).unwrap().3 == 255 && image.pixel(15, 10).unwrap().3 == 255);
+    assert!(image.pixel(3, 9).unwrap().3 == 0 && image.pixel(10, 8).unwrap().3 == 0);
+
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.stroke_shape(&line, &red, Some(&pen(LineJoin::Miter, CapStyle::Square, DashStyle::Solid)), 2.0).unwrap();
+    assert!(r.get_image().pixel(3, 9).unwrap().3 == 255);
+
+    let corner = Shape::Polyline{ points: vec![(5.0, 5.0), (15.0, 5.0), (15.0, 15.0)], closed: false };
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.stroke_shape(&corner, &red, Some(&pen(LineJoin::Miter, CapStyle::Flat, DashStyle::Solid)), 4.0).unwrap();
+    assert_eq!(r.get_image().pixel(16, 3), Some((255, 0, 0, 255)));
+
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.stroke_shape(&corner, &red, Some(&pen(LineJoin::Bevel, CapStyle::Flat, DashStyle::Solid)), 4.0).unwrap();
+    assert_eq!(r.get_image().pixel(16, 3), Some((0, 0, 0, 0)));
+    assert_eq!(r.get_image().pixel(15, 4), Some((255, 0, 0, 255)));
+
+    // Dashes are two line widths long and separated by two line widths
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    let dashed = Shape::Line{ start: (0.0, 10.0), end: (20.0, 10.0) };
+    r.stroke_shape(&dashed, &red, Some(&pen(LineJoin::Miter, CapStyle::Flat, DashStyle::Dash)), 2.0).unwrap();
+    let image = r.get_image();
+    let painted: Vec<bool> = (0..20).map(|x| image.pixel(x, 10).unwrap().3 == 255).collect();
+    assert_eq!(painted, (0..20).map(|x| (x / 4) % 2 == 0).collect::<Vec<bool>>());
+
+    // Transformations and clips
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.set_matrix(&Matrix::translation(10.0, 0.0));
+    r.push_clip(&rect(0.0, 0.0, 5.0, 20.0));
+    r.fill_shape(&Shape::Rectangle(rect(-10.0, 0.0, 10.0, 20.0)), &red).unwrap();
+    r.pop_clip();
+    let image = r.get_image();
+    assert!(image.pixel(9, 5).unwrap().3 == 0 && image.pixel(10, 5).unwrap().3 == 255);
+    assert!(image.pixel(14, 5).unwrap().3 == 255 && image.pixel(15, 5).unwrap().3 == 0);
+
+    // Gradients are evaluated at the center of the pixels
+    let stops = vec![GradientStop{ position: 0.0, color: (0.0, 0.0, 0.0, 1.0) }, GradientStop{ position: 1.0, color: (1.0, 1.0, 1.0, 1.0) }];
+    let linear = Paint::Linear(LinearGradientBrush{ start: (0.0, 0.0), end: (20.0, 0.0), stops: stops, extend: ExtendMode::Clamp });
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.fill_shape(&Shape::Rectangle(rect(0.0, 0.0, 20.0, 20.0)), &linear).unwrap();
+    let image = r.get_image();
+    assert_eq!(image.pixel(0, 0), Some((6, 6, 6, 255)));
+    assert_eq!(image.pixel(10, 0), Some((134, 134, 134, 255)));
+
+    // Layers are blended with their opacity when they are popped
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.clear(white);
+    r.push_layer(0.5, None, Some(&rect(0.0, 0.0, 10.0, 20.0))).unwrap();
+    r.fill_shape(&Shape::Rectangle(rect(0.0, 0.0, 20.0, 20.0)), &red).unwrap();
+    assert_eq!(r.get_image().pixel(5, 5), Some((255, 128, 128, 255)));
+    r.pop_layer();
+    let image = r.get_image();
+    assert_eq!(image.pixel(5, 5), Some((255, 128, 128, 255)));
+    assert_eq!(image.pixel(15, 5), Some((255, 255, 255, 255)));
+
+    // Bitmaps are scaled to their destination and bitmap brushes repeat their image
+    let mut checker = ImageData::new(2, 2);
+    checker.data = vec![0, 0, 255, 255,  255, 0, 0, 255,  255, 0, 0, 255,  0, 0, 255, 255];
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.draw_image(&checker, &rect(0.0, 0.0, 10.0, 10.0), None, 1.0, BitmapInterpolation::NearestNeighbor).unwrap();
+    let image = r.get_image();
+    assert_eq!(image.pixel(2, 2), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(7, 2), Some((0, 0, 255, 255)));
+    assert_eq!(image.pixel(12, 2), Some((0, 0, 0, 0)));
+
+    let tiles = Paint::Bitmap{ image: Rc::new(checker), extend: (ExtendMode::Wrap, ExtendMode::Wrap), interpolation: BitmapInterpolation::NearestNeighbor };
+    let mut r = SoftwareRenderer::new(20, 20).unwrap();
+    r.fill_shape(&Shape::Rectangle(rect(0.0, 0.0, 20.0, 20.0)), &tiles).unwrap();
+    let image = r.get_image();
+    assert_eq!(image.pixel(12, 17), Some((0, 0, 255, 255)));
+    assert_eq!(image.pixel(13, 17), Some((255, 0, 0, 255)));
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_render_software() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let red = BrushType::SolidBrush(SolidBrush{ color: (1.0, 0.0, 0.0, 1.0) });
+    let blue = BrushType::SolidBrush(SolidBrush{ color: (0.0, 0.0, 1.0, 1.0) });
+    let triangle = PathData::polyline(&[(10.0, 10.0), (20.0, 10.0), (10.0, 20.0)], true);
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, BrushT{ canvas: 1001, btype: red });
+    ui.pack_resource(&1003, BrushT{ canvas: 1001, btype: blue });
+    ui.pack_resource(&1004, PenT{ canvas: 1001, start_cap: CapStyle::Flat, end_cap: CapStyle::Flat, dash_cap: CapStyle::Flat,
+        line_join: LineJoin::Miter, miter_limit: 10.0, dash_style: DashStyle::Dash, dash_offset: 0.0 });
+    ui.pack_resource(&1005, PathT{ canvas: 1001, path: triangle });
+    ui.commit().expect("Commit was not successful");
+
+    let draw = |r: &mut CanvasRenderer<u64>| -> Result<(), Error> {
+        r.clear(1.0, 1.0, 1.0, 1.0);
+        r.fill_rectangle(&1002, &Rectangle{ left: 2.0, top: 2.0, right: 8.0, bottom: 8.0 })?;
+        r.set_matrix(&Matrix::translation(10.0, 0.0));
+        r.fill_rectangle(&1003, &Rectangle{ left: 2.0, top: 2.0, right: 8.0, bottom: 8.0 })
+    };
+
+    // Pixel aligned shapes are drawn exactly like Direct2D draws them
+    let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+    let expected = canvas.render_offscreen(&ui, 24, 24, &draw).unwrap();
+    let image = canvas.render_software(&ui, 24, 24, &draw).unwrap();
+    assert!(image.compare(&expected, 1).matches());
+    assert_eq!(image.pixel(15, 5), Some((0, 0, 255, 255)));
+
+    // Pens and paths saved in the canvas
+    let image = canvas.render_software(&ui, 24, 24, |r| {
+        assert!(r.is_offscreen());
+        assert_eq!(r.get_render_size(), (24.0, 24.0));
+        r.draw_line(&1002, Some(&1004), (0.0, 1.0), (24.0, 1.0), 2.0)?;
+        r.fill_path(&1003, &1005)
+    }).unwrap();
+    assert_eq!(image.pixel(1, 1), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(5, 1), Some((0, 0, 0, 0)));
+    assert_eq!(image.pixel(11, 11), Some((0, 0, 255, 255)));
+    assert_eq!(image.pixel(18, 18), Some((0, 0, 0, 0)));
+
+    // Text cannot be drawn and the resources must have the right type
+    let mut backend = SoftwareRenderer::new(10, 10).unwrap();
+    let text = canvas.render_with(&ui, &mut backend, |r| r.draw_text(&1002, &1002, "Hi", &Rectangle{ left: 0.0, top: 0.0, right: 10.0, bottom: 10.0 }));
+    assert!(text.is_err());
+    assert!(canvas.render_with(&ui, &mut backend, |r| r.fill_path(&1002, &1002)).is_err());
+    assert!(canvas.render_software(&ui, 0, 10, |_| Ok(())).is_err());
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_display_list() {
+    use nwg::constants::canvas::*;
+
+    let red = Paint::Solid((1.0, 0.0, 0.0, 1.0));
+    let gradient = Paint::Linear(LinearGradientBrush{ start: (0.0, 0.0), end: (10.0, 0.0), extend: ExtendMode::Mirror,
+        stops: vec![GradientStop{ position: 0.0, color: (0.0, 0.0, 1.0, 1.0) }, GradientStop{ position: 1.0, color: (0.0, 1.0, 0.0, 0.5) }] });
+    let pen = Pen{ start_cap: CapStyle::Round, end_cap: CapStyle::Round, dash_cap: CapStyle::Flat, line_join: LineJoin::Bevel,
+        miter_limit: 10.0, dash_style: DashStyle::Dash, dash_offset: 0.0 };
+    let square = PathData::polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)], true);
+    let mut image = ImageData::new(2, 1);
+    image.data = vec![0, 0, 255, 255, 255, 0, 0, 255];
+
+    let draw = |b: &mut RenderBackend| -> Result<(), Error> {
+        b.clear((1.0, 1.0, 1.0, 1.0));
+        b.set_matrix(&Matrix::translation(10.0, 0.0));
+        b.fill_path(&square, &red)?;
+        b.push_clip(&Rectangle{ left: 0.0, top: 12.0, right: 30.0, bottom: 30.0 });
+        b.stroke_path(&PathData::polyline(&[(0.0, 14.0), (30.0, 14.0)], false), &red, Some(&pen), 2.0)?;
+        b.push_layer(0.5, None, None)?;
+        b.fill_path(&square.clone().fill_mode(FillMode::Winding), &gradient)?;
+        b.pop_layer();
+        b.pop_clip();
+        b.set_matrix(&Matrix::identity());
+        b.draw_image(&image, &Rectangle{ left: 0.0, top: 30.0, right: 4.0, bottom: 32.0 }, None, 1.0, BitmapInterpolation::NearestNeighbor)
+    };
+
+    // A replayed list draws like the commands it recorded
+    let mut list = DisplayList::new(40.0, 40.0);
+    draw(&mut list).unwrap();
+    assert_eq!(list.len(), 11);
+    assert_eq!(list.get_size(), (40.0, 40.0));
+
+    let mut expected = SoftwareRenderer::new(40, 40).unwrap();
+    draw(&mut expected).unwrap();
+    let mut replayed = SoftwareRenderer::new(40, 40).unwrap();
+    list.replay(&mut replayed).unwrap();
+    assert!(replayed.get_image().compare(&expected.get_image(), 0).matches());
+    assert_eq!(replayed.get_image().pixel(12, 5), Some((255, 0, 0, 255)));
+
+    // The transformation of the backend is applied after the one of the commands
+    let mut moved = SoftwareRenderer::new(40, 40).unwrap();
+    moved.set_matrix(&Matrix::translation(0.0, 20.0));
+    list.replay(&mut moved).unwrap();
+    assert_eq!(moved.get_image().pixel(12, 25), Some((255, 0, 0, 255)));
+    assert_eq!(moved.get_matrix(), Matrix::translation(0.0, 20.0));
+
+    // Transformations that do not change anything are not recorded
+    let mut empty = DisplayList::new(10.0, 10.0);
+    empty.set_matrix(&Matrix::identity());
+    assert!(empty.is_empty());
+    empty.set_matrix(&Matrix::translation(1.0, 0.0));
+    empty.set_matrix(&Matrix::translation(2.0, 0.0));
+    assert_eq!(empty.len(), 1);
+    empty.push(DrawCommand::PopLayer);
+    assert!(empty.replay(&mut SoftwareRenderer::new(10, 10).unwrap()).is_err());
+
+    // The svg keeps the brushes, the pens and the transformations
+    list.draw_text("a < b", &TextStyle{ family: "Segoe UI".to_string(), size: 12.0, weight: 700, style: FontStyle::Italic,
+        text_align: TextAlignment::Center, paragraph_align: ParagraphAlignment::Near, wrapping: WordWrapping::Wrap },
+        &Rectangle{ left: 0.0, top: 0.0, right: 40.0, bottom: 20.0 }, &red).unwrap();
+    let svg = list.to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("width=\"40\" height=\"40\" viewBox=\"0 0 40 40\""));
+    assert!(svg.contains("<rect width=\"40\" height=\"40\" fill=\"#ffffff\"/>"));
+    assert!(svg.contains("<path d=\"M0 0 L10 0 L10 10 L0 10 Z\" fill=\"#ff0000\" fill-rule=\"evenodd\" transform=\"matrix(1 0 0 1 10 0)\"/>"));
+    assert!(svg.contains("stroke-linecap=\"butt\" stroke-linejoin=\"bevel\" stroke-miterlimit=\"10\" stroke-dasharray=\"4 4\""));
+    assert!(svg.contains("<linearGradient") && svg.contains("spreadMethod=\"reflect\"") && svg.contains("stop-color=\"#00ff00\" stop-opacity=\"0.5\""));
+    assert!(svg.contains("<clipPath id=\"clip1\"><rect x=\"10\" y=\"12\" width=\"30\" height=\"18\"/></clipPath>"));
+    assert!(svg.contains("<g opacity=\"0.5\">"));
+    assert!(svg.contains("data:image/png;base64,"));
+    assert!(svg.contains("font-family=\"Segoe UI\" font-size=\"12\" font-weight=\"700\" font-style=\"italic\" text-anchor=\"middle\""));
+    assert!(svg.contains(">a &lt; b</tspan>"));
+    assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_record() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    let red = BrushType::SolidBrush(SolidBrush{ color: (1.0, 0.0, 0.0, 1.0) });
+
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.pack_resource(&1002, BrushT{ canvas: 1001, btype: red });
+    ui.pack_resource(&1003, PenT{ canvas: 1001, start_cap: CapStyle::Square, end_cap: CapStyle::Square, dash_cap: CapStyle::Flat,
+        line_join: LineJoin::Round, miter_limit: 10.0, dash_style: DashStyle::Solid, dash_offset: 0.0 });
+    ui.pack_resource(&1004, TextFormatT{ canvas: 1001, family: "Arial", size: 10.0, weight: FONT_WEIGHT_BOLD, style: FontStyle::Normal,
+        text_align: TextAlignment::Leading, paragraph_align: ParagraphAlignment::Near, wrapping: WordWrapping::NoWrap });
+    ui.commit().expect("Commit was not successful");
+
+    let draw = |r: &mut CanvasRenderer<u64>| -> Result<(), Error> {
+        r.clear(1.0, 1.0, 1.0, 1.0);
+        r.set_matrix(&Matrix::translation(4.0, 0.0));
+        r.fill_rectangle(&1002, &Rectangle{ left: 2.0, top: 2.0, right: 8.0, bottom: 8.0 })?;
+        r.push_clip(&Rectangle{ left: 0.0, top: 10.0, right: 20.0, bottom: 20.0 });
+        r.draw_line(&1002, Some(&1003), (2.0, 14.0), (14.0, 14.0), 2.0)
+    };
+
+    // The recorded commands are drawn again like the original drawing
+    let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+    let list = canvas.record(&ui, 24.0, 24.0, &draw).unwrap();
+    assert_eq!(list.len(), 6);
+    assert_eq!(list.get_size(), (24.0, 24.0));
+
+    let expected = canvas.render_offscreen(&ui, 24, 24, &draw).unwrap();
+    let image = canvas.render_offscreen(&ui, 24, 24, |r| r.draw_display_list(&list)).unwrap();
+    assert!(image.compare(&expected, 1).matches());
+
+    let expected = canvas.render_software(&ui, 24, 24, &draw).unwrap();
+    let image = canvas.render_software(&ui, 24, 24, |r| r.draw_display_list(&list)).unwrap();
+    assert!(image.compare(&expected, 0).matches());
+    assert_eq!(image.pixel(7, 5), Some((255, 0, 0, 255)));
+
+    // Text is recorded with its format, but the software renderer cannot draw it
+    let text = canvas.record(&ui, 24.0, 24.0, |r| r.draw_text(&1002, &1004, "Hi", &Rectangle{ left: 0.0, top: 0.0, right: 24.0, bottom: 24.0 })).unwrap();
+    assert!(text.to_svg().contains("font-family=\"Arial\" font-size=\"10\" font-weight=\"700\""));
+    assert!(canvas.render_offscreen(&ui, 24, 24, |r| r.draw_display_list(&text)).is_ok());
+    assert!(canvas.render_software(&ui, 24, 24, |r| r.draw_display_list(&text)).is_err());
+
+    let svg = list.to_svg();
+    assert!(svg.contains("stroke-linecap=\"square\" stroke-linejoin=\"round\""));
+    assert!(svg.contains("transform=\"matrix(1 0 0 1 4 0)\""));
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_frame_clock() {
+    use nwg::constants::canvas::*;
+    use std::time::{Duration, Instant};
+
+    let ms = |v: u64| Duration::from_millis(v);
+    let start = Instant::now();
+
+    // Frames paced to a frame rate wait for the whole interval
+    let mut clock = FrameClock::new(FramePacing::Fps(50.0), 60.0);
+    assert_eq!(clock.get_interval(), ms(20));
+    assert_eq!(clock.wait_time(start), None);
+    assert_eq!(clock.tick(start), ms(0));
+    assert_eq!(clock.wait_time(start + ms(5)), Some(ms(15)));
+    assert_eq!(clock.wait_time(start + ms(20)), None);
+    assert_eq!(clock.tick(start + ms(20)), ms(20));
+    assert_eq!(clock.tick(start + ms(60)), ms(40));
+    assert_eq!(clock.frame_count(), 3);
+
+    let stats = clock.stats();
+    assert_eq!(stats.frames, 3);
+    assert_eq!(stats.average, ms(30));
+    assert_eq!((stats.min, stats.max, stats.late), (ms(20), ms(40), 1));
+    assert!((stats.fps - 33.333).abs() < 0.01);
+
+    // The paused time is not counted
+    clock.pause();
+    assert!(clock.is_paused());
+    assert_eq!(clock.wait_time(start + ms(61)), None);
+    assert_eq!(clock.tick(start + ms(5000)), ms(0));
+    assert!(!clock.is_paused());
+    assert_eq!(clock.stats().max, ms(40));
+
+    // Frames paced by the display only wait for half of the refresh interval
+    let mut clock = FrameClock::new(FramePacing::Display, 100.0);
+    assert_eq!(clock.get_pacing(), FramePacing::Display);
+    clock.tick(start);
+    assert_eq!(clock.wait_time(start + ms(2)), Some(ms(3)));
+    assert_eq!(clock.wait_time(start + ms(5)), None);
+    assert_eq!(FrameClock::new(FramePacing::Fps(0.0), 0.0).get_interval(), Duration::new(0, 16_666_666));
+    assert_eq!(FrameClock::new(FramePacing::Display, 0.0).stats().fps, 0.0);
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_animation() {
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.commit().expect("Commit was not successful");
+
+    let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+    assert!(!canvas.is_animating());
+    assert!(canvas.frame_stats().is_none());
+
+    canvas.start_animation(FramePacing::Fps(30.0));
+    assert!(canvas.is_animating());
+    assert_eq!(canvas.frame_stats().map(|s| (s.frames, s.late)), Some((0, 0)));
+
+    canvas.start_animation(FramePacing::Display);
+    assert!(canvas.is_animating());
+
+    canvas.stop_animation();
+    assert!(!canvas.is_animating());
+    assert!(canvas.frame_stats().is_none());
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_canvas_animation_frames() {
+    use std::time::{Duration, Instant};
+    use nwg::constants::canvas::*;
+
+    let ui = setup_ui();
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, CanvasT{ parent: 1000, position: (0, 0), size: (100, 100), visible: true, disabled: false });
+    ui.commit().expect("Commit was not successful");
+
+    let frames = |pacing: FramePacing| {
+        ui.get_mut::<Canvas<u64>>(&1001).unwrap().start_animation(pacing);
+
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(500) {
+            wait_timeout(Duration::from_millis(10));
+        }
+
+        let mut canvas = ui.get_mut::<Canvas<u64>>(&1001).unwrap();
+        let count = canvas.frame_stats().unwrap().frames;
+        canvas.stop_animation();
+        count
+    };
+
+    // The 100 ms watchdog would only start 5 frames in 500 ms
+    let display = frames(FramePacing::Display);
+    assert!(display > 10, "Display paced animation only drew {} frames", display);
+
+    // Frames that exceed the budget of a 1000 fps animation start the next frame at once
+    let fast = frames(FramePacing::Fps(1000.0));
+    assert!(fast > 10, "Fps paced animation only drew {} frames", fast);
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_chart_scale() {
+    use nwg::constants::chart::*;
+
+    // Steps are 1, 2 or 5 times a power of ten
+    assert_eq!(nice_step(10.0, 5), 2.0);
+    assert_eq!(nice_step(100.0, 10), 10.0);
+    assert_eq!(nice_step(0.85, 5), 0.2);
+    assert_eq!(nice_step(7.0, 2), 5.0);
+    assert_eq!(nice_step(1234.0, 3), 500.0);
+    assert_eq!(nice_step(0.0, 5), 0.0);
+    assert_eq!(nice_step(1.0, 0), 0.0);
+
+    let scale = Scale::new((0.0, 10.0), (100.0, 0.0));
+    assert_eq!(scale.map(2.5), 75.0);
+    assert_eq!(scale.invert(75.0), 2.5);
+    assert_eq!(scale.ticks(5), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+
+    // Decimal ticks are exact and the domain is extended to the closest ticks
+    let scale = Scale::new((0.1, 0.95), (0.0, 1.0));
+    assert_eq!(scale.ticks(5), vec![0.2, 0.4, 0.6, 0.8]);
+    assert_eq!(scale.nice(5).domain, (0.0, 1.0));
+    assert_eq!(Scale::new((0.0, 0.3), (0.0, 1.0)).ticks(3), vec![0.0, 0.1, 0.2, 0.3]);
+    assert_eq!(Scale::new((-3.2, 7.9), (0.0, 1.0)).nice(5).domain, (-4.0, 8.0));
+    assert_eq!(Scale::new((12.0, -3.0), (0.0, 1.0)).nice(3).domain, (15.0, -5.0));
+    assert_eq!(Scale::new((12.0, -3.0), (0.0, 1.0)).ticks(3), vec![-0.0, 5.0, 10.0]);
+
+    // Empty domains
+    assert_eq!(Scale::new((5.0, 5.0), (0.0, 10.0)).map(7.0), 5.0);
+    assert_eq!(Scale::new((5.0, 5.0), (0.0, 10.0)).ticks(5), vec![5.0]);
+    assert_eq!(Scale::new((5.0, 5.0), (0.0, 10.0)).nice(5).domain, (5.0, 5.0));
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_chart_plot() {
+    use nwg::constants::chart::*;
+
+    let red = (1.0, 0.0, 0.0, 1.0);
+    let blue = (0.0, 0.0, 1.0, 1.0);
+    let mut plot = Plot::new();
+    plot.set_legend(false);
+    assert_eq!(plot.add_series(Series{ name: "Sales".to_string(), kind: SeriesType::Bar, color: red, points: vec![(0.0, 4.0), (1.0, 9.0)], max_points: None }), 0);
+    assert_eq!(plot.add_series(Series{ name: "Trend".to_string(), kind: SeriesType::Line, color: blue, points: vec![(0.0, 2.0), (1.0, 3.0)], max_points: Some(3) }), 1);
+
+    // Bars start at zero and the vertical axis ends on a tick
+    let layout = plot.layout(200.0, 150.0);
+    assert_eq!(layout.y.domain, (0.0, 10.0));
+    assert_eq!(layout.x.domain, (-0.5, 1.5));
+    assert_eq!(layout.y_ticks.first(), Some(&0.0));
+    assert_eq!(layout.y_ticks.last(), Some(&10.0));
+    assert!(layout.legend.is_none());
+    assert!(layout.area.left > 0.0 && layout.area.right < 200.0 && layout.area.top > 0.0 && layout.area.bottom < 150.0);
+
+    let bar = (layout.x.map(1.0) as f32 - 10.0, layout.y.map(5.0) as f32);
+    let line = (layout.x.map(1.0) as f32, layout.y.map(3.0) as f32);
+    assert_eq!(plot.hit_test(200.0, 150.0, bar), Some((0, 1)));
+    assert_eq!(plot.hit_test(200.0, 150.0, (line.0 + 2.0, line.1 - 2.0)), Some((1, 1)));
+    assert_eq!(plot.hit_test(200.0, 150.0, (1.0, 1.0)), None);
+
+    let mut backend = SoftwareRenderer::new(200, 150).unwrap();
+    plot.draw(&mut backend).unwrap();
+    let image = backend.get_image();
+    assert_eq!(image.pixel(bar.0 as u32, bar.1 as u32), Some((255, 0, 0, 255)));
+    assert_eq!(image.pixel(line.0 as u32, line.1 as u32).map(|p| p.2 > 200), Some(true));
+    assert_eq!(image.pixel(199, 0), Some((255, 255, 255, 255)));
+
+    // Live updates keep the last points
+    assert!(plot.set_hover(Some((1, 1))));
+    assert!(!plot.set_hover(Some((1, 1))));
+    assert!(plot.set_hover(Some((1, 5))));
+    assert_eq!(plot.get_hover(), None);
+    assert!(plot.set_hover(Some((1, 1))));
+    assert!(plot.push_points(1, &[(2.0, 5.0), (3.0, 6.0)]));
+    assert_eq!(plot.series()[1].points, vec![(1.0, 3.0), (2.0, 5.0), (3.0, 6.0)]);
+    assert_eq!(plot.get_hover(), None);
+    assert!(!plot.push_points(2, &[(0.0, 0.0)]));
+    assert_eq!(plot.layout(200.0, 150.0).x.domain, (-0.5, 3.5));
+
+    // The tooltip and the labels are recorded by the display lists
+    plot.set_title("Report");
+    plot.set_hover(Some((0, 1)));
+    let mut list = DisplayList::new(200.0, 150.0);
+    plot.draw(&mut list).unwrap();
+    let svg = list.to_svg();
+    assert!(svg.contains(">Report<"));
+    assert!(svg.contains(">x: 1, y: 9<"));
+
+    assert!(plot.remove_series(0).is_some());
+    assert!(plot.remove_series(5).is_none());
+    assert_eq!(plot.series().len(), 1);
+}
+
+#[test]
+#[cfg(feature = "canvas")]
+fn test_chart() {
+    use nwg::constants::chart::*;
+
+    let ui = setup_ui();
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, ChartT{ parent: 1000, position: (0, 0), size: (200, 150), visible: true, disabled: false, title: "Temperature", legend: true });
+    ui.commit().expect("Commit was not successful");
+
+    test_visibility!(ui, &1001, Chart<u64>);
+    test_position!(ui, &1001, Chart<u64>);
+    test_size!(ui, &1001, Chart<u64>);
+    assert_eq!(ui.type_of_control(&1001).unwrap(), ControlType::Chart);
+
+    {
+        let mut chart = ui.get_mut::<Chart<u64>>(&1001).unwrap();
+        assert_eq!(chart.plot().get_title(), "Temperature");
+        assert!(chart.plot().get_legend());
+
+        let series = Series{ name: "Outside".to_string(), kind: SeriesType::Area, color: (0.0, 0.5, 1.0, 1.0), points: Vec::new(), max_points: Some(2) };
+        let index = chart.update_plot(|p| p.add_series(series));
+        assert!(chart.update_plot(|p| p.push_points(index, &[(0.0, 20.5), (1.0, 21.0), (2.0, 19.5)])));
+        assert_eq!(chart.plot().series()[0].points, vec![(1.0, 21.0), (2.0, 19.5)]);
+
+        let svg = chart.record().unwrap().to_svg();
+        assert!(svg.contains(">Temperature<"));
+        assert!(svg.contains(">Outside<"));
+    }
+
+    // A chart is not a canvas
+    assert!(ui.get::<Canvas<u64>>(&1001).is_err());
+}
+
+#[test]
+fn test_accept_files() {
+    let ui = setup_ui();
+
+    let mut w = window();
+    w.accept_files = true;
+
+    ui.pack_control(&1000, w);
+    ui.pack_control(&1001, FrameT{position: (0, 0), size: (50, 50), visible: true, disabled: false, show_edge: true, accept_files: true, parent: 1000});
+    ui.bind(&1000, &5000, FilesDropped, |_, _, _, args|{ 
+        if let &EventArgs::Files{ref paths, ..} = args { assert!(paths.len() > 0, "Dropped files event without files"); }
+    });
+    ui.commit().expect("Commit was not successful");
+
+    // Registering twice or revoking must not fail
+    { 
+        let w = ui.get::<Window>(&1000).unwrap();
+        assert!(w.set_accept_files(true).is_ok(), "Registering a registered window failed");
+        assert!(w.set_accept_files(false).is_ok());
+        assert!(w.set_accept_files(true).is_ok());
+    }
+
+    ui.unpack(&1001);
+    ui.commit().expect("Commit was not successful");
+}
+
+#[test]
+fn test_drag_and_drop() {
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    let ui = setup_ui();
+
+    let lb_t = ListBoxT {
+        collection: vec!["Foo", "Bar"],
+        position:(10, 10), size: (80, 80),
+        visible: true, disabled: false,  readonly: false, multi_select: false,
+        parent: 1000,
+        font: None 
+    };
+
+    let end_effect = Rc::new(Cell::new(999));
+    let end_effect2 = end_effect.clone();
+
+    ui.pack_resource(&10_000, default_font());
+    ui.pack_control(&1000, window());
+    ui.pack_control(&1001, lb_t.clone());
+    ui.pack_control(&1002, lb_t);
+    ui.bind(&1001, &5000, DragEnd, move |_, _, _, args|{ 
+        if let &EventArgs::Drag(ref data) = args {
+            assert!(data.payload_is::<String>(), "Payload type was lost");
+            assert_eq!(data.payload::<String>().map(|s| s.as_ref()), Some("Foo"));
+            end_effect2.set(data.effect());
+        }
+    });
+    ui.commit().expect("Commit was not successful");
+
+    assert!(ui.register_drop_target(&1002, DROP_EFFECT_COPY|DROP_EFFECT_MOVE).is_ok());
+    assert!(ui.register_drop_target(&1002, DROP_EFFECT_ALL).is_ok());
+    assert!(ui.register_drop_target(&10_000, DROP_EFFECT_ALL).is_err(), "Registered a resource as a drop target");
+    assert!(ui.register_drop_target(&9999, DROP_EFFECT_ALL).is_err(), "Registered an unknown id as a drop target");
+
+    // Drag & cancel
+    assert!(!ui.is_dragging());
+    ui.begin_drag(&1001, Box::new("Foo".to_string()), DROP_EFFECT_MOVE, None).expect("Could not start a drag operation");
+    assert!(ui.is_dragging());
+    assert!(ui.begin_drag(&1001, Box::new(0u32), DROP_EFFECT_MOVE, None).is_err(), "Started two drag operations");
+    ui.cancel_drag();
+    assert!(!ui.is_dragging());
+    assert_eq!(end_effect.get(), DROP_EFFECT_NONE);
+
+    assert!(ui.begin_drag(&10_000, Box::new(0u32), DROP_EFFECT_MOVE, None).is_err(), "Started a drag from a resource");
+    assert!(ui.begin_drag(&1001, Box::new(0u32), DROP_EFFECT_MOVE, Some(&1002)).is_err(), "Used a control as drag image");
+
+    // Unpacking the source stops the drag operation
+    ui.begin_drag(&1001, Box::new("Foo".to_string()), DROP_EFFECT_MOVE, None).expect("Could not start a drag operation");
+    ui.unpack(&1001);
+    ui.commit().expect("Commit was not successful");
+    assert!(!ui.is_dragging());
+
+    assert!(ui.unregister_drop_target(&1002).is_ok());
+    assert!(ui.unregister_drop_target(&1002).is_err());
+}
+
 #[test]
 fn test_buttons() {
     let ui = setup_ui();
@@ -775,7 +2948,8 @@ fn test_textinput() {
         limit: 10,
         placeholder: None,
         parent: 1000,
-        font: None
+        font: None,
+        accept_files: false
     };
 
     ui.pack_control(&1000, window());